@@ -0,0 +1,105 @@
+//! The scan-to-render path with a dense scan: hundreds of networks, each
+//! seen through a few access points. A frame should stay within a few
+//! milliseconds, so rows are cached between frames and the visible order
+//! is only worked out again once something changed.
+
+use std::{hint::black_box, time::Instant};
+
+use criterion::{Criterion, criterion_group, criterion_main};
+use nm_wifi::{
+    app_state::{App, AppState},
+    ui::ui,
+    wifi::{WifiAccessPoint, WifiNetwork, WifiSecurity},
+};
+use ratatui::{Terminal, backend::TestBackend};
+
+const NETWORKS: usize = 600;
+const ACCESS_POINTS: usize = 3;
+
+fn scan(round: u8) -> Vec<WifiNetwork> {
+    (0..NETWORKS)
+        .map(|index| {
+            let access_points = (0..ACCESS_POINTS)
+                .map(|ap| WifiAccessPoint {
+                    bssid: format!(
+                        "02:00:00:{:02X}:{:02X}:{ap:02X}",
+                        index / 256,
+                        index % 256
+                    ),
+                    frequency: if ap == 0 { 2437 } else { 5180 },
+                    signal_strength: ((index * 7 + ap * 13) % 90 + 10) as u8,
+                    associated: false,
+                })
+                .collect::<Vec<_>>();
+            WifiNetwork {
+                ssid: format!("Network-{index:03}"),
+                raw_ssid: None,
+                signal_strength: ((index * 7 + usize::from(round)) % 90 + 10)
+                    as u8,
+                security: if index % 3 == 0 {
+                    WifiSecurity::Open
+                } else {
+                    WifiSecurity::WpaPsk
+                },
+                frequency: 5180,
+                connected: index == 0,
+                saved: index % 5 == 0,
+                max_bitrate: 866_700,
+                wpa_flags: 0,
+                rsn_flags: 0,
+                access_points,
+            }
+        })
+        .collect()
+}
+
+fn list_app() -> App {
+    let mut app = App::new();
+    app.state = AppState::NetworkList;
+    app.replace_scanned_networks(scan(0), Instant::now());
+    app
+}
+
+fn list_render(c: &mut Criterion) {
+    let mut terminal =
+        Terminal::new(TestBackend::new(120, 36)).expect("terminal created");
+
+    let mut app = list_app();
+    let mut round = 0u8;
+    c.bench_function("scan to render", |b| {
+        b.iter(|| {
+            round = round.wrapping_add(1);
+            app.replace_scanned_networks(scan(round), Instant::now());
+            terminal
+                .draw(|frame| ui(frame, black_box(&app)))
+                .expect("render succeeds");
+        })
+    });
+
+    let app = list_app();
+    c.bench_function("redraw", |b| {
+        b.iter(|| {
+            terminal
+                .draw(|frame| ui(frame, black_box(&app)))
+                .expect("render succeeds");
+        })
+    });
+
+    let mut app = list_app();
+    let readings = app
+        .networks
+        .iter()
+        .flat_map(|network| network.access_points.clone())
+        .collect::<Vec<_>>();
+    c.bench_function("signal refresh and redraw", |b| {
+        b.iter(|| {
+            app.apply_signal_updates(black_box(&readings));
+            terminal
+                .draw(|frame| ui(frame, &app))
+                .expect("render succeeds");
+        })
+    });
+}
+
+criterion_group!(benches, list_render);
+criterion_main!(benches);
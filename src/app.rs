@@ -7,9 +7,16 @@ use crossterm::event::{self, Event, KeyCode, KeyEventKind};
 use ratatui::{Terminal, backend::Backend};
 
 use crate::{
-    app_state::{App, AppState, OperationKind},
+    app_state::{
+        App,
+        AppState,
+        ForgetStage,
+        NetworkFilter,
+        OperationKind,
+        ProfileEdit,
+    },
     backend::{NetworkBackend, default_runtime_driver},
-    network::ConnectionRequest,
+    network::{ConnectionRequest, DeviceKind},
     ui::ui,
     wifi::WifiNetwork,
 };
@@ -69,9 +76,7 @@ fn apply_scanned_networks(
     adapter_name: Option<String>,
 ) {
     let previous_count = app.networks.len();
-    app.networks = networks;
-    app.network_count = app.networks.len();
-    app.last_scan_time = Some(Instant::now());
+    app.replace_scanned_networks(networks, Instant::now());
 
     if app.adapter_name.is_none() {
         app.adapter_name = adapter_name;
@@ -88,7 +93,15 @@ fn apply_scanned_networks(
     if !app.networks.is_empty() {
         app.status_message = format!(
             "Found {} network(s). Ready to connect!",
-            app.networks.len()
+            app.network_count
+        );
+        app.state = AppState::NetworkList;
+    } else if let Some(interface) = app.unmanaged_adapters.first() {
+        // Rescanning cannot help until the adapter is managed, so hand the
+        // list back to the user with a way out.
+        app.status_message = format!(
+            "{interface} is not managed by NetworkManager. Press M to manage \
+             it."
         );
         app.state = AppState::NetworkList;
     } else {
@@ -131,6 +144,8 @@ fn complete_connection(backend: &dyn NetworkBackend, app: &mut App) {
             network,
             passphrase: app.password_input.as_str(),
         }
+    } else if app.guest_connection {
+        ConnectionRequest::Guest { network }
     } else {
         ConnectionRequest::Open { network }
     };
@@ -237,8 +252,25 @@ async fn handle_disconnection_state(
 }
 
 fn handle_keypress(app: &mut App, key: KeyCode) {
+    if app.authorization_denied.is_some() {
+        if matches!(key, KeyCode::Enter | KeyCode::Esc | KeyCode::Char('q')) {
+            app.dismiss_authorization_denied();
+        }
+        return;
+    }
+
     match app.state {
+        AppState::NetworkList if app.search_editing => match key {
+            KeyCode::Esc => app.clear_search(),
+            KeyCode::Enter => app.finish_search(),
+            KeyCode::Backspace => app.pop_search_char(),
+            KeyCode::Down => app.next(),
+            KeyCode::Up => app.previous(),
+            KeyCode::Char(c) => app.push_search_char(c),
+            _ => {}
+        },
         AppState::NetworkList => match key {
+            KeyCode::Esc if !app.search_query.is_empty() => app.clear_search(),
             KeyCode::Char('q') | KeyCode::Esc => app.quit(),
             KeyCode::Char('j') | KeyCode::Down => app.next(),
             KeyCode::Char('k') | KeyCode::Up => app.previous(),
@@ -246,11 +278,33 @@ fn handle_keypress(app: &mut App, key: KeyCode) {
                 app.activate_selected_network()
             }
             KeyCode::Char('d') => begin_disconnect_for_selected_network(app),
+            KeyCode::Char('g') => app.begin_guest_connection(),
             KeyCode::Char('r') => app.start_scan(),
+            KeyCode::Char('x') => app.begin_qr_share(),
+            KeyCode::Char(':') => app.open_palette(),
+            KeyCode::Char('/') => app.open_search(),
+            KeyCode::Char('a') => app.open_roaming_view(),
+            KeyCode::Char('w') => app.open_spectrum_view(),
+            KeyCode::Char('S') => app.open_site_survey(),
+            KeyCode::Char('H') => app.open_connection_history(),
+            KeyCode::Char('J') => app.open_log_view(),
+            KeyCode::Char('R') => app.reconnect_current_network(),
+            KeyCode::Char('L') => app.renew_current_lease(),
+            KeyCode::Char('M') => app.manage_adapter(),
+            KeyCode::Char('o') => app.open_captive_portal(),
+            KeyCode::Char('s') => app.cycle_sort_order(),
+            KeyCode::Char('T') => app.cycle_theme(),
+            KeyCode::Char('y') => app.connect_suggested_network(),
+            KeyCode::Char('t') => app.open_speed_test(),
             KeyCode::Char('h') => app.state = AppState::Help,
-            KeyCode::Char('i') if !app.networks.is_empty() => {
-                app.state = AppState::NetworkDetails;
-            }
+            KeyCode::Char('i') => app.open_network_details(),
+            KeyCode::Tab => app.open_device_tab(DeviceKind::Ethernet),
+            KeyCode::F(2) => app.toggle_filter(NetworkFilter::FiveGhz),
+            KeyCode::F(3) => app.toggle_filter(NetworkFilter::Open),
+            KeyCode::F(4) => app.toggle_filter(NetworkFilter::Saved),
+            KeyCode::F(5) => app.toggle_show_ignored(),
+            KeyCode::Char('I') => app.toggle_ignored_network(),
+            KeyCode::Char('f') => app.toggle_favorite_network(),
             _ => {}
         },
         AppState::Help => match key {
@@ -259,26 +313,279 @@ fn handle_keypress(app: &mut App, key: KeyCode) {
             }
             _ => {}
         },
+        AppState::ShareQr => match key {
+            KeyCode::Esc => app.close_qr_share(),
+            KeyCode::Enter => app.export_qr_share(),
+            KeyCode::Backspace => {
+                if let Some(share) = app.qr_share.as_mut() {
+                    share.export_path.pop();
+                }
+            }
+            KeyCode::Char(c) => {
+                if let Some(share) = app.qr_share.as_mut() {
+                    share.export_path.push(c);
+                    share.export_result = None;
+                }
+            }
+            _ => {}
+        },
+        AppState::CommandPalette => match key {
+            KeyCode::Esc => app.close_palette(),
+            KeyCode::Enter => app.run_palette_command(),
+            KeyCode::Up => {
+                if let Some(palette) = app.palette.as_mut() {
+                    palette.previous();
+                }
+            }
+            KeyCode::Down => {
+                if let Some(palette) = app.palette.as_mut() {
+                    palette.next();
+                }
+            }
+            KeyCode::Backspace => {
+                if let Some(palette) = app.palette.as_mut() {
+                    palette.query.pop();
+                    palette.selected = 0;
+                }
+            }
+            KeyCode::Char(c) => {
+                if let Some(palette) = app.palette.as_mut() {
+                    palette.query.push(c);
+                    palette.selected = 0;
+                }
+            }
+            _ => {}
+        },
+        AppState::ForgetProfiles => {
+            let stage = app.bulk_forget.as_ref().map(|forget| forget.stage);
+            match (stage, key) {
+                (Some(ForgetStage::Editing), KeyCode::Esc) => {
+                    app.close_bulk_forget()
+                }
+                (Some(ForgetStage::Editing), KeyCode::Enter)
+                | (Some(ForgetStage::Confirming), KeyCode::Char('y')) => {
+                    app.advance_bulk_forget()
+                }
+                (
+                    Some(ForgetStage::Confirming),
+                    KeyCode::Esc | KeyCode::Char('n'),
+                ) => app.cancel_bulk_forget_confirmation(),
+                (Some(ForgetStage::Editing), KeyCode::Backspace) => {
+                    if let Some(forget) = app.bulk_forget.as_mut() {
+                        forget.pattern.pop();
+                    }
+                }
+                (Some(ForgetStage::Editing), KeyCode::Char(c)) => {
+                    if let Some(forget) = app.bulk_forget.as_mut() {
+                        forget.pattern.push(c);
+                    }
+                }
+                _ => {}
+            }
+        }
+        AppState::ExportProfiles => match key {
+            KeyCode::Esc => app.close_profile_export(),
+            KeyCode::Enter => app.confirm_profile_export(),
+            KeyCode::Tab => app.toggle_profile_export_secrets(),
+            KeyCode::Up | KeyCode::Down => app.toggle_profile_export_field(),
+            KeyCode::Backspace => app.remove_char_from_profile_export(),
+            KeyCode::Char(c) => app.add_char_to_profile_export(c),
+            _ => {}
+        },
+        AppState::KnownNetworks
+            if app
+                .known_networks
+                .as_ref()
+                .is_some_and(|known| known.edit_input.is_some()) =>
+        {
+            match key {
+                KeyCode::Esc => app.cancel_profile_edit(),
+                KeyCode::Enter => app.confirm_profile_edit(),
+                KeyCode::Backspace => app.edit_profile_input(None),
+                KeyCode::Char(c) => app.edit_profile_input(Some(c)),
+                _ => {}
+            }
+        }
+        AppState::KnownNetworks => match key {
+            KeyCode::Esc | KeyCode::Char('q') => app.close_known_networks(),
+            KeyCode::Down | KeyCode::Char('j') => {
+                app.move_known_network_selection(true)
+            }
+            KeyCode::Up | KeyCode::Char('k') => {
+                app.move_known_network_selection(false)
+            }
+            KeyCode::Char('s') => app.toggle_known_network_scope(),
+            KeyCode::Char('r') => app.begin_profile_edit(ProfileEdit::Rename),
+            KeyCode::Char('c') => app.begin_profile_edit(ProfileEdit::Clone),
+            KeyCode::Char('p') => app.begin_profile_edit(ProfileEdit::Password),
+            KeyCode::Char('b') => app.cycle_known_network_band(),
+            KeyCode::Char('a') => app.begin_profile_edit(ProfileEdit::Bssid),
+            KeyCode::Char('m') => app.begin_profile_edit(ProfileEdit::Mtu),
+            KeyCode::Char('M') => {
+                app.begin_profile_edit(ProfileEdit::ClonedMac)
+            }
+            KeyCode::Char('v') => app.begin_profile_edit(ProfileEdit::Vpn),
+            _ => {}
+        },
+        AppState::CleanupProfiles => {
+            let stage =
+                app.profile_cleanup.as_ref().map(|cleanup| cleanup.stage);
+            match (stage, key) {
+                (Some(ForgetStage::Editing), KeyCode::Esc) => {
+                    app.close_profile_cleanup()
+                }
+                (Some(ForgetStage::Editing), KeyCode::Enter)
+                | (Some(ForgetStage::Confirming), KeyCode::Char('y')) => {
+                    app.advance_profile_cleanup()
+                }
+                (
+                    Some(ForgetStage::Confirming),
+                    KeyCode::Esc | KeyCode::Char('n'),
+                ) => app.cancel_profile_cleanup_confirmation(),
+                (
+                    Some(ForgetStage::Editing),
+                    KeyCode::Down | KeyCode::Char('j'),
+                ) => app.move_profile_cleanup_selection(true),
+                (
+                    Some(ForgetStage::Editing),
+                    KeyCode::Up | KeyCode::Char('k'),
+                ) => app.move_profile_cleanup_selection(false),
+                (Some(ForgetStage::Editing), KeyCode::Char(' ')) => {
+                    app.toggle_profile_cleanup_pick()
+                }
+                (Some(ForgetStage::Editing), KeyCode::Char('a')) => {
+                    app.toggle_profile_cleanup_all()
+                }
+                _ => {}
+            }
+        }
+        AppState::ImportQr => match key {
+            KeyCode::Esc => app.close_qr_import(),
+            KeyCode::Enter => app.confirm_qr_import(),
+            KeyCode::Backspace => app.remove_char_from_qr_import(),
+            KeyCode::Char(c) => app.add_char_to_qr_import(c),
+            _ => {}
+        },
+        AppState::Roaming => match key {
+            KeyCode::Esc | KeyCode::Char('a') | KeyCode::Char('q') => {
+                app.state = AppState::NetworkList;
+            }
+            KeyCode::Up | KeyCode::Char('k') => {
+                app.move_roaming_selection(true)
+            }
+            KeyCode::Down | KeyCode::Char('j') => {
+                app.move_roaming_selection(false)
+            }
+            KeyCode::Char('b') => app.toggle_avoided_access_point(),
+            KeyCode::Char('p') => app.pin_current_access_point(),
+            _ => {}
+        },
+        AppState::Spectrum => match key {
+            KeyCode::Esc | KeyCode::Char('q') | KeyCode::Char('w') => {
+                app.state = AppState::NetworkList;
+            }
+            KeyCode::Tab | KeyCode::Right | KeyCode::Char('l') => {
+                app.cycle_spectrum_band(false)
+            }
+            KeyCode::BackTab | KeyCode::Left | KeyCode::Char('h') => {
+                app.cycle_spectrum_band(true)
+            }
+            _ => {}
+        },
+        AppState::ConnectionHistory => match key {
+            KeyCode::Esc | KeyCode::Char('q') | KeyCode::Char('H') => {
+                app.state = AppState::NetworkList;
+            }
+            KeyCode::Down | KeyCode::Char('j') => {
+                app.move_history_selection(true)
+            }
+            KeyCode::Up | KeyCode::Char('k') => {
+                app.move_history_selection(false)
+            }
+            _ => {}
+        },
+        AppState::NetworkManagerLog => match key {
+            KeyCode::Esc | KeyCode::Char('q') | KeyCode::Char('J') => {
+                app.close_log_view()
+            }
+            KeyCode::Up | KeyCode::Char('k') => app.scroll_log_view(true, 1),
+            KeyCode::Down | KeyCode::Char('j') => app.scroll_log_view(false, 1),
+            KeyCode::PageUp => app.scroll_log_view(true, 10),
+            KeyCode::PageDown => app.scroll_log_view(false, 10),
+            KeyCode::End | KeyCode::Char('G') => {
+                app.scroll_log_view(false, usize::MAX)
+            }
+            KeyCode::Char('f') => app.toggle_log_filter(),
+            _ => {}
+        },
+        AppState::SiteSurvey => match key {
+            KeyCode::Esc | KeyCode::Char('q') => app.close_site_survey(),
+            KeyCode::Char('e') => app.export_site_survey(),
+            KeyCode::Char('c') => app.restart_site_survey(),
+            _ => {}
+        },
+        AppState::Devices => match key {
+            KeyCode::Tab => app.next_device_tab(),
+            KeyCode::Esc | KeyCode::Char('q') => app.close_device_tab(),
+            KeyCode::Down | KeyCode::Char('j') => {
+                app.move_device_selection(true)
+            }
+            KeyCode::Up | KeyCode::Char('k') => {
+                app.move_device_selection(false)
+            }
+            KeyCode::Enter => app.toggle_selected_device_profile(),
+            KeyCode::Char('r') => app.refresh_devices(),
+            _ => {}
+        },
+        AppState::P2pPeers => match key {
+            KeyCode::Esc | KeyCode::Char('q') => app.close_p2p_tab(),
+            KeyCode::Down | KeyCode::Char('j') => app.move_p2p_selection(true),
+            KeyCode::Up | KeyCode::Char('k') => app.move_p2p_selection(false),
+            KeyCode::Enter => app.connect_selected_p2p_peer(),
+            KeyCode::Char('r') => app.refresh_p2p_peers(),
+            _ => {}
+        },
+        AppState::SpeedTest => match key {
+            KeyCode::Esc | KeyCode::Char('t') | KeyCode::Char('q') => {
+                app.state = AppState::NetworkList;
+            }
+            KeyCode::Enter
+                if app
+                    .speed_test
+                    .as_ref()
+                    .is_some_and(|test| test.is_finished()) =>
+            {
+                app.open_speed_test()
+            }
+            _ => {}
+        },
         AppState::NetworkDetails => match key {
             KeyCode::Esc | KeyCode::Char('i') | KeyCode::Char('q') => {
                 app.state = AppState::NetworkList;
             }
+            KeyCode::Char('u') => app.clear_autoconnect_block(),
             _ => {}
         },
         AppState::PasswordInput => match key {
-            KeyCode::Esc => {
-                app.state = AppState::NetworkList;
-                app.password_input.clear();
-                app.password_visible = false;
-            }
+            KeyCode::Esc => app.cancel_password_input(),
             KeyCode::Enter => app.confirm_password(),
             KeyCode::Backspace => app.remove_char_from_password(),
             KeyCode::Tab => app.password_visible = !app.password_visible,
+            KeyCode::Up | KeyCode::Down => app.toggle_password_field(),
+            KeyCode::Left => app.cycle_password_choice(true),
+            KeyCode::Right => app.cycle_password_choice(false),
             KeyCode::Char(c) => app.add_char_to_password(c),
             _ => {}
         },
+        AppState::ManagerUnavailable => match key {
+            KeyCode::Char('q') | KeyCode::Esc => app.quit(),
+            KeyCode::Char('s') => app.start_network_manager(),
+            KeyCode::Char('r') if !app.starting_manager => app.start_scan(),
+            _ => {}
+        },
         AppState::ConnectionResult => match key {
             KeyCode::Char('q') | KeyCode::Esc => app.quit(),
+            KeyCode::Char('o') => app.open_captive_portal(),
             KeyCode::Enter => {
                 app.back_to_network_list();
                 app.start_scan();
@@ -290,6 +597,42 @@ fn handle_keypress(app: &mut App, key: KeyCode) {
     }
 }
 
+/// Pasted text goes into the password dialog's focused field in one piece
+/// and is typed into the other text inputs; where nothing is being typed it
+/// is dropped, so a paste never runs as key commands.
+fn handle_paste(app: &mut App, text: &str) {
+    if app.authorization_denied.is_some() {
+        return;
+    }
+    if app.state == AppState::PasswordInput {
+        app.paste_into_password(text);
+    } else if editing_text(app) {
+        for c in text.chars().filter(|c| !c.is_control()) {
+            handle_keypress(app, KeyCode::Char(c));
+        }
+    }
+}
+
+/// Whether the screen has a text input taking typed characters.
+fn editing_text(app: &App) -> bool {
+    match app.state {
+        AppState::NetworkList => app.search_editing,
+        AppState::ShareQr
+        | AppState::CommandPalette
+        | AppState::ExportProfiles
+        | AppState::ImportQr => true,
+        AppState::ForgetProfiles => app
+            .bulk_forget
+            .as_ref()
+            .is_some_and(|forget| forget.stage == ForgetStage::Editing),
+        AppState::KnownNetworks => app
+            .known_networks
+            .as_ref()
+            .is_some_and(|known| known.edit_input.is_some()),
+        _ => false,
+    }
+}
+
 pub async fn run_app_with_backend<B>(
     terminal: &mut Terminal<B>,
     backend: &dyn NetworkBackend,
@@ -362,6 +705,7 @@ mod tests {
         begin_disconnect_for_selected_network,
         complete_connection,
         complete_disconnection,
+        handle_paste,
     };
     use crate::{
         app_state::{App, AppState},
@@ -406,10 +750,16 @@ mod tests {
     fn network(ssid: &str, connected: bool) -> WifiNetwork {
         WifiNetwork {
             ssid: ssid.to_string(),
+            raw_ssid: None,
             signal_strength: 80,
             security: WifiSecurity::WpaPsk,
             frequency: 5180,
             connected,
+            saved: false,
+            max_bitrate: 0,
+            wpa_flags: 0,
+            rsn_flags: 0,
+            access_points: Vec::new(),
         }
     }
 
@@ -448,6 +798,34 @@ mod tests {
         assert_eq!(app.status_message, "Disconnecting from home...");
     }
 
+    #[test]
+    fn pastes_fill_the_password_whole_and_search_by_character() {
+        let mut app = App::new();
+        app.state = AppState::PasswordInput;
+        app.password_input = "ab".to_string();
+        handle_paste(&mut app, "q9 x\tZ!\n");
+        assert_eq!(app.password_input, "abq9 xZ!");
+        assert!(!app.should_quit);
+
+        app.state = AppState::NetworkList;
+        app.open_search();
+        handle_paste(&mut app, "Cat\n");
+        assert_eq!(app.search_query, "Cat");
+    }
+
+    #[test]
+    fn pastes_outside_text_inputs_are_dropped() {
+        let mut app = App::new();
+        app.state = AppState::NetworkList;
+        app.networks = vec![network("home", true)];
+
+        handle_paste(&mut app, "qt");
+
+        assert!(!app.should_quit);
+        assert!(app.state == AppState::NetworkList);
+        assert!(app.speed_test.is_none());
+    }
+
     #[test]
     fn disconnect_shortcut_ignores_unconnected_selected_network() {
         let mut app = App::new();
@@ -1,76 +1,411 @@
-use std::{error::Error, time::Duration};
+use std::{
+    error::Error,
+    path::PathBuf,
+    time::{Duration, Instant},
+};
 
-use crossterm::event::{self, Event, KeyCode, KeyEventKind};
-use ratatui::{Terminal, backend::Backend};
+use crossterm::event::{
+    self,
+    Event,
+    KeyCode,
+    KeyEventKind,
+    KeyModifiers,
+    MouseButton,
+    MouseEvent,
+    MouseEventKind,
+};
+use ratatui::{Terminal, backend::Backend, layout::Rect};
 
 use super::{
     CONNECTION_COMPLETION_REQUIRES_NETWORK,
     DISCONNECTION_COMPLETION_REQUIRES_NETWORK,
     apply_scanned_networks,
     handle_keypress,
+    handle_paste,
     handle_scanning_keypress,
     selected_network_for_operation,
 };
 use crate::{
-    app_state::{App, AppState},
-    ui::ui,
-    wifi::{WifiNetwork, WifiSecurity},
+    app_state::{App, AppState, OperationKind},
+    clipboard,
+    latency::LatencySample,
+    network::{
+        ActivationProgress,
+        AutoconnectBlock,
+        CancelToken,
+        ConnectionOptions,
+        ConnectivityReport,
+        DeviceKind,
+        DeviceProfile,
+        DhcpLease,
+        FailureKind,
+        ForgetReport,
+        LinkSetting,
+        ManagerStatus,
+        NetworkDevice,
+        P2pPeer,
+        ProfileSettings,
+        SavedProfile,
+        is_authorization_failure,
+    },
+    notify::ToastLevel,
+    speedtest::{ChunkTransfer, TransferDirection},
+    traffic::TrafficCounters,
+    ui::{MouseTarget, mouse_target, ui},
+    wifi::{WifiAccessPoint, WifiBand, WifiNetwork, WifiSecurity},
 };
 
-const INPUT_POLL_INTERVAL: Duration = Duration::from_millis(100);
+/// Time between frames while something on screen animates: 10 per second.
+const ANIMATION_FRAME_INTERVAL: Duration = Duration::from_millis(100);
+/// Time between frames otherwise; backend results and timers are still
+/// picked up this often.
+const IDLE_FRAME_INTERVAL: Duration = Duration::from_millis(250);
 
 #[derive(Debug, Clone)]
 pub(crate) struct ScanSnapshot {
     pub(crate) networks: Vec<WifiNetwork>,
     pub(crate) adapter_name: Option<String>,
+    pub(crate) manager_status: Option<ManagerStatus>,
+    pub(crate) unmanaged_adapters: Vec<String>,
+    /// NetworkManager's unique bus name, to notice it restarting.
+    pub(crate) manager_owner: Option<String>,
 }
 
 #[derive(Debug, Clone)]
 pub(crate) enum RuntimeRequest {
-    Scan,
+    /// Lists the networks in range, asking the adapter to scan first when
+    /// `fresh`, and only those on `band` when one is given.
+    Scan {
+        // The demo backend's scans are always fresh.
+        #[cfg_attr(feature = "demo", allow(dead_code))]
+        fresh: bool,
+        band: Option<WifiBand>,
+    },
+    RefreshSignals,
+    ReadTraffic {
+        interface: String,
+    },
+    ReadBitrate,
+    MeasureLatency {
+        host: String,
+    },
+    CheckAutoconnect {
+        network: WifiNetwork,
+    },
+    ClearAutoconnectBlock {
+        network: WifiNetwork,
+    },
+    ManageAdapter {
+        interface: String,
+    },
+    StartNetworkManager,
+    CheckManager,
+    LoadProfile {
+        network: WifiNetwork,
+    },
+    CheckConnectivity,
+    OpenPortal {
+        url: String,
+    },
+    SpeedTestChunk {
+        endpoint: String,
+        direction: TransferDirection,
+    },
+    LoadSavedPassword {
+        network: WifiNetwork,
+    },
+    ListSavedProfiles,
+    ForgetProfiles {
+        profiles: Vec<SavedProfile>,
+    },
+    ExportProfiles {
+        profiles: Vec<SavedProfile>,
+        directory: PathBuf,
+        include_secrets: bool,
+    },
+    /// Restricts the profile to `owner`, or opens it to every user.
+    SetProfileScope {
+        profile: SavedProfile,
+        owner: Option<String>,
+    },
+    RenameProfile {
+        profile: SavedProfile,
+        name: String,
+    },
+    CloneProfile {
+        profile: SavedProfile,
+        name: String,
+    },
+    SetProfilePassword {
+        profile: SavedProfile,
+        password: String,
+    },
+    /// Locks the profile to `band`, or unlocks it.
+    SetProfileBand {
+        profile: SavedProfile,
+        band: Option<WifiBand>,
+    },
+    /// Pins the profile to the access point `bssid`, or unpins it.
+    SetProfileBssid {
+        profile: SavedProfile,
+        bssid: Option<String>,
+    },
+    /// Changes the MTU or cloned MAC address of the profile.
+    SetProfileLink {
+        profile: SavedProfile,
+        setting: LinkSetting,
+    },
+    /// Brings the saved VPN named `vpn` up with the profile, or none.
+    SetProfileVpn {
+        profile: SavedProfile,
+        vpn: Option<String>,
+    },
+    /// Lists Ethernet devices, modems or Bluetooth phones.
+    ListDevices {
+        kind: DeviceKind,
+    },
+    /// Brings `profile` up on `interface`, or takes the
+    /// interface down.
+    SetDeviceProfile {
+        interface: String,
+        profile: Option<DeviceProfile>,
+    },
+    /// Reads NetworkManager's journal, keeping lines that mention any of
+    /// `filters`, or all of them when it is empty.
+    ReadNetworkManagerLog {
+        filters: Vec<String>,
+    },
+    /// Searches for Wi-Fi Direct peers nearby.
+    FindP2pPeers,
+    /// Joins the Wi-Fi Direct group of `peer`.
+    ConnectP2pPeer {
+        peer: P2pPeer,
+    },
+    /// Pins the saved profile of `network` to the access point `bssid`.
+    PinAccessPoint {
+        network: WifiNetwork,
+        bssid: String,
+    },
     Connect {
         network: WifiNetwork,
         passphrase: Option<String>,
+        guest: bool,
+        options: ConnectionOptions,
+        cancel: CancelToken,
+        progress: ActivationProgress,
     },
     Disconnect {
         network: WifiNetwork,
     },
+    /// Deletes the profile a guest connect to `ssid` created.
+    EndGuestSession {
+        ssid: String,
+    },
+    Reconnect {
+        network: WifiNetwork,
+        cancel: CancelToken,
+    },
+    RenewLease {
+        network: WifiNetwork,
+        cancel: CancelToken,
+    },
+    SteerTo {
+        network: WifiNetwork,
+        bssid: String,
+    },
 }
 
 #[derive(Debug, Clone)]
 pub(crate) enum RuntimeEvent {
     Scan(Result<ScanSnapshot, String>),
+    Signals(Result<Vec<WifiAccessPoint>, String>),
+    Traffic(Result<TrafficCounters, String>),
+    Bitrate(Result<Option<u32>, String>),
+    Latency(Result<LatencySample, String>),
+    AutoconnectBlock(Result<Option<AutoconnectBlock>, String>),
+    AutoconnectCleared(Result<(), String>),
+    AdapterManaged(Result<(), String>),
+    /// A scan found no system bus or no NetworkManager on it.
+    ManagerUnavailable(String),
+    ManagerStarted(Result<(), String>),
+    /// Whether NetworkManager is back on the bus.
+    ManagerChecked(Result<(), String>),
+    ProfileSettings(Result<Option<ProfileSettings>, String>),
+    Connectivity(Result<ConnectivityReport, String>),
+    PortalOpened(Result<(), String>),
+    SpeedTestChunk(TransferDirection, Result<ChunkTransfer, String>),
+    SavedPassword(Result<Option<String>, String>),
+    SavedProfiles(Result<Vec<SavedProfile>, String>),
+    ProfilesForgotten(Result<ForgetReport, String>),
+    ProfilesExported(Result<usize, String>),
+    ProfileScopeChanged(Option<String>, Result<(), String>),
+    ProfileRenamed(String, Result<(), String>),
+    ProfileCloned(Result<SavedProfile, String>),
+    ProfilePasswordSet(Result<(), String>),
+    ProfileBandChanged(Option<WifiBand>, Result<(), String>),
+    ProfileBssidSet(Option<String>, Result<(), String>),
+    ProfileLinkSet(LinkSetting, Result<(), String>),
+    ProfileVpnSet(Option<String>, Result<(), String>),
+    Devices(Result<Vec<NetworkDevice>, String>),
+    DeviceProfileSet(String, Option<DeviceProfile>, Result<(), String>),
+    NetworkManagerLog(Result<Vec<String>, String>),
+    P2pPeers(Result<Vec<P2pPeer>, String>),
+    P2pPeerConnected(String, Result<(), String>),
+    AccessPointPinned(String, Result<(), String>),
     Connect(Result<(), String>),
+    /// A connect failed because the network rejected the password.
+    PasswordRejected(String),
+    /// A connect failed for another reason, which may be worth retrying.
+    ConnectFailed(FailureKind, String),
     Disconnect(Result<(), String>),
+    GuestSessionEnded(Result<(), String>),
+    Reconnect(Result<(), String>),
+    RenewLease(Result<DhcpLease, String>),
+    Steered(String, Result<(), String>),
+}
+
+impl RuntimeEvent {
+    /// The error of an action the user asked for, if it failed. Periodic
+    /// refreshes are left out so a denial cannot pop up over and over.
+    fn action_error(&self) -> Option<&str> {
+        match self {
+            Self::Scan(Err(error))
+            | Self::AutoconnectCleared(Err(error))
+            | Self::AdapterManaged(Err(error))
+            | Self::ManagerStarted(Err(error))
+            | Self::ProfilesForgotten(Err(error))
+            | Self::ProfilesExported(Err(error))
+            | Self::Connect(Err(error))
+            | Self::ConnectFailed(_, error)
+            | Self::Disconnect(Err(error))
+            | Self::Reconnect(Err(error))
+            | Self::RenewLease(Err(error)) => Some(error),
+            _ => None,
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub(crate) enum Input {
+    Key(KeyCode),
+    Mouse(MouseEvent),
+    /// Text the terminal pasted as a whole (bracketed paste).
+    Paste(String),
+    /// Ctrl+V, asking for the clipboard.
+    PasteClipboard,
 }
 
 pub(crate) trait RuntimeInput {
-    fn next_key(
+    fn next_input(
         &mut self,
         timeout: Duration,
-    ) -> Result<Option<KeyCode>, Box<dyn Error>>;
+    ) -> Result<Option<Input>, Box<dyn Error>>;
 }
 
 pub(crate) struct CrosstermInput;
 
 impl RuntimeInput for CrosstermInput {
-    fn next_key(
+    fn next_input(
         &mut self,
         timeout: Duration,
-    ) -> Result<Option<KeyCode>, Box<dyn Error>> {
+    ) -> Result<Option<Input>, Box<dyn Error>> {
         if !event::poll(timeout)? {
             return Ok(None);
         }
 
         match event::read()? {
+            Event::Key(key)
+                if key.kind == KeyEventKind::Press
+                    && key.code == KeyCode::Char('v')
+                    && key.modifiers.contains(KeyModifiers::CONTROL) =>
+            {
+                Ok(Some(Input::PasteClipboard))
+            }
             Event::Key(key) if key.kind == KeyEventKind::Press => {
-                Ok(Some(key.code))
+                Ok(Some(Input::Key(key.code)))
             }
+            Event::Mouse(mouse) => Ok(Some(Input::Mouse(mouse))),
+            Event::Paste(text) => Ok(Some(Input::Paste(text))),
             _ => Ok(None),
         }
     }
 }
 
+/// Paces redraws on a steady tick, so spinners and timers advance at the
+/// same rate whether or not keys come in. Input and backend results still
+/// draw at once, without moving the next tick.
+struct FrameClock {
+    next_frame: Instant,
+}
+
+impl FrameClock {
+    fn new(now: Instant) -> Self {
+        Self { next_frame: now }
+    }
+
+    /// How long to wait for input before the next frame is due, starting
+    /// the next tick once the last one is over.
+    fn wait(&mut self, now: Instant, animating: bool) -> Duration {
+        let interval = if animating {
+            ANIMATION_FRAME_INTERVAL
+        } else {
+            IDLE_FRAME_INTERVAL
+        };
+        if self.next_frame <= now || self.next_frame > now + interval {
+            self.next_frame = now + interval;
+        }
+        self.next_frame - now
+    }
+}
+
+/// The next key pressed within `wait`, with mouse input and pastes applied
+/// on the way: a click selects a row of the network list and a
+/// double-click connects to it, while the wheel and clicked status-bar
+/// hints stand for the keys they amount to. `area` is the screen as last
+/// drawn.
+fn next_key<I: RuntimeInput + ?Sized>(
+    input: &mut I,
+    app: &mut App,
+    area: Rect,
+    wait: Duration,
+) -> Result<Option<KeyCode>, Box<dyn Error>> {
+    let mouse = match input.next_input(wait)? {
+        Some(Input::Key(key)) => return Ok(Some(key)),
+        Some(Input::Mouse(mouse)) => mouse,
+        Some(Input::Paste(text)) => {
+            handle_paste(app, &text);
+            return Ok(None);
+        }
+        Some(Input::PasteClipboard) => {
+            match clipboard::read_text() {
+                Ok(text) => handle_paste(app, &text),
+                Err(error) => {
+                    app.status_message =
+                        format!("Could not read the clipboard: {error}")
+                }
+            }
+            return Ok(None);
+        }
+        None => return Ok(None),
+    };
+
+    Ok(match mouse.kind {
+        MouseEventKind::ScrollDown => Some(KeyCode::Down),
+        MouseEventKind::ScrollUp => Some(KeyCode::Up),
+        MouseEventKind::Down(MouseButton::Left) => {
+            match mouse_target(app, area, mouse.column, mouse.row) {
+                Some(MouseTarget::Key(key)) => Some(key),
+                Some(MouseTarget::NetworkRow(index)) => {
+                    app.click_network_row(index, Instant::now());
+                    None
+                }
+                None => None,
+            }
+        }
+        _ => None,
+    })
+}
+
 pub(crate) trait RuntimeBackendDriver {
     fn begin(&mut self, request: RuntimeRequest);
 
@@ -80,10 +415,87 @@ pub(crate) trait RuntimeBackendDriver {
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
 enum InFlightRequest {
     Scan,
+    BackgroundScan,
+    SignalRefresh,
+    Traffic,
+    Bitrate,
+    Latency,
+    Autoconnect,
+    ManageAdapter,
+    StartManager,
+    CheckManager,
+    Profile,
+    Connectivity,
+    Portal,
+    SpeedTest,
+    SavedPassword,
+    SavedProfiles,
+    ForgetProfiles,
+    ExportProfiles,
+    ProfileScope,
+    RenameProfile,
+    CloneProfile,
+    ProfilePassword,
+    ProfileBand,
+    ProfileBssid,
+    ProfileLink,
+    ProfileVpn,
+    Devices,
+    DeviceSwitch,
+    NetworkManagerLog,
+    P2pPeers,
+    P2pConnect,
+    PinAccessPoint,
+    Steer,
+    GuestCleanup,
     Connect,
     Disconnect,
 }
 
+impl InFlightRequest {
+    /// How the request is named in the status bar while it runs.
+    fn label(self) -> &'static str {
+        match self {
+            Self::Scan => "Scanning",
+            Self::BackgroundScan => "Rescanning",
+            Self::SignalRefresh => "Refreshing signal",
+            Self::Traffic => "Reading traffic",
+            Self::Bitrate => "Reading bitrate",
+            Self::Latency => "Measuring latency",
+            Self::Autoconnect => "Updating profile",
+            Self::ManageAdapter => "Managing adapter",
+            Self::StartManager => "Starting NetworkManager",
+            Self::CheckManager => "Waiting for NetworkManager",
+            Self::Profile => "Loading profile",
+            Self::Connectivity => "Checking connectivity",
+            Self::Portal => "Opening portal",
+            Self::SpeedTest => "Speed testing",
+            Self::SavedPassword => "Loading password",
+            Self::SavedProfiles => "Listing profiles",
+            Self::ForgetProfiles => "Forgetting profiles",
+            Self::ExportProfiles => "Exporting profiles",
+            Self::ProfileScope => "Changing profile scope",
+            Self::RenameProfile => "Renaming profile",
+            Self::CloneProfile => "Cloning profile",
+            Self::ProfilePassword => "Updating password",
+            Self::ProfileBand => "Changing band",
+            Self::ProfileBssid => "Changing access point",
+            Self::ProfileLink => "Changing link settings",
+            Self::ProfileVpn => "Changing VPN",
+            Self::Devices => "Listing devices",
+            Self::DeviceSwitch => "Switching connection",
+            Self::NetworkManagerLog => "Reading NetworkManager log",
+            Self::P2pPeers => "Searching for peers",
+            Self::P2pConnect => "Connecting to peer",
+            Self::PinAccessPoint => "Pinning access point",
+            Self::Steer => "Leaving avoided AP",
+            Self::GuestCleanup => "Deleting guest profile",
+            Self::Connect => "Connecting",
+            Self::Disconnect => "Disconnecting",
+        }
+    }
+}
+
 pub(crate) async fn run_app_with_runtime<B, I, D>(
     terminal: &mut Terminal<B>,
     input: &mut I,
@@ -97,85 +509,393 @@ where
     D: RuntimeBackendDriver + ?Sized,
 {
     let mut in_flight = None;
+    let mut frames = FrameClock::new(Instant::now());
 
     loop {
-        terminal.draw(|frame| ui(frame, &app))?;
+        let area = terminal.draw(|frame| ui(frame, &app))?.area;
+        let wait = frames.wait(Instant::now(), app.animating());
 
         if app.should_quit {
-            break;
+            // Deleting the profile drops the connection, so a guest session
+            // only ends with the app when the config asks for it.
+            if app.delete_guest_profile_on_quit {
+                app.end_guest_session();
+            }
+            match (app.pending_guest_cleanup(), in_flight) {
+                (None, _) => break,
+                (Some(ssid), None) => {
+                    driver.begin(RuntimeRequest::EndGuestSession {
+                        ssid: ssid.to_string(),
+                    });
+                    in_flight = Some(InFlightRequest::GuestCleanup);
+                    app.begin_backend_activity(
+                        InFlightRequest::GuestCleanup.label(),
+                    );
+                }
+                // The cleanup starts once the request under way is done.
+                (Some(_), Some(_)) => {}
+            }
         }
+        app.reload_changed_config(Instant::now());
 
         if let Some(event) = driver.poll_event()? {
-            apply_runtime_event(&mut app, event);
+            match (in_flight, event) {
+                (
+                    Some(InFlightRequest::BackgroundScan),
+                    RuntimeEvent::Scan(result),
+                ) => apply_background_scan(&mut app, result),
+                // Late result of an operation cancelled with Esc.
+                (
+                    None,
+                    RuntimeEvent::Connect(_)
+                    | RuntimeEvent::PasswordRejected(_)
+                    | RuntimeEvent::ConnectFailed(..)
+                    | RuntimeEvent::Reconnect(_)
+                    | RuntimeEvent::RenewLease(_),
+                ) => {}
+                (_, event) => apply_runtime_event(&mut app, event),
+            }
             in_flight = None;
+            app.finish_backend_activity();
             continue;
         }
 
         if let Some(request) = in_flight {
-            handle_in_flight_request(input, &mut app, request)?;
+            in_flight =
+                handle_in_flight_request(input, &mut app, request, area, wait)?;
+            if in_flight.is_none() {
+                app.finish_backend_activity();
+            }
             continue;
         }
 
         match app.state {
-            AppState::Scanning => match input.next_key(INPUT_POLL_INTERVAL)? {
-                Some(key) => handle_scanning_keypress(&mut app, key),
-                None => {
-                    driver.begin(RuntimeRequest::Scan);
-                    in_flight = Some(InFlightRequest::Scan);
+            AppState::Scanning => {
+                match next_key(input, &mut app, area, wait)? {
+                    Some(key) => handle_scanning_keypress(&mut app, key),
+                    None => {
+                        driver.begin(scan_request(&mut app));
+                        in_flight = Some(InFlightRequest::Scan);
+                    }
                 }
-            },
+            }
             AppState::Connecting => {
-                if let Some(key) = input.next_key(INPUT_POLL_INTERVAL)? {
+                if let Some(key) = next_key(input, &mut app, area, wait)? {
                     if key == KeyCode::Esc {
-                        app.quit();
+                        app.cancel_operation();
                     }
-                } else {
+                } else if app.connect_attempt_due() {
                     driver.begin(connection_request(&app));
                     in_flight = Some(InFlightRequest::Connect);
                 }
             }
             AppState::Disconnecting => {
-                if let Some(key) = input.next_key(INPUT_POLL_INTERVAL)? {
-                    if key == KeyCode::Esc {
-                        app.quit();
-                    }
-                } else {
+                // A disconnect cannot be cancelled, so keys are ignored.
+                if next_key(input, &mut app, area, wait)?.is_none() {
                     driver.begin(disconnection_request(&app));
                     in_flight = Some(InFlightRequest::Disconnect);
                 }
             }
             _ => {
-                if let Some(key) = input.next_key(INPUT_POLL_INTERVAL)? {
+                if let Some(key) = next_key(input, &mut app, area, wait)? {
                     handle_keypress(&mut app, key);
+                } else if let Some((request, kind)) = dialog_request(&app) {
+                    driver.begin(request);
+                    in_flight = Some(kind);
+                } else if let Some(ssid) = app.pending_guest_cleanup() {
+                    driver.begin(RuntimeRequest::EndGuestSession {
+                        ssid: ssid.to_string(),
+                    });
+                    in_flight = Some(InFlightRequest::GuestCleanup);
+                } else if let Some((network, bssid)) = app.pending_steer() {
+                    driver.begin(RuntimeRequest::SteerTo {
+                        network: network.clone(),
+                        bssid: bssid.to_string(),
+                    });
+                    app.begin_steer();
+                    in_flight = Some(InFlightRequest::Steer);
+                } else if app.rescan_due() {
+                    driver.begin(scan_request(&mut app));
+                    in_flight = Some(InFlightRequest::BackgroundScan);
+                } else if app.signal_refresh_due() {
+                    driver.begin(RuntimeRequest::RefreshSignals);
+                    in_flight = Some(InFlightRequest::SignalRefresh);
+                } else if let Some(filters) = app.pending_log_read() {
+                    driver.begin(RuntimeRequest::ReadNetworkManagerLog {
+                        filters,
+                    });
+                    in_flight = Some(InFlightRequest::NetworkManagerLog);
+                } else if let Some(interface) = app.pending_traffic_interface()
+                {
+                    driver.begin(RuntimeRequest::ReadTraffic {
+                        interface: interface.to_string(),
+                    });
+                    in_flight = Some(InFlightRequest::Traffic);
+                } else if let Some(host) = app.pending_latency_host() {
+                    driver.begin(RuntimeRequest::MeasureLatency {
+                        host: host.to_string(),
+                    });
+                    in_flight = Some(InFlightRequest::Latency);
+                } else if app.bitrate_refresh_due() {
+                    driver.begin(RuntimeRequest::ReadBitrate);
+                    in_flight = Some(InFlightRequest::Bitrate);
                 }
             }
         }
+
+        if let Some(request) = in_flight {
+            app.begin_backend_activity(request.label());
+        }
     }
 
     Ok(app)
 }
 
+/// Handles a key pressed while `request` runs. Returns the request that is
+/// still awaited, or `None` once Esc cancelled it.
 fn handle_in_flight_request<I: RuntimeInput + ?Sized>(
     input: &mut I,
     app: &mut App,
     request: InFlightRequest,
-) -> Result<(), Box<dyn Error>> {
+    area: Rect,
+    wait: Duration,
+) -> Result<Option<InFlightRequest>, Box<dyn Error>> {
     match request {
         InFlightRequest::Scan => {
-            if let Some(key) = input.next_key(INPUT_POLL_INTERVAL)? {
+            if let Some(key) = next_key(input, app, area, wait)? {
                 handle_scanning_keypress(app, key);
             }
         }
-        InFlightRequest::Connect | InFlightRequest::Disconnect => {
-            if let Some(key) = input.next_key(INPUT_POLL_INTERVAL)?
+        InFlightRequest::BackgroundScan
+        | InFlightRequest::SignalRefresh
+        | InFlightRequest::Traffic
+        | InFlightRequest::Bitrate
+        | InFlightRequest::Latency
+        | InFlightRequest::Autoconnect
+        | InFlightRequest::ManageAdapter
+        | InFlightRequest::StartManager
+        | InFlightRequest::CheckManager
+        | InFlightRequest::Profile
+        | InFlightRequest::Connectivity
+        | InFlightRequest::Portal
+        | InFlightRequest::SpeedTest
+        | InFlightRequest::SavedPassword
+        | InFlightRequest::SavedProfiles
+        | InFlightRequest::ForgetProfiles
+        | InFlightRequest::ExportProfiles
+        | InFlightRequest::ProfileScope
+        | InFlightRequest::RenameProfile
+        | InFlightRequest::CloneProfile
+        | InFlightRequest::ProfilePassword
+        | InFlightRequest::ProfileBand
+        | InFlightRequest::ProfileBssid
+        | InFlightRequest::ProfileLink
+        | InFlightRequest::ProfileVpn
+        | InFlightRequest::Devices
+        | InFlightRequest::DeviceSwitch
+        | InFlightRequest::NetworkManagerLog
+        | InFlightRequest::P2pPeers
+        | InFlightRequest::P2pConnect
+        | InFlightRequest::PinAccessPoint
+        | InFlightRequest::Steer
+        | InFlightRequest::GuestCleanup
+        | InFlightRequest::Disconnect => {
+            if let Some(key) = next_key(input, app, area, wait)? {
+                handle_keypress(app, key);
+            }
+        }
+        InFlightRequest::Connect => {
+            if let Some(key) = next_key(input, app, area, wait)?
                 && key == KeyCode::Esc
             {
-                app.quit();
+                app.cancel_operation();
+                return Ok(None);
             }
         }
     }
 
-    Ok(())
+    Ok(Some(request))
+}
+
+/// Backend data an open dialog is waiting for.
+fn dialog_request(app: &App) -> Option<(RuntimeRequest, InFlightRequest)> {
+    if let Some(network) = app
+        .pending_share_password()
+        .or_else(|| app.pending_prefill_password())
+    {
+        return Some((
+            RuntimeRequest::LoadSavedPassword {
+                network: network.clone(),
+            },
+            InFlightRequest::SavedPassword,
+        ));
+    }
+    if let Some(network) = app.pending_autoconnect_check() {
+        return Some((
+            RuntimeRequest::CheckAutoconnect {
+                network: network.clone(),
+            },
+            InFlightRequest::Autoconnect,
+        ));
+    }
+    if let Some(network) = app.pending_profile_lookup() {
+        return Some((
+            RuntimeRequest::LoadProfile {
+                network: network.clone(),
+            },
+            InFlightRequest::Profile,
+        ));
+    }
+    if let Some(network) = app.pending_autoconnect_clear() {
+        return Some((
+            RuntimeRequest::ClearAutoconnectBlock {
+                network: network.clone(),
+            },
+            InFlightRequest::Autoconnect,
+        ));
+    }
+    if app.pending_connectivity_check() {
+        return Some((
+            RuntimeRequest::CheckConnectivity,
+            InFlightRequest::Connectivity,
+        ));
+    }
+    if let Some(url) = app.pending_portal_open() {
+        return Some((
+            RuntimeRequest::OpenPortal {
+                url: url.to_string(),
+            },
+            InFlightRequest::Portal,
+        ));
+    }
+    if let Some(direction) = app.pending_speed_test_chunk() {
+        return Some((
+            RuntimeRequest::SpeedTestChunk {
+                endpoint: app.speedtest_url.clone(),
+                direction,
+            },
+            InFlightRequest::SpeedTest,
+        ));
+    }
+    if let Some(interface) = app.pending_adapter_manage() {
+        return Some((
+            RuntimeRequest::ManageAdapter {
+                interface: interface.to_string(),
+            },
+            InFlightRequest::ManageAdapter,
+        ));
+    }
+    if app.pending_manager_start() {
+        return Some((
+            RuntimeRequest::StartNetworkManager,
+            InFlightRequest::StartManager,
+        ));
+    }
+    if app.manager_check_due() {
+        return Some((
+            RuntimeRequest::CheckManager,
+            InFlightRequest::CheckManager,
+        ));
+    }
+    if app.pending_profile_list() {
+        return Some((
+            RuntimeRequest::ListSavedProfiles,
+            InFlightRequest::SavedProfiles,
+        ));
+    }
+    if let Some((profiles, directory, include_secrets)) =
+        app.pending_profile_export()
+    {
+        return Some((
+            RuntimeRequest::ExportProfiles {
+                profiles,
+                directory,
+                include_secrets,
+            },
+            InFlightRequest::ExportProfiles,
+        ));
+    }
+    if let Some((profile, owner)) = app.pending_profile_scope() {
+        return Some((
+            RuntimeRequest::SetProfileScope { profile, owner },
+            InFlightRequest::ProfileScope,
+        ));
+    }
+    if let Some((profile, name)) = app.pending_profile_rename() {
+        return Some((
+            RuntimeRequest::RenameProfile { profile, name },
+            InFlightRequest::RenameProfile,
+        ));
+    }
+    if let Some((profile, name)) = app.pending_profile_clone() {
+        return Some((
+            RuntimeRequest::CloneProfile { profile, name },
+            InFlightRequest::CloneProfile,
+        ));
+    }
+    if let Some((profile, password)) = app.pending_profile_password() {
+        return Some((
+            RuntimeRequest::SetProfilePassword { profile, password },
+            InFlightRequest::ProfilePassword,
+        ));
+    }
+    if let Some((profile, band)) = app.pending_profile_band() {
+        return Some((
+            RuntimeRequest::SetProfileBand { profile, band },
+            InFlightRequest::ProfileBand,
+        ));
+    }
+    if let Some((profile, bssid)) = app.pending_profile_bssid() {
+        return Some((
+            RuntimeRequest::SetProfileBssid { profile, bssid },
+            InFlightRequest::ProfileBssid,
+        ));
+    }
+    if let Some((profile, setting)) = app.pending_profile_link() {
+        return Some((
+            RuntimeRequest::SetProfileLink { profile, setting },
+            InFlightRequest::ProfileLink,
+        ));
+    }
+    if let Some((profile, vpn)) = app.pending_profile_vpn() {
+        return Some((
+            RuntimeRequest::SetProfileVpn { profile, vpn },
+            InFlightRequest::ProfileVpn,
+        ));
+    }
+    if let Some(kind) = app.pending_device_list() {
+        return Some((
+            RuntimeRequest::ListDevices { kind },
+            InFlightRequest::Devices,
+        ));
+    }
+    if let Some((interface, profile)) = app.pending_device_change() {
+        return Some((
+            RuntimeRequest::SetDeviceProfile { interface, profile },
+            InFlightRequest::DeviceSwitch,
+        ));
+    }
+    if app.pending_p2p_search() {
+        return Some((RuntimeRequest::FindP2pPeers, InFlightRequest::P2pPeers));
+    }
+    if let Some(peer) = app.pending_p2p_connect() {
+        return Some((
+            RuntimeRequest::ConnectP2pPeer { peer },
+            InFlightRequest::P2pConnect,
+        ));
+    }
+    if let Some((network, bssid)) = app.pending_access_point_pin() {
+        return Some((
+            RuntimeRequest::PinAccessPoint { network, bssid },
+            InFlightRequest::PinAccessPoint,
+        ));
+    }
+    app.pending_forget().map(|profiles| {
+        (
+            RuntimeRequest::ForgetProfiles { profiles },
+            InFlightRequest::ForgetProfiles,
+        )
+    })
 }
 
 fn connection_request(app: &App) -> RuntimeRequest {
@@ -184,6 +904,21 @@ fn connection_request(app: &App) -> RuntimeRequest {
         CONNECTION_COMPLETION_REQUIRES_NETWORK,
     )
     .clone();
+    match app.operation {
+        OperationKind::Reconnect => {
+            return RuntimeRequest::Reconnect {
+                network,
+                cancel: app.operation_cancel.clone(),
+            };
+        }
+        OperationKind::RenewLease => {
+            return RuntimeRequest::RenewLease {
+                network,
+                cancel: app.operation_cancel.clone(),
+            };
+        }
+        OperationKind::Connect | OperationKind::Disconnect => {}
+    }
     let passphrase = matches!(network.security, WifiSecurity::Open)
         .then(|| None)
         .unwrap_or_else(|| Some(app.password_input.clone()));
@@ -191,6 +926,10 @@ fn connection_request(app: &App) -> RuntimeRequest {
     RuntimeRequest::Connect {
         network,
         passphrase,
+        guest: app.guest_connection,
+        options: app.connection_options(),
+        cancel: app.operation_cancel.clone(),
+        progress: app.activation_progress.clone(),
     }
 }
 
@@ -203,43 +942,190 @@ fn disconnection_request(app: &App) -> RuntimeRequest {
     RuntimeRequest::Disconnect { network }
 }
 
+fn scan_request(app: &mut App) -> RuntimeRequest {
+    RuntimeRequest::Scan {
+        fresh: app.take_fresh_scan(),
+        band: app.scan_band,
+    }
+}
+
+fn apply_background_scan(app: &mut App, result: Result<ScanSnapshot, String>) {
+    match result {
+        Ok(snapshot) => {
+            app.update_manager_status(snapshot.manager_status);
+            app.unmanaged_adapters = snapshot.unmanaged_adapters;
+            app.merge_rescanned_networks(snapshot.networks);
+            app.note_manager_owner(snapshot.manager_owner);
+            app.publish_link_status();
+            app.schedule_signal_refresh();
+        }
+        Err(error) => app.notifier.toasts().push(
+            ToastLevel::Warning,
+            "Background scan failed",
+            error,
+        ),
+    }
+    app.schedule_rescan();
+}
+
 fn apply_runtime_event(app: &mut App, event: RuntimeEvent) {
+    if let Some(error) = event
+        .action_error()
+        .filter(|error| is_authorization_failure(error))
+    {
+        app.show_authorization_denied(error.to_string());
+    }
+
     match event {
-        RuntimeEvent::Scan(Ok(snapshot)) => apply_scanned_networks(
-            app,
-            snapshot.networks,
-            snapshot.adapter_name,
-        ),
-        RuntimeEvent::Scan(Err(error)) => app.handle_scan_error(error),
+        RuntimeEvent::Scan(Ok(snapshot)) => {
+            app.update_manager_status(snapshot.manager_status);
+            app.unmanaged_adapters = snapshot.unmanaged_adapters;
+            apply_scanned_networks(
+                app,
+                snapshot.networks,
+                snapshot.adapter_name,
+            );
+            app.note_manager_owner(snapshot.manager_owner);
+            app.publish_link_status();
+            app.schedule_rescan();
+            app.schedule_signal_refresh();
+        }
+        RuntimeEvent::Scan(Err(error)) => {
+            app.handle_scan_error(error);
+            app.schedule_rescan();
+        }
+        // A failed refresh just leaves the last known bars until the next try.
+        RuntimeEvent::Signals(result) => {
+            if let Ok(signals) = result {
+                app.apply_signal_updates(&signals);
+                app.record_survey_sample(&signals);
+                app.publish_link_status();
+            }
+            app.schedule_signal_refresh();
+        }
+        RuntimeEvent::Traffic(result) => app.apply_traffic_sample(result),
+        RuntimeEvent::Latency(result) => app.apply_latency_sample(result),
+        RuntimeEvent::Bitrate(result) => app.apply_link_bitrate(result),
+        RuntimeEvent::AutoconnectBlock(result) => {
+            app.apply_autoconnect_block(result)
+        }
+        RuntimeEvent::AutoconnectCleared(result) => {
+            app.finish_autoconnect_clear(result)
+        }
+        RuntimeEvent::AdapterManaged(result) => {
+            app.finish_adapter_manage(result)
+        }
+        RuntimeEvent::ManagerUnavailable(error) => {
+            app.show_manager_unavailable(error)
+        }
+        RuntimeEvent::ManagerStarted(result) => {
+            app.finish_manager_start(result)
+        }
+        RuntimeEvent::ManagerChecked(result) => app.apply_manager_check(result),
+        RuntimeEvent::ProfileSettings(result) => {
+            app.apply_profile_settings(result)
+        }
+        RuntimeEvent::Connectivity(result) => app.apply_connectivity(result),
+        RuntimeEvent::PortalOpened(result) => app.finish_portal_open(result),
+        RuntimeEvent::SpeedTestChunk(direction, result) => {
+            app.apply_speed_test_chunk(direction, result)
+        }
+        RuntimeEvent::SavedPassword(result) => app.apply_saved_password(result),
+        RuntimeEvent::SavedProfiles(result) => app.apply_saved_profiles(result),
+        RuntimeEvent::ProfilesForgotten(result) => {
+            app.finish_bulk_forget(result)
+        }
+        RuntimeEvent::ProfilesExported(result) => {
+            app.finish_profile_export(result)
+        }
+        RuntimeEvent::ProfileScopeChanged(owner, result) => {
+            app.finish_profile_scope(owner, result)
+        }
+        RuntimeEvent::ProfileRenamed(name, result) => {
+            app.finish_profile_rename(name, result)
+        }
+        RuntimeEvent::ProfileCloned(result) => app.finish_profile_clone(result),
+        RuntimeEvent::ProfilePasswordSet(result) => {
+            app.finish_profile_password(result)
+        }
+        RuntimeEvent::ProfileBandChanged(band, result) => {
+            app.finish_profile_band(band, result)
+        }
+        RuntimeEvent::ProfileBssidSet(bssid, result) => {
+            app.finish_profile_bssid(bssid, result)
+        }
+        RuntimeEvent::ProfileLinkSet(setting, result) => {
+            app.finish_profile_link(setting, result)
+        }
+        RuntimeEvent::ProfileVpnSet(vpn, result) => {
+            app.finish_profile_vpn(vpn, result)
+        }
+        RuntimeEvent::Devices(result) => app.apply_devices(result),
+        RuntimeEvent::DeviceProfileSet(interface, profile, result) => {
+            app.finish_device_change(&interface, profile, result)
+        }
+        RuntimeEvent::NetworkManagerLog(result) => app.apply_log_lines(result),
+        RuntimeEvent::P2pPeers(result) => app.apply_p2p_peers(result),
+        RuntimeEvent::P2pPeerConnected(name, result) => {
+            app.finish_p2p_connect(&name, result)
+        }
+        RuntimeEvent::AccessPointPinned(bssid, result) => {
+            app.finish_access_point_pin(&bssid, result)
+        }
         RuntimeEvent::Connect(Ok(())) => app.finish_operation(true, None),
         RuntimeEvent::Connect(Err(error)) => {
             app.finish_operation(false, Some(error))
         }
+        RuntimeEvent::PasswordRejected(error) => app.reject_password(error),
+        RuntimeEvent::ConnectFailed(kind, error) => {
+            app.fail_connection(kind, error)
+        }
+        RuntimeEvent::Reconnect(Ok(())) => app.finish_operation(true, None),
+        RuntimeEvent::Reconnect(Err(error)) => {
+            app.finish_operation(false, Some(error))
+        }
+        RuntimeEvent::RenewLease(result) => app.finish_lease_renewal(result),
+        RuntimeEvent::Steered(bssid, result) => {
+            app.finish_steer(&bssid, result)
+        }
         RuntimeEvent::Disconnect(Ok(())) => app.finish_operation(true, None),
         RuntimeEvent::Disconnect(Err(error)) => {
             app.finish_operation(false, Some(error))
         }
+        RuntimeEvent::GuestSessionEnded(result) => {
+            app.finish_guest_cleanup(result)
+        }
     }
 }
 
 #[cfg(test)]
 mod tests {
-    use std::{collections::VecDeque, error::Error, time::Duration};
+    use std::{
+        collections::VecDeque,
+        error::Error,
+        time::{Duration, Instant},
+    };
 
     use crossterm::event::KeyCode;
     use ratatui::{Terminal, backend::TestBackend};
 
     use super::{
+        FrameClock,
+        Input,
         RuntimeBackendDriver,
         RuntimeEvent,
         RuntimeInput,
         RuntimeRequest,
         apply_runtime_event,
+        connection_request,
+        dialog_request,
+        handle_keypress,
         run_app_with_runtime,
     };
     use crate::{
-        app_state::{App, AppState},
-        wifi::{WifiNetwork, WifiSecurity},
+        app_state::{App, AppState, GuestSession, OperationKind},
+        network::{ForgetReport, SavedProfile},
+        wifi::{WifiAccessPoint, WifiNetwork, WifiSecurity},
     };
 
     fn network(
@@ -249,10 +1135,16 @@ mod tests {
     ) -> WifiNetwork {
         WifiNetwork {
             ssid: ssid.to_string(),
+            raw_ssid: None,
             signal_strength: 78,
             security,
             frequency: 5180,
             connected,
+            saved: false,
+            max_bitrate: 0,
+            wpa_flags: 0,
+            rsn_flags: 0,
+            access_points: Vec::new(),
         }
     }
 
@@ -269,11 +1161,11 @@ mod tests {
     }
 
     impl RuntimeInput for ScriptedInput {
-        fn next_key(
+        fn next_input(
             &mut self,
             _timeout: Duration,
-        ) -> Result<Option<KeyCode>, Box<dyn Error>> {
-            Ok(self.keys.pop_front().flatten())
+        ) -> Result<Option<Input>, Box<dyn Error>> {
+            Ok(self.keys.pop_front().flatten().map(Input::Key))
         }
     }
 
@@ -294,10 +1186,104 @@ mod tests {
     impl RuntimeBackendDriver for ScriptedDriver {
         fn begin(&mut self, request: RuntimeRequest) {
             match request {
-                RuntimeRequest::Scan => self.begin_calls.push("scan"),
+                RuntimeRequest::Scan { .. } => self.begin_calls.push("scan"),
+                RuntimeRequest::RefreshSignals => {
+                    self.begin_calls.push("signals")
+                }
+                RuntimeRequest::ReadTraffic { .. } => {
+                    self.begin_calls.push("traffic")
+                }
+                RuntimeRequest::MeasureLatency { .. } => {
+                    self.begin_calls.push("latency")
+                }
+                RuntimeRequest::ReadBitrate => self.begin_calls.push("bitrate"),
+                RuntimeRequest::CheckAutoconnect { .. } => {
+                    self.begin_calls.push("check_autoconnect")
+                }
+                RuntimeRequest::ClearAutoconnectBlock { .. } => {
+                    self.begin_calls.push("clear_autoconnect")
+                }
+                RuntimeRequest::StartNetworkManager => {
+                    self.begin_calls.push("start_manager")
+                }
+                RuntimeRequest::CheckManager => {
+                    self.begin_calls.push("check_manager")
+                }
+                RuntimeRequest::ManageAdapter { .. } => {
+                    self.begin_calls.push("manage_adapter")
+                }
+                RuntimeRequest::LoadProfile { .. } => {
+                    self.begin_calls.push("profile")
+                }
+                RuntimeRequest::CheckConnectivity => {
+                    self.begin_calls.push("connectivity")
+                }
+                RuntimeRequest::OpenPortal { .. } => {
+                    self.begin_calls.push("open_portal")
+                }
+                RuntimeRequest::SpeedTestChunk { .. } => {
+                    self.begin_calls.push("speed-test")
+                }
+                RuntimeRequest::LoadSavedPassword { .. } => {
+                    self.begin_calls.push("saved-password")
+                }
+                RuntimeRequest::ListSavedProfiles => {
+                    self.begin_calls.push("saved-profiles")
+                }
+                RuntimeRequest::ForgetProfiles { profiles } => {
+                    assert_eq!(profiles.len(), 2);
+                    self.begin_calls.push("forget")
+                }
+                RuntimeRequest::ExportProfiles { .. } => {
+                    self.begin_calls.push("export")
+                }
+                RuntimeRequest::SetProfileScope { .. } => {
+                    self.begin_calls.push("profile-scope")
+                }
+                RuntimeRequest::RenameProfile { .. } => {
+                    self.begin_calls.push("rename-profile")
+                }
+                RuntimeRequest::CloneProfile { .. } => {
+                    self.begin_calls.push("clone-profile")
+                }
+                RuntimeRequest::SetProfilePassword { .. } => {
+                    self.begin_calls.push("profile-password")
+                }
+                RuntimeRequest::SetProfileBand { .. } => {
+                    self.begin_calls.push("profile-band")
+                }
+                RuntimeRequest::SetProfileBssid { .. } => {
+                    self.begin_calls.push("profile-bssid")
+                }
+                RuntimeRequest::SetProfileLink { .. } => {
+                    self.begin_calls.push("profile-link")
+                }
+                RuntimeRequest::SetProfileVpn { .. } => {
+                    self.begin_calls.push("profile-vpn")
+                }
+                RuntimeRequest::ListDevices { .. } => {
+                    self.begin_calls.push("devices")
+                }
+                RuntimeRequest::SetDeviceProfile { .. } => {
+                    self.begin_calls.push("device-profile")
+                }
+                RuntimeRequest::ReadNetworkManagerLog { .. } => {
+                    self.begin_calls.push("nm-log")
+                }
+                RuntimeRequest::FindP2pPeers => {
+                    self.begin_calls.push("p2p-peers")
+                }
+                RuntimeRequest::ConnectP2pPeer { .. } => {
+                    self.begin_calls.push("p2p-connect")
+                }
+                RuntimeRequest::PinAccessPoint { network, .. } => {
+                    assert_eq!(network.ssid, "CatCat");
+                    self.begin_calls.push("pin-access-point")
+                }
                 RuntimeRequest::Connect {
                     network,
                     passphrase,
+                    ..
                 } => {
                     assert_eq!(network.ssid, "CatCat");
                     assert_eq!(passphrase.as_deref(), Some("AcerolaAcai"));
@@ -307,6 +1293,22 @@ mod tests {
                     assert_eq!(network.ssid, "CatCat");
                     self.begin_calls.push("disconnect")
                 }
+                RuntimeRequest::Reconnect { network, .. } => {
+                    assert_eq!(network.ssid, "CatCat");
+                    self.begin_calls.push("reconnect")
+                }
+                RuntimeRequest::RenewLease { network, .. } => {
+                    assert_eq!(network.ssid, "CatCat");
+                    self.begin_calls.push("renew_lease")
+                }
+                RuntimeRequest::SteerTo { network, .. } => {
+                    assert_eq!(network.ssid, "CatCat");
+                    self.begin_calls.push("steer")
+                }
+                RuntimeRequest::EndGuestSession { ssid } => {
+                    assert_eq!(ssid, "CatCat");
+                    self.begin_calls.push("end-guest-session")
+                }
             }
         }
 
@@ -318,11 +1320,19 @@ mod tests {
     }
 
     #[tokio::test]
-    async fn pending_connect_can_be_aborted_with_esc() {
+    async fn pending_connect_is_cancelled_with_esc() {
         let backend = TestBackend::new(80, 24);
         let mut terminal = Terminal::new(backend).expect("terminal created");
-        let mut input = ScriptedInput::new(vec![None, Some(KeyCode::Esc)]);
-        let mut driver = ScriptedDriver::new(vec![None, None]);
+        let mut input = ScriptedInput::new(vec![
+            None,
+            Some(KeyCode::Esc),
+            Some(KeyCode::Char('q')),
+        ]);
+        let mut driver = ScriptedDriver::new(vec![
+            None,
+            None,
+            Some(RuntimeEvent::Connect(Ok(()))),
+        ]);
         let mut app = App::new();
         app.state = AppState::Connecting;
         app.selected_network =
@@ -334,8 +1344,11 @@ mod tests {
                 .await
                 .expect("runtime loop succeeds");
 
-        assert!(app.should_quit);
-        assert!(matches!(app.state, AppState::Connecting));
+        assert!(matches!(app.state, AppState::NetworkList));
+        assert!(app.operation_cancel.is_cancelled());
+        assert!(!app.connection_success);
+        assert_eq!(app.status_message, "Cancelled connecting to CatCat");
+        assert!(app.backend_activity.is_none());
         assert_eq!(driver.begin_calls, vec!["connect"]);
     }
 
@@ -385,9 +1398,426 @@ mod tests {
         assert!(matches!(app.state, AppState::ConnectionResult));
         assert!(!app.connection_success);
         assert_eq!(app.connection_error.as_deref(), Some("disconnect failed"));
+        assert!(app.backend_activity.is_none());
         assert_eq!(driver.begin_calls, vec!["disconnect"]);
     }
 
+    #[tokio::test]
+    async fn pending_disconnect_is_not_cancelled_with_esc() {
+        let backend = TestBackend::new(80, 24);
+        let mut terminal = Terminal::new(backend).expect("terminal created");
+        let mut input = ScriptedInput::new(vec![
+            None,
+            Some(KeyCode::Esc),
+            None,
+            Some(KeyCode::Esc),
+        ]);
+        let mut driver = ScriptedDriver::new(vec![
+            None,
+            None,
+            Some(RuntimeEvent::Disconnect(Ok(()))),
+            None,
+        ]);
+        let mut app = App::new();
+        app.state = AppState::Disconnecting;
+        app.selected_network =
+            Some(network("CatCat", WifiSecurity::WpaSae, true));
+        app.is_disconnect_operation = true;
+        app.operation = OperationKind::Disconnect;
+
+        let app =
+            run_app_with_runtime(&mut terminal, &mut input, &mut driver, app)
+                .await
+                .expect("runtime loop succeeds");
+
+        assert!(matches!(app.state, AppState::ConnectionResult));
+        assert!(app.connection_success);
+        assert!(!app.operation_cancel.is_cancelled());
+        assert_eq!(driver.begin_calls, vec!["disconnect"]);
+    }
+
+    #[tokio::test]
+    async fn quitting_leaves_a_guest_session_connected_by_default() {
+        let backend = TestBackend::new(80, 24);
+        let mut terminal = Terminal::new(backend).expect("terminal created");
+        let mut input = ScriptedInput::new(vec![Some(KeyCode::Char('q'))]);
+        let mut driver = ScriptedDriver::new(vec![None]);
+        let mut app = App::new();
+        app.state = AppState::NetworkList;
+        app.guest_session = Some(GuestSession {
+            ssid: "CatCat".to_string(),
+            started: Instant::now(),
+        });
+
+        let app =
+            run_app_with_runtime(&mut terminal, &mut input, &mut driver, app)
+                .await
+                .expect("runtime loop succeeds");
+
+        assert!(app.should_quit);
+        assert!(app.guest_session.is_some());
+        assert!(driver.begin_calls.is_empty());
+    }
+
+    #[tokio::test]
+    async fn quitting_a_guest_session_deletes_its_profile_first() {
+        let backend = TestBackend::new(80, 24);
+        let mut terminal = Terminal::new(backend).expect("terminal created");
+        let mut input = ScriptedInput::new(vec![Some(KeyCode::Char('q'))]);
+        let mut driver = ScriptedDriver::new(vec![
+            None,
+            Some(RuntimeEvent::GuestSessionEnded(Ok(()))),
+        ]);
+        let mut app = App::new();
+        app.state = AppState::NetworkList;
+        app.delete_guest_profile_on_quit = true;
+        app.guest_session = Some(GuestSession {
+            ssid: "CatCat".to_string(),
+            started: Instant::now(),
+        });
+
+        let app =
+            run_app_with_runtime(&mut terminal, &mut input, &mut driver, app)
+                .await
+                .expect("runtime loop succeeds");
+
+        assert!(app.should_quit);
+        assert!(app.guest_session.is_none());
+        assert_eq!(app.pending_guest_cleanup(), None);
+        assert_eq!(driver.begin_calls, vec!["end-guest-session"]);
+    }
+
+    #[tokio::test]
+    async fn the_guest_cleanup_on_quit_waits_for_the_request_under_way() {
+        let backend = TestBackend::new(80, 24);
+        let mut terminal = Terminal::new(backend).expect("terminal created");
+        let mut input =
+            ScriptedInput::new(vec![None, Some(KeyCode::Char('q'))]);
+        let mut driver = ScriptedDriver::new(vec![
+            None,
+            None,
+            Some(RuntimeEvent::Scan(Ok(super::ScanSnapshot {
+                networks: vec![network("CatCat", WifiSecurity::Open, true)],
+                adapter_name: None,
+                manager_status: None,
+                unmanaged_adapters: Vec::new(),
+                manager_owner: None,
+            }))),
+            Some(RuntimeEvent::GuestSessionEnded(Ok(()))),
+        ]);
+        let mut app = App::new();
+        app.state = AppState::NetworkList;
+        app.rescan_interval = Some(Duration::from_secs(60));
+        app.next_rescan_at = Some(Instant::now());
+        app.delete_guest_profile_on_quit = true;
+        app.guest_session = Some(GuestSession {
+            ssid: "CatCat".to_string(),
+            started: Instant::now(),
+        });
+
+        let app =
+            run_app_with_runtime(&mut terminal, &mut input, &mut driver, app)
+                .await
+                .expect("runtime loop succeeds");
+
+        assert!(app.should_quit);
+        assert_eq!(app.pending_guest_cleanup(), None);
+        assert_eq!(driver.begin_calls, vec!["scan", "end-guest-session"]);
+    }
+
+    #[tokio::test]
+    async fn a_missing_network_manager_can_be_started_from_its_screen() {
+        let backend = TestBackend::new(80, 24);
+        let mut terminal = Terminal::new(backend).expect("terminal created");
+        let mut input = ScriptedInput::new(vec![
+            None,
+            Some(KeyCode::Char('s')),
+            None,
+            None,
+            Some(KeyCode::Char('q')),
+        ]);
+        let mut driver = ScriptedDriver::new(vec![
+            None,
+            Some(RuntimeEvent::ManagerUnavailable(
+                "NetworkManager is not running on the system bus".to_string(),
+            )),
+            None,
+            None,
+            Some(RuntimeEvent::ManagerStarted(Ok(()))),
+            None,
+            Some(RuntimeEvent::Scan(Ok(super::ScanSnapshot {
+                networks: vec![network("CatCat", WifiSecurity::WpaSae, false)],
+                adapter_name: None,
+                manager_status: None,
+                unmanaged_adapters: Vec::new(),
+                manager_owner: None,
+            }))),
+            None,
+        ]);
+
+        let app = run_app_with_runtime(
+            &mut terminal,
+            &mut input,
+            &mut driver,
+            App::new(),
+        )
+        .await
+        .expect("runtime loop succeeds");
+
+        assert_eq!(driver.begin_calls, vec!["scan", "start_manager", "scan"]);
+        assert!(matches!(app.state, AppState::NetworkList));
+        assert!(app.manager_error.is_none());
+        assert_eq!(app.networks.len(), 1);
+    }
+
+    #[tokio::test]
+    async fn background_rescans_keep_the_list_and_selection() {
+        let backend = TestBackend::new(80, 24);
+        let mut terminal = Terminal::new(backend).expect("terminal created");
+        let mut input =
+            ScriptedInput::new(vec![None, Some(KeyCode::Char('q'))]);
+        let mut driver = ScriptedDriver::new(vec![
+            None,
+            Some(RuntimeEvent::Scan(Ok(super::ScanSnapshot {
+                networks: vec![
+                    network("Neighbour", WifiSecurity::WpaPsk, false),
+                    network("CatCat", WifiSecurity::WpaSae, true),
+                ],
+                adapter_name: None,
+                manager_status: None,
+                unmanaged_adapters: Vec::new(),
+                manager_owner: None,
+            }))),
+            None,
+        ]);
+        let mut app = App::new();
+        app.state = AppState::NetworkList;
+        app.networks = vec![network("CatCat", WifiSecurity::WpaSae, true)];
+        app.rescan_interval = Some(Duration::from_secs(60));
+        app.next_rescan_at = Some(std::time::Instant::now());
+
+        let app =
+            run_app_with_runtime(&mut terminal, &mut input, &mut driver, app)
+                .await
+                .expect("runtime loop succeeds");
+
+        assert_eq!(driver.begin_calls, vec!["scan"]);
+        assert!(matches!(app.state, AppState::NetworkList));
+        assert_eq!(app.network_count, 2);
+        assert_eq!(
+            app.selected_network_in_list().map(|n| n.ssid.as_str()),
+            Some("CatCat")
+        );
+        assert!(
+            app.rescan_countdown()
+                .is_some_and(|countdown| countdown > Duration::from_secs(50))
+        );
+    }
+
+    #[tokio::test]
+    async fn signal_refreshes_update_bars_without_reordering() {
+        let backend = TestBackend::new(80, 24);
+        let mut terminal = Terminal::new(backend).expect("terminal created");
+        let mut input =
+            ScriptedInput::new(vec![None, Some(KeyCode::Char('q'))]);
+        let access_point = |bssid: &str, signal_strength| WifiAccessPoint {
+            bssid: bssid.to_string(),
+            frequency: 5180,
+            signal_strength,
+            associated: false,
+        };
+        let mut driver = ScriptedDriver::new(vec![
+            None,
+            Some(RuntimeEvent::Signals(Ok(vec![access_point("bb", 90)]))),
+            None,
+        ]);
+        let mut weak = network("Weak", WifiSecurity::WpaPsk, false);
+        weak.signal_strength = 20;
+        weak.access_points = vec![access_point("bb", 20)];
+        let mut app = App::new();
+        app.state = AppState::NetworkList;
+        app.networks =
+            vec![network("CatCat", WifiSecurity::WpaSae, true), weak];
+        app.next_signal_refresh_at = Some(std::time::Instant::now());
+
+        let app =
+            run_app_with_runtime(&mut terminal, &mut input, &mut driver, app)
+                .await
+                .expect("runtime loop succeeds");
+
+        assert_eq!(driver.begin_calls, vec!["signals"]);
+        assert_eq!(app.networks[1].ssid, "Weak");
+        assert_eq!(app.networks[1].signal_strength, 90);
+        assert_eq!(app.networks[1].access_points[0].signal_strength, 90);
+        assert!(app.next_signal_refresh_at.is_some());
+    }
+
+    #[tokio::test]
+    async fn sharing_a_saved_network_loads_its_password_once() {
+        let backend = TestBackend::new(80, 24);
+        let mut terminal = Terminal::new(backend).expect("terminal created");
+        let mut input = ScriptedInput::new(vec![
+            Some(KeyCode::Char('x')),
+            None,
+            Some(KeyCode::Esc),
+            Some(KeyCode::Char('q')),
+        ]);
+        let mut driver = ScriptedDriver::new(vec![
+            None,
+            None,
+            Some(RuntimeEvent::SavedPassword(Ok(Some(
+                "AcerolaAcai".to_string(),
+            )))),
+            None,
+            None,
+        ]);
+        let mut catcat = network("CatCat", WifiSecurity::WpaSae, false);
+        catcat.saved = true;
+        let mut app = App::new();
+        app.state = AppState::NetworkList;
+        app.networks = vec![catcat];
+
+        let app =
+            run_app_with_runtime(&mut terminal, &mut input, &mut driver, app)
+                .await
+                .expect("runtime loop succeeds");
+
+        assert_eq!(driver.begin_calls, vec!["saved-password"]);
+        assert!(app.qr_share.is_none());
+        assert!(app.should_quit);
+    }
+
+    #[tokio::test]
+    async fn bulk_forget_lists_profiles_then_forgets_confirmed_matches() {
+        let backend = TestBackend::new(80, 24);
+        let mut terminal = Terminal::new(backend).expect("terminal created");
+        let profile = |id: &str| SavedProfile {
+            id: id.to_string(),
+            uuid: format!("uuid-{id}"),
+            ssid: id.to_string(),
+            users: Vec::new(),
+            last_used: None,
+            band: None,
+            bssid: None,
+            mtu: None,
+            cloned_mac: None,
+            vpn: None,
+        };
+        let mut keys = vec![Some(KeyCode::Char(':')), Some(KeyCode::Char('f'))];
+        keys.push(Some(KeyCode::Enter));
+        keys.push(None);
+        keys.extend("*guest".chars().map(|c| Some(KeyCode::Char(c))));
+        keys.extend([Some(KeyCode::Enter), Some(KeyCode::Char('y')), None]);
+        keys.push(Some(KeyCode::Esc));
+        let mut input = ScriptedInput::new(keys);
+        let mut events = vec![None, None, None, None];
+        events.push(Some(RuntimeEvent::SavedProfiles(Ok(vec![
+            profile("Home"),
+            profile("Hotel_Guest"),
+            profile("Airport_Guest"),
+        ]))));
+        events.extend((0..9).map(|_| None));
+        events.push(Some(RuntimeEvent::ProfilesForgotten(Ok(ForgetReport {
+            forgotten: 2,
+            requested: 2,
+            error: None,
+        }))));
+        let mut driver = ScriptedDriver::new(events);
+        let mut app = App::new();
+        app.state = AppState::NetworkList;
+
+        let app =
+            run_app_with_runtime(&mut terminal, &mut input, &mut driver, app)
+                .await
+                .expect("runtime loop succeeds");
+
+        assert_eq!(driver.begin_calls, vec!["saved-profiles", "forget"]);
+        assert!(app.bulk_forget.is_none());
+        assert_eq!(
+            app.status_message,
+            "Forgot 2 saved network(s). Rescanning..."
+        );
+    }
+
+    #[test]
+    fn frames_keep_a_steady_tick_through_input() {
+        let start = Instant::now();
+        let ms = |millis| start + Duration::from_millis(millis);
+        let mut frames = FrameClock::new(start);
+
+        assert_eq!(frames.wait(start, true), Duration::from_millis(100));
+        // A key at 30ms draws, then waits out the rest of the tick.
+        assert_eq!(frames.wait(ms(30), true), Duration::from_millis(70));
+        assert_eq!(frames.wait(ms(100), true), Duration::from_millis(100));
+
+        assert_eq!(frames.wait(ms(200), false), Duration::from_millis(250));
+        // An animation starting mid-way does not wait for the idle tick.
+        assert_eq!(frames.wait(ms(220), true), Duration::from_millis(100));
+    }
+
+    #[test]
+    fn polkit_denials_open_a_dialog_over_the_result() {
+        let mut app = App::new();
+        app.state = AppState::Connecting;
+        app.selected_network =
+            Some(network("CatCat", WifiSecurity::WpaSae, false));
+
+        apply_runtime_event(
+            &mut app,
+            RuntimeEvent::Connect(Err(
+                "NetworkManager failed to activate the WiFi connection: Not \
+                 authorized to control networking."
+                    .to_string(),
+            )),
+        );
+        assert!(matches!(app.state, AppState::ConnectionResult));
+        assert!(app.authorization_denied.is_some());
+
+        handle_keypress(&mut app, KeyCode::Esc);
+        assert!(app.authorization_denied.is_none());
+        assert!(!app.should_quit);
+
+        apply_runtime_event(
+            &mut app,
+            RuntimeEvent::ProfilesExported(Err(
+                "Permission denied (os error 13)".to_string(),
+            )),
+        );
+        assert!(app.authorization_denied.is_none());
+    }
+
+    #[test]
+    fn empty_scans_of_unmanaged_adapters_offer_to_manage_them() {
+        let mut app = App::new();
+        apply_runtime_event(
+            &mut app,
+            RuntimeEvent::Scan(Ok(super::ScanSnapshot {
+                networks: Vec::new(),
+                adapter_name: Some("wlan0".to_string()),
+                manager_status: None,
+                unmanaged_adapters: vec!["wlan0".to_string()],
+                manager_owner: None,
+            })),
+        );
+
+        assert!(matches!(app.state, AppState::NetworkList));
+        assert!(app.adapter_unmanaged());
+        assert_eq!(
+            app.status_message,
+            "wlan0 is not managed by NetworkManager. Press M to manage it."
+        );
+        assert!(dialog_request(&app).is_none());
+
+        app.manage_adapter();
+        assert!(matches!(
+            dialog_request(&app),
+            Some((
+                RuntimeRequest::ManageAdapter { ref interface },
+                super::InFlightRequest::ManageAdapter
+            )) if interface == "wlan0"
+        ));
+    }
+
     #[test]
     fn runtime_events_apply_scan_and_connect_results() {
         let mut app = App::new();
@@ -396,6 +1826,9 @@ mod tests {
             RuntimeEvent::Scan(Ok(super::ScanSnapshot {
                 networks: vec![network("CatCat", WifiSecurity::WpaSae, true)],
                 adapter_name: Some("demo-wlan0".to_string()),
+                manager_status: None,
+                unmanaged_adapters: Vec::new(),
+                manager_owner: None,
             })),
         );
 
@@ -410,4 +1843,54 @@ mod tests {
         assert!(matches!(app.state, AppState::ConnectionResult));
         assert!(app.connection_success);
     }
+
+    #[test]
+    fn rejected_passwords_reopen_the_prompt() {
+        let mut app = App::new();
+        app.begin_operation(
+            network("CatCat", WifiSecurity::WpaSae, false),
+            OperationKind::Connect,
+        );
+        app.password_input = "wrong".to_string();
+        app.profile_name_input = "Home".to_string();
+
+        apply_runtime_event(
+            &mut app,
+            RuntimeEvent::PasswordRejected(
+                "Demo mode: invalid password".to_string(),
+            ),
+        );
+
+        assert!(matches!(app.state, AppState::PasswordInput));
+        assert!(app.password_rejected);
+        assert!(app.password_input.is_empty());
+        assert_eq!(app.profile_name_input, "Home");
+        assert_eq!(
+            app.selected_network
+                .as_ref()
+                .map(|network| network.ssid.as_str()),
+            Some("CatCat")
+        );
+
+        app.password_input = "AcerolaAcai".to_string();
+        app.confirm_password();
+        assert!(matches!(app.state, AppState::Connecting));
+        assert!(!app.password_rejected);
+    }
+
+    #[test]
+    fn connect_requests_carry_the_custom_profile_name() {
+        let mut app = App::new();
+        app.selected_network =
+            Some(network("CatCat", WifiSecurity::WpaSae, false));
+        app.password_input = "AcerolaAcai".to_string();
+        app.profile_name_input = "Home".to_string();
+
+        let RuntimeRequest::Connect { options, .. } = connection_request(&app)
+        else {
+            panic!("expected a connect request");
+        };
+
+        assert_eq!(options.profile_name.as_deref(), Some("Home"));
+    }
 }
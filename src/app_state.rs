@@ -1,6 +1,99 @@
-use std::time::Instant;
+use std::{
+    cell::RefCell,
+    cmp::{Ordering, Reverse},
+    collections::{HashMap, HashSet},
+    fs,
+    path::{Path, PathBuf},
+    time::{Duration, Instant, SystemTime, UNIX_EPOCH},
+};
 
-use crate::wifi::WifiNetwork;
+use crate::{
+    avoid::AvoidedBssids,
+    config::{Config, store_setting},
+    glyphs::Glyphs,
+    health::{HealthMonitor, Recovery},
+    history::{History, HistoryEntry},
+    latency::{LatencyMonitor, LatencySample, Probe},
+    network::{
+        ActivationProgress,
+        AutoconnectBlock,
+        CancelToken,
+        ConnectionOptions,
+        Connectivity,
+        ConnectivityReport,
+        DeviceKind,
+        DeviceProfile,
+        DhcpLease,
+        FailureKind,
+        ForgetReport,
+        LinkSetting,
+        ManagerState,
+        ManagerStatus,
+        NetworkDevice,
+        P2pPeer,
+        Pmf,
+        ProfilePattern,
+        ProfileSettings,
+        ProxyConfig,
+        ProxyMode,
+        SavedProfile,
+        ScanMode,
+        compare_ssids,
+        current_user_name,
+        format_bssid,
+        parse_bssid,
+        parse_cloned_mac,
+        parse_mtu,
+        validate_pac_url,
+        validate_proxy_address,
+        validate_psk,
+    },
+    notify::{NotificationEvent, Notifier, ToastLevel},
+    qr::{expand_export_path, export_qr, parse_wifi_qr, wifi_qr_payload},
+    search::fuzzy_match,
+    spectrum::{ChannelLoad, channel_loads},
+    speedtest::{
+        ChunkTransfer,
+        DEFAULT_SPEEDTEST_URL,
+        SpeedTest,
+        TransferDirection,
+    },
+    ssid_list::SsidList,
+    status::{LinkStatus, write_cache},
+    survey::{SURVEY_SAMPLE_INTERVAL, SiteSurvey},
+    terminal::{Background, ColorDepth},
+    theme::{Theme, ThemeColors, ThemeName},
+    traffic::{TrafficCounters, TrafficMeter},
+    ui::RowCache,
+    wifi::{WifiAccessPoint, WifiBand, WifiNetwork, WifiSecurity},
+};
+
+const SIGNAL_REFRESH_INTERVAL: Duration = Duration::from_secs(2);
+const TRAFFIC_REFRESH_INTERVAL: Duration = Duration::from_secs(1);
+const BITRATE_REFRESH_INTERVAL: Duration = Duration::from_secs(2);
+const LATENCY_REFRESH_INTERVAL: Duration = Duration::from_secs(2);
+const LOG_REFRESH_INTERVAL: Duration = Duration::from_secs(2);
+/// Periodic refreshes usually finish well within this, so only requests
+/// that take longer show up in the status bar.
+const BUSY_INDICATOR_DELAY: Duration = Duration::from_millis(300);
+/// How long to leave a connection alone after steering it away from an
+/// avoided BSSID, so a roam back is not fought in a tight loop.
+const STEER_RETRY_INTERVAL: Duration = Duration::from_secs(30);
+/// How often the NetworkManager error screen checks whether it came back,
+/// e.g. after a restart.
+const MANAGER_CHECK_INTERVAL: Duration = Duration::from_secs(3);
+/// Wait before the first retry of a transient connect failure; every
+/// further retry waits twice as long as the one before.
+const CONNECT_RETRY_BACKOFF: Duration = Duration::from_secs(2);
+/// How long a network missing from rescans stays in the list, dimmed, so a
+/// single missed beacon does not make the list jump.
+const STALE_NETWORK_GRACE: Duration = Duration::from_secs(60);
+/// Two clicks on one row within this time connect to it.
+const DOUBLE_CLICK_INTERVAL: Duration = Duration::from_millis(400);
+/// How often the config file is checked for a changed theme.
+const CONFIG_CHECK_INTERVAL: Duration = Duration::from_secs(1);
+pub const DEFAULT_CONNECT_RETRIES: u32 = 2;
+pub const DEFAULT_UNUSED_PROFILE_DAYS: u64 = 30;
 
 #[derive(PartialEq)]
 pub enum AppState {
@@ -12,12 +105,711 @@ pub enum AppState {
     ConnectionResult,
     Help,
     NetworkDetails,
+    ShareQr,
+    CommandPalette,
+    ForgetProfiles,
+    ExportProfiles,
+    KnownNetworks,
+    CleanupProfiles,
+    ImportQr,
+    Roaming,
+    /// Access points per channel of one band.
+    Spectrum,
+    /// Live signal of one access point while walking around.
+    SiteSurvey,
+    /// Past connections and disconnections, newest first.
+    ConnectionHistory,
+    /// NetworkManager's journal messages.
+    NetworkManagerLog,
+    SpeedTest,
+    /// The Ethernet, mobile broadband or Bluetooth devices tab.
+    Devices,
+    /// Wi-Fi Direct peers nearby.
+    P2pPeers,
+    /// No system bus or no NetworkManager on it.
+    ManagerUnavailable,
 }
 
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub enum OperationKind {
     Connect,
     Disconnect,
+    /// Deactivates and reactivates the active profile of a network.
+    Reconnect,
+    /// Reactivates the active profile to force a fresh DHCP lease.
+    RenewLease,
+}
+
+/// How a hidden password is drawn, the `password_mask` setting. A dot per
+/// character tells whoever looks over your shoulder how long it is.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum PasswordMask {
+    #[default]
+    Dots,
+    /// The same eight dots whatever the length, once anything is typed.
+    Fixed,
+    /// Nothing at all.
+    Hidden,
+}
+
+impl PasswordMask {
+    const FIXED_WIDTH: usize = 8;
+
+    pub fn from_key(key: &str) -> Option<Self> {
+        match key {
+            "dots" => Some(Self::Dots),
+            "fixed" => Some(Self::Fixed),
+            "hidden" => Some(Self::Hidden),
+            _ => None,
+        }
+    }
+
+    pub fn apply(self, password: &str) -> String {
+        match self {
+            Self::Dots => "•".repeat(password.chars().count()),
+            Self::Fixed if !password.is_empty() => {
+                "•".repeat(Self::FIXED_WIDTH)
+            }
+            Self::Fixed | Self::Hidden => String::new(),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum PasswordField {
+    #[default]
+    Password,
+    ProfileName,
+    ProxyMode,
+    /// The PAC URL of an automatic proxy, or the `host:port` of a manual
+    /// one.
+    ProxyAddress,
+    Pmf,
+    Visibility,
+    SecretStorage,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum NetworkFilter {
+    FiveGhz,
+    Open,
+    Saved,
+}
+
+impl NetworkFilter {
+    pub fn label(self) -> &'static str {
+        match self {
+            Self::FiveGhz => "5 GHz",
+            Self::Open => "Open",
+            Self::Saved => "Saved",
+        }
+    }
+}
+
+/// Order of the network list, cycled with `s`. Favorites stay on top in
+/// every order, and ties go to the stronger signal.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum SortOrder {
+    /// Strongest first. Equal signals keep the scan order, which lists the
+    /// connected network first.
+    #[default]
+    Signal,
+    Alphabetical,
+    /// Strongest security first.
+    Security,
+    /// Highest band first.
+    Band,
+    /// Networks with a saved profile first.
+    KnownFirst,
+}
+
+impl SortOrder {
+    pub fn label(self) -> &'static str {
+        match self {
+            Self::Signal => "Signal",
+            Self::Alphabetical => "Name",
+            Self::Security => "Security",
+            Self::Band => "Band",
+            Self::KnownFirst => "Known first",
+        }
+    }
+
+    fn next(self) -> Self {
+        match self {
+            Self::Signal => Self::Alphabetical,
+            Self::Alphabetical => Self::Security,
+            Self::Security => Self::Band,
+            Self::Band => Self::KnownFirst,
+            Self::KnownFirst => Self::Signal,
+        }
+    }
+
+    fn compare(self, a: &WifiNetwork, b: &WifiNetwork) -> Ordering {
+        let security_rank = |security| match security {
+            WifiSecurity::WpaSae => 0,
+            WifiSecurity::Enterprise => 1,
+            WifiSecurity::WpaPsk => 2,
+            WifiSecurity::Open => 3,
+            WifiSecurity::Unsupported => 4,
+        };
+        let band_rank = |band| match band {
+            WifiBand::SixGhz => 0,
+            WifiBand::FiveGhz => 1,
+            WifiBand::TwoPointFourGhz => 2,
+        };
+        match self {
+            Self::Signal => Ordering::Equal,
+            Self::Alphabetical => compare_ssids(&a.ssid, &b.ssid),
+            Self::Security => {
+                security_rank(a.security).cmp(&security_rank(b.security))
+            }
+            Self::Band => band_rank(a.band()).cmp(&band_rank(b.band())),
+            Self::KnownFirst => b.saved.cmp(&a.saved),
+        }
+        .then(b.signal_strength.cmp(&a.signal_strength))
+    }
+}
+
+/// Quick filters toggled from the network list; active ones are combined.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct NetworkFilters {
+    pub five_ghz: bool,
+    pub open: bool,
+    pub saved: bool,
+}
+
+impl NetworkFilters {
+    pub fn is_active(self) -> bool {
+        self.five_ghz || self.open || self.saved
+    }
+
+    pub fn active(self) -> Vec<NetworkFilter> {
+        [
+            (self.five_ghz, NetworkFilter::FiveGhz),
+            (self.open, NetworkFilter::Open),
+            (self.saved, NetworkFilter::Saved),
+        ]
+        .into_iter()
+        .filter_map(|(enabled, filter)| enabled.then_some(filter))
+        .collect()
+    }
+
+    pub fn matches(self, network: &WifiNetwork) -> bool {
+        (!self.five_ghz || network.band() == WifiBand::FiveGhz)
+            && (!self.open || network.security == WifiSecurity::Open)
+            && (!self.saved || network.saved)
+    }
+
+    fn toggle(&mut self, filter: NetworkFilter) {
+        let enabled = match filter {
+            NetworkFilter::FiveGhz => &mut self.five_ghz,
+            NetworkFilter::Open => &mut self.open,
+            NetworkFilter::Saved => &mut self.saved,
+        };
+        *enabled = !*enabled;
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PaletteCommand {
+    Rescan,
+    ForgetMatching,
+    ExportProfiles,
+    KnownNetworks,
+    CleanupProfiles,
+    ImportQr,
+    RenewLease,
+    EthernetDevices,
+    MobileDevices,
+    BluetoothDevices,
+    WifiDirectPeers,
+    ConnectionHistory,
+    NetworkManagerLog,
+    ShowHelp,
+}
+
+impl PaletteCommand {
+    pub const ALL: [Self; 14] = [
+        Self::Rescan,
+        Self::ForgetMatching,
+        Self::ExportProfiles,
+        Self::KnownNetworks,
+        Self::CleanupProfiles,
+        Self::ImportQr,
+        Self::RenewLease,
+        Self::EthernetDevices,
+        Self::MobileDevices,
+        Self::BluetoothDevices,
+        Self::WifiDirectPeers,
+        Self::ConnectionHistory,
+        Self::NetworkManagerLog,
+        Self::ShowHelp,
+    ];
+
+    pub fn label(self) -> &'static str {
+        match self {
+            Self::Rescan => "Rescan networks",
+            Self::ForgetMatching => "Forget saved networks matching…",
+            Self::ExportProfiles => "Export saved profiles as keyfiles…",
+            Self::KnownNetworks => "Known networks and their scope",
+            Self::CleanupProfiles => "Clean up unused saved profiles…",
+            Self::ImportQr => "Import a network from a WIFI: QR string…",
+            Self::RenewLease => "Renew DHCP lease",
+            Self::EthernetDevices => "Ethernet devices",
+            Self::MobileDevices => "Mobile broadband modems",
+            Self::BluetoothDevices => "Bluetooth tethering",
+            Self::WifiDirectPeers => "Wi-Fi Direct peers",
+            Self::ConnectionHistory => "Connection history",
+            Self::NetworkManagerLog => "NetworkManager log",
+            Self::ShowHelp => "Show help",
+        }
+    }
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct CommandPalette {
+    pub query: String,
+    pub selected: usize,
+}
+
+impl CommandPalette {
+    /// Commands whose label contains the query, ignoring case.
+    pub fn commands(&self) -> Vec<PaletteCommand> {
+        let query = self.query.to_lowercase();
+        PaletteCommand::ALL
+            .into_iter()
+            .filter(|command| command.label().to_lowercase().contains(&query))
+            .collect()
+    }
+
+    pub fn next(&mut self) {
+        let count = self.commands().len();
+        if count > 0 {
+            self.selected = (self.selected + 1) % count;
+        }
+    }
+
+    pub fn previous(&mut self) {
+        let count = self.commands().len();
+        if count > 0 {
+            self.selected = (self.selected + count - 1) % count;
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ForgetStage {
+    Editing,
+    Confirming,
+    Forgetting,
+}
+
+/// Pattern-based removal of saved profiles, previewed before confirming.
+#[derive(Debug, Clone)]
+pub struct BulkForget {
+    pub pattern: String,
+    /// Every saved Wi-Fi profile; `None` while they are being listed.
+    pub profiles: Option<Result<Vec<SavedProfile>, String>>,
+    pub stage: ForgetStage,
+}
+
+impl BulkForget {
+    pub fn compiled_pattern(&self) -> Result<ProfilePattern, String> {
+        ProfilePattern::parse(&self.pattern)
+    }
+
+    pub fn matches(&self) -> Vec<&SavedProfile> {
+        matching_profiles(&self.profiles, &self.pattern)
+    }
+}
+
+fn matching_profiles<'a>(
+    profiles: &'a Option<Result<Vec<SavedProfile>, String>>,
+    pattern: &str,
+) -> Vec<&'a SavedProfile> {
+    match (profiles, ProfilePattern::parse(pattern)) {
+        (Some(Ok(profiles)), Ok(pattern)) => profiles
+            .iter()
+            .filter(|profile| pattern.matches(profile))
+            .collect(),
+        _ => Vec::new(),
+    }
+}
+
+pub const DEFAULT_EXPORT_DIRECTORY: &str = "~/nm-wifi-profiles";
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ExportField {
+    Pattern,
+    Directory,
+}
+
+/// Saved profiles picked by pattern and written out as keyfiles.
+#[derive(Debug, Clone)]
+pub struct ProfileExport {
+    pub pattern: String,
+    pub directory: String,
+    pub include_secrets: bool,
+    pub field: ExportField,
+    /// Every saved Wi-Fi profile; `None` while they are being listed.
+    pub profiles: Option<Result<Vec<SavedProfile>, String>>,
+    pub exporting: bool,
+    pub result: Option<Result<String, String>>,
+}
+
+impl ProfileExport {
+    pub fn compiled_pattern(&self) -> Result<ProfilePattern, String> {
+        ProfilePattern::parse(&self.pattern)
+    }
+
+    pub fn matches(&self) -> Vec<&SavedProfile> {
+        matching_profiles(&self.profiles, &self.pattern)
+    }
+
+    fn focused_input(&mut self) -> &mut String {
+        match self.field {
+            ExportField::Pattern => &mut self.pattern,
+            ExportField::Directory => &mut self.directory,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ProfileEdit {
+    Rename,
+    /// Saves a copy of the profile under the new name.
+    Clone,
+    /// Replaces the stored Wi-Fi password.
+    Password,
+    /// Pins the profile to one access point; left empty, unpins it.
+    Bssid,
+    /// Sets `wifi.mtu`; left empty, the driver picks it.
+    Mtu,
+    /// Sets a fixed or `random`/`stable` MAC address; left empty, the
+    /// adapter's own address is used.
+    ClonedMac,
+    /// Names a saved VPN to bring up whenever the profile connects; left
+    /// empty, none is.
+    Vpn,
+}
+
+/// Every saved profile with whether it is system-wide or user-scoped; the
+/// selected one can be moved between the two.
+#[derive(Debug, Clone)]
+pub struct KnownNetworks {
+    /// `None` while the profiles are being listed.
+    pub profiles: Option<Result<Vec<SavedProfile>, String>>,
+    pub selected: usize,
+    /// The scope change of the selected profile has not been sent yet.
+    pub converting: bool,
+    /// The band lock change of the selected profile has not been sent yet.
+    pub changing_band: bool,
+    /// The name, password, BSSID, MTU, MAC address or VPN being typed for
+    /// the selected profile.
+    pub edit_input: Option<(ProfileEdit, String)>,
+    /// The typed edit of the selected profile has not been sent yet.
+    pub saving_edit: bool,
+    pub result: Option<Result<String, String>>,
+}
+
+impl KnownNetworks {
+    pub fn selected_profile(&self) -> Option<&SavedProfile> {
+        match &self.profiles {
+            Some(Ok(profiles)) => profiles.get(self.selected),
+            _ => None,
+        }
+    }
+}
+
+/// Ethernet, mobile broadband or Bluetooth devices with the profiles each
+/// can bring up; every profile of every device is a row that can be selected.
+#[derive(Debug, Clone)]
+pub struct DeviceTab {
+    pub kind: DeviceKind,
+    /// `None` while the devices are being listed.
+    pub devices: Option<Result<Vec<NetworkDevice>, String>>,
+    pub selected: usize,
+    /// Bringing the selected profile up or down has not been sent yet.
+    pub switching: bool,
+    pub result: Option<Result<String, String>>,
+}
+
+impl DeviceTab {
+    /// Every profile of every device, in the order they are listed.
+    pub fn rows(&self) -> Vec<(&NetworkDevice, &DeviceProfile)> {
+        match &self.devices {
+            Some(Ok(devices)) => devices
+                .iter()
+                .flat_map(|device| {
+                    device.profiles.iter().map(move |profile| (device, profile))
+                })
+                .collect(),
+            _ => Vec::new(),
+        }
+    }
+
+    pub fn selected_row(&self) -> Option<(&NetworkDevice, &DeviceProfile)> {
+        self.rows().get(self.selected).copied()
+    }
+}
+
+/// The NetworkManager journal, re-read while it is on screen.
+#[derive(Debug, Clone)]
+pub struct LogView {
+    /// Narrowed to the adapter and network in use.
+    pub filtered: bool,
+    /// `None` until the journal was read once.
+    pub lines: Option<Result<Vec<String>, String>>,
+    /// Lines scrolled back from the newest; 0 follows new lines.
+    pub scroll: usize,
+    pub next_read_at: Instant,
+}
+
+/// Wi-Fi Direct peers found by the last search, such as printers and
+/// screen-cast receivers.
+#[derive(Debug, Clone, Default)]
+pub struct P2pTab {
+    /// `None` while peers are being searched for.
+    pub peers: Option<Result<Vec<P2pPeer>, String>>,
+    pub selected: usize,
+    /// Connecting to the selected peer has not been sent yet.
+    pub connecting: bool,
+    pub result: Option<Result<String, String>>,
+}
+
+impl P2pTab {
+    pub fn selected_peer(&self) -> Option<&P2pPeer> {
+        match &self.peers {
+            Some(Ok(peers)) => peers.get(self.selected),
+            _ => None,
+        }
+    }
+}
+
+/// Saved profiles out of range and unused for a while, picked one by one
+/// and forgotten in one confirmed batch.
+#[derive(Debug, Clone)]
+pub struct ProfileCleanup {
+    /// Unused profiles; `None` while the profiles are being listed.
+    pub profiles: Option<Result<Vec<SavedProfile>, String>>,
+    pub selected: usize,
+    /// UUIDs of the profiles picked for removal.
+    pub picked: HashSet<String>,
+    pub stage: ForgetStage,
+}
+
+impl ProfileCleanup {
+    pub fn picked_profiles(&self) -> Vec<&SavedProfile> {
+        match &self.profiles {
+            Some(Ok(profiles)) => profiles
+                .iter()
+                .filter(|profile| self.picked.contains(&profile.uuid))
+                .collect(),
+            _ => Vec::new(),
+        }
+    }
+}
+
+/// A pasted `WIFI:` string, e.g. from a phone's share sheet, to connect
+/// to.
+#[derive(Debug, Clone, Default)]
+pub struct QrImport {
+    pub input: String,
+    pub error: Option<String>,
+}
+
+/// Where the password prompt of a saved network stands in reading the PSK
+/// NetworkManager already stores for it.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum PasswordPrefill {
+    #[default]
+    None,
+    Loading,
+    /// The prompt holds the saved password, untouched since.
+    Filled,
+}
+
+/// A connect that failed in a way worth trying again, waiting for `due`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ConnectRetry {
+    /// The attempt that runs next, the first one being 1.
+    pub attempt: u32,
+    pub failure: FailureKind,
+    pub due: Instant,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum SharePassword {
+    Loading,
+    Loaded(Option<String>),
+    Failed(String),
+}
+
+/// `ssid` with everything but ASCII letters, digits and dashes replaced by
+/// underscores, to name exported files after it.
+fn file_stem(ssid: &str) -> String {
+    ssid.chars()
+        .map(|c| {
+            if c.is_ascii_alphanumeric() || c == '-' {
+                c
+            } else {
+                '_'
+            }
+        })
+        .collect()
+}
+
+fn file_modified(path: &Path) -> Option<SystemTime> {
+    fs::metadata(path)
+        .and_then(|metadata| metadata.modified())
+        .ok()
+}
+
+/// The network being shared as a QR code and where to export it.
+#[derive(Debug, Clone)]
+pub struct QrShare {
+    pub network: WifiNetwork,
+    pub password: SharePassword,
+    pub export_path: String,
+    pub export_result: Option<Result<String, String>>,
+}
+
+impl QrShare {
+    fn new(network: WifiNetwork) -> Self {
+        let file_stem = file_stem(&network.ssid);
+        let password = if network.is_secured() {
+            SharePassword::Loading
+        } else {
+            SharePassword::Loaded(None)
+        };
+
+        Self {
+            export_path: format!("~/wifi-{file_stem}.png"),
+            network,
+            password,
+            export_result: None,
+        }
+    }
+
+    /// The `WIFI:` payload, once everything needed to join is known.
+    pub fn payload(&self) -> Option<String> {
+        match &self.password {
+            SharePassword::Loaded(password)
+                if password.is_some() || !self.network.is_secured() =>
+            {
+                Some(wifi_qr_payload(&self.network, password.as_deref()))
+            }
+            _ => None,
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum AutoconnectCheck {
+    Loading,
+    Loaded(Option<AutoconnectBlock>),
+    Failed(String),
+    Clearing,
+}
+
+/// Connectivity check run once a connect or reconnect succeeds.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ConnectivityCheck {
+    Checking,
+    Checked(Connectivity),
+    Failed(String),
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ProfileCheck {
+    Loading,
+    /// `None` when no saved profile was found for the network.
+    Loaded(Option<ProfileSettings>),
+    Failed(String),
+}
+
+/// Proxy and permission settings of the saved profile shown in the network
+/// details.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ProfileLookup {
+    pub ssid: String,
+    pub check: ProfileCheck,
+}
+
+/// The autoconnect diagnosis shown in the details of a saved network.
+#[derive(Debug, Clone)]
+pub struct AutoconnectDiagnosis {
+    pub ssid: String,
+    pub check: AutoconnectCheck,
+}
+
+#[derive(Debug, Clone)]
+pub struct GuestSession {
+    pub ssid: String,
+    pub started: Instant,
+}
+
+/// When the active connection came up. A connection that was already up
+/// when it was first seen only has a lower bound, marked `observed`.
+#[derive(Debug, Clone)]
+pub struct ConnectionUptime {
+    pub ssid: String,
+    pub since: Instant,
+    pub observed: bool,
+}
+
+/// The backend request the runtime is waiting on.
+#[derive(Debug, Clone)]
+pub struct BackendActivity {
+    pub operation: &'static str,
+    pub started: Instant,
+}
+
+impl BackendActivity {
+    pub fn is_visible(&self, now: Instant) -> bool {
+        now.saturating_duration_since(self.started) >= BUSY_INDICATOR_DELAY
+    }
+}
+
+/// The order `visible_networks` last worked out, and the `list_generation`
+/// it was worked out at. The list is read several times a frame and on
+/// every key, and filtering, fuzzy matching and collating hundreds of SSIDs
+/// each time shows in dense areas.
+#[derive(Debug, Default)]
+struct VisibleOrder {
+    generation: Option<u64>,
+    /// Positions in `networks`, in list order.
+    indices: Vec<usize>,
+}
+
+impl VisibleOrder {
+    fn of(app: &App) -> Self {
+        let mut indices = app
+            .networks
+            .iter()
+            .enumerate()
+            .filter(|(_, network)| app.filters.matches(network))
+            .filter(|(_, network)| {
+                app.show_ignored || !app.ignored.contains(&network.ssid)
+            })
+            .filter(|(_, network)| {
+                fuzzy_match(&app.search_query, &network.ssid).is_some()
+            })
+            .map(|(index, _)| index)
+            .collect::<Vec<_>>();
+        indices.sort_by(|&a, &b| {
+            let (a, b) = (&app.networks[a], &app.networks[b]);
+            app.favorites
+                .contains(&b.ssid)
+                .cmp(&app.favorites.contains(&a.ssid))
+                .then_with(|| app.sort_order.compare(a, b))
+        });
+
+        Self {
+            generation: Some(app.list_generation),
+            indices,
+        }
+    }
 }
 
 pub struct App {
@@ -31,11 +823,173 @@ pub struct App {
     pub connection_success: bool,
     pub connection_error: Option<String>,
     pub is_disconnect_operation: bool,
+    pub operation: OperationKind,
+    pub renewed_lease: Option<DhcpLease>,
+    pub connectivity: Option<ConnectivityCheck>,
+    /// Login page of the captive portal the active network sits behind.
+    pub captive_portal: Option<String>,
+    pub opening_portal: bool,
     pub adapter_name: Option<String>,
+    /// Wi-Fi interfaces NetworkManager ignores; they never return scan
+    /// results until they are handed back to it.
+    pub unmanaged_adapters: Vec<String>,
+    pub managing_adapter: Option<String>,
+    /// Why NetworkManager could not be reached.
+    pub manager_error: Option<String>,
+    /// An action polkit refused, shown over the current screen until
+    /// dismissed.
+    pub authorization_denied: Option<String>,
+    pub starting_manager: bool,
+    /// When the error screen next looks for NetworkManager on the bus.
+    pub next_manager_check_at: Option<Instant>,
+    /// NetworkManager's unique bus name at the last scan.
+    pub manager_owner: Option<String>,
     pub network_count: usize,
     pub last_scan_time: Option<Instant>,
+    /// The last connect error per SSID, until a connect to it succeeds.
+    pub connect_errors: HashMap<String, String>,
+    /// When the networks the last scan missed were last seen, by SSID.
+    pub stale_networks: HashMap<String, Instant>,
     pub connection_start_time: Option<Instant>,
+    /// Set by Esc to abandon the running connect; replaced for every
+    /// operation.
+    pub operation_cancel: CancelToken,
+    /// Steps the running connect went through, shown while connecting.
+    pub activation_progress: ActivationProgress,
+    /// How often a connect that failed on DHCP or a missing access point
+    /// is tried again.
+    pub connect_retries: u32,
+    pub connect_retry: Option<ConnectRetry>,
     pub password_visible: bool,
+    pub password_mask: PasswordMask,
+    pub password_field: PasswordField,
+    /// Set when the prompt reopened because the network rejected the
+    /// password that was tried.
+    pub password_rejected: bool,
+    pub password_prefill: PasswordPrefill,
+    pub profile_name_input: String,
+    pub proxy_mode_input: ProxyMode,
+    pub proxy_input: String,
+    pub pmf_input: Pmf,
+    /// Whether the new profile is restricted to `user_name`.
+    pub private_profile: bool,
+    /// Whether the password goes to the session keyring instead of the
+    /// system connection file.
+    pub keyring_secrets: bool,
+    /// Starts every new profile private to the user with its password in
+    /// the keyring (`user_profiles = true`).
+    pub user_scoped_profiles: bool,
+    pub user_name: Option<String>,
+    pub guest_connection: bool,
+    /// Whether the network being joined hides its SSID.
+    pub hidden_network: bool,
+    pub guest_session: Option<GuestSession>,
+    /// The SSID of a guest session that ended and whose profile is still to
+    /// be deleted.
+    pub ended_guest_session: Option<String>,
+    /// Ends a guest session when the app quits, deleting its profile and
+    /// so dropping the connection; otherwise the connection outlives the
+    /// app.
+    pub delete_guest_profile_on_quit: bool,
+    pub connection_uptime: Option<ConnectionUptime>,
+    pub manager_status: Option<ManagerStatus>,
+    pub filters: NetworkFilters,
+    pub sort_order: SortOrder,
+    /// When and on which row of the network list the last click landed.
+    last_click: Option<(Instant, usize)>,
+    /// Narrows the list to SSIDs fuzzily matching it; typed after `/`.
+    pub search_query: String,
+    /// Keys go to `search_query` instead of the list.
+    pub search_editing: bool,
+    pub glyphs: Glyphs,
+    pub theme: ThemeName,
+    pub theme_colors: ThemeColors,
+    /// Decides between the light and dark palette of the `auto` theme.
+    pub background: Background,
+    /// Palettes are approximated on terminals with fewer colours.
+    pub color_depth: ColorDepth,
+    /// Draws in the terminal's default colours, with reverse and bold for
+    /// emphasis; set by `NO_COLOR` and `--no-color`.
+    pub monochrome: bool,
+    /// The colours the UI is drawn with: `theme` with `theme_colors` laid
+    /// over it.
+    colors: Theme,
+    /// Where settings changed in the app, like the theme, are saved; `None`
+    /// keeps them for this session only.
+    pub config_path: Option<PathBuf>,
+    /// When the config file was last written, to reload its theme once it
+    /// changes.
+    config_modified: Option<SystemTime>,
+    next_config_check_at: Option<Instant>,
+    pub rescan_interval: Option<Duration>,
+    pub next_rescan_at: Option<Instant>,
+    pub scan_min_interval: Duration,
+    pub scan_mode: ScanMode,
+    pub scan_band: Option<WifiBand>,
+    /// When the adapter was last asked to scan.
+    pub last_fresh_scan: Option<Instant>,
+    pub next_signal_refresh_at: Option<Instant>,
+    pub traffic: TrafficMeter,
+    pub next_traffic_refresh_at: Option<Instant>,
+    /// Set when the latency monitor is enabled in the config.
+    pub latency: Option<LatencyMonitor>,
+    pub next_latency_refresh_at: Option<Instant>,
+    /// Set when `health_check_failures` is configured; fed by the latency
+    /// monitor's gateway pings.
+    pub health: Option<HealthMonitor>,
+    /// The password prompt open for a switch the health monitor made, to
+    /// be confirmed as soon as the stored password is filled in.
+    unattended_connect: bool,
+    /// Negotiated PHY rate of the active link in kb/s, read while its
+    /// details are open.
+    pub link_bitrate: Option<u32>,
+    pub next_bitrate_refresh_at: Option<Instant>,
+    pub history: History,
+    /// Highlighted row of the connection history, counted from the newest.
+    pub history_selected: usize,
+    pub avoided: AvoidedBssids,
+    pub ignored: SsidList,
+    /// Networks listed above all others, whatever their signal.
+    pub favorites: SsidList,
+    /// Lists ignored networks too, dimmed.
+    pub show_ignored: bool,
+    /// Where the current connection is published for `--status`; `None`
+    /// publishes nothing.
+    pub status_cache: Option<PathBuf>,
+    visible_order: RefCell<VisibleOrder>,
+    /// Bumped by `list_changed` whenever the visible order may change.
+    list_generation: u64,
+    /// Network list rows kept between frames.
+    pub list_rows: RowCache,
+    /// Highlighted row of the roaming view, in signal order.
+    pub roaming_index: usize,
+    /// Band plotted by the channel spectrum view.
+    pub spectrum_band: WifiBand,
+    pub last_steer_at: Option<Instant>,
+    /// Pinning the connected profile to its current access point has not
+    /// been sent yet.
+    pub pinning_access_point: bool,
+    pub qr_share: Option<QrShare>,
+    pub site_survey: Option<SiteSurvey>,
+    pub palette: Option<CommandPalette>,
+    pub bulk_forget: Option<BulkForget>,
+    pub profile_export: Option<ProfileExport>,
+    pub known_networks: Option<KnownNetworks>,
+    pub device_tab: Option<DeviceTab>,
+    pub p2p_tab: Option<P2pTab>,
+    pub log_view: Option<LogView>,
+    pub profile_cleanup: Option<ProfileCleanup>,
+    /// Days without a connection after which the cleanup offers to forget
+    /// a saved profile that is out of range.
+    pub unused_profile_days: u64,
+    pub qr_import: Option<QrImport>,
+    pub autoconnect: Option<AutoconnectDiagnosis>,
+    pub profile_lookup: Option<ProfileLookup>,
+    /// Endpoint of the speed test, set in the config.
+    pub speedtest_url: String,
+    pub speed_test: Option<SpeedTest>,
+    pub backend_activity: Option<BackendActivity>,
+    pub notifier: Notifier,
 }
 
 impl Default for App {
@@ -61,17 +1015,328 @@ impl App {
             connection_success: false,
             connection_error: None,
             is_disconnect_operation: false,
+            operation: OperationKind::Connect,
+            renewed_lease: None,
+            connectivity: None,
+            captive_portal: None,
+            opening_portal: false,
             adapter_name: None,
+            unmanaged_adapters: Vec::new(),
+            managing_adapter: None,
+            manager_error: None,
+            authorization_denied: None,
+            starting_manager: false,
+            next_manager_check_at: None,
+            manager_owner: None,
             network_count: 0,
             last_scan_time: None,
+            connect_errors: HashMap::new(),
+            stale_networks: HashMap::new(),
             connection_start_time: None,
+            operation_cancel: CancelToken::default(),
+            activation_progress: ActivationProgress::default(),
+            connect_retries: DEFAULT_CONNECT_RETRIES,
+            connect_retry: None,
             password_visible: false,
+            password_mask: PasswordMask::Dots,
+            password_field: PasswordField::Password,
+            password_rejected: false,
+            password_prefill: PasswordPrefill::None,
+            profile_name_input: String::new(),
+            proxy_mode_input: ProxyMode::None,
+            proxy_input: String::new(),
+            pmf_input: Pmf::Default,
+            private_profile: false,
+            keyring_secrets: false,
+            user_scoped_profiles: false,
+            user_name: current_user_name(),
+            guest_connection: false,
+            hidden_network: false,
+            guest_session: None,
+            ended_guest_session: None,
+            delete_guest_profile_on_quit: false,
+            connection_uptime: None,
+            manager_status: None,
+            filters: NetworkFilters::default(),
+            sort_order: SortOrder::default(),
+            last_click: None,
+            search_query: String::new(),
+            search_editing: false,
+            glyphs: Glyphs::default(),
+            theme: ThemeName::default(),
+            theme_colors: ThemeColors::default(),
+            background: Background::Dark,
+            color_depth: ColorDepth::TrueColor,
+            monochrome: false,
+            colors: *ThemeName::default().palette(Background::Dark),
+            config_path: None,
+            config_modified: None,
+            next_config_check_at: None,
+            rescan_interval: None,
+            next_rescan_at: None,
+            scan_min_interval: Duration::ZERO,
+            scan_mode: ScanMode::Active,
+            scan_band: None,
+            last_fresh_scan: None,
+            next_signal_refresh_at: None,
+            traffic: TrafficMeter::default(),
+            next_traffic_refresh_at: None,
+            latency: None,
+            next_latency_refresh_at: None,
+            health: None,
+            unattended_connect: false,
+            link_bitrate: None,
+            next_bitrate_refresh_at: None,
+            history: History::default(),
+            history_selected: 0,
+            avoided: AvoidedBssids::default(),
+            ignored: SsidList::default(),
+            favorites: SsidList::default(),
+            show_ignored: false,
+            status_cache: None,
+            visible_order: RefCell::default(),
+            list_generation: 0,
+            list_rows: RowCache::default(),
+            roaming_index: 0,
+            spectrum_band: WifiBand::TwoPointFourGhz,
+            last_steer_at: None,
+            pinning_access_point: false,
+            qr_share: None,
+            site_survey: None,
+            palette: None,
+            bulk_forget: None,
+            profile_export: None,
+            known_networks: None,
+            device_tab: None,
+            p2p_tab: None,
+            log_view: None,
+            profile_cleanup: None,
+            unused_profile_days: DEFAULT_UNUSED_PROFILE_DAYS,
+            qr_import: None,
+            autoconnect: None,
+            profile_lookup: None,
+            speedtest_url: DEFAULT_SPEEDTEST_URL.to_string(),
+            speed_test: None,
+            backend_activity: None,
+            notifier: Notifier::default(),
+        }
+    }
+
+    /// Networks shown in the list once the active filters are applied,
+    /// favorites first and otherwise in the sort order; `selected_index`
+    /// points into this list. Worked out again only after `list_changed`.
+    pub fn visible_networks(&self) -> Vec<&WifiNetwork> {
+        self.visible_network_indices()
+            .into_iter()
+            .map(|index| &self.networks[index])
+            .collect()
+    }
+
+    /// The positions in `networks` of `visible_networks`.
+    pub(crate) fn visible_network_indices(&self) -> Vec<usize> {
+        let mut order = self.visible_order.borrow_mut();
+        if order.generation != Some(self.list_generation) {
+            *order = VisibleOrder::of(self);
+        }
+        order.indices.clone()
+    }
+
+    /// Has the visible order worked out again. Called wherever `networks`,
+    /// the filters, the search, the ignored or favorite networks or the
+    /// sort order change; code setting those fields directly calls it too.
+    pub fn list_changed(&mut self) {
+        self.list_generation = self.list_generation.wrapping_add(1);
+    }
+
+    pub fn colors(&self) -> &Theme {
+        &self.colors
+    }
+
+    pub fn set_theme(&mut self, theme: ThemeName, colors: ThemeColors) {
+        self.colors = colors
+            .apply(theme.palette(self.background))
+            .for_depth(self.color_depth);
+        self.theme = theme;
+        self.theme_colors = colors;
+    }
+
+    /// Switches to the next theme and saves it as the `theme` setting.
+    pub fn cycle_theme(&mut self) {
+        self.set_theme(self.theme.next(), self.theme_colors.clone());
+        let saved = match self.config_path.as_deref() {
+            Some(path) => {
+                store_setting(path, "theme", self.theme.key()).map(|()| {
+                    // Our own write is not an edit to reload.
+                    self.config_modified = file_modified(path);
+                })
+            }
+            None => Ok(()),
+        };
+        self.status_message = match saved {
+            Ok(()) => format!("Theme: {}", self.theme.label()),
+            Err(error) => {
+                format!("Theme: {} (not saved: {error})", self.theme.label())
+            }
+        };
+    }
+
+    /// Saves settings to the config file at `path` and reloads its theme
+    /// whenever the file changes.
+    pub fn watch_config(&mut self, path: Option<PathBuf>) {
+        self.config_modified = path.as_deref().and_then(file_modified);
+        self.config_path = path;
+    }
+
+    /// Applies the theme of the config file when it changed since it was
+    /// last read, checking at most once every `CONFIG_CHECK_INTERVAL`.
+    pub fn reload_changed_config(&mut self, now: Instant) {
+        if self.next_config_check_at.is_some_and(|at| now < at) {
+            return;
+        }
+        self.next_config_check_at = Some(now + CONFIG_CHECK_INTERVAL);
+        let Some(path) = self.config_path.clone() else {
+            return;
+        };
+        let modified = file_modified(&path);
+        if modified == self.config_modified {
+            return;
         }
+        self.config_modified = modified;
+
+        self.status_message = match Config::load_from(&path) {
+            Ok(config) => {
+                self.set_theme(config.theme, config.theme_colors);
+                format!("Theme reloaded: {}", self.theme.label())
+            }
+            Err(error) => format!("Theme not reloaded: {error}"),
+        };
+    }
+
+    pub fn cycle_sort_order(&mut self) {
+        let selected_ssid = self
+            .selected_network_in_list()
+            .map(|network| network.ssid.clone());
+        self.sort_order = self.sort_order.next();
+        self.list_changed();
+        self.reselect(selected_ssid);
+        self.status_message =
+            format!("Sorted by {}", self.sort_order.label().to_lowercase());
+    }
+
+    pub fn toggle_filter(&mut self, filter: NetworkFilter) {
+        let selected_ssid = self
+            .selected_network_in_list()
+            .map(|network| network.ssid.clone());
+        self.filters.toggle(filter);
+        self.list_changed();
+        self.reselect(selected_ssid);
+    }
+
+    pub fn open_search(&mut self) {
+        self.search_editing = true;
+    }
+
+    pub fn push_search_char(&mut self, c: char) {
+        self.edit_search(|query| query.push(c));
+    }
+
+    pub fn pop_search_char(&mut self) {
+        self.edit_search(|query| {
+            query.pop();
+        });
+    }
+
+    /// Stops typing, keeping the list narrowed to the query.
+    pub fn finish_search(&mut self) {
+        self.search_editing = false;
+    }
+
+    pub fn clear_search(&mut self) {
+        self.search_editing = false;
+        self.edit_search(String::clear);
+    }
+
+    fn edit_search(&mut self, edit: impl FnOnce(&mut String)) {
+        let selected_ssid = self
+            .selected_network_in_list()
+            .map(|network| network.ssid.clone());
+        edit(&mut self.search_query);
+        self.list_changed();
+        self.reselect(selected_ssid);
+    }
+
+    pub fn toggle_show_ignored(&mut self) {
+        let selected_ssid = self
+            .selected_network_in_list()
+            .map(|network| network.ssid.clone());
+        self.show_ignored = !self.show_ignored;
+        self.list_changed();
+        self.reselect(selected_ssid);
+        self.status_message =
+            match (self.show_ignored, self.ignored.ssids.len()) {
+                (true, count) => format!("Showing {count} ignored network(s)"),
+                (false, _) => "Hiding ignored networks".to_string(),
+            };
+    }
+
+    /// Ignores the highlighted network, or shows it again. Ignoring keeps
+    /// the cursor on the row the network left.
+    pub fn toggle_ignored_network(&mut self) {
+        let Some(ssid) = self
+            .selected_network_in_list()
+            .map(|network| network.ssid.clone())
+        else {
+            return;
+        };
+
+        self.status_message = if self.ignored.toggle(&ssid) {
+            format!("Ignoring {ssid}; F5 shows ignored networks")
+        } else {
+            format!("No longer ignoring {ssid}")
+        };
+        self.list_changed();
+        let count = self.visible_networks().len();
+        self.set_selected_index(
+            self.selected_index.min(count.saturating_sub(1)),
+        );
+    }
+
+    /// Pins the highlighted network to the top of the list, or unpins it;
+    /// the cursor follows it.
+    pub fn toggle_favorite_network(&mut self) {
+        let Some(ssid) = self
+            .selected_network_in_list()
+            .map(|network| network.ssid.clone())
+        else {
+            return;
+        };
+
+        self.status_message = if self.favorites.toggle(&ssid) {
+            format!("Pinned {ssid} to the top")
+        } else {
+            format!("Unpinned {ssid}")
+        };
+        self.list_changed();
+        self.reselect(Some(ssid));
+    }
+
+    /// Moves the cursor back onto `ssid` after the visible list changed, or
+    /// to the top when it is no longer listed.
+    fn reselect(&mut self, ssid: Option<String>) {
+        let index = ssid
+            .and_then(|ssid| {
+                self.visible_networks()
+                    .iter()
+                    .position(|network| network.ssid == ssid)
+            })
+            .unwrap_or(0);
+        self.set_selected_index(index);
     }
 
     pub fn next(&mut self) {
-        if !self.networks.is_empty() {
-            let i = if self.selected_index >= self.networks.len() - 1 {
+        let visible_count = self.visible_networks().len();
+        if visible_count > 0 {
+            let i = if self.selected_index >= visible_count - 1 {
                 0
             } else {
                 self.selected_index + 1
@@ -81,9 +1346,10 @@ impl App {
     }
 
     pub fn previous(&mut self) {
-        if !self.networks.is_empty() {
+        let visible_count = self.visible_networks().len();
+        if visible_count > 0 {
             let i = if self.selected_index == 0 {
-                self.networks.len() - 1
+                visible_count - 1
             } else {
                 self.selected_index - 1
             };
@@ -91,8 +1357,27 @@ impl App {
         }
     }
 
+    /// Selects the clicked row of the network list, and connects to it (or
+    /// disconnects it) when this is the second click of a double-click.
+    pub fn click_network_row(&mut self, index: usize, now: Instant) {
+        if index >= self.visible_networks().len() {
+            return;
+        }
+        self.set_selected_index(index);
+        let double_click = self.last_click.is_some_and(|(at, row)| {
+            row == index
+                && now.saturating_duration_since(at) <= DOUBLE_CLICK_INTERVAL
+        });
+        if double_click {
+            self.last_click = None;
+            self.activate_selected_network();
+        } else {
+            self.last_click = Some((now, index));
+        }
+    }
+
     pub fn selected_network_in_list(&self) -> Option<&WifiNetwork> {
-        self.networks.get(self.selected_index)
+        self.visible_networks().get(self.selected_index).copied()
     }
 
     pub fn begin_operation(
@@ -101,10 +1386,19 @@ impl App {
         operation: OperationKind,
     ) {
         self.selected_network = Some(network.clone());
+        self.guest_connection = false;
+        self.hidden_network = false;
         self.is_disconnect_operation = operation == OperationKind::Disconnect;
+        self.operation = operation;
+        self.renewed_lease = None;
         self.connection_start_time = Some(Instant::now());
+        self.operation_cancel = CancelToken::default();
+        self.activation_progress = ActivationProgress::default();
+        self.connect_retry = None;
         self.state = match operation {
-            OperationKind::Connect => AppState::Connecting,
+            OperationKind::Connect
+            | OperationKind::Reconnect
+            | OperationKind::RenewLease => AppState::Connecting,
             OperationKind::Disconnect => AppState::Disconnecting,
         };
         self.status_message = match operation {
@@ -114,7 +1408,33 @@ impl App {
             OperationKind::Disconnect => {
                 format!("Disconnecting from {}...", network.ssid)
             }
+            OperationKind::Reconnect => {
+                format!("Reconnecting to {}...", network.ssid)
+            }
+            OperationKind::RenewLease => {
+                format!("Renewing DHCP lease on {}...", network.ssid)
+            }
+        };
+    }
+
+    /// Abandons the running connect, reconnect or lease renewal: the
+    /// backend is told to back out of the activation and the network list
+    /// comes back. A disconnect cannot be cancelled and is left running.
+    pub fn cancel_operation(&mut self) {
+        let action = match self.operation {
+            OperationKind::Connect => "connecting to",
+            OperationKind::Reconnect => "reconnecting to",
+            OperationKind::RenewLease => "renewing the lease on",
+            OperationKind::Disconnect => return,
         };
+        self.operation_cancel.cancel();
+        let ssid = self
+            .selected_network
+            .as_ref()
+            .map(|network| network.ssid.clone())
+            .unwrap_or_default();
+        self.status_message = format!("Cancelled {action} {ssid}");
+        self.back_to_network_list();
     }
 
     pub fn activate_selected_network(&mut self) {
@@ -127,6 +1447,19 @@ impl App {
             Some(network) if network.is_secured() => {
                 self.state = AppState::PasswordInput;
                 self.password_input.clear();
+                self.profile_name_input.clear();
+                self.proxy_mode_input = ProxyMode::None;
+                self.proxy_input.clear();
+                self.pmf_input = Pmf::Default;
+                self.reset_profile_scope();
+                self.password_field = PasswordField::Password;
+                self.password_rejected = false;
+                self.password_prefill = if network.saved {
+                    PasswordPrefill::Loading
+                } else {
+                    PasswordPrefill::None
+                };
+                self.hidden_network = false;
                 self.selected_network = Some(network);
             }
             Some(network) => {
@@ -136,217 +1469,5346 @@ impl App {
         }
     }
 
-    pub fn add_char_to_password(&mut self, c: char) {
-        self.password_input.push(c);
-    }
-
+    pub fn begin_guest_connection(&mut self) {
+        match self.selected_network_in_list().cloned() {
+            Some(network) if network.connected => {}
+            Some(network) if network.security == WifiSecurity::Open => {
+                let ssid = network.ssid.clone();
+                self.begin_operation(network, OperationKind::Connect);
+                self.guest_connection = true;
+                self.status_message =
+                    format!("Connecting to {ssid} as guest...");
+            }
+            Some(_) => {
+                self.status_message =
+                    "Guest connect is only available for open networks"
+                        .to_string();
+            }
+            None => {}
+        }
+    }
+
+    fn focused_password_field_input(&mut self) -> Option<&mut String> {
+        match self.password_field {
+            PasswordField::Password => Some(&mut self.password_input),
+            PasswordField::ProfileName => Some(&mut self.profile_name_input),
+            PasswordField::ProxyAddress => Some(&mut self.proxy_input),
+            PasswordField::ProxyMode
+            | PasswordField::Pmf
+            | PasswordField::Visibility
+            | PasswordField::SecretStorage => None,
+        }
+    }
+
+    pub fn connected_network(&self) -> Option<&WifiNetwork> {
+        self.networks.iter().find(|network| network.connected)
+    }
+
+    pub fn reconnect_current_network(&mut self) {
+        self.begin_operation_on_connected_network(OperationKind::Reconnect);
+    }
+
+    pub fn renew_current_lease(&mut self) {
+        self.begin_operation_on_connected_network(OperationKind::RenewLease);
+    }
+
+    fn begin_operation_on_connected_network(
+        &mut self,
+        operation: OperationKind,
+    ) {
+        match self.connected_network().cloned() {
+            Some(network) => self.begin_operation(network, operation),
+            None => {
+                self.status_message = "Not connected to a network".to_string()
+            }
+        }
+    }
+
+    pub fn finish_lease_renewal(&mut self, result: Result<DhcpLease, String>) {
+        match result {
+            Ok(lease) => {
+                self.renewed_lease = Some(lease);
+                self.finish_operation(true, None);
+            }
+            Err(error) => self.finish_operation(false, Some(error)),
+        }
+    }
+
+    /// Starts a fresh speed test over the active connection.
+    pub fn open_speed_test(&mut self) {
+        if self.connected_network().is_some() {
+            self.speed_test = Some(SpeedTest::default());
+            self.state = AppState::SpeedTest;
+        } else {
+            self.status_message = "Not connected to a network".to_string();
+        }
+    }
+
+    /// The next chunk the open speed test waits for.
+    pub fn pending_speed_test_chunk(&self) -> Option<TransferDirection> {
+        if self.state != AppState::SpeedTest {
+            return None;
+        }
+        self.speed_test.as_ref()?.next_direction()
+    }
+
+    /// Records a chunk; one that finishes after the test was closed is
+    /// dropped.
+    pub fn apply_speed_test_chunk(
+        &mut self,
+        direction: TransferDirection,
+        result: Result<ChunkTransfer, String>,
+    ) {
+        if self.state != AppState::SpeedTest {
+            return;
+        }
+        if let Some(test) = self.speed_test.as_mut() {
+            test.record(direction, result);
+        }
+    }
+
+    pub fn open_roaming_view(&mut self) {
+        if self.connected_network().is_some() {
+            self.roaming_index = 0;
+            self.state = AppState::Roaming;
+        } else {
+            self.status_message = "Not connected to a network".to_string();
+        }
+    }
+
+    /// Plots the band of the connected network, or 2.4 GHz.
+    pub fn open_spectrum_view(&mut self) {
+        self.spectrum_band = self
+            .connected_network()
+            .map_or(WifiBand::TwoPointFourGhz, WifiNetwork::band);
+        self.state = AppState::Spectrum;
+    }
+
+    pub fn cycle_spectrum_band(&mut self, backwards: bool) {
+        const BANDS: [WifiBand; 3] = [
+            WifiBand::TwoPointFourGhz,
+            WifiBand::FiveGhz,
+            WifiBand::SixGhz,
+        ];
+        let index = BANDS
+            .iter()
+            .position(|band| *band == self.spectrum_band)
+            .unwrap_or(0);
+        let step = if backwards { BANDS.len() - 1 } else { 1 };
+        self.spectrum_band = BANDS[(index + step) % BANDS.len()];
+    }
+
+    /// Access points of every network in range per channel of the plotted
+    /// band.
+    pub fn spectrum(&self) -> Vec<ChannelLoad> {
+        channel_loads(
+            self.networks
+                .iter()
+                .flat_map(|network| &network.access_points),
+            self.spectrum_band,
+        )
+    }
+
+    /// Access points of the connected SSID, strongest first.
+    pub fn roaming_access_points(&self) -> Vec<&WifiAccessPoint> {
+        let mut access_points = self
+            .connected_network()
+            .map(|network| network.access_points.iter().collect::<Vec<_>>())
+            .unwrap_or_default();
+        access_points
+            .sort_by_key(|access_point| Reverse(access_point.signal_strength));
+        access_points
+    }
+
+    pub fn move_roaming_selection(&mut self, up: bool) {
+        let count = self.roaming_access_points().len();
+        self.roaming_index = if up {
+            self.roaming_index.saturating_sub(1)
+        } else {
+            (self.roaming_index + 1).min(count.saturating_sub(1))
+        };
+    }
+
+    /// Avoids the highlighted access point, or allows it again.
+    pub fn toggle_avoided_access_point(&mut self) {
+        let Some(ssid) =
+            self.connected_network().map(|network| network.ssid.clone())
+        else {
+            return;
+        };
+        let Some(bssid) = self
+            .roaming_access_points()
+            .get(self.roaming_index)
+            .map(|access_point| access_point.bssid.clone())
+        else {
+            return;
+        };
+
+        self.status_message = if self.avoided.toggle(&ssid, &bssid) {
+            self.last_steer_at = None;
+            format!("Avoiding {bssid} on {ssid}")
+        } else {
+            format!("No longer avoiding {bssid} on {ssid}")
+        };
+    }
+
+    /// The connected network and the BSSID to move it to, when it sits on
+    /// an avoided access point and was not steered moments ago.
+    pub fn pending_steer(&self) -> Option<(&WifiNetwork, &str)> {
+        if self
+            .last_steer_at
+            .is_some_and(|at| at.elapsed() < STEER_RETRY_INTERVAL)
+        {
+            return None;
+        }
+        let network = self.connected_network()?;
+        let target = self.avoided.steer_target(network)?;
+        Some((network, target.bssid.as_str()))
+    }
+
+    pub fn begin_steer(&mut self) {
+        self.last_steer_at = Some(Instant::now());
+    }
+
+    pub fn finish_steer(&mut self, bssid: &str, result: Result<(), String>) {
+        match result {
+            Ok(()) => {
+                self.status_message =
+                    format!("Moved to {bssid}, away from an avoided AP")
+            }
+            Err(error) => self.notifier.toasts().push(
+                ToastLevel::Warning,
+                format!("Could not move to {bssid}"),
+                error,
+            ),
+        }
+    }
+
+    /// Asks to pin the saved profile of the connected network to the
+    /// access point it is associated with.
+    pub fn pin_current_access_point(&mut self) {
+        let Some(network) = self.connected_network() else {
+            return;
+        };
+        if !network.saved {
+            self.status_message =
+                format!("{} has no saved profile", network.ssid);
+        } else if network
+            .access_points
+            .iter()
+            .any(|access_point| access_point.associated)
+        {
+            self.pinning_access_point = true;
+        } else {
+            self.status_message = "No associated access point".to_string();
+        }
+    }
+
+    /// The connected network and the BSSID to pin its profile to, of a pin
+    /// that has not been sent yet.
+    pub fn pending_access_point_pin(&self) -> Option<(WifiNetwork, String)> {
+        if !self.pinning_access_point {
+            return None;
+        }
+        let network = self.connected_network()?;
+        let associated = network
+            .access_points
+            .iter()
+            .find(|access_point| access_point.associated)?;
+        Some((network.clone(), associated.bssid.to_ascii_uppercase()))
+    }
+
+    pub fn finish_access_point_pin(
+        &mut self,
+        bssid: &str,
+        result: Result<(), String>,
+    ) {
+        self.pinning_access_point = false;
+        self.status_message = match result {
+            Ok(()) => format!("Pinned to {bssid}"),
+            Err(error) => format!("Could not pin to {bssid}: {error}"),
+        };
+    }
+
+    /// Opens the details view. Saved networks get their proxy settings
+    /// loaded, and those not connected are also checked for whatever keeps
+    /// NM from autoconnecting to them.
+    pub fn open_network_details(&mut self) {
+        let Some(network) = self.selected_network_in_list().cloned() else {
+            return;
+        };
+
+        self.autoconnect = (network.saved && !network.connected).then(|| {
+            AutoconnectDiagnosis {
+                ssid: network.ssid.clone(),
+                check: AutoconnectCheck::Loading,
+            }
+        });
+        self.profile_lookup = network.saved.then(|| ProfileLookup {
+            ssid: network.ssid.clone(),
+            check: ProfileCheck::Loading,
+        });
+        self.link_bitrate = None;
+        self.next_bitrate_refresh_at = None;
+        self.state = AppState::NetworkDetails;
+    }
+
+    /// The saved network whose profile settings the details view waits for.
+    pub fn pending_profile_lookup(&self) -> Option<&WifiNetwork> {
+        let lookup = self.profile_lookup.as_ref().filter(|lookup| {
+            self.state == AppState::NetworkDetails
+                && lookup.check == ProfileCheck::Loading
+        })?;
+        self.networks
+            .iter()
+            .find(|network| network.ssid == lookup.ssid)
+    }
+
+    pub fn apply_profile_settings(
+        &mut self,
+        result: Result<Option<ProfileSettings>, String>,
+    ) {
+        if let Some(lookup) = self.profile_lookup.as_mut() {
+            lookup.check = match result {
+                Ok(settings) => ProfileCheck::Loaded(settings),
+                Err(error) => ProfileCheck::Failed(error),
+            };
+        }
+    }
+
+    fn autoconnect_network(
+        &self,
+        check: AutoconnectCheck,
+    ) -> Option<&WifiNetwork> {
+        let diagnosis = self.autoconnect.as_ref().filter(|diagnosis| {
+            self.state == AppState::NetworkDetails && diagnosis.check == check
+        })?;
+        self.networks
+            .iter()
+            .find(|network| network.ssid == diagnosis.ssid)
+    }
+
+    /// The network whose autoconnect diagnosis the details view waits for.
+    pub fn pending_autoconnect_check(&self) -> Option<&WifiNetwork> {
+        self.autoconnect_network(AutoconnectCheck::Loading)
+    }
+
+    /// The network whose autoconnect block is being cleared.
+    pub fn pending_autoconnect_clear(&self) -> Option<&WifiNetwork> {
+        self.autoconnect_network(AutoconnectCheck::Clearing)
+    }
+
+    pub fn apply_autoconnect_block(
+        &mut self,
+        result: Result<Option<AutoconnectBlock>, String>,
+    ) {
+        if let Some(diagnosis) = self.autoconnect.as_mut() {
+            diagnosis.check = match result {
+                Ok(block) => AutoconnectCheck::Loaded(block),
+                Err(error) => AutoconnectCheck::Failed(error),
+            };
+        }
+    }
+
+    pub fn clear_autoconnect_block(&mut self) {
+        if let Some(diagnosis) = self.autoconnect.as_mut()
+            && let AutoconnectCheck::Loaded(Some(block)) = diagnosis.check
+            && block.clearable()
+        {
+            diagnosis.check = AutoconnectCheck::Clearing;
+        }
+    }
+
+    /// A cleared block is checked again, since NM may still have another
+    /// reason to skip the network.
+    pub fn finish_autoconnect_clear(&mut self, result: Result<(), String>) {
+        let Some(diagnosis) = self.autoconnect.as_mut() else {
+            return;
+        };
+
+        match result {
+            Ok(()) => {
+                self.status_message = format!(
+                    "Cleared the autoconnect block for {}",
+                    diagnosis.ssid
+                );
+                diagnosis.check = AutoconnectCheck::Loading;
+            }
+            Err(error) => diagnosis.check = AutoconnectCheck::Failed(error),
+        }
+    }
+
+    /// Whether the adapter the app is using is one NM does not manage.
+    pub fn adapter_unmanaged(&self) -> bool {
+        self.adapter_name
+            .as_ref()
+            .is_some_and(|name| self.unmanaged_adapters.contains(name))
+    }
+
+    /// Asks NM to take over the first unmanaged adapter.
+    pub fn manage_adapter(&mut self) {
+        if self.managing_adapter.is_some() {
+            return;
+        }
+        match self.unmanaged_adapters.first().cloned() {
+            Some(interface) => {
+                self.status_message =
+                    format!("Asking NetworkManager to manage {interface}...");
+                self.managing_adapter = Some(interface);
+            }
+            None => {
+                self.status_message =
+                    "All Wi-Fi adapters are managed by NetworkManager"
+                        .to_string();
+            }
+        }
+    }
+
+    /// Leaves the list for the NetworkManager error screen. Dialogs stay
+    /// open; their own requests report the failure.
+    pub fn show_manager_unavailable(&mut self, error: String) {
+        if matches!(self.state, AppState::Scanning | AppState::NetworkList) {
+            self.state = AppState::ManagerUnavailable;
+        }
+        self.status_message = error.clone();
+        self.manager_error = Some(error);
+        self.manager_owner = None;
+        self.next_manager_check_at =
+            Some(Instant::now() + MANAGER_CHECK_INTERVAL);
+    }
+
+    pub fn manager_check_due(&self) -> bool {
+        self.state == AppState::ManagerUnavailable
+            && !self.starting_manager
+            && self
+                .next_manager_check_at
+                .is_some_and(|at| at <= Instant::now())
+    }
+
+    /// Scans again as soon as NetworkManager is back on the bus.
+    pub fn apply_manager_check(&mut self, result: Result<(), String>) {
+        match result {
+            Ok(()) if self.state == AppState::ManagerUnavailable => {
+                self.manager_error = None;
+                self.next_manager_check_at = None;
+                self.start_scan();
+                self.status_message =
+                    "NetworkManager is back. Scanning...".to_string();
+            }
+            Ok(()) => {}
+            Err(error) => {
+                self.manager_error = Some(error);
+                self.next_manager_check_at =
+                    Some(Instant::now() + MANAGER_CHECK_INTERVAL);
+            }
+        }
+    }
+
+    /// Remembers NetworkManager's bus name and says so when it changed,
+    /// i.e. NetworkManager restarted since the last scan.
+    pub fn note_manager_owner(&mut self, owner: Option<String>) {
+        if let (Some(previous), Some(current)) = (&self.manager_owner, &owner)
+            && previous != current
+        {
+            self.status_message =
+                "NetworkManager restarted; networks reloaded".to_string();
+        }
+        if owner.is_some() {
+            self.manager_owner = owner;
+        }
+    }
+
+    pub fn show_authorization_denied(&mut self, error: String) {
+        self.authorization_denied = Some(error);
+    }
+
+    pub fn dismiss_authorization_denied(&mut self) {
+        self.authorization_denied = None;
+    }
+
+    pub fn start_network_manager(&mut self) {
+        if !self.starting_manager {
+            self.starting_manager = true;
+            self.status_message = "Starting NetworkManager...".to_string();
+        }
+    }
+
+    pub fn pending_manager_start(&self) -> bool {
+        self.starting_manager
+    }
+
+    pub fn finish_manager_start(&mut self, result: Result<(), String>) {
+        self.starting_manager = false;
+        match result {
+            Ok(()) => {
+                self.manager_error = None;
+                self.next_manager_check_at = None;
+                self.start_scan();
+                self.status_message =
+                    "NetworkManager started. Scanning...".to_string();
+            }
+            Err(error) => {
+                self.status_message =
+                    format!("Could not start NetworkManager: {error}");
+            }
+        }
+    }
+
+    pub fn pending_adapter_manage(&self) -> Option<&str> {
+        self.managing_adapter.as_deref()
+    }
+
+    /// A newly managed adapter is scanned right away, since its previous
+    /// results were empty.
+    pub fn finish_adapter_manage(&mut self, result: Result<(), String>) {
+        let Some(interface) = self.managing_adapter.take() else {
+            return;
+        };
+
+        match result {
+            Ok(()) => {
+                self.unmanaged_adapters.retain(|name| *name != interface);
+                self.start_scan();
+                self.status_message = format!(
+                    "{interface} is now managed by NetworkManager. \
+                     Scanning..."
+                );
+            }
+            Err(error) => self.status_message = error,
+        }
+    }
+
+    pub fn begin_qr_share(&mut self) {
+        match self.selected_network_in_list().cloned() {
+            Some(network)
+                if network.is_secured()
+                    && !network.saved
+                    && !network.connected =>
+            {
+                self.status_message =
+                    format!("No saved password to share for {}", network.ssid);
+            }
+            Some(network) => {
+                self.qr_share = Some(QrShare::new(network));
+                self.state = AppState::ShareQr;
+            }
+            None => {}
+        }
+    }
+
+    /// The network whose saved password the QR share is waiting for.
+    pub fn pending_share_password(&self) -> Option<&WifiNetwork> {
+        self.qr_share
+            .as_ref()
+            .filter(|share| {
+                self.state == AppState::ShareQr
+                    && share.password == SharePassword::Loading
+            })
+            .map(|share| &share.network)
+    }
+
+    /// The saved network whose stored password the prompt is waiting for.
+    pub fn pending_prefill_password(&self) -> Option<&WifiNetwork> {
+        self.selected_network.as_ref().filter(|_| {
+            self.state == AppState::PasswordInput
+                && self.password_prefill == PasswordPrefill::Loading
+        })
+    }
+
+    pub fn apply_saved_password(
+        &mut self,
+        result: Result<Option<String>, String>,
+    ) {
+        if self.password_prefill == PasswordPrefill::Loading {
+            // Secrets that could not be read (e.g. polkit said no) leave
+            // the prompt empty, and what the user already typed wins.
+            self.password_prefill = match result {
+                Ok(Some(password))
+                    if self.state == AppState::PasswordInput
+                        && self.password_input.is_empty() =>
+                {
+                    self.password_input = password;
+                    PasswordPrefill::Filled
+                }
+                _ => PasswordPrefill::None,
+            };
+            if std::mem::take(&mut self.unattended_connect)
+                && self.password_prefill == PasswordPrefill::Filled
+            {
+                self.confirm_password();
+            }
+            return;
+        }
+        if let Some(share) = self.qr_share.as_mut() {
+            share.password = match result {
+                Ok(password) => SharePassword::Loaded(password),
+                Err(error) => SharePassword::Failed(error),
+            };
+        }
+    }
+
+    pub fn export_qr_share(&mut self) {
+        let Some(share) = self.qr_share.as_mut() else {
+            return;
+        };
+        let Some(payload) = share.payload() else {
+            return;
+        };
+
+        let path = expand_export_path(share.export_path.trim());
+        share.export_result = Some(
+            export_qr(&payload, &path)
+                .map(|()| format!("Saved {}", path.display()))
+                .map_err(|error| format!("Export failed: {error}")),
+        );
+    }
+
+    /// Surveys the associated access point of the selected network, or its
+    /// strongest one.
+    pub fn open_site_survey(&mut self) {
+        let Some(network) = self.selected_network_in_list() else {
+            return;
+        };
+        let Some(access_point) = network
+            .access_points
+            .iter()
+            .find(|access_point| access_point.associated)
+            .or_else(|| {
+                network
+                    .access_points
+                    .iter()
+                    .max_by_key(|access_point| access_point.signal_strength)
+            })
+        else {
+            self.status_message =
+                format!("No access point of {} to survey", network.ssid);
+            return;
+        };
+
+        let now = Instant::now();
+        let mut survey = SiteSurvey::new(&network.ssid, access_point, now);
+        survey.record(Some(access_point.signal_strength), now);
+        self.site_survey = Some(survey);
+        self.state = AppState::SiteSurvey;
+        self.next_signal_refresh_at = Some(now);
+    }
+
+    pub fn close_site_survey(&mut self) {
+        self.site_survey = None;
+        self.state = AppState::NetworkList;
+        self.schedule_signal_refresh();
+    }
+
+    pub fn restart_site_survey(&mut self) {
+        if let Some(survey) = self.site_survey.as_mut() {
+            survey.restart(Instant::now());
+        }
+    }
+
+    /// Records the surveyed access point from a signal refresh, as missed
+    /// when the refresh did not hear it.
+    pub fn record_survey_sample(&mut self, signals: &[WifiAccessPoint]) {
+        if self.state != AppState::SiteSurvey {
+            return;
+        }
+        if let Some(survey) = self.site_survey.as_mut() {
+            let signal = signals
+                .iter()
+                .find(|signal| signal.bssid.eq_ignore_ascii_case(&survey.bssid))
+                .map(|signal| signal.signal_strength);
+            survey.record(signal, Instant::now());
+        }
+    }
+
+    /// Writes the samples to `~/nm-wifi-survey-<ssid>-<unix time>.csv`.
+    pub fn export_site_survey(&mut self) {
+        let Some(survey) = self.site_survey.as_mut() else {
+            return;
+        };
+        let timestamp = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map_or(0, |elapsed| elapsed.as_secs());
+        let path = expand_export_path(&format!(
+            "~/nm-wifi-survey-{}-{timestamp}.csv",
+            file_stem(&survey.ssid)
+        ));
+        survey.export_result = Some(
+            survey
+                .export(&path)
+                .map(|()| {
+                    format!(
+                        "Saved {} samples to {}",
+                        survey.samples.len(),
+                        path.display()
+                    )
+                })
+                .map_err(|error| format!("Export failed: {error}")),
+        );
+    }
+
+    pub fn close_qr_share(&mut self) {
+        self.qr_share = None;
+        self.state = AppState::NetworkList;
+    }
+
+    pub fn open_palette(&mut self) {
+        self.palette = Some(CommandPalette::default());
+        self.state = AppState::CommandPalette;
+    }
+
+    pub fn close_palette(&mut self) {
+        self.palette = None;
+        self.state = AppState::NetworkList;
+    }
+
+    pub fn run_palette_command(&mut self) {
+        let Some(command) = self.palette.take().and_then(|palette| {
+            palette.commands().get(palette.selected).copied()
+        }) else {
+            self.close_palette();
+            return;
+        };
+
+        self.state = AppState::NetworkList;
+        match command {
+            PaletteCommand::Rescan => self.start_scan(),
+            PaletteCommand::ForgetMatching => self.begin_bulk_forget(),
+            PaletteCommand::ExportProfiles => self.begin_profile_export(),
+            PaletteCommand::KnownNetworks => self.open_known_networks(),
+            PaletteCommand::CleanupProfiles => self.begin_profile_cleanup(),
+            PaletteCommand::ImportQr => self.begin_qr_import(),
+            PaletteCommand::RenewLease => self.renew_current_lease(),
+            PaletteCommand::EthernetDevices => {
+                self.open_device_tab(DeviceKind::Ethernet)
+            }
+            PaletteCommand::MobileDevices => {
+                self.open_device_tab(DeviceKind::Mobile)
+            }
+            PaletteCommand::BluetoothDevices => {
+                self.open_device_tab(DeviceKind::Bluetooth)
+            }
+            PaletteCommand::WifiDirectPeers => self.open_p2p_tab(),
+            PaletteCommand::ConnectionHistory => self.open_connection_history(),
+            PaletteCommand::NetworkManagerLog => self.open_log_view(),
+            PaletteCommand::ShowHelp => self.state = AppState::Help,
+        }
+    }
+
+    pub fn open_connection_history(&mut self) {
+        self.history_selected = 0;
+        self.state = AppState::ConnectionHistory;
+    }
+
+    pub fn move_history_selection(&mut self, forward: bool) {
+        let count = self.history.entries.len();
+        if count == 0 {
+            return;
+        }
+        self.history_selected = if forward {
+            (self.history_selected + 1) % count
+        } else {
+            (self.history_selected + count - 1) % count
+        };
+    }
+
+    pub fn open_log_view(&mut self) {
+        self.log_view = Some(LogView {
+            filtered: true,
+            lines: None,
+            scroll: 0,
+            next_read_at: Instant::now(),
+        });
+        self.state = AppState::NetworkManagerLog;
+    }
+
+    pub fn close_log_view(&mut self) {
+        self.log_view = None;
+        self.state = AppState::NetworkList;
+    }
+
+    /// Words a journal line must contain to be shown while the log is
+    /// narrowed: the adapter and the connected network.
+    pub fn log_filters(&self) -> Vec<String> {
+        self.adapter_name
+            .iter()
+            .cloned()
+            .chain(self.connected_network().map(|network| network.ssid.clone()))
+            .collect()
+    }
+
+    /// The filters of a journal read that is due, empty for the whole log.
+    pub fn pending_log_read(&self) -> Option<Vec<String>> {
+        let view = self.log_view.as_ref()?;
+        if self.state != AppState::NetworkManagerLog
+            || view.next_read_at > Instant::now()
+        {
+            return None;
+        }
+        Some(if view.filtered {
+            self.log_filters()
+        } else {
+            Vec::new()
+        })
+    }
+
+    pub fn apply_log_lines(&mut self, result: Result<Vec<String>, String>) {
+        if let Some(view) = self.log_view.as_mut() {
+            view.lines = Some(result);
+            view.next_read_at = Instant::now() + LOG_REFRESH_INTERVAL;
+        }
+    }
+
+    /// Scrolls `lines` back in time, or forward when `back` is false.
+    pub fn scroll_log_view(&mut self, back: bool, lines: usize) {
+        let Some(view) = self.log_view.as_mut() else {
+            return;
+        };
+        let count = match &view.lines {
+            Some(Ok(log)) => log.len(),
+            _ => 0,
+        };
+        view.scroll = if back {
+            (view.scroll + lines).min(count.saturating_sub(1))
+        } else {
+            view.scroll.saturating_sub(lines)
+        };
+    }
+
+    /// Switches between the narrowed and the whole log, read again at once.
+    pub fn toggle_log_filter(&mut self) {
+        if let Some(view) = self.log_view.as_mut() {
+            view.filtered = !view.filtered;
+            view.scroll = 0;
+            view.next_read_at = Instant::now();
+        }
+    }
+
+    /// Switches to the devices tab of `kind`, listing them afresh.
+    pub fn open_device_tab(&mut self, kind: DeviceKind) {
+        self.device_tab = Some(DeviceTab {
+            kind,
+            devices: None,
+            selected: 0,
+            switching: false,
+            result: None,
+        });
+        self.state = AppState::Devices;
+    }
+
+    /// Steps through the Ethernet, mobile broadband and Bluetooth tabs, and
+    /// from the last one back to the Wi-Fi list.
+    pub fn next_device_tab(&mut self) {
+        match self.device_tab.as_ref().map(|tab| tab.kind) {
+            Some(DeviceKind::Ethernet) => {
+                self.open_device_tab(DeviceKind::Mobile)
+            }
+            Some(DeviceKind::Mobile) => {
+                self.open_device_tab(DeviceKind::Bluetooth)
+            }
+            Some(DeviceKind::Bluetooth) | None => self.close_device_tab(),
+        }
+    }
+
+    pub fn close_device_tab(&mut self) {
+        self.device_tab = None;
+        self.state = AppState::NetworkList;
+    }
+
+    pub fn refresh_devices(&mut self) {
+        if let Some(tab) = self.device_tab.as_mut()
+            && !tab.switching
+        {
+            tab.devices = None;
+        }
+    }
+
+    pub fn move_device_selection(&mut self, forward: bool) {
+        let Some(tab) = self.device_tab.as_mut() else {
+            return;
+        };
+        let count = tab.rows().len();
+        if count == 0 {
+            return;
+        }
+        tab.selected = if forward {
+            (tab.selected + 1) % count
+        } else {
+            (tab.selected + count - 1) % count
+        };
+    }
+
+    /// Asks to bring the selected profile up, or its device down when the
+    /// profile is already active there.
+    pub fn toggle_selected_device_profile(&mut self) {
+        if let Some(tab) = self.device_tab.as_mut()
+            && tab.selected_row().is_some()
+        {
+            tab.switching = true;
+            tab.result = None;
+        }
+    }
+
+    /// The kind of devices to list, when the open tab has not listed them
+    /// yet.
+    pub fn pending_device_list(&self) -> Option<DeviceKind> {
+        self.device_tab
+            .as_ref()
+            .filter(|tab| {
+                self.state == AppState::Devices && tab.devices.is_none()
+            })
+            .map(|tab| tab.kind)
+    }
+
+    /// The interface and the profile to bring up on it (`None` to take it
+    /// down), of a switch that has not been sent yet.
+    pub fn pending_device_change(
+        &self,
+    ) -> Option<(String, Option<DeviceProfile>)> {
+        let tab = self
+            .device_tab
+            .as_ref()
+            .filter(|tab| self.state == AppState::Devices && tab.switching)?;
+        let (device, profile) = tab.selected_row()?;
+        let profile = (!device.is_active(profile)).then(|| profile.clone());
+        Some((device.interface.clone(), profile))
+    }
+
+    pub fn apply_devices(
+        &mut self,
+        result: Result<Vec<NetworkDevice>, String>,
+    ) {
+        if let Some(tab) = self.device_tab.as_mut() {
+            tab.devices = Some(result);
+            let count = tab.rows().len();
+            tab.selected = tab.selected.min(count.saturating_sub(1));
+        }
+    }
+
+    /// Reports the outcome and lists the devices again to show their new
+    /// state.
+    pub fn finish_device_change(
+        &mut self,
+        interface: &str,
+        profile: Option<DeviceProfile>,
+        result: Result<(), String>,
+    ) {
+        let Some(tab) = self.device_tab.as_mut() else {
+            return;
+        };
+        tab.switching = false;
+        tab.result = Some(match (result, profile) {
+            (Ok(()), Some(profile)) => {
+                Ok(format!("Brought {} up on {interface}", profile.id))
+            }
+            (Ok(()), None) => Ok(format!("Took {interface} down")),
+            (Err(error), _) => Err(error),
+        });
+        tab.devices = None;
+    }
+
+    /// Switches to the Wi-Fi Direct peers, searching for them afresh.
+    pub fn open_p2p_tab(&mut self) {
+        self.p2p_tab = Some(P2pTab::default());
+        self.state = AppState::P2pPeers;
+    }
+
+    pub fn close_p2p_tab(&mut self) {
+        self.p2p_tab = None;
+        self.state = AppState::NetworkList;
+    }
+
+    pub fn refresh_p2p_peers(&mut self) {
+        if let Some(tab) = self.p2p_tab.as_mut()
+            && !tab.connecting
+        {
+            tab.peers = None;
+        }
+    }
+
+    pub fn move_p2p_selection(&mut self, forward: bool) {
+        let Some(tab) = self.p2p_tab.as_mut() else {
+            return;
+        };
+        let count = match &tab.peers {
+            Some(Ok(peers)) => peers.len(),
+            _ => 0,
+        };
+        if count == 0 {
+            return;
+        }
+        tab.selected = if forward {
+            (tab.selected + 1) % count
+        } else {
+            (tab.selected + count - 1) % count
+        };
+    }
+
+    pub fn connect_selected_p2p_peer(&mut self) {
+        if let Some(tab) = self.p2p_tab.as_mut()
+            && tab.selected_peer().is_some()
+        {
+            tab.connecting = true;
+            tab.result = None;
+        }
+    }
+
+    /// Whether the open peers tab has not searched for peers yet.
+    pub fn pending_p2p_search(&self) -> bool {
+        self.state == AppState::P2pPeers
+            && self.p2p_tab.as_ref().is_some_and(|tab| tab.peers.is_none())
+    }
+
+    /// The peer to connect to, when that has not been sent yet.
+    pub fn pending_p2p_connect(&self) -> Option<P2pPeer> {
+        self.p2p_tab
+            .as_ref()
+            .filter(|tab| self.state == AppState::P2pPeers && tab.connecting)?
+            .selected_peer()
+            .cloned()
+    }
+
+    pub fn apply_p2p_peers(&mut self, result: Result<Vec<P2pPeer>, String>) {
+        if let Some(tab) = self.p2p_tab.as_mut() {
+            let count = result.as_ref().map_or(0, Vec::len);
+            tab.peers = Some(result);
+            tab.selected = tab.selected.min(count.saturating_sub(1));
+        }
+    }
+
+    pub fn finish_p2p_connect(
+        &mut self,
+        name: &str,
+        result: Result<(), String>,
+    ) {
+        if let Some(tab) = self.p2p_tab.as_mut() {
+            tab.connecting = false;
+            tab.result = Some(result.map(|()| format!("Connected to {name}")));
+        }
+    }
+
+    pub fn begin_qr_import(&mut self) {
+        self.qr_import = Some(QrImport::default());
+        self.state = AppState::ImportQr;
+    }
+
+    pub fn close_qr_import(&mut self) {
+        self.qr_import = None;
+        self.state = AppState::NetworkList;
+    }
+
+    pub fn add_char_to_qr_import(&mut self, c: char) {
+        if let Some(import) = self.qr_import.as_mut() {
+            import.input.push(c);
+            import.error = None;
+        }
+    }
+
+    pub fn remove_char_from_qr_import(&mut self) {
+        if let Some(import) = self.qr_import.as_mut() {
+            import.input.pop();
+            import.error = None;
+        }
+    }
+
+    /// Connects to the network of the pasted `WIFI:` string, creating its
+    /// profile. A scanned network of the same name and kind of security is
+    /// used when there is one, so its access points are tried in order.
+    pub fn confirm_qr_import(&mut self) {
+        let Some(import) = self.qr_import.as_mut() else {
+            return;
+        };
+        let imported = match parse_wifi_qr(&import.input) {
+            Ok(imported) => imported,
+            Err(error) => {
+                import.error = Some(error);
+                return;
+            }
+        };
+
+        let personal = |security| {
+            matches!(security, WifiSecurity::WpaPsk | WifiSecurity::WpaSae)
+        };
+        let network = self
+            .networks
+            .iter()
+            .find(|network| {
+                network.ssid == imported.ssid
+                    && (network.security == imported.security
+                        || personal(network.security)
+                            && personal(imported.security))
+            })
+            .cloned()
+            .unwrap_or_else(|| WifiNetwork {
+                ssid: imported.ssid.clone(),
+                raw_ssid: None,
+                signal_strength: 0,
+                security: imported.security,
+                frequency: 0,
+                connected: false,
+                saved: false,
+                max_bitrate: 0,
+                wpa_flags: 0,
+                rsn_flags: 0,
+                access_points: Vec::new(),
+            });
+
+        self.qr_import = None;
+        self.password_input = imported.password.unwrap_or_default();
+        self.profile_name_input.clear();
+        self.proxy_mode_input = ProxyMode::None;
+        self.proxy_input.clear();
+        self.pmf_input = Pmf::Default;
+        self.reset_profile_scope();
+        self.begin_operation(network, OperationKind::Connect);
+        self.hidden_network = imported.hidden;
+    }
+
+    pub fn begin_bulk_forget(&mut self) {
+        self.bulk_forget = Some(BulkForget {
+            pattern: String::new(),
+            profiles: None,
+            stage: ForgetStage::Editing,
+        });
+        self.state = AppState::ForgetProfiles;
+    }
+
+    pub fn close_bulk_forget(&mut self) {
+        self.bulk_forget = None;
+        self.state = AppState::NetworkList;
+    }
+
+    /// Whether a dialog working on saved profiles still needs their list.
+    pub fn pending_profile_list(&self) -> bool {
+        match self.state {
+            AppState::ForgetProfiles => self
+                .bulk_forget
+                .as_ref()
+                .is_some_and(|forget| forget.profiles.is_none()),
+            AppState::ExportProfiles => self
+                .profile_export
+                .as_ref()
+                .is_some_and(|export| export.profiles.is_none()),
+            AppState::KnownNetworks => self
+                .known_networks
+                .as_ref()
+                .is_some_and(|known| known.profiles.is_none()),
+            AppState::CleanupProfiles => self
+                .profile_cleanup
+                .as_ref()
+                .is_some_and(|cleanup| cleanup.profiles.is_none()),
+            _ => false,
+        }
+    }
+
+    pub fn apply_saved_profiles(
+        &mut self,
+        result: Result<Vec<SavedProfile>, String>,
+    ) {
+        if let Some(forget) = self.bulk_forget.as_mut() {
+            forget.profiles = Some(result);
+        } else if let Some(export) = self.profile_export.as_mut() {
+            export.profiles = Some(result);
+        } else if let Some(known) = self.known_networks.as_mut() {
+            known.profiles = Some(result);
+        } else if self.profile_cleanup.is_some() {
+            let unused = result.map(|profiles| self.unused_profiles(profiles));
+            if let Some(cleanup) = self.profile_cleanup.as_mut() {
+                cleanup.picked = unused
+                    .iter()
+                    .flatten()
+                    .map(|profile| profile.uuid.clone())
+                    .collect();
+                cleanup.profiles = Some(unused);
+            }
+        }
+    }
+
+    /// Profiles whose network is not in range and that were not used in
+    /// the last `unused_profile_days`, least recently used first.
+    fn unused_profiles(
+        &self,
+        profiles: Vec<SavedProfile>,
+    ) -> Vec<SavedProfile> {
+        let now = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map(|elapsed| elapsed.as_secs())
+            .unwrap_or_default();
+        let cutoff =
+            now.saturating_sub(self.unused_profile_days * 24 * 60 * 60);
+        let mut unused = profiles
+            .into_iter()
+            .filter(|profile| {
+                profile.unused_since(cutoff)
+                    && !self
+                        .networks
+                        .iter()
+                        .any(|network| network.ssid == profile.ssid)
+            })
+            .collect::<Vec<_>>();
+        unused.sort_by_key(|profile| profile.last_used);
+        unused
+    }
+
+    pub fn begin_profile_cleanup(&mut self) {
+        self.profile_cleanup = Some(ProfileCleanup {
+            profiles: None,
+            selected: 0,
+            picked: HashSet::new(),
+            stage: ForgetStage::Editing,
+        });
+        self.state = AppState::CleanupProfiles;
+    }
+
+    pub fn close_profile_cleanup(&mut self) {
+        self.profile_cleanup = None;
+        self.state = AppState::NetworkList;
+    }
+
+    pub fn move_profile_cleanup_selection(&mut self, forward: bool) {
+        let Some(cleanup) = self.profile_cleanup.as_mut() else {
+            return;
+        };
+        let count = match &cleanup.profiles {
+            Some(Ok(profiles)) if !profiles.is_empty() => profiles.len(),
+            _ => return,
+        };
+        cleanup.selected = if forward {
+            (cleanup.selected + 1) % count
+        } else {
+            (cleanup.selected + count - 1) % count
+        };
+    }
+
+    /// Picks the selected profile for removal, or unpicks it.
+    pub fn toggle_profile_cleanup_pick(&mut self) {
+        let Some(cleanup) = self.profile_cleanup.as_mut() else {
+            return;
+        };
+        let Some(Ok(profiles)) = &cleanup.profiles else {
+            return;
+        };
+        let Some(uuid) =
+            profiles.get(cleanup.selected).map(|profile| &profile.uuid)
+        else {
+            return;
+        };
+        if !cleanup.picked.remove(uuid) {
+            cleanup.picked.insert(uuid.clone());
+        }
+    }
+
+    /// Picks every listed profile, or none when all are picked already.
+    pub fn toggle_profile_cleanup_all(&mut self) {
+        let Some(cleanup) = self.profile_cleanup.as_mut() else {
+            return;
+        };
+        let Some(Ok(profiles)) = &cleanup.profiles else {
+            return;
+        };
+        if cleanup.picked.len() == profiles.len() {
+            cleanup.picked.clear();
+        } else {
+            cleanup.picked = profiles
+                .iter()
+                .map(|profile| profile.uuid.clone())
+                .collect();
+        }
+    }
+
+    /// Moves from picking to confirmation, and from confirmation to
+    /// forgetting the picked profiles.
+    pub fn advance_profile_cleanup(&mut self) {
+        let Some(cleanup) = self.profile_cleanup.as_mut() else {
+            return;
+        };
+
+        cleanup.stage = match cleanup.stage {
+            ForgetStage::Editing if !cleanup.picked_profiles().is_empty() => {
+                ForgetStage::Confirming
+            }
+            ForgetStage::Confirming => ForgetStage::Forgetting,
+            stage => stage,
+        };
+    }
+
+    pub fn cancel_profile_cleanup_confirmation(&mut self) {
+        if let Some(cleanup) = self.profile_cleanup.as_mut()
+            && cleanup.stage == ForgetStage::Confirming
+        {
+            cleanup.stage = ForgetStage::Editing;
+        }
+    }
+
+    pub fn open_known_networks(&mut self) {
+        self.known_networks = Some(KnownNetworks {
+            profiles: None,
+            selected: 0,
+            converting: false,
+            changing_band: false,
+            edit_input: None,
+            saving_edit: false,
+            result: None,
+        });
+        self.state = AppState::KnownNetworks;
+    }
+
+    pub fn close_known_networks(&mut self) {
+        self.known_networks = None;
+        self.state = AppState::NetworkList;
+    }
+
+    pub fn move_known_network_selection(&mut self, forward: bool) {
+        let Some(known) = self.known_networks.as_mut() else {
+            return;
+        };
+        let count = match &known.profiles {
+            Some(Ok(profiles)) if !profiles.is_empty() => profiles.len(),
+            _ => return,
+        };
+        known.selected = if forward {
+            (known.selected + 1) % count
+        } else {
+            (known.selected + count - 1) % count
+        };
+    }
+
+    /// Asks to make the selected profile user-scoped when it is
+    /// system-wide, and system-wide when it is user-scoped.
+    pub fn toggle_known_network_scope(&mut self) {
+        let user_name = self.user_name.clone();
+        let Some(known) = self.known_networks.as_mut() else {
+            return;
+        };
+        let Some(profile) = known.selected_profile() else {
+            return;
+        };
+        if known.converting
+            || known.changing_band
+            || known.saving_edit
+            || known.edit_input.is_some()
+        {
+            return;
+        }
+        if profile.users.is_empty() && user_name.is_none() {
+            known.result =
+                Some(Err("Cannot tell which user is logged in".to_string()));
+            return;
+        }
+        known.converting = true;
+        known.result = None;
+    }
+
+    /// The selected profile and its new owner (`None` for every user) of a
+    /// scope change that has not been sent yet.
+    pub fn pending_profile_scope(
+        &self,
+    ) -> Option<(SavedProfile, Option<String>)> {
+        let known = self.known_networks.as_ref().filter(|known| {
+            self.state == AppState::KnownNetworks && known.converting
+        })?;
+        let profile = known.selected_profile()?;
+        let owner = if profile.users.is_empty() {
+            self.user_name.clone()
+        } else {
+            None
+        };
+        Some((profile.clone(), owner))
+    }
+
+    /// Opens the input of `action` for the selected profile, filled with
+    /// its current name, a name for the copy or its current BSSID, MTU, MAC
+    /// address or VPN; passwords start empty.
+    pub fn begin_profile_edit(&mut self, action: ProfileEdit) {
+        if let Some(known) = self.known_networks.as_mut()
+            && !known.converting
+            && !known.changing_band
+            && !known.saving_edit
+            && let Some(profile) = known.selected_profile()
+        {
+            let input = match action {
+                ProfileEdit::Rename => profile.id.clone(),
+                ProfileEdit::Clone => format!("{} copy", profile.id),
+                ProfileEdit::Password => String::new(),
+                ProfileEdit::Bssid => profile.bssid.clone().unwrap_or_default(),
+                ProfileEdit::Mtu => {
+                    profile.mtu.map(|mtu| mtu.to_string()).unwrap_or_default()
+                }
+                ProfileEdit::ClonedMac => {
+                    profile.cloned_mac.clone().unwrap_or_default()
+                }
+                ProfileEdit::Vpn => profile.vpn.clone().unwrap_or_default(),
+            };
+            known.edit_input = Some((action, input));
+            known.result = None;
+        }
+    }
+
+    pub fn cancel_profile_edit(&mut self) {
+        if let Some(known) = self.known_networks.as_mut() {
+            known.edit_input = None;
+        }
+    }
+
+    pub fn edit_profile_input(&mut self, c: Option<char>) {
+        if let Some((_, input)) = self
+            .known_networks
+            .as_mut()
+            .and_then(|known| known.edit_input.as_mut())
+        {
+            match c {
+                Some(c) => input.push(c),
+                None => {
+                    input.pop();
+                }
+            }
+        }
+    }
+
+    pub fn confirm_profile_edit(&mut self) {
+        let Some(known) = self.known_networks.as_mut() else {
+            return;
+        };
+        let Some((action, input)) = known.edit_input.as_ref() else {
+            return;
+        };
+        if *action == ProfileEdit::Password {
+            match validate_psk(input) {
+                Ok(()) => known.saving_edit = true,
+                Err(error) => known.result = Some(Err(error)),
+            }
+            return;
+        }
+        if *action == ProfileEdit::Bssid {
+            let bssid = input.trim();
+            if bssid.is_empty() || parse_bssid(bssid).is_some() {
+                known.saving_edit = true;
+            } else {
+                known.result = Some(Err(format!(
+                    "{bssid} is not a BSSID like AA:BB:CC:DD:EE:FF"
+                )));
+            }
+            return;
+        }
+        if matches!(action, ProfileEdit::Mtu | ProfileEdit::ClonedMac) {
+            let check = if *action == ProfileEdit::Mtu {
+                parse_mtu(input).map(|_| ())
+            } else {
+                parse_cloned_mac(input).map(|_| ())
+            };
+            match check {
+                Ok(()) => known.saving_edit = true,
+                Err(error) => known.result = Some(Err(error)),
+            }
+            return;
+        }
+        if *action == ProfileEdit::Vpn {
+            known.saving_edit = true;
+            return;
+        }
+        let (action, name) = (*action, input.trim());
+        if name.is_empty() {
+            known.result = Some(Err("The name cannot be empty".to_string()));
+            return;
+        }
+        if action == ProfileEdit::Rename
+            && known
+                .selected_profile()
+                .is_some_and(|profile| profile.id == name)
+        {
+            known.edit_input = None;
+            return;
+        }
+        known.saving_edit = true;
+    }
+
+    /// The selected profile and the input typed for it, of an edit that has
+    /// not been sent yet. Names are trimmed, passwords kept as typed and
+    /// BSSIDs written in uppercase.
+    fn pending_profile_edit(
+        &self,
+        action: ProfileEdit,
+    ) -> Option<(SavedProfile, String)> {
+        let known = self.known_networks.as_ref().filter(|known| {
+            self.state == AppState::KnownNetworks && known.saving_edit
+        })?;
+        let (pending, input) = known.edit_input.as_ref()?;
+        if *pending != action {
+            return None;
+        }
+        let input = match action {
+            ProfileEdit::Password => input.clone(),
+            ProfileEdit::Rename
+            | ProfileEdit::Clone
+            | ProfileEdit::Mtu
+            | ProfileEdit::ClonedMac
+            | ProfileEdit::Vpn => input.trim().to_string(),
+            ProfileEdit::Bssid => parse_bssid(input)
+                .map(|bytes| format_bssid(&bytes))
+                .unwrap_or_default(),
+        };
+        Some((known.selected_profile()?.clone(), input))
+    }
+
+    pub fn pending_profile_password(&self) -> Option<(SavedProfile, String)> {
+        self.pending_profile_edit(ProfileEdit::Password)
+    }
+
+    /// Keeps the dialog open with the outcome of a password change.
+    pub fn finish_profile_password(&mut self, result: Result<(), String>) {
+        let Some(known) = self.known_networks.as_mut() else {
+            return;
+        };
+        known.saving_edit = false;
+        known.edit_input = None;
+        let id = known
+            .selected_profile()
+            .map(|profile| profile.id.clone())
+            .unwrap_or_default();
+        known.result = Some(
+            result
+                .map(|()| format!("Updated the password of {id}"))
+                .map_err(|error| format!("Password change failed: {error}")),
+        );
+    }
+
+    /// The selected profile and the BSSID to pin it to (`None` to unpin),
+    /// of a change that has not been sent yet.
+    pub fn pending_profile_bssid(
+        &self,
+    ) -> Option<(SavedProfile, Option<String>)> {
+        let (profile, bssid) = self.pending_profile_edit(ProfileEdit::Bssid)?;
+        Some((profile, Some(bssid).filter(|bssid| !bssid.is_empty())))
+    }
+
+    /// Keeps the dialog open with the outcome, updating the listed pin of
+    /// the selected profile on success.
+    pub fn finish_profile_bssid(
+        &mut self,
+        bssid: Option<String>,
+        result: Result<(), String>,
+    ) {
+        let Some(known) = self.known_networks.as_mut() else {
+            return;
+        };
+        known.saving_edit = false;
+        known.edit_input = None;
+        let selected = known.selected;
+        let Some(Ok(profiles)) = known.profiles.as_mut() else {
+            return;
+        };
+        let Some(profile) = profiles.get_mut(selected) else {
+            return;
+        };
+        known.result = Some(match result {
+            Ok(()) => {
+                let message = match &bssid {
+                    Some(bssid) => format!("Pinned {} to {bssid}", profile.id),
+                    None => format!("{} may use any access point", profile.id),
+                };
+                profile.bssid = bssid;
+                Ok(message)
+            }
+            Err(error) => Err(format!("Access point change failed: {error}")),
+        });
+    }
+
+    /// The selected profile and its new MTU or cloned MAC address, of a
+    /// change that has not been sent yet.
+    pub fn pending_profile_link(&self) -> Option<(SavedProfile, LinkSetting)> {
+        if let Some((profile, mtu)) =
+            self.pending_profile_edit(ProfileEdit::Mtu)
+        {
+            return Some((profile, LinkSetting::Mtu(parse_mtu(&mtu).ok()?)));
+        }
+        let (profile, mac) =
+            self.pending_profile_edit(ProfileEdit::ClonedMac)?;
+        Some((
+            profile,
+            LinkSetting::ClonedMac(parse_cloned_mac(&mac).ok()?),
+        ))
+    }
+
+    /// Keeps the dialog open with the outcome, updating the listed MTU or
+    /// MAC address of the selected profile on success.
+    pub fn finish_profile_link(
+        &mut self,
+        setting: LinkSetting,
+        result: Result<(), String>,
+    ) {
+        let Some(known) = self.known_networks.as_mut() else {
+            return;
+        };
+        known.saving_edit = false;
+        known.edit_input = None;
+        let selected = known.selected;
+        let Some(Ok(profiles)) = known.profiles.as_mut() else {
+            return;
+        };
+        let Some(profile) = profiles.get_mut(selected) else {
+            return;
+        };
+        known.result = Some(match result {
+            Ok(()) => {
+                let message = match &setting {
+                    LinkSetting::Mtu(Some(mtu)) => {
+                        format!("Set the MTU of {} to {mtu}", profile.id)
+                    }
+                    LinkSetting::Mtu(None) => {
+                        format!("{} uses the default MTU", profile.id)
+                    }
+                    LinkSetting::ClonedMac(Some(mac)) => {
+                        format!("{} now uses MAC address {mac}", profile.id)
+                    }
+                    LinkSetting::ClonedMac(None) => {
+                        format!("{} uses the adapter's MAC address", profile.id)
+                    }
+                };
+                match setting {
+                    LinkSetting::Mtu(mtu) => profile.mtu = mtu,
+                    LinkSetting::ClonedMac(mac) => profile.cloned_mac = mac,
+                }
+                Ok(message)
+            }
+            Err(error) => Err(format!("Link settings change failed: {error}")),
+        });
+    }
+
+    /// The selected profile and the VPN to bring up with it (`None` for
+    /// none), of a change that has not been sent yet.
+    pub fn pending_profile_vpn(
+        &self,
+    ) -> Option<(SavedProfile, Option<String>)> {
+        let (profile, vpn) = self.pending_profile_edit(ProfileEdit::Vpn)?;
+        Some((profile, Some(vpn).filter(|vpn| !vpn.is_empty())))
+    }
+
+    /// Keeps the dialog open with the outcome, updating the listed VPN of
+    /// the selected profile on success.
+    pub fn finish_profile_vpn(
+        &mut self,
+        vpn: Option<String>,
+        result: Result<(), String>,
+    ) {
+        let Some(known) = self.known_networks.as_mut() else {
+            return;
+        };
+        known.saving_edit = false;
+        known.edit_input = None;
+        let selected = known.selected;
+        let Some(Ok(profiles)) = known.profiles.as_mut() else {
+            return;
+        };
+        let Some(profile) = profiles.get_mut(selected) else {
+            return;
+        };
+        known.result = Some(match result {
+            Ok(()) => {
+                let message = match &vpn {
+                    Some(vpn) => {
+                        format!(
+                            "{vpn} comes up whenever {} connects",
+                            profile.id
+                        )
+                    }
+                    None => format!("{} no longer brings a VPN up", profile.id),
+                };
+                profile.vpn = vpn;
+                Ok(message)
+            }
+            Err(error) => Err(format!("VPN change failed: {error}")),
+        });
+    }
+
+    pub fn pending_profile_rename(&self) -> Option<(SavedProfile, String)> {
+        self.pending_profile_edit(ProfileEdit::Rename)
+    }
+
+    pub fn pending_profile_clone(&self) -> Option<(SavedProfile, String)> {
+        self.pending_profile_edit(ProfileEdit::Clone)
+    }
+
+    /// Keeps the dialog open with the outcome, listing the copy below the
+    /// original and selecting it on success.
+    pub fn finish_profile_clone(
+        &mut self,
+        result: Result<SavedProfile, String>,
+    ) {
+        let Some(known) = self.known_networks.as_mut() else {
+            return;
+        };
+        known.saving_edit = false;
+        known.edit_input = None;
+        let Some(Ok(profiles)) = known.profiles.as_mut() else {
+            return;
+        };
+        known.result = Some(match result {
+            Ok(copy) => {
+                let message = format!("Created {}", copy.id);
+                known.selected = (known.selected + 1).min(profiles.len());
+                profiles.insert(known.selected, copy);
+                Ok(message)
+            }
+            Err(error) => Err(format!("Clone failed: {error}")),
+        });
+    }
+
+    /// Keeps the dialog open with the outcome, listing the profile under
+    /// its new name on success.
+    pub fn finish_profile_rename(
+        &mut self,
+        name: String,
+        result: Result<(), String>,
+    ) {
+        let Some(known) = self.known_networks.as_mut() else {
+            return;
+        };
+        known.saving_edit = false;
+        known.edit_input = None;
+        let selected = known.selected;
+        let Some(Ok(profiles)) = known.profiles.as_mut() else {
+            return;
+        };
+        let Some(profile) = profiles.get_mut(selected) else {
+            return;
+        };
+        known.result = Some(match result {
+            Ok(()) => {
+                let message = format!("Renamed {} to {name}", profile.id);
+                profile.id = name;
+                Ok(message)
+            }
+            Err(error) => Err(format!("Rename failed: {error}")),
+        });
+    }
+
+    /// Asks to move the band lock of the selected profile along
+    /// any → 2.4 GHz → 5 GHz → any.
+    pub fn cycle_known_network_band(&mut self) {
+        if let Some(known) = self.known_networks.as_mut()
+            && known.selected_profile().is_some()
+            && !known.converting
+            && !known.changing_band
+            && !known.saving_edit
+            && known.edit_input.is_none()
+        {
+            known.changing_band = true;
+            known.result = None;
+        }
+    }
+
+    /// The selected profile and the band to lock it to next (`None` for
+    /// any band), of a change that has not been sent yet.
+    pub fn pending_profile_band(
+        &self,
+    ) -> Option<(SavedProfile, Option<WifiBand>)> {
+        let known = self.known_networks.as_ref().filter(|known| {
+            self.state == AppState::KnownNetworks && known.changing_band
+        })?;
+        let profile = known.selected_profile()?;
+        let band = match profile.band {
+            None => Some(WifiBand::TwoPointFourGhz),
+            Some(WifiBand::TwoPointFourGhz) => Some(WifiBand::FiveGhz),
+            Some(WifiBand::FiveGhz | WifiBand::SixGhz) => None,
+        };
+        Some((profile.clone(), band))
+    }
+
+    /// Keeps the dialog open with the outcome, updating the listed band
+    /// lock of the selected profile on success.
+    pub fn finish_profile_band(
+        &mut self,
+        band: Option<WifiBand>,
+        result: Result<(), String>,
+    ) {
+        let Some(known) = self.known_networks.as_mut() else {
+            return;
+        };
+        known.changing_band = false;
+        let selected = known.selected;
+        let Some(Ok(profiles)) = known.profiles.as_mut() else {
+            return;
+        };
+        let Some(profile) = profiles.get_mut(selected) else {
+            return;
+        };
+        known.result = Some(match result {
+            Ok(()) => {
+                profile.band = band;
+                Ok(match band {
+                    Some(band) => {
+                        format!("{} is locked to {}", profile.id, band.label())
+                    }
+                    None => format!("{} may use any band", profile.id),
+                })
+            }
+            Err(error) => Err(format!("Band change failed: {error}")),
+        });
+    }
+
+    /// Keeps the dialog open with the outcome, updating the listed scope of
+    /// the selected profile on success.
+    pub fn finish_profile_scope(
+        &mut self,
+        owner: Option<String>,
+        result: Result<(), String>,
+    ) {
+        let Some(known) = self.known_networks.as_mut() else {
+            return;
+        };
+        known.converting = false;
+        let selected = known.selected;
+        let Some(Ok(profiles)) = known.profiles.as_mut() else {
+            return;
+        };
+        let Some(profile) = profiles.get_mut(selected) else {
+            return;
+        };
+        known.result = Some(match result {
+            Ok(()) => {
+                let message = match &owner {
+                    Some(owner) => {
+                        format!("{} is now only for {owner}", profile.id)
+                    }
+                    None => {
+                        format!("{} is now available to all users", profile.id)
+                    }
+                };
+                profile.users = owner.into_iter().collect();
+                Ok(message)
+            }
+            Err(error) => Err(format!("Scope change failed: {error}")),
+        });
+    }
+
+    pub fn begin_profile_export(&mut self) {
+        self.profile_export = Some(ProfileExport {
+            pattern: "*".to_string(),
+            directory: DEFAULT_EXPORT_DIRECTORY.to_string(),
+            include_secrets: false,
+            field: ExportField::Pattern,
+            profiles: None,
+            exporting: false,
+            result: None,
+        });
+        self.state = AppState::ExportProfiles;
+    }
+
+    pub fn close_profile_export(&mut self) {
+        self.profile_export = None;
+        self.state = AppState::NetworkList;
+    }
+
+    pub fn add_char_to_profile_export(&mut self, c: char) {
+        if let Some(export) = self.profile_export.as_mut() {
+            export.focused_input().push(c);
+            export.result = None;
+        }
+    }
+
+    pub fn remove_char_from_profile_export(&mut self) {
+        if let Some(export) = self.profile_export.as_mut() {
+            export.focused_input().pop();
+            export.result = None;
+        }
+    }
+
+    pub fn toggle_profile_export_field(&mut self) {
+        if let Some(export) = self.profile_export.as_mut() {
+            export.field = match export.field {
+                ExportField::Pattern => ExportField::Directory,
+                ExportField::Directory => ExportField::Pattern,
+            };
+        }
+    }
+
+    pub fn toggle_profile_export_secrets(&mut self) {
+        if let Some(export) = self.profile_export.as_mut() {
+            export.include_secrets = !export.include_secrets;
+            export.result = None;
+        }
+    }
+
+    pub fn confirm_profile_export(&mut self) {
+        if let Some(export) = self.profile_export.as_mut()
+            && !export.matches().is_empty()
+            && !export.directory.trim().is_empty()
+        {
+            export.exporting = true;
+            export.result = None;
+        }
+    }
+
+    /// The matching profiles, target directory and secrets choice of an
+    /// export that has not been sent yet.
+    pub fn pending_profile_export(
+        &self,
+    ) -> Option<(Vec<SavedProfile>, PathBuf, bool)> {
+        self.profile_export
+            .as_ref()
+            .filter(|export| {
+                self.state == AppState::ExportProfiles && export.exporting
+            })
+            .map(|export| {
+                (
+                    export.matches().into_iter().cloned().collect(),
+                    expand_export_path(export.directory.trim()),
+                    export.include_secrets,
+                )
+            })
+    }
+
+    /// Keeps the dialog open with the outcome so another set of profiles
+    /// can be exported.
+    pub fn finish_profile_export(&mut self, result: Result<usize, String>) {
+        if let Some(export) = self.profile_export.as_mut() {
+            export.exporting = false;
+            export.result = Some(
+                result
+                    .map(|count| {
+                        format!(
+                            "Exported {count} profile(s) to {}",
+                            export.directory.trim()
+                        )
+                    })
+                    .map_err(|error| format!("Export failed: {error}")),
+            );
+        }
+    }
+
+    /// Moves from editing to confirmation, and from confirmation to
+    /// forgetting the previewed profiles.
+    pub fn advance_bulk_forget(&mut self) {
+        let Some(forget) = self.bulk_forget.as_mut() else {
+            return;
+        };
+
+        forget.stage = match forget.stage {
+            ForgetStage::Editing if !forget.matches().is_empty() => {
+                ForgetStage::Confirming
+            }
+            ForgetStage::Confirming => ForgetStage::Forgetting,
+            stage => stage,
+        };
+    }
+
+    pub fn cancel_bulk_forget_confirmation(&mut self) {
+        if let Some(forget) = self.bulk_forget.as_mut()
+            && forget.stage == ForgetStage::Confirming
+        {
+            forget.stage = ForgetStage::Editing;
+        }
+    }
+
+    /// Profiles confirmed for removal, by pattern or by the cleanup, that
+    /// have not been sent yet.
+    pub fn pending_forget(&self) -> Option<Vec<SavedProfile>> {
+        match self.state {
+            AppState::ForgetProfiles => self
+                .bulk_forget
+                .as_ref()
+                .filter(|forget| forget.stage == ForgetStage::Forgetting)
+                .map(|forget| forget.matches().into_iter().cloned().collect()),
+            AppState::CleanupProfiles => self
+                .profile_cleanup
+                .as_ref()
+                .filter(|cleanup| cleanup.stage == ForgetStage::Forgetting)
+                .map(|cleanup| {
+                    cleanup.picked_profiles().into_iter().cloned().collect()
+                }),
+            _ => None,
+        }
+    }
+
+    pub fn finish_bulk_forget(&mut self, result: Result<ForgetReport, String>) {
+        self.bulk_forget = None;
+        self.profile_cleanup = None;
+        match result {
+            Ok(ForgetReport {
+                forgotten,
+                error: None,
+                ..
+            }) => {
+                self.start_scan();
+                self.status_message = format!(
+                    "Forgot {forgotten} saved network(s). Rescanning..."
+                );
+            }
+            Ok(ForgetReport {
+                forgotten,
+                requested,
+                error: Some(error),
+            }) => {
+                self.start_scan();
+                self.status_message = format!(
+                    "Forgot {forgotten} of {requested} saved networks \
+                     ({error}). Rescanning..."
+                );
+            }
+            Err(error) => {
+                self.state = AppState::NetworkList;
+                self.status_message = format!("Forget failed: {error}");
+            }
+        }
+    }
+
+    pub fn add_char_to_password(&mut self, c: char) {
+        if let Some(input) = self.focused_password_field_input() {
+            input.push(c);
+        }
+        self.edited_password_field();
+    }
+
+    /// Adds pasted text to the focused field, leaving out line breaks (a
+    /// copied line ends with one) and other control characters.
+    pub fn paste_into_password(&mut self, text: &str) {
+        if let Some(input) = self.focused_password_field_input() {
+            input.extend(text.chars().filter(|c| !c.is_control()));
+        }
+        self.edited_password_field();
+    }
+
     pub fn remove_char_from_password(&mut self) {
-        self.password_input.pop();
+        if let Some(input) = self.focused_password_field_input() {
+            input.pop();
+        }
+        self.edited_password_field();
+    }
+
+    fn edited_password_field(&mut self) {
+        if self.password_field == PasswordField::Password
+            && self.password_prefill == PasswordPrefill::Filled
+        {
+            self.password_prefill = PasswordPrefill::None;
+        }
+    }
+
+    pub fn toggle_password_field(&mut self) {
+        self.password_field = match self.password_field {
+            PasswordField::Password => PasswordField::ProfileName,
+            PasswordField::ProfileName => PasswordField::ProxyMode,
+            PasswordField::ProxyMode
+                if self.proxy_mode_input != ProxyMode::None =>
+            {
+                PasswordField::ProxyAddress
+            }
+            PasswordField::ProxyMode | PasswordField::ProxyAddress => {
+                PasswordField::Pmf
+            }
+            PasswordField::Pmf => PasswordField::Visibility,
+            PasswordField::Visibility => PasswordField::SecretStorage,
+            PasswordField::SecretStorage => PasswordField::Password,
+        };
+    }
+
+    /// Steps through the choices of the focused proxy, PMF, visibility or
+    /// secret storage field.
+    pub fn cycle_password_choice(&mut self, backwards: bool) {
+        match self.password_field {
+            PasswordField::ProxyMode => {
+                self.proxy_mode_input = self.proxy_mode_input.cycle(backwards);
+                self.proxy_input.clear();
+            }
+            PasswordField::Pmf => {
+                self.pmf_input = self.pmf_input.cycle(backwards)
+            }
+            PasswordField::Visibility if self.user_name.is_some() => {
+                self.private_profile = !self.private_profile;
+            }
+            PasswordField::SecretStorage => {
+                self.keyring_secrets = !self.keyring_secrets;
+            }
+            _ => {}
+        }
+    }
+
+    pub fn cancel_password_input(&mut self) {
+        self.state = AppState::NetworkList;
+        self.password_input.clear();
+        self.password_visible = false;
+        self.profile_name_input.clear();
+        self.proxy_mode_input = ProxyMode::None;
+        self.proxy_input.clear();
+        self.pmf_input = Pmf::Default;
+        self.reset_profile_scope();
+        self.password_field = PasswordField::Password;
+        self.password_rejected = false;
+        self.password_prefill = PasswordPrefill::None;
+        self.hidden_network = false;
+    }
+
+    /// Puts the "available to" and "password stored in" choices back to
+    /// their configured defaults.
+    pub fn reset_profile_scope(&mut self) {
+        self.private_profile = self.user_scoped_profiles;
+        self.keyring_secrets = self.user_scoped_profiles;
+    }
+
+    pub fn connection_options(&self) -> ConnectionOptions {
+        let optional = |input: &str| {
+            let input = input.trim();
+            (!input.is_empty()).then(|| input.to_string())
+        };
+        let proxy = match (self.proxy_mode_input, optional(&self.proxy_input)) {
+            (ProxyMode::None, _) => ProxyConfig::None,
+            (ProxyMode::Auto, None) => ProxyConfig::Wpad,
+            (ProxyMode::Auto, Some(url)) => ProxyConfig::Pac(url),
+            (ProxyMode::Manual, address) => {
+                ProxyConfig::Manual(address.unwrap_or_default())
+            }
+        };
+        ConnectionOptions {
+            profile_name: optional(&self.profile_name_input),
+            proxy,
+            pmf: self.pmf_input,
+            owner: self.user_name.clone().filter(|_| self.private_profile),
+            hidden: self.hidden_network,
+            agent_owned_secrets: self.keyring_secrets,
+        }
+    }
+
+    pub fn confirm_password(&mut self) {
+        let proxy_check = match self.connection_options().proxy {
+            ProxyConfig::Pac(url) => validate_pac_url(&url),
+            ProxyConfig::Manual(address) => validate_proxy_address(&address),
+            ProxyConfig::None | ProxyConfig::Wpad => Ok(()),
+        };
+        if let Err(error) = proxy_check {
+            self.status_message = error;
+            self.password_field = PasswordField::ProxyAddress;
+            return;
+        }
+        if let Some(network) = self.selected_network.clone() {
+            // A retry after a rejected password still targets the hidden
+            // network it was imported as.
+            let hidden = self.hidden_network;
+            self.password_rejected = false;
+            self.begin_operation(network, OperationKind::Connect);
+            self.hidden_network = hidden;
+        }
+    }
+
+    /// Reopens the password prompt for the network that just rejected its
+    /// password, keeping the other choices made in it. The profile created
+    /// for the attempt was already deactivated and deleted again.
+    pub fn reject_password(&mut self, error: String) {
+        let Some(ssid) = self
+            .selected_network
+            .as_ref()
+            .filter(|network| network.is_secured())
+            .map(|network| network.ssid.clone())
+        else {
+            self.finish_operation(false, Some(error));
+            return;
+        };
+
+        self.state = AppState::PasswordInput;
+        self.password_input.clear();
+        self.password_field = PasswordField::Password;
+        self.password_rejected = true;
+        self.password_prefill = PasswordPrefill::None;
+        self.connection_start_time = None;
+        self.status_message = format!("Incorrect password for {ssid}");
+        self.connect_errors.insert(ssid, error);
+    }
+
+    /// Schedules another attempt when the failure is transient and retries
+    /// are left, with the wait doubling each time; otherwise the connect
+    /// fails as usual.
+    pub fn fail_connection(&mut self, failure: FailureKind, error: String) {
+        let attempt = self.connect_retry.map_or(1, |retry| retry.attempt);
+        if !failure.is_transient()
+            || self.operation != OperationKind::Connect
+            || attempt > self.connect_retries
+        {
+            self.finish_operation(false, Some(error));
+            return;
+        }
+
+        let backoff = CONNECT_RETRY_BACKOFF * 2u32.pow(attempt - 1);
+        self.connect_retry = Some(ConnectRetry {
+            attempt: attempt + 1,
+            failure,
+            due: Instant::now() + backoff,
+        });
+        self.status_message =
+            format!("{}, retrying in {}s", failure.label(), backoff.as_secs());
+    }
+
+    /// Whether the connect may start, i.e. no retry is still backing off.
+    pub fn connect_attempt_due(&self) -> bool {
+        self.connect_retry
+            .is_none_or(|retry| Instant::now() >= retry.due)
+    }
+
+    /// `(attempt, total)` while a connect is being retried.
+    pub fn connect_attempt(&self) -> Option<(u32, u32)> {
+        self.connect_retry
+            .map(|retry| (retry.attempt, self.connect_retries + 1))
+    }
+
+    pub fn quit(&mut self) {
+        self.should_quit = true;
+    }
+
+    pub fn finish_operation(&mut self, succeeded: bool, error: Option<String>) {
+        self.connection_success = succeeded;
+        self.connection_error = error;
+        // A reconnect or lease renewal keeps the same profile, so the guest
+        // session and connection history stay as they were.
+        if succeeded
+            && matches!(
+                self.operation,
+                OperationKind::Connect | OperationKind::Disconnect
+            )
+        {
+            self.update_guest_session();
+            self.record_connection();
+            self.reset_health();
+        }
+        if succeeded {
+            self.restart_connection_uptime();
+        }
+        self.remember_connect_error();
+        if succeeded && self.operation == OperationKind::Disconnect {
+            self.captive_portal = None;
+        }
+        self.connectivity = (succeeded
+            && matches!(
+                self.operation,
+                OperationKind::Connect | OperationKind::Reconnect
+            ))
+        .then_some(ConnectivityCheck::Checking);
+        self.status_message = match (self.operation, succeeded) {
+            (OperationKind::Connect, true) => "Connected successfully!",
+            (OperationKind::Connect, false) => "Connection failed",
+            (OperationKind::Disconnect, true) => "Disconnected successfully!",
+            (OperationKind::Disconnect, false) => "Disconnection failed",
+            (OperationKind::Reconnect, true) => "Reconnected successfully!",
+            (OperationKind::Reconnect, false) => "Reconnection failed",
+            (OperationKind::RenewLease, true) => "DHCP lease renewed!",
+            (OperationKind::RenewLease, false) => "DHCP lease renewal failed",
+        }
+        .to_string();
+        self.notify_operation_result();
+        self.state = AppState::ConnectionResult;
+    }
+
+    /// Whether the result modal is still waiting on a connectivity check.
+    pub fn pending_connectivity_check(&self) -> bool {
+        self.state == AppState::ConnectionResult
+            && self.connectivity == Some(ConnectivityCheck::Checking)
+    }
+
+    pub fn apply_connectivity(
+        &mut self,
+        result: Result<ConnectivityReport, String>,
+    ) {
+        if self.connectivity.is_none() {
+            return;
+        }
+        self.connectivity = Some(match result {
+            Ok(report) => {
+                self.captive_portal = report.portal_url;
+                if self.captive_portal.is_some() {
+                    self.status_message =
+                        "Captive portal detected. Press o to sign in."
+                            .to_string();
+                }
+                ConnectivityCheck::Checked(report.connectivity)
+            }
+            Err(error) => ConnectivityCheck::Failed(error),
+        });
+    }
+
+    /// NM only reports global connectivity once the portal lets traffic
+    /// through, which retires the sign-in banner.
+    pub fn update_manager_status(&mut self, status: Option<ManagerStatus>) {
+        let Some(status) = status else {
+            return;
+        };
+        if status.state == ManagerState::ConnectedGlobal {
+            self.captive_portal = None;
+        }
+        self.manager_status = Some(status);
+    }
+
+    pub fn open_captive_portal(&mut self) {
+        if self.captive_portal.is_some() {
+            self.opening_portal = true;
+        } else {
+            self.status_message = "No captive portal detected".to_string();
+        }
+    }
+
+    pub fn pending_portal_open(&self) -> Option<&str> {
+        self.captive_portal
+            .as_deref()
+            .filter(|_| self.opening_portal)
+    }
+
+    /// Without a browser the URL is left in the status bar to open by hand.
+    pub fn finish_portal_open(&mut self, result: Result<(), String>) {
+        self.opening_portal = false;
+        let Some(url) = &self.captive_portal else {
+            return;
+        };
+        self.status_message = match result {
+            Ok(()) => format!("Opened {url} in the browser"),
+            Err(_) => format!("Open {url} in a browser to sign in"),
+        };
+    }
+
+    fn notify_operation_result(&mut self) {
+        let event =
+            match (self.is_disconnect_operation, self.connection_success) {
+                (true, true) => NotificationEvent::Disconnected,
+                (true, false) => NotificationEvent::DisconnectFailed,
+                (false, true) => NotificationEvent::Connected,
+                (false, false) => NotificationEvent::ConnectionFailed,
+            };
+        let ssid = self
+            .selected_network
+            .as_ref()
+            .map(|network| network.ssid.as_str())
+            .unwrap_or("Wi-Fi");
+        let body = match &self.connection_error {
+            Some(error) => format!("{ssid}: {error}"),
+            None => ssid.to_string(),
+        };
+        let summary = self.status_message.trim_end_matches('!').to_string();
+        self.notifier.notify(event, summary, body);
+    }
+
+    fn record_connection(&mut self) {
+        if self.is_disconnect_operation || self.guest_connection {
+            return;
+        }
+
+        if let Some(network) = &self.selected_network {
+            let entry = HistoryEntry::now(
+                &network.ssid,
+                &self.networks,
+                self.adapter_name.as_deref(),
+            );
+            self.history.record(entry);
+        }
+    }
+
+    /// The network usually joined around here, unless already connected.
+    pub fn suggested_network(&self) -> Option<&WifiNetwork> {
+        self.history.suggest(&self.networks).filter(|network| {
+            !network.connected
+                && !self.stale_networks.contains_key(&network.ssid)
+                && !self.ignored.contains(&network.ssid)
+        })
+    }
+
+    pub fn connect_suggested_network(&mut self) {
+        let Some(ssid) =
+            self.suggested_network().map(|network| network.ssid.clone())
+        else {
+            return;
+        };
+
+        if !self
+            .visible_networks()
+            .iter()
+            .any(|network| network.ssid == ssid)
+        {
+            self.filters = NetworkFilters::default();
+            self.list_changed();
+        }
+        if let Some(index) = self
+            .visible_networks()
+            .iter()
+            .position(|network| network.ssid == ssid)
+        {
+            self.set_selected_index(index);
+            self.activate_selected_network();
+        }
+    }
+
+    fn update_guest_session(&mut self) {
+        let Some(network) = self.selected_network.as_ref() else {
+            return;
+        };
+
+        let same_network = self
+            .guest_session
+            .as_ref()
+            .is_some_and(|session| session.ssid == network.ssid);
+        if self.is_disconnect_operation {
+            if same_network {
+                self.end_guest_session();
+            }
+        } else if self.guest_connection {
+            // A guest connect to the same network reuses the profile id, so
+            // that profile must outlive the session it replaces.
+            let ssid = network.ssid.clone();
+            if !same_network {
+                self.end_guest_session();
+            }
+            self.guest_session = Some(GuestSession {
+                ssid,
+                started: Instant::now(),
+            });
+        } else {
+            self.end_guest_session();
+        }
+    }
+
+    /// Ends the guest session, leaving its profile to be deleted.
+    pub fn end_guest_session(&mut self) {
+        if let Some(session) = self.guest_session.take() {
+            self.ended_guest_session = Some(session.ssid);
+        }
+    }
+
+    /// The SSID of an ended guest session whose profile is not deleted yet.
+    pub fn pending_guest_cleanup(&self) -> Option<&str> {
+        self.ended_guest_session.as_deref()
+    }
+
+    pub fn finish_guest_cleanup(&mut self, result: Result<(), String>) {
+        let Some(ssid) = self.ended_guest_session.take() else {
+            return;
+        };
+        if let Err(error) = result {
+            self.notifier.toasts().push(
+                ToastLevel::Warning,
+                format!("Could not delete the guest profile for {ssid}"),
+                error,
+            );
+        }
+    }
+
+    fn reset_link_meters(&mut self) {
+        self.traffic.reset();
+        if let Some(latency) = self.latency.as_mut() {
+            latency.reset();
+        }
+    }
+
+    /// Ends the outage the health monitor follows once the connection
+    /// changed. A reconnect keeps it, so a reconnect that did not help
+    /// still escalates to roaming.
+    fn reset_health(&mut self) {
+        if let Some(health) = self.health.as_mut() {
+            health.reset();
+        }
+    }
+
+    /// Keeps the error of a failed connect against its SSID, and forgets it
+    /// once a connect succeeds.
+    fn remember_connect_error(&mut self) {
+        if !matches!(
+            self.operation,
+            OperationKind::Connect | OperationKind::Reconnect
+        ) {
+            return;
+        }
+        let Some(ssid) =
+            self.selected_network.as_ref().map(|network| &network.ssid)
+        else {
+            return;
+        };
+
+        match (&self.connection_error, self.connection_success) {
+            (_, true) => {
+                self.connect_errors.remove(ssid);
+            }
+            (Some(error), false) => {
+                self.connect_errors.insert(ssid.clone(), error.clone());
+            }
+            (None, false) => {}
+        }
+    }
+
+    fn restart_connection_uptime(&mut self) {
+        self.reset_link_meters();
+        let uptime = match (&self.selected_network, self.operation) {
+            (_, OperationKind::Disconnect) | (None, _) => None,
+            (Some(network), _) => Some(ConnectionUptime {
+                ssid: network.ssid.clone(),
+                since: Instant::now(),
+                observed: false,
+            }),
+        };
+        self.set_connection_uptime(uptime);
+    }
+
+    /// Follows the connected network across scans, starting an observed
+    /// uptime for a connection made outside the app. Connections found by
+    /// a later scan are logged; the one the first scan finds was made
+    /// before the app started.
+    pub fn track_connection_uptime(&mut self) {
+        let connected =
+            self.connected_network().map(|network| network.ssid.clone());
+        let tracked =
+            self.connection_uptime.as_ref().map(|uptime| &uptime.ssid);
+        if connected.as_ref() == tracked {
+            return;
+        }
+
+        // NetworkManager's reason tells a dropped link from one ended on
+        // purpose, here or elsewhere.
+        if let Some(previous) = tracked
+            && let Some(reason) = self
+                .manager_status
+                .as_ref()
+                .and_then(|status| status.wifi_state_reason)
+                .filter(|reason| reason.is_unexpected())
+        {
+            let body = format!("{previous}: {reason}");
+            self.notifier.notify(
+                NotificationEvent::Disconnected,
+                "Connection dropped",
+                body,
+            );
+        }
+
+        if let Some(ssid) = &connected
+            && self.last_scan_time.is_some()
+        {
+            let entry = HistoryEntry::now(
+                ssid,
+                &self.networks,
+                self.adapter_name.as_deref(),
+            );
+            self.history.record(entry);
+        }
+        self.set_connection_uptime(connected.map(|ssid| ConnectionUptime {
+            ssid,
+            since: Instant::now(),
+            observed: true,
+        }));
+        self.reset_link_meters();
+        self.reset_health();
+    }
+
+    /// Replaces the tracked connection, logging the end of the previous one
+    /// unless it continues on the same network.
+    fn set_connection_uptime(&mut self, uptime: Option<ConnectionUptime>) {
+        if let Some(previous) = self.connection_uptime.take()
+            && uptime
+                .as_ref()
+                .is_none_or(|uptime| uptime.ssid != previous.ssid)
+        {
+            self.history.record(HistoryEntry::disconnected_now(
+                &previous.ssid,
+                previous.since.elapsed(),
+                self.adapter_name.as_deref(),
+            ));
+        }
+        self.connection_uptime = uptime;
+    }
+
+    /// How long `network` has been connected, if it is the active network.
+    pub fn uptime_of(
+        &self,
+        network: &WifiNetwork,
+    ) -> Option<&ConnectionUptime> {
+        self.connection_uptime
+            .as_ref()
+            .filter(|uptime| network.connected && uptime.ssid == network.ssid)
+    }
+
+    pub fn back_to_network_list(&mut self) {
+        self.state = AppState::NetworkList;
+        self.guest_connection = false;
+        self.hidden_network = false;
+        self.connection_success = false;
+        self.connection_error = None;
+        self.password_input.clear();
+        self.password_visible = false;
+        self.profile_name_input.clear();
+        self.proxy_mode_input = ProxyMode::None;
+        self.proxy_input.clear();
+        self.pmf_input = Pmf::Default;
+        self.reset_profile_scope();
+        self.password_field = PasswordField::Password;
+        self.password_rejected = false;
+        self.password_prefill = PasswordPrefill::None;
+        self.is_disconnect_operation = false;
+        self.operation = OperationKind::Connect;
+        self.renewed_lease = None;
+        self.connectivity = None;
+        self.connection_start_time = None;
+        self.connect_retry = None;
+        self.unattended_connect = false;
+    }
+
+    pub fn start_scan(&mut self) {
+        self.state = AppState::Scanning;
+        self.status_message = "Scanning for networks...".to_string();
+        self.networks.clear();
+        self.list_changed();
+        self.network_count = 0;
+        self.last_scan_time = None;
+        self.set_selected_index(0);
+    }
+
+    pub fn handle_scan_error(&mut self, error: impl std::fmt::Display) {
+        self.state = AppState::NetworkList;
+        self.network_count = self.networks.len();
+        self.last_scan_time = None;
+        self.status_message =
+            format!("Scan failed: {}. Press r to retry.", error);
+        self.notifier.notify(
+            NotificationEvent::ScanFailed,
+            "Scan failed",
+            error.to_string(),
+        );
+    }
+
+    pub fn begin_backend_activity(&mut self, operation: &'static str) {
+        self.backend_activity = Some(BackendActivity {
+            operation,
+            started: Instant::now(),
+        });
+    }
+
+    pub fn finish_backend_activity(&mut self) {
+        self.backend_activity = None;
+    }
+
+    /// Whether something on screen moves on its own: the busy spinner, the
+    /// scanning and connecting screens, a retry countdown or a screen flash.
+    pub fn animating(&self) -> bool {
+        self.backend_activity.is_some()
+            || self.notifier.flash().is_active(Instant::now())
+            || matches!(
+                self.state,
+                AppState::Scanning
+                    | AppState::Connecting
+                    | AppState::Disconnecting
+            )
+    }
+
+    /// The running backend request, once it has taken long enough to be
+    /// worth showing.
+    pub fn visible_backend_activity(&self) -> Option<&BackendActivity> {
+        self.backend_activity
+            .as_ref()
+            .filter(|activity| activity.is_visible(Instant::now()))
+    }
+
+    pub fn schedule_rescan(&mut self) {
+        self.next_rescan_at = self
+            .rescan_interval
+            .map(|interval| Instant::now() + interval);
+    }
+
+    /// Background rescans only run while the user is looking at the list.
+    pub fn rescan_due(&self) -> bool {
+        self.state == AppState::NetworkList
+            && self.next_rescan_at.is_some_and(|at| at <= Instant::now())
+    }
+
+    /// Whether the scan about to start asks the adapter to scan, which it
+    /// does in active mode unless it did so less than `scan_min_interval`
+    /// ago; otherwise the scan reads the results already there.
+    pub fn take_fresh_scan(&mut self) -> bool {
+        let now = Instant::now();
+        let fresh = self.scan_mode == ScanMode::Active
+            && self.last_fresh_scan.is_none_or(|at| {
+                now.saturating_duration_since(at) >= self.scan_min_interval
+            });
+        if fresh {
+            self.last_fresh_scan = Some(now);
+        }
+        fresh
+    }
+
+    pub fn rescan_countdown(&self) -> Option<Duration> {
+        self.next_rescan_at
+            .map(|at| at.saturating_duration_since(Instant::now()))
+    }
+
+    /// Site surveys sample much faster than the list refreshes its bars.
+    pub fn schedule_signal_refresh(&mut self) {
+        let interval = if self.state == AppState::SiteSurvey {
+            SURVEY_SAMPLE_INTERVAL
+        } else {
+            SIGNAL_REFRESH_INTERVAL
+        };
+        self.next_signal_refresh_at = Some(Instant::now() + interval);
+    }
+
+    pub fn signal_refresh_due(&self) -> bool {
+        matches!(self.state, AppState::NetworkList | AppState::SiteSurvey)
+            && self
+                .next_signal_refresh_at
+                .is_some_and(|at| at <= Instant::now())
+    }
+
+    /// The interface to sample traffic counters from, once a second while
+    /// a network is connected.
+    pub fn pending_traffic_interface(&self) -> Option<&str> {
+        self.connected_network()?;
+        if self
+            .next_traffic_refresh_at
+            .is_some_and(|at| at > Instant::now())
+        {
+            return None;
+        }
+        self.adapter_name.as_deref()
+    }
+
+    /// A failed read drops the rates rather than showing stale numbers.
+    pub fn apply_traffic_sample(
+        &mut self,
+        result: Result<TrafficCounters, String>,
+    ) {
+        match result {
+            Ok(counters) => self.traffic.sample(counters, Instant::now()),
+            Err(_) => self.traffic.reset(),
+        }
+        self.next_traffic_refresh_at =
+            Some(Instant::now() + TRAFFIC_REFRESH_INTERVAL);
+    }
+
+    /// The host to ping next, every couple of seconds while connected and
+    /// the latency monitor is enabled.
+    pub fn pending_latency_host(&self) -> Option<&str> {
+        self.connected_network()?;
+        if self
+            .next_latency_refresh_at
+            .is_some_and(|at| at > Instant::now())
+        {
+            return None;
+        }
+        self.latency.as_ref().map(|latency| latency.host.as_str())
+    }
+
+    pub fn apply_latency_sample(
+        &mut self,
+        result: Result<LatencySample, String>,
+    ) {
+        let gateway = result.as_ref().ok().and_then(|sample| sample.gateway);
+        if let Some(latency) = self.latency.as_mut() {
+            match result {
+                Ok(sample) => latency.record(sample),
+                Err(_) => latency.reset(),
+            }
+        }
+        self.next_latency_refresh_at =
+            Some(Instant::now() + LATENCY_REFRESH_INTERVAL);
+        if let Some(probe) = gateway {
+            self.check_health(probe);
+        }
+    }
+
+    /// Feeds a gateway ping to the health monitor and, once too many were
+    /// lost in a row, reconnects or switches to another known network. The
+    /// recovery waits while a dialog is open, so it never pulls one out
+    /// from under the user.
+    fn check_health(&mut self, probe: Probe) {
+        let Some(ssid) =
+            self.connected_network().map(|network| network.ssid.clone())
+        else {
+            return;
+        };
+        let Some(health) = self.health.as_mut() else {
+            return;
+        };
+        health.record(probe);
+        if self.state != AppState::NetworkList {
+            return;
+        }
+        let Some(recovery) = health.take_recovery(&ssid) else {
+            return;
+        };
+
+        self.history.record(HistoryEntry::gateway_lost_now(
+            &ssid,
+            self.adapter_name.as_deref(),
+        ));
+        let target = match recovery {
+            Recovery::Reconnect => None,
+            Recovery::Roam => self.roaming_candidate(&ssid),
+        };
+        let Some(target) = target else {
+            self.reconnect_current_network();
+            self.status_message =
+                format!("{ssid}'s gateway stopped answering, reconnecting...");
+            return;
+        };
+
+        self.filters = NetworkFilters::default();
+        self.list_changed();
+        if let Some(index) = self
+            .visible_networks()
+            .iter()
+            .position(|network| network.ssid == target)
+        {
+            self.set_selected_index(index);
+            self.activate_selected_network();
+            self.unattended_connect = self.state == AppState::PasswordInput;
+            self.status_message = format!(
+                "{ssid}'s gateway stopped answering, switching to {target}..."
+            );
+        }
+    }
+
+    /// The strongest other saved network in range to move to.
+    fn roaming_candidate(&self, current: &str) -> Option<String> {
+        self.networks
+            .iter()
+            .filter(|network| {
+                network.saved
+                    && network.ssid != current
+                    && !self.ignored.contains(&network.ssid)
+                    && self.stale_for(network).is_none()
+            })
+            .max_by_key(|network| network.signal_strength)
+            .map(|network| network.ssid.clone())
+    }
+
+    pub fn bitrate_refresh_due(&self) -> bool {
+        self.state == AppState::NetworkDetails
+            && self
+                .selected_network_in_list()
+                .is_some_and(|network| network.connected)
+            && self
+                .next_bitrate_refresh_at
+                .is_none_or(|at| at <= Instant::now())
+    }
+
+    pub fn apply_link_bitrate(&mut self, result: Result<Option<u32>, String>) {
+        self.link_bitrate = result.ok().flatten();
+        self.next_bitrate_refresh_at =
+            Some(Instant::now() + BITRATE_REFRESH_INTERVAL);
+    }
+
+    /// Updates signal bars in place from fresh AP readings. The list order is
+    /// left alone so rows do not jump around between scans.
+    pub fn apply_signal_updates(&mut self, signals: &[WifiAccessPoint]) {
+        let signals = signals
+            .iter()
+            .map(|signal| (signal.bssid.as_str(), signal))
+            .collect::<HashMap<_, _>>();
+
+        for network in &mut self.networks {
+            for access_point in &mut network.access_points {
+                if let Some(signal) = signals.get(access_point.bssid.as_str()) {
+                    access_point.signal_strength = signal.signal_strength;
+                    access_point.associated = signal.associated;
+                }
+            }
+
+            if let Some(strength) = network
+                .access_points
+                .iter()
+                .filter(|access_point| {
+                    access_point.frequency == network.frequency
+                })
+                .map(|access_point| access_point.signal_strength)
+                .max()
+            {
+                network.signal_strength = strength;
+            }
+        }
+        self.list_changed();
+    }
+
+    /// Refreshes the `--status` cache with the connected network as the
+    /// list currently knows it.
+    pub fn publish_link_status(&self) {
+        if let Some(path) = &self.status_cache {
+            let status = self.connected_network().map(LinkStatus::of);
+            write_cache(path, status.as_ref(), SystemTime::now());
+        }
+    }
+
+    /// Replaces the scan results in place, keeping the highlighted network
+    /// selected so a background rescan does not move the cursor.
+    pub fn merge_rescanned_networks(&mut self, networks: Vec<WifiNetwork>) {
+        let selected_ssid = self
+            .selected_network_in_list()
+            .map(|network| network.ssid.clone());
+
+        self.replace_scanned_networks(networks, Instant::now());
+
+        let visible_networks = self.visible_networks();
+        let index = selected_ssid
+            .and_then(|ssid| {
+                visible_networks
+                    .iter()
+                    .position(|network| network.ssid == ssid)
+            })
+            .unwrap_or_else(|| {
+                self.selected_index
+                    .min(visible_networks.len().saturating_sub(1))
+            });
+        self.set_selected_index(index);
+    }
+
+    /// Takes the networks of a scan finished at `now`. Networks it missed
+    /// stay where they were, disconnected, until `STALE_NETWORK_GRACE`
+    /// after they were last seen. Networks that were not listed before are
+    /// announced in a toast, except on the first scan.
+    pub fn replace_scanned_networks(
+        &mut self,
+        mut networks: Vec<WifiNetwork>,
+        now: Instant,
+    ) {
+        let previous_scan = self.last_scan_time.unwrap_or(now);
+        let previous = std::mem::take(&mut self.networks);
+        let previous_ssids = previous
+            .iter()
+            .map(|network| network.ssid.as_str())
+            .collect::<HashSet<_>>();
+        let new_networks = networks
+            .iter()
+            .filter(|scanned| !previous_ssids.contains(scanned.ssid.as_str()))
+            .count();
+        if !previous.is_empty() && new_networks > 0 {
+            self.notifier.toasts().push(
+                ToastLevel::Info,
+                "Scan complete",
+                format!("{new_networks} new network(s) in range"),
+            );
+        }
+        let scanned_ssids = networks
+            .iter()
+            .map(|network| network.ssid.clone())
+            .collect::<HashSet<_>>();
+        let mut stale_networks = HashMap::new();
+        for (index, mut network) in previous.into_iter().enumerate() {
+            if scanned_ssids.contains(&network.ssid) {
+                continue;
+            }
+            let last_seen = self
+                .stale_networks
+                .get(&network.ssid)
+                .copied()
+                .unwrap_or(previous_scan);
+            if now.saturating_duration_since(last_seen) >= STALE_NETWORK_GRACE {
+                continue;
+            }
+
+            network.connected = false;
+            for access_point in &mut network.access_points {
+                access_point.associated = false;
+            }
+            stale_networks.insert(network.ssid.clone(), last_seen);
+            networks.insert(index.min(networks.len()), network);
+        }
+
+        self.network_count = networks.len() - stale_networks.len();
+        self.networks = networks;
+        self.list_changed();
+        self.stale_networks = stale_networks;
+        self.track_connection_uptime();
+        self.last_scan_time = Some(now);
+    }
+
+    /// How long ago `network` was last seen, when the last scan missed it.
+    pub fn stale_for(&self, network: &WifiNetwork) -> Option<Duration> {
+        self.stale_networks
+            .get(&network.ssid)
+            .map(|last_seen| last_seen.elapsed())
+    }
+
+    pub fn update_selection_after_rescan(&mut self) {
+        if let Some(selected_network) = &self.selected_network {
+            if let Some(new_index) = self
+                .visible_networks()
+                .iter()
+                .position(|n| n.ssid == selected_network.ssid)
+            {
+                self.set_selected_index(new_index);
+            } else {
+                self.set_selected_index(0);
+            }
+        }
+        self.selected_network = None;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::{Duration, Instant, SystemTime, UNIX_EPOCH};
+
+    use ratatui::style::Color;
+
+    use super::{
+        App,
+        AppState,
+        ConnectivityCheck,
+        ForgetStage,
+        NetworkFilter,
+        OperationKind,
+        PaletteCommand,
+        PasswordField,
+        PasswordPrefill,
+        ProfileCheck,
+        ProfileEdit,
+        QrImport,
+        SharePassword,
+        SortOrder,
+    };
+    use crate::{
+        health::HealthMonitor,
+        history::HistoryEvent,
+        latency::{LatencyMonitor, LatencySample, Probe},
+        network::{
+            AutoconnectBlock,
+            Connectivity,
+            ConnectivityReport,
+            DeviceKind,
+            DeviceLink,
+            DeviceProfile,
+            DeviceState,
+            DeviceStateReason,
+            DhcpLease,
+            FailureKind,
+            ForgetReport,
+            LinkSetting,
+            ManagerState,
+            ManagerStatus,
+            NetworkDevice,
+            P2pPeer,
+            Pmf,
+            ProfileSettings,
+            ProxyConfig,
+            SavedProfile,
+            ScanMode,
+        },
+        notify::ToastLevel,
+        speedtest::{ChunkTransfer, TransferDirection},
+        theme::{LATTE, ThemeName},
+        wifi::{WifiAccessPoint, WifiBand, WifiNetwork, WifiSecurity},
+    };
+
+    fn network(
+        ssid: &str,
+        security: WifiSecurity,
+        connected: bool,
+    ) -> WifiNetwork {
+        WifiNetwork {
+            ssid: ssid.to_string(),
+            raw_ssid: None,
+            signal_strength: 80,
+            security,
+            frequency: 5180,
+            connected,
+            saved: false,
+            max_bitrate: 0,
+            wpa_flags: 0,
+            rsn_flags: 0,
+            access_points: Vec::new(),
+        }
+    }
+
+    fn connected_network(ssid: &str) -> WifiNetwork {
+        network(ssid, WifiSecurity::WpaPsk, true)
+    }
+
+    #[test]
+    fn next_wraps_and_keeps_selection_state_in_sync() {
+        let mut app = App::new();
+        app.networks =
+            vec![connected_network("home"), connected_network("guest")];
+        app.selected_index = 1;
+
+        app.next();
+
+        assert_eq!(app.selected_index, 0);
+    }
+
+    #[test]
+    fn previous_wraps_and_keeps_selection_state_in_sync() {
+        let mut app = App::new();
+        app.networks =
+            vec![connected_network("home"), connected_network("guest")];
+        app.selected_index = 0;
+
+        app.previous();
+
+        assert_eq!(app.selected_index, 1);
+    }
+
+    #[test]
+    fn selecting_a_connected_network_starts_disconnect_timing() {
+        let mut app = App::new();
+        app.state = AppState::NetworkList;
+        app.networks = vec![connected_network("home")];
+
+        app.activate_selected_network();
+
+        assert!(matches!(app.state, AppState::Disconnecting));
+        assert!(app.connection_start_time.is_some());
+    }
+
+    #[test]
+    fn activate_selected_network_uses_current_selection_not_just_index_zero() {
+        let mut app = App::new();
+        app.state = AppState::NetworkList;
+        app.networks = vec![
+            network("cafe", WifiSecurity::Open, false),
+            network("office", WifiSecurity::WpaPsk, false),
+        ];
+        app.selected_index = 1;
+
+        app.activate_selected_network();
+
+        assert!(matches!(app.state, AppState::PasswordInput));
+        assert_eq!(
+            app.selected_network
+                .as_ref()
+                .map(|network| network.ssid.as_str()),
+            Some("office")
+        );
+    }
+
+    #[test]
+    fn starting_a_scan_clears_stale_scan_metadata() {
+        let mut app = App::new();
+        app.state = AppState::NetworkList;
+        app.networks = vec![connected_network("home")];
+        app.network_count = 3;
+        app.last_scan_time = Some(Instant::now());
+        app.selected_index = 0;
+
+        app.start_scan();
+
+        assert!(matches!(app.state, AppState::Scanning));
+        assert!(app.networks.is_empty());
+        assert_eq!(app.network_count, 0);
+        assert!(app.last_scan_time.is_none());
+        assert_eq!(app.selected_index, 0);
+    }
+
+    #[test]
+    fn scans_within_the_minimum_interval_read_the_last_results() {
+        let mut app = App::new();
+        app.scan_min_interval = Duration::from_secs(60);
+
+        assert!(app.take_fresh_scan());
+        assert!(!app.take_fresh_scan(), "too soon after the last request");
+
+        app.last_fresh_scan =
+            Instant::now().checked_sub(Duration::from_secs(61));
+        assert!(app.take_fresh_scan());
+
+        app.scan_mode = ScanMode::Passive;
+        app.last_fresh_scan = None;
+        assert!(!app.take_fresh_scan(), "passive mode never asks");
+    }
+
+    #[test]
+    fn spectrum_view_starts_on_the_connected_band_and_cycles() {
+        let mut app = App::new();
+        let mut home = connected_network("home");
+        home.frequency = 5180;
+        home.access_points = vec![WifiAccessPoint {
+            bssid: "aa:00".to_string(),
+            frequency: 5180,
+            signal_strength: 70,
+            associated: true,
+        }];
+        app.networks = vec![home];
+
+        app.open_spectrum_view();
+        assert!(matches!(app.state, AppState::Spectrum));
+        assert_eq!(app.spectrum_band, WifiBand::FiveGhz);
+        assert_eq!(app.spectrum()[0].access_points, 1);
+
+        app.cycle_spectrum_band(false);
+        assert_eq!(app.spectrum_band, WifiBand::SixGhz);
+        app.cycle_spectrum_band(false);
+        assert_eq!(app.spectrum_band, WifiBand::TwoPointFourGhz);
+        app.cycle_spectrum_band(true);
+        assert_eq!(app.spectrum_band, WifiBand::SixGhz);
+    }
+
+    #[test]
+    fn site_survey_samples_the_associated_access_point() {
+        let mut app = App::new();
+        let mut home = connected_network("home");
+        let access_point =
+            |bssid: &str, signal_strength, associated| WifiAccessPoint {
+                bssid: bssid.to_string(),
+                frequency: 2437,
+                signal_strength,
+                associated,
+            };
+        home.access_points = vec![
+            access_point("aa:01", 90, false),
+            access_point("aa:02", 55, true),
+        ];
+        app.networks = vec![home];
+
+        app.open_site_survey();
+        assert!(matches!(app.state, AppState::SiteSurvey));
+        assert!(app.signal_refresh_due());
+
+        app.record_survey_sample(&[access_point("AA:02", 61, true)]);
+        app.record_survey_sample(&[access_point("aa:01", 90, false)]);
+        let survey = app.site_survey.as_ref().expect("survey is open");
+        assert_eq!(survey.bssid, "aa:02");
+        assert_eq!(survey.channel, Some(6));
+        let signals = survey
+            .samples
+            .iter()
+            .map(|sample| sample.signal_strength)
+            .collect::<Vec<_>>();
+        assert_eq!(signals, [Some(55), Some(61), None]);
+
+        app.close_site_survey();
+        assert!(app.site_survey.is_none());
+        assert!(matches!(app.state, AppState::NetworkList));
+    }
+
+    #[test]
+    fn log_view_reads_the_journal_narrowed_to_the_connection() {
+        let mut app = App::new();
+        app.adapter_name = Some("wlan0".to_string());
+        app.networks = vec![connected_network("home")];
+
+        app.open_log_view();
+        assert!(matches!(app.state, AppState::NetworkManagerLog));
+        assert_eq!(
+            app.pending_log_read(),
+            Some(vec!["wlan0".to_string(), "home".to_string()])
+        );
+
+        app.apply_log_lines(Ok(vec![
+            "one".into(),
+            "two".into(),
+            "three".into(),
+        ]));
+        assert_eq!(app.pending_log_read(), None, "not due again yet");
+        app.scroll_log_view(true, 10);
+        assert_eq!(app.log_view.as_ref().map(|view| view.scroll), Some(2));
+
+        app.toggle_log_filter();
+        assert_eq!(app.log_view.as_ref().map(|view| view.scroll), Some(0));
+        assert_eq!(app.pending_log_read(), Some(Vec::new()));
+
+        app.close_log_view();
+        assert!(app.log_view.is_none());
+        assert_eq!(app.pending_log_read(), None);
+    }
+
+    #[test]
+    fn start_scan_resets_selection_fields_together() {
+        let mut app = App::new();
+        app.networks =
+            vec![connected_network("home"), connected_network("guest")];
+        app.selected_index = 1;
+
+        app.start_scan();
+
+        assert_eq!(app.selected_index, 0);
+    }
+
+    #[test]
+    fn update_selection_after_rescan_restores_matching_ssid() {
+        let mut app = App::new();
+        app.networks =
+            vec![connected_network("guest"), connected_network("home")];
+        app.selected_network = Some(connected_network("home"));
+
+        app.update_selection_after_rescan();
+
+        assert_eq!(app.selected_index, 1);
+        assert!(app.selected_network.is_none());
+    }
+
+    #[test]
+    fn update_selection_after_rescan_resets_to_first_when_selected_ssid_disappears()
+     {
+        let mut app = App::new();
+        app.selected_index = 1;
+        app.networks =
+            vec![connected_network("guest"), connected_network("cafe")];
+        app.selected_network = Some(connected_network("home"));
+
+        app.update_selection_after_rescan();
+
+        assert_eq!(app.selected_index, 0);
+        assert!(app.selected_network.is_none());
+    }
+
+    #[test]
+    fn guest_connect_starts_a_session_for_open_networks() {
+        let mut app = App::new();
+        app.state = AppState::NetworkList;
+        app.networks = vec![network("airport", WifiSecurity::Open, false)];
+
+        app.begin_guest_connection();
+
+        assert!(matches!(app.state, AppState::Connecting));
+        assert!(app.guest_connection);
+        assert_eq!(app.status_message, "Connecting to airport as guest...");
+
+        app.finish_operation(true, None);
+
+        assert_eq!(
+            app.guest_session
+                .as_ref()
+                .map(|session| session.ssid.as_str()),
+            Some("airport")
+        );
+
+        app.back_to_network_list();
+        app.begin_operation(
+            connected_network("airport"),
+            OperationKind::Disconnect,
+        );
+        app.finish_operation(true, None);
+
+        assert!(app.guest_session.is_none());
+        assert_eq!(app.pending_guest_cleanup(), Some("airport"));
+
+        app.finish_guest_cleanup(Ok(()));
+
+        assert_eq!(app.pending_guest_cleanup(), None);
+    }
+
+    #[test]
+    fn a_connect_elsewhere_ends_the_guest_session_but_a_guest_reconnect_does_not()
+     {
+        let mut app = App::new();
+        app.state = AppState::NetworkList;
+        app.networks = vec![
+            network("airport", WifiSecurity::Open, false),
+            network("lounge", WifiSecurity::Open, false),
+        ];
+        app.begin_guest_connection();
+        app.finish_operation(true, None);
+        app.back_to_network_list();
+
+        app.begin_guest_connection();
+        app.finish_operation(true, None);
+        app.back_to_network_list();
+
+        assert_eq!(app.pending_guest_cleanup(), None);
+
+        app.begin_operation(
+            network("lounge", WifiSecurity::Open, false),
+            OperationKind::Connect,
+        );
+        app.finish_operation(true, None);
+
+        assert!(app.guest_session.is_none());
+        assert_eq!(app.pending_guest_cleanup(), Some("airport"));
+    }
+
+    #[test]
+    fn networks_missing_from_a_rescan_linger_as_stale() {
+        let mut app = App::new();
+        let now = Instant::now();
+        app.replace_scanned_networks(
+            vec![
+                connected_network("home"),
+                network("cafe", WifiSecurity::Open, false),
+                network("library", WifiSecurity::Open, false),
+            ],
+            now,
+        );
+        assert_eq!(app.network_count, 3);
+
+        let later = now + Duration::from_secs(40);
+        app.replace_scanned_networks(
+            vec![
+                network("home", WifiSecurity::Open, false),
+                network("library", WifiSecurity::Open, false),
+            ],
+            later,
+        );
+        let ssids = app
+            .networks
+            .iter()
+            .map(|network| network.ssid.as_str())
+            .collect::<Vec<_>>();
+        assert_eq!(ssids, ["home", "cafe", "library"], "cafe keeps its row");
+        assert_eq!(app.network_count, 2);
+        assert_eq!(app.stale_networks.get("cafe"), Some(&now));
+        assert!(app.stale_for(&app.networks[1]).is_some());
+        assert!(app.stale_for(&app.networks[0]).is_none());
+
+        app.replace_scanned_networks(
+            vec![network("library", WifiSecurity::Open, false)],
+            now + Duration::from_secs(61),
+        );
+        let ssids = app
+            .networks
+            .iter()
+            .map(|network| network.ssid.as_str())
+            .collect::<Vec<_>>();
+        assert_eq!(ssids, ["home", "library"], "cafe was gone for a minute");
+        assert_eq!(app.stale_networks.get("home"), Some(&later));
+        assert!(!app.networks[0].connected);
+    }
+
+    #[test]
+    fn connection_uptime_follows_the_connected_network() {
+        let mut app = App::new();
+        app.networks = vec![connected_network("home")];
+        app.track_connection_uptime();
+
+        let uptime = app.connection_uptime.clone().expect("home is up");
+        assert_eq!(uptime.ssid, "home");
+        assert!(uptime.observed, "home was up before the app saw it");
+
+        app.merge_rescanned_networks(vec![connected_network("home")]);
+        assert_eq!(
+            app.connection_uptime.as_ref().map(|uptime| uptime.since),
+            Some(uptime.since)
+        );
+
+        app.begin_operation(
+            network("cafe", WifiSecurity::Open, false),
+            OperationKind::Connect,
+        );
+        app.finish_operation(true, None);
+        let uptime = app.connection_uptime.clone().expect("cafe is up");
+        assert_eq!(uptime.ssid, "cafe");
+        assert!(!uptime.observed);
+
+        app.merge_rescanned_networks(vec![network(
+            "cafe",
+            WifiSecurity::Open,
+            false,
+        )]);
+        assert!(app.connection_uptime.is_none());
+    }
+
+    #[test]
+    fn connect_errors_are_kept_until_a_connect_succeeds() {
+        let mut app = App::new();
+        let cafe = network("cafe", WifiSecurity::Open, false);
+        app.begin_operation(cafe.clone(), OperationKind::Connect);
+        app.finish_operation(false, Some("No DHCP lease".to_string()));
+        assert_eq!(
+            app.connect_errors.get("cafe").map(String::as_str),
+            Some("No DHCP lease")
+        );
+
+        app.begin_operation(cafe.clone(), OperationKind::Disconnect);
+        app.finish_operation(false, Some("Not active".to_string()));
+        assert_eq!(
+            app.connect_errors.get("cafe").map(String::as_str),
+            Some("No DHCP lease"),
+            "only connects count"
+        );
+
+        app.begin_operation(cafe, OperationKind::Connect);
+        app.finish_operation(true, None);
+        assert!(app.connect_errors.is_empty());
+    }
+
+    #[test]
+    fn drops_and_outside_connections_are_logged() {
+        let mut app = App::new();
+        app.adapter_name = Some("wlan0".to_string());
+        let now = Instant::now();
+        app.replace_scanned_networks(vec![connected_network("home")], now);
+        assert!(app.history.entries.is_empty(), "home was up before");
+
+        app.replace_scanned_networks(
+            vec![network("home", WifiSecurity::WpaPsk, false)],
+            now + Duration::from_secs(30),
+        );
+        app.replace_scanned_networks(
+            vec![connected_network("home")],
+            now + Duration::from_secs(60),
+        );
+
+        let events = app
+            .history
+            .entries
+            .iter()
+            .map(|entry| (entry.event, entry.ssid.as_str()))
+            .collect::<Vec<_>>();
+        assert_eq!(
+            events,
+            [
+                (HistoryEvent::Disconnected, "home"),
+                (HistoryEvent::Connected, "home"),
+            ]
+        );
+        assert_eq!(app.history.entries[0].adapter.as_deref(), Some("wlan0"));
+        assert!(app.history.entries[0].duration.is_some());
+
+        app.open_connection_history();
+        app.move_history_selection(false);
+        assert_eq!(app.history_selected, 1);
+    }
+
+    #[test]
+    fn unmanaged_adapters_can_be_handed_to_network_manager() {
+        let mut app = App::new();
+        app.manage_adapter();
+        assert!(app.pending_adapter_manage().is_none());
+        assert_eq!(
+            app.status_message,
+            "All Wi-Fi adapters are managed by NetworkManager"
+        );
+
+        app.adapter_name = Some("wlan0".to_string());
+        app.unmanaged_adapters = vec!["wlan0".to_string()];
+        assert!(app.adapter_unmanaged());
+
+        app.manage_adapter();
+        assert_eq!(app.pending_adapter_manage(), Some("wlan0"));
+        app.finish_adapter_manage(Err("Not allowed".to_string()));
+        assert!(app.pending_adapter_manage().is_none());
+        assert_eq!(app.status_message, "Not allowed");
+        assert!(app.adapter_unmanaged());
+
+        app.state = AppState::NetworkList;
+        app.manage_adapter();
+        app.finish_adapter_manage(Ok(()));
+        assert!(!app.adapter_unmanaged());
+        assert!(matches!(app.state, AppState::Scanning));
+        assert!(app.status_message.starts_with("wlan0 is now managed"));
+    }
+
+    #[test]
+    fn details_diagnose_and_clear_autoconnect_blocks_of_saved_networks() {
+        let mut home = network("home", WifiSecurity::WpaPsk, false);
+        home.saved = true;
+        let mut app = App::new();
+        app.state = AppState::NetworkList;
+        app.networks = vec![home, network("cafe", WifiSecurity::Open, false)];
+
+        app.open_network_details();
+        assert_eq!(
+            app.pending_autoconnect_check()
+                .map(|network| network.ssid.as_str()),
+            Some("home")
+        );
+
+        app.apply_autoconnect_block(Ok(Some(
+            AutoconnectBlock::ProfileAutoconnectOff,
+        )));
+        app.clear_autoconnect_block();
+        assert!(
+            app.pending_autoconnect_clear().is_none(),
+            "a profile setting is left to the user"
+        );
+
+        app.apply_autoconnect_block(Ok(Some(
+            AutoconnectBlock::RetriesExhausted,
+        )));
+        app.clear_autoconnect_block();
+        assert!(app.pending_autoconnect_clear().is_some());
+
+        app.finish_autoconnect_clear(Ok(()));
+        assert_eq!(
+            app.status_message,
+            "Cleared the autoconnect block for home"
+        );
+        assert!(app.pending_autoconnect_check().is_some());
+
+        app.state = AppState::NetworkList;
+        app.next();
+        app.open_network_details();
+        assert!(
+            app.autoconnect.is_none(),
+            "unsaved networks are not checked"
+        );
+    }
+
+    #[test]
+    fn details_load_the_settings_of_saved_profiles() {
+        let mut home = network("home", WifiSecurity::WpaPsk, false);
+        home.saved = true;
+        let mut app = App::new();
+        app.state = AppState::NetworkList;
+        app.networks = vec![home, network("cafe", WifiSecurity::Open, false)];
+
+        app.open_network_details();
+        assert_eq!(
+            app.pending_profile_lookup()
+                .map(|network| network.ssid.as_str()),
+            Some("home")
+        );
+
+        let settings = ProfileSettings {
+            proxy: ProxyConfig::Wpad,
+            users: vec!["alice".to_string()],
+        };
+        app.apply_profile_settings(Ok(Some(settings.clone())));
+        assert!(app.pending_profile_lookup().is_none());
+        assert_eq!(
+            app.profile_lookup.as_ref().map(|lookup| &lookup.check),
+            Some(&ProfileCheck::Loaded(Some(settings)))
+        );
+
+        app.state = AppState::NetworkList;
+        app.next();
+        app.open_network_details();
+        assert!(app.profile_lookup.is_none(), "unsaved networks have none");
+    }
+
+    #[test]
+    fn link_bitrate_refreshes_only_in_details_of_the_connected_network() {
+        let mut app = App::new();
+        app.state = AppState::NetworkList;
+        app.networks = vec![
+            connected_network("home"),
+            network("cafe", WifiSecurity::Open, false),
+        ];
+        assert!(!app.bitrate_refresh_due());
+
+        app.open_network_details();
+        assert!(app.bitrate_refresh_due());
+
+        app.apply_link_bitrate(Ok(Some(866_700)));
+        assert_eq!(app.link_bitrate, Some(866_700));
+        assert!(!app.bitrate_refresh_due(), "next read is scheduled");
+
+        app.state = AppState::NetworkList;
+        app.next();
+        app.open_network_details();
+        assert_eq!(app.link_bitrate, None);
+        assert!(!app.bitrate_refresh_due());
+    }
+
+    #[test]
+    fn latency_is_sampled_only_when_enabled_and_connected() {
+        let mut app = App::new();
+        app.networks = vec![connected_network("home")];
+        assert!(app.pending_latency_host().is_none(), "monitor is opt-in");
+
+        app.latency = Some(LatencyMonitor::new("9.9.9.9"));
+        assert_eq!(app.pending_latency_host(), Some("9.9.9.9"));
+
+        app.apply_latency_sample(Ok(LatencySample {
+            gateway: None,
+            host: Probe::Lost,
+        }));
+        assert!(app.pending_latency_host().is_none(), "next probe waits");
+        assert_eq!(
+            app.latency.as_ref().and_then(LatencyMonitor::loss_percent),
+            Some(100)
+        );
+
+        app.networks = vec![network("home", WifiSecurity::WpaPsk, false)];
+        app.next_latency_refresh_at = None;
+        assert!(app.pending_latency_host().is_none());
+    }
+
+    #[test]
+    fn a_silent_gateway_reconnects_then_switches_networks() {
+        let mut app = App::new();
+        let office = WifiNetwork {
+            saved: true,
+            ..network("office", WifiSecurity::WpaPsk, false)
+        };
+        app.state = AppState::NetworkList;
+        app.networks = vec![connected_network("home"), office];
+        app.health = Some(HealthMonitor::new(2));
+        let lost = || {
+            Ok(LatencySample {
+                gateway: Some(Probe::Lost),
+                host: Probe::Lost,
+            })
+        };
+
+        app.apply_latency_sample(lost());
+        assert!(matches!(app.state, AppState::NetworkList));
+        app.apply_latency_sample(lost());
+        assert!(matches!(app.state, AppState::Connecting));
+        assert_eq!(app.operation, OperationKind::Reconnect);
+        assert_eq!(
+            app.history.entries.last().map(|entry| entry.event),
+            Some(HistoryEvent::GatewayLost)
+        );
+
+        app.finish_operation(true, None);
+        app.back_to_network_list();
+        app.state = AppState::Help;
+        app.apply_latency_sample(lost());
+        app.apply_latency_sample(lost());
+        assert!(
+            matches!(app.state, AppState::Help),
+            "no recovery while a dialog is open"
+        );
+        app.state = AppState::NetworkList;
+        app.apply_latency_sample(lost());
+        assert!(matches!(app.state, AppState::PasswordInput));
+        assert_eq!(
+            app.selected_network
+                .as_ref()
+                .map(|network| network.ssid.as_str()),
+            Some("office")
+        );
+        app.apply_saved_password(Ok(Some("correct horse".to_string())));
+        assert!(
+            matches!(app.state, AppState::Connecting),
+            "the stored password is used right away"
+        );
+    }
+
+    #[test]
+    fn guest_connect_rejects_secured_networks() {
+        let mut app = App::new();
+        app.state = AppState::NetworkList;
+        app.networks = vec![network("office", WifiSecurity::WpaPsk, false)];
+
+        app.begin_guest_connection();
+
+        assert!(matches!(app.state, AppState::NetworkList));
+        assert!(!app.guest_connection);
+        assert_eq!(
+            app.status_message,
+            "Guest connect is only available for open networks"
+        );
+    }
+
+    #[test]
+    fn quick_filters_combine_and_keep_the_selection_when_visible() {
+        let mut app = App::new();
+        app.state = AppState::NetworkList;
+        let mut cafe_5g = network("cafe-5g", WifiSecurity::Open, false);
+        cafe_5g.frequency = 5180;
+        let mut cafe_2g = network("cafe-2g", WifiSecurity::Open, false);
+        cafe_2g.frequency = 2412;
+        let mut home = network("home", WifiSecurity::WpaPsk, false);
+        home.frequency = 5180;
+        home.saved = true;
+        app.networks = vec![home, cafe_2g, cafe_5g];
+        app.selected_index = 2;
+
+        app.toggle_filter(NetworkFilter::FiveGhz);
+        let visible = app
+            .visible_networks()
+            .iter()
+            .map(|network| network.ssid.as_str())
+            .collect::<Vec<_>>();
+        assert_eq!(visible, vec!["home", "cafe-5g"]);
+        assert_eq!(
+            app.selected_network_in_list().map(|n| n.ssid.as_str()),
+            Some("cafe-5g")
+        );
+
+        app.toggle_filter(NetworkFilter::Open);
+        assert_eq!(app.visible_networks().len(), 1);
+        assert_eq!(app.selected_index, 0);
+
+        app.toggle_filter(NetworkFilter::Saved);
+        assert!(app.visible_networks().is_empty());
+        assert!(app.selected_network_in_list().is_none());
+        assert_eq!(
+            app.filters.active(),
+            vec![
+                NetworkFilter::FiveGhz,
+                NetworkFilter::Open,
+                NetworkFilter::Saved
+            ]
+        );
+    }
+
+    #[test]
+    fn successful_connections_feed_the_suggestion() {
+        let mut app = App::new();
+        app.state = AppState::NetworkList;
+        app.networks = vec![
+            network("lobby", WifiSecurity::Open, false),
+            network("office", WifiSecurity::Open, false),
+        ];
+
+        for _ in 0..2 {
+            app.selected_index = 1;
+            app.activate_selected_network();
+            app.finish_operation(true, None);
+            app.back_to_network_list();
+        }
+
+        assert_eq!(app.history.entries.len(), 2);
+        assert_eq!(app.history.entries[0].neighbours, vec!["lobby"]);
+        assert_eq!(
+            app.suggested_network().map(|network| network.ssid.as_str()),
+            Some("office")
+        );
+
+        app.selected_index = 0;
+        app.connect_suggested_network();
+
+        assert!(matches!(app.state, AppState::Connecting));
+        assert_eq!(
+            app.selected_network.as_ref().map(|n| n.ssid.as_str()),
+            Some("office")
+        );
+    }
+
+    #[test]
+    fn favorites_are_listed_first_and_keep_the_cursor() {
+        let mut app = App::new();
+        app.networks = vec![
+            network("strong", WifiSecurity::Open, false),
+            network("middle", WifiSecurity::Open, false),
+            network("weak", WifiSecurity::Open, false),
+        ];
+        app.set_selected_index(2);
+
+        app.toggle_favorite_network();
+        let visible = |app: &App| {
+            app.visible_networks()
+                .iter()
+                .map(|network| network.ssid.clone())
+                .collect::<Vec<_>>()
+        };
+        assert_eq!(visible(&app), ["weak", "strong", "middle"]);
+        assert_eq!(app.selected_index, 0);
+
+        // Favorites keep the scan order among themselves.
+        app.set_selected_index(2);
+        app.toggle_favorite_network();
+        assert_eq!(visible(&app), ["middle", "weak", "strong"]);
+        assert_eq!(app.selected_index, 0);
+
+        app.toggle_favorite_network();
+        assert_eq!(visible(&app), ["weak", "strong", "middle"]);
+        assert_eq!(app.selected_index, 2);
+    }
+
+    #[test]
+    fn double_clicking_a_row_connects_to_it() {
+        let mut app = App::new();
+        app.state = AppState::NetworkList;
+        app.networks = vec![
+            network("home", WifiSecurity::Open, false),
+            network("cafe", WifiSecurity::Open, false),
+        ];
+        let start = Instant::now();
+
+        app.click_network_row(1, start);
+        assert_eq!(app.selected_index, 1);
+        assert!(app.state == AppState::NetworkList);
+
+        // Too slow for a double-click.
+        app.click_network_row(1, start + Duration::from_secs(1));
+        assert!(app.state == AppState::NetworkList);
+        app.click_network_row(5, start + Duration::from_secs(1));
+        assert_eq!(app.selected_index, 1, "clicks below the list do nothing");
+
+        app.click_network_row(1, start + Duration::from_millis(1200));
+        assert!(app.state == AppState::Connecting);
+        assert_eq!(
+            app.selected_network
+                .as_ref()
+                .map(|network| network.ssid.as_str()),
+            Some("cafe")
+        );
+    }
+
+    #[test]
+    fn sort_orders_cycle_and_keep_the_cursor() {
+        let mut app = App::new();
+        app.networks = vec![
+            WifiNetwork {
+                signal_strength: 90,
+                frequency: 2437,
+                ..network("cafe", WifiSecurity::Open, true)
+            },
+            WifiNetwork {
+                signal_strength: 40,
+                saved: true,
+                ..network("Attic", WifiSecurity::WpaPsk, false)
+            },
+            WifiNetwork {
+                signal_strength: 70,
+                frequency: 5955,
+                ..network("bistro", WifiSecurity::WpaSae, false)
+            },
+        ];
+        let visible = |app: &App| {
+            app.visible_networks()
+                .iter()
+                .map(|network| network.ssid.clone())
+                .collect::<Vec<_>>()
+        };
+        assert_eq!(app.sort_order, SortOrder::Signal);
+        assert_eq!(visible(&app), ["cafe", "bistro", "Attic"]);
+        app.set_selected_index(1);
+
+        app.cycle_sort_order();
+        assert_eq!(app.sort_order, SortOrder::Alphabetical);
+        assert_eq!(visible(&app), ["Attic", "bistro", "cafe"]);
+        assert_eq!(app.selected_index, 1);
+
+        app.cycle_sort_order();
+        assert_eq!(visible(&app), ["bistro", "Attic", "cafe"]);
+
+        app.cycle_sort_order();
+        assert_eq!(visible(&app), ["bistro", "Attic", "cafe"]);
+
+        app.cycle_sort_order();
+        assert_eq!(app.sort_order, SortOrder::KnownFirst);
+        assert_eq!(visible(&app), ["Attic", "cafe", "bistro"]);
+        assert_eq!(app.selected_index, 2);
+
+        app.toggle_favorite_network();
+        assert_eq!(visible(&app), ["bistro", "Attic", "cafe"]);
+
+        app.cycle_sort_order();
+        assert_eq!(app.sort_order, SortOrder::Signal);
+    }
+
+    #[test]
+    fn the_signal_sort_keeps_the_scan_order_among_equal_signals() {
+        let mut app = App::new();
+        app.networks = vec![
+            WifiNetwork {
+                signal_strength: 60,
+                ..network("home", WifiSecurity::WpaPsk, true)
+            },
+            WifiNetwork {
+                signal_strength: 60,
+                ..network("Attic", WifiSecurity::WpaPsk, false)
+            },
+            WifiNetwork {
+                signal_strength: 85,
+                ..network("cafe", WifiSecurity::Open, false)
+            },
+            WifiNetwork {
+                signal_strength: 60,
+                ..network("bistro", WifiSecurity::Open, false)
+            },
+        ];
+        let visible = |app: &App| {
+            app.visible_networks()
+                .iter()
+                .map(|network| network.ssid.clone())
+                .collect::<Vec<_>>()
+        };
+
+        assert_eq!(visible(&app), ["cafe", "home", "Attic", "bistro"]);
+
+        app.networks[1].signal_strength = 90;
+        app.list_changed();
+        assert_eq!(visible(&app), ["Attic", "cafe", "home", "bistro"]);
+    }
+
+    #[test]
+    fn ignored_networks_are_hidden_and_never_suggested() {
+        let mut app = App::new();
+        app.state = AppState::NetworkList;
+        app.networks = vec![
+            network("lobby", WifiSecurity::Open, false),
+            network("office", WifiSecurity::Open, false),
+        ];
+        for _ in 0..2 {
+            app.selected_index = 1;
+            app.activate_selected_network();
+            app.finish_operation(true, None);
+            app.back_to_network_list();
+        }
+        assert!(app.suggested_network().is_some());
+
+        app.selected_index = 1;
+        app.toggle_ignored_network();
+        let visible = |app: &App| {
+            app.visible_networks()
+                .iter()
+                .map(|network| network.ssid.clone())
+                .collect::<Vec<_>>()
+        };
+        assert_eq!(visible(&app), ["lobby"]);
+        assert_eq!(app.selected_index, 0);
+        assert!(app.suggested_network().is_none());
+
+        app.toggle_show_ignored();
+        assert_eq!(visible(&app), ["lobby", "office"]);
+        assert!(app.suggested_network().is_none());
+
+        app.selected_index = 1;
+        app.toggle_ignored_network();
+        app.toggle_show_ignored();
+        assert_eq!(visible(&app), ["lobby", "office"]);
+        assert!(app.suggested_network().is_some());
+    }
+
+    #[test]
+    fn password_prompt_routes_typing_to_the_focused_field() {
+        let mut app = App::new();
+        app.state = AppState::NetworkList;
+        app.networks = vec![network("office", WifiSecurity::WpaPsk, false)];
+        app.activate_selected_network();
+
+        app.add_char_to_password('p');
+        app.toggle_password_field();
+        for c in " Office ".chars() {
+            app.add_char_to_password(c);
+        }
+        app.remove_char_from_password();
+
+        assert_eq!(app.password_input, "p");
+        assert_eq!(app.profile_name_input, " Office");
+        assert_eq!(
+            app.connection_options().profile_name.as_deref(),
+            Some("Office")
+        );
+
+        app.cancel_password_input();
+
+        assert!(matches!(app.state, AppState::NetworkList));
+        assert!(app.profile_name_input.is_empty());
+        assert_eq!(app.password_field, PasswordField::Password);
+        assert_eq!(app.connection_options(), Default::default());
+    }
+
+    #[test]
+    fn invalid_pac_urls_keep_the_password_prompt_open() {
+        let mut app = App::new();
+        app.state = AppState::NetworkList;
+        app.networks = vec![network("office", WifiSecurity::WpaPsk, false)];
+        app.activate_selected_network();
+        app.toggle_password_field();
+        app.toggle_password_field();
+        app.cycle_password_choice(false);
+        assert_eq!(app.connection_options().proxy, ProxyConfig::Wpad);
+        app.toggle_password_field();
+        for c in "wpad.example/proxy.pac".chars() {
+            app.add_char_to_password(c);
+        }
+
+        app.confirm_password();
+        assert!(matches!(app.state, AppState::PasswordInput));
+        assert_eq!(app.password_field, PasswordField::ProxyAddress);
+        assert!(app.status_message.starts_with("PAC URL must start with"));
+
+        app.proxy_input.insert_str(0, "http://");
+        app.confirm_password();
+        assert!(matches!(app.state, AppState::Connecting));
+        assert_eq!(
+            app.connection_options().proxy,
+            ProxyConfig::Pac("http://wpad.example/proxy.pac".to_string())
+        );
+    }
+
+    #[test]
+    fn manual_proxies_need_a_host_and_port() {
+        let mut app = App::new();
+        app.state = AppState::NetworkList;
+        app.networks = vec![network("office", WifiSecurity::WpaPsk, false)];
+        app.activate_selected_network();
+        app.toggle_password_field();
+        app.toggle_password_field();
+        app.cycle_password_choice(true);
+        app.toggle_password_field();
+        for c in "proxy.corp".chars() {
+            app.add_char_to_password(c);
+        }
+
+        app.confirm_password();
+        assert!(matches!(app.state, AppState::PasswordInput));
+        assert_eq!(app.status_message, "Proxy must be written as host:port");
+
+        app.proxy_input.push_str(":3128");
+        app.confirm_password();
+        assert!(matches!(app.state, AppState::Connecting));
+        assert_eq!(
+            app.connection_options().proxy,
+            ProxyConfig::Manual("proxy.corp:3128".to_string())
+        );
+    }
+
+    #[test]
+    fn saved_networks_prefill_the_prompt_until_edited() {
+        let mut app = App::new();
+        app.state = AppState::NetworkList;
+        let mut office = network("office", WifiSecurity::WpaPsk, false);
+        office.saved = true;
+        app.networks =
+            vec![office, network("cafe", WifiSecurity::WpaPsk, false)];
+        app.activate_selected_network();
+        assert_eq!(
+            app.pending_prefill_password()
+                .map(|network| network.ssid.as_str()),
+            Some("office")
+        );
+
+        app.apply_saved_password(Ok(Some("hunter2".to_string())));
+        assert_eq!(app.password_input, "hunter2");
+        assert_eq!(app.password_prefill, PasswordPrefill::Filled);
+        assert!(app.pending_prefill_password().is_none());
+
+        app.remove_char_from_password();
+        assert_eq!(app.password_prefill, PasswordPrefill::None);
+
+        app.cancel_password_input();
+        app.selected_index = 1;
+        app.activate_selected_network();
+        assert!(app.pending_prefill_password().is_none());
+    }
+
+    #[test]
+    fn transient_connect_failures_are_retried_with_backoff() {
+        let mut app = App::new();
+        app.connect_retries = 2;
+        let cafe = network("cafe", WifiSecurity::Open, false);
+        app.begin_operation(cafe.clone(), OperationKind::Connect);
+
+        app.fail_connection(FailureKind::Dhcp, "no address".to_string());
+        assert!(app.state == AppState::Connecting);
+        assert_eq!(app.status_message, "DHCP failed, retrying in 2s");
+        assert_eq!(app.connect_attempt(), Some((2, 3)));
+        assert!(!app.connect_attempt_due());
+
+        app.fail_connection(FailureKind::ApNotFound, "gone".to_string());
+        assert_eq!(
+            app.status_message,
+            "Access point not found, retrying in 4s"
+        );
+        assert_eq!(app.connect_attempt(), Some((3, 3)));
+
+        app.fail_connection(FailureKind::ApNotFound, "gone".to_string());
+        assert!(app.state == AppState::ConnectionResult);
+        assert_eq!(app.connection_error.as_deref(), Some("gone"));
+
+        app.begin_operation(cafe, OperationKind::Connect);
+        assert!(app.connect_attempt_due());
+        assert_eq!(app.connect_attempt(), None);
+        app.fail_connection(FailureKind::Other, "supplicant".to_string());
+        assert!(app.state == AppState::ConnectionResult);
+    }
+
+    #[test]
+    fn network_manager_restarts_are_waited_out() {
+        let mut app = App::new();
+        app.state = AppState::NetworkList;
+        app.note_manager_owner(Some(":1.5".to_string()));
+
+        app.show_manager_unavailable("NetworkManager is gone".to_string());
+        assert!(app.state == AppState::ManagerUnavailable);
+        assert!(!app.manager_check_due());
+
+        app.next_manager_check_at = Some(Instant::now());
+        assert!(app.manager_check_due());
+        app.apply_manager_check(Err("still gone".to_string()));
+        assert_eq!(app.manager_error.as_deref(), Some("still gone"));
+        assert!(!app.manager_check_due());
+
+        app.apply_manager_check(Ok(()));
+        assert!(app.state == AppState::Scanning);
+        assert_eq!(app.status_message, "NetworkManager is back. Scanning...");
+
+        app.note_manager_owner(Some(":1.9".to_string()));
+        app.note_manager_owner(Some(":1.9".to_string()));
+        assert_eq!(app.status_message, "NetworkManager is back. Scanning...");
+
+        app.status_message.clear();
+        app.note_manager_owner(Some(":1.12".to_string()));
+        assert_eq!(
+            app.status_message,
+            "NetworkManager restarted; networks reloaded"
+        );
+    }
+
+    #[test]
+    fn pmf_and_visibility_are_chosen_with_arrows() {
+        let mut app = App::new();
+        app.state = AppState::NetworkList;
+        app.networks = vec![network("office", WifiSecurity::WpaSae, false)];
+        app.user_name = Some("alice".to_string());
+        app.activate_selected_network();
+
+        app.cycle_password_choice(false);
+        assert_eq!(app.pmf_input, Pmf::Default, "password field focused");
+
+        for _ in 0..3 {
+            app.toggle_password_field();
+        }
+        app.add_char_to_password('x');
+        app.cycle_password_choice(true);
+        app.cycle_password_choice(true);
+        app.toggle_password_field();
+        app.cycle_password_choice(false);
+        app.toggle_password_field();
+        app.cycle_password_choice(false);
+
+        let options = app.connection_options();
+        assert_eq!(app.password_input, "");
+        assert_eq!(options.pmf, Pmf::Optional);
+        assert_eq!(options.owner.as_deref(), Some("alice"));
+        assert!(options.agent_owned_secrets);
+
+        app.cancel_password_input();
+        assert_eq!(app.pmf_input, Pmf::Default);
+        assert!(!app.private_profile);
+        assert!(!app.keyring_secrets);
+    }
+
+    #[test]
+    fn user_scoped_profiles_are_the_default_when_configured() {
+        let mut app = App::new();
+        app.state = AppState::NetworkList;
+        app.user_name = Some("alice".to_string());
+        app.user_scoped_profiles = true;
+        app.networks = vec![
+            network("office", WifiSecurity::WpaSae, false),
+            network("cafe", WifiSecurity::Open, false),
+        ];
+
+        app.activate_selected_network();
+        let options = app.connection_options();
+        assert_eq!(options.owner.as_deref(), Some("alice"));
+        assert!(options.agent_owned_secrets);
+
+        for _ in 0..4 {
+            app.toggle_password_field();
+        }
+        app.cycle_password_choice(false);
+        assert_eq!(app.connection_options().owner, None);
+
+        app.cancel_password_input();
+        app.selected_index = 1;
+        app.activate_selected_network();
+        assert_eq!(app.connection_options().owner.as_deref(), Some("alice"));
+    }
+
+    #[test]
+    fn qr_share_waits_for_the_saved_password_of_secured_networks() {
+        let mut app = App::new();
+        app.state = AppState::NetworkList;
+        let mut office = network("Office Wi-Fi", WifiSecurity::WpaPsk, false);
+        office.saved = true;
+        app.networks = vec![office];
+
+        app.begin_qr_share();
+
+        assert!(matches!(app.state, AppState::ShareQr));
+        assert_eq!(
+            app.pending_share_password()
+                .map(|network| network.ssid.as_str()),
+            Some("Office Wi-Fi")
+        );
+        let share = app.qr_share.as_ref().expect("share started");
+        assert_eq!(share.export_path, "~/wifi-Office_Wi-Fi.png");
+        assert!(share.payload().is_none());
+
+        app.apply_saved_password(Ok(Some("hunter2".to_string())));
+
+        assert!(app.pending_share_password().is_none());
+        assert_eq!(
+            app.qr_share.as_ref().and_then(|share| share.payload()),
+            Some("WIFI:T:WPA;S:Office Wi-Fi;P:hunter2;;".to_string())
+        );
+
+        app.apply_saved_password(Err("secrets agent missing".to_string()));
+        assert_eq!(
+            app.qr_share.as_ref().map(|share| &share.password),
+            Some(&SharePassword::Failed("secrets agent missing".to_string()))
+        );
+    }
+
+    #[test]
+    fn qr_share_needs_a_saved_password_for_secured_networks() {
+        let mut app = App::new();
+        app.state = AppState::NetworkList;
+        app.networks = vec![network("Neighbour", WifiSecurity::WpaPsk, false)];
+
+        app.begin_qr_share();
+
+        assert!(matches!(app.state, AppState::NetworkList));
+        assert!(app.qr_share.is_none());
+        assert_eq!(
+            app.status_message,
+            "No saved password to share for Neighbour"
+        );
+
+        app.networks = vec![network("Lobby", WifiSecurity::Open, false)];
+        app.begin_qr_share();
+
+        assert!(app.pending_share_password().is_none());
+        assert_eq!(
+            app.qr_share.as_ref().and_then(|share| share.payload()),
+            Some("WIFI:T:nopass;S:Lobby;;".to_string())
+        );
+    }
+
+    #[test]
+    fn speed_test_runs_chunks_until_closed() {
+        let chunk = Ok(ChunkTransfer {
+            bytes: 4_000_000,
+            elapsed: Duration::from_millis(400),
+        });
+        let mut app = App::new();
+        app.state = AppState::NetworkList;
+        app.networks = vec![network("Office", WifiSecurity::WpaPsk, false)];
+
+        app.open_speed_test();
+        assert_eq!(app.status_message, "Not connected to a network");
+        assert!(app.pending_speed_test_chunk().is_none());
+
+        app.networks = vec![connected_network("Office")];
+        app.open_speed_test();
+        assert_eq!(
+            app.pending_speed_test_chunk(),
+            Some(TransferDirection::Download)
+        );
+
+        app.apply_speed_test_chunk(TransferDirection::Download, chunk.clone());
+        app.state = AppState::NetworkList;
+        app.apply_speed_test_chunk(TransferDirection::Download, chunk);
+        assert!(app.pending_speed_test_chunk().is_none());
+        assert_eq!(
+            app.speed_test.as_ref().map(|test| test.download.chunks),
+            Some(1),
+            "chunks after closing are dropped"
+        );
+    }
+
+    #[test]
+    fn roaming_view_needs_a_connection_and_tracks_reassociation() {
+        let access_point = |bssid: &str, associated| WifiAccessPoint {
+            bssid: bssid.to_string(),
+            frequency: 5180,
+            signal_strength: 40,
+            associated,
+        };
+        let mut app = App::new();
+        app.state = AppState::NetworkList;
+        app.networks = vec![network("Office", WifiSecurity::WpaPsk, false)];
+
+        app.open_roaming_view();
+
+        assert!(matches!(app.state, AppState::NetworkList));
+        assert_eq!(app.status_message, "Not connected to a network");
+
+        let mut office = connected_network("Office");
+        office.access_points =
+            vec![access_point("aa", true), access_point("bb", false)];
+        app.networks = vec![office];
+        app.open_roaming_view();
+        app.apply_signal_updates(&[
+            access_point("aa", false),
+            access_point("bb", true),
+        ]);
+
+        assert!(matches!(app.state, AppState::Roaming));
+        assert_eq!(
+            app.connected_network()
+                .and_then(|network| {
+                    network.access_points.iter().find(|ap| ap.associated)
+                })
+                .map(|access_point| access_point.bssid.as_str()),
+            Some("bb")
+        );
+    }
+
+    #[test]
+    fn avoiding_the_associated_ap_steers_to_another_once() {
+        let access_point =
+            |bssid: &str, signal_strength, associated| WifiAccessPoint {
+                bssid: bssid.to_string(),
+                frequency: 5180,
+                signal_strength,
+                associated,
+            };
+        let mut office = connected_network("Office");
+        office.access_points =
+            vec![access_point("aa", 70, false), access_point("bb", 90, true)];
+        let mut app = App::new();
+        app.state = AppState::NetworkList;
+        app.networks = vec![office];
+        app.open_roaming_view();
+        assert!(app.pending_steer().is_none());
+
+        app.toggle_avoided_access_point();
+
+        assert_eq!(app.status_message, "Avoiding bb on Office");
+        assert_eq!(
+            app.pending_steer()
+                .map(|(network, bssid)| (network.ssid.as_str(), bssid)),
+            Some(("Office", "aa"))
+        );
+
+        app.begin_steer();
+        assert!(app.pending_steer().is_none(), "no retry right away");
+
+        app.move_roaming_selection(true);
+        app.toggle_avoided_access_point();
+        assert_eq!(app.status_message, "No longer avoiding bb on Office");
+    }
+
+    #[test]
+    fn reconnect_bounces_the_connected_network_only() {
+        let mut app = App::new();
+        app.state = AppState::NetworkList;
+        app.networks = vec![network("Office", WifiSecurity::WpaPsk, false)];
+
+        app.reconnect_current_network();
+
+        assert!(matches!(app.state, AppState::NetworkList));
+        assert_eq!(app.status_message, "Not connected to a network");
+
+        app.networks = vec![connected_network("Office")];
+        app.reconnect_current_network();
+
+        assert!(matches!(app.state, AppState::Connecting));
+        assert_eq!(app.operation, OperationKind::Reconnect);
+        assert_eq!(app.status_message, "Reconnecting to Office...");
+
+        app.finish_operation(true, None);
+
+        assert_eq!(app.status_message, "Reconnected successfully!");
+        app.back_to_network_list();
+        assert_eq!(app.operation, OperationKind::Connect);
+    }
+
+    #[test]
+    fn lease_renewal_keeps_the_new_lease_for_the_result() {
+        let mut app = App::new();
+        app.networks = vec![connected_network("Office")];
+        app.renew_current_lease();
+
+        assert!(matches!(app.state, AppState::Connecting));
+        assert_eq!(app.status_message, "Renewing DHCP lease on Office...");
+
+        let lease = DhcpLease {
+            address: "10.20.0.14".to_string(),
+            gateway: Some("10.20.0.1".to_string()),
+            lease_seconds: Some(3600),
+        };
+        app.finish_lease_renewal(Ok(lease.clone()));
+
+        assert!(matches!(app.state, AppState::ConnectionResult));
+        assert_eq!(app.status_message, "DHCP lease renewed!");
+        assert_eq!(app.renewed_lease, Some(lease));
+        assert!(!app.pending_connectivity_check());
+    }
+
+    #[test]
+    fn successful_connects_check_connectivity_for_the_result() {
+        let mut app = App::new();
+        app.networks = vec![connected_network("Office")];
+        app.finish_operation(false, Some("Secrets were required".to_string()));
+        assert!(!app.pending_connectivity_check());
+
+        app.finish_operation(true, None);
+        assert!(app.pending_connectivity_check());
+
+        app.apply_connectivity(Ok(ConnectivityReport {
+            connectivity: Connectivity::Limited,
+            portal_url: None,
+        }));
+        assert!(!app.pending_connectivity_check());
+        assert_eq!(
+            app.connectivity,
+            Some(ConnectivityCheck::Checked(Connectivity::Limited))
+        );
+
+        app.back_to_network_list();
+        assert!(app.connectivity.is_none());
+        app.apply_connectivity(Err("late".to_string()));
+        assert!(app.connectivity.is_none(), "late results are dropped");
+    }
+
+    #[test]
+    fn captive_portals_open_until_nm_reports_global_connectivity() {
+        let url = "https://portal.hotel/login";
+        let mut app = App::new();
+        app.networks = vec![connected_network("Hotel")];
+        app.open_captive_portal();
+        assert!(app.pending_portal_open().is_none());
+        assert_eq!(app.status_message, "No captive portal detected");
+
+        app.finish_operation(true, None);
+        app.apply_connectivity(Ok(ConnectivityReport {
+            connectivity: Connectivity::Portal,
+            portal_url: Some(url.to_string()),
+        }));
+        app.back_to_network_list();
+        assert_eq!(app.captive_portal.as_deref(), Some(url));
+
+        app.open_captive_portal();
+        assert_eq!(app.pending_portal_open(), Some(url));
+        app.finish_portal_open(Err("xdg-open not found".to_string()));
+        assert!(app.pending_portal_open().is_none());
+        assert_eq!(
+            app.status_message,
+            format!("Open {url} in a browser to sign in")
+        );
+
+        app.update_manager_status(Some(ManagerStatus {
+            state: ManagerState::ConnectedSite,
+            primary_connection: None,
+            wifi_state_reason: None,
+        }));
+        assert!(app.captive_portal.is_some());
+        app.update_manager_status(Some(ManagerStatus {
+            state: ManagerState::ConnectedGlobal,
+            primary_connection: None,
+            wifi_state_reason: None,
+        }));
+        assert!(app.captive_portal.is_none());
+    }
+
+    #[test]
+    fn palette_filters_commands_and_opens_bulk_forget() {
+        let mut app = App::new();
+        app.state = AppState::NetworkList;
+
+        app.open_palette();
+        let palette = app.palette.as_mut().expect("palette opened");
+        palette.query = "FORGET".to_string();
+
+        assert_eq!(palette.commands(), vec![PaletteCommand::ForgetMatching]);
+
+        app.run_palette_command();
+
+        assert!(matches!(app.state, AppState::ForgetProfiles));
+        assert!(app.palette.is_none());
+        assert!(app.pending_profile_list());
+    }
+
+    #[test]
+    fn bulk_forget_previews_matches_and_requires_confirmation() {
+        let profile = |id: &str| SavedProfile {
+            id: id.to_string(),
+            uuid: format!("uuid-{id}"),
+            ssid: id.to_string(),
+            users: Vec::new(),
+            last_used: None,
+            band: None,
+            bssid: None,
+            mtu: None,
+            cloned_mac: None,
+            vpn: None,
+        };
+        let mut app = App::new();
+        app.begin_bulk_forget();
+        app.apply_saved_profiles(Ok(vec![
+            profile("Home"),
+            profile("Hotel_Guest"),
+            profile("Airport_Guest"),
+        ]));
+
+        app.advance_bulk_forget();
+        assert_eq!(
+            app.bulk_forget.as_ref().map(|forget| forget.stage),
+            Some(ForgetStage::Editing),
+            "nothing to forget without a matching pattern"
+        );
+
+        app.bulk_forget.as_mut().expect("dialog open").pattern =
+            "*_guest".to_string();
+        app.advance_bulk_forget();
+        assert!(app.pending_forget().is_none());
+
+        app.cancel_bulk_forget_confirmation();
+        app.advance_bulk_forget();
+        app.advance_bulk_forget();
+
+        let pending = app.pending_forget().expect("confirmed");
+        assert_eq!(
+            pending
+                .iter()
+                .map(|profile| profile.id.as_str())
+                .collect::<Vec<_>>(),
+            vec!["Hotel_Guest", "Airport_Guest"]
+        );
+
+        app.finish_bulk_forget(Ok(ForgetReport {
+            forgotten: 2,
+            requested: 2,
+            error: None,
+        }));
+
+        assert!(matches!(app.state, AppState::Scanning));
+        assert!(app.bulk_forget.is_none());
+        assert_eq!(
+            app.status_message,
+            "Forgot 2 saved network(s). Rescanning..."
+        );
+    }
+
+    #[test]
+    fn a_partly_failed_forget_reports_what_stayed_and_rescans() {
+        let mut app = App::new();
+        app.state = AppState::ForgetProfiles;
+
+        app.finish_bulk_forget(Ok(ForgetReport {
+            forgotten: 1,
+            requested: 2,
+            error: Some("Failed to forget Office: denied".to_string()),
+        }));
+
+        assert!(matches!(app.state, AppState::Scanning));
+        assert_eq!(
+            app.status_message,
+            "Forgot 1 of 2 saved networks (Failed to forget Office: denied). \
+             Rescanning..."
+        );
+    }
+
+    #[test]
+    fn imported_wifi_strings_connect_with_their_password() {
+        let mut app = App::new();
+        app.state = AppState::NetworkList;
+        app.networks = vec![network("Attic", WifiSecurity::WpaSae, false)];
+
+        app.begin_qr_import();
+        for c in "WIFI:T:WPA;S:Attic;;".chars() {
+            app.add_char_to_qr_import(c);
+        }
+        app.confirm_qr_import();
+        assert!(app.state == AppState::ImportQr);
+        assert!(
+            app.qr_import
+                .as_ref()
+                .is_some_and(|import| import.error.is_some())
+        );
+
+        app.qr_import = Some(QrImport::default());
+        for c in "WIFI:T:WPA;S:Attic;P:hunter2;H:true;;".chars() {
+            app.add_char_to_qr_import(c);
+        }
+        app.confirm_qr_import();
+
+        assert!(app.state == AppState::Connecting);
+        assert!(app.qr_import.is_none());
+        assert_eq!(app.password_input, "hunter2");
+        assert!(app.connection_options().hidden);
+        assert_eq!(
+            app.selected_network
+                .as_ref()
+                .map(|network| network.security),
+            Some(WifiSecurity::WpaSae)
+        );
     }
 
-    pub fn confirm_password(&mut self) {
-        if let Some(network) = self.selected_network.clone() {
-            self.begin_operation(network, OperationKind::Connect);
+    #[test]
+    fn profile_export_sends_matches_to_the_chosen_directory() {
+        let profile = |id: &str| SavedProfile {
+            id: id.to_string(),
+            uuid: format!("uuid-{id}"),
+            ssid: id.to_string(),
+            users: Vec::new(),
+            last_used: None,
+            band: None,
+            bssid: None,
+            mtu: None,
+            cloned_mac: None,
+            vpn: None,
+        };
+        let mut app = App::new();
+        app.begin_profile_export();
+        assert!(app.pending_profile_list());
+        app.apply_saved_profiles(Ok(vec![profile("Home"), profile("Office")]));
+
+        app.remove_char_from_profile_export();
+        for c in "off*".chars() {
+            app.add_char_to_profile_export(c);
         }
-    }
+        app.toggle_profile_export_field();
+        for _ in 0.."~/nm-wifi-profiles".len() {
+            app.remove_char_from_profile_export();
+        }
+        for c in "/tmp/profiles".chars() {
+            app.add_char_to_profile_export(c);
+        }
+        app.toggle_profile_export_secrets();
+        assert!(app.pending_profile_export().is_none());
 
-    pub fn quit(&mut self) {
-        self.should_quit = true;
+        app.confirm_profile_export();
+
+        let (profiles, directory, include_secrets) =
+            app.pending_profile_export().expect("export confirmed");
+        assert_eq!(profiles, vec![profile("Office")]);
+        assert_eq!(directory, std::path::PathBuf::from("/tmp/profiles"));
+        assert!(include_secrets);
+
+        app.finish_profile_export(Ok(1));
+
+        assert!(app.pending_profile_export().is_none());
+        assert_eq!(
+            app.profile_export
+                .as_ref()
+                .and_then(|export| export.result.clone()),
+            Some(Ok("Exported 1 profile(s) to /tmp/profiles".to_string()))
+        );
     }
 
-    pub fn finish_operation(&mut self, succeeded: bool, error: Option<String>) {
-        self.connection_success = succeeded;
-        self.connection_error = error;
-        self.status_message = match (self.is_disconnect_operation, succeeded) {
-            (true, true) => "Disconnected successfully!".to_string(),
-            (true, false) => "Disconnection failed".to_string(),
-            (false, true) => "Connected successfully!".to_string(),
-            (false, false) => "Connection failed".to_string(),
+    #[test]
+    fn known_networks_move_profiles_between_system_and_user_scope() {
+        let profile = |id: &str, users: &[&str]| SavedProfile {
+            id: id.to_string(),
+            uuid: format!("uuid-{id}"),
+            ssid: id.to_string(),
+            users: users.iter().map(|user| user.to_string()).collect(),
+            last_used: None,
+            band: None,
+            bssid: None,
+            mtu: None,
+            cloned_mac: None,
+            vpn: None,
         };
-        self.state = AppState::ConnectionResult;
-    }
+        let mut app = App::new();
+        app.user_name = Some("alice".to_string());
+        app.open_known_networks();
+        assert!(app.pending_profile_list());
+        app.apply_saved_profiles(Ok(vec![
+            profile("Home", &[]),
+            profile("Office", &["bob"]),
+        ]));
 
-    pub fn back_to_network_list(&mut self) {
-        self.state = AppState::NetworkList;
-        self.connection_success = false;
-        self.connection_error = None;
-        self.password_input.clear();
-        self.password_visible = false;
-        self.is_disconnect_operation = false;
-        self.connection_start_time = None;
-    }
+        app.toggle_known_network_scope();
+        let (request, owner) =
+            app.pending_profile_scope().expect("scope change requested");
+        assert_eq!(request.id, "Home");
+        assert_eq!(owner.as_deref(), Some("alice"));
 
-    pub fn start_scan(&mut self) {
-        self.state = AppState::Scanning;
-        self.status_message = "Scanning for networks...".to_string();
-        self.networks.clear();
-        self.network_count = 0;
-        self.last_scan_time = None;
-        self.set_selected_index(0);
+        app.finish_profile_scope(owner, Ok(()));
+        assert!(app.pending_profile_scope().is_none());
+        let known = app.known_networks.as_ref().expect("view open");
+        assert_eq!(
+            known.selected_profile().map(|home| home.users.clone()),
+            Some(vec!["alice".to_string()])
+        );
+        assert_eq!(
+            known.result,
+            Some(Ok("Home is now only for alice".to_string()))
+        );
+
+        app.move_known_network_selection(true);
+        app.toggle_known_network_scope();
+        let (request, owner) =
+            app.pending_profile_scope().expect("scope change requested");
+        assert_eq!(request.id, "Office");
+        assert_eq!(owner, None);
     }
 
-    pub fn handle_scan_error(&mut self, error: impl std::fmt::Display) {
-        self.state = AppState::NetworkList;
-        self.network_count = self.networks.len();
-        self.last_scan_time = None;
-        self.status_message =
-            format!("Scan failed: {}. Press r to retry.", error);
+    #[test]
+    fn known_networks_rename_the_selected_profile() {
+        let profile = |id: &str| SavedProfile {
+            id: id.to_string(),
+            uuid: format!("uuid-{id}"),
+            ssid: "MyCafe".to_string(),
+            users: Vec::new(),
+            last_used: None,
+            band: None,
+            bssid: None,
+            mtu: None,
+            cloned_mac: None,
+            vpn: None,
+        };
+        let mut app = App::new();
+        app.open_known_networks();
+        app.apply_saved_profiles(Ok(vec![profile("MyCafe 2")]));
+
+        app.begin_profile_edit(ProfileEdit::Rename);
+        for _ in 0.."MyCafe 2".len() {
+            app.edit_profile_input(None);
+        }
+        app.confirm_profile_edit();
+        assert!(app.pending_profile_rename().is_none());
+
+        for c in "MyCafe ".chars() {
+            app.edit_profile_input(Some(c));
+        }
+        app.confirm_profile_edit();
+        let (renamed, name) =
+            app.pending_profile_rename().expect("rename requested");
+        assert_eq!(renamed.uuid, "uuid-MyCafe 2");
+        assert_eq!(name, "MyCafe");
+
+        app.finish_profile_rename(name, Ok(()));
+
+        let known = app.known_networks.as_ref().expect("view open");
+        assert!(known.edit_input.is_none());
+        assert_eq!(
+            known.selected_profile().map(|cafe| cafe.id.as_str()),
+            Some("MyCafe")
+        );
+        assert_eq!(
+            known.result,
+            Some(Ok("Renamed MyCafe 2 to MyCafe".to_string()))
+        );
     }
 
-    pub fn update_selection_after_rescan(&mut self) {
-        if let Some(selected_network) = &self.selected_network {
-            if let Some(new_index) = self
-                .networks
-                .iter()
-                .position(|n| n.ssid == selected_network.ssid)
-            {
-                self.set_selected_index(new_index);
-            } else {
-                self.set_selected_index(0);
-            }
+    #[test]
+    fn known_networks_clone_the_selected_profile_below_it() {
+        let profile = |id: &str| SavedProfile {
+            id: id.to_string(),
+            uuid: format!("uuid-{id}"),
+            ssid: "Office".to_string(),
+            users: Vec::new(),
+            last_used: None,
+            band: None,
+            bssid: None,
+            mtu: None,
+            cloned_mac: None,
+            vpn: None,
+        };
+        let mut app = App::new();
+        app.open_known_networks();
+        app.apply_saved_profiles(Ok(vec![profile("Office"), profile("Home")]));
+
+        app.begin_profile_edit(ProfileEdit::Clone);
+        for _ in 0.." copy".len() {
+            app.edit_profile_input(None);
         }
-        self.selected_network = None;
+        for c in " (dock)".chars() {
+            app.edit_profile_input(Some(c));
+        }
+        app.confirm_profile_edit();
+        assert!(app.pending_profile_rename().is_none());
+        let (original, name) =
+            app.pending_profile_clone().expect("clone requested");
+        assert_eq!(original.id, "Office");
+        assert_eq!(name, "Office (dock)");
+
+        app.finish_profile_clone(Ok(profile("Office (dock)")));
+
+        let known = app.known_networks.as_ref().expect("view open");
+        let listed = match &known.profiles {
+            Some(Ok(profiles)) => profiles
+                .iter()
+                .map(|profile| profile.id.as_str())
+                .collect::<Vec<_>>(),
+            _ => Vec::new(),
+        };
+        assert_eq!(listed, vec!["Office", "Office (dock)", "Home"]);
+        assert_eq!(known.selected, 1);
+        assert!(app.pending_profile_clone().is_none());
     }
-}
 
-#[cfg(test)]
-mod tests {
-    use std::time::Instant;
+    #[test]
+    fn known_networks_update_the_stored_password() {
+        let mut app = App::new();
+        app.open_known_networks();
+        app.apply_saved_profiles(Ok(vec![SavedProfile {
+            id: "Home".to_string(),
+            uuid: "uuid-Home".to_string(),
+            ssid: "Home".to_string(),
+            users: Vec::new(),
+            last_used: None,
+            band: None,
+            bssid: None,
+            mtu: None,
+            cloned_mac: None,
+            vpn: None,
+        }]));
 
-    use super::{App, AppState};
-    use crate::wifi::{WifiNetwork, WifiSecurity};
+        app.begin_profile_edit(ProfileEdit::Password);
+        for c in "short".chars() {
+            app.edit_profile_input(Some(c));
+        }
+        app.confirm_profile_edit();
+        assert!(app.pending_profile_password().is_none());
 
-    fn network(
-        ssid: &str,
-        security: WifiSecurity,
-        connected: bool,
-    ) -> WifiNetwork {
-        WifiNetwork {
-            ssid: ssid.to_string(),
-            signal_strength: 80,
-            security,
-            frequency: 5180,
-            connected,
+        for c in " pass ".chars() {
+            app.edit_profile_input(Some(c));
         }
+        app.confirm_profile_edit();
+        let (profile, password) = app
+            .pending_profile_password()
+            .expect("password change requested");
+        assert_eq!(profile.uuid, "uuid-Home");
+        assert_eq!(password, "short pass ");
+        assert!(app.pending_profile_rename().is_none());
+
+        app.finish_profile_password(Ok(()));
+
+        let known = app.known_networks.as_ref().expect("view open");
+        assert!(known.edit_input.is_none());
+        assert_eq!(
+            known.result,
+            Some(Ok("Updated the password of Home".to_string()))
+        );
     }
 
-    fn connected_network(ssid: &str) -> WifiNetwork {
-        network(ssid, WifiSecurity::WpaPsk, true)
+    #[test]
+    fn known_networks_cycle_the_band_lock() {
+        let mut app = App::new();
+        app.open_known_networks();
+        app.apply_saved_profiles(Ok(vec![SavedProfile {
+            id: "Home".to_string(),
+            uuid: "uuid-Home".to_string(),
+            ssid: "Home".to_string(),
+            users: Vec::new(),
+            last_used: None,
+            band: None,
+            bssid: None,
+            mtu: None,
+            cloned_mac: None,
+            vpn: None,
+        }]));
+        let mut locks = Vec::new();
+
+        for _ in 0..3 {
+            app.cycle_known_network_band();
+            let (_, band) =
+                app.pending_profile_band().expect("band change requested");
+            locks.push(band);
+            app.finish_profile_band(band, Ok(()));
+        }
+
+        assert_eq!(
+            locks,
+            vec![
+                Some(WifiBand::TwoPointFourGhz),
+                Some(WifiBand::FiveGhz),
+                None
+            ]
+        );
+        assert!(app.pending_profile_band().is_none());
+        assert_eq!(
+            app.known_networks
+                .as_ref()
+                .and_then(|known| known.result.clone()),
+            Some(Ok("Home may use any band".to_string()))
+        );
     }
 
     #[test]
-    fn next_wraps_and_keeps_selection_state_in_sync() {
+    fn known_networks_pin_the_selected_profile_to_a_bssid() {
         let mut app = App::new();
-        app.networks =
-            vec![connected_network("home"), connected_network("guest")];
-        app.selected_index = 1;
+        app.open_known_networks();
+        app.apply_saved_profiles(Ok(vec![SavedProfile {
+            id: "Home".to_string(),
+            uuid: "uuid-Home".to_string(),
+            ssid: "Home".to_string(),
+            users: Vec::new(),
+            last_used: None,
+            band: None,
+            bssid: None,
+            mtu: None,
+            cloned_mac: None,
+            vpn: None,
+        }]));
 
-        app.next();
+        app.begin_profile_edit(ProfileEdit::Bssid);
+        "aa:bb:cc:dd:ee"
+            .chars()
+            .for_each(|c| app.edit_profile_input(Some(c)));
+        app.confirm_profile_edit();
+        assert!(app.pending_profile_bssid().is_none(), "incomplete BSSID");
 
-        assert_eq!(app.selected_index, 0);
+        ":0f".chars().for_each(|c| app.edit_profile_input(Some(c)));
+        app.confirm_profile_edit();
+        let (_, bssid) = app.pending_profile_bssid().expect("pin requested");
+        assert_eq!(bssid.as_deref(), Some("AA:BB:CC:DD:EE:0F"));
+        app.finish_profile_bssid(bssid, Ok(()));
+
+        app.begin_profile_edit(ProfileEdit::Bssid);
+        assert_eq!(
+            app.known_networks
+                .as_ref()
+                .and_then(|known| known.edit_input.clone()),
+            Some((ProfileEdit::Bssid, "AA:BB:CC:DD:EE:0F".to_string()))
+        );
+        (0..17).for_each(|_| app.edit_profile_input(None));
+        app.confirm_profile_edit();
+        let (_, bssid) = app.pending_profile_bssid().expect("unpin requested");
+        assert_eq!(bssid, None);
+        app.finish_profile_bssid(bssid, Ok(()));
+
+        assert_eq!(
+            app.known_networks
+                .as_ref()
+                .and_then(|known| known.result.clone()),
+            Some(Ok("Home may use any access point".to_string()))
+        );
     }
 
     #[test]
-    fn previous_wraps_and_keeps_selection_state_in_sync() {
+    fn known_networks_change_the_mtu_and_mac_of_a_profile() {
         let mut app = App::new();
-        app.networks =
-            vec![connected_network("home"), connected_network("guest")];
-        app.selected_index = 0;
+        app.open_known_networks();
+        app.apply_saved_profiles(Ok(vec![SavedProfile {
+            id: "Office".to_string(),
+            uuid: "uuid-Office".to_string(),
+            ssid: "Office".to_string(),
+            users: Vec::new(),
+            last_used: None,
+            band: None,
+            bssid: None,
+            mtu: Some(1500),
+            cloned_mac: None,
+            vpn: None,
+        }]));
 
-        app.previous();
+        app.begin_profile_edit(ProfileEdit::Mtu);
+        assert_eq!(
+            app.known_networks
+                .as_ref()
+                .and_then(|known| known.edit_input.clone()),
+            Some((ProfileEdit::Mtu, "1500".to_string()))
+        );
+        (0..4).for_each(|_| app.edit_profile_input(None));
+        "9".chars().for_each(|c| app.edit_profile_input(Some(c)));
+        app.confirm_profile_edit();
+        assert!(app.pending_profile_link().is_none(), "MTU too small");
 
-        assert_eq!(app.selected_index, 1);
+        app.edit_profile_input(None);
+        "1380".chars().for_each(|c| app.edit_profile_input(Some(c)));
+        app.confirm_profile_edit();
+        let (_, setting) = app.pending_profile_link().expect("MTU requested");
+        assert_eq!(setting, LinkSetting::Mtu(Some(1380)));
+        app.finish_profile_link(setting, Ok(()));
+
+        app.begin_profile_edit(ProfileEdit::ClonedMac);
+        "02:00:00:aa:bb:cc"
+            .chars()
+            .for_each(|c| app.edit_profile_input(Some(c)));
+        app.confirm_profile_edit();
+        let (_, setting) = app.pending_profile_link().expect("MAC requested");
+        assert_eq!(
+            setting,
+            LinkSetting::ClonedMac(Some("02:00:00:AA:BB:CC".to_string()))
+        );
+        app.finish_profile_link(setting, Ok(()));
+
+        let known = app.known_networks.as_ref().unwrap();
+        let profile = known.selected_profile().unwrap();
+        assert_eq!(profile.mtu, Some(1380));
+        assert_eq!(profile.cloned_mac.as_deref(), Some("02:00:00:AA:BB:CC"));
+        assert_eq!(
+            known.result,
+            Some(Ok(
+                "Office now uses MAC address 02:00:00:AA:BB:CC".to_string()
+            ))
+        );
     }
 
     #[test]
-    fn selecting_a_connected_network_starts_disconnect_timing() {
+    fn known_networks_bring_a_vpn_up_with_a_profile() {
+        let mut app = App::new();
+        app.open_known_networks();
+        app.apply_saved_profiles(Ok(vec![SavedProfile {
+            id: "Cafe".to_string(),
+            uuid: "uuid-Cafe".to_string(),
+            ssid: "Cafe".to_string(),
+            users: Vec::new(),
+            last_used: None,
+            band: None,
+            bssid: None,
+            mtu: None,
+            cloned_mac: None,
+            vpn: None,
+        }]));
+
+        app.begin_profile_edit(ProfileEdit::Vpn);
+        " Work VPN "
+            .chars()
+            .for_each(|c| app.edit_profile_input(Some(c)));
+        app.confirm_profile_edit();
+        let (_, vpn) = app.pending_profile_vpn().expect("VPN requested");
+        assert_eq!(vpn.as_deref(), Some("Work VPN"));
+        app.finish_profile_vpn(vpn, Ok(()));
+        assert_eq!(
+            app.known_networks
+                .as_ref()
+                .and_then(|known| known.selected_profile())
+                .and_then(|profile| profile.vpn.clone()),
+            Some("Work VPN".to_string())
+        );
+
+        app.begin_profile_edit(ProfileEdit::Vpn);
+        (0..8).for_each(|_| app.edit_profile_input(None));
+        app.confirm_profile_edit();
+        let (_, vpn) = app.pending_profile_vpn().expect("VPN cleared");
+        assert_eq!(vpn, None);
+        app.finish_profile_vpn(vpn, Err("No saved VPN is named".to_string()));
+        let known = app.known_networks.as_ref().unwrap();
+        assert_eq!(
+            known
+                .selected_profile()
+                .and_then(|profile| profile.vpn.clone()),
+            Some("Work VPN".to_string()),
+            "a failed change keeps the listed VPN"
+        );
+        assert!(matches!(known.result, Some(Err(_))));
+    }
+
+    #[test]
+    fn ethernet_tab_brings_profiles_up_and_devices_down() {
+        let profile = |id: &str| DeviceProfile {
+            id: id.to_string(),
+            uuid: format!("uuid-{id}"),
+        };
         let mut app = App::new();
         app.state = AppState::NetworkList;
-        app.networks = vec![connected_network("home")];
+        app.open_device_tab(DeviceKind::Ethernet);
+        assert_eq!(app.pending_device_list(), Some(DeviceKind::Ethernet));
+        app.apply_devices(Ok(vec![
+            NetworkDevice {
+                interface: "enx0".to_string(),
+                state: DeviceState::Connected,
+                link: DeviceLink::Wired {
+                    carrier: true,
+                    speed_mbps: Some(1000),
+                },
+                active_uuid: Some("uuid-Dock".to_string()),
+                profiles: vec![profile("Dock"), profile("VLAN")],
+            },
+            NetworkDevice {
+                interface: "eth0".to_string(),
+                state: DeviceState::Unavailable,
+                link: DeviceLink::Wired {
+                    carrier: false,
+                    speed_mbps: None,
+                },
+                active_uuid: None,
+                profiles: Vec::new(),
+            },
+        ]));
+        assert_eq!(app.pending_device_list(), None);
 
-        app.activate_selected_network();
+        app.toggle_selected_device_profile();
+        assert_eq!(
+            app.pending_device_change(),
+            Some(("enx0".to_string(), None))
+        );
+        app.finish_device_change("enx0", None, Ok(()));
+        assert_eq!(
+            app.pending_device_list(),
+            Some(DeviceKind::Ethernet),
+            "lists the devices again"
+        );
 
-        assert!(matches!(app.state, AppState::Disconnecting));
-        assert!(app.connection_start_time.is_some());
+        app.apply_devices(Ok(vec![NetworkDevice {
+            interface: "enx0".to_string(),
+            state: DeviceState::Disconnected,
+            link: DeviceLink::Wired {
+                carrier: true,
+                speed_mbps: Some(1000),
+            },
+            active_uuid: None,
+            profiles: vec![profile("Dock"), profile("VLAN")],
+        }]));
+        app.move_device_selection(true);
+        app.toggle_selected_device_profile();
+        let (interface, requested) =
+            app.pending_device_change().expect("switch requested");
+        assert_eq!(requested, Some(profile("VLAN")));
+        app.finish_device_change(&interface, requested, Ok(()));
+        assert_eq!(
+            app.device_tab.as_ref().and_then(|tab| tab.result.clone()),
+            Some(Ok("Brought VLAN up on enx0".to_string()))
+        );
+
+        app.next_device_tab();
+        assert_eq!(app.pending_device_list(), Some(DeviceKind::Mobile));
+        app.next_device_tab();
+        assert_eq!(app.pending_device_list(), Some(DeviceKind::Bluetooth));
+        app.next_device_tab();
+        assert!(matches!(app.state, AppState::NetworkList));
+        assert!(app.device_tab.is_none());
     }
 
     #[test]
-    fn activate_selected_network_uses_current_selection_not_just_index_zero() {
+    fn wifi_direct_tab_searches_and_connects_to_peers() {
+        let peer = |name: &str, hw_address: &str| P2pPeer {
+            name: name.to_string(),
+            hw_address: hw_address.to_string(),
+            manufacturer: None,
+            model: None,
+            signal_strength: 60,
+        };
         let mut app = App::new();
         app.state = AppState::NetworkList;
-        app.networks = vec![
-            network("cafe", WifiSecurity::Open, false),
-            network("office", WifiSecurity::WpaPsk, false),
-        ];
-        app.selected_index = 1;
+        app.open_palette();
+        app.palette.as_mut().expect("palette opened").query =
+            "direct".to_string();
+        app.run_palette_command();
+        assert!(matches!(app.state, AppState::P2pPeers));
+        assert!(app.pending_p2p_search());
 
-        app.activate_selected_network();
+        app.apply_p2p_peers(Ok(vec![
+            peer("Living Room TV", "DA:A1:19:00:00:01"),
+            peer("Office Printer", "DA:A1:19:00:00:02"),
+        ]));
+        assert!(!app.pending_p2p_search());
+        assert_eq!(app.pending_p2p_connect(), None);
 
-        assert!(matches!(app.state, AppState::PasswordInput));
+        app.move_p2p_selection(false);
+        app.connect_selected_p2p_peer();
         assert_eq!(
-            app.selected_network
-                .as_ref()
-                .map(|network| network.ssid.as_str()),
-            Some("office")
+            app.pending_p2p_connect(),
+            Some(peer("Office Printer", "DA:A1:19:00:00:02"))
+        );
+        app.refresh_p2p_peers();
+        assert!(
+            !app.pending_p2p_search(),
+            "keeps the peers while connecting"
+        );
+
+        app.finish_p2p_connect("Office Printer", Ok(()));
+        assert_eq!(app.pending_p2p_connect(), None);
+        assert_eq!(
+            app.p2p_tab.as_ref().and_then(|tab| tab.result.clone()),
+            Some(Ok("Connected to Office Printer".to_string()))
         );
+
+        app.close_p2p_tab();
+        assert!(matches!(app.state, AppState::NetworkList));
+        assert!(app.p2p_tab.is_none());
     }
 
     #[test]
-    fn starting_a_scan_clears_stale_scan_metadata() {
+    fn cleanup_offers_unused_profiles_out_of_range() {
+        let now = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .expect("clock after epoch")
+            .as_secs();
+        let profile = |id: &str, days_ago: Option<u64>| SavedProfile {
+            id: id.to_string(),
+            uuid: format!("uuid-{id}"),
+            ssid: id.to_string(),
+            users: Vec::new(),
+            last_used: days_ago.map(|days| now - days * 24 * 60 * 60),
+            band: None,
+            bssid: None,
+            mtu: None,
+            cloned_mac: None,
+            vpn: None,
+        };
+        let mut app = App::new();
+        app.networks = vec![network("Office", WifiSecurity::WpaPsk, false)];
+        app.unused_profile_days = 30;
+        app.begin_profile_cleanup();
+        assert!(app.pending_profile_list());
+        app.apply_saved_profiles(Ok(vec![
+            profile("Home", Some(2)),
+            profile("Office", None),
+            profile("Hotel", Some(90)),
+            profile("Airport", None),
+        ]));
+
+        let cleanup = app.profile_cleanup.as_ref().expect("cleanup open");
+        let listed = match &cleanup.profiles {
+            Some(Ok(profiles)) => profiles
+                .iter()
+                .map(|profile| profile.id.as_str())
+                .collect::<Vec<_>>(),
+            _ => Vec::new(),
+        };
+        assert_eq!(listed, vec!["Airport", "Hotel"]);
+
+        app.toggle_profile_cleanup_pick();
+        app.advance_profile_cleanup();
+        assert!(app.pending_forget().is_none());
+        app.advance_profile_cleanup();
+
+        assert_eq!(
+            app.pending_forget(),
+            Some(vec![profile("Hotel", Some(90))])
+        );
+
+        app.finish_bulk_forget(Ok(ForgetReport {
+            forgotten: 1,
+            requested: 1,
+            error: None,
+        }));
+        assert!(app.profile_cleanup.is_none());
+    }
+
+    #[test]
+    fn only_slow_backend_requests_show_as_busy() {
         let mut app = App::new();
         app.state = AppState::NetworkList;
-        app.networks = vec![connected_network("home")];
-        app.network_count = 3;
-        app.last_scan_time = Some(Instant::now());
-        app.selected_index = 0;
+        assert!(!app.animating());
+        app.begin_backend_activity("Scanning");
+        assert!(app.visible_backend_activity().is_none());
+        assert!(app.animating());
 
-        app.start_scan();
+        if let Some(activity) = app.backend_activity.as_mut() {
+            activity.started -= Duration::from_secs(1);
+        }
+        assert_eq!(
+            app.visible_backend_activity()
+                .map(|activity| activity.operation),
+            Some("Scanning")
+        );
 
-        assert!(matches!(app.state, AppState::Scanning));
-        assert!(app.networks.is_empty());
-        assert_eq!(app.network_count, 0);
-        assert!(app.last_scan_time.is_none());
-        assert_eq!(app.selected_index, 0);
+        app.finish_backend_activity();
+        assert!(app.visible_backend_activity().is_none());
+        assert!(!app.animating());
     }
 
     #[test]
-    fn start_scan_resets_selection_fields_together() {
+    fn operation_results_are_announced_through_the_notifier() {
         let mut app = App::new();
-        app.networks =
-            vec![connected_network("home"), connected_network("guest")];
-        app.selected_index = 1;
+        app.selected_network =
+            Some(network("Office", WifiSecurity::WpaPsk, false));
 
-        app.start_scan();
+        app.finish_operation(false, Some("wrong password".to_string()));
 
-        assert_eq!(app.selected_index, 0);
+        let toasts = app.notifier.toasts().active(Instant::now());
+        assert_eq!(toasts.len(), 1);
+        assert_eq!(toasts[0].level, ToastLevel::Error);
+        assert_eq!(toasts[0].summary, "Connection failed");
+        assert_eq!(toasts[0].body, "Office: wrong password");
     }
 
     #[test]
-    fn update_selection_after_rescan_restores_matching_ssid() {
+    fn rescans_announce_networks_that_were_not_listed() {
         let mut app = App::new();
-        app.networks =
-            vec![connected_network("guest"), connected_network("home")];
-        app.selected_network = Some(connected_network("home"));
+        app.merge_rescanned_networks(vec![network(
+            "Home",
+            WifiSecurity::WpaPsk,
+            true,
+        )]);
+        assert!(app.notifier.toasts().active(Instant::now()).is_empty());
 
-        app.update_selection_after_rescan();
+        app.merge_rescanned_networks(vec![
+            network("Home", WifiSecurity::WpaPsk, true),
+            network("Cafe", WifiSecurity::Open, false),
+            network("Library", WifiSecurity::Open, false),
+        ]);
+        app.finish_steer("AA:AA:AA:AA:AA:02", Err("timed out".to_string()));
 
-        assert_eq!(app.selected_index, 1);
-        assert!(app.selected_network.is_none());
+        let toasts = app.notifier.toasts().active(Instant::now());
+        assert_eq!(toasts.len(), 2);
+        assert_eq!(toasts[0].level, ToastLevel::Warning);
+        assert_eq!(toasts[0].summary, "Could not move to AA:AA:AA:AA:AA:02");
+        assert_eq!(toasts[1].level, ToastLevel::Info);
+        assert_eq!(toasts[1].body, "2 new network(s) in range");
+        assert_eq!(app.status_message, "Scanning for networks...");
     }
 
     #[test]
-    fn update_selection_after_rescan_resets_to_first_when_selected_ssid_disappears()
-     {
+    fn unexpected_drops_are_announced_with_their_reason() {
         let mut app = App::new();
-        app.selected_index = 1;
-        app.networks =
-            vec![connected_network("guest"), connected_network("cafe")];
-        app.selected_network = Some(connected_network("home"));
+        app.networks = vec![connected_network("home")];
+        app.track_connection_uptime();
 
-        app.update_selection_after_rescan();
+        app.manager_status = Some(ManagerStatus {
+            state: ManagerState::Disconnected,
+            primary_connection: None,
+            wifi_state_reason: Some(DeviceStateReason(8)),
+        });
+        app.merge_rescanned_networks(vec![network(
+            "home",
+            WifiSecurity::Open,
+            false,
+        )]);
 
-        assert_eq!(app.selected_index, 0);
-        assert!(app.selected_network.is_none());
+        let toasts = app.notifier.toasts().active(Instant::now());
+        assert_eq!(toasts.len(), 1);
+        assert_eq!(toasts[0].summary, "Connection dropped");
+        assert_eq!(
+            toasts[0].body,
+            "home: the access point ended the Wi-Fi handshake \
+             (SUPPLICANT_DISCONNECT)"
+        );
+
+        app.networks = vec![connected_network("home")];
+        app.track_connection_uptime();
+        app.manager_status =
+            app.manager_status.take().map(|status| ManagerStatus {
+                wifi_state_reason: Some(DeviceStateReason(39)),
+                ..status
+            });
+        app.merge_rescanned_networks(Vec::new());
+        assert_eq!(
+            app.notifier.toasts().active(Instant::now()).len(),
+            1,
+            "a requested disconnect is not a drop"
+        );
     }
 
     #[test]
@@ -362,4 +6824,44 @@ mod tests {
             "Scan failed: dbus unavailable. Press r to retry."
         );
     }
+
+    #[test]
+    fn edited_config_files_reload_the_theme() {
+        let path = std::env::temp_dir()
+            .join(format!("nm-wifi-config-{}.toml", std::process::id()));
+        std::fs::write(&path, "theme = latte\n").expect("write fixture");
+        let mut app = App::new();
+        app.watch_config(Some(path.clone()));
+        let start = Instant::now();
+
+        app.reload_changed_config(start);
+        assert_eq!(app.theme, ThemeName::Auto);
+
+        std::fs::write(&path, "theme = latte\n[theme]\nred = \"#ff0000\"\n")
+            .expect("write fixture");
+        std::fs::File::options()
+            .write(true)
+            .open(&path)
+            .and_then(|file| {
+                file.set_modified(SystemTime::now() + Duration::from_secs(5))
+            })
+            .expect("touch fixture");
+        app.reload_changed_config(start + Duration::from_millis(500));
+        assert_eq!(app.theme, ThemeName::Auto);
+
+        app.reload_changed_config(start + Duration::from_secs(1));
+        assert_eq!(app.status_message, "Theme reloaded: Catppuccin Latte");
+        assert_eq!(app.colors().red, Color::Rgb(255, 0, 0));
+        assert_eq!(app.colors().base, LATTE.base);
+
+        // Saving the next theme is not picked up as an edit.
+        app.cycle_theme();
+        app.reload_changed_config(start + Duration::from_secs(2));
+        let saved = std::fs::read_to_string(&path).expect("read fixture");
+        std::fs::remove_file(&path).expect("remove fixture");
+
+        assert_eq!(app.status_message, "Theme: Terminal colours");
+        assert_eq!(app.colors().red, Color::Rgb(255, 0, 0));
+        assert!(saved.starts_with("theme = \"terminal\"\n[theme]"));
+    }
 }
@@ -0,0 +1,182 @@
+//! Access points the user asked to stay away from, stored one
+//! `<bssid>\t<ssid>` record per line in `$XDG_STATE_HOME/nm-wifi/avoided`.
+//!
+//! NetworkManager has no BSSID blocklist, so avoiding works by steering:
+//! whenever the adapter is found on an avoided BSSID, the profile is
+//! activated again on the strongest other access point of the SSID.
+
+use std::{fs, path::PathBuf};
+
+use crate::{
+    config::xdg_path,
+    history::{escape, unescape},
+    wifi::{WifiAccessPoint, WifiNetwork},
+};
+
+#[derive(Debug, Clone, Default)]
+pub struct AvoidedBssids {
+    /// Where the list is saved; `None` keeps it in memory only.
+    pub path: Option<PathBuf>,
+    /// `(ssid, bssid)` pairs, BSSIDs in lowercase.
+    pub entries: Vec<(String, String)>,
+}
+
+impl AvoidedBssids {
+    pub fn default_path() -> Option<PathBuf> {
+        xdg_path("XDG_STATE_HOME", ".local/state", "avoided")
+    }
+
+    /// Reads the list at `path`; unreadable files and malformed lines are
+    /// skipped.
+    pub fn load(path: Option<PathBuf>) -> Self {
+        let entries = path
+            .as_ref()
+            .and_then(|path| fs::read_to_string(path).ok())
+            .map(|contents| {
+                contents
+                    .lines()
+                    .filter_map(|line| {
+                        let (bssid, ssid) = line.split_once('\t')?;
+                        Some((unescape(ssid), bssid.to_ascii_lowercase()))
+                    })
+                    .collect()
+            })
+            .unwrap_or_default();
+
+        Self { path, entries }
+    }
+
+    pub fn contains(&self, ssid: &str, bssid: &str) -> bool {
+        self.entries.iter().any(|(avoided_ssid, avoided_bssid)| {
+            avoided_ssid == ssid && avoided_bssid.eq_ignore_ascii_case(bssid)
+        })
+    }
+
+    /// Avoids `bssid` for `ssid`, or allows it again when it already was,
+    /// and saves the list. Returns whether it is avoided now.
+    pub fn toggle(&mut self, ssid: &str, bssid: &str) -> bool {
+        let avoided = if self.contains(ssid, bssid) {
+            self.entries.retain(|(avoided_ssid, avoided_bssid)| {
+                !(avoided_ssid == ssid
+                    && avoided_bssid.eq_ignore_ascii_case(bssid))
+            });
+            false
+        } else {
+            self.entries
+                .push((ssid.to_string(), bssid.to_ascii_lowercase()));
+            true
+        };
+        self.save();
+        avoided
+    }
+
+    fn save(&self) {
+        let Some(path) = &self.path else {
+            return;
+        };
+        if let Some(parent) = path.parent() {
+            let _ = fs::create_dir_all(parent);
+        }
+        let contents = self
+            .entries
+            .iter()
+            .map(|(ssid, bssid)| format!("{bssid}\t{}\n", escape(ssid)))
+            .collect::<String>();
+        let _ = fs::write(path, contents);
+    }
+
+    /// Where to move a connection currently on an avoided BSSID: the
+    /// strongest access point of the same SSID that is not avoided.
+    pub fn steer_target<'a>(
+        &self,
+        network: &'a WifiNetwork,
+    ) -> Option<&'a WifiAccessPoint> {
+        let associated = network
+            .access_points
+            .iter()
+            .find(|access_point| access_point.associated)?;
+        if !self.contains(&network.ssid, &associated.bssid) {
+            return None;
+        }
+
+        network
+            .access_points
+            .iter()
+            .filter(|access_point| {
+                !self.contains(&network.ssid, &access_point.bssid)
+            })
+            .max_by_key(|access_point| access_point.signal_strength)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::AvoidedBssids;
+    use crate::wifi::{WifiAccessPoint, WifiNetwork, WifiSecurity};
+
+    fn access_point(
+        bssid: &str,
+        signal: u8,
+        associated: bool,
+    ) -> WifiAccessPoint {
+        WifiAccessPoint {
+            bssid: bssid.to_string(),
+            frequency: 5180,
+            signal_strength: signal,
+            associated,
+        }
+    }
+
+    fn network(access_points: Vec<WifiAccessPoint>) -> WifiNetwork {
+        WifiNetwork {
+            ssid: "Office".to_string(),
+            raw_ssid: None,
+            signal_strength: 80,
+            security: WifiSecurity::WpaPsk,
+            frequency: 5180,
+            connected: true,
+            saved: true,
+            max_bitrate: 0,
+            wpa_flags: 0,
+            rsn_flags: 0,
+            access_points,
+        }
+    }
+
+    #[test]
+    fn connections_on_an_avoided_bssid_move_to_the_strongest_other() {
+        let mut avoided = AvoidedBssids::default();
+        avoided.toggle("Office", "AA:AA:AA:AA:AA:01");
+        avoided.toggle("Office", "aa:aa:aa:aa:aa:02");
+        let office = network(vec![
+            access_point("AA:AA:AA:AA:AA:01", 90, true),
+            access_point("AA:AA:AA:AA:AA:02", 85, false),
+            access_point("AA:AA:AA:AA:AA:03", 40, false),
+            access_point("AA:AA:AA:AA:AA:04", 60, false),
+        ]);
+
+        assert_eq!(
+            avoided
+                .steer_target(&office)
+                .map(|access_point| access_point.bssid.as_str()),
+            Some("AA:AA:AA:AA:AA:04")
+        );
+
+        assert!(!avoided.toggle("Office", "aa:aa:aa:aa:aa:01"));
+        assert!(avoided.steer_target(&office).is_none());
+    }
+
+    #[test]
+    fn the_list_survives_a_restart() {
+        let path = std::env::temp_dir()
+            .join(format!("nm-wifi-avoided-{}", std::process::id()));
+        let mut avoided = AvoidedBssids::load(Some(path.clone()));
+        avoided.toggle("Cafe\tBar", "AA:AA:AA:AA:AA:01");
+
+        let reloaded = AvoidedBssids::load(Some(path.clone()));
+        std::fs::remove_file(&path).expect("remove fixture");
+
+        assert!(reloaded.contains("Cafe\tBar", "aa:aa:aa:aa:aa:01"));
+        assert!(!reloaded.contains("Cafe", "aa:aa:aa:aa:aa:01"));
+    }
+}
@@ -1,3 +1,5 @@
+#[cfg(feature = "demo")]
+use std::time::Duration;
 use std::{
     error::Error,
     future::Future,
@@ -6,6 +8,8 @@ use std::{
     sync::mpsc::{self, Receiver, TryRecvError},
 };
 
+#[cfg(feature = "demo")]
+use crate::network::demo_faults::{DemoFaults, DemoOperation, FaultOutcome};
 use crate::{
     app::runtime::{
         RuntimeBackendDriver,
@@ -13,9 +17,14 @@ use crate::{
         RuntimeRequest,
         ScanSnapshot,
     },
-    network::ConnectionRequest,
+    network::{ConnectionRequest, FailureKind, restrict_to_band},
     wifi::WifiNetwork,
 };
+#[cfg(not(feature = "demo"))]
+use crate::{
+    latency::LatencySample,
+    network::{ActivationProgress, CancelToken},
+};
 
 pub type BackendFuture<'a, T> = Pin<Box<dyn Future<Output = T> + 'a>>;
 
@@ -32,6 +41,35 @@ pub trait NetworkBackend {
     fn disconnect(&self, network: &WifiNetwork) -> Result<(), Box<dyn Error>>;
 }
 
+fn connection_request<'a>(
+    network: &'a WifiNetwork,
+    passphrase: Option<&'a str>,
+    guest: bool,
+) -> ConnectionRequest<'a> {
+    match passphrase {
+        Some(passphrase) => ConnectionRequest::Secured {
+            network,
+            passphrase,
+        },
+        None if guest => ConnectionRequest::Guest { network },
+        None => ConnectionRequest::Open { network },
+    }
+}
+
+/// Tells rejected passwords apart from other failed connects, so the
+/// password prompt can be reopened.
+fn connect_event(result: Result<(), Box<dyn Error>>) -> RuntimeEvent {
+    match result {
+        Ok(()) => RuntimeEvent::Connect(Ok(())),
+        Err(error) => match FailureKind::of(&*error) {
+            FailureKind::Auth => {
+                RuntimeEvent::PasswordRejected(error.to_string())
+            }
+            kind => RuntimeEvent::ConnectFailed(kind, error.to_string()),
+        },
+    }
+}
+
 fn runtime_channel_closed_error() -> Box<dyn Error> {
     io::Error::other("runtime backend event channel closed").into()
 }
@@ -88,48 +126,84 @@ impl NetworkBackend for DemoNetworkBackend {
 }
 
 #[cfg(feature = "demo")]
-#[derive(Default)]
 struct DemoRuntimeDriver {
     pending_event: Option<Receiver<RuntimeEvent>>,
+    faults: Result<DemoFaults, String>,
+    /// Sender of a request the faults made hang, kept so its channel stays
+    /// open without ever answering.
+    hung_sender: Option<mpsc::Sender<RuntimeEvent>>,
+}
+
+#[cfg(feature = "demo")]
+impl Default for DemoRuntimeDriver {
+    fn default() -> Self {
+        Self {
+            pending_event: None,
+            faults: DemoFaults::from_env(),
+            hung_sender: None,
+        }
+    }
+}
+
+#[cfg(feature = "demo")]
+fn demo_operation(request: &RuntimeRequest) -> Option<DemoOperation> {
+    match request {
+        RuntimeRequest::Scan { .. } => Some(DemoOperation::Scan),
+        RuntimeRequest::Connect { .. } => Some(DemoOperation::Connect),
+        RuntimeRequest::Disconnect { .. } => Some(DemoOperation::Disconnect),
+        RuntimeRequest::Reconnect { .. } => Some(DemoOperation::Reconnect),
+        RuntimeRequest::RenewLease { .. } => Some(DemoOperation::RenewLease),
+        _ => None,
+    }
+}
+
+#[cfg(feature = "demo")]
+fn demo_fault_event(operation: DemoOperation, message: String) -> RuntimeEvent {
+    match operation {
+        DemoOperation::Scan => RuntimeEvent::Scan(Err(message)),
+        DemoOperation::Connect => RuntimeEvent::Connect(Err(message)),
+        DemoOperation::Disconnect => RuntimeEvent::Disconnect(Err(message)),
+        DemoOperation::Reconnect => RuntimeEvent::Reconnect(Err(message)),
+        DemoOperation::RenewLease => RuntimeEvent::RenewLease(Err(message)),
+    }
 }
 
 #[cfg(feature = "demo")]
 impl RuntimeBackendDriver for DemoRuntimeDriver {
     fn begin(&mut self, request: RuntimeRequest) {
         let (sender, receiver) = mpsc::channel();
-        let event = match request {
-            RuntimeRequest::Scan => RuntimeEvent::Scan(Ok(ScanSnapshot {
-                networks: crate::network::demo::demo_networks(),
-                adapter_name: crate::network::demo::get_wifi_adapter_name()
-                    .ok()
-                    .flatten(),
-            })),
-            RuntimeRequest::Connect {
-                network,
-                passphrase,
-            } => {
-                let result = match passphrase.as_deref() {
-                    Some(passphrase) => {
-                        crate::network::demo::connect_to_network(
-                            ConnectionRequest::Secured {
-                                network: &network,
-                                passphrase,
-                            },
-                        )
-                    }
-                    None => crate::network::demo::connect_to_network(
-                        ConnectionRequest::Open { network: &network },
-                    ),
-                };
-                RuntimeEvent::Connect(result.map_err(|error| error.to_string()))
+        self.pending_event = Some(receiver);
+        self.hung_sender = None;
+
+        let operation = demo_operation(&request);
+        // A malformed fault spec surfaces as a scan error instead of being
+        // silently ignored.
+        let (delay, outcome) = match &self.faults {
+            Ok(faults) => faults.plan(operation),
+            Err(error) if operation == Some(DemoOperation::Scan) => {
+                (Duration::ZERO, FaultOutcome::Fail(error.clone()))
             }
-            RuntimeRequest::Disconnect { network } => RuntimeEvent::Disconnect(
-                crate::network::demo::disconnect_from_network(&network)
-                    .map_err(|error| error.to_string()),
-            ),
+            Err(_) => (Duration::ZERO, FaultOutcome::Reply),
         };
-        let _ = sender.send(event);
-        self.pending_event = Some(receiver);
+        let event = match (operation, outcome) {
+            (_, FaultOutcome::Hang) => {
+                self.hung_sender = Some(sender);
+                return;
+            }
+            (Some(operation), FaultOutcome::Fail(message)) => {
+                demo_fault_event(operation, message)
+            }
+            _ => demo_event(request),
+        };
+
+        if delay.is_zero() {
+            let _ = sender.send(event);
+        } else {
+            std::thread::spawn(move || {
+                std::thread::sleep(delay);
+                let _ = sender.send(event);
+            });
+        }
     }
 
     fn poll_event(&mut self) -> Result<Option<RuntimeEvent>, Box<dyn Error>> {
@@ -137,6 +211,243 @@ impl RuntimeBackendDriver for DemoRuntimeDriver {
     }
 }
 
+#[cfg(feature = "demo")]
+fn demo_event(request: RuntimeRequest) -> RuntimeEvent {
+    match request {
+        RuntimeRequest::Scan { band, .. } => {
+            RuntimeEvent::Scan(Ok(ScanSnapshot {
+                networks: restrict_to_band(
+                    crate::network::demo::scanned_networks(),
+                    band,
+                ),
+                adapter_name: crate::network::demo::get_wifi_adapter_name()
+                    .ok()
+                    .flatten(),
+                manager_status: crate::network::demo::get_manager_status().ok(),
+                unmanaged_adapters: Vec::new(),
+                manager_owner: None,
+            }))
+        }
+        RuntimeRequest::RefreshSignals => RuntimeEvent::Signals(
+            crate::network::demo::get_access_point_signals()
+                .map_err(|error| error.to_string()),
+        ),
+        RuntimeRequest::ReadTraffic { interface } => RuntimeEvent::Traffic(
+            crate::network::demo::read_traffic_counters(&interface)
+                .map_err(|error| error.to_string()),
+        ),
+        RuntimeRequest::ReadBitrate => RuntimeEvent::Bitrate(
+            crate::network::demo::get_link_bitrate()
+                .map_err(|error| error.to_string()),
+        ),
+        RuntimeRequest::MeasureLatency { host } => RuntimeEvent::Latency(
+            crate::network::demo::measure_latency(&host)
+                .map_err(|error| error.to_string()),
+        ),
+        RuntimeRequest::CheckAutoconnect { network } => {
+            RuntimeEvent::AutoconnectBlock(
+                crate::network::demo::autoconnect_block(&network)
+                    .map_err(|error| error.to_string()),
+            )
+        }
+        RuntimeRequest::ClearAutoconnectBlock { network } => {
+            RuntimeEvent::AutoconnectCleared(
+                crate::network::demo::clear_autoconnect_block(&network)
+                    .map_err(|error| error.to_string()),
+            )
+        }
+        RuntimeRequest::ManageAdapter { interface } => {
+            RuntimeEvent::AdapterManaged(
+                crate::network::demo::set_adapter_managed(&interface)
+                    .map_err(|error| error.to_string()),
+            )
+        }
+        RuntimeRequest::StartNetworkManager => RuntimeEvent::ManagerStarted(
+            crate::network::demo::start_network_manager()
+                .map_err(|error| error.to_string()),
+        ),
+        RuntimeRequest::CheckManager => RuntimeEvent::ManagerChecked(Ok(())),
+        RuntimeRequest::LoadProfile { network } => {
+            RuntimeEvent::ProfileSettings(
+                crate::network::demo::saved_profile_settings(&network)
+                    .map_err(|error| error.to_string()),
+            )
+        }
+        RuntimeRequest::CheckConnectivity => RuntimeEvent::Connectivity(
+            crate::network::demo::check_connectivity()
+                .map_err(|error| error.to_string()),
+        ),
+        RuntimeRequest::OpenPortal { url } => RuntimeEvent::PortalOpened(Err(
+            format!("demo mode does not open {url}"),
+        )),
+        RuntimeRequest::SpeedTestChunk {
+            endpoint,
+            direction,
+        } => RuntimeEvent::SpeedTestChunk(
+            direction,
+            crate::network::demo::speed_test_chunk(&endpoint, direction)
+                .map_err(|error| error.to_string()),
+        ),
+        RuntimeRequest::LoadSavedPassword { network } => {
+            RuntimeEvent::SavedPassword(
+                crate::network::demo::get_saved_password(&network)
+                    .map_err(|error| error.to_string()),
+            )
+        }
+        RuntimeRequest::ListSavedProfiles => RuntimeEvent::SavedProfiles(
+            crate::network::demo::list_saved_profiles()
+                .map_err(|error| error.to_string()),
+        ),
+        RuntimeRequest::ForgetProfiles { profiles } => {
+            RuntimeEvent::ProfilesForgotten(
+                crate::network::demo::forget_profiles(&profiles)
+                    .map_err(|error| error.to_string()),
+            )
+        }
+        RuntimeRequest::ExportProfiles {
+            profiles,
+            directory,
+            include_secrets,
+        } => RuntimeEvent::ProfilesExported(
+            crate::network::demo::export_profiles(
+                &profiles,
+                &directory,
+                include_secrets,
+            )
+            .map_err(|error| error.to_string()),
+        ),
+        RuntimeRequest::SetProfileScope { profile, owner } => {
+            RuntimeEvent::ProfileScopeChanged(
+                owner.clone(),
+                crate::network::demo::set_profile_owner(
+                    &profile,
+                    owner.as_deref(),
+                )
+                .map_err(|error| error.to_string()),
+            )
+        }
+        RuntimeRequest::RenameProfile { profile, name } => {
+            let result = crate::network::demo::rename_profile(&profile, &name)
+                .map_err(|error| error.to_string());
+            RuntimeEvent::ProfileRenamed(name, result)
+        }
+        RuntimeRequest::SetProfileBand { profile, band } => {
+            RuntimeEvent::ProfileBandChanged(
+                band,
+                crate::network::demo::set_profile_band(&profile, band)
+                    .map_err(|error| error.to_string()),
+            )
+        }
+        RuntimeRequest::SetProfileBssid { profile, bssid } => {
+            let result = crate::network::demo::set_profile_bssid(
+                &profile,
+                bssid.as_deref(),
+            )
+            .map_err(|error| error.to_string());
+            RuntimeEvent::ProfileBssidSet(bssid, result)
+        }
+        RuntimeRequest::SetProfileLink { profile, setting } => {
+            let result =
+                crate::network::demo::set_profile_link(&profile, &setting)
+                    .map_err(|error| error.to_string());
+            RuntimeEvent::ProfileLinkSet(setting, result)
+        }
+        RuntimeRequest::SetProfileVpn { profile, vpn } => {
+            let result =
+                crate::network::demo::set_profile_vpn(&profile, vpn.as_deref())
+                    .map_err(|error| error.to_string());
+            RuntimeEvent::ProfileVpnSet(vpn, result)
+        }
+        RuntimeRequest::ListDevices { kind } => RuntimeEvent::Devices(
+            crate::network::demo::list_devices(kind)
+                .map_err(|error| error.to_string()),
+        ),
+        RuntimeRequest::SetDeviceProfile { interface, profile } => {
+            let result = crate::network::demo::set_device_profile(
+                &interface,
+                profile.as_ref(),
+            )
+            .map_err(|error| error.to_string());
+            RuntimeEvent::DeviceProfileSet(interface, profile, result)
+        }
+        RuntimeRequest::ReadNetworkManagerLog { filters } => {
+            RuntimeEvent::NetworkManagerLog(
+                crate::network::demo::network_manager_log(&filters)
+                    .map_err(|error| error.to_string()),
+            )
+        }
+        RuntimeRequest::FindP2pPeers => RuntimeEvent::P2pPeers(
+            crate::network::demo::find_p2p_peers()
+                .map_err(|error| error.to_string()),
+        ),
+        RuntimeRequest::ConnectP2pPeer { peer } => {
+            let result = crate::network::demo::connect_p2p_peer(&peer)
+                .map_err(|error| error.to_string());
+            RuntimeEvent::P2pPeerConnected(peer.name, result)
+        }
+        RuntimeRequest::PinAccessPoint { network, bssid } => {
+            let result =
+                crate::network::demo::pin_network_bssid(&network, &bssid)
+                    .map_err(|error| error.to_string());
+            RuntimeEvent::AccessPointPinned(bssid, result)
+        }
+        RuntimeRequest::SetProfilePassword { profile, password } => {
+            RuntimeEvent::ProfilePasswordSet(
+                crate::network::demo::set_profile_password(&profile, &password)
+                    .map_err(|error| error.to_string()),
+            )
+        }
+        RuntimeRequest::CloneProfile { profile, name } => {
+            RuntimeEvent::ProfileCloned(
+                crate::network::demo::clone_profile(&profile, &name)
+                    .map_err(|error| error.to_string()),
+            )
+        }
+        RuntimeRequest::Connect {
+            network,
+            passphrase,
+            guest,
+            options,
+            cancel,
+            progress,
+        } => {
+            let result = crate::network::demo::connect_with_options(
+                connection_request(&network, passphrase.as_deref(), guest),
+                &options,
+                &cancel,
+                &progress,
+            );
+            connect_event(result)
+        }
+        RuntimeRequest::Disconnect { network } => RuntimeEvent::Disconnect(
+            crate::network::demo::disconnect_from_network(&network)
+                .map_err(|error| error.to_string()),
+        ),
+        RuntimeRequest::Reconnect { network, cancel } => {
+            RuntimeEvent::Reconnect(
+                crate::network::demo::reconnect_network(&network, &cancel)
+                    .map_err(|error| error.to_string()),
+            )
+        }
+        RuntimeRequest::RenewLease { network, cancel } => {
+            RuntimeEvent::RenewLease(
+                crate::network::demo::renew_dhcp_lease(&network, &cancel)
+                    .map_err(|error| error.to_string()),
+            )
+        }
+        RuntimeRequest::SteerTo { network, bssid } => {
+            let result =
+                crate::network::demo::steer_to_access_point(&network, &bssid)
+                    .map_err(|error| error.to_string());
+            RuntimeEvent::Steered(bssid, result)
+        }
+        // Demo guest connects leave no profile behind.
+        RuntimeRequest::EndGuestSession { .. } => {
+            RuntimeEvent::GuestSessionEnded(Ok(()))
+        }
+    }
+}
+
 #[cfg(feature = "demo")]
 pub(crate) fn default_runtime_driver() -> Box<dyn RuntimeBackendDriver> {
     Box::new(DemoRuntimeDriver::default())
@@ -180,89 +491,742 @@ struct NetworkManagerRuntimeDriver {
     pending_event: Option<Receiver<RuntimeEvent>>,
 }
 
+/// Runs a blocking NetworkManager call off the async runtime and forwards its
+/// event, turning a panicked task into an event built by `on_task_failure`.
+#[cfg(not(feature = "demo"))]
+fn spawn_blocking_event(
+    sender: mpsc::Sender<RuntimeEvent>,
+    task: impl FnOnce() -> RuntimeEvent + Send + 'static,
+    on_task_failure: impl FnOnce(String) -> RuntimeEvent + Send + 'static,
+) {
+    tokio::spawn(async move {
+        let event = match tokio::task::spawn_blocking(task).await {
+            Ok(event) => event,
+            Err(error) => on_task_failure(error.to_string()),
+        };
+
+        let _ = sender.send(event);
+    });
+}
+
 #[cfg(not(feature = "demo"))]
 impl RuntimeBackendDriver for NetworkManagerRuntimeDriver {
     fn begin(&mut self, request: RuntimeRequest) {
         let (sender, receiver) = mpsc::channel();
 
         match request {
-            RuntimeRequest::Scan => {
-                tokio::spawn(async move {
-                    let event = match tokio::task::spawn_blocking(|| {
-                        let networks = crate::network::networkmanager::scan_wifi_networks_blocking();
-                        let adapter_name = crate::network::networkmanager::get_wifi_adapter_name()
+            RuntimeRequest::Scan { fresh, band } => spawn_blocking_event(
+                sender,
+                move || {
+                    // Every request opens its own bus connection and lists
+                    // devices afresh, so a restarted NetworkManager is picked
+                    // up by the next scan without any state to rebuild.
+                    let manager_owner =
+                        match crate::network::networkmanager::manager_bus_owner(
+                        ) {
+                            Ok(owner) => owner,
+                            Err(error) => {
+                                return RuntimeEvent::ManagerUnavailable(
+                                    error.to_string(),
+                                );
+                            }
+                        };
+                    let networks = crate::network::networkmanager::scan_wifi_networks_blocking(fresh)
+                        .map(|networks| restrict_to_band(networks, band));
+                    let adapter_name =
+                        crate::network::networkmanager::get_wifi_adapter_name()
                             .ok()
                             .flatten();
+                    let manager_status =
+                        crate::network::networkmanager::get_manager_status()
+                            .ok();
+                    let unmanaged_adapters =
+                        crate::network::networkmanager::get_unmanaged_wifi_adapters()
+                            .unwrap_or_default();
 
-                        match networks {
-                            Ok(networks) => RuntimeEvent::Scan(Ok(ScanSnapshot {
-                                networks,
-                                adapter_name,
-                            })),
-                            Err(error) => RuntimeEvent::Scan(Err(error.to_string())),
-                        }
-                    })
-                    .await
-                    {
-                        Ok(event) => event,
-                        Err(error) => RuntimeEvent::Scan(Err(format!(
-                            "runtime scan task failed: {error}"
-                        ))),
-                    };
-
-                    let _ = sender.send(event);
-                });
+                    match networks {
+                        Ok(networks) => RuntimeEvent::Scan(Ok(ScanSnapshot {
+                            networks,
+                            adapter_name,
+                            manager_status,
+                            unmanaged_adapters,
+                            manager_owner: Some(manager_owner),
+                        })),
+                        // NetworkManager going away mid-scan is not a
+                        // scan failure.
+                        Err(error) => match crate::network::networkmanager::manager_bus_owner() {
+                            Ok(_) => RuntimeEvent::Scan(Err(error.to_string())),
+                            Err(unavailable) => RuntimeEvent::ManagerUnavailable(
+                                unavailable.to_string(),
+                            ),
+                        },
+                    }
+                },
+                |error| {
+                    RuntimeEvent::Scan(Err(format!(
+                        "runtime scan task failed: {error}"
+                    )))
+                },
+            ),
+            RuntimeRequest::RefreshSignals => spawn_blocking_event(
+                sender,
+                || {
+                    RuntimeEvent::Signals(
+                        crate::network::networkmanager::get_access_point_signals()
+                            .map_err(|error| error.to_string()),
+                    )
+                },
+                |error| {
+                    RuntimeEvent::Signals(Err(format!(
+                        "runtime signal refresh task failed: {error}"
+                    )))
+                },
+            ),
+            RuntimeRequest::ReadTraffic { interface } => spawn_blocking_event(
+                sender,
+                move || {
+                    RuntimeEvent::Traffic(
+                        crate::traffic::read_interface_counters(&interface)
+                            .map_err(|error| error.to_string()),
+                    )
+                },
+                |error| {
+                    RuntimeEvent::Traffic(Err(format!(
+                        "runtime traffic read task failed: {error}"
+                    )))
+                },
+            ),
+            RuntimeRequest::ReadBitrate => spawn_blocking_event(
+                sender,
+                || {
+                    RuntimeEvent::Bitrate(
+                        crate::network::networkmanager::get_link_bitrate()
+                            .map_err(|error| error.to_string()),
+                    )
+                },
+                |error| {
+                    RuntimeEvent::Bitrate(Err(format!(
+                        "runtime bitrate read task failed: {error}"
+                    )))
+                },
+            ),
+            RuntimeRequest::MeasureLatency { host } => spawn_blocking_event(
+                sender,
+                move || {
+                    RuntimeEvent::Latency(
+                        crate::network::networkmanager::measure_latency(&host)
+                            .map_err(|error| error.to_string()),
+                    )
+                },
+                |error| {
+                    RuntimeEvent::Latency(Err(format!(
+                        "runtime latency task failed: {error}"
+                    )))
+                },
+            ),
+            RuntimeRequest::CheckAutoconnect { network } => {
+                spawn_blocking_event(
+                    sender,
+                    move || {
+                        RuntimeEvent::AutoconnectBlock(
+                            crate::network::networkmanager::autoconnect_block(
+                                &network,
+                            )
+                            .map_err(|error| error.to_string()),
+                        )
+                    },
+                    |error| {
+                        RuntimeEvent::AutoconnectBlock(Err(format!(
+                            "runtime autoconnect check task failed: {error}"
+                        )))
+                    },
+                )
+            }
+            RuntimeRequest::ClearAutoconnectBlock { network } => {
+                spawn_blocking_event(
+                    sender,
+                    move || {
+                        RuntimeEvent::AutoconnectCleared(
+                            crate::network::networkmanager::clear_autoconnect_block(
+                                &network,
+                            )
+                            .map_err(|error| error.to_string()),
+                        )
+                    },
+                    |error| {
+                        RuntimeEvent::AutoconnectCleared(Err(format!(
+                            "runtime autoconnect clear task failed: {error}"
+                        )))
+                    },
+                )
+            }
+            RuntimeRequest::ManageAdapter { interface } => {
+                spawn_blocking_event(
+                    sender,
+                    move || {
+                        RuntimeEvent::AdapterManaged(
+                            crate::network::networkmanager::set_adapter_managed(
+                                &interface,
+                            )
+                            .map_err(|error| error.to_string()),
+                        )
+                    },
+                    |error| {
+                        RuntimeEvent::AdapterManaged(Err(format!(
+                            "runtime adapter manage task failed: {error}"
+                        )))
+                    },
+                )
+            }
+            RuntimeRequest::CheckManager => spawn_blocking_event(
+                sender,
+                || {
+                    RuntimeEvent::ManagerChecked(
+                        crate::network::networkmanager::manager_bus_owner()
+                            .map(|_| ())
+                            .map_err(|error| error.to_string()),
+                    )
+                },
+                |error| {
+                    RuntimeEvent::ManagerChecked(Err(format!(
+                        "runtime NetworkManager check task failed: {error}"
+                    )))
+                },
+            ),
+            RuntimeRequest::StartNetworkManager => spawn_blocking_event(
+                sender,
+                || {
+                    RuntimeEvent::ManagerStarted(
+                        crate::network::networkmanager::start_network_manager()
+                            .map_err(|error| error.to_string()),
+                    )
+                },
+                |error| {
+                    RuntimeEvent::ManagerStarted(Err(format!(
+                        "runtime NetworkManager start task failed: {error}"
+                    )))
+                },
+            ),
+            RuntimeRequest::LoadProfile { network } => {
+                spawn_blocking_event(
+                    sender,
+                    move || {
+                        RuntimeEvent::ProfileSettings(
+                        crate::network::networkmanager::saved_profile_settings(&network)
+                            .map_err(|error| error.to_string()),
+                    )
+                    },
+                    |error| {
+                        RuntimeEvent::ProfileSettings(Err(format!(
+                            "runtime proxy read task failed: {error}"
+                        )))
+                    },
+                )
+            }
+            RuntimeRequest::CheckConnectivity => spawn_blocking_event(
+                sender,
+                || {
+                    RuntimeEvent::Connectivity(
+                        crate::network::networkmanager::check_connectivity()
+                            .map_err(|error| error.to_string()),
+                    )
+                },
+                |error| {
+                    RuntimeEvent::Connectivity(Err(format!(
+                        "runtime connectivity task failed: {error}"
+                    )))
+                },
+            ),
+            RuntimeRequest::OpenPortal { url } => spawn_blocking_event(
+                sender,
+                move || {
+                    RuntimeEvent::PortalOpened(
+                        crate::portal::open_in_browser(&url)
+                            .map_err(|error| error.to_string()),
+                    )
+                },
+                |error| {
+                    RuntimeEvent::PortalOpened(Err(format!(
+                        "runtime browser task failed: {error}"
+                    )))
+                },
+            ),
+            RuntimeRequest::SpeedTestChunk {
+                endpoint,
+                direction,
+            } => spawn_blocking_event(
+                sender,
+                move || {
+                    RuntimeEvent::SpeedTestChunk(
+                        direction,
+                        crate::speedtest::transfer_chunk(&endpoint, direction)
+                            .map_err(|error| error.to_string()),
+                    )
+                },
+                move |error| {
+                    RuntimeEvent::SpeedTestChunk(
+                        direction,
+                        Err(format!("runtime speed test task failed: {error}")),
+                    )
+                },
+            ),
+            RuntimeRequest::LoadSavedPassword { network } => {
+                spawn_blocking_event(
+                    sender,
+                    move || {
+                        RuntimeEvent::SavedPassword(
+                            crate::network::networkmanager::get_saved_password(
+                                &network,
+                            )
+                            .map_err(|error| error.to_string()),
+                        )
+                    },
+                    |error| {
+                        RuntimeEvent::SavedPassword(Err(format!(
+                            "runtime secrets task failed: {error}"
+                        )))
+                    },
+                )
+            }
+            RuntimeRequest::ListSavedProfiles => spawn_blocking_event(
+                sender,
+                || {
+                    RuntimeEvent::SavedProfiles(
+                        crate::network::networkmanager::list_saved_profiles()
+                            .map_err(|error| error.to_string()),
+                    )
+                },
+                |error| {
+                    RuntimeEvent::SavedProfiles(Err(format!(
+                        "runtime profiles task failed: {error}"
+                    )))
+                },
+            ),
+            RuntimeRequest::ForgetProfiles { profiles } => {
+                spawn_blocking_event(
+                    sender,
+                    move || {
+                        RuntimeEvent::ProfilesForgotten(
+                            crate::network::networkmanager::forget_profiles(
+                                &profiles,
+                            )
+                            .map_err(|error| error.to_string()),
+                        )
+                    },
+                    |error| {
+                        RuntimeEvent::ProfilesForgotten(Err(format!(
+                            "runtime forget task failed: {error}"
+                        )))
+                    },
+                )
+            }
+            RuntimeRequest::ExportProfiles {
+                profiles,
+                directory,
+                include_secrets,
+            } => spawn_blocking_event(
+                sender,
+                move || {
+                    RuntimeEvent::ProfilesExported(
+                        crate::network::networkmanager::export_profiles(
+                            &profiles,
+                            &directory,
+                            include_secrets,
+                        )
+                        .map_err(|error| error.to_string()),
+                    )
+                },
+                |error| {
+                    RuntimeEvent::ProfilesExported(Err(format!(
+                        "runtime export task failed: {error}"
+                    )))
+                },
+            ),
+            RuntimeRequest::SetProfileScope { profile, owner } => {
+                let failed_owner = owner.clone();
+                spawn_blocking_event(
+                    sender,
+                    move || {
+                        let result =
+                            crate::network::networkmanager::set_profile_owner(
+                                &profile,
+                                owner.as_deref(),
+                            )
+                            .map_err(|error| error.to_string());
+                        RuntimeEvent::ProfileScopeChanged(owner, result)
+                    },
+                    move |error| {
+                        RuntimeEvent::ProfileScopeChanged(
+                            failed_owner,
+                            Err(format!("runtime scope task failed: {error}")),
+                        )
+                    },
+                )
+            }
+            RuntimeRequest::RenameProfile { profile, name } => {
+                let failed_name = name.clone();
+                spawn_blocking_event(
+                    sender,
+                    move || {
+                        let result =
+                            crate::network::networkmanager::rename_profile(
+                                &profile, &name,
+                            )
+                            .map_err(|error| error.to_string());
+                        RuntimeEvent::ProfileRenamed(name, result)
+                    },
+                    move |error| {
+                        RuntimeEvent::ProfileRenamed(
+                            failed_name,
+                            Err(format!("runtime rename task failed: {error}")),
+                        )
+                    },
+                )
+            }
+            RuntimeRequest::SetProfileBand { profile, band } => {
+                spawn_blocking_event(
+                    sender,
+                    move || {
+                        RuntimeEvent::ProfileBandChanged(
+                            band,
+                            crate::network::networkmanager::set_profile_band(
+                                &profile, band,
+                            )
+                            .map_err(|error| error.to_string()),
+                        )
+                    },
+                    move |error| {
+                        RuntimeEvent::ProfileBandChanged(
+                            band,
+                            Err(format!("runtime band task failed: {error}")),
+                        )
+                    },
+                )
+            }
+            RuntimeRequest::SetProfilePassword { profile, password } => {
+                spawn_blocking_event(
+                    sender,
+                    move || {
+                        RuntimeEvent::ProfilePasswordSet(
+                            crate::network::networkmanager::set_profile_password(
+                                &profile, &password,
+                            )
+                            .map_err(|error| error.to_string()),
+                        )
+                    },
+                    |error| {
+                        RuntimeEvent::ProfilePasswordSet(Err(format!(
+                            "runtime password task failed: {error}"
+                        )))
+                    },
+                )
+            }
+            RuntimeRequest::CloneProfile { profile, name } => {
+                spawn_blocking_event(
+                    sender,
+                    move || {
+                        RuntimeEvent::ProfileCloned(
+                            crate::network::networkmanager::clone_profile(
+                                &profile, &name,
+                            )
+                            .map_err(|error| error.to_string()),
+                        )
+                    },
+                    |error| {
+                        RuntimeEvent::ProfileCloned(Err(format!(
+                            "runtime clone task failed: {error}"
+                        )))
+                    },
+                )
             }
             RuntimeRequest::Connect {
                 network,
                 passphrase,
-            } => {
-                tokio::spawn(async move {
-                    let event = match tokio::task::spawn_blocking(move || {
-                        let result = match passphrase.as_deref() {
-                            Some(passphrase) => crate::network::networkmanager::connect_to_network(
-                                ConnectionRequest::Secured {
-                                    network: &network,
-                                    passphrase,
-                                },
-                            ),
-                            None => crate::network::networkmanager::connect_to_network(
-                                ConnectionRequest::Open { network: &network },
+                guest,
+                options,
+                cancel,
+                progress,
+            } => spawn_blocking_event(
+                sender,
+                move || {
+                    let result =
+                        crate::network::networkmanager::connect_with_options(
+                            connection_request(
+                                &network,
+                                passphrase.as_deref(),
+                                guest,
                             ),
-                        };
+                            &options,
+                            &cancel,
+                            &progress,
+                        );
 
-                        RuntimeEvent::Connect(result.map_err(|error| error.to_string()))
-                    })
-                    .await
-                    {
-                        Ok(event) => event,
-                        Err(error) => RuntimeEvent::Connect(Err(format!(
-                            "runtime connect task failed: {error}"
-                        ))),
-                    };
-
-                    let _ = sender.send(event);
-                });
+                    connect_event(result)
+                },
+                |error| {
+                    RuntimeEvent::Connect(Err(format!(
+                        "runtime connect task failed: {error}"
+                    )))
+                },
+            ),
+            RuntimeRequest::Disconnect { network } => spawn_blocking_event(
+                sender,
+                move || {
+                    RuntimeEvent::Disconnect(
+                        crate::network::networkmanager::disconnect_from_network(&network)
+                            .map_err(|error| error.to_string()),
+                    )
+                },
+                |error| {
+                    RuntimeEvent::Disconnect(Err(format!(
+                        "runtime disconnect task failed: {error}"
+                    )))
+                },
+            ),
+            RuntimeRequest::Reconnect { network, cancel } => {
+                spawn_blocking_event(
+                    sender,
+                    move || {
+                        RuntimeEvent::Reconnect(
+                            crate::network::networkmanager::reconnect_network(
+                                &network, &cancel,
+                            )
+                            .map_err(|error| error.to_string()),
+                        )
+                    },
+                    |error| {
+                        RuntimeEvent::Reconnect(Err(format!(
+                            "runtime reconnect task failed: {error}"
+                        )))
+                    },
+                )
+            }
+            RuntimeRequest::RenewLease { network, cancel } => {
+                spawn_blocking_event(
+                    sender,
+                    move || {
+                        RuntimeEvent::RenewLease(
+                            crate::network::networkmanager::renew_dhcp_lease(
+                                &network, &cancel,
+                            )
+                            .map_err(|error| error.to_string()),
+                        )
+                    },
+                    |error| {
+                        RuntimeEvent::RenewLease(Err(format!(
+                            "runtime lease renewal task failed: {error}"
+                        )))
+                    },
+                )
             }
-            RuntimeRequest::Disconnect { network } => {
-                tokio::spawn(async move {
-                    let event = match tokio::task::spawn_blocking(move || {
-                        RuntimeEvent::Disconnect(
-                            crate::network::networkmanager::disconnect_from_network(&network)
+            RuntimeRequest::SetProfileBssid { profile, bssid } => {
+                let pinned = bssid.clone();
+                spawn_blocking_event(
+                    sender,
+                    move || {
+                        let result =
+                            crate::network::networkmanager::set_profile_bssid(
+                                &profile,
+                                bssid.as_deref(),
+                            )
+                            .map_err(|error| error.to_string());
+                        RuntimeEvent::ProfileBssidSet(bssid, result)
+                    },
+                    move |error| {
+                        RuntimeEvent::ProfileBssidSet(
+                            pinned,
+                            Err(format!("runtime BSSID task failed: {error}")),
+                        )
+                    },
+                );
+            }
+            RuntimeRequest::SetProfileLink { profile, setting } => {
+                let changed = setting.clone();
+                spawn_blocking_event(
+                    sender,
+                    move || {
+                        let result =
+                            crate::network::networkmanager::set_profile_link(
+                                &profile, &setting,
+                            )
+                            .map_err(|error| error.to_string());
+                        RuntimeEvent::ProfileLinkSet(setting, result)
+                    },
+                    move |error| {
+                        RuntimeEvent::ProfileLinkSet(
+                            changed,
+                            Err(format!("runtime link task failed: {error}")),
+                        )
+                    },
+                );
+            }
+            RuntimeRequest::SetProfileVpn { profile, vpn } => {
+                let changed = vpn.clone();
+                spawn_blocking_event(
+                    sender,
+                    move || {
+                        let result =
+                            crate::network::networkmanager::set_profile_vpn(
+                                &profile,
+                                vpn.as_deref(),
+                            )
+                            .map_err(|error| error.to_string());
+                        RuntimeEvent::ProfileVpnSet(vpn, result)
+                    },
+                    move |error| {
+                        RuntimeEvent::ProfileVpnSet(
+                            changed,
+                            Err(format!("runtime VPN task failed: {error}")),
+                        )
+                    },
+                );
+            }
+            RuntimeRequest::ListDevices { kind } => spawn_blocking_event(
+                sender,
+                move || {
+                    RuntimeEvent::Devices(
+                        crate::network::networkmanager::list_devices(kind)
+                            .map_err(|error| error.to_string()),
+                    )
+                },
+                |error| {
+                    RuntimeEvent::Devices(Err(format!(
+                        "runtime devices task failed: {error}"
+                    )))
+                },
+            ),
+            RuntimeRequest::SetDeviceProfile { interface, profile } => {
+                let (target, requested) = (interface.clone(), profile.clone());
+                spawn_blocking_event(
+                    sender,
+                    move || {
+                        let result =
+                            crate::network::networkmanager::set_device_profile(
+                                &interface,
+                                profile.as_ref(),
+                            )
+                            .map_err(|error| error.to_string());
+                        RuntimeEvent::DeviceProfileSet(
+                            interface, profile, result,
+                        )
+                    },
+                    move |error| {
+                        RuntimeEvent::DeviceProfileSet(
+                            target,
+                            requested,
+                            Err(format!("runtime wired task failed: {error}")),
+                        )
+                    },
+                );
+            }
+            RuntimeRequest::ReadNetworkManagerLog { filters } => {
+                spawn_blocking_event(
+                    sender,
+                    move || {
+                        RuntimeEvent::NetworkManagerLog(
+                            crate::journal::read_network_manager_log(&filters)
                                 .map_err(|error| error.to_string()),
                         )
-                    })
-                    .await
-                    {
-                        Ok(event) => event,
-                        Err(error) => RuntimeEvent::Disconnect(Err(format!(
-                            "runtime disconnect task failed: {error}"
-                        ))),
-                    };
-
-                    let _ = sender.send(event);
-                });
+                    },
+                    |error| {
+                        RuntimeEvent::NetworkManagerLog(Err(format!(
+                            "runtime journal task failed: {error}"
+                        )))
+                    },
+                )
+            }
+            RuntimeRequest::FindP2pPeers => spawn_blocking_event(
+                sender,
+                move || {
+                    RuntimeEvent::P2pPeers(
+                        crate::network::networkmanager::find_p2p_peers()
+                            .map_err(|error| error.to_string()),
+                    )
+                },
+                |error| {
+                    RuntimeEvent::P2pPeers(Err(format!(
+                        "runtime peer search task failed: {error}"
+                    )))
+                },
+            ),
+            RuntimeRequest::ConnectP2pPeer { peer } => {
+                let name = peer.name.clone();
+                spawn_blocking_event(
+                    sender,
+                    move || {
+                        let result =
+                            crate::network::networkmanager::connect_p2p_peer(
+                                &peer,
+                            )
+                            .map_err(|error| error.to_string());
+                        RuntimeEvent::P2pPeerConnected(peer.name, result)
+                    },
+                    move |error| {
+                        RuntimeEvent::P2pPeerConnected(
+                            name,
+                            Err(format!("runtime peer task failed: {error}")),
+                        )
+                    },
+                );
+            }
+            RuntimeRequest::PinAccessPoint { network, bssid } => {
+                let target = bssid.clone();
+                spawn_blocking_event(
+                    sender,
+                    move || {
+                        let result =
+                            crate::network::networkmanager::pin_network_bssid(
+                                &network, &bssid,
+                            )
+                            .map_err(|error| error.to_string());
+                        RuntimeEvent::AccessPointPinned(bssid, result)
+                    },
+                    move |error| {
+                        RuntimeEvent::AccessPointPinned(
+                            target,
+                            Err(format!("runtime pin task failed: {error}")),
+                        )
+                    },
+                );
+            }
+            RuntimeRequest::SteerTo { network, bssid } => {
+                let target = bssid.clone();
+                spawn_blocking_event(
+                    sender,
+                    move || {
+                        let result =
+                            crate::network::networkmanager::steer_to_access_point(
+                                &network, &bssid,
+                            )
+                            .map_err(|error| error.to_string());
+                        RuntimeEvent::Steered(bssid, result)
+                    },
+                    move |error| {
+                        RuntimeEvent::Steered(
+                            target,
+                            Err(format!(
+                                "runtime steering task failed: {error}"
+                            )),
+                        )
+                    },
+                )
             }
+            RuntimeRequest::EndGuestSession { ssid } => spawn_blocking_event(
+                sender,
+                move || {
+                    RuntimeEvent::GuestSessionEnded(
+                        crate::network::networkmanager::delete_guest_profile(
+                            &ssid,
+                        )
+                        .map_err(|error| error.to_string()),
+                    )
+                },
+                |error| {
+                    RuntimeEvent::GuestSessionEnded(Err(format!(
+                        "runtime guest cleanup task failed: {error}"
+                    )))
+                },
+            ),
         }
 
         self.pending_event = Some(receiver);
@@ -273,9 +1237,379 @@ impl RuntimeBackendDriver for NetworkManagerRuntimeDriver {
     }
 }
 
+/// The interface to drive through wpa_supplicant: set only when
+/// NetworkManager is not on the system bus but wpa_supplicant is running.
+#[cfg(not(feature = "demo"))]
+fn wpa_supplicant_interface() -> Option<String> {
+    if crate::network::networkmanager::manager_bus_owner().is_ok() {
+        return None;
+    }
+    crate::network::wpa_supplicant::control_interface()
+}
+
+#[cfg(not(feature = "demo"))]
+#[derive(Debug, Clone)]
+pub struct WpaSupplicantBackend {
+    interface: String,
+}
+
+#[cfg(not(feature = "demo"))]
+impl NetworkBackend for WpaSupplicantBackend {
+    fn connected_ssid(&self) -> Result<Option<String>, Box<dyn Error>> {
+        crate::network::wpa_supplicant::get_connected_ssid(&self.interface)
+    }
+
+    fn adapter_name(&self) -> Result<Option<String>, Box<dyn Error>> {
+        Ok(Some(self.interface.clone()))
+    }
+
+    fn scan_networks(
+        &self,
+    ) -> BackendFuture<'_, Result<Vec<WifiNetwork>, Box<dyn Error>>> {
+        Box::pin(async move {
+            crate::network::wpa_supplicant::scan_networks(
+                &self.interface,
+                true,
+                None,
+            )
+        })
+    }
+
+    fn connect(
+        &self,
+        request: ConnectionRequest<'_>,
+    ) -> Result<(), Box<dyn Error>> {
+        crate::network::wpa_supplicant::connect(
+            &self.interface,
+            request,
+            &CancelToken::default(),
+            &ActivationProgress::default(),
+        )
+    }
+
+    fn disconnect(&self, _network: &WifiNetwork) -> Result<(), Box<dyn Error>> {
+        crate::network::wpa_supplicant::disconnect(&self.interface)
+    }
+}
+
+#[cfg(not(feature = "demo"))]
+struct WpaSupplicantRuntimeDriver {
+    interface: String,
+    pending_event: Option<Receiver<RuntimeEvent>>,
+}
+
+/// The failure a request gets when only wpa_supplicant is there to serve it.
+#[cfg(not(feature = "demo"))]
+fn wpa_supplicant_unsupported_event(request: RuntimeRequest) -> RuntimeEvent {
+    let error = || {
+        "Not available without NetworkManager; nm-wifi is driving \
+         wpa_supplicant directly"
+            .to_string()
+    };
+    match request {
+        RuntimeRequest::Scan { .. } => RuntimeEvent::Scan(Err(error())),
+        RuntimeRequest::RefreshSignals => RuntimeEvent::Signals(Err(error())),
+        RuntimeRequest::ReadTraffic { .. } => {
+            RuntimeEvent::Traffic(Err(error()))
+        }
+        RuntimeRequest::ReadBitrate => RuntimeEvent::Bitrate(Err(error())),
+        RuntimeRequest::MeasureLatency { .. } => {
+            RuntimeEvent::Latency(Err(error()))
+        }
+        RuntimeRequest::CheckAutoconnect { .. } => {
+            RuntimeEvent::AutoconnectBlock(Err(error()))
+        }
+        RuntimeRequest::ClearAutoconnectBlock { .. } => {
+            RuntimeEvent::AutoconnectCleared(Err(error()))
+        }
+        RuntimeRequest::ManageAdapter { .. } => {
+            RuntimeEvent::AdapterManaged(Err(error()))
+        }
+        RuntimeRequest::StartNetworkManager => {
+            RuntimeEvent::ManagerStarted(Err(error()))
+        }
+        RuntimeRequest::CheckManager => {
+            RuntimeEvent::ManagerChecked(Err(error()))
+        }
+        RuntimeRequest::LoadProfile { .. } => {
+            RuntimeEvent::ProfileSettings(Err(error()))
+        }
+        RuntimeRequest::CheckConnectivity => {
+            RuntimeEvent::Connectivity(Err(error()))
+        }
+        RuntimeRequest::OpenPortal { .. } => {
+            RuntimeEvent::PortalOpened(Err(error()))
+        }
+        RuntimeRequest::SpeedTestChunk { direction, .. } => {
+            RuntimeEvent::SpeedTestChunk(direction, Err(error()))
+        }
+        RuntimeRequest::LoadSavedPassword { .. } => {
+            RuntimeEvent::SavedPassword(Err(error()))
+        }
+        RuntimeRequest::ListSavedProfiles => {
+            RuntimeEvent::SavedProfiles(Err(error()))
+        }
+        RuntimeRequest::ForgetProfiles { .. } => {
+            RuntimeEvent::ProfilesForgotten(Err(error()))
+        }
+        RuntimeRequest::ExportProfiles { .. } => {
+            RuntimeEvent::ProfilesExported(Err(error()))
+        }
+        RuntimeRequest::SetProfileScope { owner, .. } => {
+            RuntimeEvent::ProfileScopeChanged(owner, Err(error()))
+        }
+        RuntimeRequest::RenameProfile { name, .. } => {
+            RuntimeEvent::ProfileRenamed(name, Err(error()))
+        }
+        RuntimeRequest::CloneProfile { .. } => {
+            RuntimeEvent::ProfileCloned(Err(error()))
+        }
+        RuntimeRequest::SetProfilePassword { .. } => {
+            RuntimeEvent::ProfilePasswordSet(Err(error()))
+        }
+        RuntimeRequest::SetProfileBand { band, .. } => {
+            RuntimeEvent::ProfileBandChanged(band, Err(error()))
+        }
+        RuntimeRequest::SetProfileBssid { bssid, .. } => {
+            RuntimeEvent::ProfileBssidSet(bssid, Err(error()))
+        }
+        RuntimeRequest::SetProfileLink { setting, .. } => {
+            RuntimeEvent::ProfileLinkSet(setting, Err(error()))
+        }
+        RuntimeRequest::SetProfileVpn { vpn, .. } => {
+            RuntimeEvent::ProfileVpnSet(vpn, Err(error()))
+        }
+        RuntimeRequest::ListDevices { .. } => {
+            RuntimeEvent::Devices(Err(error()))
+        }
+        RuntimeRequest::SetDeviceProfile {
+            interface, profile, ..
+        } => RuntimeEvent::DeviceProfileSet(interface, profile, Err(error())),
+        RuntimeRequest::ReadNetworkManagerLog { .. } => {
+            RuntimeEvent::NetworkManagerLog(Err(error()))
+        }
+        RuntimeRequest::FindP2pPeers => RuntimeEvent::P2pPeers(Err(error())),
+        RuntimeRequest::ConnectP2pPeer { peer } => {
+            RuntimeEvent::P2pPeerConnected(peer.name, Err(error()))
+        }
+        RuntimeRequest::PinAccessPoint { bssid, .. } => {
+            RuntimeEvent::AccessPointPinned(bssid, Err(error()))
+        }
+        RuntimeRequest::Connect { .. } => RuntimeEvent::Connect(Err(error())),
+        RuntimeRequest::Disconnect { .. } => {
+            RuntimeEvent::Disconnect(Err(error()))
+        }
+        RuntimeRequest::Reconnect { .. } => {
+            RuntimeEvent::Reconnect(Err(error()))
+        }
+        RuntimeRequest::RenewLease { .. } => {
+            RuntimeEvent::RenewLease(Err(error()))
+        }
+        RuntimeRequest::SteerTo { bssid, .. } => {
+            RuntimeEvent::Steered(bssid, Err(error()))
+        }
+        // Nothing to fail: a guest connect through wpa_supplicant is a plain
+        // open one, without a profile of its own to delete.
+        RuntimeRequest::EndGuestSession { .. } => {
+            RuntimeEvent::GuestSessionEnded(Ok(()))
+        }
+    }
+}
+
+#[cfg(not(feature = "demo"))]
+impl RuntimeBackendDriver for WpaSupplicantRuntimeDriver {
+    fn begin(&mut self, request: RuntimeRequest) {
+        let (sender, receiver) = mpsc::channel();
+        let interface = self.interface.clone();
+
+        match request {
+            RuntimeRequest::Scan { fresh, band } => spawn_blocking_event(
+                sender,
+                move || {
+                    RuntimeEvent::Scan(
+                        crate::network::wpa_supplicant::scan_networks(
+                            &interface, fresh, band,
+                        )
+                        .map(|networks| ScanSnapshot {
+                            networks,
+                            adapter_name: Some(interface),
+                            manager_status: None,
+                            unmanaged_adapters: Vec::new(),
+                            manager_owner: None,
+                        })
+                        .map_err(|error| error.to_string()),
+                    )
+                },
+                |error| {
+                    RuntimeEvent::Scan(Err(format!(
+                        "runtime scan task failed: {error}"
+                    )))
+                },
+            ),
+            RuntimeRequest::RefreshSignals => spawn_blocking_event(
+                sender,
+                move || {
+                    RuntimeEvent::Signals(
+                        crate::network::wpa_supplicant::get_access_point_signals(
+                            &interface,
+                        )
+                        .map_err(|error| error.to_string()),
+                    )
+                },
+                |error| {
+                    RuntimeEvent::Signals(Err(format!(
+                        "runtime signal refresh task failed: {error}"
+                    )))
+                },
+            ),
+            RuntimeRequest::ReadTraffic { interface } => spawn_blocking_event(
+                sender,
+                move || {
+                    RuntimeEvent::Traffic(
+                        crate::traffic::read_interface_counters(&interface)
+                            .map_err(|error| error.to_string()),
+                    )
+                },
+                |error| {
+                    RuntimeEvent::Traffic(Err(format!(
+                        "runtime traffic read task failed: {error}"
+                    )))
+                },
+            ),
+            // Without NetworkManager there is no gateway to read, so only
+            // the host is pinged.
+            RuntimeRequest::MeasureLatency { host } => spawn_blocking_event(
+                sender,
+                move || {
+                    RuntimeEvent::Latency(
+                        crate::latency::ping(&host)
+                            .map(|host| LatencySample {
+                                gateway: None,
+                                host,
+                            })
+                            .map_err(|error| error.to_string()),
+                    )
+                },
+                |error| {
+                    RuntimeEvent::Latency(Err(format!(
+                        "runtime latency task failed: {error}"
+                    )))
+                },
+            ),
+            RuntimeRequest::OpenPortal { url } => spawn_blocking_event(
+                sender,
+                move || {
+                    RuntimeEvent::PortalOpened(
+                        crate::portal::open_in_browser(&url)
+                            .map_err(|error| error.to_string()),
+                    )
+                },
+                |error| {
+                    RuntimeEvent::PortalOpened(Err(format!(
+                        "runtime browser task failed: {error}"
+                    )))
+                },
+            ),
+            RuntimeRequest::SpeedTestChunk {
+                endpoint,
+                direction,
+            } => spawn_blocking_event(
+                sender,
+                move || {
+                    RuntimeEvent::SpeedTestChunk(
+                        direction,
+                        crate::speedtest::transfer_chunk(&endpoint, direction)
+                            .map_err(|error| error.to_string()),
+                    )
+                },
+                move |error| {
+                    RuntimeEvent::SpeedTestChunk(
+                        direction,
+                        Err(format!("runtime speed test task failed: {error}")),
+                    )
+                },
+            ),
+            RuntimeRequest::Connect {
+                network,
+                passphrase,
+                guest,
+                cancel,
+                progress,
+                ..
+            } => spawn_blocking_event(
+                sender,
+                move || {
+                    connect_event(crate::network::wpa_supplicant::connect(
+                        &interface,
+                        connection_request(
+                            &network,
+                            passphrase.as_deref(),
+                            guest,
+                        ),
+                        &cancel,
+                        &progress,
+                    ))
+                },
+                |error| {
+                    RuntimeEvent::Connect(Err(format!(
+                        "runtime connect task failed: {error}"
+                    )))
+                },
+            ),
+            RuntimeRequest::Disconnect { .. } => spawn_blocking_event(
+                sender,
+                move || {
+                    RuntimeEvent::Disconnect(
+                        crate::network::wpa_supplicant::disconnect(&interface)
+                            .map_err(|error| error.to_string()),
+                    )
+                },
+                |error| {
+                    RuntimeEvent::Disconnect(Err(format!(
+                        "runtime disconnect task failed: {error}"
+                    )))
+                },
+            ),
+            RuntimeRequest::Reconnect { cancel, .. } => spawn_blocking_event(
+                sender,
+                move || {
+                    RuntimeEvent::Reconnect(
+                        crate::network::wpa_supplicant::reconnect(
+                            &interface, &cancel,
+                        )
+                        .map_err(|error| error.to_string()),
+                    )
+                },
+                |error| {
+                    RuntimeEvent::Reconnect(Err(format!(
+                        "runtime reconnect task failed: {error}"
+                    )))
+                },
+            ),
+            request => {
+                let _ = sender.send(wpa_supplicant_unsupported_event(request));
+            }
+        }
+
+        self.pending_event = Some(receiver);
+    }
+
+    fn poll_event(&mut self) -> Result<Option<RuntimeEvent>, Box<dyn Error>> {
+        poll_pending_event(&mut self.pending_event)
+    }
+}
+
+/// NetworkManager when it is on the system bus, or else wpa_supplicant when
+/// it runs without NetworkManager.
 #[cfg(not(feature = "demo"))]
 pub(crate) fn default_runtime_driver() -> Box<dyn RuntimeBackendDriver> {
-    Box::new(NetworkManagerRuntimeDriver::default())
+    match wpa_supplicant_interface() {
+        Some(interface) => Box::new(WpaSupplicantRuntimeDriver {
+            interface,
+            pending_event: None,
+        }),
+        None => Box::new(NetworkManagerRuntimeDriver::default()),
+    }
 }
 
 #[cfg(feature = "demo")]
@@ -285,5 +1619,15 @@ pub fn default_backend() -> Box<dyn NetworkBackend> {
 
 #[cfg(not(feature = "demo"))]
 pub fn default_backend() -> Box<dyn NetworkBackend> {
-    Box::new(NetworkManagerBackend)
+    match wpa_supplicant_interface() {
+        Some(interface) => Box::new(WpaSupplicantBackend { interface }),
+        None => Box::new(NetworkManagerBackend),
+    }
+}
+
+/// Handles `--demo`: makes the demo backend's signals drift and its replies
+/// slow and occasionally failing.
+#[cfg(feature = "demo")]
+pub fn enable_lively_demo() {
+    crate::network::demo::set_lively(true);
 }
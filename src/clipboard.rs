@@ -0,0 +1,9 @@
+//! The desktop clipboard, read for Ctrl+V. Terminals paste on their own
+//! with their paste shortcut; in raw mode Ctrl+V is just a key.
+
+/// The text on the clipboard, through X11 (or XWayland).
+pub fn read_text() -> Result<String, String> {
+    arboard::Clipboard::new()
+        .and_then(|mut clipboard| clipboard.get_text())
+        .map_err(|error| error.to_string())
+}
@@ -0,0 +1,724 @@
+//! User configuration read from `$XDG_CONFIG_HOME/nm-wifi/config`.
+//!
+//! The file is `key = value` lines with `#` comments, plus a `[theme]`
+//! section of colours. It only looks like TOML: quotes around values are
+//! optional and lists are bare comma-separated words, which keeps the file
+//! hand-editable without pulling in a parser dependency.
+
+use std::{
+    env,
+    error::Error,
+    fs,
+    io,
+    path::{Path, PathBuf},
+    time::Duration,
+};
+
+use crate::{
+    app_state::{
+        DEFAULT_CONNECT_RETRIES,
+        DEFAULT_UNUSED_PROFILE_DAYS,
+        PasswordMask,
+    },
+    glyphs::GlyphWidth,
+    latency::DEFAULT_LATENCY_HOST,
+    network::ScanMode,
+    notify::{NotificationEvent, NotificationRoutes, SinkKind},
+    speedtest::DEFAULT_SPEEDTEST_URL,
+    terminal::ColorDepth,
+    theme::{ThemeColors, ThemeName},
+    wifi::WifiBand,
+};
+
+const DEFAULT_RESCAN_INTERVAL: Duration = Duration::from_secs(30);
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Config {
+    /// How often the network list re-scans in the background; `None`
+    /// disables periodic rescans (`rescan_interval = 0`).
+    pub rescan_interval: Option<Duration>,
+    /// Shortest time between two scans asked of the adapter; scans sooner
+    /// than that read the last results instead.
+    pub scan_min_interval: Duration,
+    pub scan_mode: ScanMode,
+    /// Lists only the access points on this band (`scan_band = 2.4`, `5` or
+    /// `6`); `None` lists every band.
+    pub scan_band: Option<WifiBand>,
+    /// Sinks per event, set with `notify.<event> = toast, desktop, ...`.
+    pub notifications: NotificationRoutes,
+    /// Target of the `webhook` notification sink.
+    pub webhook_url: Option<String>,
+    /// Pings the gateway and `latency_host` while connected.
+    pub latency_monitor: bool,
+    pub latency_host: String,
+    /// Whether list icons are emoji or single-width symbols.
+    pub glyph_width: GlyphWidth,
+    /// Colour palette, also switched with `T` in the app.
+    pub theme: ThemeName,
+    /// `#rrggbb` colours by role from the `[theme]` section, laid over the
+    /// palette.
+    pub theme_colors: ThemeColors,
+    /// Colours the terminal can show, to approximate the palette in.
+    pub color_depth: ColorDepth,
+    /// How a hidden password is drawn while typed.
+    pub password_mask: PasswordMask,
+    /// Server of the speed test, speaking Cloudflare's `__down`/`__up` API.
+    pub speedtest_url: String,
+    /// Retries of a connect that failed on DHCP or a missing access point.
+    pub connect_retries: u32,
+    /// Gateway pings lost in a row before the connection is recovered; 0
+    /// leaves the health monitor off.
+    pub health_check_failures: u32,
+    /// Days without a connection before the cleanup offers to forget an
+    /// out-of-range profile.
+    pub unused_profile_days: u64,
+    /// Creates new profiles for the current user only, with agent-owned
+    /// secrets.
+    pub user_profiles: bool,
+    /// Deletes the profile of a guest session when the app quits, which
+    /// drops the guest connection.
+    pub delete_guest_profile_on_quit: bool,
+}
+
+impl Default for Config {
+    fn default() -> Self {
+        Self {
+            rescan_interval: Some(DEFAULT_RESCAN_INTERVAL),
+            scan_min_interval: Duration::ZERO,
+            scan_mode: ScanMode::Active,
+            scan_band: None,
+            notifications: NotificationRoutes::default(),
+            webhook_url: None,
+            latency_monitor: false,
+            latency_host: DEFAULT_LATENCY_HOST.to_string(),
+            glyph_width: GlyphWidth::Auto,
+            theme: ThemeName::Auto,
+            theme_colors: ThemeColors::default(),
+            color_depth: ColorDepth::Auto,
+            password_mask: PasswordMask::Dots,
+            speedtest_url: DEFAULT_SPEEDTEST_URL.to_string(),
+            connect_retries: DEFAULT_CONNECT_RETRIES,
+            health_check_failures: 0,
+            unused_profile_days: DEFAULT_UNUSED_PROFILE_DAYS,
+            user_profiles: false,
+            delete_guest_profile_on_quit: false,
+        }
+    }
+}
+
+/// Resolves `file` inside the `nm-wifi` directory of an XDG base directory,
+/// using `home_fallback` (relative to `$HOME`) when the variable is unset.
+pub(crate) fn xdg_path(
+    variable: &str,
+    home_fallback: &str,
+    file: &str,
+) -> Option<PathBuf> {
+    env::var_os(variable)
+        .filter(|dir| !dir.is_empty())
+        .map(PathBuf::from)
+        .or_else(|| {
+            env::var_os("HOME")
+                .map(|home| PathBuf::from(home).join(home_fallback))
+        })
+        .map(|dir| dir.join("nm-wifi").join(file))
+}
+
+impl Config {
+    pub fn path() -> Option<PathBuf> {
+        xdg_path("XDG_CONFIG_HOME", ".config", "config")
+    }
+
+    /// Loads the config file, falling back to defaults when it is missing.
+    pub fn load() -> Result<Self, Box<dyn Error>> {
+        match Self::path() {
+            Some(path) => Self::load_from(&path),
+            None => Ok(Self::default()),
+        }
+    }
+
+    /// Loads the config file at `path`, falling back to defaults when it is
+    /// missing.
+    pub fn load_from(path: &Path) -> Result<Self, Box<dyn Error>> {
+        match fs::read_to_string(path) {
+            Ok(contents) => Self::parse(&contents).map_err(|error| {
+                format!("Invalid config {}: {error}", path.display()).into()
+            }),
+            Err(error) if error.kind() == io::ErrorKind::NotFound => {
+                Ok(Self::default())
+            }
+            Err(error) => {
+                Err(format!("Failed to read {}: {error}", path.display())
+                    .into())
+            }
+        }
+    }
+
+    pub fn parse(contents: &str) -> Result<Self, String> {
+        let mut config = Self::default();
+        let mut in_theme = false;
+
+        for (number, line) in contents.lines().enumerate() {
+            let line = split_comment(line).0.trim();
+            if line.is_empty() {
+                continue;
+            }
+
+            if let Some(section) = section_name(line) {
+                if section != "theme" {
+                    return Err(format!(
+                        "line {}: unknown section `[{section}]`",
+                        number + 1
+                    ));
+                }
+                in_theme = true;
+                continue;
+            }
+
+            let Some((key, value)) = line.split_once('=') else {
+                return Err(format!(
+                    "line {}: expected `key = value`",
+                    number + 1
+                ));
+            };
+            let value = value.trim().trim_matches('"');
+
+            if in_theme {
+                config
+                    .theme_colors
+                    .set(key.trim(), value)
+                    .map_err(|error| format!("line {}: {error}", number + 1))?;
+                continue;
+            }
+
+            match key.trim() {
+                "rescan_interval" => {
+                    let seconds = value.parse::<u64>().map_err(|_| {
+                        format!(
+                            "line {}: rescan_interval must be a number of seconds",
+                            number + 1
+                        )
+                    })?;
+                    config.rescan_interval =
+                        (seconds > 0).then(|| Duration::from_secs(seconds));
+                }
+                "scan_min_interval" => {
+                    let seconds = value.parse::<u64>().map_err(|_| {
+                        format!(
+                            "line {}: scan_min_interval must be a number of seconds",
+                            number + 1
+                        )
+                    })?;
+                    config.scan_min_interval = Duration::from_secs(seconds);
+                }
+                "scan_mode" => {
+                    config.scan_mode =
+                        ScanMode::from_key(value).ok_or_else(|| {
+                            format!(
+                                "line {}: scan_mode must be active or passive",
+                                number + 1
+                            )
+                        })?;
+                }
+                "scan_band" => {
+                    config.scan_band = match value {
+                        "all" => None,
+                        band => Some(WifiBand::from_key(band).ok_or_else(
+                            || {
+                                format!(
+                                    "line {}: scan_band must be all, 2.4, 5 or 6",
+                                    number + 1
+                                )
+                            },
+                        )?),
+                    };
+                }
+                "connect_retries" => {
+                    config.connect_retries = value.parse().map_err(|_| {
+                        format!(
+                            "line {}: connect_retries must be a whole number",
+                            number + 1
+                        )
+                    })?;
+                }
+                "health_check_failures" => {
+                    config.health_check_failures =
+                        value.parse().map_err(|_| {
+                            format!(
+                                "line {}: health_check_failures must be a whole number",
+                                number + 1
+                            )
+                        })?;
+                }
+                "unused_profile_days" => {
+                    config.unused_profile_days = value.parse().map_err(|_| {
+                        format!(
+                            "line {}: unused_profile_days must be a whole number",
+                            number + 1
+                        )
+                    })?;
+                }
+                "webhook_url" => config.webhook_url = Some(value.to_string()),
+                "latency_monitor" => {
+                    config.latency_monitor = match value {
+                        "true" => true,
+                        "false" => false,
+                        _ => {
+                            return Err(format!(
+                                "line {}: latency_monitor must be true or false",
+                                number + 1
+                            ));
+                        }
+                    };
+                }
+                "user_profiles" => {
+                    config.user_profiles = match value {
+                        "true" => true,
+                        "false" => false,
+                        _ => {
+                            return Err(format!(
+                                "line {}: user_profiles must be true or false",
+                                number + 1
+                            ));
+                        }
+                    };
+                }
+                "delete_guest_profile_on_quit" => {
+                    config.delete_guest_profile_on_quit = match value {
+                        "true" => true,
+                        "false" => false,
+                        _ => {
+                            return Err(format!(
+                                "line {}: delete_guest_profile_on_quit must be true or false",
+                                number + 1
+                            ));
+                        }
+                    };
+                }
+                "latency_host" => config.latency_host = value.to_string(),
+                "speedtest_url" => config.speedtest_url = value.to_string(),
+                "glyph_width" => {
+                    config.glyph_width = GlyphWidth::from_key(value)
+                        .ok_or_else(|| {
+                            format!(
+                                "line {}: glyph_width must be auto, double or single",
+                                number + 1
+                            )
+                        })?;
+                }
+                "theme" => {
+                    config.theme =
+                        ThemeName::from_key(value).ok_or_else(|| {
+                            format!(
+                                "line {}: theme must be auto, mocha, macchiato, frappe, latte or terminal",
+                                number + 1
+                            )
+                        })?;
+                }
+                "color_depth" => {
+                    config.color_depth = ColorDepth::from_key(value)
+                        .ok_or_else(|| {
+                            format!(
+                                "line {}: color_depth must be auto, truecolor, 256 or 16",
+                                number + 1
+                            )
+                        })?;
+                }
+                "password_mask" => {
+                    config.password_mask = PasswordMask::from_key(value)
+                        .ok_or_else(|| {
+                            format!(
+                                "line {}: password_mask must be dots, fixed or hidden",
+                                number + 1
+                            )
+                        })?;
+                }
+                key if key.starts_with("notify.") => {
+                    let event = NotificationEvent::from_key(&key[7..])
+                        .ok_or_else(|| {
+                            format!(
+                                "line {}: unknown notification event `{}`",
+                                number + 1,
+                                &key[7..]
+                            )
+                        })?;
+                    let sinks = NotificationRoutes::parse_sinks(value)
+                        .map_err(|error| {
+                            format!("line {}: {error}", number + 1)
+                        })?;
+                    config.notifications.set(event, sinks);
+                }
+                other => {
+                    return Err(format!(
+                        "line {}: unknown setting `{other}`",
+                        number + 1
+                    ));
+                }
+            }
+        }
+
+        if config.notifications.uses(SinkKind::Webhook)
+            && config.webhook_url.is_none()
+        {
+            return Err("the webhook sink needs `webhook_url`".to_string());
+        }
+
+        Ok(config)
+    }
+}
+
+/// Sets `key` to `value` in the config file at `path`, keeping the rest of
+/// the file as it is.
+pub fn store_setting(path: &Path, key: &str, value: &str) -> io::Result<()> {
+    let contents = match fs::read_to_string(path) {
+        Ok(contents) => contents,
+        Err(error) if error.kind() == io::ErrorKind::NotFound => String::new(),
+        Err(error) => return Err(error),
+    };
+    if let Some(parent) = path.parent() {
+        fs::create_dir_all(parent)?;
+    }
+    fs::write(path, with_setting(&contents, key, value))
+}
+
+/// `contents` with the top-level line setting `key` replaced, keeping a
+/// comment at its end, or with a line added before the first section when
+/// `key` was not set.
+fn with_setting(contents: &str, key: &str, value: &str) -> String {
+    let setting = format!("{key} = \"{value}\"");
+    let mut lines = contents.lines().map(str::to_string).collect::<Vec<_>>();
+    let top_level = lines
+        .iter()
+        .position(|line| section_name(split_comment(line).0.trim()).is_some())
+        .unwrap_or(lines.len());
+
+    let existing = lines[..top_level].iter().position(|line| {
+        split_comment(line)
+            .0
+            .split_once('=')
+            .is_some_and(|(name, _)| name.trim() == key)
+    });
+    match existing {
+        Some(at) => {
+            lines[at] = match split_comment(&lines[at]).1 {
+                Some(comment) => format!("{setting} #{comment}"),
+                None => setting,
+            };
+        }
+        None => {
+            let mut at = top_level;
+            while at > 0 && lines[at - 1].trim().is_empty() {
+                at -= 1;
+            }
+            lines.insert(at, setting);
+        }
+    }
+    lines.join("\n") + "\n"
+}
+
+/// Splits `line` at the `#` starting its comment; a `#` inside quotes, as
+/// in a hex colour, belongs to the value.
+fn split_comment(line: &str) -> (&str, Option<&str>) {
+    let mut quoted = false;
+    for (at, character) in line.char_indices() {
+        match character {
+            '"' => quoted = !quoted,
+            '#' if !quoted => return (&line[..at], Some(&line[at + 1..])),
+            _ => {}
+        }
+    }
+    (line, None)
+}
+
+/// The name of the section a `[name]` line opens.
+fn section_name(line: &str) -> Option<&str> {
+    line.strip_prefix('[')?.strip_suffix(']').map(str::trim)
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use ratatui::style::Color;
+
+    use super::{Config, with_setting};
+    use crate::{
+        app_state::PasswordMask,
+        glyphs::GlyphWidth,
+        network::ScanMode,
+        notify::{NotificationEvent, SinkKind},
+        terminal::{Background, ColorDepth},
+        theme::ThemeName,
+        wifi::WifiBand,
+    };
+
+    #[test]
+    fn themes_are_read_and_stored_in_place() {
+        assert_eq!(
+            Config::parse("theme = \"latte\"").map(|config| config.theme),
+            Ok(ThemeName::Latte)
+        );
+        assert!(Config::parse("theme = solarized").is_err());
+
+        let contents = "# Colours\ntheme = mocha # dark\nscan_mode = active\n";
+        assert_eq!(
+            with_setting(contents, "theme", "frappe"),
+            "# Colours\ntheme = \"frappe\" # dark\nscan_mode = active\n"
+        );
+        assert_eq!(
+            with_setting("scan_mode = active", "theme", "terminal"),
+            "scan_mode = active\ntheme = \"terminal\"\n"
+        );
+        assert_eq!(with_setting("", "theme", "latte"), "theme = \"latte\"\n");
+        assert_eq!(
+            with_setting(
+                "scan_mode = active\n\n[theme]\nbase = \"#000000\"\n",
+                "theme",
+                "latte"
+            ),
+            "scan_mode = active\ntheme = \"latte\"\n\n[theme]\nbase = \"#000000\"\n"
+        );
+    }
+
+    #[test]
+    fn theme_section_sets_colours_by_role() {
+        let config = Config::parse(
+            "theme = latte # light\n\n[theme]\nbase = \"#fafafa\" # paper\ntext = \"#202020\"",
+        )
+        .expect("valid config");
+
+        assert_eq!(config.theme, ThemeName::Latte);
+        let palette = config
+            .theme_colors
+            .apply(ThemeName::Latte.palette(Background::Dark));
+        assert_eq!(palette.base, Color::Rgb(250, 250, 250));
+        assert_eq!(palette.text, Color::Rgb(32, 32, 32));
+        assert_eq!(
+            Config::parse("[theme]\nbase = blue"),
+            Err("line 2: base must be a hex colour like #1e1e2e".to_string())
+        );
+        assert_eq!(
+            Config::parse("[theme]\nscan_mode = active"),
+            Err("line 2: unknown theme colour `scan_mode`".to_string())
+        );
+        assert_eq!(
+            Config::parse("[colours]"),
+            Err("line 1: unknown section `[colours]`".to_string())
+        );
+    }
+
+    #[test]
+    fn missing_settings_keep_the_defaults() {
+        assert_eq!(Config::parse("# nothing here\n"), Ok(Config::default()));
+    }
+
+    #[test]
+    fn user_profiles_is_a_boolean() {
+        assert_eq!(
+            Config::parse("user_profiles = true")
+                .map(|config| config.user_profiles),
+            Ok(true)
+        );
+        assert!(Config::parse("user_profiles = yes").is_err());
+    }
+
+    #[test]
+    fn deleting_the_guest_profile_on_quit_is_opt_in() {
+        assert!(!Config::default().delete_guest_profile_on_quit);
+        assert_eq!(
+            Config::parse("delete_guest_profile_on_quit = true")
+                .map(|config| config.delete_guest_profile_on_quit),
+            Ok(true)
+        );
+    }
+
+    #[test]
+    fn connect_retries_must_be_a_count() {
+        assert_eq!(
+            Config::parse("connect_retries = 0")
+                .map(|config| config.connect_retries),
+            Ok(0)
+        );
+        assert_eq!(
+            Config::parse("connect_retries = -1"),
+            Err("line 1: connect_retries must be a whole number".to_string())
+        );
+    }
+
+    #[test]
+    fn health_monitor_is_off_unless_given_a_count() {
+        assert_eq!(Config::default().health_check_failures, 0);
+        assert_eq!(
+            Config::parse("health_check_failures = 3")
+                .map(|config| config.health_check_failures),
+            Ok(3)
+        );
+        assert!(Config::parse("health_check_failures = often").is_err());
+    }
+
+    #[test]
+    fn unused_profile_days_must_be_a_count() {
+        assert_eq!(
+            Config::parse("unused_profile_days = 90")
+                .map(|config| config.unused_profile_days),
+            Ok(90)
+        );
+        assert_eq!(
+            Config::parse("unused_profile_days = soon"),
+            Err("line 1: unused_profile_days must be a whole number"
+                .to_string())
+        );
+    }
+
+    #[test]
+    fn rescan_interval_is_read_in_seconds_and_zero_disables_it() {
+        assert_eq!(
+            Config::parse("rescan_interval = 45 # seconds")
+                .map(|config| config.rescan_interval),
+            Ok(Some(Duration::from_secs(45)))
+        );
+        assert_eq!(
+            Config::parse("rescan_interval = 0")
+                .map(|config| config.rescan_interval),
+            Ok(None)
+        );
+    }
+
+    #[test]
+    fn scan_tuning_settings_are_read() {
+        let config = Config::parse(
+            "scan_min_interval = 20\nscan_mode = passive\nscan_band = 5",
+        )
+        .expect("valid config");
+
+        assert_eq!(config.scan_min_interval, Duration::from_secs(20));
+        assert_eq!(config.scan_mode, ScanMode::Passive);
+        assert_eq!(config.scan_band, Some(WifiBand::FiveGhz));
+        assert_eq!(
+            Config::parse("scan_band = all").map(|config| config.scan_band),
+            Ok(None)
+        );
+        assert_eq!(
+            Config::parse("scan_band = 60"),
+            Err("line 1: scan_band must be all, 2.4, 5 or 6".to_string())
+        );
+        assert_eq!(
+            Config::parse("scan_mode = lazy"),
+            Err("line 1: scan_mode must be active or passive".to_string())
+        );
+    }
+
+    #[test]
+    fn glyph_width_overrides_terminal_detection() {
+        assert_eq!(Config::default().glyph_width, GlyphWidth::Auto);
+        assert_eq!(
+            Config::parse("glyph_width = single")
+                .map(|config| config.glyph_width),
+            Ok(GlyphWidth::Single)
+        );
+        assert_eq!(
+            Config::parse("glyph_width = narrow"),
+            Err("line 1: glyph_width must be auto, double or single"
+                .to_string())
+        );
+    }
+
+    #[test]
+    fn color_depth_overrides_terminal_detection() {
+        assert_eq!(Config::default().color_depth, ColorDepth::Auto);
+        assert_eq!(
+            Config::parse("color_depth = 256").map(|config| config.color_depth),
+            Ok(ColorDepth::Ansi256)
+        );
+        assert_eq!(
+            Config::parse("color_depth = 8"),
+            Err("line 1: color_depth must be auto, truecolor, 256 or 16"
+                .to_string())
+        );
+    }
+
+    #[test]
+    fn password_mask_can_hide_the_length() {
+        assert_eq!(Config::default().password_mask, PasswordMask::Dots);
+        assert_eq!(
+            Config::parse("password_mask = fixed")
+                .map(|config| config.password_mask),
+            Ok(PasswordMask::Fixed)
+        );
+        assert_eq!(
+            Config::parse("password_mask = stars"),
+            Err("line 1: password_mask must be dots, fixed or hidden"
+                .to_string())
+        );
+    }
+
+    #[test]
+    fn latency_monitor_is_off_unless_enabled() {
+        let config =
+            Config::parse("latency_monitor = true\nlatency_host = \"9.9.9.9\"")
+                .expect("valid config");
+
+        assert!(!Config::default().latency_monitor);
+        assert!(config.latency_monitor);
+        assert_eq!(config.latency_host, "9.9.9.9");
+        assert_eq!(
+            Config::parse("latency_monitor = yes"),
+            Err("line 1: latency_monitor must be true or false".to_string())
+        );
+    }
+
+    #[test]
+    fn notification_routes_are_set_per_event() {
+        let config = Config::parse(
+            "notify.connection_failed = toast, desktop, webhook\n\
+             notify.connected = none\n\
+             webhook_url = \"https://ntfy.sh/nm-wifi\"",
+        )
+        .expect("valid config");
+
+        assert_eq!(
+            config
+                .notifications
+                .sinks(NotificationEvent::ConnectionFailed),
+            [SinkKind::Toast, SinkKind::Desktop, SinkKind::Webhook]
+        );
+        assert!(
+            config
+                .notifications
+                .sinks(NotificationEvent::Connected)
+                .is_empty()
+        );
+        assert_eq!(
+            config.notifications.sinks(NotificationEvent::ScanFailed),
+            [SinkKind::Toast]
+        );
+        assert_eq!(
+            config.webhook_url.as_deref(),
+            Some("https://ntfy.sh/nm-wifi")
+        );
+    }
+
+    #[test]
+    fn webhook_routes_require_a_url() {
+        assert_eq!(
+            Config::parse("notify.scan_failed = webhook"),
+            Err("the webhook sink needs `webhook_url`".to_string())
+        );
+        assert_eq!(
+            Config::parse("notify.roamed = toast"),
+            Err("line 1: unknown notification event `roamed`".to_string())
+        );
+    }
+
+    #[test]
+    fn invalid_lines_report_their_position() {
+        assert_eq!(
+            Config::parse("\nrescan_interval = soon"),
+            Err("line 2: rescan_interval must be a number of seconds"
+                .to_string())
+        );
+        assert_eq!(
+            Config::parse("colour = blue"),
+            Err("line 1: unknown setting `colour`".to_string())
+        );
+    }
+}
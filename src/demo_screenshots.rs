@@ -38,7 +38,13 @@ mod tests {
                 "network-list.svg",
                 "help.svg",
                 "details.svg",
+                "roaming.svg",
+                "speed-test.svg",
                 "password.svg",
+                "share-qr.svg",
+                "forget-profiles.svg",
+                "export-profiles.svg",
+                "import-qr.svg",
                 "connecting.svg",
                 "disconnecting.svg",
                 "result-success.svg",
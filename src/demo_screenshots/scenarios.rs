@@ -1,7 +1,28 @@
-use std::time::Instant;
+use std::time::{Duration, Instant};
 
 use crate::{
-    app_state::{App, AppState},
+    app_state::{
+        App,
+        AppState,
+        AutoconnectCheck,
+        AutoconnectDiagnosis,
+        ConnectivityCheck,
+        ProfileCheck,
+        ProfileLookup,
+    },
+    latency::{DEFAULT_LATENCY_HOST, LatencyMonitor, LatencySample, Probe},
+    network::{
+        AutoconnectBlock,
+        Connectivity,
+        ManagerState,
+        ManagerStatus,
+        PrimaryConnection,
+        ProfileSettings,
+        ProxyConfig,
+        SavedProfile,
+    },
+    speedtest::{ChunkTransfer, TransferDirection},
+    traffic::TrafficCounters,
     wifi::{WifiNetwork, WifiSecurity},
 };
 
@@ -11,7 +32,13 @@ pub enum DemoScreen {
     NetworkList,
     Help,
     Details,
+    Roaming,
+    SpeedTest,
     Password,
+    ShareQr,
+    ForgetProfiles,
+    ExportProfiles,
+    ImportQr,
     Connecting,
     Disconnecting,
     ResultSuccess,
@@ -25,7 +52,13 @@ impl DemoScreen {
             Self::NetworkList => "network-list.svg",
             Self::Help => "help.svg",
             Self::Details => "details.svg",
+            Self::Roaming => "roaming.svg",
+            Self::SpeedTest => "speed-test.svg",
             Self::Password => "password.svg",
+            Self::ShareQr => "share-qr.svg",
+            Self::ForgetProfiles => "forget-profiles.svg",
+            Self::ExportProfiles => "export-profiles.svg",
+            Self::ImportQr => "import-qr.svg",
             Self::Connecting => "connecting.svg",
             Self::Disconnecting => "disconnecting.svg",
             Self::ResultSuccess => "result-success.svg",
@@ -40,7 +73,13 @@ pub fn build_demo_screen(screen: DemoScreen, networks: &[WifiNetwork]) -> App {
         DemoScreen::NetworkList => network_list_app(networks),
         DemoScreen::Help => help_app(networks),
         DemoScreen::Details => details_app(networks),
+        DemoScreen::Roaming => roaming_app(networks),
+        DemoScreen::SpeedTest => speed_test_app(networks),
         DemoScreen::Password => password_app(networks),
+        DemoScreen::ShareQr => share_qr_app(networks),
+        DemoScreen::ForgetProfiles => forget_profiles_app(networks),
+        DemoScreen::ExportProfiles => export_profiles_app(networks),
+        DemoScreen::ImportQr => import_qr_app(networks),
         DemoScreen::Connecting => connecting_app(networks),
         DemoScreen::Disconnecting => disconnecting_app(networks),
         DemoScreen::ResultSuccess => result_success_app(networks),
@@ -54,7 +93,13 @@ pub fn demo_shot_apps(networks: &[WifiNetwork]) -> Vec<(&'static str, App)> {
         DemoScreen::NetworkList,
         DemoScreen::Help,
         DemoScreen::Details,
+        DemoScreen::Roaming,
+        DemoScreen::SpeedTest,
         DemoScreen::Password,
+        DemoScreen::ShareQr,
+        DemoScreen::ForgetProfiles,
+        DemoScreen::ExportProfiles,
+        DemoScreen::ImportQr,
         DemoScreen::Connecting,
         DemoScreen::Disconnecting,
         DemoScreen::ResultSuccess,
@@ -70,6 +115,17 @@ fn base_app(networks: &[WifiNetwork]) -> App {
     app.networks = networks.to_vec();
     app.network_count = app.networks.len();
     app.adapter_name = Some("demo-wlan0".to_string());
+    app.manager_status = Some(ManagerStatus {
+        state: ManagerState::ConnectedGlobal,
+        primary_connection: networks
+            .iter()
+            .find(|network| network.connected)
+            .map(|network| PrimaryConnection {
+                id: network.ssid.clone(),
+                connection_type: "802-11-wireless".to_string(),
+            }),
+        wifi_state_reason: None,
+    });
     app.selected_index = 0;
     app.status_message = if networks.is_empty() {
         "Scanning for WiFi networks...".to_string()
@@ -86,6 +142,27 @@ fn scanning_app() -> App {
 fn network_list_app(networks: &[WifiNetwork]) -> App {
     let mut app = base_app(networks);
     app.state = AppState::NetworkList;
+    let sampled_at = Instant::now();
+    app.traffic.sample(
+        TrafficCounters {
+            rx_bytes: 734_003_200,
+            tx_bytes: 52_428_800,
+        },
+        sampled_at,
+    );
+    app.traffic.sample(
+        TrafficCounters {
+            rx_bytes: 735_313_920,
+            tx_bytes: 52_474_880,
+        },
+        sampled_at + Duration::from_secs(1),
+    );
+    let mut latency = LatencyMonitor::new(DEFAULT_LATENCY_HOST);
+    latency.record(LatencySample {
+        gateway: Some(Probe::Reply(Duration::from_millis(3))),
+        host: Probe::Reply(Duration::from_millis(24)),
+    });
+    app.latency = Some(latency);
     app
 }
 
@@ -99,6 +176,53 @@ fn details_app(networks: &[WifiNetwork]) -> App {
     let mut app = base_app(networks);
     app.state = AppState::NetworkDetails;
     app.selected_index = 1;
+    app.autoconnect = Some(AutoconnectDiagnosis {
+        ssid: networks[1].ssid.clone(),
+        check: AutoconnectCheck::Loaded(Some(
+            AutoconnectBlock::RetriesExhausted,
+        )),
+    });
+    app.profile_lookup = Some(ProfileLookup {
+        ssid: networks[1].ssid.clone(),
+        check: ProfileCheck::Loaded(Some(ProfileSettings {
+            proxy: ProxyConfig::Pac(
+                "http://wpad.example/proxy.pac".to_string(),
+            ),
+            users: vec!["demo".to_string()],
+        })),
+    });
+    app
+}
+
+fn roaming_app(networks: &[WifiNetwork]) -> App {
+    let mut app = base_app(networks);
+    app.open_roaming_view();
+    let unused = app
+        .roaming_access_points()
+        .into_iter()
+        .find(|access_point| !access_point.associated)
+        .map(|access_point| access_point.bssid.clone());
+    if let (Some(network), Some(bssid)) = (app.connected_network(), unused) {
+        let ssid = network.ssid.clone();
+        app.avoided.toggle(&ssid, &bssid);
+    }
+    app
+}
+
+fn speed_test_app(networks: &[WifiNetwork]) -> App {
+    let mut app = base_app(networks);
+    app.open_speed_test();
+    let chunk = |millis| {
+        Ok(ChunkTransfer {
+            bytes: 4_000_000,
+            elapsed: Duration::from_millis(millis),
+        })
+    };
+    for millis in [380, 410, 395, 420] {
+        app.apply_speed_test_chunk(TransferDirection::Download, chunk(millis));
+    }
+    app.apply_speed_test_chunk(TransferDirection::Upload, chunk(1580));
+    app.apply_speed_test_chunk(TransferDirection::Upload, chunk(1640));
     app
 }
 
@@ -116,6 +240,68 @@ fn password_app(networks: &[WifiNetwork]) -> App {
     app
 }
 
+fn share_qr_app(networks: &[WifiNetwork]) -> App {
+    let mut app = base_app(networks);
+    app.state = AppState::NetworkList;
+    app.selected_index = networks
+        .iter()
+        .position(|network| network.connected)
+        .expect("demo connected network exists");
+    app.begin_qr_share();
+    app.apply_saved_password(Ok(Some("AcerolaAcai".to_string())));
+    app
+}
+
+fn saved_profiles(networks: &[WifiNetwork]) -> Vec<SavedProfile> {
+    networks
+        .iter()
+        .filter(|network| network.saved)
+        .map(|network| network.ssid.as_str())
+        .chain(["Airport_Guest", "Hotel_Guest", "Marriott_Guest"])
+        .map(|ssid| SavedProfile {
+            id: ssid.to_string(),
+            uuid: format!("demo-{ssid}"),
+            ssid: ssid.to_string(),
+            users: Vec::new(),
+            last_used: None,
+            band: None,
+            bssid: None,
+            mtu: None,
+            cloned_mac: None,
+            vpn: None,
+        })
+        .collect()
+}
+
+fn forget_profiles_app(networks: &[WifiNetwork]) -> App {
+    let mut app = base_app(networks);
+    app.begin_bulk_forget();
+    app.apply_saved_profiles(Ok(saved_profiles(networks)));
+    if let Some(forget) = app.bulk_forget.as_mut() {
+        forget.pattern = "*_Guest".to_string();
+    }
+    app.advance_bulk_forget();
+    app
+}
+
+fn export_profiles_app(networks: &[WifiNetwork]) -> App {
+    let mut app = base_app(networks);
+    app.begin_profile_export();
+    app.apply_saved_profiles(Ok(saved_profiles(networks)));
+    app.toggle_profile_export_secrets();
+    app.finish_profile_export(Ok(saved_profiles(networks).len()));
+    app
+}
+
+fn import_qr_app(networks: &[WifiNetwork]) -> App {
+    let mut app = base_app(networks);
+    app.begin_qr_import();
+    for c in "WIFI:T:WPA;S:Guest Loft;P:welcome-home;;".chars() {
+        app.add_char_to_qr_import(c);
+    }
+    app
+}
+
 fn connecting_app(networks: &[WifiNetwork]) -> App {
     let mut app = base_app(networks);
     let network = networks
@@ -155,6 +341,7 @@ fn result_success_app(networks: &[WifiNetwork]) -> App {
     app.state = AppState::ConnectionResult;
     app.selected_network = Some(network);
     app.connection_success = true;
+    app.connectivity = Some(ConnectivityCheck::Checked(Connectivity::Full));
     app.status_message = "Connected successfully!".to_string();
     app
 }
@@ -3,7 +3,7 @@ use ratatui::{
     style::{Color, Modifier},
 };
 
-use crate::theme::CatppuccinColors;
+use crate::theme::MOCHA;
 
 const CELL_WIDTH: u32 = 10;
 const CELL_HEIGHT: u32 = 20;
@@ -24,8 +24,8 @@ pub fn buffer_to_svg(buffer: &Buffer) -> String {
             let cell = &buffer[(x, y)];
             let px = u32::from(x) * CELL_WIDTH;
             let py = u32::from(y) * CELL_HEIGHT;
-            let bg = color_to_hex(cell.bg, CatppuccinColors::BASE);
-            let fg = color_to_hex(cell.fg, CatppuccinColors::TEXT);
+            let bg = color_to_hex(cell.bg, MOCHA.base);
+            let fg = color_to_hex(cell.fg, MOCHA.text);
 
             svg.push_str(&format!(
                 r#"<rect x="{px}" y="{py}" width="{CELL_WIDTH}" height="{CELL_HEIGHT}" fill="{bg}"/>"#
@@ -119,7 +119,7 @@ mod tests {
     use ratatui::style::Color;
 
     use super::{color_to_hex, escape_xml};
-    use crate::theme::CatppuccinColors;
+    use crate::theme::MOCHA;
 
     #[test]
     fn escape_xml_escapes_svg_metacharacters() {
@@ -128,16 +128,10 @@ mod tests {
 
     #[test]
     fn ansi_and_reset_colors_serialize_stably() {
+        assert_eq!(color_to_hex(Color::Reset, MOCHA.base), "#1e1e2e");
+        assert_eq!(color_to_hex(Color::Indexed(196), MOCHA.base), "#ff0000");
         assert_eq!(
-            color_to_hex(Color::Reset, CatppuccinColors::BASE),
-            "#1e1e2e"
-        );
-        assert_eq!(
-            color_to_hex(Color::Indexed(196), CatppuccinColors::BASE),
-            "#ff0000"
-        );
-        assert_eq!(
-            color_to_hex(Color::Rgb(205, 214, 244), CatppuccinColors::BASE),
+            color_to_hex(Color::Rgb(205, 214, 244), MOCHA.base),
             "#cdd6f4"
         );
     }
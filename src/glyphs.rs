@@ -0,0 +1,128 @@
+//! Icons of the network list and status bar. Emoji take two columns, but
+//! some terminals draw them one column wide (or not at all), which shifts
+//! every column after them; those terminals get single-width symbols instead.
+
+use std::env;
+
+/// The `glyph_width` setting.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum GlyphWidth {
+    /// Decide from `$TERM`.
+    #[default]
+    Auto,
+    Double,
+    Single,
+}
+
+impl GlyphWidth {
+    pub fn from_key(key: &str) -> Option<Self> {
+        match key {
+            "auto" => Some(Self::Auto),
+            "double" => Some(Self::Double),
+            "single" => Some(Self::Single),
+            _ => None,
+        }
+    }
+}
+
+/// Terminal families whose emoji widths disagree with Unicode's: the Linux
+/// console and old VTs have no emoji, while rxvt, st, Eterm and GNU screen
+/// advance the cursor by one column.
+const NARROW_EMOJI_TERMS: &[&str] =
+    &["linux", "vt", "rxvt", "st", "eterm", "screen", "dumb"];
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Glyphs {
+    pub connected: &'static str,
+    pub secured: &'static str,
+    /// Networks with a saved profile, which connect without a password
+    /// prompt.
+    pub saved: &'static str,
+    /// Networks pinned to the top of the list.
+    pub favorite: &'static str,
+    /// Networks whose last connect failed.
+    pub failed: &'static str,
+    pub signal: &'static str,
+    /// Fills the place of an icon a network does not have.
+    pub blank: &'static str,
+    /// Frames of the busy indicator in the status bar.
+    pub spinner: &'static [&'static str],
+}
+
+const EMOJI: Glyphs = Glyphs {
+    connected: "🔗",
+    secured: "🔒",
+    saved: "⭐",
+    favorite: "📌",
+    failed: "❗",
+    signal: "📶",
+    blank: "  ",
+    spinner: &["⠋", "⠙", "⠹", "⠸", "⠼", "⠴", "⠦", "⠧", "⠇", "⠏"],
+};
+
+const NARROW: Glyphs = Glyphs {
+    connected: "✓",
+    secured: "⚿",
+    saved: "★",
+    favorite: "♥",
+    failed: "!",
+    signal: "▂▄▆",
+    blank: " ",
+    spinner: &["|", "/", "-", "\\"],
+};
+
+impl Default for Glyphs {
+    fn default() -> Self {
+        EMOJI
+    }
+}
+
+impl Glyphs {
+    pub fn resolve(width: GlyphWidth) -> Self {
+        match width {
+            GlyphWidth::Auto => {
+                Self::for_terminal(env::var("TERM").ok().as_deref())
+            }
+            GlyphWidth::Double => EMOJI,
+            GlyphWidth::Single => NARROW,
+        }
+    }
+
+    fn for_terminal(term: Option<&str>) -> Self {
+        let family = term
+            .unwrap_or("dumb")
+            .split('-')
+            .next()
+            .unwrap_or_default()
+            .to_ascii_lowercase();
+        let narrow = NARROW_EMOJI_TERMS.iter().any(|known| {
+            family == *known || (*known == "vt" && family.starts_with("vt"))
+        });
+
+        if narrow { NARROW } else { EMOJI }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{EMOJI, GlyphWidth, Glyphs, NARROW};
+
+    #[test]
+    fn terminals_that_misrender_emoji_get_narrow_icons() {
+        for term in ["linux", "rxvt-unicode-256color", "st-256color", "vt220"] {
+            assert_eq!(Glyphs::for_terminal(Some(term)), NARROW, "{term}");
+        }
+        for term in ["xterm-256color", "alacritty", "tmux-256color", "foot"] {
+            assert_eq!(Glyphs::for_terminal(Some(term)), EMOJI, "{term}");
+        }
+        assert_eq!(Glyphs::for_terminal(None), NARROW);
+    }
+
+    #[test]
+    fn the_setting_overrides_detection() {
+        assert_eq!(GlyphWidth::from_key("single"), Some(GlyphWidth::Single));
+        assert_eq!(GlyphWidth::from_key("wide"), None);
+        assert_eq!(Glyphs::resolve(GlyphWidth::Double), EMOJI);
+        assert_eq!(Glyphs::resolve(GlyphWidth::Single), NARROW);
+    }
+}
@@ -0,0 +1,114 @@
+//! Connection health: gateway pings lost in a row while connected, and how
+//! to recover once too many were. A first outage bounces the connection; if
+//! the gateway stays silent after that, another known network is tried.
+
+use crate::latency::Probe;
+
+/// What to do about a connection whose gateway stopped answering.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Recovery {
+    Reconnect,
+    /// Switch to another known network in range.
+    Roam,
+}
+
+#[derive(Debug, Clone)]
+pub struct HealthMonitor {
+    /// Consecutive lost gateway pings that trigger a recovery.
+    pub threshold: u32,
+    pub failures: u32,
+    /// The network already reconnected in this outage; the next recovery
+    /// on it roams instead.
+    reconnected: Option<String>,
+}
+
+impl HealthMonitor {
+    pub fn new(threshold: u32) -> Self {
+        Self {
+            threshold,
+            failures: 0,
+            reconnected: None,
+        }
+    }
+
+    /// Counts a gateway probe; a reply ends the outage.
+    pub fn record(&mut self, probe: Probe) {
+        match probe {
+            Probe::Reply(_) => self.reset(),
+            Probe::Lost => self.failures += 1,
+        }
+    }
+
+    /// The recovery that is due on `ssid`, starting the count over once it
+    /// is handed out. Until then the count keeps growing, so a recovery
+    /// that had to wait is still due later.
+    pub fn take_recovery(&mut self, ssid: &str) -> Option<Recovery> {
+        if self.failures < self.threshold {
+            return None;
+        }
+        self.failures = 0;
+        if self.reconnected.as_deref() == Some(ssid) {
+            self.reconnected = None;
+            Some(Recovery::Roam)
+        } else {
+            self.reconnected = Some(ssid.to_string());
+            Some(Recovery::Reconnect)
+        }
+    }
+
+    /// Forgets the outage, e.g. when the connection changed.
+    pub fn reset(&mut self) {
+        self.failures = 0;
+        self.reconnected = None;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use super::{HealthMonitor, Recovery};
+    use crate::latency::Probe;
+
+    fn lose(health: &mut HealthMonitor) -> Option<Recovery> {
+        health.record(Probe::Lost);
+        health.take_recovery("home")
+    }
+
+    #[test]
+    fn a_reconnect_that_does_not_help_escalates_to_roaming() {
+        let mut health = HealthMonitor::new(2);
+
+        assert_eq!(lose(&mut health), None);
+        assert_eq!(lose(&mut health), Some(Recovery::Reconnect));
+        assert_eq!(health.failures, 0);
+        assert_eq!(lose(&mut health), None);
+        assert_eq!(lose(&mut health), Some(Recovery::Roam));
+
+        // A reply in between ends the outage.
+        lose(&mut health);
+        lose(&mut health);
+        health.record(Probe::Reply(Duration::from_millis(3)));
+        lose(&mut health);
+        assert_eq!(lose(&mut health), Some(Recovery::Reconnect));
+    }
+
+    #[test]
+    fn a_recovery_waits_until_it_is_taken_and_reset_ends_the_outage() {
+        let mut health = HealthMonitor::new(2);
+
+        health.record(Probe::Lost);
+        health.record(Probe::Lost);
+        health.record(Probe::Lost);
+        assert_eq!(health.take_recovery("home"), Some(Recovery::Reconnect));
+
+        health.reset();
+        health.record(Probe::Lost);
+        health.record(Probe::Lost);
+        assert_eq!(
+            health.take_recovery("home"),
+            Some(Recovery::Reconnect),
+            "a reset forgets the earlier reconnect"
+        );
+    }
+}
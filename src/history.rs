@@ -0,0 +1,366 @@
+//! Log of successful connections and of their end, stored one tab-separated
+//! record per line in `$XDG_STATE_HOME/nm-wifi/history.log`.
+
+use std::{
+    collections::{HashMap, HashSet},
+    fs::{self, OpenOptions},
+    io::Write,
+    path::PathBuf,
+    time::{Duration, SystemTime, UNIX_EPOCH},
+};
+
+use crate::{config::xdg_path, wifi::WifiNetwork};
+
+/// Visits needed before a network counts as "usual" for a place.
+const SUGGESTION_MIN_VISITS: usize = 2;
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum HistoryEvent {
+    #[default]
+    Connected,
+    /// The connection ended, asked for or not.
+    Disconnected,
+    /// The gateway stopped answering and the health monitor stepped in.
+    GatewayLost,
+}
+
+impl HistoryEvent {
+    pub fn label(self) -> &'static str {
+        match self {
+            Self::Connected => "Connected",
+            Self::Disconnected => "Disconnected",
+            Self::GatewayLost => "Gateway lost",
+        }
+    }
+
+    fn key(self) -> &'static str {
+        match self {
+            Self::Connected => "connected",
+            Self::Disconnected => "disconnected",
+            Self::GatewayLost => "gateway_lost",
+        }
+    }
+
+    fn from_key(key: &str) -> Option<Self> {
+        match key {
+            "connected" => Some(Self::Connected),
+            "disconnected" => Some(Self::Disconnected),
+            "gateway_lost" => Some(Self::GatewayLost),
+            _ => None,
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct HistoryEntry {
+    /// Seconds since the Unix epoch.
+    pub timestamp: u64,
+    pub ssid: String,
+    /// Other SSIDs in range when the connection was made, used as a cheap
+    /// fingerprint of where it happened.
+    pub neighbours: Vec<String>,
+    pub event: HistoryEvent,
+    pub adapter: Option<String>,
+    /// Seconds a disconnected connection had been up.
+    pub duration: Option<u64>,
+}
+
+fn unix_now() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|elapsed| elapsed.as_secs())
+        .unwrap_or_default()
+}
+
+impl HistoryEntry {
+    pub fn now(
+        ssid: &str,
+        networks: &[WifiNetwork],
+        adapter: Option<&str>,
+    ) -> Self {
+        Self {
+            timestamp: unix_now(),
+            ssid: ssid.to_string(),
+            neighbours: networks
+                .iter()
+                .filter(|network| network.ssid != ssid)
+                .map(|network| network.ssid.clone())
+                .collect(),
+            event: HistoryEvent::Connected,
+            adapter: adapter.map(str::to_string),
+            duration: None,
+        }
+    }
+
+    /// The end of a connection to `ssid` that lasted `duration`.
+    pub fn disconnected_now(
+        ssid: &str,
+        duration: Duration,
+        adapter: Option<&str>,
+    ) -> Self {
+        Self {
+            timestamp: unix_now(),
+            ssid: ssid.to_string(),
+            neighbours: Vec::new(),
+            event: HistoryEvent::Disconnected,
+            adapter: adapter.map(str::to_string),
+            duration: Some(duration.as_secs()),
+        }
+    }
+
+    /// The health monitor giving up on the gateway of `ssid`.
+    pub fn gateway_lost_now(ssid: &str, adapter: Option<&str>) -> Self {
+        Self {
+            timestamp: unix_now(),
+            ssid: ssid.to_string(),
+            neighbours: Vec::new(),
+            event: HistoryEvent::GatewayLost,
+            adapter: adapter.map(str::to_string),
+            duration: None,
+        }
+    }
+
+    fn to_line(&self) -> String {
+        let neighbours = self
+            .neighbours
+            .iter()
+            .map(|ssid| escape(ssid))
+            .collect::<Vec<_>>()
+            .join(",");
+        format!(
+            "{}\t{}\t{}\t{}\t{}\t{}",
+            self.timestamp,
+            escape(&self.ssid),
+            neighbours,
+            self.event.key(),
+            self.adapter.as_deref().map(escape).unwrap_or_default(),
+            self.duration
+                .map(|secs| secs.to_string())
+                .unwrap_or_default()
+        )
+    }
+
+    /// Reads a record; the older three-field records are connections.
+    fn from_line(line: &str) -> Option<Self> {
+        let mut fields = line.split('\t');
+        let timestamp = fields.next()?.parse().ok()?;
+        let ssid = unescape(fields.next()?);
+        let neighbours = fields
+            .next()
+            .unwrap_or_default()
+            .split(',')
+            .filter(|ssid| !ssid.is_empty())
+            .map(unescape)
+            .collect();
+        let event = match fields.next() {
+            Some(key) => HistoryEvent::from_key(key)?,
+            None => HistoryEvent::Connected,
+        };
+        let adapter = fields
+            .next()
+            .filter(|adapter| !adapter.is_empty())
+            .map(unescape);
+        let duration = fields.next().and_then(|secs| secs.parse().ok());
+
+        Some(Self {
+            timestamp,
+            ssid,
+            neighbours,
+            event,
+            adapter,
+            duration,
+        })
+    }
+
+    /// A past connection happened "here" when at least half of the networks
+    /// seen back then are in range again.
+    fn matches_environment(&self, visible: &HashSet<&str>) -> bool {
+        let shared = self
+            .neighbours
+            .iter()
+            .filter(|ssid| visible.contains(ssid.as_str()))
+            .count();
+        shared > 0 && shared * 2 >= self.neighbours.len()
+    }
+}
+
+pub(crate) fn escape(value: &str) -> String {
+    value
+        .replace('%', "%25")
+        .replace('\t', "%09")
+        .replace('\n', "%0A")
+        .replace(',', "%2C")
+}
+
+pub(crate) fn unescape(value: &str) -> String {
+    value
+        .replace("%2C", ",")
+        .replace("%0A", "\n")
+        .replace("%09", "\t")
+        .replace("%25", "%")
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct History {
+    /// Where new entries are appended; `None` keeps history in memory only.
+    pub path: Option<PathBuf>,
+    pub entries: Vec<HistoryEntry>,
+}
+
+impl History {
+    pub fn default_path() -> Option<PathBuf> {
+        xdg_path("XDG_STATE_HOME", ".local/state", "history.log")
+    }
+
+    /// Reads the log at `path`; unreadable files and malformed lines are
+    /// skipped since history is only a convenience.
+    pub fn load(path: Option<PathBuf>) -> Self {
+        let entries = path
+            .as_ref()
+            .and_then(|path| fs::read_to_string(path).ok())
+            .map(|contents| {
+                contents
+                    .lines()
+                    .filter_map(HistoryEntry::from_line)
+                    .collect()
+            })
+            .unwrap_or_default();
+
+        Self { path, entries }
+    }
+
+    pub fn record(&mut self, entry: HistoryEntry) {
+        if let Some(path) = &self.path {
+            if let Some(parent) = path.parent() {
+                let _ = fs::create_dir_all(parent);
+            }
+            if let Ok(mut file) =
+                OpenOptions::new().create(true).append(true).open(path)
+            {
+                let _ = writeln!(file, "{}", entry.to_line());
+            }
+        }
+
+        self.entries.push(entry);
+    }
+
+    /// The SSID most often joined in an environment resembling the visible
+    /// networks, if it is in range and has been used here repeatedly.
+    pub fn suggest<'a>(
+        &self,
+        networks: &'a [WifiNetwork],
+    ) -> Option<&'a WifiNetwork> {
+        let visible = networks
+            .iter()
+            .map(|network| network.ssid.as_str())
+            .collect::<HashSet<_>>();
+
+        let mut visits: HashMap<&str, (usize, u64)> = HashMap::new();
+        for entry in &self.entries {
+            if entry.event == HistoryEvent::Connected
+                && visible.contains(entry.ssid.as_str())
+                && entry.matches_environment(&visible)
+            {
+                let (count, last_seen) =
+                    visits.entry(entry.ssid.as_str()).or_default();
+                *count += 1;
+                *last_seen = (*last_seen).max(entry.timestamp);
+            }
+        }
+
+        let (ssid, _) = visits
+            .into_iter()
+            .filter(|(_, (count, _))| *count >= SUGGESTION_MIN_VISITS)
+            .max_by_key(|(_, visits)| *visits)?;
+
+        networks.iter().find(|network| network.ssid == ssid)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{History, HistoryEntry, HistoryEvent};
+    use crate::wifi::{WifiNetwork, WifiSecurity};
+
+    fn network(ssid: &str) -> WifiNetwork {
+        WifiNetwork {
+            ssid: ssid.to_string(),
+            raw_ssid: None,
+            signal_strength: 70,
+            security: WifiSecurity::WpaPsk,
+            frequency: 5180,
+            connected: false,
+            saved: false,
+            max_bitrate: 0,
+            wpa_flags: 0,
+            rsn_flags: 0,
+            access_points: Vec::new(),
+        }
+    }
+
+    fn entry(timestamp: u64, ssid: &str, neighbours: &[&str]) -> HistoryEntry {
+        HistoryEntry {
+            timestamp,
+            ssid: ssid.to_string(),
+            neighbours: neighbours
+                .iter()
+                .map(|ssid| ssid.to_string())
+                .collect(),
+            event: HistoryEvent::Connected,
+            adapter: None,
+            duration: None,
+        }
+    }
+
+    #[test]
+    fn entries_round_trip_through_the_log_format() {
+        let original = entry(42, "Cafe\t%1", &["Guest, 2.4", "Office"]);
+        assert_eq!(
+            HistoryEntry::from_line(&original.to_line()),
+            Some(original)
+        );
+
+        let drop = HistoryEntry {
+            event: HistoryEvent::Disconnected,
+            adapter: Some("wlan0".to_string()),
+            duration: Some(3600),
+            ..entry(43, "Cafe", &[])
+        };
+        assert_eq!(HistoryEntry::from_line(&drop.to_line()), Some(drop));
+        let lost = HistoryEntry::gateway_lost_now("Cafe", Some("wlan0"));
+        assert_eq!(HistoryEntry::from_line(&lost.to_line()), Some(lost));
+        assert_eq!(
+            HistoryEntry::from_line("41\tHome\tOffice"),
+            Some(entry(41, "Home", &["Office"])),
+            "records from before disconnects were logged"
+        );
+    }
+
+    #[test]
+    fn suggests_the_network_usually_joined_among_these_neighbours() {
+        let history = History {
+            path: None,
+            entries: vec![
+                entry(1, "Office", &["Lobby", "Printer"]),
+                entry(2, "Office", &["Lobby", "Printer", "Cafe"]),
+                entry(3, "Home", &["Neighbour"]),
+                entry(4, "Home", &["Neighbour"]),
+                entry(5, "Lobby", &["Office", "Printer"]),
+                HistoryEntry {
+                    event: HistoryEvent::Disconnected,
+                    ..entry(6, "Lobby", &["Office", "Printer"])
+                },
+            ],
+        };
+        let networks =
+            [network("Lobby"), network("Printer"), network("Office")];
+
+        assert_eq!(
+            history
+                .suggest(&networks)
+                .map(|network| network.ssid.as_str()),
+            Some("Office")
+        );
+        assert!(history.suggest(&[network("Neighbour")]).is_none());
+    }
+}
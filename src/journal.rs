@@ -0,0 +1,77 @@
+//! NetworkManager's messages from the systemd journal, read with
+//! `journalctl` and narrowed to the adapter and network in use, so failed
+//! authentication or DHCP can be looked into without leaving the TUI.
+
+use std::{
+    io,
+    process::{Command, Stdio},
+};
+
+/// Lines of the journal read per refresh, before narrowing them down.
+const JOURNAL_SCAN_LINES: usize = 2000;
+/// Lines kept for the log screen.
+pub const JOURNAL_LINES: usize = 300;
+
+/// The newest NetworkManager messages mentioning any of `filters`, or all
+/// of them when `filters` is empty, oldest first.
+pub fn read_network_manager_log(filters: &[String]) -> io::Result<Vec<String>> {
+    let output = Command::new("journalctl")
+        .args(["--unit", "NetworkManager", "--output", "short-iso"])
+        .args(["--no-pager", "--quiet", "--lines"])
+        .arg(JOURNAL_SCAN_LINES.to_string())
+        .stdin(Stdio::null())
+        .output()?;
+    if !output.status.success() {
+        let stderr = String::from_utf8_lossy(&output.stderr);
+        return Err(io::Error::other(match stderr.trim() {
+            "" => format!("journalctl exited with {}", output.status),
+            message => message.to_string(),
+        }));
+    }
+
+    Ok(matching_lines(
+        &String::from_utf8_lossy(&output.stdout),
+        filters,
+    ))
+}
+
+/// The last `JOURNAL_LINES` lines of `output` that mention any of
+/// `filters`.
+pub fn matching_lines(output: &str, filters: &[String]) -> Vec<String> {
+    let mut lines = output
+        .lines()
+        .filter(|line| {
+            filters.is_empty()
+                || filters.iter().any(|filter| line.contains(filter.as_str()))
+        })
+        .map(str::to_string)
+        .collect::<Vec<_>>();
+    let excess = lines.len().saturating_sub(JOURNAL_LINES);
+    lines.drain(..excess);
+    lines
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{JOURNAL_LINES, matching_lines};
+
+    #[test]
+    fn lines_are_narrowed_to_the_adapter_or_network() {
+        let output = "\
+2026-10-17T02:13:01+0000 host NetworkManager[612]: <info>  device (wlan0): state change: activated -> failed
+2026-10-17T02:13:01+0000 host NetworkManager[612]: <info>  device (eth0): carrier: link connected
+2026-10-17T02:13:02+0000 host NetworkManager[612]: <warn>  Connection 'home' failed to autoconnect
+";
+        let filters = ["wlan0".to_string(), "home".to_string()];
+
+        let lines = matching_lines(output, &filters);
+
+        assert_eq!(lines.len(), 2);
+        assert!(lines[0].contains("activated -> failed"));
+        assert!(lines[1].contains("failed to autoconnect"));
+        assert_eq!(matching_lines(output, &[]).len(), 3);
+
+        let long = "wlan0\n".repeat(JOURNAL_LINES + 5);
+        assert_eq!(matching_lines(&long, &filters).len(), JOURNAL_LINES);
+    }
+}
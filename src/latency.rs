@@ -0,0 +1,185 @@
+//! Round-trip times to the gateway and a configurable host, measured with
+//! the system `ping` so no raw-socket privileges are needed.
+
+use std::{
+    collections::VecDeque,
+    io,
+    process::{Command, Stdio},
+    time::Duration,
+};
+
+pub const DEFAULT_LATENCY_HOST: &str = "1.1.1.1";
+
+/// Replies kept for the packet loss figure.
+const LOSS_WINDOW: usize = 20;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Probe {
+    Reply(Duration),
+    Lost,
+}
+
+/// One round of probes. `gateway` is `None` when the connection has no
+/// IPv4 gateway to ping.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct LatencySample {
+    pub gateway: Option<Probe>,
+    pub host: Probe,
+}
+
+/// Sends a single echo request with a one second deadline. Only a missing
+/// `ping` binary is an error; no reply counts as a lost probe.
+pub fn ping(host: &str) -> io::Result<Probe> {
+    let output = Command::new("ping")
+        .args(["-n", "-c", "1", "-W", "1", host])
+        .stdin(Stdio::null())
+        .stderr(Stdio::null())
+        .output()?;
+
+    Ok(output
+        .status
+        .success()
+        .then(|| parse_ping_rtt(&String::from_utf8_lossy(&output.stdout)))
+        .flatten()
+        .map_or(Probe::Lost, Probe::Reply))
+}
+
+/// Reads the `time=12.3 ms` field of a ping reply line.
+fn parse_ping_rtt(output: &str) -> Option<Duration> {
+    let time = output.split("time=").nth(1)?;
+    let millis = time.split_whitespace().next()?.trim_end_matches("ms");
+    millis
+        .parse::<f64>()
+        .ok()
+        .map(|millis| Duration::from_secs_f64(millis / 1000.0))
+}
+
+/// Recent probes to one target.
+#[derive(Debug, Clone, Default)]
+pub struct LatencyTracker {
+    probes: VecDeque<Probe>,
+}
+
+impl LatencyTracker {
+    pub fn record(&mut self, probe: Probe) {
+        if self.probes.len() == LOSS_WINDOW {
+            self.probes.pop_front();
+        }
+        self.probes.push_back(probe);
+    }
+
+    pub fn last(&self) -> Option<Probe> {
+        self.probes.back().copied()
+    }
+
+    pub fn loss_percent(&self) -> Option<u8> {
+        if self.probes.is_empty() {
+            return None;
+        }
+        let lost = self
+            .probes
+            .iter()
+            .filter(|probe| **probe == Probe::Lost)
+            .count();
+        Some((lost * 100 / self.probes.len()) as u8)
+    }
+}
+
+/// Latency to the gateway and to the configured host while connected.
+#[derive(Debug, Clone)]
+pub struct LatencyMonitor {
+    pub host: String,
+    pub gateway: LatencyTracker,
+    pub remote: LatencyTracker,
+}
+
+impl LatencyMonitor {
+    pub fn new(host: impl Into<String>) -> Self {
+        Self {
+            host: host.into(),
+            gateway: LatencyTracker::default(),
+            remote: LatencyTracker::default(),
+        }
+    }
+
+    pub fn record(&mut self, sample: LatencySample) {
+        if let Some(probe) = sample.gateway {
+            self.gateway.record(probe);
+        }
+        self.remote.record(sample.host);
+    }
+
+    /// The worse loss of the two targets, once anything was measured.
+    pub fn loss_percent(&self) -> Option<u8> {
+        self.gateway.loss_percent().max(self.remote.loss_percent())
+    }
+
+    pub fn reset(&mut self) {
+        self.gateway = LatencyTracker::default();
+        self.remote = LatencyTracker::default();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use super::{
+        LatencyMonitor,
+        LatencySample,
+        LatencyTracker,
+        Probe,
+        parse_ping_rtt,
+    };
+
+    #[test]
+    fn rtt_is_read_from_the_reply_line() {
+        let output = "PING 1.1.1.1 (1.1.1.1) 56(84) bytes of data.\n\
+                      64 bytes from 1.1.1.1: icmp_seq=1 ttl=57 time=12.4 ms\n";
+
+        assert_eq!(
+            parse_ping_rtt(output),
+            Some(Duration::from_secs_f64(0.0124))
+        );
+        assert_eq!(
+            parse_ping_rtt("time=0.9ms"),
+            Some(Duration::from_micros(900))
+        );
+        assert_eq!(parse_ping_rtt("1 packets transmitted, 0 received"), None);
+    }
+
+    #[test]
+    fn loss_covers_the_recent_window() {
+        let mut tracker = LatencyTracker::default();
+        assert_eq!(tracker.loss_percent(), None);
+
+        tracker.record(Probe::Lost);
+        for _ in 0..19 {
+            tracker.record(Probe::Reply(Duration::from_millis(5)));
+        }
+        assert_eq!(tracker.loss_percent(), Some(5));
+
+        tracker.record(Probe::Reply(Duration::from_millis(5)));
+        assert_eq!(tracker.loss_percent(), Some(0), "old loss ages out");
+    }
+
+    #[test]
+    fn monitor_reports_the_worse_target() {
+        let mut monitor = LatencyMonitor::new("1.1.1.1");
+        monitor.record(LatencySample {
+            gateway: None,
+            host: Probe::Lost,
+        });
+        monitor.record(LatencySample {
+            gateway: Some(Probe::Reply(Duration::from_millis(2))),
+            host: Probe::Reply(Duration::from_millis(20)),
+        });
+
+        assert_eq!(monitor.gateway.loss_percent(), Some(0));
+        assert_eq!(monitor.loss_percent(), Some(50));
+        assert_eq!(
+            monitor.remote.last(),
+            Some(Probe::Reply(Duration::from_millis(20)))
+        );
+    }
+}
@@ -1,9 +1,28 @@
 pub mod app;
 pub mod app_state;
+pub mod avoid;
 pub mod backend;
+pub mod clipboard;
+pub mod config;
 pub mod demo_screenshots;
+pub mod glyphs;
+pub mod health;
+pub mod history;
+pub mod journal;
+pub mod latency;
 pub mod network;
+pub mod notify;
+pub mod portal;
+pub mod qr;
+pub mod search;
+pub mod spectrum;
+pub mod speedtest;
+pub mod ssid_list;
+pub mod status;
+pub mod survey;
+pub mod terminal;
 pub mod theme;
+pub mod traffic;
 pub mod types;
 pub mod ui;
 pub mod wifi;
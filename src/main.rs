@@ -1,7 +1,12 @@
 use std::{error::Error, io};
 
 use crossterm::{
-    event::{DisableMouseCapture, EnableMouseCapture},
+    event::{
+        DisableBracketedPaste,
+        DisableMouseCapture,
+        EnableBracketedPaste,
+        EnableMouseCapture,
+    },
     execute,
     terminal::{
         EnterAlternateScreen,
@@ -12,26 +17,113 @@ use crossterm::{
 };
 use nm_wifi::{
     app::{CleanupGuard, run_app},
+    avoid::AvoidedBssids,
+    config::Config,
+    glyphs::Glyphs,
+    health::HealthMonitor,
+    history::History,
+    latency::LatencyMonitor,
+    notify::Notifier,
+    ssid_list::SsidList,
+    status::{self, current_status},
+    terminal::{Background, no_color_requested},
+    theme::ThemeName,
     types::App,
 };
 use ratatui::{Terminal, backend::CrosstermBackend};
 
+/// `--demo` only exists in builds with the demo backend.
+const USAGE: &str = if cfg!(feature = "demo") {
+    "usage: nm-wifi [--demo] [--no-color] [--status [--format plain|tmux]]"
+} else {
+    "usage: nm-wifi [--no-color] [--status [--format plain|tmux]]"
+};
+
 #[tokio::main]
 async fn main() -> Result<(), Box<dyn Error>> {
+    #[cfg(feature = "demo")]
+    let args = {
+        let (demo, args): (Vec<_>, Vec<_>) =
+            std::env::args().skip(1).partition(|arg| arg == "--demo");
+        if !demo.is_empty() {
+            nm_wifi::backend::enable_lively_demo();
+        }
+        args
+    };
+    #[cfg(not(feature = "demo"))]
+    let args = std::env::args().skip(1);
+    let (no_color, args): (Vec<_>, Vec<_>) =
+        args.into_iter().partition(|arg| arg == "--no-color");
+    let status_format = status::parse_args(args).unwrap_or_else(|error| {
+        eprintln!("nm-wifi: {error}\n{USAGE}");
+        std::process::exit(2);
+    });
+    if let Some(format) = status_format {
+        println!("{}", current_status(format)?);
+        return Ok(());
+    }
+
+    let config = Config::load()?;
+
     enable_raw_mode()?;
+    // Only the auto theme looks at the background, so other themes spare
+    // the terminal the query.
+    let background = if config.theme == ThemeName::Auto {
+        Background::detect().unwrap_or_default()
+    } else {
+        Background::default()
+    };
     let mut stdout = io::stdout();
-    execute!(stdout, EnterAlternateScreen, EnableMouseCapture)?;
+    execute!(stdout, EnterAlternateScreen)?;
+    // Terminals without mouse reporting are driven by the keyboard alone.
+    let _ = execute!(stdout, EnableMouseCapture);
+    let _ = execute!(stdout, EnableBracketedPaste);
 
     let cleanup_guard = CleanupGuard::new(|| {
         let _ = disable_raw_mode();
-        let _ =
-            execute!(io::stdout(), LeaveAlternateScreen, DisableMouseCapture);
+        let _ = execute!(
+            io::stdout(),
+            LeaveAlternateScreen,
+            DisableMouseCapture,
+            DisableBracketedPaste
+        );
     });
 
     let backend = CrosstermBackend::new(stdout);
     let mut terminal = Terminal::new(backend)?;
 
-    let app = App::new();
+    let mut app = App::new();
+    app.rescan_interval = config.rescan_interval;
+    app.scan_min_interval = config.scan_min_interval;
+    app.scan_mode = config.scan_mode;
+    app.scan_band = config.scan_band;
+    // The health monitor works off the latency monitor's gateway pings.
+    let health_monitor = config.health_check_failures > 0;
+    app.latency = (config.latency_monitor || health_monitor)
+        .then(|| LatencyMonitor::new(config.latency_host));
+    app.health = health_monitor
+        .then(|| HealthMonitor::new(config.health_check_failures));
+    app.glyphs = Glyphs::resolve(config.glyph_width);
+    app.background = background;
+    app.color_depth = config.color_depth.resolve();
+    app.monochrome = !no_color.is_empty() || no_color_requested();
+    app.set_theme(config.theme, config.theme_colors);
+    app.password_mask = config.password_mask;
+    app.speedtest_url = config.speedtest_url;
+    app.connect_retries = config.connect_retries;
+    app.unused_profile_days = config.unused_profile_days;
+    app.user_scoped_profiles = config.user_profiles;
+    app.delete_guest_profile_on_quit = config.delete_guest_profile_on_quit;
+    app.reset_profile_scope();
+    app.notifier = Notifier::new(config.notifications, config.webhook_url);
+    if !cfg!(feature = "demo") {
+        app.history = History::load(History::default_path());
+        app.avoided = AvoidedBssids::load(AvoidedBssids::default_path());
+        app.ignored = SsidList::load(SsidList::ignored_path());
+        app.favorites = SsidList::load(SsidList::favorites_path());
+        app.status_cache = status::cache_path();
+        app.watch_config(Config::path());
+    }
     let res = run_app(&mut terminal, app).await;
 
     terminal.show_cursor()?;
@@ -40,7 +132,8 @@ async fn main() -> Result<(), Box<dyn Error>> {
     execute!(
         terminal.backend_mut(),
         LeaveAlternateScreen,
-        DisableMouseCapture
+        DisableMouseCapture,
+        DisableBracketedPaste
     )?;
 
     if let Err(err) = res {
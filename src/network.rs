@@ -1,16 +1,39 @@
+use std::{
+    cmp::Ordering,
+    error::Error,
+    fmt,
+    sync::{
+        Arc,
+        atomic::{AtomicBool, AtomicU8, Ordering as AtomicOrdering},
+    },
+};
 #[cfg(any(test, not(feature = "demo")))]
-use std::collections::HashMap;
-use std::error::Error;
+use std::{
+    cmp::Reverse,
+    collections::HashMap,
+    time::{Duration, Instant},
+};
 
 #[cfg(any(test, not(feature = "demo")))]
 use dbus::arg::{PropMap, RefArg, Variant};
+use icu_collator::{Collator, CollatorOptions, Strength};
+use icu_locid::Locale;
+use regex::Regex;
 
-use crate::wifi::WifiNetwork;
+#[cfg(any(test, not(feature = "demo")))]
+use crate::wifi::WifiAccessPoint;
+use crate::wifi::{WifiBand, WifiNetwork};
 
 #[cfg(feature = "demo")]
 pub(crate) mod demo;
+#[cfg(feature = "demo")]
+pub(crate) mod demo_faults;
+#[cfg(any(test, not(feature = "demo")))]
+pub(crate) mod keyfile;
 #[cfg(not(feature = "demo"))]
 pub(crate) mod networkmanager;
+#[cfg(not(feature = "demo"))]
+pub(crate) mod wpa_supplicant;
 
 pub enum ConnectionRequest<'a> {
     Open {
@@ -20,8 +43,1017 @@ pub enum ConnectionRequest<'a> {
         network: &'a WifiNetwork,
         passphrase: &'a str,
     },
+    /// Joins an open network with throwaway hygiene settings: a randomized
+    /// MAC address, no autoconnect and a metered link.
+    Guest {
+        network: &'a WifiNetwork,
+    },
+}
+
+/// Profile settings chosen by the user when a connection is created.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct ConnectionOptions {
+    /// Overrides the `connection.id` of the new profile.
+    pub profile_name: Option<String>,
+    /// Proxy for networks that need one.
+    pub proxy: ProxyConfig,
+    pub pmf: Pmf,
+    /// Restricts the profile to this user instead of sharing it with every
+    /// user of the machine.
+    pub owner: Option<String>,
+    /// Probes for the SSID by name because its access points do not
+    /// broadcast it.
+    pub hidden: bool,
+    /// Leaves the password out of the system connection file; a secret
+    /// agent of the user's session (e.g. GNOME Keyring or KWallet) stores
+    /// it and hands it to NetworkManager on activation.
+    pub agent_owned_secrets: bool,
+}
+
+/// Returned by connects whose password the network rejected, so the UI can
+/// ask for it again instead of showing a generic failure.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct WrongPassword(pub String);
+
+impl fmt::Display for WrongPassword {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.0)
+    }
+}
+
+impl Error for WrongPassword {}
+
+/// Whether an error message says polkit refused the action, as
+/// NetworkManager's `PermissionDenied` errors and `pkexec` put it.
+pub fn is_authorization_failure(message: &str) -> bool {
+    let message = message.to_lowercase();
+    message.contains("not authorized")
+        || message.contains("insufficient privileges")
+}
+
+/// Returned when the system bus or NetworkManager itself cannot be reached,
+/// so the UI can explain that instead of failing every request.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ManagerUnavailable(pub String);
+
+impl fmt::Display for ManagerUnavailable {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.0)
+    }
+}
+
+impl Error for ManagerUnavailable {}
+
+/// Why a connect failed, as far as trying again is concerned.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum FailureKind {
+    /// The network rejected the credentials.
+    Auth,
+    /// The link came up but no address could be obtained.
+    Dhcp,
+    /// The access point was out of range or did not answer.
+    ApNotFound,
+    Other,
+}
+
+impl FailureKind {
+    pub fn of(error: &(dyn Error + 'static)) -> Self {
+        if error.is::<WrongPassword>() {
+            Self::Auth
+        } else if let Some(failure) = error.downcast_ref::<ActivationFailed>() {
+            failure.kind
+        } else {
+            Self::Other
+        }
+    }
+
+    /// Whether the same attempt may well succeed a moment later.
+    pub fn is_transient(self) -> bool {
+        matches!(self, Self::Dhcp | Self::ApNotFound)
+    }
+
+    pub fn label(self) -> &'static str {
+        match self {
+            Self::Auth => "Authentication failed",
+            Self::Dhcp => "DHCP failed",
+            Self::ApNotFound => "Access point not found",
+            Self::Other => "Connection failed",
+        }
+    }
+}
+
+/// An activation NetworkManager gave up on, with the failure classified.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ActivationFailed {
+    pub kind: FailureKind,
+    pub message: String,
+}
+
+impl fmt::Display for ActivationFailed {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.message)
+    }
+}
+
+impl Error for ActivationFailed {}
+
+/// NetworkManager's `NMDeviceStateReason`: why a device last changed state,
+/// e.g. why an activation failed or a connection dropped.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct DeviceStateReason(pub u32);
+
+impl DeviceStateReason {
+    const NONE: u32 = 0;
+    const USER_REQUESTED: u32 = 39;
+    const NEW_ACTIVATION: u32 = 60;
+
+    /// The reason's name without the `NM_DEVICE_STATE_REASON_` prefix, for
+    /// the reasons a Wi-Fi device can report.
+    pub fn name(self) -> Option<&'static str> {
+        Some(match self.0 {
+            0 => "NONE",
+            1 => "UNKNOWN",
+            3 => "NOW_UNMANAGED",
+            4 => "CONFIG_FAILED",
+            5 => "IP_CONFIG_UNAVAILABLE",
+            6 => "IP_CONFIG_EXPIRED",
+            7 => "NO_SECRETS",
+            8 => "SUPPLICANT_DISCONNECT",
+            9 => "SUPPLICANT_CONFIG_FAILED",
+            10 => "SUPPLICANT_FAILED",
+            11 => "SUPPLICANT_TIMEOUT",
+            15 => "DHCP_START_FAILED",
+            16 => "DHCP_ERROR",
+            17 => "DHCP_FAILED",
+            35 => "FIRMWARE_MISSING",
+            36 => "REMOVED",
+            37 => "SLEEPING",
+            38 => "CONNECTION_REMOVED",
+            39 => "USER_REQUESTED",
+            40 => "CARRIER",
+            42 => "SUPPLICANT_AVAILABLE",
+            50 => "DEPENDENCY_FAILED",
+            53 => "SSID_NOT_FOUND",
+            54 => "SECONDARY_CONNECTION_FAILED",
+            60 => "NEW_ACTIVATION",
+            64 => "IP_ADDRESS_DUPLICATE",
+            65 => "IP_METHOD_UNSUPPORTED",
+            _ => return None,
+        })
+    }
+
+    /// What the reason means for someone trying to get online.
+    pub fn explanation(self) -> String {
+        match self.0 {
+            1 => "NetworkManager did not say why".to_string(),
+            3 => {
+                "the adapter is no longer managed by NetworkManager".to_string()
+            }
+            4 => "the connection profile could not be applied".to_string(),
+            5 => "no IP configuration could be obtained".to_string(),
+            6 => "the IP address lease expired".to_string(),
+            7 => "the password was missing or not accepted".to_string(),
+            8 => "the access point ended the Wi-Fi handshake".to_string(),
+            9 => "the Wi-Fi supplicant rejected the profile".to_string(),
+            10 => "the Wi-Fi supplicant failed".to_string(),
+            11 => "the access point did not answer in time".to_string(),
+            15 => "the DHCP client could not be started".to_string(),
+            16 => "the DHCP client hit an error".to_string(),
+            17 => "no DHCP server handed out an address".to_string(),
+            35 => "the adapter's firmware is missing".to_string(),
+            36 => "the adapter was removed".to_string(),
+            37 => "the system went to sleep".to_string(),
+            38 => "the connection profile was deleted".to_string(),
+            39 => "it was disconnected on request".to_string(),
+            40 => "the adapter lost its link".to_string(),
+            42 => "the Wi-Fi supplicant restarted".to_string(),
+            50 => "a connection it depends on failed".to_string(),
+            53 => "the network is out of range".to_string(),
+            54 => "a secondary connection (e.g. a VPN) failed".to_string(),
+            60 => "another connection was activated".to_string(),
+            64 => "another host already uses the IP address".to_string(),
+            65 => "the profile's IP method is not supported".to_string(),
+            reason => format!("device state reason {reason}"),
+        }
+    }
+
+    /// Whether the reason explains a drop the user did not ask for.
+    pub fn is_unexpected(self) -> bool {
+        !matches!(
+            self.0,
+            Self::NONE | Self::USER_REQUESTED | Self::NEW_ACTIVATION
+        )
+    }
+}
+
+impl fmt::Display for DeviceStateReason {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self.name() {
+            Some(name) => write!(f, "{} ({name})", self.explanation()),
+            None => f.write_str(&self.explanation()),
+        }
+    }
+}
+
+/// Shared flag the UI sets when the user abandons a connect; the backend
+/// checks it while waiting for the activation and backs out.
+#[derive(Debug, Clone, Default)]
+pub struct CancelToken(Arc<AtomicBool>);
+
+impl CancelToken {
+    pub fn cancel(&self) {
+        self.0.store(true, AtomicOrdering::SeqCst);
+    }
+
+    pub fn is_cancelled(&self) -> bool {
+        self.0.load(AtomicOrdering::SeqCst)
+    }
+}
+
+/// The steps of an activation, as NetworkManager's device states go
+/// through them.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum ActivationStep {
+    Prepare,
+    /// Associating and, on secured networks, the key handshake.
+    Config,
+    /// NetworkManager asked for secrets it did not have.
+    NeedAuth,
+    IpConfig,
+    Activated,
+}
+
+impl ActivationStep {
+    pub const ALL: [Self; 5] = [
+        Self::Prepare,
+        Self::Config,
+        Self::NeedAuth,
+        Self::IpConfig,
+        Self::Activated,
+    ];
+
+    /// The step of the `NMDeviceState` `state`; the IP check and secondary
+    /// connections count as part of the IP configuration.
+    pub fn from_device_state(state: u32) -> Option<Self> {
+        match state {
+            40 => Some(Self::Prepare),
+            50 => Some(Self::Config),
+            60 => Some(Self::NeedAuth),
+            70..=90 => Some(Self::IpConfig),
+            100 => Some(Self::Activated),
+            _ => None,
+        }
+    }
+
+    pub fn label(self) -> &'static str {
+        match self {
+            Self::Prepare => "Preparing the adapter",
+            Self::Config => "Associating with the access point",
+            Self::NeedAuth => "Waiting for credentials",
+            Self::IpConfig => "Obtaining an IP address",
+            Self::Activated => "Connected",
+        }
+    }
+
+    fn bit(self) -> u8 {
+        1 << self as u8
+    }
+}
+
+/// Steps a running connect went through, recorded by the backend as the
+/// device changes state and read by the connecting screen.
+#[derive(Debug, Clone, Default)]
+pub struct ActivationProgress(Arc<AtomicU8>);
+
+impl ActivationProgress {
+    pub fn reach(&self, step: ActivationStep) {
+        self.0.fetch_or(step.bit(), AtomicOrdering::SeqCst);
+    }
+
+    /// Starts over, for another attempt of the same connect.
+    pub fn reset(&self) {
+        self.0.store(0, AtomicOrdering::SeqCst);
+    }
+
+    pub fn reached(&self, step: ActivationStep) -> bool {
+        self.0.load(AtomicOrdering::SeqCst) & step.bit() != 0
+    }
+
+    /// The furthest step reached, `None` before the device moved.
+    pub fn current(&self) -> Option<ActivationStep> {
+        ActivationStep::ALL
+            .into_iter()
+            .rev()
+            .find(|step| self.reached(*step))
+    }
+}
+
+/// Whether scans ask the adapter to scan (`scan_mode = active`) or only read
+/// what the backend's own background scans found (`passive`), which spares
+/// the battery at the cost of fresher results.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum ScanMode {
+    #[default]
+    Active,
+    Passive,
+}
+
+impl ScanMode {
+    pub fn from_key(key: &str) -> Option<Self> {
+        match key {
+            "active" => Some(Self::Active),
+            "passive" => Some(Self::Passive),
+            _ => None,
+        }
+    }
+}
+
+/// Protected Management Frames (802.11w), the `pmf` property of the
+/// `802-11-wireless-security` setting.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum Pmf {
+    /// Leaves the choice to NetworkManager's global default.
+    #[default]
+    Default,
+    Disable,
+    Optional,
+    Required,
+}
+
+impl Pmf {
+    const ALL: [Self; 4] =
+        [Self::Default, Self::Disable, Self::Optional, Self::Required];
+
+    pub fn label(self) -> &'static str {
+        match self {
+            Self::Default => "default",
+            Self::Disable => "disable",
+            Self::Optional => "optional",
+            Self::Required => "required",
+        }
+    }
+
+    /// The next setting in `ALL`, or the previous one going `backwards`.
+    pub fn cycle(self, backwards: bool) -> Self {
+        let index = Self::ALL.iter().position(|pmf| *pmf == self).unwrap_or(0);
+        let step = if backwards { Self::ALL.len() - 1 } else { 1 };
+        Self::ALL[(index + step) % Self::ALL.len()]
+    }
+
+    /// `NMSettingWirelessSecurityPmf`.
+    #[cfg(any(test, not(feature = "demo")))]
+    fn nm_value(self) -> i32 {
+        match self {
+            Self::Default => 0,
+            Self::Disable => 1,
+            Self::Optional => 2,
+            Self::Required => 3,
+        }
+    }
+}
+
+/// The `proxy` setting group of a saved profile. NetworkManager only knows
+/// automatic configuration, either by WPAD discovery or from a PAC URL or
+/// script.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub enum ProxyConfig {
+    #[default]
+    None,
+    Wpad,
+    Pac(String),
+    /// Every request goes through `host:port`, written as a PAC script
+    /// because NetworkManager has no manual method.
+    Manual(String),
+}
+
+/// How the proxy of a new profile is chosen in the password prompt.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum ProxyMode {
+    #[default]
+    None,
+    /// WPAD discovery, or the PAC URL given.
+    Auto,
+    /// A single `host:port` proxy.
+    Manual,
+}
+
+impl ProxyMode {
+    const ALL: [Self; 3] = [Self::None, Self::Auto, Self::Manual];
+
+    pub fn label(self) -> &'static str {
+        match self {
+            Self::None => "none",
+            Self::Auto => "automatic",
+            Self::Manual => "manual",
+        }
+    }
+
+    /// The next mode in `ALL`, or the previous one going `backwards`.
+    pub fn cycle(self, backwards: bool) -> Self {
+        let index =
+            Self::ALL.iter().position(|mode| *mode == self).unwrap_or(0);
+        let step = if backwards { Self::ALL.len() - 1 } else { 1 };
+        Self::ALL[(index + step) % Self::ALL.len()]
+    }
+}
+
+/// Settings of a saved profile shown in the network details.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ProfileSettings {
+    pub proxy: ProxyConfig,
+    /// Users allowed to see and use the profile; empty when it is
+    /// available to every user.
+    pub users: Vec<String>,
+}
+
+/// The login name of the user running the app.
+pub fn current_user_name() -> Option<String> {
+    ["USER", "LOGNAME"]
+        .into_iter()
+        .filter_map(|key| std::env::var(key).ok())
+        .find(|name| !name.is_empty())
+}
+
+impl ProxyConfig {
+    pub fn description(&self) -> String {
+        match self {
+            Self::None => "none".to_string(),
+            Self::Wpad => "automatic (WPAD)".to_string(),
+            Self::Pac(url) => format!("PAC {url}"),
+            Self::Manual(address) => format!("manual {address}"),
+        }
+    }
+}
+
+/// Accepts a proxy written as `host:port`.
+pub fn validate_proxy_address(address: &str) -> Result<(), String> {
+    let valid = address.rsplit_once(':').is_some_and(|(host, port)| {
+        !host.is_empty()
+            && !host.contains(char::is_whitespace)
+            && port.parse::<u16>().is_ok_and(|port| port != 0)
+    });
+    if valid {
+        Ok(())
+    } else {
+        Err("Proxy must be written as host:port".to_string())
+    }
+}
+
+/// Accepts WPA passphrases of 8 to 63 characters, or a raw 64-digit hex
+/// key.
+pub fn validate_psk(psk: &str) -> Result<(), String> {
+    let length = psk.chars().count();
+    if (8..=63).contains(&length)
+        || (length == 64 && psk.chars().all(|c| c.is_ascii_hexdigit()))
+    {
+        Ok(())
+    } else {
+        Err("Wi-Fi passwords have 8 to 63 characters, or 64 hex digits"
+            .to_string())
+    }
+}
+
+/// Accepts the URL schemes NetworkManager allows for `proxy.pac-url`.
+pub fn validate_pac_url(url: &str) -> Result<(), String> {
+    if ["http://", "https://", "file://"]
+        .iter()
+        .any(|scheme| url.starts_with(scheme))
+    {
+        Ok(())
+    } else {
+        Err("PAC URL must start with http://, https:// or file://".to_string())
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ManagerState {
+    Unknown,
+    Asleep,
+    Disconnected,
+    Disconnecting,
+    Connecting,
+    ConnectedLocal,
+    ConnectedSite,
+    ConnectedGlobal,
+}
+
+impl ManagerState {
+    /// Maps NetworkManager's `NMState` values.
+    pub fn from_nm(value: u32) -> Self {
+        match value {
+            10 => Self::Asleep,
+            20 => Self::Disconnected,
+            30 => Self::Disconnecting,
+            40 => Self::Connecting,
+            50 => Self::ConnectedLocal,
+            60 => Self::ConnectedSite,
+            70 => Self::ConnectedGlobal,
+            _ => Self::Unknown,
+        }
+    }
+
+    pub fn display_name(self) -> &'static str {
+        match self {
+            Self::Unknown => "unknown",
+            Self::Asleep => "asleep",
+            Self::Disconnected => "disconnected",
+            Self::Disconnecting => "disconnecting",
+            Self::Connecting => "connecting",
+            Self::ConnectedLocal => "local only",
+            Self::ConnectedSite => "site only",
+            Self::ConnectedGlobal => "online",
+        }
+    }
+}
+
+/// Where a NetworkManager device is in bringing up a connection.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum DeviceState {
+    Unknown,
+    Unmanaged,
+    /// No cable, or the device is not ready to connect.
+    Unavailable,
+    Disconnected,
+    Connecting,
+    Connected,
+    Disconnecting,
+    Failed,
+}
+
+impl DeviceState {
+    /// Maps NetworkManager's `NMDeviceState` values, folding the activation
+    /// stages into `Connecting`.
+    pub fn from_nm(value: u32) -> Self {
+        match value {
+            10 => Self::Unmanaged,
+            20 => Self::Unavailable,
+            30 => Self::Disconnected,
+            40..=90 => Self::Connecting,
+            100 => Self::Connected,
+            110 => Self::Disconnecting,
+            120 => Self::Failed,
+            _ => Self::Unknown,
+        }
+    }
+
+    pub fn display_name(self) -> &'static str {
+        match self {
+            Self::Unknown => "unknown",
+            Self::Unmanaged => "unmanaged",
+            Self::Unavailable => "unavailable",
+            Self::Disconnected => "disconnected",
+            Self::Connecting => "connecting",
+            Self::Connected => "connected",
+            Self::Disconnecting => "disconnecting",
+            Self::Failed => "failed",
+        }
+    }
+}
+
+/// A saved profile a device can bring up.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct DeviceProfile {
+    pub id: String,
+    pub uuid: String,
+}
+
+/// Which kind of device the devices tab lists.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum DeviceKind {
+    Ethernet,
+    Mobile,
+    /// Paired phones that share their connection over Bluetooth.
+    Bluetooth,
+}
+
+impl DeviceKind {
+    pub fn label(self) -> &'static str {
+        match self {
+            Self::Ethernet => "Ethernet",
+            Self::Mobile => "Mobile broadband",
+            Self::Bluetooth => "Bluetooth",
+        }
+    }
+}
+
+/// Link details that depend on the kind of device.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum DeviceLink {
+    Wired {
+        /// Whether a cable is plugged in and the link is up.
+        carrier: bool,
+        /// Negotiated link speed in Mbit/s, when there is a link.
+        speed_mbps: Option<u32>,
+    },
+    Modem {
+        /// Signal quality reported by ModemManager, in percent.
+        signal_quality: Option<u8>,
+        /// Name of the registered network operator.
+        operator: Option<String>,
+    },
+    Bluetooth {
+        /// Name the paired device gives itself.
+        name: Option<String>,
+    },
+}
+
+/// An Ethernet device or modem and the saved profiles it can bring up.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct NetworkDevice {
+    pub interface: String,
+    pub state: DeviceState,
+    pub link: DeviceLink,
+    /// UUID of the profile active on the device.
+    pub active_uuid: Option<String>,
+    /// Profiles bound to this interface or to no interface at all.
+    pub profiles: Vec<DeviceProfile>,
+}
+
+impl NetworkDevice {
+    pub fn is_active(&self, profile: &DeviceProfile) -> bool {
+        self.active_uuid.as_deref() == Some(profile.uuid.as_str())
+    }
+}
+
+/// A Wi-Fi Direct peer nearby, such as a printer or a screen-cast receiver.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct P2pPeer {
+    pub name: String,
+    pub hw_address: String,
+    pub manufacturer: Option<String>,
+    pub model: Option<String>,
+    pub signal_strength: u8,
+}
+
+/// How far past the local network NetworkManager's connectivity check gets.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Connectivity {
+    /// Checking is disabled or has not run yet.
+    Unknown,
+    None,
+    Portal,
+    Limited,
+    Full,
+}
+
+impl Connectivity {
+    /// Maps NetworkManager's `NMConnectivityState` values.
+    pub fn from_nm(value: u32) -> Self {
+        match value {
+            1 => Self::None,
+            2 => Self::Portal,
+            3 => Self::Limited,
+            4 => Self::Full,
+            _ => Self::Unknown,
+        }
+    }
+
+    pub fn description(self) -> &'static str {
+        match self {
+            Self::Unknown => "unknown (connectivity checking is off)",
+            Self::None => "no network access",
+            Self::Portal => "captive portal, sign in with a browser",
+            Self::Limited => "limited, no internet access",
+            Self::Full => "full internet access",
+        }
+    }
+}
+
+/// Outcome of a connectivity check; behind a portal it also carries the
+/// login page to open.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ConnectivityReport {
+    pub connectivity: Connectivity,
+    pub portal_url: Option<String>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PrimaryConnection {
+    pub id: String,
+    pub connection_type: String,
+}
+
+impl PrimaryConnection {
+    pub fn is_wifi(&self) -> bool {
+        self.connection_type == "802-11-wireless"
+    }
+
+    pub fn kind_label(&self) -> &str {
+        match self.connection_type.as_str() {
+            "802-11-wireless" => "wifi",
+            "802-3-ethernet" => "ethernet",
+            "gsm" | "cdma" => "mobile",
+            "bluetooth" => "bluetooth",
+            "vpn" | "wireguard" => "vpn",
+            other => other,
+        }
+    }
+}
+
+/// NetworkManager's overall state and the connection currently carrying the
+/// default route, which may not be the Wi-Fi link.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ManagerStatus {
+    pub state: ManagerState,
+    pub primary_connection: Option<PrimaryConnection>,
+    /// Why the Wi-Fi device last changed state; `None` when unknown.
+    pub wifi_state_reason: Option<DeviceStateReason>,
+}
+
+/// A saved Wi-Fi connection profile, whether or not the network is in range.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SavedProfile {
+    pub id: String,
+    pub uuid: String,
+    pub ssid: String,
+    /// Users named in `connection.permissions`; empty when the profile is
+    /// system-wide.
+    pub users: Vec<String>,
+    /// Seconds since the Unix epoch of the last successful activation;
+    /// `None` when the profile was never used.
+    pub last_used: Option<u64>,
+    /// The band the profile is locked to with `wifi.band`, if any.
+    pub band: Option<WifiBand>,
+    /// The access point the profile is pinned to with `wifi.bssid`, as
+    /// `AA:BB:CC:DD:EE:FF`.
+    pub bssid: Option<String>,
+    /// The `wifi.mtu` of the profile; `None` leaves it to the driver.
+    pub mtu: Option<u32>,
+    /// The `wifi.cloned-mac-address` of the profile: a fixed address, or
+    /// one of NetworkManager's `random`, `stable`, `permanent` or
+    /// `preserve`.
+    pub cloned_mac: Option<String>,
+    /// Name of the VPN brought up with the profile through
+    /// `connection.secondaries`, if any.
+    pub vpn: Option<String>,
+}
+
+/// How a bulk forget went. A profile NetworkManager refuses to delete does
+/// not stop the others, so some of the `requested` may have stayed.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ForgetReport {
+    pub forgotten: usize,
+    pub requested: usize,
+    /// Why the first profile that stayed could not be deleted.
+    pub error: Option<String>,
+}
+
+/// A link setting of a saved profile changed from the known networks view;
+/// `None` goes back to NetworkManager's default.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum LinkSetting {
+    Mtu(Option<u32>),
+    ClonedMac(Option<String>),
+}
+
+/// The cloned MAC address modes NetworkManager knows besides a fixed
+/// address.
+const CLONED_MAC_MODES: [&str; 4] =
+    ["random", "stable", "permanent", "preserve"];
+
+/// Reads an MTU typed for a profile; left empty, the driver picks it.
+pub fn parse_mtu(text: &str) -> Result<Option<u32>, String> {
+    let text = text.trim();
+    if text.is_empty() {
+        return Ok(None);
+    }
+    match text.parse::<u32>() {
+        Ok(mtu) if (576..=9000).contains(&mtu) => Ok(Some(mtu)),
+        _ => Err("MTU must be a number from 576 to 9000".to_string()),
+    }
+}
+
+/// Reads a cloned MAC address typed for a profile, written in uppercase;
+/// left empty, the permanent address of the adapter is used.
+pub fn parse_cloned_mac(text: &str) -> Result<Option<String>, String> {
+    let text = text.trim();
+    if text.is_empty() {
+        return Ok(None);
+    }
+    if let Some(mode) = CLONED_MAC_MODES
+        .iter()
+        .find(|mode| mode.eq_ignore_ascii_case(text))
+    {
+        return Ok(Some(mode.to_string()));
+    }
+    parse_bssid(text)
+        .map(|bytes| Some(format_bssid(&bytes)))
+        .ok_or_else(|| {
+            format!("{text} is not a MAC address like AA:BB:CC:DD:EE:FF")
+        })
+}
+
+/// Reads a BSSID written as six colon-separated hex bytes.
+pub fn parse_bssid(text: &str) -> Option<[u8; 6]> {
+    let mut bytes = [0u8; 6];
+    let mut parts = text.trim().split(':');
+    for byte in &mut bytes {
+        let part = parts.next()?;
+        if part.len() != 2 {
+            return None;
+        }
+        *byte = u8::from_str_radix(part, 16).ok()?;
+    }
+    parts.next().is_none().then_some(bytes)
+}
+
+pub fn format_bssid(bytes: &[u8]) -> String {
+    bytes
+        .iter()
+        .map(|byte| format!("{byte:02X}"))
+        .collect::<Vec<_>>()
+        .join(":")
+}
+
+impl SavedProfile {
+    /// Whether the profile was never used, or last used before `cutoff`
+    /// (seconds since the Unix epoch).
+    pub fn unused_since(&self, cutoff: u64) -> bool {
+        self.last_used.is_none_or(|last_used| last_used < cutoff)
+    }
+}
+
+/// Why NetworkManager is not autoconnecting to a saved network in range.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum AutoconnectBlock {
+    RadioDisabled,
+    DeviceUnmanaged,
+    /// Set by a manual disconnect, which stops NM from reconnecting.
+    DeviceAutoconnectOff,
+    ProfileAutoconnectOff,
+    /// Every attempt failed and NM stopped trying until the profile changes.
+    RetriesExhausted,
+}
+
+impl AutoconnectBlock {
+    pub fn description(self) -> &'static str {
+        match self {
+            Self::RadioDisabled => "the Wi-Fi radio is disabled",
+            Self::DeviceUnmanaged => "the device is not managed by NM",
+            Self::DeviceAutoconnectOff => {
+                "off on the device after a manual disconnect"
+            }
+            Self::ProfileAutoconnectOff => "turned off in the profile",
+            Self::RetriesExhausted => "retries exhausted after failed attempts",
+        }
+    }
+
+    /// Whether clearing the block can lift it; the others are settings the
+    /// user chose.
+    pub fn clearable(self) -> bool {
+        matches!(self, Self::DeviceAutoconnectOff | Self::RetriesExhausted)
+    }
+}
+
+/// What NetworkManager reports about the Wi-Fi device and a saved profile.
+#[cfg(any(test, not(feature = "demo")))]
+#[derive(Debug, Clone, Copy)]
+pub(crate) struct AutoconnectFacts {
+    pub wireless_enabled: bool,
+    pub device_managed: bool,
+    pub device_autoconnect: bool,
+    pub profile_autoconnect: bool,
+    /// The device is disconnected rather than busy with another network.
+    pub device_idle: bool,
 }
 
+#[cfg(any(test, not(feature = "demo")))]
+impl AutoconnectFacts {
+    /// The first reason NM would skip the profile. NM does not expose the
+    /// retry counter, so an idle device with nothing else in the way is
+    /// taken to have run out of retries.
+    pub(crate) fn block(self) -> Option<AutoconnectBlock> {
+        if !self.wireless_enabled {
+            Some(AutoconnectBlock::RadioDisabled)
+        } else if !self.device_managed {
+            Some(AutoconnectBlock::DeviceUnmanaged)
+        } else if !self.profile_autoconnect {
+            Some(AutoconnectBlock::ProfileAutoconnectOff)
+        } else if !self.device_autoconnect {
+            Some(AutoconnectBlock::DeviceAutoconnectOff)
+        } else if self.device_idle {
+            Some(AutoconnectBlock::RetriesExhausted)
+        } else {
+            None
+        }
+    }
+}
+
+/// The IPv4 lease NetworkManager obtained over DHCP.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct DhcpLease {
+    pub address: String,
+    pub gateway: Option<String>,
+    pub lease_seconds: Option<u64>,
+}
+
+impl DhcpLease {
+    /// Reads a lease from the raw DHCP options NetworkManager exposes on
+    /// `Dhcp4Config`; returns `None` until an address has been assigned.
+    #[cfg(any(test, not(feature = "demo")))]
+    pub(crate) fn from_options(
+        options: &HashMap<String, String>,
+    ) -> Option<Self> {
+        let address = options.get("ip_address")?.trim();
+        if address.is_empty() {
+            return None;
+        }
+
+        Some(Self {
+            address: address.to_string(),
+            gateway: options
+                .get("routers")
+                .and_then(|routers| routers.split_whitespace().next())
+                .map(str::to_string),
+            lease_seconds: options
+                .get("dhcp_lease_time")
+                .and_then(|seconds| seconds.trim().parse().ok()),
+        })
+    }
+}
+
+/// Selects saved profiles by name or SSID. `/.../` is a regular expression;
+/// anything else is a case-insensitive glob where `*` and `?` are wildcards.
+#[derive(Debug, Clone)]
+pub enum ProfilePattern {
+    Glob(Vec<char>),
+    Regex(Regex),
+}
+
+impl ProfilePattern {
+    pub fn parse(pattern: &str) -> Result<Self, String> {
+        let pattern = pattern.trim();
+        match pattern
+            .strip_prefix('/')
+            .and_then(|rest| rest.strip_suffix('/'))
+        {
+            Some(expression) => Regex::new(expression)
+                .map(Self::Regex)
+                .map_err(|error| format!("Invalid regex: {error}")),
+            None if pattern.is_empty() => Err("Enter a pattern".to_string()),
+            None => Ok(Self::Glob(pattern.to_lowercase().chars().collect())),
+        }
+    }
+
+    pub fn matches(&self, profile: &SavedProfile) -> bool {
+        [&profile.id, &profile.ssid]
+            .into_iter()
+            .any(|name| self.matches_name(name))
+    }
+
+    fn matches_name(&self, name: &str) -> bool {
+        match self {
+            Self::Glob(pattern) => glob_matches(
+                pattern,
+                &name.to_lowercase().chars().collect::<Vec<_>>(),
+            ),
+            Self::Regex(regex) => regex.is_match(name),
+        }
+    }
+}
+
+fn glob_matches(pattern: &[char], text: &[char]) -> bool {
+    let (mut p, mut t) = (0, 0);
+    // Last `*` seen and the text position it currently absorbs up to.
+    let mut backtrack = None;
+
+    while t < text.len() {
+        match pattern.get(p) {
+            Some('*') => {
+                backtrack = Some((p, t));
+                p += 1;
+            }
+            Some(&c) if c == '?' || c == text[t] => {
+                p += 1;
+                t += 1;
+            }
+            _ => match backtrack {
+                Some((star, absorbed)) => {
+                    p = star + 1;
+                    t = absorbed + 1;
+                    backtrack = Some((star, t));
+                }
+                None => return false,
+            },
+        }
+    }
+
+    pattern[p..].iter().all(|&c| c == '*')
+}
+
+impl<'a> ConnectionRequest<'a> {
+    pub fn network(&self) -> &'a WifiNetwork {
+        match self {
+            Self::Open { network }
+            | Self::Secured { network, .. }
+            | Self::Guest { network } => network,
+        }
+    }
+}
+
+#[cfg(any(test, not(feature = "demo")))]
+const NM_METERED_YES: i32 = 1;
+
 #[cfg(any(test, not(feature = "demo")))]
 fn variant<T: RefArg + 'static>(value: T) -> Variant<Box<dyn RefArg>> {
     Variant(Box::new(value))
@@ -52,6 +1084,19 @@ fn base_connection_settings(ssid: &str) -> HashMap<&'static str, PropMap> {
     settings
 }
 
+/// Replaces the SSID of `settings` with the exact bytes of `network`, which
+/// differ from its name when that is not UTF-8.
+#[cfg(any(test, not(feature = "demo")))]
+pub(crate) fn set_ssid_bytes(
+    settings: &mut HashMap<&'static str, PropMap>,
+    network: &WifiNetwork,
+) {
+    if let Some(wireless) = settings.get_mut("802-11-wireless") {
+        wireless
+            .insert("ssid".to_string(), variant(network.ssid_bytes().to_vec()));
+    }
+}
+
 #[cfg(any(test, not(feature = "demo")))]
 fn open_network_connection_settings(
     ssid: &str,
@@ -59,6 +1104,65 @@ fn open_network_connection_settings(
     base_connection_settings(ssid)
 }
 
+/// The id of the throwaway profile a guest connect to `ssid` creates.
+#[cfg(any(test, not(feature = "demo")))]
+pub(crate) fn guest_profile_id(ssid: &str) -> String {
+    format!("nm-wifi-guest-{ssid}")
+}
+
+#[cfg(any(test, not(feature = "demo")))]
+fn guest_network_connection_settings(
+    ssid: &str,
+) -> HashMap<&'static str, PropMap> {
+    let mut settings = base_connection_settings(ssid);
+
+    if let Some(connection) = settings.get_mut("connection") {
+        connection.insert("id".to_string(), variant(guest_profile_id(ssid)));
+        connection.insert("autoconnect".to_string(), variant(false));
+        connection.insert("metered".to_string(), variant(NM_METERED_YES));
+    }
+
+    if let Some(wireless) = settings.get_mut("802-11-wireless") {
+        wireless.insert(
+            "cloned-mac-address".to_string(),
+            variant("random".to_string()),
+        );
+    }
+
+    settings
+}
+
+/// A one-off profile joining the Wi-Fi Direct group of `peer`. It does not
+/// autoconnect, since the peer is only around while it is being used.
+#[cfg(any(test, not(feature = "demo")))]
+pub(crate) fn p2p_connection_settings(
+    peer: &P2pPeer,
+) -> HashMap<&'static str, PropMap> {
+    let mut connection = PropMap::new();
+    connection.insert("type".to_string(), variant("wifi-p2p".to_string()));
+    connection.insert(
+        "id".to_string(),
+        variant(format!("nm-wifi-p2p-{}", peer.name)),
+    );
+    connection.insert("autoconnect".to_string(), variant(false));
+
+    let mut p2p = PropMap::new();
+    p2p.insert("peer".to_string(), variant(peer.hw_address.clone()));
+
+    let mut ipv4 = PropMap::new();
+    ipv4.insert("method".to_string(), variant("auto".to_string()));
+
+    let mut ipv6 = PropMap::new();
+    ipv6.insert("method".to_string(), variant("auto".to_string()));
+
+    let mut settings = HashMap::new();
+    settings.insert("connection", connection);
+    settings.insert("wifi-p2p", p2p);
+    settings.insert("ipv4", ipv4);
+    settings.insert("ipv6", ipv6);
+    settings
+}
+
 #[cfg(any(test, not(feature = "demo")))]
 fn secured_network_connection_settings(
     ssid: &str,
@@ -83,6 +1187,566 @@ fn secured_network_connection_settings(
     settings
 }
 
+#[cfg(any(test, not(feature = "demo")))]
+fn apply_connection_options(
+    settings: &mut HashMap<&'static str, PropMap>,
+    options: &ConnectionOptions,
+) {
+    let profile_name = options
+        .profile_name
+        .as_deref()
+        .map(str::trim)
+        .filter(|name| !name.is_empty());
+
+    if let Some(profile_name) = profile_name
+        && let Some(connection) = settings.get_mut("connection")
+    {
+        connection.insert("id".to_string(), variant(profile_name.to_string()));
+    }
+
+    if options.proxy != ProxyConfig::None {
+        let mut proxy = PropMap::new();
+        proxy.insert("method".to_string(), variant(PROXY_METHOD_AUTO));
+        match &options.proxy {
+            ProxyConfig::Pac(url) => {
+                proxy.insert("pac-url".to_string(), variant(url.clone()));
+            }
+            ProxyConfig::Manual(address) => {
+                proxy.insert(
+                    "pac-script".to_string(),
+                    variant(manual_proxy_script(address)),
+                );
+            }
+            ProxyConfig::None | ProxyConfig::Wpad => {}
+        }
+        settings.insert("proxy", proxy);
+    }
+
+    if options.pmf != Pmf::Default
+        && let Some(security) = settings.get_mut("802-11-wireless-security")
+    {
+        security.insert("pmf".to_string(), variant(options.pmf.nm_value()));
+    }
+
+    if let Some(owner) = &options.owner
+        && let Some(connection) = settings.get_mut("connection")
+    {
+        connection.insert(
+            "permissions".to_string(),
+            variant(vec![format!("user:{owner}:")]),
+        );
+    }
+
+    if options.hidden
+        && let Some(wireless) = settings.get_mut("802-11-wireless")
+    {
+        wireless.insert("hidden".to_string(), variant(true));
+    }
+
+    if options.agent_owned_secrets
+        && let Some(security) = settings.get_mut("802-11-wireless-security")
+    {
+        security
+            .insert("psk-flags".to_string(), variant(SECRET_FLAG_AGENT_OWNED));
+    }
+}
+
+/// Users named in `connection.permissions`, whose entries look like
+/// `user:<name>:`.
+#[cfg(any(test, not(feature = "demo")))]
+pub(crate) fn profile_users(
+    settings: &HashMap<String, PropMap>,
+) -> Vec<String> {
+    settings
+        .get("connection")
+        .and_then(|connection| connection.get("permissions"))
+        .and_then(|permissions| permissions.0.as_iter())
+        .into_iter()
+        .flatten()
+        .filter_map(|entry| entry.as_str()?.strip_prefix("user:"))
+        .filter_map(|entry| entry.split(':').next())
+        .filter(|name| !name.is_empty())
+        .map(str::to_string)
+        .collect()
+}
+
+/// The access point a saved profile is pinned to.
+#[cfg(any(test, not(feature = "demo")))]
+pub(crate) fn profile_bssid(
+    settings: &HashMap<String, PropMap>,
+) -> Option<String> {
+    let bytes = settings
+        .get("802-11-wireless")?
+        .get("bssid")?
+        .0
+        .as_iter()?
+        .map(|byte| byte.as_u64().map(|byte| byte as u8))
+        .collect::<Option<Vec<u8>>>()?;
+    (bytes.len() == 6).then(|| format_bssid(&bytes))
+}
+
+/// The MTU a saved profile sets; NetworkManager stores 0 for automatic.
+#[cfg(any(test, not(feature = "demo")))]
+pub(crate) fn profile_mtu(settings: &HashMap<String, PropMap>) -> Option<u32> {
+    settings
+        .get("802-11-wireless")?
+        .get("mtu")?
+        .0
+        .as_u64()
+        .and_then(|mtu| u32::try_from(mtu).ok())
+        .filter(|mtu| *mtu > 0)
+}
+
+/// The cloned MAC address of a saved profile, from `assigned-mac-address`
+/// or the older byte form of `cloned-mac-address`.
+#[cfg(any(test, not(feature = "demo")))]
+pub(crate) fn profile_cloned_mac(
+    settings: &HashMap<String, PropMap>,
+) -> Option<String> {
+    let wireless = settings.get("802-11-wireless")?;
+    if let Some(mac) = wireless
+        .get("assigned-mac-address")
+        .and_then(|value| value.0.as_str())
+        .filter(|mac| !mac.is_empty())
+    {
+        return Some(mac.to_string());
+    }
+    let bytes = wireless
+        .get("cloned-mac-address")?
+        .0
+        .as_iter()?
+        .map(|byte| byte.as_u64().map(|byte| byte as u8))
+        .collect::<Option<Vec<u8>>>()?;
+    (bytes.len() == 6).then(|| format_bssid(&bytes))
+}
+
+/// The name and UUID of a saved VPN or WireGuard profile.
+#[cfg(any(test, not(feature = "demo")))]
+pub(crate) fn vpn_profile(
+    settings: &HashMap<String, PropMap>,
+) -> Option<(String, String)> {
+    let connection = settings.get("connection")?;
+    let text = |key: &str| {
+        connection
+            .get(key)
+            .and_then(|value| value.0.as_str())
+            .filter(|text| !text.is_empty())
+            .map(str::to_string)
+    };
+    if !matches!(text("type")?.as_str(), "vpn" | "wireguard") {
+        return None;
+    }
+    Some((text("id")?, text("uuid")?))
+}
+
+/// UUIDs of the profiles NetworkManager brings up together with this one,
+/// from `connection.secondaries`.
+#[cfg(any(test, not(feature = "demo")))]
+pub(crate) fn profile_secondaries(
+    settings: &HashMap<String, PropMap>,
+) -> Vec<String> {
+    settings
+        .get("connection")
+        .and_then(|connection| connection.get("secondaries"))
+        .and_then(|secondaries| secondaries.0.as_iter())
+        .map(|uuids| {
+            uuids
+                .filter_map(|uuid| uuid.as_str().map(str::to_string))
+                .collect()
+        })
+        .unwrap_or_default()
+}
+
+/// A saved Ethernet, mobile broadband or Bluetooth profile and the interface
+/// it is bound to with `connection.interface-name`, if any. Bluetooth
+/// profiles are bound to the address of the paired device instead, which
+/// NetworkManager uses as the interface name of Bluetooth devices.
+#[cfg(any(test, not(feature = "demo")))]
+pub(crate) fn device_profile(
+    settings: &HashMap<String, PropMap>,
+    kind: DeviceKind,
+) -> Option<(DeviceProfile, Option<String>)> {
+    let connection = settings.get("connection")?;
+    let text = |key: &str| {
+        connection
+            .get(key)
+            .and_then(|value| value.0.as_str())
+            .filter(|text| !text.is_empty())
+            .map(str::to_string)
+    };
+    let matches = match kind {
+        DeviceKind::Ethernet => text("type")? == "802-3-ethernet",
+        DeviceKind::Mobile => matches!(text("type")?.as_str(), "gsm" | "cdma"),
+        DeviceKind::Bluetooth => text("type")? == "bluetooth",
+    };
+    if !matches {
+        return None;
+    }
+    let profile = DeviceProfile {
+        id: text("id")?,
+        uuid: text("uuid")?,
+    };
+    let bound = match kind {
+        DeviceKind::Bluetooth => settings
+            .get("bluetooth")
+            .and_then(|bluetooth| bluetooth.get("bdaddr"))
+            .and_then(|bdaddr| {
+                bdaddr
+                    .0
+                    .as_iter()?
+                    .map(|byte| byte.as_u64().map(|byte| byte as u8))
+                    .collect::<Option<Vec<u8>>>()
+            })
+            .filter(|bytes| bytes.len() == 6)
+            .map(|bytes| format_bssid(&bytes)),
+        _ => text("interface-name"),
+    };
+    Some((profile, bound))
+}
+
+/// The `wifi.band` values NetworkManager knows; 6 GHz cannot be locked.
+#[cfg(any(test, not(feature = "demo")))]
+pub(crate) fn nm_band_name(band: WifiBand) -> Option<&'static str> {
+    match band {
+        WifiBand::TwoPointFourGhz => Some("bg"),
+        WifiBand::FiveGhz => Some("a"),
+        WifiBand::SixGhz => None,
+    }
+}
+
+/// The band a saved profile is locked to.
+#[cfg(any(test, not(feature = "demo")))]
+pub(crate) fn profile_band(
+    settings: &HashMap<String, PropMap>,
+) -> Option<WifiBand> {
+    match settings.get("802-11-wireless")?.get("band")?.0.as_str()? {
+        "bg" => Some(WifiBand::TwoPointFourGhz),
+        "a" => Some(WifiBand::FiveGhz),
+        _ => None,
+    }
+}
+
+#[cfg(any(test, not(feature = "demo")))]
+const PROXY_METHOD_AUTO: i32 = 1;
+
+/// `NM_SETTING_SECRET_FLAG_AGENT_OWNED`.
+#[cfg(any(test, not(feature = "demo")))]
+const SECRET_FLAG_AGENT_OWNED: u32 = 1;
+
+/// A PAC script sending every request through `address`.
+#[cfg(any(test, not(feature = "demo")))]
+fn manual_proxy_script(address: &str) -> String {
+    format!(
+        "function FindProxyForURL(url, host) {{ return \"PROXY {address}\"; }}"
+    )
+}
+
+/// Reads the `proxy` group of saved connection settings; a profile without
+/// one uses no proxy.
+#[cfg(any(test, not(feature = "demo")))]
+pub(crate) fn proxy_config(settings: &HashMap<String, PropMap>) -> ProxyConfig {
+    let Some(proxy) = settings.get("proxy") else {
+        return ProxyConfig::None;
+    };
+    let method = proxy.get("method").and_then(|value| value.0.as_i64());
+    if method != Some(i64::from(PROXY_METHOD_AUTO)) {
+        return ProxyConfig::None;
+    }
+
+    let text = |key| {
+        proxy
+            .get(key)
+            .and_then(|value| value.0.as_str())
+            .filter(|text| !text.is_empty())
+    };
+    if let Some(url) = text("pac-url") {
+        return ProxyConfig::Pac(url.to_string());
+    }
+    let address = text("pac-script")
+        .and_then(|script| script.split_once("PROXY "))
+        .and_then(|(_, rest)| rest.split(['"', ';']).next());
+    match address {
+        Some(address) => ProxyConfig::Manual(address.trim().to_string()),
+        None => ProxyConfig::Wpad,
+    }
+}
+
+#[cfg(any(test, not(feature = "demo")))]
+pub(crate) const MAX_CONNECT_ATTEMPTS: usize = 3;
+
+/// The collation locale named by a POSIX locale string such as
+/// `de_DE.UTF-8@euro`; `C` and `POSIX` have no collation rules.
+fn collation_locale(value: &str) -> Option<Locale> {
+    let name = value.split(['.', '@']).next()?.replace('_', "-");
+    match name.as_str() {
+        "" | "C" | "POSIX" => None,
+        name => name.parse().ok(),
+    }
+}
+
+fn ssid_collator() -> Option<Collator> {
+    let locale = ["LC_ALL", "LC_COLLATE", "LANG"]
+        .into_iter()
+        .filter_map(|key| std::env::var(key).ok())
+        .find(|value| !value.is_empty())
+        .and_then(|value| collation_locale(&value))
+        .unwrap_or(Locale::UND);
+    let mut options = CollatorOptions::new();
+    options.strength = Some(Strength::Secondary);
+    Collator::try_new(&(&locale).into(), options).ok()
+}
+
+/// Compares SSIDs with the collation rules of the user's locale, ignoring
+/// case, and falls back to a byte comparison so the order is total.
+pub(crate) fn compare_ssids(a: &str, b: &str) -> Ordering {
+    // The collator's data is not `Sync`, and scans run on worker threads.
+    thread_local! {
+        static COLLATOR: Option<Collator> = ssid_collator();
+    }
+
+    COLLATOR
+        .with(|collator| {
+            collator
+                .as_ref()
+                .map_or(Ordering::Equal, |collator| collator.compare(a, b))
+        })
+        .then_with(|| a.to_lowercase().cmp(&b.to_lowercase()))
+        .then_with(|| a.cmp(b))
+}
+
+#[cfg(any(test, not(feature = "demo")))]
+pub(crate) fn deduplicate_networks(
+    networks: Vec<WifiNetwork>,
+) -> Vec<WifiNetwork> {
+    let mut unique_networks: HashMap<Vec<u8>, WifiNetwork> = HashMap::new();
+    for network in networks {
+        match unique_networks.get_mut(network.ssid_bytes()) {
+            Some(existing) => {
+                let mut access_points =
+                    std::mem::take(&mut existing.access_points);
+                access_points.extend(network.access_points.iter().cloned());
+                if network.frequency > existing.frequency {
+                    *existing = network;
+                }
+                existing.access_points = access_points;
+            }
+            None => {
+                unique_networks.insert(network.ssid_bytes().to_vec(), network);
+            }
+        }
+    }
+
+    let mut deduplicated_networks: Vec<WifiNetwork> = unique_networks
+        .into_values()
+        .map(|mut network| {
+            network.access_points.sort_by(|a, b| {
+                b.frequency
+                    .cmp(&a.frequency)
+                    .then(b.signal_strength.cmp(&a.signal_strength))
+            });
+            network
+        })
+        .collect();
+
+    deduplicated_networks.sort_by(|a, b| {
+        b.connected
+            .cmp(&a.connected)
+            .then(b.signal_strength.cmp(&a.signal_strength))
+            .then_with(|| compare_ssids(&a.ssid, &b.ssid))
+    });
+
+    deduplicated_networks
+}
+
+/// Keeps only the access points on `band`, dropping networks left without
+/// any. The connected network stays listed whatever its band.
+pub(crate) fn restrict_to_band(
+    networks: Vec<WifiNetwork>,
+    band: Option<WifiBand>,
+) -> Vec<WifiNetwork> {
+    let Some(band) = band else {
+        return networks;
+    };
+
+    networks
+        .into_iter()
+        .filter_map(|mut network| {
+            if network.access_points.is_empty() {
+                return (network.connected || network.band() == band)
+                    .then_some(network);
+            }
+            let mut access_points = network.access_points.clone();
+            access_points.retain(|access_point| access_point.band() == band);
+            let strongest = access_points
+                .iter()
+                .max_by_key(|access_point| access_point.signal_strength);
+            match strongest {
+                Some(strongest) => {
+                    network.frequency = strongest.frequency;
+                    network.signal_strength = strongest.signal_strength;
+                    network.access_points = access_points;
+                    Some(network)
+                }
+                None => network.connected.then_some(network),
+            }
+        })
+        .collect()
+}
+
+/// Access points to try, in order, when activating `network`. The preferred
+/// (highest-frequency) AP comes first, followed by the strongest AP on a band
+/// that has not been tried yet, then any remaining BSSIDs.
+#[cfg(any(test, not(feature = "demo")))]
+pub(crate) fn connect_attempt_order(
+    network: &WifiNetwork,
+) -> Vec<&WifiAccessPoint> {
+    let mut remaining: Vec<&WifiAccessPoint> =
+        network.access_points.iter().collect();
+    if remaining.is_empty() {
+        return remaining;
+    }
+
+    let preferred = remaining
+        .iter()
+        .position(|access_point| access_point.frequency == network.frequency)
+        .unwrap_or(0);
+    let mut order = vec![remaining.remove(preferred)];
+    remaining.sort_by_key(|access_point| Reverse(access_point.signal_strength));
+
+    while order.len() < MAX_CONNECT_ATTEMPTS && !remaining.is_empty() {
+        let next = remaining
+            .iter()
+            .position(|candidate| {
+                order.iter().all(|tried| tried.band() != candidate.band())
+            })
+            .unwrap_or(0);
+        order.push(remaining.remove(next));
+    }
+
+    order
+}
+
+/// Whether an activation that failed with `error` on one access point is
+/// retried on the next. A rejected password fails on every access point of
+/// the SSID alike, and a cancelled connect stops where it is; any other
+/// failure reason, stall or timeout may well be down to that one AP.
+#[cfg(any(test, not(feature = "demo")))]
+pub(crate) fn tries_next_access_point(error: &(dyn Error + 'static)) -> bool {
+    if error.is::<WrongPassword>() {
+        return false;
+    }
+    error
+        .downcast_ref::<std::io::Error>()
+        .is_none_or(|error| error.kind() != std::io::ErrorKind::Interrupted)
+}
+
+/// The error for an activation that left the device in the failed state
+/// with `NMDeviceStateReason` `reason`, after a password was sent when
+/// `secured`. `NO_SECRETS` and `SUPPLICANT_DISCONNECT` mean the supplicant
+/// gave up on the handshake, which for a pre-shared key means the password
+/// was wrong; without one they are only a failure.
+#[cfg(any(test, not(feature = "demo")))]
+pub(crate) fn activation_failure(reason: u32, secured: bool) -> Box<dyn Error> {
+    let reason = DeviceStateReason(reason);
+    let kind = match reason.0 {
+        7 | 8 if secured => {
+            return WrongPassword(format!(
+                "The network rejected the password ({})",
+                reason.name().unwrap_or_default()
+            ))
+            .into();
+        }
+        5 | 15 | 16 | 17 => FailureKind::Dhcp,
+        11 | 53 => FailureKind::ApNotFound,
+        _ => FailureKind::Other,
+    };
+    ActivationFailed {
+        kind,
+        message: format!("Activation failed: {reason}"),
+    }
+    .into()
+}
+
+/// How long an activation may sit in one device state before the watchdog
+/// gives up on it; NetworkManager's own timeouts for each step are shorter.
+#[cfg(any(test, not(feature = "demo")))]
+pub(crate) const ACTIVATION_STALL_TIMEOUT: Duration = Duration::from_secs(30);
+
+/// Follows the device states of an activation and notices when it stops
+/// moving, so a stuck connect is cut short with the step it hung in.
+#[cfg(any(test, not(feature = "demo")))]
+#[derive(Debug, Clone, Copy)]
+pub(crate) struct ActivationWatchdog {
+    state: Option<u32>,
+    since: Instant,
+}
+
+#[cfg(any(test, not(feature = "demo")))]
+impl ActivationWatchdog {
+    pub(crate) fn new(now: Instant) -> Self {
+        Self {
+            state: None,
+            since: now,
+        }
+    }
+
+    /// Notes a `StateChanged` to the `NMDeviceState` `state`.
+    pub(crate) fn observe(&mut self, state: u32, now: Instant) {
+        if self.state != Some(state) {
+            self.state = Some(state);
+            self.since = now;
+        }
+    }
+
+    /// The error to abort with once the activation sat in the same state
+    /// for `ACTIVATION_STALL_TIMEOUT`.
+    pub(crate) fn stalled(&self, now: Instant) -> Option<Box<dyn Error>> {
+        if now.saturating_duration_since(self.since) < ACTIVATION_STALL_TIMEOUT
+        {
+            return None;
+        }
+        let (kind, diagnosis) = match self.state {
+            None | Some(40) => (
+                FailureKind::Other,
+                "stuck preparing the adapter — check that it is not blocked \
+                 (rfkill)",
+            ),
+            Some(50) => (
+                FailureKind::ApNotFound,
+                "stuck associating with the access point — move closer or \
+                 try another band",
+            ),
+            Some(60) => (
+                FailureKind::Auth,
+                "stuck waiting for credentials — check the password",
+            ),
+            Some(70) => (
+                FailureKind::Dhcp,
+                "stuck obtaining an IP address — check DHCP",
+            ),
+            Some(80) => (
+                FailureKind::Other,
+                "stuck checking the IP connection — check the gateway",
+            ),
+            Some(90) => (
+                FailureKind::Other,
+                "stuck starting secondary connections — check the VPN",
+            ),
+            Some(_) => return None,
+        };
+        Some(
+            ActivationFailed {
+                kind,
+                message: format!(
+                    "Activation aborted after {}s: {diagnosis}",
+                    ACTIVATION_STALL_TIMEOUT.as_secs()
+                ),
+            }
+            .into(),
+        )
+    }
+}
+
 #[cfg(feature = "demo")]
 pub use demo::demo_networks;
 
@@ -96,6 +1760,16 @@ pub fn get_connected_ssid() -> Result<Option<String>, Box<dyn Error>> {
     networkmanager::get_connected_ssid()
 }
 
+#[cfg(feature = "demo")]
+pub fn get_manager_status() -> Result<ManagerStatus, Box<dyn Error>> {
+    demo::get_manager_status()
+}
+
+#[cfg(not(feature = "demo"))]
+pub fn get_manager_status() -> Result<ManagerStatus, Box<dyn Error>> {
+    networkmanager::get_manager_status()
+}
+
 #[cfg(feature = "demo")]
 pub fn get_wifi_adapter_name() -> Result<Option<String>, Box<dyn Error>> {
     demo::get_wifi_adapter_name()
@@ -146,8 +1820,13 @@ pub fn disconnect_from_network(
 
 #[cfg(test)]
 mod tests {
-    #[cfg(not(feature = "demo"))]
-    use std::time::Duration;
+    use std::{
+        collections::HashMap,
+        error::Error,
+        time::{Duration, Instant},
+    };
+
+    use dbus::arg::{PropMap, Variant};
 
     #[cfg(feature = "demo")]
     use super::ConnectionRequest;
@@ -159,20 +1838,204 @@ mod tests {
         AP_SEC_KEY_MGMT_8021X,
         AP_SEC_KEY_MGMT_PSK,
         AP_SEC_KEY_MGMT_SAE,
+        ScanWait,
         SecurityKind,
         choose_wifi_adapter_name,
         classify_access_point_security,
         classify_security,
-        scan_wait_duration,
+        scan_completed,
         should_disconnect_device,
     };
     use super::{
+        ActivationFailed,
+        ActivationProgress,
+        ActivationStep,
+        ActivationWatchdog,
+        AutoconnectBlock,
+        AutoconnectFacts,
+        ConnectionOptions,
+        Connectivity,
+        DeviceKind,
+        DeviceState,
+        DeviceStateReason,
+        DhcpLease,
+        FailureKind,
+        MAX_CONNECT_ATTEMPTS,
+        ManagerState,
+        P2pPeer,
+        Pmf,
+        PrimaryConnection,
+        ProfilePattern,
+        ProxyConfig,
+        SavedProfile,
+        WrongPassword,
+        activation_failure,
+        apply_connection_options,
+        collation_locale,
+        connect_attempt_order,
+        deduplicate_networks,
+        device_profile,
+        guest_network_connection_settings,
+        nm_band_name,
         open_network_connection_settings,
+        p2p_connection_settings,
+        parse_bssid,
+        parse_cloned_mac,
+        parse_mtu,
+        profile_band,
+        profile_bssid,
+        profile_cloned_mac,
+        profile_mtu,
+        profile_secondaries,
+        profile_users,
+        proxy_config,
+        restrict_to_band,
         secured_network_connection_settings,
+        set_ssid_bytes,
+        tries_next_access_point,
+        validate_pac_url,
+        validate_proxy_address,
+        validate_psk,
+        vpn_profile,
     };
-    #[cfg(not(feature = "demo"))]
-    use crate::wifi::WifiNetwork;
-    use crate::wifi::WifiSecurity;
+    use crate::wifi::{WifiAccessPoint, WifiBand, WifiNetwork, WifiSecurity};
+
+    fn access_point(
+        bssid: &str,
+        frequency: u32,
+        signal_strength: u8,
+    ) -> WifiAccessPoint {
+        WifiAccessPoint {
+            bssid: bssid.to_string(),
+            frequency,
+            signal_strength,
+            associated: false,
+        }
+    }
+
+    fn scanned(ssid: &str, access_point: WifiAccessPoint) -> WifiNetwork {
+        WifiNetwork {
+            ssid: ssid.to_string(),
+            raw_ssid: None,
+            signal_strength: access_point.signal_strength,
+            security: WifiSecurity::WpaPsk,
+            frequency: access_point.frequency,
+            connected: false,
+            saved: false,
+            max_bitrate: 0,
+            wpa_flags: 0,
+            rsn_flags: 0,
+            access_points: vec![access_point],
+        }
+    }
+
+    #[test]
+    fn deduplication_keeps_highest_frequency_and_collects_every_bssid() {
+        let networks = deduplicate_networks(vec![
+            scanned("home", access_point("aa:00", 2437, 90)),
+            scanned("home", access_point("aa:01", 5180, 40)),
+            scanned("cafe", access_point("bb:00", 2412, 60)),
+        ]);
+
+        assert_eq!(networks.len(), 2);
+        let home = networks
+            .iter()
+            .find(|network| network.ssid == "home")
+            .expect("home survives deduplication");
+        assert_eq!(home.frequency, 5180);
+        assert_eq!(
+            home.access_points
+                .iter()
+                .map(|access_point| access_point.bssid.as_str())
+                .collect::<Vec<_>>(),
+            vec!["aa:01", "aa:00"]
+        );
+    }
+
+    #[test]
+    fn band_restriction_drops_other_bands_but_keeps_the_connected_network() {
+        let mut connected = scanned("home", access_point("aa:00", 5180, 70));
+        connected.connected = true;
+        let networks = deduplicate_networks(vec![
+            connected,
+            scanned("cafe", access_point("bb:00", 2412, 60)),
+            scanned("cafe", access_point("bb:01", 5500, 40)),
+            scanned("lobby", access_point("cc:00", 2437, 80)),
+        ]);
+
+        let restricted =
+            restrict_to_band(networks.clone(), Some(WifiBand::FiveGhz));
+        assert_eq!(
+            restricted
+                .iter()
+                .map(|network| network.ssid.as_str())
+                .collect::<Vec<_>>(),
+            vec!["home", "cafe"]
+        );
+        let cafe = &restricted[1];
+        assert_eq!((cafe.frequency, cafe.signal_strength), (5500, 40));
+        assert_eq!(cafe.access_points.len(), 1);
+
+        let two_four =
+            restrict_to_band(networks.clone(), Some(WifiBand::TwoPointFourGhz));
+        assert_eq!(
+            two_four
+                .iter()
+                .map(|network| network.ssid.as_str())
+                .collect::<Vec<_>>(),
+            vec!["home", "lobby", "cafe"]
+        );
+        assert_eq!(restrict_to_band(networks, None).len(), 3);
+    }
+
+    #[test]
+    fn equal_signal_networks_are_ordered_by_ssid_ignoring_case() {
+        let order = |networks: Vec<WifiNetwork>| {
+            deduplicate_networks(networks)
+                .into_iter()
+                .map(|network| network.ssid)
+                .collect::<Vec<_>>()
+        };
+        let networks = vec![
+            scanned("zulu", access_point("aa:00", 2412, 60)),
+            scanned("Bravo", access_point("bb:00", 2412, 60)),
+            scanned("alpha", access_point("cc:00", 2412, 60)),
+            scanned("strong", access_point("dd:00", 2412, 90)),
+        ];
+        let mut reversed = networks.clone();
+        reversed.reverse();
+
+        assert_eq!(order(networks), vec!["strong", "alpha", "Bravo", "zulu"]);
+        assert_eq!(order(reversed), vec!["strong", "alpha", "Bravo", "zulu"]);
+    }
+
+    #[test]
+    fn collation_locales_come_from_posix_locale_names() {
+        assert_eq!(
+            collation_locale("de_DE.UTF-8@euro").map(|l| l.to_string()),
+            Some("de-DE".to_string())
+        );
+        assert_eq!(collation_locale("C.UTF-8"), None);
+        assert_eq!(collation_locale("POSIX"), None);
+    }
+
+    #[test]
+    fn connect_attempts_switch_band_before_retrying_the_same_band() {
+        let mut network = scanned("home", access_point("5g-a", 5180, 40));
+        network.access_points.extend([
+            access_point("5g-b", 5240, 70),
+            access_point("2g-a", 2437, 55),
+            access_point("2g-b", 2462, 30),
+        ]);
+
+        let order: Vec<_> = connect_attempt_order(&network)
+            .into_iter()
+            .map(|access_point| access_point.bssid.as_str())
+            .collect();
+
+        assert_eq!(order.len(), MAX_CONNECT_ATTEMPTS);
+        assert_eq!(order, vec!["5g-a", "2g-a", "5g-b"]);
+    }
 
     #[cfg(not(feature = "demo"))]
     #[test]
@@ -210,10 +2073,16 @@ mod tests {
     fn network(security: WifiSecurity) -> WifiNetwork {
         WifiNetwork {
             ssid: "test".to_string(),
+            raw_ssid: None,
             signal_strength: 60,
             security,
             frequency: 2412,
             connected: false,
+            saved: false,
+            max_bitrate: 0,
+            wpa_flags: 0,
+            rsn_flags: 0,
+            access_points: Vec::new(),
         }
     }
 
@@ -262,6 +2131,32 @@ mod tests {
         );
     }
 
+    #[test]
+    fn manager_state_maps_nm_state_values() {
+        assert_eq!(ManagerState::from_nm(70), ManagerState::ConnectedGlobal);
+        assert_eq!(ManagerState::from_nm(10), ManagerState::Asleep);
+        assert_eq!(ManagerState::from_nm(99), ManagerState::Unknown);
+    }
+
+    #[test]
+    fn connectivity_maps_nm_connectivity_values() {
+        assert_eq!(Connectivity::from_nm(4), Connectivity::Full);
+        assert_eq!(Connectivity::from_nm(2), Connectivity::Portal);
+        assert_eq!(Connectivity::from_nm(3), Connectivity::Limited);
+        assert_eq!(Connectivity::from_nm(0), Connectivity::Unknown);
+    }
+
+    #[test]
+    fn primary_connection_labels_non_wifi_links() {
+        let ethernet = PrimaryConnection {
+            id: "Wired connection 1".to_string(),
+            connection_type: "802-3-ethernet".to_string(),
+        };
+
+        assert!(!ethernet.is_wifi());
+        assert_eq!(ethernet.kind_label(), "ethernet");
+    }
+
     #[test]
     fn open_network_settings_include_wireless_and_ip_defaults() {
         let settings = open_network_connection_settings("cafe");
@@ -294,6 +2189,503 @@ mod tests {
         );
     }
 
+    #[test]
+    fn p2p_settings_name_the_peer_and_do_not_autoconnect() {
+        let settings = p2p_connection_settings(&P2pPeer {
+            name: "Office Printer".to_string(),
+            hw_address: "DA:A1:19:00:00:02".to_string(),
+            manufacturer: None,
+            model: None,
+            signal_strength: 70,
+        });
+
+        let connection = settings.get("connection").expect("connection");
+        assert_eq!(
+            connection.get("type").and_then(|value| value.0.as_str()),
+            Some("wifi-p2p")
+        );
+        assert_eq!(
+            connection
+                .get("autoconnect")
+                .and_then(|value| value.0.as_u64()),
+            Some(0)
+        );
+        assert_eq!(
+            settings
+                .get("wifi-p2p")
+                .and_then(|p2p| p2p.get("peer"))
+                .and_then(|value| value.0.as_str()),
+            Some("DA:A1:19:00:00:02")
+        );
+    }
+
+    #[test]
+    fn guest_network_settings_randomize_mac_and_disable_autoconnect() {
+        let settings = guest_network_connection_settings("airport");
+
+        let connection = settings.get("connection").expect("connection");
+        assert_eq!(
+            connection.get("id").and_then(|value| value.0.as_str()),
+            Some("nm-wifi-guest-airport")
+        );
+        assert_eq!(
+            connection
+                .get("autoconnect")
+                .and_then(|value| value.0.as_u64()),
+            Some(0)
+        );
+        assert_eq!(
+            connection.get("metered").and_then(|value| value.0.as_i64()),
+            Some(1)
+        );
+        assert_eq!(
+            settings
+                .get("802-11-wireless")
+                .and_then(|wireless| wireless.get("cloned-mac-address"))
+                .and_then(|value| value.0.as_str()),
+            Some("random")
+        );
+        assert!(!settings.contains_key("802-11-wireless-security"));
+    }
+
+    #[test]
+    fn custom_profile_names_replace_the_generated_connection_id() {
+        let mut settings = open_network_connection_settings("cafe");
+        apply_connection_options(
+            &mut settings,
+            &ConnectionOptions {
+                profile_name: Some("  Cafe downstairs ".to_string()),
+                ..ConnectionOptions::default()
+            },
+        );
+
+        assert_eq!(
+            settings
+                .get("connection")
+                .and_then(|connection| connection.get("id"))
+                .and_then(|value| value.0.as_str()),
+            Some("Cafe downstairs")
+        );
+
+        let mut blank = open_network_connection_settings("cafe");
+        apply_connection_options(
+            &mut blank,
+            &ConnectionOptions {
+                profile_name: Some("   ".to_string()),
+                ..ConnectionOptions::default()
+            },
+        );
+        assert_eq!(
+            blank
+                .get("connection")
+                .and_then(|connection| connection.get("id"))
+                .and_then(|value| value.0.as_str()),
+            Some("nm-wifi-cafe")
+        );
+    }
+
+    #[test]
+    fn pac_urls_become_an_automatic_proxy_group() {
+        let mut settings =
+            secured_network_connection_settings("corp", "hunter2", "wpa-psk");
+        apply_connection_options(
+            &mut settings,
+            &ConnectionOptions {
+                proxy: ProxyConfig::Pac(
+                    "http://wpad.corp/proxy.pac".to_string(),
+                ),
+                ..ConnectionOptions::default()
+            },
+        );
+        let saved = settings
+            .into_iter()
+            .map(|(group, values)| (group.to_string(), values))
+            .collect::<HashMap<_, _>>();
+
+        assert_eq!(
+            proxy_config(&saved),
+            ProxyConfig::Pac("http://wpad.corp/proxy.pac".to_string())
+        );
+        assert_eq!(proxy_config(&HashMap::new()), ProxyConfig::None);
+        assert!(validate_pac_url("ftp://corp/proxy.pac").is_err());
+        assert!(validate_pac_url("file:///etc/proxy.pac").is_ok());
+    }
+
+    #[test]
+    fn manual_proxies_round_trip_through_a_pac_script() {
+        let mut settings =
+            secured_network_connection_settings("corp", "hunter2", "wpa-psk");
+        apply_connection_options(
+            &mut settings,
+            &ConnectionOptions {
+                proxy: ProxyConfig::Manual("proxy.corp:3128".to_string()),
+                ..ConnectionOptions::default()
+            },
+        );
+        let saved = settings
+            .into_iter()
+            .map(|(group, values)| (group.to_string(), values))
+            .collect::<HashMap<_, _>>();
+
+        assert_eq!(
+            proxy_config(&saved),
+            ProxyConfig::Manual("proxy.corp:3128".to_string())
+        );
+        assert!(validate_proxy_address("proxy.corp:3128").is_ok());
+        assert!(validate_proxy_address("[::1]:8080").is_ok());
+        assert!(validate_proxy_address("proxy.corp").is_err());
+        assert!(validate_proxy_address(":3128").is_err());
+        assert!(validate_proxy_address("proxy.corp:0").is_err());
+    }
+
+    #[test]
+    fn psks_are_passphrases_or_hex_keys() {
+        assert!(validate_psk("hunter22").is_ok());
+        assert!(validate_psk("short").is_err());
+        assert!(validate_psk(&"a".repeat(64)).is_ok());
+        assert!(validate_psk(&"z".repeat(64)).is_err());
+    }
+
+    #[test]
+    fn pmf_is_only_set_on_secured_profiles_when_chosen() {
+        let pmf = |settings: &HashMap<&str, PropMap>| {
+            settings
+                .get("802-11-wireless-security")
+                .and_then(|security| security.get("pmf"))
+                .and_then(|value| value.0.as_i64())
+        };
+        let required = ConnectionOptions {
+            pmf: Pmf::Required,
+            ..ConnectionOptions::default()
+        };
+
+        let mut secured =
+            secured_network_connection_settings("home", "pw", "sae");
+        apply_connection_options(&mut secured, &required);
+        assert_eq!(pmf(&secured), Some(3));
+
+        let mut open = open_network_connection_settings("cafe");
+        apply_connection_options(&mut open, &required);
+        assert!(!open.contains_key("802-11-wireless-security"));
+
+        let mut untouched =
+            secured_network_connection_settings("home", "pw", "wpa-psk");
+        apply_connection_options(&mut untouched, &ConnectionOptions::default());
+        assert_eq!(pmf(&untouched), None);
+
+        assert_eq!(Pmf::Default.cycle(true), Pmf::Required);
+        assert_eq!(Pmf::Required.cycle(false), Pmf::Default);
+    }
+
+    #[test]
+    fn owned_profiles_are_restricted_to_their_user() {
+        let mut settings =
+            secured_network_connection_settings("home", "pw", "sae");
+        apply_connection_options(
+            &mut settings,
+            &ConnectionOptions {
+                owner: Some("alice".to_string()),
+                ..ConnectionOptions::default()
+            },
+        );
+        let saved = settings
+            .into_iter()
+            .map(|(group, values)| (group.to_string(), values))
+            .collect::<HashMap<_, _>>();
+
+        assert_eq!(profile_users(&saved), vec!["alice".to_string()]);
+        assert!(profile_users(&HashMap::new()).is_empty());
+    }
+
+    #[test]
+    fn pinned_bssids_are_read_as_colon_separated_hex() {
+        let mut wireless = PropMap::new();
+        wireless.insert(
+            "bssid".to_string(),
+            Variant(Box::new(vec![0xaau8, 0xbb, 0xcc, 0x00, 0x11, 0x2f])),
+        );
+        let settings =
+            HashMap::from([("802-11-wireless".to_string(), wireless)]);
+
+        assert_eq!(
+            profile_bssid(&settings).as_deref(),
+            Some("AA:BB:CC:00:11:2F")
+        );
+        assert_eq!(
+            parse_bssid(" aa:bb:cc:00:11:2f "),
+            Some([0xaa, 0xbb, 0xcc, 0x00, 0x11, 0x2f])
+        );
+        assert_eq!(parse_bssid("aa:bb:cc:00:11"), None);
+        assert_eq!(parse_bssid("aa:bb:cc:00:11:2f:00"), None);
+        assert_eq!(parse_bssid("aa:bb:cc:00:11:zz"), None);
+        assert_eq!(parse_bssid("aabb:cc:00:11:2f"), None);
+    }
+
+    #[test]
+    fn link_settings_are_read_and_validated() {
+        let mut wireless = PropMap::new();
+        wireless.insert("mtu".to_string(), Variant(Box::new(1400u32)));
+        wireless.insert(
+            "cloned-mac-address".to_string(),
+            Variant(Box::new(vec![0x02u8, 0x00, 0x00, 0xaa, 0xbb, 0xcc])),
+        );
+        let mut settings =
+            HashMap::from([("802-11-wireless".to_string(), wireless)]);
+
+        assert_eq!(profile_mtu(&settings), Some(1400));
+        assert_eq!(
+            profile_cloned_mac(&settings).as_deref(),
+            Some("02:00:00:AA:BB:CC")
+        );
+
+        let wireless = settings.get_mut("802-11-wireless").unwrap();
+        wireless.insert("mtu".to_string(), Variant(Box::new(0u32)));
+        wireless.insert(
+            "assigned-mac-address".to_string(),
+            Variant(Box::new("stable".to_string())),
+        );
+        assert_eq!(profile_mtu(&settings), None);
+        assert_eq!(profile_cloned_mac(&settings).as_deref(), Some("stable"));
+
+        assert_eq!(parse_mtu(" 1280 "), Ok(Some(1280)));
+        assert_eq!(parse_mtu(""), Ok(None));
+        assert!(parse_mtu("100").is_err());
+        assert!(parse_mtu("jumbo").is_err());
+        assert_eq!(
+            parse_cloned_mac("02:00:00:aa:bb:cc"),
+            Ok(Some("02:00:00:AA:BB:CC".to_string()))
+        );
+        assert_eq!(parse_cloned_mac("Random"), Ok(Some("random".to_string())));
+        assert_eq!(parse_cloned_mac(" "), Ok(None));
+        assert!(parse_cloned_mac("02:00:00:aa:bb").is_err());
+    }
+
+    #[test]
+    fn device_profiles_keep_the_interface_they_are_bound_to() {
+        let connection = |kind: &str, interface: Option<&str>| {
+            let mut connection = PropMap::new();
+            connection.insert(
+                "id".to_string(),
+                Variant(Box::new("Dock".to_string())),
+            );
+            connection.insert(
+                "uuid".to_string(),
+                Variant(Box::new("uuid-dock".to_string())),
+            );
+            connection.insert(
+                "type".to_string(),
+                Variant(Box::new(kind.to_string())),
+            );
+            if let Some(interface) = interface {
+                connection.insert(
+                    "interface-name".to_string(),
+                    Variant(Box::new(interface.to_string())),
+                );
+            }
+            HashMap::from([("connection".to_string(), connection)])
+        };
+
+        let (profile, interface) = device_profile(
+            &connection("802-3-ethernet", Some("enx0")),
+            DeviceKind::Ethernet,
+        )
+        .expect("Ethernet profile");
+        assert_eq!(profile.id, "Dock");
+        assert_eq!(profile.uuid, "uuid-dock");
+        assert_eq!(interface.as_deref(), Some("enx0"));
+        assert_eq!(
+            device_profile(
+                &connection("802-3-ethernet", None),
+                DeviceKind::Ethernet
+            )
+            .map(|(_, interface)| interface),
+            Some(None)
+        );
+        assert!(
+            device_profile(
+                &connection("802-11-wireless", None),
+                DeviceKind::Ethernet
+            )
+            .is_none()
+        );
+        assert!(
+            device_profile(&connection("gsm", None), DeviceKind::Mobile)
+                .is_some()
+        );
+        assert!(
+            device_profile(&connection("gsm", None), DeviceKind::Ethernet)
+                .is_none()
+        );
+
+        let mut phone = connection("bluetooth", None);
+        let mut bluetooth = PropMap::new();
+        bluetooth.insert(
+            "bdaddr".to_string(),
+            Variant(Box::new(vec![0xa4u8, 0x50, 0x46, 0x01, 0x02, 0x03])),
+        );
+        phone.insert("bluetooth".to_string(), bluetooth);
+        assert_eq!(
+            device_profile(&phone, DeviceKind::Bluetooth)
+                .map(|(_, interface)| interface),
+            Some(Some("A4:50:46:01:02:03".to_string()))
+        );
+
+        assert_eq!(DeviceState::from_nm(100), DeviceState::Connected);
+        assert_eq!(DeviceState::from_nm(70), DeviceState::Connecting);
+        assert_eq!(DeviceState::from_nm(20), DeviceState::Unavailable);
+    }
+
+    #[test]
+    fn vpn_profiles_are_found_among_the_secondaries() {
+        let mut vpn = PropMap::new();
+        vpn.insert("id".to_string(), Variant(Box::new("Work".to_string())));
+        vpn.insert(
+            "uuid".to_string(),
+            Variant(Box::new("uuid-work".to_string())),
+        );
+        vpn.insert(
+            "type".to_string(),
+            Variant(Box::new("wireguard".to_string())),
+        );
+        let vpn = HashMap::from([("connection".to_string(), vpn)]);
+        assert_eq!(
+            vpn_profile(&vpn),
+            Some(("Work".to_string(), "uuid-work".to_string()))
+        );
+        assert_eq!(profile_secondaries(&vpn), Vec::<String>::new());
+
+        let mut wifi = PropMap::new();
+        wifi.insert(
+            "type".to_string(),
+            Variant(Box::new("802-11-wireless".to_string())),
+        );
+        wifi.insert(
+            "secondaries".to_string(),
+            Variant(Box::new(vec!["uuid-work".to_string()])),
+        );
+        let wifi = HashMap::from([("connection".to_string(), wifi)]);
+        assert_eq!(vpn_profile(&wifi), None);
+        assert_eq!(profile_secondaries(&wifi), vec!["uuid-work".to_string()]);
+    }
+
+    #[test]
+    fn band_locks_round_trip_through_wifi_band() {
+        for band in [WifiBand::TwoPointFourGhz, WifiBand::FiveGhz] {
+            let mut wireless = PropMap::new();
+            wireless.insert(
+                "band".to_string(),
+                Variant(Box::new(
+                    nm_band_name(band).expect("lockable").to_string(),
+                )),
+            );
+            let settings =
+                HashMap::from([("802-11-wireless".to_string(), wireless)]);
+
+            assert_eq!(profile_band(&settings), Some(band));
+        }
+        assert_eq!(nm_band_name(WifiBand::SixGhz), None);
+        assert_eq!(profile_band(&HashMap::new()), None);
+    }
+
+    #[test]
+    fn profiles_use_the_exact_bytes_of_non_utf8_ssids() {
+        let mut latin1 = scanned("Caf\\xE9", access_point("aa:00", 2437, 70));
+        latin1.raw_ssid = Some(b"Caf\xe9".to_vec());
+        let ssid = |settings: &HashMap<&str, PropMap>| {
+            settings
+                .get("802-11-wireless")
+                .and_then(|wireless| wireless.get("ssid"))
+                .and_then(|value| value.0.as_iter())
+                .map(|bytes| {
+                    bytes
+                        .filter_map(|byte| byte.as_u64().map(|byte| byte as u8))
+                        .collect::<Vec<_>>()
+                })
+        };
+
+        let mut settings = open_network_connection_settings(&latin1.ssid);
+        set_ssid_bytes(&mut settings, &latin1);
+        assert_eq!(ssid(&settings), Some(b"Caf\xe9".to_vec()));
+
+        let plain = scanned("Café", access_point("aa:01", 2437, 70));
+        set_ssid_bytes(&mut settings, &plain);
+        assert_eq!(ssid(&settings), Some("Café".as_bytes().to_vec()));
+    }
+
+    #[test]
+    fn hidden_networks_are_probed_by_name() {
+        let mut settings = open_network_connection_settings("attic");
+        apply_connection_options(
+            &mut settings,
+            &ConnectionOptions {
+                hidden: true,
+                ..ConnectionOptions::default()
+            },
+        );
+
+        assert_eq!(
+            settings
+                .get("802-11-wireless")
+                .and_then(|wireless| wireless.get("hidden"))
+                .and_then(|value| value.0.as_u64()),
+            Some(1)
+        );
+    }
+
+    #[test]
+    fn keyring_passwords_are_agent_owned() {
+        let options = ConnectionOptions {
+            agent_owned_secrets: true,
+            ..ConnectionOptions::default()
+        };
+        let psk_flags = |settings: &HashMap<&str, PropMap>| {
+            settings
+                .get("802-11-wireless-security")
+                .and_then(|security| security.get("psk-flags"))
+                .and_then(|value| value.0.as_u64())
+        };
+
+        let mut secured =
+            secured_network_connection_settings("home", "pw", "wpa-psk");
+        apply_connection_options(&mut secured, &options);
+        assert_eq!(psk_flags(&secured), Some(1));
+
+        let mut system =
+            secured_network_connection_settings("home", "pw", "wpa-psk");
+        apply_connection_options(&mut system, &ConnectionOptions::default());
+        assert_eq!(psk_flags(&system), None);
+
+        let mut open = open_network_connection_settings("cafe");
+        apply_connection_options(&mut open, &options);
+        assert!(!open.contains_key("802-11-wireless-security"));
+    }
+
+    #[test]
+    fn hostile_ssids_pass_through_settings_untouched() {
+        for ssid in ["ACTIVE:SSID", "say \"hi\" 'there'", "caf\\e\t😊", "-- -x"]
+        {
+            let settings =
+                secured_network_connection_settings(ssid, "p:a\"ss", "wpa-psk");
+            let wireless = &settings["802-11-wireless"];
+            let bytes = wireless["ssid"]
+                .0
+                .as_iter()
+                .expect("ssid is a byte array")
+                .filter_map(|byte| byte.as_u64().map(|byte| byte as u8))
+                .collect::<Vec<_>>();
+
+            assert_eq!(bytes, ssid.as_bytes());
+            assert_eq!(
+                settings["connection"]["id"].0.as_str(),
+                Some(format!("nm-wifi-{ssid}").as_str())
+            );
+            assert_eq!(
+                settings["802-11-wireless-security"]["psk"].0.as_str(),
+                Some("p:a\"ss")
+            );
+        }
+    }
+
     #[test]
     fn sae_network_settings_use_sae_key_management() {
         let settings =
@@ -349,15 +2741,37 @@ mod tests {
 
     #[cfg(not(feature = "demo"))]
     #[test]
-    fn recent_scans_do_not_force_an_extra_wait() {
-        assert_eq!(scan_wait_duration(5_000), Duration::from_millis(0));
+    fn scans_complete_only_once_last_scan_moves_past_the_request() {
+        assert!(!scan_completed(5_000, 5_000));
+        assert!(!scan_completed(5_000, 4_000));
+        assert!(scan_completed(5_000, 5_120));
+    }
+
+    #[cfg(not(feature = "demo"))]
+    #[test]
+    fn first_scan_completes_after_the_never_scanned_sentinel() {
+        assert!(!scan_completed(-1, -1));
+        assert!(scan_completed(-1, 0));
     }
 
     #[cfg(not(feature = "demo"))]
     #[test]
-    fn stale_scans_wait_longer_than_the_old_fixed_delay() {
-        assert_eq!(scan_wait_duration(20_000), Duration::from_millis(750));
-        assert_eq!(scan_wait_duration(-1), Duration::from_millis(750));
+    fn scans_without_last_scan_finish_once_access_points_settle() {
+        let start = std::time::Instant::now();
+        let at = |millis| start + std::time::Duration::from_millis(millis);
+
+        let mut tracked = ScanWait::new(Some(5_000), start);
+        assert!(!tracked.is_done(Some(5_000), || 3, at(100)));
+        assert!(tracked.is_done(Some(5_200), || 3, at(200)));
+
+        let mut untracked = ScanWait::new(None, start);
+        assert!(!untracked.is_done(None, || 3, at(100)));
+        assert!(!untracked.is_done(None, || 5, at(1_000)));
+        assert!(!untracked.is_done(None, || 5, at(2_000)));
+        assert!(untracked.is_done(None, || 5, at(2_600)));
+
+        let mut stalled = ScanWait::new(Some(5_000), start);
+        assert!(stalled.is_done(Some(5_000), || 0, at(10_000)));
     }
 
     #[cfg(feature = "demo")]
@@ -388,6 +2802,239 @@ mod tests {
         assert!(result.is_ok());
     }
 
+    fn saved_profile(id: &str, ssid: &str) -> SavedProfile {
+        SavedProfile {
+            id: id.to_string(),
+            uuid: format!("uuid-{id}"),
+            ssid: ssid.to_string(),
+            users: Vec::new(),
+            last_used: None,
+            band: None,
+            bssid: None,
+            mtu: None,
+            cloned_mac: None,
+            vpn: None,
+        }
+    }
+
+    #[test]
+    fn profile_globs_match_names_or_ssids_case_insensitively() {
+        let pattern = ProfilePattern::parse("*_guest").expect("valid glob");
+
+        assert!(pattern.matches(&saved_profile("Hotel_Guest", "Hotel_Guest")));
+        assert!(pattern.matches(&saved_profile("Lobby", "Airport_GUEST")));
+        assert!(!pattern.matches(&saved_profile("Guest House", "Guest House")));
+
+        let pattern = ProfilePattern::parse("h?tel*").expect("valid glob");
+        assert!(pattern.matches(&saved_profile("Hotel Ibis", "Ibis")));
+        assert!(!pattern.matches(&saved_profile("Motel", "Motel")));
+    }
+
+    #[test]
+    fn autoconnect_block_reports_the_first_reason() {
+        let facts = AutoconnectFacts {
+            wireless_enabled: true,
+            device_managed: true,
+            device_autoconnect: false,
+            profile_autoconnect: true,
+            device_idle: true,
+        };
+
+        assert_eq!(facts.block(), Some(AutoconnectBlock::DeviceAutoconnectOff));
+        assert_eq!(
+            AutoconnectFacts {
+                device_autoconnect: true,
+                ..facts
+            }
+            .block(),
+            Some(AutoconnectBlock::RetriesExhausted)
+        );
+        assert_eq!(
+            AutoconnectFacts {
+                device_autoconnect: true,
+                device_idle: false,
+                ..facts
+            }
+            .block(),
+            None,
+            "a device busy with another network is not blocked"
+        );
+        assert_eq!(
+            AutoconnectFacts {
+                device_managed: false,
+                ..facts
+            }
+            .block(),
+            Some(AutoconnectBlock::DeviceUnmanaged)
+        );
+        assert!(!AutoconnectBlock::ProfileAutoconnectOff.clearable());
+    }
+
+    #[test]
+    fn dhcp_lease_reads_address_gateway_and_lease_time() {
+        let options = |pairs: &[(&str, &str)]| {
+            pairs
+                .iter()
+                .map(|(key, value)| (key.to_string(), value.to_string()))
+                .collect::<HashMap<_, _>>()
+        };
+
+        assert_eq!(
+            DhcpLease::from_options(&options(&[
+                ("ip_address", "10.20.0.14"),
+                ("routers", "10.20.0.1 10.20.0.2"),
+                ("dhcp_lease_time", "3600"),
+            ])),
+            Some(DhcpLease {
+                address: "10.20.0.14".to_string(),
+                gateway: Some("10.20.0.1".to_string()),
+                lease_seconds: Some(3600),
+            })
+        );
+        assert_eq!(
+            DhcpLease::from_options(&options(&[("routers", "10.20.0.1")])),
+            None
+        );
+    }
+
+    #[test]
+    fn profile_patterns_in_slashes_are_regexes() {
+        let pattern =
+            ProfilePattern::parse("/^(Hilton|Marriott)/").expect("valid regex");
+
+        assert!(pattern.matches(&saved_profile("Marriott Lobby", "MLobby")));
+        assert!(!pattern.matches(&saved_profile("hilton", "hilton")));
+        assert!(ProfilePattern::parse("/(unclosed/").is_err());
+        assert!(ProfilePattern::parse("  ").is_err());
+    }
+
+    #[test]
+    fn handshake_failures_read_as_wrong_passwords() {
+        assert!(activation_failure(7, true).is::<WrongPassword>());
+        assert!(activation_failure(8, true).is::<WrongPassword>());
+        assert_eq!(
+            activation_failure(8, true).to_string(),
+            "The network rejected the password (SUPPLICANT_DISCONNECT)"
+        );
+        assert_eq!(
+            activation_failure(5, true).to_string(),
+            "Activation failed: no IP configuration could be obtained \
+             (IP_CONFIG_UNAVAILABLE)"
+        );
+        assert!(!activation_failure(53, true).is::<WrongPassword>());
+    }
+
+    #[test]
+    fn handshake_failures_of_open_networks_are_not_about_passwords() {
+        let error = activation_failure(8, false);
+        assert!(!error.is::<WrongPassword>());
+        assert!(matches!(
+            error.downcast_ref::<ActivationFailed>(),
+            Some(ActivationFailed {
+                kind: FailureKind::Other,
+                ..
+            })
+        ));
+        assert_eq!(
+            error.to_string(),
+            "Activation failed: the access point ended the Wi-Fi handshake \
+             (SUPPLICANT_DISCONNECT)"
+        );
+    }
+
+    #[test]
+    fn failed_activations_fall_back_unless_the_password_was_wrong() {
+        assert!(tries_next_access_point(&*activation_failure(53, true)));
+        assert!(tries_next_access_point(&*activation_failure(17, true)));
+        assert!(tries_next_access_point(&*activation_failure(36, true)));
+        assert!(!tries_next_access_point(&*activation_failure(7, true)));
+
+        let stalled = std::io::Error::new(
+            std::io::ErrorKind::TimedOut,
+            "Timed out waiting for the connection to activate",
+        );
+        assert!(tries_next_access_point(&stalled));
+        let cancelled = std::io::Error::new(
+            std::io::ErrorKind::Interrupted,
+            "Connection cancelled",
+        );
+        assert!(!tries_next_access_point(&cancelled));
+    }
+
+    #[test]
+    fn device_state_reasons_read_as_explanations() {
+        assert_eq!(
+            DeviceStateReason(17).to_string(),
+            "no DHCP server handed out an address (DHCP_FAILED)"
+        );
+        assert_eq!(
+            DeviceStateReason(999).to_string(),
+            "device state reason 999"
+        );
+        assert!(DeviceStateReason(8).is_unexpected());
+        assert!(!DeviceStateReason(39).is_unexpected());
+        assert!(!DeviceStateReason(0).is_unexpected());
+    }
+
+    #[test]
+    fn the_watchdog_names_the_state_an_activation_stalled_in() {
+        let start = Instant::now();
+        let mut watchdog = ActivationWatchdog::new(start);
+        watchdog.observe(50, start + Duration::from_secs(2));
+        watchdog.observe(70, start + Duration::from_secs(5));
+        watchdog.observe(70, start + Duration::from_secs(20));
+
+        assert!(watchdog.stalled(start + Duration::from_secs(34)).is_none());
+        let stalled = watchdog
+            .stalled(start + Duration::from_secs(35))
+            .expect("stuck in IP_CONFIG");
+        assert_eq!(
+            stalled.to_string(),
+            "Activation aborted after 30s: stuck obtaining an IP address — \
+             check DHCP"
+        );
+        assert_eq!(FailureKind::of(&*stalled), FailureKind::Dhcp);
+
+        watchdog.observe(100, start + Duration::from_secs(36));
+        assert!(watchdog.stalled(start + Duration::from_secs(90)).is_none());
+    }
+
+    #[test]
+    fn activation_progress_follows_device_states() {
+        let progress = ActivationProgress::default();
+        assert_eq!(progress.current(), None);
+
+        for state in [30, 40, 50, 80] {
+            if let Some(step) = ActivationStep::from_device_state(state) {
+                progress.reach(step);
+            }
+        }
+        assert_eq!(progress.current(), Some(ActivationStep::IpConfig));
+        assert!(progress.reached(ActivationStep::Config));
+        assert!(!progress.reached(ActivationStep::NeedAuth));
+
+        progress.reset();
+        assert_eq!(progress.current(), None);
+    }
+
+    #[test]
+    fn only_dhcp_and_missing_access_points_are_retried() {
+        let kind = |reason| FailureKind::of(&*activation_failure(reason, true));
+
+        assert_eq!(kind(7), FailureKind::Auth);
+        assert_eq!(kind(5), FailureKind::Dhcp);
+        assert_eq!(kind(17), FailureKind::Dhcp);
+        assert_eq!(kind(11), FailureKind::ApNotFound);
+        assert_eq!(kind(53), FailureKind::ApNotFound);
+        assert_eq!(kind(9), FailureKind::Other);
+        assert!(kind(5).is_transient() && kind(53).is_transient());
+        assert!(!kind(7).is_transient() && !kind(9).is_transient());
+        assert_eq!(
+            FailureKind::of(&*Box::<dyn Error>::from("D-Bus went away")),
+            FailureKind::Other
+        );
+    }
+
     #[cfg(feature = "demo")]
     #[test]
     fn demo_connect_rejects_invalid_passwords() {
@@ -401,9 +3048,8 @@ mod tests {
             passphrase: "wrong-password",
         });
 
-        assert_eq!(
-            result.expect_err("demo connect should fail").to_string(),
-            "Demo mode: invalid password"
-        );
+        let error = result.expect_err("demo connect should fail");
+        assert!(error.is::<WrongPassword>());
+        assert_eq!(error.to_string(), "Demo mode: invalid password");
     }
 }
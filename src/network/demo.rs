@@ -1,39 +1,120 @@
-use std::error::Error;
+use std::{
+    error::Error,
+    path::Path,
+    sync::{
+        OnceLock,
+        atomic::{AtomicBool, Ordering},
+    },
+    time::{Duration, Instant, SystemTime, UNIX_EPOCH},
+};
 
 use crate::{
-    network::ConnectionRequest,
-    wifi::{WifiNetwork, WifiSecurity},
+    latency::{LatencySample, Probe},
+    network::{
+        ActivationProgress,
+        AutoconnectBlock,
+        CancelToken,
+        ConnectionOptions,
+        ConnectionRequest,
+        Connectivity,
+        ConnectivityReport,
+        DeviceKind,
+        DeviceLink,
+        DeviceProfile,
+        DeviceState,
+        DhcpLease,
+        ForgetReport,
+        LinkSetting,
+        ManagerState,
+        ManagerStatus,
+        NetworkDevice,
+        P2pPeer,
+        PrimaryConnection,
+        ProfileSettings,
+        ProxyConfig,
+        SavedProfile,
+        WrongPassword,
+    },
+    speedtest::{ChunkTransfer, TransferDirection},
+    traffic::TrafficCounters,
+    wifi::{WifiAccessPoint, WifiBand, WifiNetwork, WifiSecurity},
 };
 
+fn access_point(
+    bssid: &str,
+    frequency: u32,
+    signal_strength: u8,
+) -> WifiAccessPoint {
+    WifiAccessPoint {
+        bssid: bssid.to_string(),
+        frequency,
+        signal_strength,
+        associated: false,
+    }
+}
+
 pub fn demo_networks() -> Vec<WifiNetwork> {
     vec![
         WifiNetwork {
             ssid: "CatCat".to_string(),
+            raw_ssid: None,
             signal_strength: 69,
             security: WifiSecurity::WpaSae,
             frequency: 5220,
             connected: true,
+            saved: true,
+            max_bitrate: 2_402_000,
+            wpa_flags: 0,
+            rsn_flags: 0x488,
+            access_points: vec![
+                WifiAccessPoint {
+                    associated: true,
+                    ..access_point("A4:2B:B0:11:52:20", 5220, 69)
+                },
+                access_point("A4:2B:B0:11:24:37", 2437, 81),
+            ],
         },
         WifiNetwork {
             ssid: "VIVOFIBRA-5210-5G".to_string(),
+            raw_ssid: None,
             signal_strength: 72,
             security: WifiSecurity::WpaPsk,
             frequency: 5200,
             connected: false,
+            saved: true,
+            max_bitrate: 866_700,
+            wpa_flags: 0x144,
+            rsn_flags: 0x148,
+            access_points: vec![access_point("C8:5A:9F:52:10:05", 5200, 72)],
         },
         WifiNetwork {
             ssid: "Coffee Corner".to_string(),
+            raw_ssid: None,
             signal_strength: 54,
             security: WifiSecurity::Open,
             frequency: 2412,
             connected: false,
+            saved: false,
+            max_bitrate: 144_400,
+            wpa_flags: 0,
+            rsn_flags: 0,
+            access_points: vec![access_point("00:1E:58:C0:FF:EE", 2412, 54)],
         },
         WifiNetwork {
             ssid: "Office Secure".to_string(),
+            raw_ssid: None,
             signal_strength: 63,
             security: WifiSecurity::Enterprise,
             frequency: 5745,
             connected: false,
+            saved: false,
+            max_bitrate: 1_733_300,
+            wpa_flags: 0,
+            rsn_flags: 0x288,
+            access_points: vec![
+                access_point("F0:9F:C2:0F:57:45", 5745, 63),
+                access_point("F0:9F:C2:0F:51:80", 5180, 48),
+            ],
         },
     ]
 }
@@ -41,6 +122,15 @@ pub fn demo_networks() -> Vec<WifiNetwork> {
 fn demo_connect(request: ConnectionRequest<'_>) -> Result<(), Box<dyn Error>> {
     let (network, password) = match request {
         ConnectionRequest::Open { network } => (network, None),
+        ConnectionRequest::Guest { network } => {
+            if network.security != WifiSecurity::Open {
+                return Err(
+                    "Demo mode: guest connections require an open network"
+                        .into(),
+                );
+            }
+            (network, None)
+        }
         ConnectionRequest::Secured {
             network,
             passphrase,
@@ -55,7 +145,9 @@ fn demo_connect(request: ConnectionRequest<'_>) -> Result<(), Box<dyn Error>> {
             Err("Demo mode: enterprise networks are not supported".into())
         }
         (_, WifiSecurity::Open, _) => Ok(()),
-        (_, _, Some(_)) => Err("Demo mode: invalid password".into()),
+        (_, _, Some(_)) => {
+            Err(WrongPassword("Demo mode: invalid password".to_string()).into())
+        }
         _ => Err("Demo mode: password required for secured network".into()),
     }
 }
@@ -67,12 +159,379 @@ pub fn get_connected_ssid() -> Result<Option<String>, Box<dyn Error>> {
         .map(|network| network.ssid))
 }
 
+pub fn get_manager_status() -> Result<ManagerStatus, Box<dyn Error>> {
+    Ok(ManagerStatus {
+        state: ManagerState::ConnectedGlobal,
+        primary_connection: get_connected_ssid()?.map(|ssid| {
+            PrimaryConnection {
+                id: ssid,
+                connection_type: "802-11-wireless".to_string(),
+            }
+        }),
+        wifi_state_reason: None,
+    })
+}
+
+pub fn check_connectivity() -> Result<ConnectivityReport, Box<dyn Error>> {
+    Ok(ConnectivityReport {
+        connectivity: Connectivity::Full,
+        portal_url: None,
+    })
+}
+
+pub fn get_saved_password(
+    network: &WifiNetwork,
+) -> Result<Option<String>, Box<dyn Error>> {
+    Ok(match network.ssid.as_str() {
+        "CatCat" => Some("AcerolaAcai".to_string()),
+        "VIVOFIBRA-5210-5G" => Some("hunter2".to_string()),
+        _ => None,
+    })
+}
+
+pub fn list_saved_profiles() -> Result<Vec<SavedProfile>, Box<dyn Error>> {
+    const DAY: u64 = 24 * 60 * 60;
+    let now = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|elapsed| elapsed.as_secs())
+        .unwrap_or_default();
+
+    Ok([
+        ("CatCat", "CatCat", Some("demo"), Some(0)),
+        ("VIVOFIBRA-5210-5G", "VIVOFIBRA-5210-5G", None, Some(2)),
+        ("Airport_Guest", "Airport_Guest", None, Some(120)),
+        ("Hotel_Guest", "Hotel_Guest", None, None),
+        ("Hilton Honors", "HHonors", Some("demo"), Some(400)),
+        ("Marriott Bonvoy", "Marriott_Guest", None, Some(45)),
+    ]
+    .into_iter()
+    .enumerate()
+    .map(|(index, (id, ssid, owner, days_ago))| SavedProfile {
+        id: id.to_string(),
+        uuid: format!("00000000-0000-4000-8000-{index:012}"),
+        ssid: ssid.to_string(),
+        users: owner.into_iter().map(str::to_string).collect(),
+        last_used: days_ago.map(|days: u64| now.saturating_sub(days * DAY)),
+        band: ssid.ends_with("-5G").then_some(WifiBand::FiveGhz),
+        bssid: None,
+        mtu: None,
+        cloned_mac: ssid.ends_with("_Guest").then(|| "random".to_string()),
+        vpn: ssid.starts_with("Hilton").then(|| "Work VPN".to_string()),
+    })
+    .collect())
+}
+
+pub fn forget_profiles(
+    profiles: &[SavedProfile],
+) -> Result<ForgetReport, Box<dyn Error>> {
+    Ok(ForgetReport {
+        forgotten: profiles.len(),
+        requested: profiles.len(),
+        error: None,
+    })
+}
+
+pub fn set_profile_owner(
+    _profile: &SavedProfile,
+    _owner: Option<&str>,
+) -> Result<(), Box<dyn Error>> {
+    Ok(())
+}
+
+pub fn rename_profile(
+    _profile: &SavedProfile,
+    _name: &str,
+) -> Result<(), Box<dyn Error>> {
+    Ok(())
+}
+
+pub fn set_profile_band(
+    _profile: &SavedProfile,
+    _band: Option<WifiBand>,
+) -> Result<(), Box<dyn Error>> {
+    Ok(())
+}
+
+pub fn set_profile_bssid(
+    _profile: &SavedProfile,
+    _bssid: Option<&str>,
+) -> Result<(), Box<dyn Error>> {
+    Ok(())
+}
+
+pub fn set_profile_link(
+    _profile: &SavedProfile,
+    _setting: &LinkSetting,
+) -> Result<(), Box<dyn Error>> {
+    Ok(())
+}
+
+pub fn set_profile_vpn(
+    _profile: &SavedProfile,
+    _vpn: Option<&str>,
+) -> Result<(), Box<dyn Error>> {
+    Ok(())
+}
+
+pub fn pin_network_bssid(
+    _network: &WifiNetwork,
+    _bssid: &str,
+) -> Result<(), Box<dyn Error>> {
+    Ok(())
+}
+
+pub fn set_profile_password(
+    _profile: &SavedProfile,
+    _password: &str,
+) -> Result<(), Box<dyn Error>> {
+    Ok(())
+}
+
+pub fn clone_profile(
+    profile: &SavedProfile,
+    name: &str,
+) -> Result<SavedProfile, Box<dyn Error>> {
+    Ok(SavedProfile {
+        id: name.to_string(),
+        uuid: format!("{}-copy", profile.uuid),
+        last_used: None,
+        ..profile.clone()
+    })
+}
+
+pub fn export_profiles(
+    profiles: &[SavedProfile],
+    _directory: &Path,
+    _include_secrets: bool,
+) -> Result<usize, Box<dyn Error>> {
+    Ok(profiles.len())
+}
+
+static LIVELY: AtomicBool = AtomicBool::new(false);
+
+/// Turns on `--demo`: signals drift between scans and replies come with
+/// [`DemoFaults::lively`](super::demo_faults::DemoFaults::lively) unless
+/// `NM_WIFI_DEMO_FAULTS` is set.
+pub fn set_lively(lively: bool) {
+    LIVELY.store(lively, Ordering::Relaxed);
+}
+
+pub fn is_lively() -> bool {
+    LIVELY.load(Ordering::Relaxed)
+}
+
+/// The demo networks as a scan sees them: with `--demo` every signal drifts
+/// a few points each time, as it would on a real adapter.
+pub fn scanned_networks() -> Vec<WifiNetwork> {
+    let mut networks = demo_networks();
+    if is_lively() {
+        for network in &mut networks {
+            network.signal_strength = drift(network.signal_strength);
+            for access_point in &mut network.access_points {
+                access_point.signal_strength =
+                    drift(access_point.signal_strength);
+            }
+        }
+    }
+    networks
+}
+
+fn drift(signal_strength: u8) -> u8 {
+    let offset = (super::demo_faults::next_random() % 13) as i16 - 6;
+    (i16::from(signal_strength) + offset).clamp(1, 100) as u8
+}
+
+pub fn get_access_point_signals() -> Result<Vec<WifiAccessPoint>, Box<dyn Error>>
+{
+    Ok(scanned_networks()
+        .into_iter()
+        .flat_map(|network| network.access_points)
+        .collect())
+}
+
+pub fn get_link_bitrate() -> Result<Option<u32>, Box<dyn Error>> {
+    Ok(Some(866_700))
+}
+
+static AUTOCONNECT_RETRIES_RESET: AtomicBool = AtomicBool::new(false);
+
+/// Saved networks out of reach of autoconnect read as having run out of
+/// retries until the block is cleared.
+pub fn autoconnect_block(
+    network: &WifiNetwork,
+) -> Result<Option<AutoconnectBlock>, Box<dyn Error>> {
+    let blocked = network.saved
+        && !network.connected
+        && !AUTOCONNECT_RETRIES_RESET.load(Ordering::Relaxed);
+    Ok(blocked.then_some(AutoconnectBlock::RetriesExhausted))
+}
+
+pub fn clear_autoconnect_block(
+    _network: &WifiNetwork,
+) -> Result<(), Box<dyn Error>> {
+    AUTOCONNECT_RETRIES_RESET.store(true, Ordering::Relaxed);
+    Ok(())
+}
+
+pub fn measure_latency(_host: &str) -> Result<LatencySample, Box<dyn Error>> {
+    Ok(LatencySample {
+        gateway: Some(Probe::Reply(Duration::from_micros(2_800))),
+        host: Probe::Reply(Duration::from_micros(23_600)),
+    })
+}
+
+/// A steady 80 Mbps down and 20 Mbps up.
+pub fn speed_test_chunk(
+    _endpoint: &str,
+    direction: TransferDirection,
+) -> Result<ChunkTransfer, Box<dyn Error>> {
+    let elapsed = match direction {
+        TransferDirection::Download => Duration::from_millis(400),
+        TransferDirection::Upload => Duration::from_millis(1600),
+    };
+    Ok(ChunkTransfer {
+        bytes: 4_000_000,
+        elapsed,
+    })
+}
+
+pub fn saved_profile_settings(
+    network: &WifiNetwork,
+) -> Result<Option<ProfileSettings>, Box<dyn Error>> {
+    Ok(network.saved.then(|| ProfileSettings {
+        proxy: ProxyConfig::None,
+        users: Vec::new(),
+    }))
+}
+
+/// A dock's Ethernet port with a link and the laptop's own port without a
+/// cable, a registered LTE modem, or a paired phone that can tether.
+pub fn list_devices(
+    kind: DeviceKind,
+) -> Result<Vec<NetworkDevice>, Box<dyn Error>> {
+    let profile = |id: &str, index: usize| DeviceProfile {
+        id: id.to_string(),
+        uuid: format!("00000000-0000-4000-9000-{index:012}"),
+    };
+    Ok(match kind {
+        DeviceKind::Ethernet => vec![
+            NetworkDevice {
+                interface: "demo-enx0".to_string(),
+                state: DeviceState::Connected,
+                link: DeviceLink::Wired {
+                    carrier: true,
+                    speed_mbps: Some(1000),
+                },
+                active_uuid: Some(profile("Dock", 0).uuid),
+                profiles: vec![profile("Dock", 0), profile("Office VLAN", 1)],
+            },
+            NetworkDevice {
+                interface: "demo-eth0".to_string(),
+                state: DeviceState::Unavailable,
+                link: DeviceLink::Wired {
+                    carrier: false,
+                    speed_mbps: None,
+                },
+                active_uuid: None,
+                profiles: vec![profile("Wired connection 1", 2)],
+            },
+        ],
+        DeviceKind::Mobile => vec![NetworkDevice {
+            interface: "demo-wwan0".to_string(),
+            state: DeviceState::Disconnected,
+            link: DeviceLink::Modem {
+                signal_quality: Some(72),
+                operator: Some("Demo Mobile".to_string()),
+            },
+            active_uuid: None,
+            profiles: vec![profile("Carrier LTE", 3)],
+        }],
+        DeviceKind::Bluetooth => vec![NetworkDevice {
+            interface: "DE:40:00:00:00:01".to_string(),
+            state: DeviceState::Disconnected,
+            link: DeviceLink::Bluetooth {
+                name: Some("Demo Phone".to_string()),
+            },
+            active_uuid: None,
+            profiles: vec![profile("Demo Phone Network", 4)],
+        }],
+    })
+}
+
+pub fn set_device_profile(
+    _interface: &str,
+    _profile: Option<&DeviceProfile>,
+) -> Result<(), Box<dyn Error>> {
+    Ok(())
+}
+
+pub fn network_manager_log(
+    filters: &[String],
+) -> Result<Vec<String>, Box<dyn Error>> {
+    let journal = "\
+2026-10-17T02:12:58+0000 demo NetworkManager[612]: <info>  [1792203178.1021] device (demo-wlan0): Activation: starting connection 'CatCat'
+2026-10-17T02:12:58+0000 demo NetworkManager[612]: <info>  [1792203178.2240] device (demo-wlan0): state change: prepare -> config (reason 'none')
+2026-10-17T02:12:59+0000 demo NetworkManager[612]: <info>  [1792203179.0113] device (demo-wlan0): supplicant interface state: associating -> completed
+2026-10-17T02:12:59+0000 demo NetworkManager[612]: <info>  [1792203179.3410] dhcp4 (demo-wlan0): state changed new lease, address=192.168.1.42
+2026-10-17T02:13:00+0000 demo NetworkManager[612]: <info>  [1792203180.0051] device (demo-wlan0): Activation: successful, device activated.
+2026-10-17T02:13:05+0000 demo NetworkManager[612]: <info>  [1792203185.7702] device (demo-eth0): carrier: link connected
+2026-10-17T02:14:12+0000 demo NetworkManager[612]: <warn>  [1792203252.4410] device (demo-wlan0): supplicant interface state: completed -> disconnected
+2026-10-17T02:14:13+0000 demo NetworkManager[612]: <info>  [1792203253.0020] device (demo-wlan0): supplicant interface state: disconnected -> completed
+";
+    Ok(crate::journal::matching_lines(journal, filters))
+}
+
+pub fn find_p2p_peers() -> Result<Vec<P2pPeer>, Box<dyn Error>> {
+    Ok(vec![
+        P2pPeer {
+            name: "Living Room TV".to_string(),
+            hw_address: "DA:A1:19:00:00:01".to_string(),
+            manufacturer: Some("Demo Electronics".to_string()),
+            model: Some("Screen Cast 4K".to_string()),
+            signal_strength: 82,
+        },
+        P2pPeer {
+            name: "DIRECT-4F-Office Printer".to_string(),
+            hw_address: "DA:A1:19:00:00:02".to_string(),
+            manufacturer: Some("Demo Printing".to_string()),
+            model: None,
+            signal_strength: 47,
+        },
+    ])
+}
+
+pub fn connect_p2p_peer(_peer: &P2pPeer) -> Result<(), Box<dyn Error>> {
+    Ok(())
+}
+
+pub fn set_adapter_managed(_interface: &str) -> Result<(), Box<dyn Error>> {
+    Ok(())
+}
+
+pub fn start_network_manager() -> Result<(), Box<dyn Error>> {
+    Ok(())
+}
+
+/// Counters for a steady download trickle, growing with the time since the
+/// demo started.
+pub fn read_traffic_counters(
+    _interface: &str,
+) -> Result<TrafficCounters, Box<dyn Error>> {
+    static STARTED: OnceLock<Instant> = OnceLock::new();
+
+    let elapsed = STARTED.get_or_init(Instant::now).elapsed().as_secs_f64();
+    Ok(TrafficCounters {
+        rx_bytes: 734_003_200 + (elapsed * 1_310_720.0) as u64,
+        tx_bytes: 52_428_800 + (elapsed * 46_080.0) as u64,
+    })
+}
+
 pub fn get_wifi_adapter_name() -> Result<Option<String>, Box<dyn Error>> {
     Ok(Some("demo-wlan0".to_string()))
 }
 
 pub async fn scan_wifi_networks() -> Result<Vec<WifiNetwork>, Box<dyn Error>> {
-    Ok(demo_networks())
+    Ok(scanned_networks())
 }
 
 pub fn connect_to_network(
@@ -81,6 +540,45 @@ pub fn connect_to_network(
     demo_connect(request)
 }
 
+pub fn connect_with_options(
+    request: ConnectionRequest<'_>,
+    _options: &ConnectionOptions,
+    _cancel: &CancelToken,
+    _progress: &ActivationProgress,
+) -> Result<(), Box<dyn Error>> {
+    demo_connect(request)
+}
+
+pub fn reconnect_network(
+    network: &WifiNetwork,
+    _cancel: &CancelToken,
+) -> Result<(), Box<dyn Error>> {
+    if network.connected {
+        Ok(())
+    } else {
+        Err("Demo mode: selected network is not connected".into())
+    }
+}
+
+pub fn steer_to_access_point(
+    network: &WifiNetwork,
+    _bssid: &str,
+) -> Result<(), Box<dyn Error>> {
+    reconnect_network(network, &CancelToken::default())
+}
+
+pub fn renew_dhcp_lease(
+    network: &WifiNetwork,
+    cancel: &CancelToken,
+) -> Result<DhcpLease, Box<dyn Error>> {
+    reconnect_network(network, cancel)?;
+    Ok(DhcpLease {
+        address: "192.168.1.42".to_string(),
+        gateway: Some("192.168.1.1".to_string()),
+        lease_seconds: Some(86_400),
+    })
+}
+
 pub fn disconnect_from_network(
     network: &WifiNetwork,
 ) -> Result<(), Box<dyn Error>> {
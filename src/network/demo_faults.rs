@@ -0,0 +1,330 @@
+//! Artificial latency and failures for the demo backend, so the waiting,
+//! cancel and error paths of the UI can be tried without a misbehaving
+//! network.
+//!
+//! `NM_WIFI_DEMO_FAULTS` holds comma-separated `key=value` pairs:
+//! `delay=<duration>` slows every reply, and `<operation>=<fault>` sets the
+//! fault of one operation, e.g.
+//! `delay=300ms,scan=hang,connect=fail:ip-config`.
+//!
+//! With `--demo` and the variable unset, [`DemoFaults::lively`] applies
+//! instead.
+
+use std::{
+    env,
+    sync::atomic::{AtomicU64, Ordering},
+    time::{Duration, SystemTime, UNIX_EPOCH},
+};
+
+pub const FAULTS_VAR: &str = "NM_WIFI_DEMO_FAULTS";
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum DemoOperation {
+    Scan,
+    Connect,
+    Disconnect,
+    Reconnect,
+    RenewLease,
+}
+
+impl DemoOperation {
+    const ALL: [Self; 5] = [
+        Self::Scan,
+        Self::Connect,
+        Self::Disconnect,
+        Self::Reconnect,
+        Self::RenewLease,
+    ];
+
+    fn key(self) -> &'static str {
+        match self {
+            Self::Scan => "scan",
+            Self::Connect => "connect",
+            Self::Disconnect => "disconnect",
+            Self::Reconnect => "reconnect",
+            Self::RenewLease => "renew",
+        }
+    }
+
+    fn label(self) -> &'static str {
+        match self {
+            Self::Scan => "scan",
+            Self::Connect => "connection",
+            Self::Disconnect => "disconnect",
+            Self::Reconnect => "reconnect",
+            Self::RenewLease => "lease renewal",
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Fault {
+    /// Answers normally after the given time.
+    Delay(Duration),
+    /// Fails with a timeout error after the given time.
+    Timeout(Duration),
+    /// Never answers.
+    Hang,
+    /// Fails right away, optionally naming the activation stage, e.g.
+    /// `ip-config`.
+    Fail(Option<String>),
+    /// Fails the given percentage of requests, picked at random.
+    Flaky(u8),
+}
+
+/// What the demo backend does with one request.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum FaultOutcome {
+    Reply,
+    Fail(String),
+    Hang,
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct DemoFaults {
+    delay: Duration,
+    faults: Vec<(DemoOperation, Fault)>,
+}
+
+impl DemoFaults {
+    /// Reads `NM_WIFI_DEMO_FAULTS`; unset means no faults.
+    pub fn from_env() -> Result<Self, String> {
+        match env::var(FAULTS_VAR) {
+            Ok(spec) => Self::parse(&spec)
+                .map_err(|error| format!("{FAULTS_VAR}: {error}")),
+            Err(_) if super::demo::is_lively() => Ok(Self::lively()),
+            Err(_) => Ok(Self::default()),
+        }
+    }
+
+    /// What `--demo` runs with: every reply takes a moment and roughly one
+    /// connect in seven fails.
+    pub fn lively() -> Self {
+        Self {
+            delay: Duration::from_millis(800),
+            faults: vec![(DemoOperation::Connect, Fault::Flaky(15))],
+        }
+    }
+
+    pub fn parse(spec: &str) -> Result<Self, String> {
+        let mut faults = Self::default();
+
+        for entry in spec.split(',').map(str::trim) {
+            if entry.is_empty() {
+                continue;
+            }
+            let Some((key, value)) = entry.split_once('=') else {
+                return Err(format!("expected `key=value`, got `{entry}`"));
+            };
+            let (key, value) = (key.trim(), value.trim());
+
+            if key == "delay" {
+                faults.delay = parse_duration(value)?;
+                continue;
+            }
+            let operation = DemoOperation::ALL
+                .into_iter()
+                .find(|operation| operation.key() == key)
+                .ok_or_else(|| format!("unknown operation `{key}`"))?;
+            faults.faults.retain(|(existing, _)| *existing != operation);
+            faults.faults.push((operation, parse_fault(value)?));
+        }
+
+        Ok(faults)
+    }
+
+    /// How long to wait before answering `operation` (or any other
+    /// request, when `None`) and what to answer with.
+    pub fn plan(
+        &self,
+        operation: Option<DemoOperation>,
+    ) -> (Duration, FaultOutcome) {
+        let fault = operation.and_then(|operation| {
+            self.faults
+                .iter()
+                .find(|(faulted, _)| *faulted == operation)
+                .map(|(_, fault)| (operation, fault))
+        });
+
+        match fault {
+            None => (self.delay, FaultOutcome::Reply),
+            Some((_, Fault::Delay(delay))) => {
+                (self.delay + *delay, FaultOutcome::Reply)
+            }
+            Some((operation, Fault::Timeout(timeout))) => (
+                self.delay + *timeout,
+                FaultOutcome::Fail(format!(
+                    "Demo mode: {} timed out after {}",
+                    operation.label(),
+                    format_duration(*timeout)
+                )),
+            ),
+            Some((_, Fault::Hang)) => (self.delay, FaultOutcome::Hang),
+            Some((operation, Fault::Fail(stage))) => (
+                self.delay,
+                FaultOutcome::Fail(match stage {
+                    Some(stage) => format!(
+                        "Demo mode: {} failed at {stage}",
+                        operation.label()
+                    ),
+                    None => format!("Demo mode: {} failed", operation.label()),
+                }),
+            ),
+            Some((operation, Fault::Flaky(percent))) => {
+                if random_percent() < *percent {
+                    (
+                        self.delay,
+                        FaultOutcome::Fail(format!(
+                            "Demo mode: {} failed",
+                            operation.label()
+                        )),
+                    )
+                } else {
+                    (self.delay, FaultOutcome::Reply)
+                }
+            }
+        }
+    }
+}
+
+fn parse_fault(value: &str) -> Result<Fault, String> {
+    let (kind, argument) = match value.split_once(':') {
+        Some((kind, argument)) => (kind, Some(argument)),
+        None => (value, None),
+    };
+
+    match (kind, argument) {
+        ("hang", None) => Ok(Fault::Hang),
+        ("fail", stage) => Ok(Fault::Fail(stage.map(str::to_string))),
+        ("delay", Some(duration)) => parse_duration(duration).map(Fault::Delay),
+        ("timeout", Some(duration)) => {
+            parse_duration(duration).map(Fault::Timeout)
+        }
+        ("flaky", Some(percent)) => match percent.parse() {
+            Ok(percent @ 0..=100) => Ok(Fault::Flaky(percent)),
+            _ => Err(format!(
+                "invalid percentage `{percent}`, expected 0 to 100"
+            )),
+        },
+        _ => Err(format!(
+            "unknown fault `{value}`, expected hang, fail[:stage], \
+             delay:<duration>, timeout:<duration> or flaky:<percent>"
+        )),
+    }
+}
+
+/// `250ms` or `2s`.
+fn parse_duration(value: &str) -> Result<Duration, String> {
+    let invalid = || format!("invalid duration `{value}`, expected e.g. 500ms");
+    if let Some(millis) = value.strip_suffix("ms") {
+        millis
+            .parse()
+            .map(Duration::from_millis)
+            .map_err(|_| invalid())
+    } else if let Some(seconds) = value.strip_suffix('s') {
+        seconds
+            .parse()
+            .map(Duration::from_secs)
+            .map_err(|_| invalid())
+    } else {
+        Err(invalid())
+    }
+}
+
+/// A pseudo-random number mixed from the clock and a counter; plenty for
+/// picking demo failures and signal drift.
+pub(crate) fn next_random() -> u64 {
+    static COUNTER: AtomicU64 = AtomicU64::new(0);
+    let nanos = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map_or(0, |elapsed| elapsed.as_nanos() as u64);
+    let mut value =
+        nanos ^ COUNTER.fetch_add(0x9e37_79b9_7f4a_7c15, Ordering::Relaxed);
+    value = (value ^ (value >> 30)).wrapping_mul(0xbf58_476d_1ce4_e5b9);
+    value = (value ^ (value >> 27)).wrapping_mul(0x94d0_49bb_1331_11eb);
+    value ^ (value >> 31)
+}
+
+fn random_percent() -> u8 {
+    (next_random() % 100) as u8
+}
+
+fn format_duration(duration: Duration) -> String {
+    if duration.subsec_millis() == 0 {
+        format!("{}s", duration.as_secs())
+    } else {
+        format!("{}ms", duration.as_millis())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use super::{DemoFaults, DemoOperation, FaultOutcome};
+
+    #[test]
+    fn faults_apply_per_operation_on_top_of_the_global_delay() {
+        let faults = DemoFaults::parse(
+            "delay=200ms, scan=hang, connect=fail:ip-config, renew=timeout:3s",
+        )
+        .expect("valid spec");
+
+        assert_eq!(
+            faults.plan(None),
+            (Duration::from_millis(200), FaultOutcome::Reply)
+        );
+        assert_eq!(
+            faults.plan(Some(DemoOperation::Scan)),
+            (Duration::from_millis(200), FaultOutcome::Hang)
+        );
+        assert_eq!(
+            faults.plan(Some(DemoOperation::Connect)).1,
+            FaultOutcome::Fail(
+                "Demo mode: connection failed at ip-config".to_string()
+            )
+        );
+        assert_eq!(
+            faults.plan(Some(DemoOperation::RenewLease)),
+            (
+                Duration::from_millis(3200),
+                FaultOutcome::Fail(
+                    "Demo mode: lease renewal timed out after 3s".to_string()
+                )
+            )
+        );
+        assert_eq!(
+            faults.plan(Some(DemoOperation::Disconnect)).1,
+            FaultOutcome::Reply
+        );
+    }
+
+    #[test]
+    fn invalid_specs_are_rejected() {
+        assert_eq!(DemoFaults::parse(""), Ok(DemoFaults::default()));
+        assert!(DemoFaults::parse("wifi=hang").is_err());
+        assert!(DemoFaults::parse("scan=slow").is_err());
+        assert!(DemoFaults::parse("delay=5").is_err());
+        assert!(DemoFaults::parse("connect").is_err());
+        assert!(DemoFaults::parse("connect=flaky").is_err());
+        assert!(DemoFaults::parse("connect=flaky:101").is_err());
+    }
+
+    #[test]
+    fn flaky_faults_fail_the_given_share_of_requests() {
+        let never = DemoFaults::parse("connect=flaky:0").expect("valid spec");
+        let always =
+            DemoFaults::parse("connect=flaky:100").expect("valid spec");
+
+        for _ in 0..20 {
+            assert_eq!(
+                never.plan(Some(DemoOperation::Connect)).1,
+                FaultOutcome::Reply
+            );
+            assert_eq!(
+                always.plan(Some(DemoOperation::Connect)).1,
+                FaultOutcome::Fail("Demo mode: connection failed".to_string())
+            );
+        }
+    }
+}
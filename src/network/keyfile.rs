@@ -0,0 +1,343 @@
+//! NetworkManager keyfiles (`.nmconnection`) rendered from the settings a
+//! saved profile reports over D-Bus, for backups and moving profiles to
+//! another machine.
+
+use std::{
+    collections::{HashMap, HashSet},
+    fs,
+    io::{self, Write},
+    os::unix::fs::{OpenOptionsExt, PermissionsExt},
+    path::{Path, PathBuf},
+};
+
+use dbus::arg::{PropMap, RefArg};
+
+/// Settings in the order NM writes them; any others follow by name.
+const SETTING_ORDER: [&str; 7] = [
+    "connection",
+    "802-11-wireless",
+    "802-11-wireless-security",
+    "802-1x",
+    "ipv4",
+    "ipv6",
+    "proxy",
+];
+
+/// The keyfile group of a D-Bus setting name.
+fn group_name(setting: &str) -> &str {
+    match setting {
+        "802-11-wireless" => "wifi",
+        "802-11-wireless-security" => "wifi-security",
+        "802-3-ethernet" => "ethernet",
+        setting => setting,
+    }
+}
+
+pub(crate) fn render_keyfile(settings: &HashMap<String, PropMap>) -> String {
+    let rank = |name: &str| {
+        SETTING_ORDER
+            .iter()
+            .position(|ordered| *ordered == name)
+            .unwrap_or(SETTING_ORDER.len())
+    };
+    let mut names = settings.keys().collect::<Vec<_>>();
+    names.sort_by(|a, b| rank(a).cmp(&rank(b)).then(a.cmp(b)));
+
+    let mut keyfile = String::new();
+    for name in names {
+        let mut entries = settings[name]
+            .iter()
+            .flat_map(|(key, value)| render_entries(key, &*value.0))
+            .collect::<Vec<_>>();
+        entries.sort();
+
+        if !keyfile.is_empty() {
+            keyfile.push('\n');
+        }
+        keyfile.push_str(&format!("[{}]\n", group_name(name)));
+        for (key, value) in entries {
+            keyfile.push_str(&format!("{key}={value}\n"));
+        }
+    }
+    keyfile
+}
+
+/// Keyfile lines of one property. Static addresses and routes are written
+/// as `addressN`/`routeN`; their deprecated D-Bus duplicates and values a
+/// keyfile cannot hold are dropped.
+fn render_entries(key: &str, value: &dyn RefArg) -> Vec<(String, String)> {
+    let signature = value.signature();
+    let items = || value.as_iter().into_iter().flatten();
+
+    match (key, &*signature) {
+        ("address-data", _) => items()
+            .enumerate()
+            .filter_map(|(index, address)| {
+                let fields = dict_fields(address);
+                Some((
+                    format!("address{}", index + 1),
+                    format!(
+                        "{}/{}",
+                        fields.get("address")?.as_str()?,
+                        fields.get("prefix")?.as_u64()?
+                    ),
+                ))
+            })
+            .collect(),
+        ("route-data", _) => items()
+            .enumerate()
+            .filter_map(|(index, route)| {
+                let fields = dict_fields(route);
+                let mut value = format!(
+                    "{}/{}",
+                    fields.get("dest")?.as_str()?,
+                    fields.get("prefix")?.as_u64()?
+                );
+                if let Some(next_hop) =
+                    fields.get("next-hop").and_then(|hop| hop.as_str())
+                {
+                    value.push_str(&format!(",{next_hop}"));
+                }
+                Some((format!("route{}", index + 1), value))
+            })
+            .collect(),
+        ("addresses" | "routes", _) => Vec::new(),
+        (_, "ay") => {
+            let bytes = items()
+                .filter_map(|byte| byte.as_u64().map(|byte| byte as u8))
+                .collect::<Vec<_>>();
+            let value = match String::from_utf8(bytes.clone()) {
+                Ok(text) if !text.chars().any(char::is_control) => {
+                    escape(&text)
+                }
+                _ => bytes.iter().map(|byte| format!("{byte};")).collect(),
+            };
+            vec![(key.to_string(), value)]
+        }
+        (_, "as") => vec![(
+            key.to_string(),
+            items()
+                .filter_map(|item| item.as_str())
+                .map(|item| format!("{};", escape(item).replace(';', "\\;")))
+                .collect(),
+        )],
+        (_, "au" | "ai") => vec![(
+            key.to_string(),
+            items()
+                .filter_map(|item| item.as_i64())
+                .map(|item| format!("{item};"))
+                .collect(),
+        )],
+        (_, "b") => {
+            vec![(key.to_string(), (value.as_u64() == Some(1)).to_string())]
+        }
+        (_, "s" | "o") => value
+            .as_str()
+            .map(|text| vec![(key.to_string(), escape(text))])
+            .unwrap_or_default(),
+        (_, "y" | "n" | "q" | "i" | "u" | "x" | "t") => value
+            .as_i64()
+            .map(|number| number.to_string())
+            .or_else(|| value.as_u64().map(|number| number.to_string()))
+            .map(|number| vec![(key.to_string(), number)])
+            .unwrap_or_default(),
+        _ => Vec::new(),
+    }
+}
+
+/// Fields of one `a{sv}` entry of an `aa{sv}` property.
+fn dict_fields(dict: &dyn RefArg) -> HashMap<String, &dyn RefArg> {
+    let mut fields = HashMap::new();
+    let mut items = dict.as_iter().into_iter().flatten();
+    while let (Some(key), Some(value)) = (items.next(), items.next()) {
+        if let Some(key) = key.as_str() {
+            fields.insert(key.to_string(), value);
+        }
+    }
+    fields
+}
+
+/// GKeyFile escapes, so secrets with backslashes or line breaks survive.
+fn escape(value: &str) -> String {
+    let mut escaped = String::with_capacity(value.len());
+    for (index, c) in value.chars().enumerate() {
+        match c {
+            '\\' => escaped.push_str("\\\\"),
+            '\n' => escaped.push_str("\\n"),
+            '\t' => escaped.push_str("\\t"),
+            '\r' => escaped.push_str("\\r"),
+            ' ' if index == 0 => escaped.push_str("\\s"),
+            c => escaped.push(c),
+        }
+    }
+    escaped
+}
+
+/// Writes `<directory>/<id>.nmconnection`, readable only by the user
+/// because it may hold secrets. Profiles may share an id, so one whose file
+/// is already in `written` gets a numbered name (`<id>-2.nmconnection`)
+/// instead of overwriting it.
+pub(crate) fn write_keyfile(
+    directory: &Path,
+    id: &str,
+    contents: &str,
+    written: &mut HashSet<PathBuf>,
+) -> io::Result<PathBuf> {
+    fs::create_dir_all(directory)?;
+    let stem = id.replace('/', "_");
+    let path = (1..)
+        .map(|n| match n {
+            1 => directory.join(format!("{stem}.nmconnection")),
+            n => directory.join(format!("{stem}-{n}.nmconnection")),
+        })
+        .find(|path| !written.contains(path))
+        .expect("a free keyfile name");
+    let mut file = fs::OpenOptions::new()
+        .write(true)
+        .create(true)
+        .truncate(true)
+        .mode(0o600)
+        .open(&path)?;
+    // `mode` only applies when the file is created, not to one an earlier
+    // export left behind.
+    file.set_permissions(fs::Permissions::from_mode(0o600))?;
+    file.write_all(contents.as_bytes())?;
+    written.insert(path.clone());
+    Ok(path)
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::{HashMap, HashSet};
+
+    use dbus::arg::{PropMap, RefArg, Variant};
+
+    use super::{render_keyfile, write_keyfile};
+
+    fn setting(values: Vec<(&str, Box<dyn RefArg>)>) -> PropMap {
+        values
+            .into_iter()
+            .map(|(key, value)| (key.to_string(), Variant(value)))
+            .collect()
+    }
+
+    #[test]
+    fn settings_become_keyfile_groups() {
+        let address: PropMap = setting(vec![
+            ("address", Box::new("192.168.1.20".to_string())),
+            ("prefix", Box::new(24u32)),
+        ]);
+        let settings = HashMap::from([
+            (
+                "ipv4".to_string(),
+                setting(vec![
+                    ("method", Box::new("manual".to_string())),
+                    ("address-data", Box::new(vec![address])),
+                    ("addresses", Box::new(vec![vec![1u32, 24, 0]])),
+                    ("dns-search", Box::new(vec!["home.lan".to_string()])),
+                ]),
+            ),
+            (
+                "802-11-wireless-security".to_string(),
+                setting(vec![
+                    ("key-mgmt", Box::new("wpa-psk".to_string())),
+                    ("psk", Box::new(" pass\\word".to_string())),
+                ]),
+            ),
+            (
+                "802-11-wireless".to_string(),
+                setting(vec![
+                    ("ssid", Box::new(b"Home".to_vec())),
+                    ("hidden", Box::new(false)),
+                ]),
+            ),
+            (
+                "connection".to_string(),
+                setting(vec![
+                    ("id", Box::new("Home".to_string())),
+                    ("autoconnect-priority", Box::new(5i32)),
+                ]),
+            ),
+        ]);
+
+        assert_eq!(
+            render_keyfile(&settings),
+            "[connection]\n\
+             autoconnect-priority=5\n\
+             id=Home\n\
+             \n\
+             [wifi]\n\
+             hidden=false\n\
+             ssid=Home\n\
+             \n\
+             [wifi-security]\n\
+             key-mgmt=wpa-psk\n\
+             psk=\\spass\\\\word\n\
+             \n\
+             [ipv4]\n\
+             address1=192.168.1.20/24\n\
+             dns-search=home.lan;\n\
+             method=manual\n"
+        );
+    }
+
+    #[test]
+    fn binary_ssids_are_written_as_bytes() {
+        let settings = HashMap::from([(
+            "802-11-wireless".to_string(),
+            setting(vec![("ssid", Box::new(vec![0xffu8, 0x01]))]),
+        )]);
+
+        assert_eq!(render_keyfile(&settings), "[wifi]\nssid=255;1;\n");
+    }
+
+    #[test]
+    fn keyfiles_are_private_to_the_user() {
+        use std::os::unix::fs::PermissionsExt;
+
+        let directory = std::env::temp_dir()
+            .join(format!("nm-wifi-keyfile-{}", std::process::id()));
+        std::fs::create_dir_all(&directory).expect("create fixture");
+        let stale = directory.join("Cafe_Guest.nmconnection");
+        std::fs::write(&stale, "old").expect("stale keyfile written");
+        std::fs::set_permissions(
+            &stale,
+            std::fs::Permissions::from_mode(0o644),
+        )
+        .expect("stale keyfile opened up");
+
+        let path = write_keyfile(
+            &directory,
+            "Cafe/Guest",
+            "[connection]\n",
+            &mut HashSet::new(),
+        )
+        .expect("keyfile written");
+        let mode = path.metadata().expect("metadata").permissions().mode();
+        let contents = std::fs::read_to_string(&path).expect("read back");
+        std::fs::remove_dir_all(&directory).expect("remove fixture");
+
+        assert_eq!(path.file_name().unwrap(), "Cafe_Guest.nmconnection");
+        assert_eq!(mode & 0o777, 0o600);
+        assert_eq!(contents, "[connection]\n");
+    }
+
+    #[test]
+    fn profiles_sharing_an_id_get_their_own_keyfiles() {
+        let directory = std::env::temp_dir()
+            .join(format!("nm-wifi-keyfile-ids-{}", std::process::id()));
+        let mut written = HashSet::new();
+        let first = write_keyfile(&directory, "Home", "first\n", &mut written)
+            .expect("first keyfile written");
+        let second =
+            write_keyfile(&directory, "Home", "second\n", &mut written)
+                .expect("second keyfile written");
+        let contents = [&first, &second]
+            .map(|path| std::fs::read_to_string(path).expect("read back"));
+        std::fs::remove_dir_all(&directory).expect("remove fixture");
+
+        assert_eq!(first.file_name().unwrap(), "Home.nmconnection");
+        assert_eq!(second.file_name().unwrap(), "Home-2.nmconnection");
+        assert_eq!(contents, ["first\n", "second\n"]);
+    }
+}
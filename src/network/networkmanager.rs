@@ -1,6 +1,19 @@
-use std::{collections::HashMap, error::Error, io, time::Duration};
+use std::{
+    cmp::Reverse,
+    collections::{HashMap, HashSet},
+    error::Error,
+    io,
+    path::Path,
+    process::{Command, Stdio},
+    sync::{Arc, Mutex},
+    time::{Duration, Instant},
+};
 
-use dbus::arg::PropMap;
+use dbus::{
+    arg::{PropMap, RefArg, Variant},
+    blocking::stdintf::org_freedesktop_dbus::Properties,
+    message::MatchRule,
+};
 use networkmanager::{
     NetworkManager,
     devices::{Any, Device, Wireless},
@@ -8,19 +21,84 @@ use networkmanager::{
 use tokio::time::sleep;
 
 use crate::{
+    latency::LatencySample,
     network::{
+        ActivationFailed,
+        ActivationProgress,
+        ActivationStep,
+        ActivationWatchdog,
+        AutoconnectBlock,
+        AutoconnectFacts,
+        CancelToken,
+        ConnectionOptions,
         ConnectionRequest,
+        Connectivity,
+        ConnectivityReport,
+        DeviceKind,
+        DeviceLink,
+        DeviceProfile,
+        DeviceState,
+        DeviceStateReason,
+        DhcpLease,
+        FailureKind,
+        ForgetReport,
+        LinkSetting,
+        ManagerState,
+        ManagerStatus,
+        ManagerUnavailable,
+        NetworkDevice,
+        P2pPeer,
+        PrimaryConnection,
+        ProfileSettings,
+        SavedProfile,
+        activation_failure,
+        apply_connection_options,
+        connect_attempt_order,
+        deduplicate_networks,
+        device_profile,
+        guest_network_connection_settings,
+        guest_profile_id,
+        keyfile::{render_keyfile, write_keyfile},
+        nm_band_name,
         open_network_connection_settings,
+        p2p_connection_settings,
+        parse_bssid,
+        profile_band,
+        profile_bssid,
+        profile_cloned_mac,
+        profile_mtu,
+        profile_secondaries,
+        profile_users,
+        proxy_config,
         secured_network_connection_settings,
+        set_ssid_bytes,
+        tries_next_access_point,
+        vpn_profile,
     },
-    wifi::{WifiNetwork, WifiSecurity},
+    wifi::{WifiAccessPoint, WifiBand, WifiNetwork, WifiSecurity, ssid_name},
 };
 
 pub(crate) const AP_FLAGS_PRIVACY: u32 = 0x1;
 pub(crate) const AP_SEC_KEY_MGMT_PSK: u32 = 0x100;
 pub(crate) const AP_SEC_KEY_MGMT_8021X: u32 = 0x200;
 pub(crate) const AP_SEC_KEY_MGMT_SAE: u32 = 0x400;
-const AP_SEC_KEY_MGMT_OWE: u32 = 0x800;
+pub(crate) const AP_SEC_KEY_MGMT_OWE: u32 = 0x800;
+const DEVICE_TYPE_ETHERNET: u32 = 1;
+const DEVICE_TYPE_WIFI: u32 = 2;
+const DEVICE_TYPE_BLUETOOTH: u32 = 5;
+const DEVICE_TYPE_MODEM: u32 = 8;
+const DEVICE_TYPE_WIFI_P2P: u32 = 30;
+/// How long to search for Wi-Fi Direct peers before listing them; peers
+/// answer the probes within a few seconds.
+const P2P_FIND_DURATION: Duration = Duration::from_secs(6);
+const DEVICE_STATE_DISCONNECTED: u32 = 30;
+const DEVICE_STATE_ACTIVATED: u32 = 100;
+const DEVICE_STATE_FAILED: u32 = 120;
+/// Longer than NM's own association and DHCP timeouts, so it reports the
+/// failure before this gives up.
+const ACTIVATION_TIMEOUT: Duration = Duration::from_secs(90);
+/// Deactivating only tears the link down, which takes NM well under this.
+const DEACTIVATION_TIMEOUT: Duration = Duration::from_secs(10);
 
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub(crate) enum SecurityKind {
@@ -78,12 +156,75 @@ pub(crate) fn should_disconnect_device(
     active_ssid == Some(target_ssid)
 }
 
-fn active_access_point_ssid(wifi_device: &impl Wireless) -> Option<String> {
+/// SSID bytes of the access points every Wi-Fi device sees, by lowercase
+/// BSSID. They are read over D-Bus directly because `AccessPoint::ssid` of
+/// the `networkmanager` crate panics on SSIDs that are not UTF-8.
+fn access_point_ssids(
+    dbus: &dbus::blocking::Connection,
+) -> HashMap<String, Vec<u8>> {
+    let Ok((devices,)): Result<(Vec<dbus::Path<'static>>,), _> = nm_wifi_proxy(
+        dbus,
+    )
+    .method_call("org.freedesktop.NetworkManager", "GetDevices", ()) else {
+        return HashMap::new();
+    };
+
+    let mut ssids = HashMap::new();
+    for device_path in devices {
+        let device = dbus.with_proxy(
+            "org.freedesktop.NetworkManager",
+            &device_path,
+            Duration::from_secs(10),
+        );
+        let device_type: Result<u32, _> =
+            device.get("org.freedesktop.NetworkManager.Device", "DeviceType");
+        if device_type.ok() != Some(DEVICE_TYPE_WIFI) {
+            continue;
+        }
+        let Ok((paths,)): Result<(Vec<dbus::Path<'static>>,), _> = device
+            .method_call(
+                "org.freedesktop.NetworkManager.Device.Wireless",
+                "GetAllAccessPoints",
+                (),
+            )
+        else {
+            continue;
+        };
+
+        for path in paths {
+            let access_point = dbus.with_proxy(
+                "org.freedesktop.NetworkManager",
+                &path,
+                Duration::from_secs(10),
+            );
+            const ACCESS_POINT: &str =
+                "org.freedesktop.NetworkManager.AccessPoint";
+            if let (Ok(bssid), Ok(ssid)) = (
+                access_point.get::<String>(ACCESS_POINT, "HwAddress"),
+                access_point.get::<Vec<u8>>(ACCESS_POINT, "Ssid"),
+            ) {
+                ssids.insert(bssid.to_ascii_lowercase(), ssid);
+            }
+        }
+    }
+    ssids
+}
+
+fn active_access_point_ssid(
+    wifi_device: &impl Wireless,
+    ssids: &HashMap<String, Vec<u8>>,
+) -> Option<String> {
+    active_access_point_bssid(wifi_device)
+        .and_then(|bssid| ssids.get(&bssid.to_ascii_lowercase()))
+        .filter(|ssid| !ssid.is_empty())
+        .map(|ssid| ssid_name(ssid))
+}
+
+fn active_access_point_bssid(wifi_device: &impl Wireless) -> Option<String> {
     wifi_device
         .active_access_point()
         .ok()
-        .and_then(|access_point| access_point.ssid().ok())
-        .filter(|ssid| !ssid.is_empty())
+        .and_then(|access_point| access_point.hw_address().ok())
 }
 
 fn get_connected_ssid_via_nm() -> Result<Option<String>, Box<dyn Error>> {
@@ -94,10 +235,11 @@ fn get_connected_ssid_via_nm() -> Result<Option<String>, Box<dyn Error>> {
     let devices = nm.get_devices().map_err(|error| {
         contextual_error("Failed to list NetworkManager devices", error)
     })?;
+    let ssids = access_point_ssids(&dbus);
 
     for device in devices {
         if let Device::WiFi(wifi_device) = device
-            && let Some(ssid) = active_access_point_ssid(&wifi_device)
+            && let Some(ssid) = active_access_point_ssid(&wifi_device, &ssids)
         {
             return Ok(Some(ssid));
         }
@@ -127,21 +269,29 @@ fn get_wifi_adapter_name_via_nm() -> Result<Option<String>, Box<dyn Error>> {
     })?;
     let mut connected = None;
     let mut available = Vec::new();
+    let mut unmanaged = Vec::new();
 
     for device in devices {
         if let Device::WiFi(wifi_device) = device {
             let iface = wifi_device.interface().map_err(|error| {
                 contextual_error("Failed to read WiFi interface name", error)
             })?;
-            let is_connected = active_access_point_ssid(&wifi_device).is_some();
+            let is_connected =
+                active_access_point_bssid(&wifi_device).is_some();
 
             if is_connected {
                 connected = Some(iface.clone());
             }
-            available.push(iface);
+            if wifi_device.managed().unwrap_or(true) {
+                available.push(iface);
+            } else {
+                unmanaged.push(iface);
+            }
         }
     }
 
+    // Unmanaged devices cannot scan, so they are only picked as a last resort.
+    available.extend(unmanaged);
     Ok(choose_wifi_adapter_name(connected, available))
 }
 
@@ -149,370 +299,2159 @@ pub fn get_wifi_adapter_name() -> Result<Option<String>, Box<dyn Error>> {
     get_wifi_adapter_name_via_nm()
 }
 
-pub(crate) fn scan_wait_duration(last_scan_delta_ms: i64) -> Duration {
-    if (0..15_000).contains(&last_scan_delta_ms) {
-        Duration::from_millis(0)
-    } else {
-        Duration::from_millis(750)
-    }
-}
-
-pub(crate) fn scan_wifi_networks_blocking()
--> Result<Vec<WifiNetwork>, Box<dyn Error>> {
+/// Wi-Fi interfaces NetworkManager sees but does not manage.
+pub fn get_unmanaged_wifi_adapters() -> Result<Vec<String>, Box<dyn Error>> {
     let dbus = dbus::blocking::Connection::new_system().map_err(|error| {
         contextual_error("Failed to connect to D-Bus", error)
     })?;
     let nm = NetworkManager::new(&dbus);
-
-    let connected_ssid = get_connected_ssid()?;
-
     let devices = nm.get_devices().map_err(|error| {
         contextual_error("Failed to list NetworkManager devices", error)
     })?;
 
+    let mut unmanaged = Vec::new();
     for device in devices {
-        if let Device::WiFi(wifi_device) = device {
-            let last_scan_before_request = wifi_device.last_scan().unwrap_or(0);
-
-            wifi_device.request_scan(HashMap::new()).map_err(|error| {
-                contextual_error("Failed to request WiFi scan", error)
-            })?;
-
-            let last_scan_after_request =
-                wifi_device.last_scan().unwrap_or(last_scan_before_request);
-            let wait_duration = scan_wait_duration(
-                last_scan_after_request - last_scan_before_request,
-            );
-            if !wait_duration.is_zero() {
-                std::thread::sleep(wait_duration);
-            }
-
-            let access_points =
-                wifi_device.get_all_access_points().map_err(|error| {
-                    contextual_error("Failed to list WiFi access points", error)
-                })?;
-
-            let mut networks = Vec::new();
-
-            for ap in access_points {
-                let ssid = ap.ssid().map_err(|error| {
-                    contextual_error("Failed to read access point SSID", error)
-                })?;
-                if !ssid.is_empty() {
-                    let flags = ap.flags().map_err(|error| {
-                        contextual_error(
-                            "Failed to read access point flags",
-                            error,
-                        )
-                    })?;
-                    let wpa_flags = ap.wpa_flags().map_err(|error| {
-                        contextual_error(
-                            "Failed to read WPA capabilities",
-                            error,
-                        )
-                    })?;
-                    let rsn_flags = ap.rsn_flags().map_err(|error| {
-                        contextual_error(
-                            "Failed to read RSN capabilities",
-                            error,
-                        )
-                    })?;
-
-                    let security = classify_access_point_security(
-                        flags, wpa_flags, rsn_flags,
-                    );
-
-                    let signal_strength = ap.strength().map_err(|error| {
-                        contextual_error(
-                            "Failed to read signal strength",
-                            error,
-                        )
-                    })?;
-
-                    let frequency = ap.frequency().map_err(|error| {
-                        contextual_error("Failed to read WiFi frequency", error)
-                    })?;
-
-                    let connected = connected_ssid.as_ref() == Some(&ssid);
-
-                    networks.push(WifiNetwork {
-                        ssid,
-                        signal_strength,
-                        security,
-                        frequency,
-                        connected,
-                    });
-                }
-            }
-
-            let mut unique_networks: HashMap<String, WifiNetwork> =
-                HashMap::new();
-            for network in networks {
-                match unique_networks.get(&network.ssid) {
-                    Some(existing) => {
-                        if network.frequency > existing.frequency {
-                            unique_networks
-                                .insert(network.ssid.clone(), network);
-                        }
-                    }
-                    None => {
-                        unique_networks.insert(network.ssid.clone(), network);
-                    }
-                }
-            }
-
-            let mut deduplicated_networks: Vec<WifiNetwork> =
-                unique_networks.into_values().collect();
-
-            deduplicated_networks.sort_by(|a, b| {
-                match (a.connected, b.connected) {
-                    (true, false) => std::cmp::Ordering::Less,
-                    (false, true) => std::cmp::Ordering::Greater,
-                    _ => b.signal_strength.cmp(&a.signal_strength),
-                }
-            });
-
-            return Ok(deduplicated_networks);
+        if let Device::WiFi(wifi_device) = device
+            && !wifi_device.managed().unwrap_or(true)
+        {
+            unmanaged.push(wifi_device.interface().map_err(|error| {
+                contextual_error("Failed to read WiFi interface name", error)
+            })?);
         }
     }
 
-    Ok(Vec::new())
+    Ok(unmanaged)
 }
 
-pub async fn scan_wifi_networks() -> Result<Vec<WifiNetwork>, Box<dyn Error>> {
+/// Hands `interface` over to NetworkManager. Changing `Managed` is guarded
+/// by polkit, so a denial gets an explanation instead of the raw D-Bus error.
+pub fn set_adapter_managed(interface: &str) -> Result<(), Box<dyn Error>> {
     let dbus = dbus::blocking::Connection::new_system().map_err(|error| {
         contextual_error("Failed to connect to D-Bus", error)
     })?;
     let nm = NetworkManager::new(&dbus);
+    let device = nm.get_device_by_ip_iface(interface).map_err(|error| {
+        contextual_error(&format!("Failed to find {interface}"), error)
+    })?;
+    let Device::WiFi(wifi_device) = device else {
+        return Err(format!("{interface} is not a Wi-Fi device").into());
+    };
 
-    let connected_ssid = get_connected_ssid()?;
+    wifi_device.set_managed(true).map_err(|error| match &error {
+        networkmanager::Error::DBus(dbus_error)
+            if is_permission_denied(dbus_error) =>
+        {
+            format!("Not authorized to manage {interface}").into()
+        }
+        _ => contextual_error(&format!("Failed to manage {interface}"), error),
+    })
+}
 
-    let devices = nm.get_devices().map_err(|error| {
-        contextual_error("Failed to list NetworkManager devices", error)
+/// Ethernet devices, modems or paired Bluetooth phones with their link and
+/// the saved profiles each can bring up. Devices are read over D-Bus directly because the
+/// `networkmanager` crate has no modem devices.
+pub fn list_devices(
+    kind: DeviceKind,
+) -> Result<Vec<NetworkDevice>, Box<dyn Error>> {
+    let dbus = dbus::blocking::Connection::new_system().map_err(|error| {
+        contextual_error("Failed to connect to D-Bus", error)
     })?;
+    let (paths,): (Vec<dbus::Path<'static>>,) = nm_wifi_proxy(&dbus)
+        .method_call("org.freedesktop.NetworkManager", "GetDevices", ())
+        .map_err(|error| {
+            contextual_error("Failed to list NetworkManager devices", error)
+        })?;
+    let profiles = saved_connections(&dbus)
+        .iter()
+        .filter_map(|(_, settings)| device_profile(settings, kind))
+        .collect::<Vec<_>>();
+    let wanted_type = match kind {
+        DeviceKind::Ethernet => DEVICE_TYPE_ETHERNET,
+        DeviceKind::Mobile => DEVICE_TYPE_MODEM,
+        DeviceKind::Bluetooth => DEVICE_TYPE_BLUETOOTH,
+    };
 
-    for device in devices {
-        if let Device::WiFi(wifi_device) = device {
-            let last_scan_before_request = wifi_device.last_scan().unwrap_or(0);
-
-            wifi_device.request_scan(HashMap::new()).map_err(|error| {
-                contextual_error("Failed to request WiFi scan", error)
+    let mut listed = Vec::new();
+    for path in paths {
+        let device = dbus.with_proxy(
+            "org.freedesktop.NetworkManager",
+            &path,
+            Duration::from_secs(10),
+        );
+        let device_type: Result<u32, _> =
+            device.get("org.freedesktop.NetworkManager.Device", "DeviceType");
+        if device_type.ok() != Some(wanted_type) {
+            continue;
+        }
+        let interface: String = device
+            .get("org.freedesktop.NetworkManager.Device", "Interface")
+            .map_err(|error| {
+                contextual_error("Failed to read device interface name", error)
             })?;
-
-            let last_scan_after_request =
-                wifi_device.last_scan().unwrap_or(last_scan_before_request);
-            let wait_duration = scan_wait_duration(
-                last_scan_after_request - last_scan_before_request,
-            );
-            if !wait_duration.is_zero() {
-                sleep(wait_duration).await;
-            }
-
-            let access_points =
-                wifi_device.get_all_access_points().map_err(|error| {
-                    contextual_error("Failed to list WiFi access points", error)
-                })?;
-
-            let mut networks = Vec::new();
-
-            for ap in access_points {
-                let ssid = ap.ssid().map_err(|error| {
-                    contextual_error("Failed to read access point SSID", error)
-                })?;
-                if !ssid.is_empty() {
-                    let flags = ap.flags().map_err(|error| {
-                        contextual_error(
-                            "Failed to read access point flags",
-                            error,
-                        )
-                    })?;
-                    let wpa_flags = ap.wpa_flags().map_err(|error| {
-                        contextual_error(
-                            "Failed to read WPA capabilities",
-                            error,
-                        )
-                    })?;
-                    let rsn_flags = ap.rsn_flags().map_err(|error| {
-                        contextual_error(
-                            "Failed to read RSN capabilities",
-                            error,
-                        )
-                    })?;
-
-                    let security = classify_access_point_security(
-                        flags, wpa_flags, rsn_flags,
-                    );
-
-                    let signal_strength = ap.strength().map_err(|error| {
-                        contextual_error(
-                            "Failed to read signal strength",
-                            error,
-                        )
-                    })?;
-
-                    let frequency = ap.frequency().map_err(|error| {
-                        contextual_error("Failed to read WiFi frequency", error)
-                    })?;
-
-                    let connected = connected_ssid.as_ref() == Some(&ssid);
-
-                    networks.push(WifiNetwork {
-                        ssid,
-                        signal_strength,
-                        security,
-                        frequency,
-                        connected,
-                    });
+        let state: u32 = device
+            .get("org.freedesktop.NetworkManager.Device", "State")
+            .unwrap_or(0);
+        let link = match kind {
+            DeviceKind::Ethernet => {
+                let wired = "org.freedesktop.NetworkManager.Device.Wired";
+                let carrier: bool =
+                    device.get(wired, "Carrier").unwrap_or(false);
+                let speed: u32 = device.get(wired, "Speed").unwrap_or(0);
+                DeviceLink::Wired {
+                    carrier,
+                    speed_mbps: (carrier && speed > 0).then_some(speed),
                 }
             }
-
-            let mut unique_networks: HashMap<String, WifiNetwork> =
-                HashMap::new();
-            for network in networks {
-                match unique_networks.get(&network.ssid) {
-                    Some(existing) => {
-                        if network.frequency > existing.frequency {
-                            unique_networks
-                                .insert(network.ssid.clone(), network);
-                        }
-                    }
-                    None => {
-                        unique_networks.insert(network.ssid.clone(), network);
-                    }
-                }
+            DeviceKind::Mobile => {
+                let udi: String = device
+                    .get("org.freedesktop.NetworkManager.Device", "Udi")
+                    .unwrap_or_default();
+                modem_link(&dbus, &udi)
             }
+            DeviceKind::Bluetooth => DeviceLink::Bluetooth {
+                name: device
+                    .get::<String>(
+                        "org.freedesktop.NetworkManager.Device.Bluetooth",
+                        "Name",
+                    )
+                    .ok()
+                    .filter(|name| !name.is_empty()),
+            },
+        };
+        listed.push(NetworkDevice {
+            state: DeviceState::from_nm(state),
+            link,
+            active_uuid: device_active_uuid(&dbus, &path),
+            profiles: profiles
+                .iter()
+                .filter(|(_, bound)| {
+                    bound.as_deref().is_none_or(|bound| bound == interface)
+                })
+                .map(|(profile, _)| profile.clone())
+                .collect(),
+            interface,
+        });
+    }
 
-            let mut deduplicated_networks: Vec<WifiNetwork> =
-                unique_networks.into_values().collect();
-
-            deduplicated_networks.sort_by(|a, b| {
-                match (a.connected, b.connected) {
-                    (true, false) => std::cmp::Ordering::Less,
-                    (false, true) => std::cmp::Ordering::Greater,
-                    _ => b.signal_strength.cmp(&a.signal_strength),
-                }
-            });
+    Ok(listed)
+}
 
-            return Ok(deduplicated_networks);
-        }
+/// Signal quality and operator of the ModemManager modem at `path`. Both
+/// are left out when ModemManager is not running.
+fn modem_link(dbus: &dbus::blocking::Connection, path: &str) -> DeviceLink {
+    let Ok(path) = dbus::Path::new(path) else {
+        return DeviceLink::Modem {
+            signal_quality: None,
+            operator: None,
+        };
+    };
+    let modem = dbus.with_proxy(
+        "org.freedesktop.ModemManager1",
+        path,
+        Duration::from_secs(10),
+    );
+    let signal: Option<(u32, bool)> = modem
+        .get("org.freedesktop.ModemManager1.Modem", "SignalQuality")
+        .ok();
+    let operator: Option<String> = modem
+        .get(
+            "org.freedesktop.ModemManager1.Modem.Modem3gpp",
+            "OperatorName",
+        )
+        .ok();
+    DeviceLink::Modem {
+        signal_quality: signal.map(|(quality, _)| quality.min(100) as u8),
+        operator: operator.filter(|operator| !operator.is_empty()),
     }
-
-    Ok(Vec::new())
 }
 
-fn nm_wifi_proxy(
+/// UUID of the connection active on the device at `device_path`, if any.
+fn device_active_uuid(
     dbus: &dbus::blocking::Connection,
-) -> dbus::blocking::Proxy<'_, &dbus::blocking::Connection> {
+    device_path: &dbus::Path<'static>,
+) -> Option<String> {
+    let active_path: dbus::Path<'static> = dbus
+        .with_proxy(
+            "org.freedesktop.NetworkManager",
+            device_path,
+            Duration::from_secs(10),
+        )
+        .get("org.freedesktop.NetworkManager.Device", "ActiveConnection")
+        .ok()?;
+    if &*active_path == "/" {
+        return None;
+    }
     dbus.with_proxy(
         "org.freedesktop.NetworkManager",
-        "/org/freedesktop/NetworkManager",
+        &active_path,
         Duration::from_secs(10),
     )
+    .get("org.freedesktop.NetworkManager.Connection.Active", "Uuid")
+    .ok()
 }
 
-fn connect_via_networkmanager(
-    settings: HashMap<&'static str, PropMap>,
-) -> Result<(), Box<dyn Error>> {
-    let adapter = get_wifi_adapter_name_via_nm()?.ok_or_else(|| {
-        io::Error::new(
-            io::ErrorKind::NotFound,
-            "No WiFi adapter was found in NetworkManager",
-        )
-    })?;
+/// The device whose `Interface` is `interface`. Unlike
+/// `GetDeviceByIpIface` this also finds Bluetooth devices, which are named
+/// after the address of the paired device and have no IP interface until
+/// they connect.
+fn device_path_by_interface(
+    dbus: &dbus::blocking::Connection,
+    interface: &str,
+) -> Result<dbus::Path<'static>, Box<dyn Error>> {
+    let (paths,): (Vec<dbus::Path<'static>>,) = nm_wifi_proxy(dbus)
+        .method_call("org.freedesktop.NetworkManager", "GetDevices", ())
+        .map_err(|error| {
+            contextual_error("Failed to list NetworkManager devices", error)
+        })?;
+    paths
+        .into_iter()
+        .find(|path| {
+            dbus.with_proxy(
+                "org.freedesktop.NetworkManager",
+                path,
+                Duration::from_secs(10),
+            )
+            .get::<String>("org.freedesktop.NetworkManager.Device", "Interface")
+            .is_ok_and(|name| name == interface)
+        })
+        .ok_or_else(|| format!("Failed to find {interface}").into())
+}
 
+/// Brings `profile` up on `interface`, or takes the interface
+/// down when `profile` is `None`.
+pub fn set_device_profile(
+    interface: &str,
+    profile: Option<&DeviceProfile>,
+) -> Result<(), Box<dyn Error>> {
     let dbus = dbus::blocking::Connection::new_system().map_err(|error| {
         contextual_error("Failed to connect to D-Bus", error)
     })?;
     let proxy = nm_wifi_proxy(&dbus);
-
-    let (device_path,): (dbus::Path<'static>,) = proxy
+    let device_path = device_path_by_interface(&dbus, interface)?;
+
+    let Some(profile) = profile else {
+        return dbus
+            .with_proxy(
+                "org.freedesktop.NetworkManager",
+                &device_path,
+                Duration::from_secs(10),
+            )
+            .method_call::<(), _, _, _>(
+                "org.freedesktop.NetworkManager.Device",
+                "Disconnect",
+                (),
+            )
+            .map_err(|error| {
+                contextual_error(
+                    &format!("Failed to take {interface} down"),
+                    error,
+                )
+            });
+    };
+    let (connection_path, _) = saved_connections(&dbus)
+        .into_iter()
+        .find(|(_, settings)| {
+            [
+                DeviceKind::Ethernet,
+                DeviceKind::Mobile,
+                DeviceKind::Bluetooth,
+            ]
+            .into_iter()
+            .any(|kind| {
+                device_profile(settings, kind)
+                    .is_some_and(|(saved, _)| saved.uuid == profile.uuid)
+            })
+        })
+        .ok_or_else(|| format!("Saved profile {} not found", profile.id))?;
+    let _: (dbus::Path<'static>,) = proxy
         .method_call(
             "org.freedesktop.NetworkManager",
-            "GetDeviceByIpIface",
-            (adapter.as_str(),),
+            "ActivateConnection",
+            (connection_path, device_path, dbus::Path::from("/")),
         )
         .map_err(|error| {
             contextual_error(
-                "Failed to find WiFi device in NetworkManager",
+                &format!("Failed to bring {} up", profile.id),
                 error,
             )
         })?;
+    Ok(())
+}
 
-    let specific_object = dbus::Path::from("/");
-    let _: (dbus::Path<'static>, dbus::Path<'static>) = proxy
-        .method_call(
+/// The Wi-Fi P2P device NetworkManager creates next to a Wi-Fi adapter
+/// that supports Wi-Fi Direct.
+fn p2p_device_path(
+    dbus: &dbus::blocking::Connection,
+) -> Result<dbus::Path<'static>, Box<dyn Error>> {
+    let (paths,): (Vec<dbus::Path<'static>>,) = nm_wifi_proxy(dbus)
+        .method_call("org.freedesktop.NetworkManager", "GetDevices", ())
+        .map_err(|error| {
+            contextual_error("Failed to list NetworkManager devices", error)
+        })?;
+    paths
+        .into_iter()
+        .find(|path| {
+            dbus.with_proxy(
+                "org.freedesktop.NetworkManager",
+                path,
+                Duration::from_secs(10),
+            )
+            .get::<u32>("org.freedesktop.NetworkManager.Device", "DeviceType")
+            .is_ok_and(|device_type| device_type == DEVICE_TYPE_WIFI_P2P)
+        })
+        .ok_or_else(|| "No Wi-Fi adapter supports Wi-Fi Direct".into())
+}
+
+/// Peers of the P2P device at `device_path` with their D-Bus paths.
+fn p2p_peers(
+    dbus: &dbus::blocking::Connection,
+    device_path: &dbus::Path<'static>,
+) -> Result<Vec<(dbus::Path<'static>, P2pPeer)>, Box<dyn Error>> {
+    let paths: Vec<dbus::Path<'static>> = dbus
+        .with_proxy(
             "org.freedesktop.NetworkManager",
-            "AddAndActivateConnection",
-            (settings, device_path, specific_object),
+            device_path,
+            Duration::from_secs(10),
         )
+        .get("org.freedesktop.NetworkManager.Device.WifiP2P", "Peers")
         .map_err(|error| {
-            contextual_error(
-                "NetworkManager failed to activate the WiFi connection",
-                error,
-            )
+            contextual_error("Failed to read Wi-Fi Direct peers", error)
         })?;
 
-    Ok(())
+    const PEER: &str = "org.freedesktop.NetworkManager.WifiP2PPeer";
+    let mut peers = Vec::new();
+    for path in paths {
+        let peer = dbus.with_proxy(
+            "org.freedesktop.NetworkManager",
+            &path,
+            Duration::from_secs(10),
+        );
+        let Ok(hw_address) = peer.get::<String>(PEER, "HwAddress") else {
+            continue;
+        };
+        let text = |property| {
+            peer.get::<String>(PEER, property)
+                .ok()
+                .filter(|value| !value.is_empty())
+        };
+        peers.push((
+            path.clone(),
+            P2pPeer {
+                name: text("Name").unwrap_or_else(|| hw_address.clone()),
+                manufacturer: text("Manufacturer"),
+                model: text("Model"),
+                signal_strength: peer.get(PEER, "Strength").unwrap_or(0),
+                hw_address,
+            },
+        ));
+    }
+    Ok(peers)
 }
 
-pub fn connect_to_network(
-    request: ConnectionRequest<'_>,
-) -> Result<(), Box<dyn Error>> {
-    let network = match &request {
-        ConnectionRequest::Open { network }
-        | ConnectionRequest::Secured { network, .. } => *network,
-    };
-
-    match request {
-        ConnectionRequest::Open { .. } => {
-            if network.security != WifiSecurity::Open {
-                return Err("Password required for secured network".into());
-            }
-            connect_via_networkmanager(open_network_connection_settings(&network.ssid))
-        }
-        ConnectionRequest::Secured { passphrase, .. } => {
-            match classify_security(network, Some(passphrase)) {
-                SecurityKind::WpaPsk => connect_via_networkmanager(
-                    secured_network_connection_settings(&network.ssid, passphrase, "wpa-psk"),
-                ),
-                SecurityKind::WpaSae => connect_via_networkmanager(
-                    secured_network_connection_settings(&network.ssid, passphrase, "sae"),
-                ),
-                SecurityKind::Open => {
-                    Err("Open networks should not be activated with a password request".into())
-                }
-                SecurityKind::Unsupported => Err(format!(
-                    "Unsupported network security for NetworkManager activation: {}",
-                    network.security.display_name()
-                )
-                .into()),
-            }
-        }
-    }
+/// Searches for Wi-Fi Direct peers for a few seconds and lists what
+/// answered, strongest first.
+pub fn find_p2p_peers() -> Result<Vec<P2pPeer>, Box<dyn Error>> {
+    let dbus = dbus::blocking::Connection::new_system().map_err(|error| {
+        contextual_error("Failed to connect to D-Bus", error)
+    })?;
+    let device_path = p2p_device_path(&dbus)?;
+    let device = dbus.with_proxy(
+        "org.freedesktop.NetworkManager",
+        &device_path,
+        Duration::from_secs(10),
+    );
+
+    let mut options = PropMap::new();
+    options.insert(
+        "timeout".to_string(),
+        Variant(Box::new(P2P_FIND_DURATION.as_secs() as i32 + 1)),
+    );
+    device
+        .method_call::<(), _, _, _>(
+            "org.freedesktop.NetworkManager.Device.WifiP2P",
+            "StartFind",
+            (options,),
+        )
+        .map_err(|error| {
+            contextual_error("Failed to search for Wi-Fi Direct peers", error)
+        })?;
+    std::thread::sleep(P2P_FIND_DURATION);
+    let _ = device.method_call::<(), _, _, _>(
+        "org.freedesktop.NetworkManager.Device.WifiP2P",
+        "StopFind",
+        (),
+    );
+
+    let mut peers = p2p_peers(&dbus, &device_path)?
+        .into_iter()
+        .map(|(_, peer)| peer)
+        .collect::<Vec<_>>();
+    peers.sort_by_key(|peer| Reverse(peer.signal_strength));
+    Ok(peers)
 }
 
-fn disconnect_via_networkmanager(
-    network: &WifiNetwork,
-) -> Result<bool, Box<dyn Error>> {
+/// Joins the Wi-Fi Direct group of `peer` and waits for it to come up.
+pub fn connect_p2p_peer(peer: &P2pPeer) -> Result<(), Box<dyn Error>> {
     let dbus = dbus::blocking::Connection::new_system().map_err(|error| {
         contextual_error("Failed to connect to D-Bus", error)
     })?;
-    let nm = NetworkManager::new(&dbus);
+    let proxy = nm_wifi_proxy(&dbus);
+    let device_path = p2p_device_path(&dbus)?;
+    let peer_path = p2p_peers(&dbus, &device_path)?
+        .into_iter()
+        .find(|(_, found)| {
+            found.hw_address.eq_ignore_ascii_case(&peer.hw_address)
+        })
+        .map(|(path, _)| path)
+        .ok_or_else(|| format!("{} is no longer in range", peer.name))?;
+
+    add_and_activate_connection(
+        &proxy,
+        p2p_connection_settings(peer),
+        &device_path,
+        peer_path,
+        false,
+        &CancelToken::default(),
+        &ActivationProgress::default(),
+    )
+}
 
-    for device in nm.get_devices().map_err(|error| {
-        contextual_error("Failed to list NetworkManager devices", error)
-    })? {
-        if let Device::WiFi(wifi_device) = device {
-            let active_ssid = active_access_point_ssid(&wifi_device);
+fn is_permission_denied(error: &dbus::Error) -> bool {
+    matches!(
+        error.name(),
+        Some(
+            "org.freedesktop.NetworkManager.PermissionDenied"
+                | "org.freedesktop.DBus.Error.AccessDenied"
+                | "org.freedesktop.DBus.Error.InteractiveAuthorizationRequired"
+        )
+    )
+}
 
-            if should_disconnect_device(active_ssid.as_deref(), &network.ssid) {
-                wifi_device.disconnect().map_err(|error| {
-                    contextual_error(
-                        "Failed to disconnect device via NetworkManager",
-                        error,
-                    )
-                })?;
-                return Ok(true);
+const SCAN_POLL_INTERVAL: Duration = Duration::from_millis(100);
+const SCAN_COMPLETION_TIMEOUT: Duration = Duration::from_secs(10);
+/// How long the access point list has to stay unchanged before a scan
+/// without a usable `LastScan` counts as finished.
+const SCAN_QUIET_PERIOD: Duration = Duration::from_millis(1500);
+
+/// `LastScan` is a CLOCK_BOOTTIME timestamp in milliseconds (-1 before the
+/// first scan), so any newer value means a scan finished after our request.
+pub(crate) fn scan_completed(
+    last_scan_before_request: i64,
+    last_scan: i64,
+) -> bool {
+    last_scan > last_scan_before_request
+}
+
+/// Decides when a requested scan has finished, so fast adapters are read
+/// as soon as they are done and slow ones are not read too early.
+/// `LastScan` advancing settles it; where NM or the driver never provides
+/// `LastScan`, the scan counts as done once the access point list stops
+/// changing. `SCAN_COMPLETION_TIMEOUT` caps the wait either way.
+pub(crate) struct ScanWait {
+    /// `LastScan` before the request, `None` when it cannot be read.
+    requested_after: Option<i64>,
+    started: Instant,
+    access_points: Option<usize>,
+    changed_at: Instant,
+}
+
+impl ScanWait {
+    pub(crate) fn new(requested_after: Option<i64>, now: Instant) -> Self {
+        Self {
+            requested_after,
+            started: now,
+            access_points: None,
+            changed_at: now,
+        }
+    }
+
+    /// `access_points` counts the APs the adapter knows about; it is only
+    /// called when `LastScan` is unavailable.
+    pub(crate) fn is_done(
+        &mut self,
+        last_scan: Option<i64>,
+        access_points: impl FnOnce() -> usize,
+        now: Instant,
+    ) -> bool {
+        if now.duration_since(self.started) >= SCAN_COMPLETION_TIMEOUT {
+            return true;
+        }
+        if let (Some(before), Some(last_scan)) =
+            (self.requested_after, last_scan)
+        {
+            return scan_completed(before, last_scan);
+        }
+
+        let count = access_points();
+        if self.access_points != Some(count) {
+            self.access_points = Some(count);
+            self.changed_at = now;
+        }
+        now.duration_since(self.changed_at) >= SCAN_QUIET_PERIOD
+    }
+}
+
+fn access_point_count(wifi_device: &impl Wireless) -> usize {
+    wifi_device
+        .get_all_access_points()
+        .map(|access_points| access_points.len())
+        .unwrap_or(0)
+}
+
+/// Every saved connection with its settings path. Best effort: a failure to
+/// read them only hides features that depend on saved profiles.
+fn saved_connections(
+    dbus: &dbus::blocking::Connection,
+) -> Vec<(dbus::Path<'static>, HashMap<String, PropMap>)> {
+    let settings = dbus.with_proxy(
+        "org.freedesktop.NetworkManager",
+        "/org/freedesktop/NetworkManager/Settings",
+        Duration::from_secs(10),
+    );
+    let Ok((paths,)): Result<(Vec<dbus::Path<'static>>,), _> = settings
+        .method_call(
+            "org.freedesktop.NetworkManager.Settings",
+            "ListConnections",
+            (),
+        )
+    else {
+        return Vec::new();
+    };
+
+    paths
+        .into_iter()
+        .filter_map(|path| {
+            let connection = dbus.with_proxy(
+                "org.freedesktop.NetworkManager",
+                &path,
+                Duration::from_secs(10),
+            );
+            let (settings,): (HashMap<String, PropMap>,) = connection
+                .method_call(
+                    "org.freedesktop.NetworkManager.Settings.Connection",
+                    "GetSettings",
+                    (),
+                )
+                .ok()?;
+            Some((path, settings))
+        })
+        .collect()
+}
+
+/// Saved Wi-Fi profiles with their settings paths.
+fn saved_wifi_connections(
+    dbus: &dbus::blocking::Connection,
+) -> Vec<(dbus::Path<'static>, SavedProfile)> {
+    let connections = saved_connections(dbus);
+    let vpns = connections
+        .iter()
+        .filter_map(|(_, settings)| vpn_profile(settings))
+        .map(|(id, uuid)| (uuid, id))
+        .collect::<HashMap<_, _>>();
+    connections
+        .into_iter()
+        .filter_map(|(path, settings)| {
+            let ssid = settings
+                .get("802-11-wireless")?
+                .get("ssid")?
+                .0
+                .as_iter()?
+                .map(|byte| byte.as_u64().map(|byte| byte as u8))
+                .collect::<Option<Vec<u8>>>()?;
+            let ssid = ssid_name(&ssid);
+            let connection = settings.get("connection")?;
+            let text = |key: &str| {
+                connection
+                    .get(key)
+                    .and_then(|value| value.0.as_str())
+                    .map(str::to_string)
+            };
+            let profile = SavedProfile {
+                id: text("id").unwrap_or_else(|| ssid.clone()),
+                uuid: text("uuid")?,
+                ssid,
+                users: profile_users(&settings),
+                band: profile_band(&settings),
+                bssid: profile_bssid(&settings),
+                mtu: profile_mtu(&settings),
+                cloned_mac: profile_cloned_mac(&settings),
+                vpn: profile_secondaries(&settings)
+                    .iter()
+                    .find_map(|uuid| vpns.get(uuid).cloned()),
+                last_used: connection
+                    .get("timestamp")
+                    .and_then(|value| value.0.as_u64())
+                    .filter(|timestamp| *timestamp > 0),
+            };
+            Some((path, profile))
+        })
+        .collect()
+}
+
+fn saved_wifi_ssids(dbus: &dbus::blocking::Connection) -> HashSet<String> {
+    saved_wifi_connections(dbus)
+        .into_iter()
+        .map(|(_, profile)| profile.ssid)
+        .collect()
+}
+
+pub fn list_saved_profiles() -> Result<Vec<SavedProfile>, Box<dyn Error>> {
+    let dbus = dbus::blocking::Connection::new_system().map_err(|error| {
+        contextual_error("Failed to connect to D-Bus", error)
+    })?;
+
+    Ok(saved_wifi_connections(&dbus)
+        .into_iter()
+        .map(|(_, profile)| profile)
+        .collect())
+}
+
+/// Deletes the given saved profiles, carrying on past one that cannot be
+/// deleted. Fails only when none of them could be.
+pub fn forget_profiles(
+    profiles: &[SavedProfile],
+) -> Result<ForgetReport, Box<dyn Error>> {
+    let dbus = dbus::blocking::Connection::new_system().map_err(|error| {
+        contextual_error("Failed to connect to D-Bus", error)
+    })?;
+    let uuids = profiles
+        .iter()
+        .map(|profile| profile.uuid.as_str())
+        .collect::<HashSet<_>>();
+
+    let mut report = ForgetReport {
+        forgotten: 0,
+        requested: 0,
+        error: None,
+    };
+    for (path, profile) in saved_wifi_connections(&dbus) {
+        if !uuids.contains(profile.uuid.as_str()) {
+            continue;
+        }
+
+        report.requested += 1;
+        let connection = dbus.with_proxy(
+            "org.freedesktop.NetworkManager",
+            &path,
+            Duration::from_secs(10),
+        );
+        let deleted = connection.method_call::<(), _, _, _>(
+            "org.freedesktop.NetworkManager.Settings.Connection",
+            "Delete",
+            (),
+        );
+        match deleted {
+            Ok(()) => report.forgotten += 1,
+            Err(error) => {
+                report.error.get_or_insert_with(|| {
+                    format!("Failed to forget {}: {error}", profile.id)
+                });
+            }
+        }
+    }
+
+    match report.error {
+        Some(error) if report.forgotten == 0 => Err(error.into()),
+        _ => Ok(report),
+    }
+}
+
+/// Deletes the throwaway profile a guest connect to `ssid` created, which
+/// also takes the connection down when it is still up.
+pub fn delete_guest_profile(ssid: &str) -> Result<(), Box<dyn Error>> {
+    let dbus = dbus::blocking::Connection::new_system().map_err(|error| {
+        contextual_error("Failed to connect to D-Bus", error)
+    })?;
+    let id = guest_profile_id(ssid);
+
+    for (path, profile) in saved_wifi_connections(&dbus) {
+        if profile.id != id {
+            continue;
+        }
+        dbus.with_proxy(
+            "org.freedesktop.NetworkManager",
+            &path,
+            Duration::from_secs(10),
+        )
+        .method_call::<(), _, _, _>(
+            "org.freedesktop.NetworkManager.Settings.Connection",
+            "Delete",
+            (),
+        )
+        .map_err(|error| {
+            contextual_error("Failed to delete the guest profile", error)
+        })?;
+    }
+    Ok(())
+}
+
+/// Rewrites the settings of the saved `profile` with `change` applied. The
+/// stored secrets are left as they are: an update without secrets keeps the
+/// existing ones.
+fn update_saved_profile(
+    profile: &SavedProfile,
+    failure: &str,
+    change: impl FnOnce(&mut HashMap<String, PropMap>) -> Result<(), Box<dyn Error>>,
+) -> Result<(), Box<dyn Error>> {
+    let dbus = dbus::blocking::Connection::new_system().map_err(|error| {
+        contextual_error("Failed to connect to D-Bus", error)
+    })?;
+    let Some((path, _)) = saved_wifi_connections(&dbus)
+        .into_iter()
+        .find(|(_, saved)| saved.uuid == profile.uuid)
+    else {
+        return Err(format!("Saved profile {} not found", profile.id).into());
+    };
+
+    let connection = dbus.with_proxy(
+        "org.freedesktop.NetworkManager",
+        &path,
+        Duration::from_secs(10),
+    );
+    let (mut settings,): (HashMap<String, PropMap>,) = connection
+        .method_call(
+            "org.freedesktop.NetworkManager.Settings.Connection",
+            "GetSettings",
+            (),
+        )
+        .map_err(|error| {
+            contextual_error("Failed to read saved connection", error)
+        })?;
+    change(&mut settings)?;
+
+    connection
+        .method_call::<(), _, _, _>(
+            "org.freedesktop.NetworkManager.Settings.Connection",
+            "Update",
+            (settings,),
+        )
+        .map_err(|error| contextual_error(failure, error))
+}
+
+/// Restricts `profile` to `owner`, or makes it available to every user when
+/// `owner` is `None`.
+pub fn set_profile_owner(
+    profile: &SavedProfile,
+    owner: Option<&str>,
+) -> Result<(), Box<dyn Error>> {
+    let permissions = owner
+        .map(|owner| vec![format!("user:{owner}:")])
+        .unwrap_or_default();
+    update_saved_profile(
+        profile,
+        &format!("Failed to change the scope of {}", profile.id),
+        |settings| {
+            settings
+                .entry("connection".to_string())
+                .or_default()
+                .insert(
+                    "permissions".to_string(),
+                    Variant(Box::new(permissions)),
+                );
+            Ok(())
+        },
+    )
+}
+
+/// Changes the `connection.id` of the saved `profile` to `name`.
+pub fn rename_profile(
+    profile: &SavedProfile,
+    name: &str,
+) -> Result<(), Box<dyn Error>> {
+    update_saved_profile(
+        profile,
+        &format!("Failed to rename {}", profile.id),
+        |settings| {
+            settings
+                .entry("connection".to_string())
+                .or_default()
+                .insert("id".to_string(), Variant(Box::new(name.to_string())));
+            Ok(())
+        },
+    )
+}
+
+/// Locks the saved `profile` to `band` with `wifi.band`, or lets it use
+/// any band when `band` is `None`.
+pub fn set_profile_band(
+    profile: &SavedProfile,
+    band: Option<WifiBand>,
+) -> Result<(), Box<dyn Error>> {
+    let band = match band {
+        Some(band) => Some(nm_band_name(band).ok_or_else(|| {
+            format!("NetworkManager cannot lock a profile to {}", band.label())
+        })?),
+        None => None,
+    };
+    update_saved_profile(
+        profile,
+        &format!("Failed to change the band of {}", profile.id),
+        |settings| {
+            let wireless =
+                settings.entry("802-11-wireless".to_string()).or_default();
+            match band {
+                Some(band) => {
+                    wireless.insert(
+                        "band".to_string(),
+                        Variant(Box::new(band.to_string())),
+                    );
+                }
+                None => {
+                    wireless.remove("band");
+                    // A channel is only valid together with a band.
+                    wireless.remove("channel");
+                }
+            }
+            Ok(())
+        },
+    )
+}
+
+/// Pins the saved `profile` to the access point `bssid`, or unpins it when
+/// `bssid` is `None`.
+pub fn set_profile_bssid(
+    profile: &SavedProfile,
+    bssid: Option<&str>,
+) -> Result<(), Box<dyn Error>> {
+    let bytes = match bssid {
+        Some(bssid) => Some(
+            parse_bssid(bssid)
+                .ok_or_else(|| format!("{bssid} is not a valid BSSID"))?,
+        ),
+        None => None,
+    };
+    update_saved_profile(
+        profile,
+        &format!("Failed to change the access point of {}", profile.id),
+        |settings| {
+            let wireless =
+                settings.entry("802-11-wireless".to_string()).or_default();
+            match bytes {
+                Some(bytes) => {
+                    wireless.insert(
+                        "bssid".to_string(),
+                        Variant(Box::new(bytes.to_vec())),
+                    );
+                }
+                None => {
+                    wireless.remove("bssid");
+                }
+            }
+            Ok(())
+        },
+    )
+}
+
+/// Changes the MTU or cloned MAC address of the saved `profile`; `None`
+/// removes the setting so NetworkManager's default applies.
+pub fn set_profile_link(
+    profile: &SavedProfile,
+    setting: &LinkSetting,
+) -> Result<(), Box<dyn Error>> {
+    update_saved_profile(
+        profile,
+        &format!("Failed to change the link settings of {}", profile.id),
+        |settings| {
+            let wireless =
+                settings.entry("802-11-wireless".to_string()).or_default();
+            match setting {
+                LinkSetting::Mtu(mtu) => {
+                    wireless.insert(
+                        "mtu".to_string(),
+                        Variant(Box::new(mtu.unwrap_or(0))),
+                    );
+                }
+                LinkSetting::ClonedMac(mac) => {
+                    // The deprecated byte form cannot hold the named modes,
+                    // so only the string form is sent back.
+                    wireless.remove("cloned-mac-address");
+                    match mac {
+                        Some(mac) => {
+                            wireless.insert(
+                                "assigned-mac-address".to_string(),
+                                Variant(Box::new(mac.clone())),
+                            );
+                        }
+                        None => {
+                            wireless.remove("assigned-mac-address");
+                        }
+                    }
+                }
+            }
+            Ok(())
+        },
+    )
+}
+
+/// Brings the saved VPN named `vpn` up whenever `profile` connects, by
+/// making it the only secondary connection of the profile; `None` brings
+/// no VPN up.
+pub fn set_profile_vpn(
+    profile: &SavedProfile,
+    vpn: Option<&str>,
+) -> Result<(), Box<dyn Error>> {
+    let uuid = match vpn {
+        Some(vpn) => {
+            let dbus =
+                dbus::blocking::Connection::new_system().map_err(|error| {
+                    contextual_error("Failed to connect to D-Bus", error)
+                })?;
+            let uuid = saved_connections(&dbus)
+                .iter()
+                .filter_map(|(_, settings)| vpn_profile(settings))
+                .find_map(|(id, uuid)| (id == vpn).then_some(uuid))
+                .ok_or_else(|| format!("No saved VPN is named {vpn}"))?;
+            Some(uuid)
+        }
+        None => None,
+    };
+    update_saved_profile(
+        profile,
+        &format!("Failed to change the VPN of {}", profile.id),
+        |settings| {
+            settings
+                .entry("connection".to_string())
+                .or_default()
+                .insert(
+                    "secondaries".to_string(),
+                    Variant(Box::new(
+                        uuid.into_iter().collect::<Vec<String>>(),
+                    )),
+                );
+            Ok(())
+        },
+    )
+}
+
+/// Pins the saved profile of `network` to the access point `bssid`.
+pub fn pin_network_bssid(
+    network: &WifiNetwork,
+    bssid: &str,
+) -> Result<(), Box<dyn Error>> {
+    let dbus = dbus::blocking::Connection::new_system().map_err(|error| {
+        contextual_error("Failed to connect to D-Bus", error)
+    })?;
+    let profile = saved_wifi_connections(&dbus)
+        .into_iter()
+        .map(|(_, profile)| profile)
+        .find(|profile| profile.ssid == network.ssid)
+        .ok_or_else(|| format!("{} has no saved profile", network.ssid))?;
+    set_profile_bssid(&profile, Some(bssid))
+}
+
+/// Replaces the stored PSK of the saved `profile` in place.
+pub fn set_profile_password(
+    profile: &SavedProfile,
+    password: &str,
+) -> Result<(), Box<dyn Error>> {
+    update_saved_profile(
+        profile,
+        &format!("Failed to update the password of {}", profile.id),
+        |settings| {
+            let security = settings
+                .get_mut("802-11-wireless-security")
+                .ok_or_else(|| format!("{} is an open network", profile.id))?;
+            security.insert(
+                "psk".to_string(),
+                Variant(Box::new(password.to_string())),
+            );
+            Ok(())
+        },
+    )
+}
+
+/// Saves a copy of `profile`, with its Wi-Fi secrets when they can be
+/// read, as a new profile named `name`.
+pub fn clone_profile(
+    profile: &SavedProfile,
+    name: &str,
+) -> Result<SavedProfile, Box<dyn Error>> {
+    let dbus = dbus::blocking::Connection::new_system().map_err(|error| {
+        contextual_error("Failed to connect to D-Bus", error)
+    })?;
+    let Some((path, _)) = saved_wifi_connections(&dbus)
+        .into_iter()
+        .find(|(_, saved)| saved.uuid == profile.uuid)
+    else {
+        return Err(format!("Saved profile {} not found", profile.id).into());
+    };
+
+    let connection = dbus.with_proxy(
+        "org.freedesktop.NetworkManager",
+        &path,
+        Duration::from_secs(10),
+    );
+    let (mut settings,): (HashMap<String, PropMap>,) = connection
+        .method_call(
+            "org.freedesktop.NetworkManager.Settings.Connection",
+            "GetSettings",
+            (),
+        )
+        .map_err(|error| {
+            contextual_error("Failed to read saved connection", error)
+        })?;
+    // Without the password the copy could not connect; a profile whose
+    // secrets cannot be read is still copied.
+    if settings.contains_key("802-11-wireless-security")
+        && let Ok((secrets,)) = connection
+            .method_call::<(HashMap<String, PropMap>,), _, _, _>(
+                "org.freedesktop.NetworkManager.Settings.Connection",
+                "GetSecrets",
+                ("802-11-wireless-security",),
+            )
+    {
+        for (setting, values) in secrets {
+            settings.entry(setting).or_default().extend(values);
+        }
+    }
+
+    // NetworkManager does not fill in a missing UUID on AddConnection.
+    let uuid = std::fs::read_to_string("/proc/sys/kernel/random/uuid")
+        .map_err(|error| {
+            contextual_error("Failed to generate a profile UUID", error)
+        })?
+        .trim()
+        .to_string();
+    let connection_setting =
+        settings.entry("connection".to_string()).or_default();
+    connection_setting
+        .insert("id".to_string(), Variant(Box::new(name.to_string())));
+    connection_setting
+        .insert("uuid".to_string(), Variant(Box::new(uuid.clone())));
+    connection_setting.remove("timestamp");
+
+    let _: (dbus::Path<'static>,) = dbus
+        .with_proxy(
+            "org.freedesktop.NetworkManager",
+            "/org/freedesktop/NetworkManager/Settings",
+            Duration::from_secs(10),
+        )
+        .method_call(
+            "org.freedesktop.NetworkManager.Settings",
+            "AddConnection",
+            (settings,),
+        )
+        .map_err(|error| {
+            contextual_error(&format!("Failed to save {name}"), error)
+        })?;
+
+    Ok(SavedProfile {
+        id: name.to_string(),
+        uuid,
+        last_used: None,
+        ..profile.clone()
+    })
+}
+
+/// Writes the given saved profiles as keyfiles into `directory`, adding
+/// their Wi-Fi secrets when `include_secrets` is set. Returns how many were
+/// written.
+pub fn export_profiles(
+    profiles: &[SavedProfile],
+    directory: &Path,
+    include_secrets: bool,
+) -> Result<usize, Box<dyn Error>> {
+    let dbus = dbus::blocking::Connection::new_system().map_err(|error| {
+        contextual_error("Failed to connect to D-Bus", error)
+    })?;
+    let uuids = profiles
+        .iter()
+        .map(|profile| profile.uuid.as_str())
+        .collect::<HashSet<_>>();
+
+    let mut written = HashSet::new();
+    for (path, profile) in saved_wifi_connections(&dbus) {
+        if !uuids.contains(profile.uuid.as_str()) {
+            continue;
+        }
+
+        let connection = dbus.with_proxy(
+            "org.freedesktop.NetworkManager",
+            &path,
+            Duration::from_secs(10),
+        );
+        let (mut settings,): (HashMap<String, PropMap>,) = connection
+            .method_call(
+                "org.freedesktop.NetworkManager.Settings.Connection",
+                "GetSettings",
+                (),
+            )
+            .map_err(|error| {
+                contextual_error(
+                    &format!("Failed to read {}", profile.id),
+                    error,
+                )
+            })?;
+        if include_secrets && settings.contains_key("802-11-wireless-security")
+        {
+            let (secrets,): (HashMap<String, PropMap>,) = connection
+                .method_call(
+                    "org.freedesktop.NetworkManager.Settings.Connection",
+                    "GetSecrets",
+                    ("802-11-wireless-security",),
+                )
+                .map_err(|error| {
+                    contextual_error(
+                        &format!("Failed to read secrets of {}", profile.id),
+                        error,
+                    )
+                })?;
+            for (setting, values) in secrets {
+                settings.entry(setting).or_default().extend(values);
+            }
+        }
+
+        write_keyfile(
+            directory,
+            &profile.id,
+            &render_keyfile(&settings),
+            &mut written,
+        )
+        .map_err(|error| {
+            contextual_error(&format!("Failed to write {}", profile.id), error)
+        })?;
+    }
+
+    Ok(written.len())
+}
+
+/// Reads the stored PSK of the saved profile for `network`, if any. Asking
+/// for secrets may require polkit authorization.
+pub fn get_saved_password(
+    network: &WifiNetwork,
+) -> Result<Option<String>, Box<dyn Error>> {
+    let dbus = dbus::blocking::Connection::new_system().map_err(|error| {
+        contextual_error("Failed to connect to D-Bus", error)
+    })?;
+
+    for (path, profile) in saved_wifi_connections(&dbus) {
+        if profile.ssid != network.ssid {
+            continue;
+        }
+
+        let connection = dbus.with_proxy(
+            "org.freedesktop.NetworkManager",
+            &path,
+            Duration::from_secs(10),
+        );
+        let (secrets,): (HashMap<String, PropMap>,) = connection
+            .method_call(
+                "org.freedesktop.NetworkManager.Settings.Connection",
+                "GetSecrets",
+                ("802-11-wireless-security",),
+            )
+            .map_err(|error| {
+                contextual_error("Failed to read saved WiFi secrets", error)
+            })?;
+
+        if let Some(psk) = secrets
+            .get("802-11-wireless-security")
+            .and_then(|security| security.get("psk"))
+            .and_then(|psk| psk.0.as_str())
+        {
+            return Ok(Some(psk.to_string()));
+        }
+    }
+
+    Ok(None)
+}
+
+fn collect_wifi_networks(
+    wifi_device: &impl Wireless,
+    ssids: &HashMap<String, Vec<u8>>,
+    connected_ssid: Option<&str>,
+    saved_ssids: &HashSet<String>,
+) -> Result<Vec<WifiNetwork>, Box<dyn Error>> {
+    let access_points =
+        wifi_device.get_all_access_points().map_err(|error| {
+            contextual_error("Failed to list WiFi access points", error)
+        })?;
+
+    let associated_bssid = active_access_point_bssid(wifi_device);
+    let mut networks = Vec::new();
+
+    for ap in access_points {
+        let bssid = ap.hw_address().map_err(|error| {
+            contextual_error("Failed to read access point BSSID", error)
+        })?;
+        // Hidden access points broadcast an empty SSID and can only be
+        // joined by name; ones that vanished since the SSIDs were read are
+        // gone from the list anyway.
+        let Some(raw_ssid) = ssids
+            .get(&bssid.to_ascii_lowercase())
+            .filter(|ssid| !ssid.is_empty())
+        else {
+            continue;
+        };
+        let ssid = ssid_name(raw_ssid);
+        let raw_ssid = std::str::from_utf8(raw_ssid)
+            .is_err()
+            .then(|| raw_ssid.clone());
+
+        let flags = ap.flags().map_err(|error| {
+            contextual_error("Failed to read access point flags", error)
+        })?;
+        let wpa_flags = ap.wpa_flags().map_err(|error| {
+            contextual_error("Failed to read WPA capabilities", error)
+        })?;
+        let rsn_flags = ap.rsn_flags().map_err(|error| {
+            contextual_error("Failed to read RSN capabilities", error)
+        })?;
+
+        let security =
+            classify_access_point_security(flags, wpa_flags, rsn_flags);
+
+        let signal_strength = ap.strength().map_err(|error| {
+            contextual_error("Failed to read signal strength", error)
+        })?;
+
+        let frequency = ap.frequency().map_err(|error| {
+            contextual_error("Failed to read WiFi frequency", error)
+        })?;
+
+        let max_bitrate = ap.max_bitrate().map_err(|error| {
+            contextual_error("Failed to read access point max bitrate", error)
+        })?;
+
+        let connected = connected_ssid == Some(ssid.as_str());
+        let saved = saved_ssids.contains(&ssid);
+        let associated = associated_bssid.as_deref() == Some(bssid.as_str());
+
+        networks.push(WifiNetwork {
+            ssid,
+            raw_ssid,
+            signal_strength,
+            security,
+            frequency,
+            connected,
+            saved,
+            max_bitrate,
+            wpa_flags,
+            rsn_flags,
+            access_points: vec![WifiAccessPoint {
+                bssid,
+                frequency,
+                signal_strength,
+                associated,
+            }],
+        });
+    }
+
+    Ok(deduplicate_networks(networks))
+}
+
+/// Requests a scan and starts waiting for it to finish.
+fn request_scan(
+    wifi_device: &impl Wireless,
+) -> Result<ScanWait, Box<dyn Error>> {
+    let last_scan_before_request = wifi_device.last_scan().ok();
+
+    wifi_device.request_scan(HashMap::new()).map_err(|error| {
+        contextual_error("Failed to request WiFi scan", error)
+    })?;
+
+    Ok(ScanWait::new(last_scan_before_request, Instant::now()))
+}
+
+/// Lists the networks the first managed Wi-Fi device sees, asking it to
+/// scan first when `fresh` and otherwise reading what NetworkManager's own
+/// background scans found.
+pub(crate) fn scan_wifi_networks_blocking(
+    fresh: bool,
+) -> Result<Vec<WifiNetwork>, Box<dyn Error>> {
+    let dbus = dbus::blocking::Connection::new_system().map_err(|error| {
+        contextual_error("Failed to connect to D-Bus", error)
+    })?;
+    let nm = NetworkManager::new(&dbus);
+
+    let connected_ssid = get_connected_ssid()?;
+
+    let devices = nm.get_devices().map_err(|error| {
+        contextual_error("Failed to list NetworkManager devices", error)
+    })?;
+
+    for device in devices {
+        if let Device::WiFi(wifi_device) = device
+            && wifi_device.managed().unwrap_or(true)
+        {
+            if fresh {
+                let mut scan = request_scan(&wifi_device)?;
+                while !scan.is_done(
+                    wifi_device.last_scan().ok(),
+                    || access_point_count(&wifi_device),
+                    Instant::now(),
+                ) {
+                    std::thread::sleep(SCAN_POLL_INTERVAL);
+                }
+            }
+
+            return collect_wifi_networks(
+                &wifi_device,
+                &access_point_ssids(&dbus),
+                connected_ssid.as_deref(),
+                &saved_wifi_ssids(&dbus),
+            );
+        }
+    }
+
+    Ok(Vec::new())
+}
+
+pub async fn scan_wifi_networks() -> Result<Vec<WifiNetwork>, Box<dyn Error>> {
+    let dbus = dbus::blocking::Connection::new_system().map_err(|error| {
+        contextual_error("Failed to connect to D-Bus", error)
+    })?;
+    let nm = NetworkManager::new(&dbus);
+
+    let connected_ssid = get_connected_ssid()?;
+
+    let devices = nm.get_devices().map_err(|error| {
+        contextual_error("Failed to list NetworkManager devices", error)
+    })?;
+
+    for device in devices {
+        if let Device::WiFi(wifi_device) = device
+            && wifi_device.managed().unwrap_or(true)
+        {
+            let mut scan = request_scan(&wifi_device)?;
+            while !scan.is_done(
+                wifi_device.last_scan().ok(),
+                || access_point_count(&wifi_device),
+                Instant::now(),
+            ) {
+                sleep(SCAN_POLL_INTERVAL).await;
+            }
+
+            return collect_wifi_networks(
+                &wifi_device,
+                &access_point_ssids(&dbus),
+                connected_ssid.as_deref(),
+                &saved_wifi_ssids(&dbus),
+            );
+        }
+    }
+
+    Ok(Vec::new())
+}
+
+/// Reads the current `Strength` of every AP the adapter knows about, without
+/// requesting a new scan.
+pub fn get_access_point_signals() -> Result<Vec<WifiAccessPoint>, Box<dyn Error>>
+{
+    let dbus = dbus::blocking::Connection::new_system().map_err(|error| {
+        contextual_error("Failed to connect to D-Bus", error)
+    })?;
+    let nm = NetworkManager::new(&dbus);
+
+    let devices = nm.get_devices().map_err(|error| {
+        contextual_error("Failed to list NetworkManager devices", error)
+    })?;
+
+    for device in devices {
+        if let Device::WiFi(wifi_device) = device {
+            let access_points =
+                wifi_device.get_all_access_points().map_err(|error| {
+                    contextual_error("Failed to list WiFi access points", error)
+                })?;
+
+            let associated_bssid = active_access_point_bssid(&wifi_device);
+
+            return access_points
+                .iter()
+                .map(|ap| {
+                    let bssid = ap.hw_address().map_err(|error| {
+                        contextual_error(
+                            "Failed to read access point BSSID",
+                            error,
+                        )
+                    })?;
+                    Ok(WifiAccessPoint {
+                        associated: associated_bssid.as_deref()
+                            == Some(bssid.as_str()),
+                        bssid,
+                        frequency: ap.frequency().map_err(|error| {
+                            contextual_error(
+                                "Failed to read WiFi frequency",
+                                error,
+                            )
+                        })?,
+                        signal_strength: ap.strength().map_err(|error| {
+                            contextual_error(
+                                "Failed to read signal strength",
+                                error,
+                            )
+                        })?,
+                    })
+                })
+                .collect();
+        }
+    }
+
+    Ok(Vec::new())
+}
+
+/// The Wi-Fi device's negotiated PHY rate in kb/s, or `None` while it is
+/// not associated.
+pub fn get_link_bitrate() -> Result<Option<u32>, Box<dyn Error>> {
+    let dbus = dbus::blocking::Connection::new_system().map_err(|error| {
+        contextual_error("Failed to connect to D-Bus", error)
+    })?;
+    let nm = NetworkManager::new(&dbus);
+
+    let devices = nm.get_devices().map_err(|error| {
+        contextual_error("Failed to list NetworkManager devices", error)
+    })?;
+
+    for device in devices {
+        if let Device::WiFi(wifi_device) = device {
+            let bitrate = wifi_device.bitrate().map_err(|error| {
+                contextual_error("Failed to read WiFi bitrate", error)
+            })?;
+            return Ok((bitrate > 0).then_some(bitrate));
+        }
+    }
+
+    Ok(None)
+}
+
+/// Pings the Wi-Fi connection's IPv4 gateway and `host`.
+pub fn measure_latency(host: &str) -> Result<LatencySample, Box<dyn Error>> {
+    let gateway = get_ipv4_gateway()?
+        .map(|gateway| crate::latency::ping(&gateway))
+        .transpose()
+        .map_err(|error| contextual_error("Failed to run ping", error))?;
+    let host = crate::latency::ping(host)
+        .map_err(|error| contextual_error("Failed to run ping", error))?;
+
+    Ok(LatencySample { gateway, host })
+}
+
+fn get_ipv4_gateway() -> Result<Option<String>, Box<dyn Error>> {
+    let dbus = dbus::blocking::Connection::new_system().map_err(|error| {
+        contextual_error("Failed to connect to D-Bus", error)
+    })?;
+    let device_path = wifi_device_path(&dbus)?;
+    let device = dbus.with_proxy(
+        "org.freedesktop.NetworkManager",
+        &device_path,
+        Duration::from_secs(10),
+    );
+    let config_path: dbus::Path<'static> = device
+        .get("org.freedesktop.NetworkManager.Device", "Ip4Config")
+        .map_err(|error| {
+            contextual_error("Failed to read IPv4 configuration", error)
+        })?;
+    if &*config_path == "/" {
+        return Ok(None);
+    }
+
+    let config = dbus.with_proxy(
+        "org.freedesktop.NetworkManager",
+        &config_path,
+        Duration::from_secs(10),
+    );
+    let gateway: String = config
+        .get("org.freedesktop.NetworkManager.IP4Config", "Gateway")
+        .map_err(|error| {
+            contextual_error("Failed to read IPv4 gateway", error)
+        })?;
+    Ok((!gateway.is_empty()).then_some(gateway))
+}
+
+fn nm_wifi_proxy(
+    dbus: &dbus::blocking::Connection,
+) -> dbus::blocking::Proxy<'_, &dbus::blocking::Connection> {
+    dbus.with_proxy(
+        "org.freedesktop.NetworkManager",
+        "/org/freedesktop/NetworkManager",
+        Duration::from_secs(10),
+    )
+}
+
+fn access_point_path_for_bssid(
+    dbus: &dbus::blocking::Connection,
+    device_path: &dbus::Path<'static>,
+    bssid: &str,
+) -> Result<Option<dbus::Path<'static>>, Box<dyn Error>> {
+    let device = dbus.with_proxy(
+        "org.freedesktop.NetworkManager",
+        device_path,
+        Duration::from_secs(10),
+    );
+    let (paths,): (Vec<dbus::Path<'static>>,) = device
+        .method_call(
+            "org.freedesktop.NetworkManager.Device.Wireless",
+            "GetAllAccessPoints",
+            (),
+        )
+        .map_err(|error| {
+            contextual_error("Failed to list WiFi access points", error)
+        })?;
+
+    for path in paths {
+        let access_point = dbus.with_proxy(
+            "org.freedesktop.NetworkManager",
+            &path,
+            Duration::from_secs(10),
+        );
+        let hw_address: String = access_point
+            .get("org.freedesktop.NetworkManager.AccessPoint", "HwAddress")
+            .map_err(|error| {
+                contextual_error("Failed to read access point BSSID", error)
+            })?;
+
+        if hw_address.eq_ignore_ascii_case(bssid) {
+            return Ok(Some(path));
+        }
+    }
+
+    Ok(None)
+}
+
+/// Tears down an activation that did not come up, whichever way it ended:
+/// deactivates it, since NetworkManager may still be trying, and deletes
+/// the profile it created. Defused once the device is activated.
+struct ActivationCleanup<'a> {
+    dbus: &'a dbus::blocking::Connection,
+    profile_path: dbus::Path<'static>,
+    active_path: dbus::Path<'static>,
+    armed: bool,
+}
+
+impl ActivationCleanup<'_> {
+    fn defuse(mut self) {
+        self.armed = false;
+    }
+}
+
+impl Drop for ActivationCleanup<'_> {
+    fn drop(&mut self) {
+        if !self.armed {
+            return;
+        }
+        let _ = nm_wifi_proxy(self.dbus).method_call::<(), _, _, _>(
+            "org.freedesktop.NetworkManager",
+            "DeactivateConnection",
+            (self.active_path.clone(),),
+        );
+        let _ = self
+            .dbus
+            .with_proxy(
+                "org.freedesktop.NetworkManager",
+                &self.profile_path,
+                Duration::from_secs(10),
+            )
+            .method_call::<(), _, _, _>(
+                "org.freedesktop.NetworkManager.Settings.Connection",
+                "Delete",
+                (),
+            );
+    }
+}
+
+/// Creates the profile and waits for the device to come up with it. A
+/// failed, stalled, timed out or cancelled activation is deactivated and
+/// its new profile deleted again, so a wrong password leaves no broken
+/// duplicate behind.
+fn add_and_activate_connection(
+    proxy: &dbus::blocking::Proxy<'_, &dbus::blocking::Connection>,
+    settings: HashMap<&'static str, PropMap>,
+    device_path: &dbus::Path<'static>,
+    specific_object: dbus::Path<'static>,
+    secured: bool,
+    cancel: &CancelToken,
+    progress: &ActivationProgress,
+) -> Result<(), Box<dyn Error>> {
+    progress.reset();
+    // Subscribed before activating, so a quick failure cannot be missed.
+    let states = DeviceStateWatch::new(proxy.connection, device_path)?;
+    let cleanup =
+        start_activation(proxy, settings, device_path, specific_object)?;
+
+    wait_for_activation(&states, secured, cancel, progress)?;
+    cleanup.defuse();
+    Ok(())
+}
+
+/// Creates the profile and starts activating it, armed to be torn down
+/// again unless the caller defuses it.
+fn start_activation<'a>(
+    proxy: &dbus::blocking::Proxy<'_, &'a dbus::blocking::Connection>,
+    settings: HashMap<&'static str, PropMap>,
+    device_path: &dbus::Path<'static>,
+    specific_object: dbus::Path<'static>,
+) -> Result<ActivationCleanup<'a>, Box<dyn Error>> {
+    let (profile_path, active_path): (
+        dbus::Path<'static>,
+        dbus::Path<'static>,
+    ) = proxy
+        .method_call(
+            "org.freedesktop.NetworkManager",
+            "AddAndActivateConnection",
+            (settings, device_path.clone(), specific_object),
+        )
+        .map_err(|error| {
+            contextual_error(
+                "NetworkManager failed to activate the WiFi connection",
+                error,
+            )
+        })?;
+    Ok(ActivationCleanup {
+        dbus: proxy.connection,
+        profile_path,
+        active_path,
+        armed: true,
+    })
+}
+
+/// Moves an activation that did not come up to another access point: takes
+/// the device off the attempt, then activates the profile that attempt
+/// created on `specific_object`, so a fallback adds no profile of its own.
+fn retry_activation(
+    proxy: &dbus::blocking::Proxy<'_, &dbus::blocking::Connection>,
+    states: &DeviceStateWatch<'_>,
+    activation: &mut ActivationCleanup<'_>,
+    device_path: &dbus::Path<'static>,
+    specific_object: dbus::Path<'static>,
+) -> Result<(), Box<dyn Error>> {
+    // Already gone when the attempt failed; still trying when it stalled.
+    let _ = proxy.method_call::<(), _, _, _>(
+        "org.freedesktop.NetworkManager",
+        "DeactivateConnection",
+        (activation.active_path.clone(),),
+    );
+    wait_for_deactivation(states)?;
+
+    let (active_path,): (dbus::Path<'static>,) = proxy
+        .method_call(
+            "org.freedesktop.NetworkManager",
+            "ActivateConnection",
+            (
+                activation.profile_path.clone(),
+                device_path.clone(),
+                specific_object,
+            ),
+        )
+        .map_err(|error| {
+            contextual_error(
+                "NetworkManager failed to activate the WiFi connection",
+                error,
+            )
+        })?;
+    activation.active_path = active_path;
+    Ok(())
+}
+
+/// The `StateChanged` signals of one device, as `(state, reason)` pairs,
+/// collected from when the watch is added until it is dropped.
+struct DeviceStateWatch<'a> {
+    dbus: &'a dbus::blocking::Connection,
+    device_path: dbus::Path<'static>,
+    transitions: Arc<Mutex<Vec<(u32, u32)>>>,
+    token: dbus::channel::Token,
+}
+
+impl<'a> DeviceStateWatch<'a> {
+    fn new(
+        dbus: &'a dbus::blocking::Connection,
+        device_path: &dbus::Path<'static>,
+    ) -> Result<Self, Box<dyn Error>> {
+        let transitions = Arc::new(Mutex::new(Vec::new()));
+        let seen = Arc::clone(&transitions);
+        let token = dbus
+            .add_match(
+                MatchRule::new_signal(
+                    "org.freedesktop.NetworkManager.Device",
+                    "StateChanged",
+                )
+                .with_path(device_path.clone()),
+                move |(state, _, reason): (u32, u32, u32), _, _| {
+                    seen.lock()
+                        .expect("transitions lock")
+                        .push((state, reason));
+                    true
+                },
+            )
+            .map_err(|error| {
+                contextual_error("Failed to watch the WiFi device", error)
+            })?;
+        Ok(Self {
+            dbus,
+            device_path: device_path.clone(),
+            transitions,
+            token,
+        })
+    }
+
+    /// The state the device is in right now.
+    fn state(&self) -> Result<u32, Box<dyn Error>> {
+        self.dbus
+            .with_proxy(
+                "org.freedesktop.NetworkManager",
+                &self.device_path,
+                Duration::from_secs(10),
+            )
+            .get("org.freedesktop.NetworkManager.Device", "State")
+            .map_err(|error| {
+                contextual_error("Failed to read the WiFi device state", error)
+            })
+    }
+
+    /// The transitions received since the last call.
+    fn take(&self) -> Vec<(u32, u32)> {
+        std::mem::take(&mut *self.transitions.lock().expect("transitions lock"))
+    }
+
+    /// Waits up to `timeout` for more signals to arrive.
+    fn process(&self, timeout: Duration) -> Result<(), Box<dyn Error>> {
+        self.dbus.process(timeout).map(|_| ()).map_err(|error| {
+            contextual_error("Lost NetworkManager while activating", error)
+        })
+    }
+}
+
+impl Drop for DeviceStateWatch<'_> {
+    fn drop(&mut self) {
+        let _ = self.dbus.remove_match(self.token);
+    }
+}
+
+/// Follows `states` until the device is activated. A failed activation
+/// returns why it failed, blaming the password only when `secured`; one
+/// that stalls, times out or is cancelled returns that instead, leaving
+/// NetworkManager still trying.
+fn wait_for_activation(
+    states: &DeviceStateWatch<'_>,
+    secured: bool,
+    cancel: &CancelToken,
+    progress: &ActivationProgress,
+) -> Result<(), Box<dyn Error>> {
+    let deadline = Instant::now() + ACTIVATION_TIMEOUT;
+    let mut watchdog = ActivationWatchdog::new(Instant::now());
+    loop {
+        let now = Instant::now();
+        for (state, reason) in states.take() {
+            watchdog.observe(state, now);
+            if let Some(step) = ActivationStep::from_device_state(state) {
+                progress.reach(step);
+            }
+            match state {
+                DEVICE_STATE_ACTIVATED => return Ok(()),
+                DEVICE_STATE_FAILED => {
+                    return Err(activation_failure(reason, secured));
+                }
+                _ => {}
+            }
+        }
+        if let Some(stalled) = watchdog.stalled(now) {
+            return Err(stalled);
+        }
+        if cancel.is_cancelled() {
+            return Err(io::Error::new(
+                io::ErrorKind::Interrupted,
+                "Connection cancelled",
+            )
+            .into());
+        }
+        if now >= deadline {
+            return Err(io::Error::new(
+                io::ErrorKind::TimedOut,
+                "Timed out waiting for the connection to activate",
+            )
+            .into());
+        }
+        states.process(Duration::from_millis(250))?;
+    }
+}
+
+/// Follows `states` until the device has let go of its connection.
+fn wait_for_deactivation(
+    states: &DeviceStateWatch<'_>,
+) -> Result<(), Box<dyn Error>> {
+    // The watch is already subscribed, so nothing can slip in between.
+    if states.state()? <= DEVICE_STATE_DISCONNECTED {
+        states.take();
+        return Ok(());
+    }
+    let deadline = Instant::now() + DEACTIVATION_TIMEOUT;
+    loop {
+        if states
+            .take()
+            .iter()
+            .any(|(state, _)| *state <= DEVICE_STATE_DISCONNECTED)
+        {
+            return Ok(());
+        }
+        if Instant::now() >= deadline {
+            return Err(io::Error::new(
+                io::ErrorKind::TimedOut,
+                "Timed out waiting for the connection to go down",
+            )
+            .into());
+        }
+        states.process(Duration::from_millis(250))?;
+    }
+}
+
+/// Asks NM to re-run its connectivity check, since the cached state is stale
+/// right after a connect. Falls back to that cached state when the check
+/// itself is not permitted.
+pub fn check_connectivity() -> Result<ConnectivityReport, Box<dyn Error>> {
+    let dbus = dbus::blocking::Connection::new_system().map_err(|error| {
+        contextual_error("Failed to connect to D-Bus", error)
+    })?;
+    let proxy = nm_wifi_proxy(&dbus);
+
+    let checked: Result<(u32,), dbus::Error> = proxy.method_call(
+        "org.freedesktop.NetworkManager",
+        "CheckConnectivity",
+        (),
+    );
+    let state = match checked {
+        Ok((state,)) => state,
+        Err(_) => proxy
+            .get("org.freedesktop.NetworkManager", "Connectivity")
+            .map_err(|error| {
+                contextual_error("Failed to read connectivity state", error)
+            })?,
+    };
+
+    let connectivity = Connectivity::from_nm(state);
+    let portal_url = (connectivity == Connectivity::Portal).then(|| {
+        let check_uri: String = proxy
+            .get("org.freedesktop.NetworkManager", "ConnectivityCheckUri")
+            .unwrap_or_default();
+        crate::portal::find_portal_url(if check_uri.is_empty() {
+            crate::portal::DEFAULT_CHECK_URI
+        } else {
+            &check_uri
+        })
+    });
+
+    Ok(ConnectivityReport {
+        connectivity,
+        portal_url,
+    })
+}
+
+/// The unique bus name NetworkManager runs under, which changes whenever it
+/// restarts. Fails with `ManagerUnavailable` when there is no system bus or
+/// nobody owns NetworkManager's name.
+pub fn manager_bus_owner() -> Result<String, Box<dyn Error>> {
+    let dbus = dbus::blocking::Connection::new_system().map_err(|error| {
+        ManagerUnavailable(format!("Failed to connect to D-Bus: {error}"))
+    })?;
+    let owner: Result<(String,), dbus::Error> = dbus
+        .with_proxy(
+            "org.freedesktop.DBus",
+            "/org/freedesktop/DBus",
+            Duration::from_secs(10),
+        )
+        .method_call(
+            "org.freedesktop.DBus",
+            "GetNameOwner",
+            ("org.freedesktop.NetworkManager",),
+        );
+
+    match owner {
+        Ok((owner,)) => Ok(owner),
+        Err(error)
+            if error.name()
+                == Some("org.freedesktop.DBus.Error.NameHasNoOwner") =>
+        {
+            Err(ManagerUnavailable(
+                "NetworkManager is not running on the system bus".to_string(),
+            )
+            .into())
+        }
+        Err(error) => Err(ManagerUnavailable(format!(
+            "Failed to query D-Bus: {error}"
+        ))
+        .into()),
+    }
+}
+
+/// Starts the NetworkManager service through `pkexec`, which asks for an
+/// administrator password with the session's polkit agent.
+pub fn start_network_manager() -> Result<(), Box<dyn Error>> {
+    let output = Command::new("pkexec")
+        .args(["systemctl", "start", "NetworkManager"])
+        .stdin(Stdio::null())
+        .output()
+        .map_err(|error| contextual_error("Failed to run pkexec", error))?;
+
+    if output.status.success() {
+        return Ok(());
+    }
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    Err(match stderr.trim() {
+        "" => format!("pkexec exited with {}", output.status),
+        message => message.to_string(),
+    }
+    .into())
+}
+
+pub fn get_manager_status() -> Result<ManagerStatus, Box<dyn Error>> {
+    let dbus = dbus::blocking::Connection::new_system().map_err(|error| {
+        contextual_error("Failed to connect to D-Bus", error)
+    })?;
+    let proxy = nm_wifi_proxy(&dbus);
+
+    let state: u32 = proxy
+        .get("org.freedesktop.NetworkManager", "State")
+        .map_err(|error| {
+            contextual_error("Failed to read NetworkManager state", error)
+        })?;
+    let primary_path: dbus::Path<'static> = proxy
+        .get("org.freedesktop.NetworkManager", "PrimaryConnection")
+        .map_err(|error| {
+            contextual_error("Failed to read primary connection", error)
+        })?;
+
+    let primary_connection = if &*primary_path == "/" {
+        None
+    } else {
+        let active = dbus.with_proxy(
+            "org.freedesktop.NetworkManager",
+            &primary_path,
+            Duration::from_secs(10),
+        );
+        let id: String = active
+            .get("org.freedesktop.NetworkManager.Connection.Active", "Id")
+            .map_err(|error| {
+                contextual_error("Failed to read primary connection id", error)
+            })?;
+        let connection_type: String = active
+            .get("org.freedesktop.NetworkManager.Connection.Active", "Type")
+            .map_err(|error| {
+                contextual_error(
+                    "Failed to read primary connection type",
+                    error,
+                )
+            })?;
+        Some(PrimaryConnection {
+            id,
+            connection_type,
+        })
+    };
+
+    // Best effort: the status is still worth showing without a Wi-Fi device.
+    let wifi_state_reason = wifi_device_path(&dbus).ok().and_then(|path| {
+        let (_, reason): (u32, u32) = dbus
+            .with_proxy(
+                "org.freedesktop.NetworkManager",
+                &path,
+                Duration::from_secs(10),
+            )
+            .get("org.freedesktop.NetworkManager.Device", "StateReason")
+            .ok()?;
+        Some(DeviceStateReason(reason))
+    });
+
+    Ok(ManagerStatus {
+        state: ManagerState::from_nm(state),
+        primary_connection,
+        wifi_state_reason,
+    })
+}
+
+fn connect_via_networkmanager(
+    request: &ConnectionRequest<'_>,
+    options: &ConnectionOptions,
+    cancel: &CancelToken,
+    progress: &ActivationProgress,
+    build_settings: impl Fn() -> HashMap<&'static str, PropMap>,
+) -> Result<(), Box<dyn Error>> {
+    let network = request.network();
+    let secured = matches!(request, ConnectionRequest::Secured { .. });
+    let settings = || {
+        let mut settings = build_settings();
+        set_ssid_bytes(&mut settings, network);
+        apply_connection_options(&mut settings, options);
+        settings
+    };
+
+    let adapter = get_wifi_adapter_name_via_nm()?.ok_or_else(|| {
+        io::Error::new(
+            io::ErrorKind::NotFound,
+            "No WiFi adapter was found in NetworkManager",
+        )
+    })?;
+
+    let dbus = dbus::blocking::Connection::new_system().map_err(|error| {
+        contextual_error("Failed to connect to D-Bus", error)
+    })?;
+    let proxy = nm_wifi_proxy(&dbus);
+
+    let (device_path,): (dbus::Path<'static>,) = proxy
+        .method_call(
+            "org.freedesktop.NetworkManager",
+            "GetDeviceByIpIface",
+            (adapter.as_str(),),
+        )
+        .map_err(|error| {
+            contextual_error(
+                "Failed to find WiFi device in NetworkManager",
+                error,
+            )
+        })?;
+
+    let attempts = connect_attempt_order(network);
+    if attempts.len() <= 1 {
+        return add_and_activate_connection(
+            &proxy,
+            settings(),
+            &device_path,
+            dbus::Path::from("/"),
+            secured,
+            cancel,
+            progress,
+        );
+    }
+
+    // Subscribed before activating, so a quick failure cannot be missed.
+    let states = DeviceStateWatch::new(&dbus, &device_path)?;
+    let mut activation: Option<ActivationCleanup<'_>> = None;
+    let mut last_error = None;
+    for access_point in &attempts {
+        let specific_object = access_point_path_for_bssid(
+            &dbus,
+            &device_path,
+            &access_point.bssid,
+        )?
+        .unwrap_or_else(|| dbus::Path::from("/"));
+
+        progress.reset();
+        let started = match activation.as_mut() {
+            Some(previous) => retry_activation(
+                &proxy,
+                &states,
+                previous,
+                &device_path,
+                specific_object,
+            ),
+            None => start_activation(
+                &proxy,
+                settings(),
+                &device_path,
+                specific_object,
+            )
+            .map(|started| activation = Some(started)),
+        };
+        match started.and_then(|()| {
+            wait_for_activation(&states, secured, cancel, progress)
+        }) {
+            Ok(()) => {
+                if let Some(activation) = activation {
+                    activation.defuse();
+                }
+                return Ok(());
+            }
+            Err(error) if !tries_next_access_point(error.as_ref()) => {
+                return Err(error);
+            }
+            Err(error) => last_error = Some(error),
+        }
+    }
+
+    Err(ActivationFailed {
+        kind: last_error
+            .as_deref()
+            .map_or(FailureKind::Other, FailureKind::of),
+        message: format!(
+            "Activation failed on {} access points: {}",
+            attempts.len(),
+            last_error
+                .map(|error| error.to_string())
+                .unwrap_or_default()
+        ),
+    }
+    .into())
+}
+
+pub fn connect_to_network(
+    request: ConnectionRequest<'_>,
+) -> Result<(), Box<dyn Error>> {
+    connect_with_options(
+        request,
+        &ConnectionOptions::default(),
+        &CancelToken::default(),
+        &ActivationProgress::default(),
+    )
+}
+
+/// Connects with the given profile options, backing out of the activation
+/// once `cancel` is set and recording its steps in `progress`.
+pub fn connect_with_options(
+    request: ConnectionRequest<'_>,
+    options: &ConnectionOptions,
+    cancel: &CancelToken,
+    progress: &ActivationProgress,
+) -> Result<(), Box<dyn Error>> {
+    let network = request.network();
+
+    match request {
+        ConnectionRequest::Open { .. } => {
+            if network.security != WifiSecurity::Open {
+                return Err("Password required for secured network".into());
+            }
+            connect_via_networkmanager(&request, options, cancel, progress, || {
+                open_network_connection_settings(&network.ssid)
+            })
+        }
+        ConnectionRequest::Guest { .. } => {
+            if network.security != WifiSecurity::Open {
+                return Err("Guest connections require an open network".into());
+            }
+            connect_via_networkmanager(&request, options, cancel, progress, || {
+                guest_network_connection_settings(&network.ssid)
+            })
+        }
+        ConnectionRequest::Secured { passphrase, .. } => {
+            match classify_security(network, Some(passphrase)) {
+                SecurityKind::WpaPsk => connect_via_networkmanager(&request, options, cancel, progress, || {
+                    secured_network_connection_settings(&network.ssid, passphrase, "wpa-psk")
+                }),
+                SecurityKind::WpaSae => connect_via_networkmanager(&request, options, cancel, progress, || {
+                    secured_network_connection_settings(&network.ssid, passphrase, "sae")
+                }),
+                SecurityKind::Open => {
+                    Err("Open networks should not be activated with a password request".into())
+                }
+                SecurityKind::Unsupported => Err(format!(
+                    "Unsupported network security for NetworkManager activation: {}",
+                    network.security.display_name()
+                )
+                .into()),
+            }
+        }
+    }
+}
+
+fn disconnect_via_networkmanager(
+    network: &WifiNetwork,
+) -> Result<bool, Box<dyn Error>> {
+    let dbus = dbus::blocking::Connection::new_system().map_err(|error| {
+        contextual_error("Failed to connect to D-Bus", error)
+    })?;
+    let nm = NetworkManager::new(&dbus);
+
+    let ssids = access_point_ssids(&dbus);
+
+    for device in nm.get_devices().map_err(|error| {
+        contextual_error("Failed to list NetworkManager devices", error)
+    })? {
+        if let Device::WiFi(wifi_device) = device {
+            let active_ssid = active_access_point_ssid(&wifi_device, &ssids);
+
+            if should_disconnect_device(active_ssid.as_deref(), &network.ssid) {
+                wifi_device.disconnect().map_err(|error| {
+                    contextual_error(
+                        "Failed to disconnect device via NetworkManager",
+                        error,
+                    )
+                })?;
+                return Ok(true);
             }
         }
     }
@@ -520,6 +2459,366 @@ fn disconnect_via_networkmanager(
     Ok(false)
 }
 
+/// Deactivates the active connection for `network`, waits for it to go
+/// down and activates the same saved profile again, on the access point
+/// `bssid` when given. Returns the device once it is activated again; an
+/// activation that does not come up is deactivated, keeping the profile.
+fn reactivate_connection(
+    dbus: &dbus::blocking::Connection,
+    network: &WifiNetwork,
+    bssid: Option<&str>,
+    cancel: &CancelToken,
+) -> Result<dbus::Path<'static>, Box<dyn Error>> {
+    const ACTIVE: &str = "org.freedesktop.NetworkManager.Connection.Active";
+
+    let proxy = nm_wifi_proxy(dbus);
+    let active_paths: Vec<dbus::Path<'static>> = proxy
+        .get("org.freedesktop.NetworkManager", "ActiveConnections")
+        .map_err(|error| {
+            contextual_error("Failed to list active connections", error)
+        })?;
+    let profiles = saved_wifi_connections(dbus);
+
+    for active_path in active_paths {
+        let active = dbus.with_proxy(
+            "org.freedesktop.NetworkManager",
+            &active_path,
+            Duration::from_secs(10),
+        );
+        let Ok(connection) =
+            active.get::<dbus::Path<'static>>(ACTIVE, "Connection")
+        else {
+            continue;
+        };
+        if !profiles.iter().any(|(path, profile)| {
+            *path == connection && profile.ssid == network.ssid
+        }) {
+            continue;
+        }
+
+        let devices: Vec<dbus::Path<'static>> =
+            active.get(ACTIVE, "Devices").map_err(|error| {
+                contextual_error("Failed to read connection devices", error)
+            })?;
+        let device = devices
+            .into_iter()
+            .next()
+            .unwrap_or_else(|| dbus::Path::from("/"));
+        let access_point = match bssid {
+            Some(bssid) => access_point_path_for_bssid(dbus, &device, bssid)?
+                .ok_or_else(|| {
+                format!("{bssid} is no longer visible to NetworkManager")
+            })?,
+            None => dbus::Path::from("/"),
+        };
+
+        let states = DeviceStateWatch::new(dbus, &device)?;
+        proxy
+            .method_call::<(), _, _, _>(
+                "org.freedesktop.NetworkManager",
+                "DeactivateConnection",
+                (active_path,),
+            )
+            .map_err(|error| {
+                contextual_error("Failed to deactivate the connection", error)
+            })?;
+        wait_for_deactivation(&states)?;
+        let (reactivated,): (dbus::Path<'static>,) = proxy
+            .method_call(
+                "org.freedesktop.NetworkManager",
+                "ActivateConnection",
+                (connection, device.clone(), access_point),
+            )
+            .map_err(|error| {
+                contextual_error("Failed to reactivate the connection", error)
+            })?;
+        // The saved profile sends its stored password, if it has one.
+        let secured = network.security != WifiSecurity::Open;
+        if let Err(error) = wait_for_activation(
+            &states,
+            secured,
+            cancel,
+            &ActivationProgress::default(),
+        ) {
+            let _ = proxy.method_call::<(), _, _, _>(
+                "org.freedesktop.NetworkManager",
+                "DeactivateConnection",
+                (reactivated,),
+            );
+            return Err(error);
+        }
+        return Ok(device);
+    }
+
+    Err(format!(
+        "NetworkManager has no active connection for {}",
+        network.ssid
+    )
+    .into())
+}
+
+/// Bounces the active connection for `network`: deactivates it, then
+/// activates the same saved profile again on the same device and waits for
+/// it to come up.
+pub fn reconnect_network(
+    network: &WifiNetwork,
+    cancel: &CancelToken,
+) -> Result<(), Box<dyn Error>> {
+    let dbus = dbus::blocking::Connection::new_system().map_err(|error| {
+        contextual_error("Failed to connect to D-Bus", error)
+    })?;
+    reactivate_connection(&dbus, network, None, cancel).map(|_| ())
+}
+
+/// Moves the active connection for `network` to the access point `bssid`
+/// of the same SSID.
+pub fn steer_to_access_point(
+    network: &WifiNetwork,
+    bssid: &str,
+) -> Result<(), Box<dyn Error>> {
+    let dbus = dbus::blocking::Connection::new_system().map_err(|error| {
+        contextual_error("Failed to connect to D-Bus", error)
+    })?;
+    reactivate_connection(&dbus, network, Some(bssid), &CancelToken::default())
+        .map(|_| ())
+}
+
+fn device_dhcp_lease(
+    dbus: &dbus::blocking::Connection,
+    device_path: &dbus::Path<'static>,
+) -> Option<DhcpLease> {
+    let device = dbus.with_proxy(
+        "org.freedesktop.NetworkManager",
+        device_path,
+        Duration::from_secs(10),
+    );
+    let config_path: dbus::Path<'static> = device
+        .get("org.freedesktop.NetworkManager.Device", "Dhcp4Config")
+        .ok()?;
+    if &*config_path == "/" {
+        return None;
+    }
+
+    let config = dbus.with_proxy(
+        "org.freedesktop.NetworkManager",
+        &config_path,
+        Duration::from_secs(10),
+    );
+    let options: PropMap = config
+        .get("org.freedesktop.NetworkManager.DHCP4Config", "Options")
+        .ok()?;
+    let options = options
+        .iter()
+        .filter_map(|(key, value)| {
+            value
+                .0
+                .as_str()
+                .map(|value| (key.clone(), value.to_string()))
+        })
+        .collect();
+    DhcpLease::from_options(&options)
+}
+
+/// Forces a fresh DHCP exchange by reactivating the connection for
+/// `network`, then reads the lease the device came back up with.
+pub fn renew_dhcp_lease(
+    network: &WifiNetwork,
+    cancel: &CancelToken,
+) -> Result<DhcpLease, Box<dyn Error>> {
+    let dbus = dbus::blocking::Connection::new_system().map_err(|error| {
+        contextual_error("Failed to connect to D-Bus", error)
+    })?;
+    let device_path = reactivate_connection(&dbus, network, None, cancel)?;
+
+    // The device only reports activated once its IP configuration is done,
+    // so the Dhcp4Config it has now is the renewed one.
+    device_dhcp_lease(&dbus, &device_path).ok_or_else(|| {
+        format!("{} came back without a DHCP lease", network.ssid).into()
+    })
+}
+
+fn wifi_device_path(
+    dbus: &dbus::blocking::Connection,
+) -> Result<dbus::Path<'static>, Box<dyn Error>> {
+    let adapter = get_wifi_adapter_name_via_nm()?.ok_or_else(|| {
+        io::Error::new(
+            io::ErrorKind::NotFound,
+            "No WiFi adapter was found in NetworkManager",
+        )
+    })?;
+    let (device_path,): (dbus::Path<'static>,) = nm_wifi_proxy(dbus)
+        .method_call(
+            "org.freedesktop.NetworkManager",
+            "GetDeviceByIpIface",
+            (adapter.as_str(),),
+        )
+        .map_err(|error| {
+            contextual_error(
+                "Failed to find WiFi device in NetworkManager",
+                error,
+            )
+        })?;
+    Ok(device_path)
+}
+
+/// `connection.autoconnect`, which defaults to on when the key is absent.
+fn profile_autoconnect(
+    dbus: &dbus::blocking::Connection,
+    path: &dbus::Path<'static>,
+) -> Result<bool, Box<dyn Error>> {
+    let connection = dbus.with_proxy(
+        "org.freedesktop.NetworkManager",
+        path,
+        Duration::from_secs(10),
+    );
+    let (settings,): (HashMap<String, PropMap>,) = connection
+        .method_call(
+            "org.freedesktop.NetworkManager.Settings.Connection",
+            "GetSettings",
+            (),
+        )
+        .map_err(|error| {
+            contextual_error("Failed to read saved connection", error)
+        })?;
+
+    Ok(settings
+        .get("connection")
+        .and_then(|connection| connection.get("autoconnect"))
+        .and_then(|value| value.0.as_u64())
+        .is_none_or(|enabled| enabled != 0))
+}
+
+/// Proxy and permission settings of the saved profile for `network`, if
+/// there is one.
+pub fn saved_profile_settings(
+    network: &WifiNetwork,
+) -> Result<Option<ProfileSettings>, Box<dyn Error>> {
+    let dbus = dbus::blocking::Connection::new_system().map_err(|error| {
+        contextual_error("Failed to connect to D-Bus", error)
+    })?;
+    let Some((path, _)) = saved_wifi_connections(&dbus)
+        .into_iter()
+        .find(|(_, profile)| profile.ssid == network.ssid)
+    else {
+        return Ok(None);
+    };
+
+    let (settings,): (HashMap<String, PropMap>,) = dbus
+        .with_proxy(
+            "org.freedesktop.NetworkManager",
+            &path,
+            Duration::from_secs(10),
+        )
+        .method_call(
+            "org.freedesktop.NetworkManager.Settings.Connection",
+            "GetSettings",
+            (),
+        )
+        .map_err(|error| {
+            contextual_error("Failed to read saved connection", error)
+        })?;
+
+    Ok(Some(ProfileSettings {
+        proxy: proxy_config(&settings),
+        users: profile_users(&settings),
+    }))
+}
+
+/// Why NetworkManager is not autoconnecting to the saved `network`, if it
+/// is blocked at all.
+pub fn autoconnect_block(
+    network: &WifiNetwork,
+) -> Result<Option<AutoconnectBlock>, Box<dyn Error>> {
+    const DEVICE: &str = "org.freedesktop.NetworkManager.Device";
+
+    let dbus = dbus::blocking::Connection::new_system().map_err(|error| {
+        contextual_error("Failed to connect to D-Bus", error)
+    })?;
+    let wireless_enabled: bool = nm_wifi_proxy(&dbus)
+        .get("org.freedesktop.NetworkManager", "WirelessEnabled")
+        .map_err(|error| {
+            contextual_error("Failed to read the Wi-Fi radio state", error)
+        })?;
+    let device_path = wifi_device_path(&dbus)?;
+    let device = dbus.with_proxy(
+        "org.freedesktop.NetworkManager",
+        &device_path,
+        Duration::from_secs(10),
+    );
+    let read = |property: &str| -> Result<bool, Box<dyn Error>> {
+        device.get(DEVICE, property).map_err(|error| {
+            contextual_error(
+                &format!("Failed to read device {property}"),
+                error,
+            )
+        })
+    };
+    let device_managed = read("Managed")?;
+    let device_autoconnect = read("Autoconnect")?;
+    let state: u32 = device.get(DEVICE, "State").map_err(|error| {
+        contextual_error("Failed to read device state", error)
+    })?;
+
+    let mut any_profile_autoconnects = false;
+    for (path, profile) in saved_wifi_connections(&dbus) {
+        if profile.ssid == network.ssid {
+            any_profile_autoconnects |= profile_autoconnect(&dbus, &path)?;
+        }
+    }
+
+    Ok(AutoconnectFacts {
+        wireless_enabled,
+        device_managed,
+        device_autoconnect,
+        profile_autoconnect: any_profile_autoconnects,
+        device_idle: state == DEVICE_STATE_DISCONNECTED,
+    }
+    .block())
+}
+
+/// Turns device autoconnect back on and re-saves the profiles for `network`
+/// unchanged, which resets NM's autoconnect retry counter.
+pub fn clear_autoconnect_block(
+    network: &WifiNetwork,
+) -> Result<(), Box<dyn Error>> {
+    let dbus = dbus::blocking::Connection::new_system().map_err(|error| {
+        contextual_error("Failed to connect to D-Bus", error)
+    })?;
+    let device_path = wifi_device_path(&dbus)?;
+    dbus.with_proxy(
+        "org.freedesktop.NetworkManager",
+        &device_path,
+        Duration::from_secs(10),
+    )
+    .set("org.freedesktop.NetworkManager.Device", "Autoconnect", true)
+    .map_err(|error| {
+        contextual_error("Failed to enable device autoconnect", error)
+    })?;
+
+    for (path, profile) in saved_wifi_connections(&dbus) {
+        if profile.ssid != network.ssid {
+            continue;
+        }
+        let connection = dbus.with_proxy(
+            "org.freedesktop.NetworkManager",
+            &path,
+            Duration::from_secs(10),
+        );
+        // Empty settings keep the profile as it is.
+        let _: (PropMap,) = connection
+            .method_call(
+                "org.freedesktop.NetworkManager.Settings.Connection",
+                "Update2",
+                (HashMap::<String, PropMap>::new(), 0u32, PropMap::new()),
+            )
+            .map_err(|error| {
+                contextual_error("Failed to reset autoconnect retries", error)
+            })?;
+    }
+
+    Ok(())
+}
+
 pub fn disconnect_from_network(
     network: &WifiNetwork,
 ) -> Result<(), Box<dyn Error>> {
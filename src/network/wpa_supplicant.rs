@@ -0,0 +1,940 @@
+//! Wi-Fi on systems without NetworkManager, driving wpa_supplicant over its
+//! control socket (the `wpa_ctrl` protocol `wpa_cli` speaks) and running a
+//! DHCP client once associated.
+//!
+//! Scanning, connecting and disconnecting work; saved profiles, devices and
+//! the other NetworkManager features report that they need it. The DHCP
+//! client is the command in `NM_WIFI_DHCP_HOOK`, run with the interface as
+//! its last argument, or else the first of `dhcpcd`, `udhcpc` and `dhclient`
+//! that is installed.
+
+use std::{
+    collections::{HashMap, HashSet},
+    env,
+    error::Error,
+    fs,
+    io,
+    os::unix::{fs::FileTypeExt, net::UnixDatagram},
+    path::{Path, PathBuf},
+    process::{Command, ExitStatus, Stdio},
+    sync::atomic::{AtomicU32, Ordering},
+    time::{Duration, Instant},
+};
+
+use super::{
+    ActivationFailed,
+    ActivationProgress,
+    ActivationStep,
+    CancelToken,
+    ConnectionRequest,
+    FailureKind,
+    WrongPassword,
+    deduplicate_networks,
+    networkmanager::{
+        AP_FLAGS_PRIVACY,
+        AP_SEC_KEY_MGMT_8021X,
+        AP_SEC_KEY_MGMT_OWE,
+        AP_SEC_KEY_MGMT_PSK,
+        AP_SEC_KEY_MGMT_SAE,
+        classify_access_point_security,
+    },
+    restrict_to_band,
+};
+use crate::wifi::{
+    WifiAccessPoint,
+    WifiBand,
+    WifiNetwork,
+    WifiSecurity,
+    ssid_name,
+};
+
+pub const DHCP_HOOK_VAR: &str = "NM_WIFI_DHCP_HOOK";
+
+const CONTROL_DIRS: [&str; 2] =
+    ["/run/wpa_supplicant", "/var/run/wpa_supplicant"];
+const REPLY_TIMEOUT: Duration = Duration::from_secs(5);
+const SCAN_TIMEOUT: Duration = Duration::from_secs(15);
+const ASSOCIATION_TIMEOUT: Duration = Duration::from_secs(30);
+/// How often a wait for an event checks whether it was cancelled.
+const EVENT_POLL: Duration = Duration::from_millis(250);
+/// DHCP clients tried in order when no hook is set, with the arguments that
+/// make them get one lease and return.
+const DHCP_CLIENTS: [(&str, &[&str]); 3] = [
+    ("dhcpcd", &["-1", "-w"]),
+    ("udhcpc", &["-n", "-q", "-i"]),
+    ("dhclient", &["-1"]),
+];
+
+const PAIR_TKIP: u32 = 0x4;
+const PAIR_CCMP: u32 = 0x8;
+const GROUP_TKIP: u32 = 0x40;
+const GROUP_CCMP: u32 = 0x80;
+
+/// A connection to the control socket of one interface. The client end is a
+/// socket file of its own, removed again on drop.
+struct Control {
+    socket: UnixDatagram,
+    local: PathBuf,
+}
+
+impl Control {
+    fn open(interface: &str) -> Result<Self, Box<dyn Error>> {
+        let remote = control_dir()
+            .ok_or("wpa_supplicant is not running")?
+            .join(interface);
+        Self::open_at(&remote, interface)
+    }
+
+    /// Connects to the control socket at `remote`, the one of `interface`.
+    fn open_at(remote: &Path, interface: &str) -> Result<Self, Box<dyn Error>> {
+        static NEXT_SOCKET: AtomicU32 = AtomicU32::new(0);
+
+        let local = env::temp_dir().join(format!(
+            "nm-wifi-wpa-{}-{}",
+            std::process::id(),
+            NEXT_SOCKET.fetch_add(1, Ordering::Relaxed)
+        ));
+        let _ = fs::remove_file(&local);
+        let control = Self {
+            socket: UnixDatagram::bind(&local)?,
+            local,
+        };
+        control.socket.connect(remote).map_err(|error| {
+            format!("Failed to reach wpa_supplicant on {interface}: {error}")
+        })?;
+        control.socket.set_read_timeout(Some(REPLY_TIMEOUT))?;
+        Ok(control)
+    }
+
+    /// Sends `command` and returns its reply, skipping the events an
+    /// attached monitor receives in between.
+    fn request(&self, command: &str) -> io::Result<String> {
+        self.socket.send(command.as_bytes())?;
+        self.socket.set_read_timeout(Some(REPLY_TIMEOUT))?;
+        loop {
+            let message = self.receive()?;
+            if !message.starts_with('<') {
+                return Ok(message);
+            }
+        }
+    }
+
+    /// Sends a command that wpa_supplicant answers with `OK`. Only the verb
+    /// goes into the error, so passwords never do.
+    fn command(&self, command: &str) -> Result<(), Box<dyn Error>> {
+        let reply = self.request(command)?;
+        if reply.trim() == "OK" {
+            return Ok(());
+        }
+        let verb = command.split_whitespace().next().unwrap_or(command);
+        Err(format!("wpa_supplicant refused {verb}: {}", reply.trim()).into())
+    }
+
+    /// Waits up to `timeout` for an event containing one of `events` and
+    /// returns it; `None` when it timed out or `cancel` was set.
+    fn wait_event(
+        &self,
+        events: &[&str],
+        timeout: Duration,
+        cancel: Option<&CancelToken>,
+    ) -> io::Result<Option<String>> {
+        let deadline = Instant::now() + timeout;
+        while let Some(remaining) =
+            deadline.checked_duration_since(Instant::now())
+        {
+            if cancel.is_some_and(CancelToken::is_cancelled) {
+                return Ok(None);
+            }
+            self.socket.set_read_timeout(Some(
+                remaining.min(EVENT_POLL).max(Duration::from_millis(1)),
+            ))?;
+            match self.receive() {
+                Ok(message) => {
+                    if events.iter().any(|event| message.contains(event)) {
+                        return Ok(Some(message));
+                    }
+                }
+                Err(error)
+                    if matches!(
+                        error.kind(),
+                        io::ErrorKind::WouldBlock | io::ErrorKind::TimedOut
+                    ) => {}
+                Err(error) => return Err(error),
+            }
+        }
+        Ok(None)
+    }
+
+    fn receive(&self) -> io::Result<String> {
+        // Scan results of a crowded place exceed the 4 KiB `wpa_cli` reads.
+        let mut buffer = vec![0; 64 * 1024];
+        let length = self.socket.recv(&mut buffer)?;
+        Ok(String::from_utf8_lossy(&buffer[..length]).into_owned())
+    }
+}
+
+impl Drop for Control {
+    fn drop(&mut self) {
+        let _ = fs::remove_file(&self.local);
+    }
+}
+
+fn control_dir() -> Option<&'static Path> {
+    CONTROL_DIRS
+        .into_iter()
+        .map(Path::new)
+        .find(|dir| dir.is_dir())
+}
+
+/// The first interface wpa_supplicant has a control socket for, leaving
+/// out P2P devices; `None` when wpa_supplicant is not running.
+pub fn control_interface() -> Option<String> {
+    let mut interfaces = fs::read_dir(control_dir()?)
+        .ok()?
+        .filter_map(Result::ok)
+        .filter(|entry| entry.file_type().is_ok_and(|kind| kind.is_socket()))
+        .filter_map(|entry| entry.file_name().into_string().ok())
+        .filter(|name| !name.starts_with("p2p-dev-"))
+        .collect::<Vec<_>>();
+    interfaces.sort();
+    interfaces.into_iter().next()
+}
+
+fn status(
+    control: &Control,
+) -> Result<HashMap<String, String>, Box<dyn Error>> {
+    Ok(parse_key_values(&control.request("STATUS")?))
+}
+
+pub fn get_connected_ssid(
+    interface: &str,
+) -> Result<Option<String>, Box<dyn Error>> {
+    let status = status(&Control::open(interface)?)?;
+    Ok(status
+        .get("ssid")
+        .filter(|_| {
+            status.get("wpa_state").map(String::as_str) == Some("COMPLETED")
+        })
+        .map(|ssid| ssid_name(&decode_ssid(ssid))))
+}
+
+/// A network block of wpa_supplicant's configuration: its id and SSID.
+type ConfiguredNetwork = (String, Vec<u8>);
+
+/// SSIDs of the networks wpa_supplicant has a configuration for.
+fn configured_networks(
+    control: &Control,
+) -> Result<Vec<ConfiguredNetwork>, Box<dyn Error>> {
+    Ok(control
+        .request("LIST_NETWORKS")?
+        .lines()
+        .skip(1)
+        .filter_map(|line| {
+            let mut fields = line.split('\t');
+            let id = fields.next()?.to_string();
+            Some((id, decode_ssid(fields.next()?)))
+        })
+        .collect())
+}
+
+fn scan_results(control: &Control) -> Result<Vec<WifiNetwork>, Box<dyn Error>> {
+    let status = status(control)?;
+    let associated = status
+        .get("bssid")
+        .filter(|_| {
+            status.get("wpa_state").map(String::as_str) == Some("COMPLETED")
+        })
+        .map(|bssid| bssid.to_ascii_uppercase());
+    let saved = configured_networks(control)?
+        .into_iter()
+        .map(|(_, ssid)| ssid)
+        .collect::<HashSet<_>>();
+    Ok(parse_scan_results(
+        &control.request("SCAN_RESULTS")?,
+        associated.as_deref(),
+        &saved,
+    ))
+}
+
+/// Lists the networks in range, asking for a fresh scan first when `fresh`,
+/// and only those on `band` when one is given.
+pub fn scan_networks(
+    interface: &str,
+    fresh: bool,
+    band: Option<WifiBand>,
+) -> Result<Vec<WifiNetwork>, Box<dyn Error>> {
+    let control = Control::open(interface)?;
+    if fresh {
+        control.command("ATTACH")?;
+        // Scanning one band only also shortens the scan.
+        let command = match band {
+            Some(band) => format!("SCAN freq={}", band_frequencies(band)),
+            None => "SCAN".to_string(),
+        };
+        // A scan already under way answers FAIL-BUSY; its results will do.
+        let started = control.request(&command)?;
+        if started.trim() == "OK" || started.trim() == "FAIL-BUSY" {
+            control.wait_event(
+                &["CTRL-EVENT-SCAN-RESULTS", "CTRL-EVENT-SCAN-FAILED"],
+                SCAN_TIMEOUT,
+                None,
+            )?;
+        }
+        let _ = control.command("DETACH");
+    }
+    Ok(restrict_to_band(scan_results(&control)?, band))
+}
+
+/// The `freq=` range list of `SCAN` covering `band`, in MHz.
+fn band_frequencies(band: WifiBand) -> &'static str {
+    match band {
+        WifiBand::TwoPointFourGhz => "2400-2500",
+        WifiBand::FiveGhz => "5000-5924",
+        WifiBand::SixGhz => "5925-7125",
+    }
+}
+
+/// Access points of the last scan, without starting a new one.
+pub fn get_access_point_signals(
+    interface: &str,
+) -> Result<Vec<WifiAccessPoint>, Box<dyn Error>> {
+    Ok(scan_results(&Control::open(interface)?)?
+        .into_iter()
+        .flat_map(|network| network.access_points)
+        .collect())
+}
+
+/// Associates with the network of `request`, reusing wpa_supplicant's
+/// configuration for it when no password was typed, and runs DHCP. The
+/// steps are recorded in `progress` as NetworkManager would name them.
+pub fn connect(
+    interface: &str,
+    request: ConnectionRequest<'_>,
+    cancel: &CancelToken,
+    progress: &ActivationProgress,
+) -> Result<(), Box<dyn Error>> {
+    progress.reset();
+    progress.reach(ActivationStep::Prepare);
+    associate(&Control::open(interface)?, request, cancel, progress)?;
+    progress.reach(ActivationStep::IpConfig);
+    run_dhcp(interface).map_err(|error| -> Box<dyn Error> {
+        Box::new(ActivationFailed {
+            kind: FailureKind::Dhcp,
+            message: error.to_string(),
+        })
+    })?;
+    progress.reach(ActivationStep::Activated);
+    Ok(())
+}
+
+/// Selects the network of `request` and waits for the association.
+/// Selecting disables every other network, so they are enabled again
+/// however the attempt ends, before anything is saved.
+fn associate(
+    control: &Control,
+    request: ConnectionRequest<'_>,
+    cancel: &CancelToken,
+    progress: &ActivationProgress,
+) -> Result<(), Box<dyn Error>> {
+    let network = request.network();
+    let configured = configured_networks(control)?
+        .into_iter()
+        .find(|(_, ssid)| ssid == network.ssid_bytes())
+        .map(|(id, _)| id);
+
+    let (id, added) = match (&request, configured) {
+        (ConnectionRequest::Secured { .. }, _) | (_, None) => {
+            let id = control.request("ADD_NETWORK")?.trim().to_string();
+            if id.parse::<u32>().is_err() {
+                return Err(format!(
+                    "wpa_supplicant refused ADD_NETWORK: {id}"
+                )
+                .into());
+            }
+            if let Err(error) = configure_network(control, &id, &request) {
+                let _ = control.command(&format!("REMOVE_NETWORK {id}"));
+                return Err(error);
+            }
+            (id, true)
+        }
+        (_, Some(id)) => (id, false),
+    };
+    let forget = || {
+        if added {
+            let _ = control.command(&format!("REMOVE_NETWORK {id}"));
+        }
+    };
+
+    control.command("ATTACH")?;
+    control.command(&format!("SELECT_NETWORK {id}"))?;
+    progress.reach(ActivationStep::Config);
+    let event = control.wait_event(
+        &[
+            "CTRL-EVENT-CONNECTED",
+            "reason=WRONG_KEY",
+            "CTRL-EVENT-NETWORK-NOT-FOUND",
+        ],
+        ASSOCIATION_TIMEOUT,
+        Some(cancel),
+    );
+    let _ = control.command("DETACH");
+    let _ = control.command("ENABLE_NETWORK all");
+    match event? {
+        Some(event) if event.contains("CTRL-EVENT-CONNECTED") => {
+            if added {
+                // Fails when the configuration is read-only, which only
+                // means the network is forgotten on restart.
+                let _ = control.command("SAVE_CONFIG");
+            }
+            Ok(())
+        }
+        Some(event) if event.contains("WRONG_KEY") => {
+            let _ = control.command("DISCONNECT");
+            forget();
+            Err(WrongPassword(format!(
+                "{} rejected the password",
+                network.ssid
+            ))
+            .into())
+        }
+        Some(_) => {
+            forget();
+            Err(ActivationFailed {
+                kind: FailureKind::ApNotFound,
+                message: format!("{} is out of range", network.ssid),
+            }
+            .into())
+        }
+        None => {
+            let _ = control.command("DISCONNECT");
+            forget();
+            if cancel.is_cancelled() {
+                Err(io::Error::new(
+                    io::ErrorKind::Interrupted,
+                    "Connection cancelled",
+                )
+                .into())
+            } else {
+                Err(io::Error::new(
+                    io::ErrorKind::TimedOut,
+                    "Timed out waiting for wpa_supplicant to associate",
+                )
+                .into())
+            }
+        }
+    }
+}
+
+/// Sets the SSID and credentials of the freshly added network `id`.
+fn configure_network(
+    control: &Control,
+    id: &str,
+    request: &ConnectionRequest<'_>,
+) -> Result<(), Box<dyn Error>> {
+    let network = request.network();
+    let hex_ssid = network
+        .ssid_bytes()
+        .iter()
+        .map(|byte| format!("{byte:02x}"))
+        .collect::<String>();
+    control.command(&format!("SET_NETWORK {id} ssid {hex_ssid}"))?;
+    let settings: Vec<(&str, String)> = match request {
+        ConnectionRequest::Open { .. } | ConnectionRequest::Guest { .. } => {
+            vec![("key_mgmt", "NONE".to_string())]
+        }
+        ConnectionRequest::Secured { passphrase, .. } => {
+            match network.security {
+                WifiSecurity::WpaPsk => vec![
+                    ("key_mgmt", "WPA-PSK".to_string()),
+                    ("psk", psk_value(passphrase)),
+                ],
+                WifiSecurity::WpaSae => vec![
+                    ("key_mgmt", "SAE".to_string()),
+                    ("sae_password", format!("\"{passphrase}\"")),
+                    ("ieee80211w", "2".to_string()),
+                ],
+                _ => {
+                    return Err(format!(
+                        "{} needs NetworkManager: only open and WPA/WPA3 \
+                         Personal networks can be joined with wpa_supplicant",
+                        network.security.display_name()
+                    )
+                    .into());
+                }
+            }
+        }
+    };
+    for (key, value) in settings {
+        control.command(&format!("SET_NETWORK {id} {key} {value}"))?;
+    }
+    Ok(())
+}
+
+/// A passphrase goes to wpa_supplicant quoted; a raw 64-digit hex key goes
+/// as is, or it would be taken for a (too long) passphrase.
+fn psk_value(passphrase: &str) -> String {
+    if passphrase.len() == 64
+        && passphrase.chars().all(|c| c.is_ascii_hexdigit())
+    {
+        passphrase.to_string()
+    } else {
+        format!("\"{passphrase}\"")
+    }
+}
+
+pub fn disconnect(interface: &str) -> Result<(), Box<dyn Error>> {
+    Control::open(interface)?.command("DISCONNECT")
+}
+
+/// Drops the association and makes it again, then renews the lease.
+pub fn reconnect(
+    interface: &str,
+    cancel: &CancelToken,
+) -> Result<(), Box<dyn Error>> {
+    let control = Control::open(interface)?;
+    control.command("ATTACH")?;
+    control.command("DISCONNECT")?;
+    control.command("RECONNECT")?;
+    let connected = control.wait_event(
+        &["CTRL-EVENT-CONNECTED"],
+        ASSOCIATION_TIMEOUT,
+        Some(cancel),
+    )?;
+    let _ = control.command("DETACH");
+    if connected.is_none() && cancel.is_cancelled() {
+        return Err(io::Error::new(
+            io::ErrorKind::Interrupted,
+            "Reconnect cancelled",
+        )
+        .into());
+    }
+    if connected.is_none() {
+        return Err(
+            "Timed out waiting for wpa_supplicant to reassociate".into()
+        );
+    }
+    run_dhcp(interface)
+}
+
+/// Runs a DHCP client on `interface`, with its output kept off the screen
+/// the TUI draws on.
+fn run_quietly<'a>(
+    program: &str,
+    args: impl IntoIterator<Item = &'a str>,
+    interface: &str,
+) -> io::Result<ExitStatus> {
+    Command::new(program)
+        .args(args)
+        .arg(interface)
+        .stdin(Stdio::null())
+        .stdout(Stdio::null())
+        .stderr(Stdio::null())
+        .status()
+}
+
+/// Runs the DHCP hook, or the first DHCP client installed, on `interface`.
+fn run_dhcp(interface: &str) -> Result<(), Box<dyn Error>> {
+    if let Some(hook) = env::var(DHCP_HOOK_VAR)
+        .ok()
+        .filter(|hook| !hook.trim().is_empty())
+    {
+        let mut words = hook.split_whitespace();
+        let program = words.next().unwrap_or_default();
+        let status = run_quietly(program, words, interface)
+            .map_err(|error| format!("Failed to run {program}: {error}"))?;
+        return if status.success() {
+            Ok(())
+        } else {
+            Err(format!("{program} could not get a lease ({status})").into())
+        };
+    }
+
+    for (program, args) in DHCP_CLIENTS {
+        match run_quietly(program, args.iter().copied(), interface) {
+            Ok(status) if status.success() => return Ok(()),
+            Ok(status) => {
+                return Err(format!(
+                    "{program} could not get a lease ({status})"
+                )
+                .into());
+            }
+            Err(error) if error.kind() == io::ErrorKind::NotFound => {}
+            Err(error) => {
+                return Err(format!("Failed to run {program}: {error}").into());
+            }
+        }
+    }
+    Err(format!(
+        "No DHCP client found; install dhcpcd, udhcpc or dhclient, or set \
+         {DHCP_HOOK_VAR}"
+    )
+    .into())
+}
+
+/// The `key=value` lines of a `STATUS` reply.
+fn parse_key_values(text: &str) -> HashMap<String, String> {
+    text.lines()
+        .filter_map(|line| line.split_once('='))
+        .map(|(key, value)| (key.to_string(), value.to_string()))
+        .collect()
+}
+
+/// Undoes the escaping wpa_supplicant applies to SSIDs in its replies:
+/// `\\`, `\"`, `\e`, `\n`, `\r`, `\t` and `\xNN`.
+fn decode_ssid(text: &str) -> Vec<u8> {
+    let bytes = text.as_bytes();
+    let mut decoded = Vec::with_capacity(bytes.len());
+    let mut index = 0;
+    while index < bytes.len() {
+        if bytes[index] != b'\\' || index + 1 == bytes.len() {
+            decoded.push(bytes[index]);
+            index += 1;
+            continue;
+        }
+        match bytes[index + 1] {
+            b'x' => match text
+                .get(index + 2..index + 4)
+                .and_then(|hex| u8::from_str_radix(hex, 16).ok())
+            {
+                Some(byte) => {
+                    decoded.push(byte);
+                    index += 4;
+                }
+                None => {
+                    decoded.push(b'\\');
+                    index += 1;
+                }
+            },
+            escaped => {
+                decoded.push(match escaped {
+                    b'e' => 0x1b,
+                    b'n' => b'\n',
+                    b'r' => b'\r',
+                    b't' => b'\t',
+                    other => other,
+                });
+                index += 2;
+            }
+        }
+    }
+    decoded
+}
+
+/// NetworkManager's AP flags, WPA flags and RSN flags for the bracketed
+/// flags of a scan result, like `[WPA2-PSK-CCMP][ESS]`.
+fn security_flags(flags: &str) -> (u32, u32, u32) {
+    let (mut privacy, mut wpa, mut rsn) = (0, 0, 0);
+    for group in flags.split(['[', ']']).filter(|group| !group.is_empty()) {
+        let suites = if group.starts_with("WPA2-") || group.starts_with("RSN-")
+        {
+            &mut rsn
+        } else if group.starts_with("WPA-") {
+            &mut wpa
+        } else {
+            if group == "WEP" {
+                privacy = AP_FLAGS_PRIVACY;
+            }
+            continue;
+        };
+        privacy = AP_FLAGS_PRIVACY;
+        for (token, flag) in [
+            ("PSK", AP_SEC_KEY_MGMT_PSK),
+            ("SAE", AP_SEC_KEY_MGMT_SAE),
+            ("EAP", AP_SEC_KEY_MGMT_8021X),
+            ("OWE", AP_SEC_KEY_MGMT_OWE),
+            ("CCMP", PAIR_CCMP | GROUP_CCMP),
+            ("TKIP", PAIR_TKIP | GROUP_TKIP),
+        ] {
+            if group.contains(token) {
+                *suites |= flag;
+            }
+        }
+    }
+    (privacy, wpa, rsn)
+}
+
+/// The signal percentage NetworkManager derives from a level in dBm.
+fn signal_percent(dbm: i32) -> u8 {
+    (2 * (dbm + 100)).clamp(0, 100) as u8
+}
+
+/// Networks of a `SCAN_RESULTS` reply, one tab-separated access point per
+/// line after the header. Hidden networks are left out.
+fn parse_scan_results(
+    text: &str,
+    associated: Option<&str>,
+    saved: &HashSet<Vec<u8>>,
+) -> Vec<WifiNetwork> {
+    let networks = text
+        .lines()
+        .skip(1)
+        .filter_map(|line| {
+            let mut fields = line.splitn(5, '\t');
+            let bssid = fields.next()?.to_ascii_uppercase();
+            let frequency = fields.next()?.parse::<u32>().ok()?;
+            let signal_strength =
+                signal_percent(fields.next()?.parse::<i32>().ok()?);
+            let (flags, wpa_flags, rsn_flags) = security_flags(fields.next()?);
+            let raw_ssid = decode_ssid(fields.next()?);
+            if raw_ssid.is_empty() || raw_ssid.iter().all(|byte| *byte == 0) {
+                return None;
+            }
+            let associated = associated == Some(bssid.as_str());
+            Some(WifiNetwork {
+                ssid: ssid_name(&raw_ssid),
+                saved: saved.contains(&raw_ssid),
+                raw_ssid: std::str::from_utf8(&raw_ssid)
+                    .is_err()
+                    .then(|| raw_ssid.clone()),
+                signal_strength,
+                security: classify_access_point_security(
+                    flags, wpa_flags, rsn_flags,
+                ),
+                frequency,
+                connected: associated,
+                max_bitrate: 0,
+                wpa_flags,
+                rsn_flags,
+                access_points: vec![WifiAccessPoint {
+                    bssid,
+                    frequency,
+                    signal_strength,
+                    associated,
+                }],
+            })
+        })
+        .collect();
+    deduplicate_networks(networks)
+        .into_iter()
+        .map(|mut network| {
+            // The merged network keeps the fields of its highest band, which
+            // need not be the access point the adapter is on.
+            network.connected =
+                network.access_points.iter().any(|ap| ap.associated);
+            network
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{
+        collections::HashSet,
+        os::unix::net::UnixDatagram,
+        sync::{
+            Arc,
+            atomic::{AtomicBool, Ordering},
+        },
+        thread,
+        time::Duration,
+    };
+
+    use super::{
+        Control,
+        associate,
+        decode_ssid,
+        parse_key_values,
+        parse_scan_results,
+        psk_value,
+        security_flags,
+        signal_percent,
+    };
+    use crate::{
+        network::{
+            ActivationProgress,
+            CancelToken,
+            ConnectionRequest,
+            WrongPassword,
+        },
+        wifi::{WifiNetwork, WifiSecurity},
+    };
+
+    /// Answers the commands `associate` sends like wpa_supplicant would,
+    /// rejecting the key of any network it is asked to select. Returns the
+    /// configured networks, as (id, SSID, enabled), once `stop` is set.
+    fn fake_wpa_supplicant(
+        server: UnixDatagram,
+        stop: Arc<AtomicBool>,
+    ) -> thread::JoinHandle<Vec<(u32, String, bool)>> {
+        server
+            .set_read_timeout(Some(Duration::from_millis(20)))
+            .expect("read timeout");
+        thread::spawn(move || {
+            let mut networks = vec![
+                (0, "Home".to_string(), true),
+                (1, "Work".to_string(), true),
+            ];
+            let mut buffer = [0; 4096];
+            while !stop.load(Ordering::SeqCst) {
+                let Ok((length, client)) = server.recv_from(&mut buffer) else {
+                    continue;
+                };
+                let client = client.as_pathname().expect("client path");
+                let command = String::from_utf8_lossy(&buffer[..length]);
+                let words = command.split_whitespace().collect::<Vec<_>>();
+                let mut events = Vec::new();
+                let reply = match words.as_slice() {
+                    ["LIST_NETWORKS"] => networks.iter().fold(
+                        "network id / ssid / bssid / flags\n".to_string(),
+                        |list, (id, ssid, enabled)| {
+                            let flags =
+                                if *enabled { "" } else { "[DISABLED]" };
+                            format!("{list}{id}\t{ssid}\tany\t{flags}\n")
+                        },
+                    ),
+                    ["ADD_NETWORK"] => {
+                        networks.push((2, String::new(), false));
+                        "2\n".to_string()
+                    }
+                    ["SELECT_NETWORK", id] => {
+                        for (other, _, enabled) in &mut networks {
+                            *enabled = other.to_string() == *id;
+                        }
+                        events.push(format!(
+                            "<3>CTRL-EVENT-SSID-TEMP-DISABLED id={id} \
+                             auth_failures=1 duration=10 reason=WRONG_KEY"
+                        ));
+                        "OK\n".to_string()
+                    }
+                    ["ENABLE_NETWORK", "all"] => {
+                        for (_, _, enabled) in &mut networks {
+                            *enabled = true;
+                        }
+                        "OK\n".to_string()
+                    }
+                    ["REMOVE_NETWORK", id] => {
+                        networks
+                            .retain(|(other, _, _)| other.to_string() != *id);
+                        "OK\n".to_string()
+                    }
+                    _ => "OK\n".to_string(),
+                };
+                server.send_to(reply.as_bytes(), client).expect("reply");
+                for event in events {
+                    server.send_to(event.as_bytes(), client).expect("event");
+                }
+            }
+            networks
+        })
+    }
+
+    #[test]
+    fn a_failed_attempt_enables_the_other_networks_again() {
+        let path = std::env::temp_dir()
+            .join(format!("nm-wifi-wpa-fake-{}", std::process::id()));
+        let _ = std::fs::remove_file(&path);
+        let server = UnixDatagram::bind(&path).expect("bind fake socket");
+        let stop = Arc::new(AtomicBool::new(false));
+        let fake = fake_wpa_supplicant(server, stop.clone());
+        let network = WifiNetwork {
+            ssid: "Cafe".to_string(),
+            raw_ssid: None,
+            signal_strength: 70,
+            security: WifiSecurity::WpaPsk,
+            frequency: 2412,
+            connected: false,
+            saved: false,
+            max_bitrate: 0,
+            wpa_flags: 0,
+            rsn_flags: 0,
+            access_points: Vec::new(),
+        };
+
+        let control = Control::open_at(&path, "wlan0").expect("open");
+        let result = associate(
+            &control,
+            ConnectionRequest::Secured {
+                network: &network,
+                passphrase: "not-the-password",
+            },
+            &CancelToken::default(),
+            &ActivationProgress::default(),
+        );
+        stop.store(true, Ordering::SeqCst);
+        let networks = fake.join().expect("fake wpa_supplicant");
+        let _ = std::fs::remove_file(&path);
+
+        let error = result.expect_err("the key is rejected");
+        assert!(error.downcast_ref::<WrongPassword>().is_some());
+        assert_eq!(
+            networks,
+            [(0, "Home".to_string(), true), (1, "Work".to_string(), true)]
+        );
+    }
+
+    #[test]
+    fn hex_keys_are_sent_unquoted() {
+        assert_eq!(psk_value("AcerolaAcai"), "\"AcerolaAcai\"");
+        let key = "0123456789abcdef".repeat(4);
+        assert_eq!(psk_value(&key), key);
+        let passphrase = "0123456789abcdeg".repeat(4);
+        assert_eq!(psk_value(&passphrase), format!("\"{passphrase}\""));
+    }
+
+    #[test]
+    fn escaped_ssids_are_decoded() {
+        assert_eq!(decode_ssid("Cafe"), b"Cafe".to_vec());
+        assert_eq!(decode_ssid(r#"a\"b\\c"#), br#"a"b\c"#.to_vec());
+        assert_eq!(decode_ssid(r"caf\xc3\xa9"), "café".as_bytes().to_vec());
+        assert_eq!(decode_ssid(r"bad\xzz"), br"bad\xzz".to_vec());
+        assert_eq!(decode_ssid(r"tab\tend"), b"tab\tend".to_vec());
+    }
+
+    #[test]
+    fn scan_flags_map_to_security() {
+        let security = |flags: &str| {
+            let (privacy, wpa, rsn) = security_flags(flags);
+            crate::network::networkmanager::classify_access_point_security(
+                privacy, wpa, rsn,
+            )
+        };
+        assert_eq!(security("[ESS]"), WifiSecurity::Open);
+        assert_eq!(security("[WPA2-PSK-CCMP][ESS]"), WifiSecurity::WpaPsk);
+        assert_eq!(security("[WPA2-PSK+SAE-CCMP][ESS]"), WifiSecurity::WpaSae);
+        assert_eq!(security("[RSN-SAE-CCMP][ESS]"), WifiSecurity::WpaSae);
+        assert_eq!(security("[WPA2-EAP-CCMP][ESS]"), WifiSecurity::Enterprise);
+        assert_eq!(security("[WEP][ESS]"), WifiSecurity::Unsupported);
+        assert_eq!(signal_percent(-40), 100);
+        assert_eq!(signal_percent(-70), 60);
+        assert_eq!(signal_percent(-110), 0);
+    }
+
+    #[test]
+    fn scan_results_are_grouped_by_ssid() {
+        let results = "bssid / frequency / signal level / flags / ssid\n\
+            aa:bb:cc:dd:ee:01\t2412\t-60\t[WPA2-PSK-CCMP][ESS]\tHome\n\
+            aa:bb:cc:dd:ee:02\t5180\t-50\t[WPA2-PSK-CCMP][ESS]\tHome\n\
+            aa:bb:cc:dd:ee:03\t2437\t-80\t[ESS]\tCafe\n\
+            aa:bb:cc:dd:ee:04\t2462\t-70\t[ESS]\t\n";
+        let saved = HashSet::from([b"Home".to_vec()]);
+        let networks =
+            parse_scan_results(results, Some("AA:BB:CC:DD:EE:02"), &saved);
+
+        assert_eq!(networks.len(), 2, "the hidden network is left out");
+        let home = &networks[0];
+        assert_eq!(home.ssid, "Home");
+        assert!(home.connected);
+        assert!(home.saved);
+        assert_eq!(home.security, WifiSecurity::WpaPsk);
+        assert_eq!(home.access_points.len(), 2);
+        assert_eq!(home.access_points[0].bssid, "AA:BB:CC:DD:EE:02");
+        assert!(home.access_points[0].associated);
+        let cafe = &networks[1];
+        assert!(!cafe.connected);
+        assert!(!cafe.saved);
+        assert_eq!(cafe.security, WifiSecurity::Open);
+        assert_eq!(cafe.signal_strength, 40);
+    }
+
+    #[test]
+    fn status_replies_are_read_as_key_values() {
+        let status = parse_key_values(
+            "bssid=aa:bb:cc:dd:ee:02\nssid=Home\nwpa_state=COMPLETED\n",
+        );
+        assert_eq!(status.get("ssid").map(String::as_str), Some("Home"));
+        assert_eq!(
+            status.get("wpa_state").map(String::as_str),
+            Some("COMPLETED")
+        );
+    }
+}
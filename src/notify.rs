@@ -0,0 +1,579 @@
+//! Event notifications fanned out to pluggable sinks: in-app toasts, desktop
+//! notifications, the terminal bell or a screen flash and webhook POSTs,
+//! routed per event type.
+
+use std::{
+    collections::{HashMap, VecDeque},
+    error::Error,
+    io::{self, Write},
+    process::{Command, Stdio},
+    sync::{Arc, Mutex},
+    thread,
+    time::{Duration, Instant},
+};
+
+const TOAST_LIFETIME: Duration = Duration::from_secs(4);
+/// Warnings and errors stay up longer, as they are worth reading.
+const ALERT_TOAST_LIFETIME: Duration = Duration::from_secs(8);
+const MAX_TOASTS: usize = 3;
+const FLASH_DURATION: Duration = Duration::from_millis(150);
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum NotificationEvent {
+    Connected,
+    ConnectionFailed,
+    Disconnected,
+    DisconnectFailed,
+    ScanFailed,
+}
+
+impl NotificationEvent {
+    pub const ALL: [Self; 5] = [
+        Self::Connected,
+        Self::ConnectionFailed,
+        Self::Disconnected,
+        Self::DisconnectFailed,
+        Self::ScanFailed,
+    ];
+
+    /// Name used in the config file and in webhook payloads.
+    pub fn key(self) -> &'static str {
+        match self {
+            Self::Connected => "connected",
+            Self::ConnectionFailed => "connection_failed",
+            Self::Disconnected => "disconnected",
+            Self::DisconnectFailed => "disconnect_failed",
+            Self::ScanFailed => "scan_failed",
+        }
+    }
+
+    pub fn from_key(key: &str) -> Option<Self> {
+        Self::ALL.into_iter().find(|event| event.key() == key)
+    }
+
+    pub fn is_failure(self) -> bool {
+        matches!(
+            self,
+            Self::ConnectionFailed | Self::DisconnectFailed | Self::ScanFailed
+        )
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum SinkKind {
+    Toast,
+    Desktop,
+    Bell,
+    Flash,
+    Webhook,
+}
+
+impl SinkKind {
+    pub const ALL: [Self; 5] = [
+        Self::Toast,
+        Self::Desktop,
+        Self::Bell,
+        Self::Flash,
+        Self::Webhook,
+    ];
+
+    pub fn key(self) -> &'static str {
+        match self {
+            Self::Toast => "toast",
+            Self::Desktop => "desktop",
+            Self::Bell => "bell",
+            Self::Flash => "flash",
+            Self::Webhook => "webhook",
+        }
+    }
+
+    pub fn from_key(key: &str) -> Option<Self> {
+        Self::ALL.into_iter().find(|sink| sink.key() == key)
+    }
+}
+
+/// Which sinks each event is delivered to; every event shows a toast unless
+/// configured otherwise.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct NotificationRoutes(HashMap<NotificationEvent, Vec<SinkKind>>);
+
+impl Default for NotificationRoutes {
+    fn default() -> Self {
+        Self(
+            NotificationEvent::ALL
+                .into_iter()
+                .map(|event| (event, vec![SinkKind::Toast]))
+                .collect(),
+        )
+    }
+}
+
+impl NotificationRoutes {
+    pub fn sinks(&self, event: NotificationEvent) -> &[SinkKind] {
+        self.0.get(&event).map(Vec::as_slice).unwrap_or_default()
+    }
+
+    pub fn set(&mut self, event: NotificationEvent, sinks: Vec<SinkKind>) {
+        self.0.insert(event, sinks);
+    }
+
+    pub fn uses(&self, sink: SinkKind) -> bool {
+        self.0.values().any(|sinks| sinks.contains(&sink))
+    }
+
+    /// Parses a comma-separated sink list such as `toast, desktop`; `none`
+    /// silences the event.
+    pub fn parse_sinks(value: &str) -> Result<Vec<SinkKind>, String> {
+        if value.trim() == "none" {
+            return Ok(Vec::new());
+        }
+
+        value
+            .split(',')
+            .map(str::trim)
+            .map(|key| {
+                SinkKind::from_key(key)
+                    .ok_or_else(|| format!("unknown notification sink `{key}`"))
+            })
+            .collect()
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Notification {
+    pub event: NotificationEvent,
+    pub summary: String,
+    pub body: String,
+}
+
+pub trait NotificationSink: Send {
+    fn deliver(
+        &mut self,
+        notification: &Notification,
+    ) -> Result<(), Box<dyn Error>>;
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ToastLevel {
+    Info,
+    Warning,
+    Error,
+}
+
+impl ToastLevel {
+    fn lifetime(self) -> Duration {
+        match self {
+            Self::Info => TOAST_LIFETIME,
+            Self::Warning | Self::Error => ALERT_TOAST_LIFETIME,
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct Toast {
+    pub level: ToastLevel,
+    pub summary: String,
+    pub body: String,
+    pub expires_at: Instant,
+}
+
+/// Toasts shared between the toast sink and the UI that draws them.
+#[derive(Debug, Clone, Default)]
+pub struct ToastQueue(Arc<Mutex<VecDeque<Toast>>>);
+
+impl ToastQueue {
+    pub fn push(
+        &self,
+        level: ToastLevel,
+        summary: impl Into<String>,
+        body: impl Into<String>,
+    ) {
+        if let Ok(mut toasts) = self.0.lock() {
+            toasts.push_front(Toast {
+                level,
+                summary: summary.into(),
+                body: body.into(),
+                expires_at: Instant::now() + level.lifetime(),
+            });
+            toasts.truncate(MAX_TOASTS);
+        }
+    }
+
+    /// Unexpired toasts, newest first.
+    pub fn active(&self, now: Instant) -> Vec<Toast> {
+        self.0
+            .lock()
+            .map(|mut toasts| {
+                toasts.retain(|toast| toast.expires_at > now);
+                toasts.iter().cloned().collect()
+            })
+            .unwrap_or_default()
+    }
+}
+
+pub struct ToastSink(pub ToastQueue);
+
+impl NotificationSink for ToastSink {
+    fn deliver(
+        &mut self,
+        notification: &Notification,
+    ) -> Result<(), Box<dyn Error>> {
+        let level = if notification.event.is_failure() {
+            ToastLevel::Error
+        } else {
+            ToastLevel::Info
+        };
+        self.0
+            .push(level, &notification.summary, &notification.body);
+        Ok(())
+    }
+}
+
+/// Desktop notifications through `notify-send`, run off the UI thread with
+/// its output kept off the screen the TUI draws on.
+pub struct DesktopSink;
+
+impl NotificationSink for DesktopSink {
+    fn deliver(
+        &mut self,
+        notification: &Notification,
+    ) -> Result<(), Box<dyn Error>> {
+        let mut command = Command::new("notify-send");
+        command
+            .arg("--app-name=nm-wifi")
+            .arg(&notification.summary)
+            .arg(&notification.body);
+        if notification.event.is_failure() {
+            command.arg("--urgency=critical");
+        }
+        command
+            .stdin(Stdio::null())
+            .stdout(Stdio::null())
+            .stderr(Stdio::null());
+
+        let mut child = command.spawn()?;
+        thread::spawn(move || child.wait());
+        Ok(())
+    }
+}
+
+/// Rings the terminal bell so a finished connection is noticed from another
+/// window; most terminals mark the tab or raise the urgency hint.
+pub struct BellSink<W: Write + Send = io::Stdout>(pub W);
+
+impl<W: Write + Send> NotificationSink for BellSink<W> {
+    fn deliver(&mut self, _: &Notification) -> Result<(), Box<dyn Error>> {
+        self.0.write_all(b"\x07")?;
+        self.0.flush()?;
+        Ok(())
+    }
+}
+
+/// When a screen flash ends, shared between the flash sink and the UI that
+/// draws the screen inverted until then.
+#[derive(Debug, Clone, Default)]
+pub struct ScreenFlash(Arc<Mutex<Option<Instant>>>);
+
+impl ScreenFlash {
+    pub fn start(&self, now: Instant) {
+        if let Ok(mut until) = self.0.lock() {
+            *until = Some(now + FLASH_DURATION);
+        }
+    }
+
+    pub fn is_active(&self, now: Instant) -> bool {
+        self.0
+            .lock()
+            .is_ok_and(|until| until.is_some_and(|until| until > now))
+    }
+}
+
+/// Briefly inverts the screen, the same visual bell most terminals use. The
+/// draw loop does the inverting, so nothing is written behind its back.
+pub struct FlashSink(pub ScreenFlash);
+
+impl NotificationSink for FlashSink {
+    fn deliver(&mut self, _: &Notification) -> Result<(), Box<dyn Error>> {
+        self.0.start(Instant::now());
+        Ok(())
+    }
+}
+
+/// POSTs a small JSON document per notification; requests run in the
+/// background and failures are dropped.
+pub struct WebhookSink {
+    pub url: String,
+}
+
+fn json_string(value: &str) -> String {
+    let mut escaped = String::with_capacity(value.len() + 2);
+    escaped.push('"');
+    for c in value.chars() {
+        match c {
+            '"' => escaped.push_str("\\\""),
+            '\\' => escaped.push_str("\\\\"),
+            '\n' => escaped.push_str("\\n"),
+            c if c.is_control() => {
+                escaped.push_str(&format!("\\u{:04x}", c as u32))
+            }
+            c => escaped.push(c),
+        }
+    }
+    escaped.push('"');
+    escaped
+}
+
+pub fn webhook_payload(notification: &Notification) -> String {
+    format!(
+        "{{\"event\":{},\"summary\":{},\"body\":{}}}",
+        json_string(notification.event.key()),
+        json_string(&notification.summary),
+        json_string(&notification.body)
+    )
+}
+
+impl NotificationSink for WebhookSink {
+    fn deliver(
+        &mut self,
+        notification: &Notification,
+    ) -> Result<(), Box<dyn Error>> {
+        let request = ureq::post(&self.url)
+            .timeout(Duration::from_secs(10))
+            .set("Content-Type", "application/json");
+        let payload = webhook_payload(notification);
+        thread::spawn(move || {
+            let _ = request.send_string(&payload);
+        });
+        Ok(())
+    }
+}
+
+/// Routes events to the configured sinks. Delivery failures are reported as
+/// warning toasts so they never interrupt the UI.
+pub struct Notifier {
+    routes: NotificationRoutes,
+    sinks: HashMap<SinkKind, Box<dyn NotificationSink>>,
+    toasts: ToastQueue,
+    flash: ScreenFlash,
+}
+
+impl Default for Notifier {
+    fn default() -> Self {
+        Self::new(NotificationRoutes::default(), None)
+    }
+}
+
+impl Notifier {
+    pub fn new(
+        routes: NotificationRoutes,
+        webhook_url: Option<String>,
+    ) -> Self {
+        let toasts = ToastQueue::default();
+        let flash = ScreenFlash::default();
+        let mut sinks: HashMap<SinkKind, Box<dyn NotificationSink>> =
+            HashMap::from([
+                (
+                    SinkKind::Toast,
+                    Box::new(ToastSink(toasts.clone()))
+                        as Box<dyn NotificationSink>,
+                ),
+                (SinkKind::Desktop, Box::new(DesktopSink)),
+                (SinkKind::Bell, Box::new(BellSink(io::stdout()))),
+                (SinkKind::Flash, Box::new(FlashSink(flash.clone()))),
+            ]);
+        if let Some(url) = webhook_url {
+            sinks.insert(SinkKind::Webhook, Box::new(WebhookSink { url }));
+        }
+
+        Self {
+            routes,
+            sinks,
+            toasts,
+            flash,
+        }
+    }
+
+    /// Replaces the sink used for `kind`.
+    pub fn with_sink(
+        mut self,
+        kind: SinkKind,
+        sink: Box<dyn NotificationSink>,
+    ) -> Self {
+        self.sinks.insert(kind, sink);
+        self
+    }
+
+    pub fn toasts(&self) -> &ToastQueue {
+        &self.toasts
+    }
+
+    pub fn flash(&self) -> &ScreenFlash {
+        &self.flash
+    }
+
+    pub fn notify(
+        &mut self,
+        event: NotificationEvent,
+        summary: impl Into<String>,
+        body: impl Into<String>,
+    ) {
+        let notification = Notification {
+            event,
+            summary: summary.into(),
+            body: body.into(),
+        };
+
+        for kind in self.routes.sinks(event) {
+            let Some(sink) = self.sinks.get_mut(kind) else {
+                continue;
+            };
+            if let Err(error) = sink.deliver(&notification) {
+                self.toasts.push(
+                    ToastLevel::Warning,
+                    format!("{} notification failed", kind.key()),
+                    error.to_string(),
+                );
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{
+        error::Error,
+        sync::{Arc, Mutex},
+        time::{Duration, Instant},
+    };
+
+    use super::{
+        BellSink,
+        FLASH_DURATION,
+        Notification,
+        NotificationEvent,
+        NotificationRoutes,
+        NotificationSink,
+        Notifier,
+        SinkKind,
+        ToastLevel,
+        webhook_payload,
+    };
+
+    struct RecordingSink(Arc<Mutex<Vec<Notification>>>);
+
+    impl NotificationSink for RecordingSink {
+        fn deliver(
+            &mut self,
+            notification: &Notification,
+        ) -> Result<(), Box<dyn Error>> {
+            self.0.lock().expect("lock").push(notification.clone());
+            Ok(())
+        }
+    }
+
+    #[test]
+    fn events_reach_only_their_configured_sinks() {
+        let delivered = Arc::new(Mutex::new(Vec::new()));
+        let mut routes = NotificationRoutes::default();
+        routes.set(
+            NotificationEvent::ConnectionFailed,
+            vec![SinkKind::Toast, SinkKind::Desktop],
+        );
+        routes.set(NotificationEvent::Connected, Vec::new());
+        let mut notifier = Notifier::new(routes, None).with_sink(
+            SinkKind::Desktop,
+            Box::new(RecordingSink(delivered.clone())),
+        );
+
+        notifier.notify(NotificationEvent::Connected, "Connected", "CatCat");
+        notifier.notify(
+            NotificationEvent::ConnectionFailed,
+            "Connection failed",
+            "Office: wrong password",
+        );
+
+        let delivered = delivered.lock().expect("lock");
+        assert_eq!(delivered.len(), 1);
+        assert_eq!(delivered[0].body, "Office: wrong password");
+        let toasts = notifier.toasts().active(Instant::now());
+        assert_eq!(toasts.len(), 1);
+        assert_eq!(toasts[0].summary, "Connection failed");
+        assert_eq!(toasts[0].level, ToastLevel::Error);
+    }
+
+    #[test]
+    fn toasts_expire_after_their_level_lifetime() {
+        let mut notifier = Notifier::default();
+        notifier.notify(NotificationEvent::ScanFailed, "Scan failed", "busy");
+        notifier.toasts().push(
+            ToastLevel::Info,
+            "Scan complete",
+            "1 new network",
+        );
+
+        let soon = Instant::now() + Duration::from_secs(5);
+        let toasts = notifier.toasts().active(soon);
+        assert_eq!(toasts.len(), 1);
+        assert_eq!(toasts[0].summary, "Scan failed");
+        let later = Instant::now() + Duration::from_secs(60);
+        assert!(notifier.toasts().active(later).is_empty());
+    }
+
+    #[test]
+    fn sink_lists_parse_and_reject_unknown_names() {
+        assert_eq!(
+            NotificationRoutes::parse_sinks("toast, webhook"),
+            Ok(vec![SinkKind::Toast, SinkKind::Webhook])
+        );
+        assert_eq!(NotificationRoutes::parse_sinks("none"), Ok(Vec::new()));
+        assert_eq!(
+            NotificationRoutes::parse_sinks("toast,email"),
+            Err("unknown notification sink `email`".to_string())
+        );
+        assert_eq!(
+            NotificationRoutes::parse_sinks("toast, bell, flash"),
+            Ok(vec![SinkKind::Toast, SinkKind::Bell, SinkKind::Flash])
+        );
+    }
+
+    #[test]
+    fn bell_sink_writes_only_the_bell_character() {
+        let mut sink = BellSink(Vec::new());
+        sink.deliver(&Notification {
+            event: NotificationEvent::Connected,
+            summary: "Connected successfully".to_string(),
+            body: "CatCat".to_string(),
+        })
+        .expect("written");
+
+        assert_eq!(sink.0, b"\x07");
+    }
+
+    #[test]
+    fn flash_sink_inverts_the_screen_for_a_moment() {
+        let mut routes = NotificationRoutes::default();
+        routes.set(NotificationEvent::Connected, vec![SinkKind::Flash]);
+        let mut notifier = Notifier::new(routes, None);
+        assert!(!notifier.flash().is_active(Instant::now()));
+
+        notifier.notify(NotificationEvent::Connected, "Connected", "CatCat");
+
+        assert!(notifier.flash().is_active(Instant::now()));
+        let later = Instant::now() + FLASH_DURATION;
+        assert!(!notifier.flash().is_active(later));
+    }
+
+    #[test]
+    fn webhook_payloads_carry_the_event_name() {
+        let notification = Notification {
+            event: NotificationEvent::Disconnected,
+            summary: "Disconnected".to_string(),
+            body: "Cafe \"Bar\"".to_string(),
+        };
+
+        assert_eq!(
+            webhook_payload(&notification),
+            r#"{"event":"disconnected","summary":"Disconnected","body":"Cafe \"Bar\""}"#
+        );
+    }
+}
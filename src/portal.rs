@@ -0,0 +1,90 @@
+//! Captive portal login pages: finding where the portal redirects NM's
+//! connectivity probe, and handing that page to the desktop browser.
+
+use std::{
+    io,
+    process::{Command, Stdio},
+    time::Duration,
+};
+
+/// Probe used when NM does not report its own `ConnectivityCheckUri`.
+pub const DEFAULT_CHECK_URI: &str =
+    "http://nmcheck.gnome.org/check_network_status.txt";
+
+const PROBE_TIMEOUT: Duration = Duration::from_secs(5);
+
+/// Fetches `check_uri` without following redirects. Portals answer with a
+/// redirect to their login page; those that rewrite the page in place are
+/// reached by opening the probe itself.
+pub fn find_portal_url(check_uri: &str) -> String {
+    let agent = ureq::AgentBuilder::new()
+        .redirects(0)
+        .timeout(PROBE_TIMEOUT)
+        .build();
+    let location = match agent.get(check_uri).call() {
+        Ok(response) | Err(ureq::Error::Status(_, response)) => {
+            response.header("Location").map(str::to_string)
+        }
+        Err(_) => None,
+    };
+
+    resolve_portal_url(check_uri, location.as_deref())
+}
+
+fn resolve_portal_url(check_uri: &str, location: Option<&str>) -> String {
+    match location {
+        Some(url)
+            if url.starts_with("http://") || url.starts_with("https://") =>
+        {
+            url.to_string()
+        }
+        Some(path) if path.starts_with('/') => {
+            format!("{}{path}", origin(check_uri))
+        }
+        _ => check_uri.to_string(),
+    }
+}
+
+fn origin(uri: &str) -> &str {
+    let host_start = uri.find("://").map_or(0, |index| index + 3);
+    match uri[host_start..].find('/') {
+        Some(index) => &uri[..host_start + index],
+        None => uri,
+    }
+}
+
+/// Starts `xdg-open` without waiting for the browser.
+pub fn open_in_browser(url: &str) -> io::Result<()> {
+    Command::new("xdg-open")
+        .arg(url)
+        .stdin(Stdio::null())
+        .stdout(Stdio::null())
+        .stderr(Stdio::null())
+        .spawn()
+        .map(|_| ())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::resolve_portal_url;
+
+    const CHECK: &str = "http://check.example/status.txt";
+
+    #[test]
+    fn redirects_point_at_the_login_page() {
+        assert_eq!(
+            resolve_portal_url(CHECK, Some("https://portal.hotel/login?x=1")),
+            "https://portal.hotel/login?x=1"
+        );
+        assert_eq!(
+            resolve_portal_url(CHECK, Some("/splash")),
+            "http://check.example/splash"
+        );
+    }
+
+    #[test]
+    fn portals_without_a_redirect_fall_back_to_the_probe() {
+        assert_eq!(resolve_portal_url(CHECK, None), CHECK);
+        assert_eq!(resolve_portal_url(CHECK, Some("splash")), CHECK);
+    }
+}
@@ -0,0 +1,375 @@
+//! Wi-Fi share QR codes using the `WIFI:` URI scheme understood by phone
+//! cameras, rendered in the terminal or exported as PNG/SVG files, and
+//! parsed back when a shared network is imported.
+
+use std::{
+    error::Error,
+    fs::File,
+    io::BufWriter,
+    path::{Path, PathBuf},
+};
+
+use qrcode::{Color, QrCode, render::svg};
+
+use crate::wifi::{WifiNetwork, WifiSecurity};
+
+/// Light modules around the code; scanners need at least four in print.
+const EXPORT_QUIET_ZONE: usize = 4;
+const TERMINAL_QUIET_ZONE: usize = 2;
+const PNG_MODULE_PIXELS: usize = 10;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum QrExportFormat {
+    Png,
+    Svg,
+}
+
+impl QrExportFormat {
+    pub fn from_path(path: &Path) -> Result<Self, String> {
+        match path
+            .extension()
+            .and_then(|extension| extension.to_str())
+            .map(str::to_ascii_lowercase)
+            .as_deref()
+        {
+            Some("png") => Ok(Self::Png),
+            Some("svg") => Ok(Self::Svg),
+            _ => Err("QR export path must end in .png or .svg".to_string()),
+        }
+    }
+}
+
+fn escape_field(value: &str) -> String {
+    let mut escaped = String::with_capacity(value.len());
+    for c in value.chars() {
+        if matches!(c, '\\' | ';' | ',' | ':' | '"') {
+            escaped.push('\\');
+        }
+        escaped.push(c);
+    }
+    escaped
+}
+
+/// Builds the `WIFI:T:...;S:...;P:...;;` payload. Phones treat `WPA` as any
+/// WPA-Personal flavour, including WPA3-SAE.
+pub fn wifi_qr_payload(
+    network: &WifiNetwork,
+    password: Option<&str>,
+) -> String {
+    let ssid = escape_field(&network.ssid);
+    match (network.security, password) {
+        (WifiSecurity::Open, _) | (_, None) => {
+            format!("WIFI:T:nopass;S:{ssid};;")
+        }
+        (_, Some(password)) => {
+            format!("WIFI:T:WPA;S:{ssid};P:{};;", escape_field(password))
+        }
+    }
+}
+
+/// A network read from a `WIFI:` string.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct WifiQrNetwork {
+    pub ssid: String,
+    pub security: WifiSecurity,
+    pub password: Option<String>,
+    pub hidden: bool,
+}
+
+/// Splits the body of a `WIFI:` string into its `;`-separated fields,
+/// undoing backslash escapes.
+fn unescaped_fields(body: &str) -> Vec<String> {
+    let mut fields = vec![String::new()];
+    let mut chars = body.chars();
+    while let Some(c) = chars.next() {
+        match c {
+            '\\' => {
+                if let Some(escaped) = chars.next() {
+                    fields.last_mut().expect("one field").push(escaped);
+                }
+            }
+            ';' => fields.push(String::new()),
+            c => fields.last_mut().expect("one field").push(c),
+        }
+    }
+    fields
+}
+
+/// Parses `WIFI:T:<type>;S:<ssid>;P:<password>;H:<hidden>;;` in any field
+/// order. `WPA` is read as WPA-Personal and `SAE` as WPA3-Personal; WEP is
+/// rejected because NetworkManager profiles created here never use it.
+pub fn parse_wifi_qr(text: &str) -> Result<WifiQrNetwork, String> {
+    let text = text.trim();
+    let body = text
+        .get(..5)
+        .filter(|scheme| scheme.eq_ignore_ascii_case("WIFI:"))
+        .map(|_| &text[5..])
+        .ok_or("Expected a WIFI: string, e.g. WIFI:T:WPA;S:Home;P:secret;;")?;
+
+    let (mut ssid, mut kind, mut password, mut hidden) =
+        (None, String::new(), None, false);
+    for field in unescaped_fields(body) {
+        match field.split_once(':') {
+            Some(("S", value)) => ssid = Some(value.to_string()),
+            Some(("T", value)) => kind = value.to_ascii_uppercase(),
+            Some(("P", value)) if !value.is_empty() => {
+                password = Some(value.to_string())
+            }
+            Some(("H", value)) => hidden = value.eq_ignore_ascii_case("true"),
+            _ => {}
+        }
+    }
+
+    let ssid = ssid
+        .filter(|ssid| !ssid.is_empty())
+        .ok_or("The WIFI: string has no network name (S:)")?;
+    let security = match (kind.as_str(), &password) {
+        ("" | "NOPASS", _) => WifiSecurity::Open,
+        ("WPA" | "WPA2", Some(_)) => WifiSecurity::WpaPsk,
+        ("SAE" | "WPA3", Some(_)) => WifiSecurity::WpaSae,
+        ("WPA" | "WPA2" | "SAE" | "WPA3", None) => {
+            return Err(format!("The WIFI: string for {ssid} has no password"));
+        }
+        (kind, _) => {
+            return Err(format!("Unsupported WIFI: security type {kind}"));
+        }
+    };
+
+    Ok(WifiQrNetwork {
+        ssid,
+        password: password.filter(|_| security != WifiSecurity::Open),
+        security,
+        hidden,
+    })
+}
+
+/// Light/dark module grid including a quiet zone, `true` meaning dark.
+fn module_grid(
+    payload: &str,
+    quiet_zone: usize,
+) -> Result<Vec<Vec<bool>>, Box<dyn Error>> {
+    let code = QrCode::new(payload.as_bytes())?;
+    let width = code.width();
+    let colors = code.to_colors();
+    let size = width + quiet_zone * 2;
+
+    Ok((0..size)
+        .map(|y| {
+            (0..size)
+                .map(|x| {
+                    let inside = quiet_zone..quiet_zone + width;
+                    inside.contains(&x)
+                        && inside.contains(&y)
+                        && colors[(y - quiet_zone) * width + (x - quiet_zone)]
+                            == Color::Dark
+                })
+                .collect()
+        })
+        .collect())
+}
+
+/// Packs two module rows into each text row with half-block glyphs. Light
+/// modules are drawn, so the code reads correctly on a dark terminal.
+pub fn terminal_lines(payload: &str) -> Result<Vec<String>, Box<dyn Error>> {
+    let grid = module_grid(payload, TERMINAL_QUIET_ZONE)?;
+
+    Ok(grid
+        .chunks(2)
+        .map(|rows| {
+            let top = &rows[0];
+            let bottom = rows.get(1);
+            top.iter()
+                .enumerate()
+                .map(|(x, top_dark)| {
+                    let bottom_dark = bottom.is_none_or(|row| row[x]);
+                    match (!top_dark, !bottom_dark) {
+                        (true, true) => '█',
+                        (true, false) => '▀',
+                        (false, true) => '▄',
+                        (false, false) => ' ',
+                    }
+                })
+                .collect()
+        })
+        .collect())
+}
+
+fn write_png(payload: &str, path: &Path) -> Result<(), Box<dyn Error>> {
+    let grid = module_grid(payload, EXPORT_QUIET_ZONE)?;
+    let size = grid.len() * PNG_MODULE_PIXELS;
+    let mut pixels = Vec::with_capacity(size * size);
+    for row in &grid {
+        let line = row
+            .iter()
+            .flat_map(|dark| {
+                std::iter::repeat_n(
+                    if *dark { 0u8 } else { 255u8 },
+                    PNG_MODULE_PIXELS,
+                )
+            })
+            .collect::<Vec<_>>();
+        for _ in 0..PNG_MODULE_PIXELS {
+            pixels.extend_from_slice(&line);
+        }
+    }
+
+    let mut encoder = png::Encoder::new(
+        BufWriter::new(File::create(path)?),
+        size as u32,
+        size as u32,
+    );
+    encoder.set_color(png::ColorType::Grayscale);
+    encoder.set_depth(png::BitDepth::Eight);
+    encoder.write_header()?.write_image_data(&pixels)?;
+    Ok(())
+}
+
+fn write_svg(payload: &str, path: &Path) -> Result<(), Box<dyn Error>> {
+    let image = QrCode::new(payload.as_bytes())?
+        .render::<svg::Color<'_>>()
+        .quiet_zone(true)
+        .min_dimensions(400, 400)
+        .build();
+    std::fs::write(path, image)?;
+    Ok(())
+}
+
+/// Expands a leading `~/` so typed paths behave like they would in a shell.
+pub fn expand_export_path(path: &str) -> PathBuf {
+    match (path.strip_prefix("~/"), std::env::var_os("HOME")) {
+        (Some(rest), Some(home)) => PathBuf::from(home).join(rest),
+        _ => PathBuf::from(path),
+    }
+}
+
+pub fn export_qr(payload: &str, path: &Path) -> Result<(), Box<dyn Error>> {
+    match QrExportFormat::from_path(path)? {
+        QrExportFormat::Png => write_png(payload, path),
+        QrExportFormat::Svg => write_svg(payload, path),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::path::Path;
+
+    use super::{
+        QrExportFormat,
+        WifiQrNetwork,
+        export_qr,
+        parse_wifi_qr,
+        terminal_lines,
+        wifi_qr_payload,
+    };
+    use crate::wifi::{WifiNetwork, WifiSecurity};
+
+    fn network(ssid: &str, security: WifiSecurity) -> WifiNetwork {
+        WifiNetwork {
+            ssid: ssid.to_string(),
+            raw_ssid: None,
+            signal_strength: 70,
+            security,
+            frequency: 2412,
+            connected: false,
+            saved: true,
+            max_bitrate: 0,
+            wpa_flags: 0,
+            rsn_flags: 0,
+            access_points: Vec::new(),
+        }
+    }
+
+    #[test]
+    fn payload_escapes_reserved_characters() {
+        assert_eq!(
+            wifi_qr_payload(
+                &network("Cafe;Bar", WifiSecurity::WpaPsk),
+                Some(r#"a:b,"c\"#)
+            ),
+            r#"WIFI:T:WPA;S:Cafe\;Bar;P:a\:b\,\"c\\;;"#
+        );
+        assert_eq!(
+            wifi_qr_payload(&network("Lobby", WifiSecurity::Open), None),
+            "WIFI:T:nopass;S:Lobby;;"
+        );
+    }
+
+    #[test]
+    fn shared_payloads_parse_back_into_networks() {
+        let shared = wifi_qr_payload(
+            &network("Cafe;Bar", WifiSecurity::WpaPsk),
+            Some(r#"a:b,"c\"#),
+        );
+        assert_eq!(
+            parse_wifi_qr(&shared),
+            Ok(WifiQrNetwork {
+                ssid: "Cafe;Bar".to_string(),
+                security: WifiSecurity::WpaPsk,
+                password: Some(r#"a:b,"c\"#.to_string()),
+                hidden: false,
+            })
+        );
+        assert_eq!(
+            parse_wifi_qr(" wifi:S:Attic;H:true;T:SAE;P:hunter2;; "),
+            Ok(WifiQrNetwork {
+                ssid: "Attic".to_string(),
+                security: WifiSecurity::WpaSae,
+                password: Some("hunter2".to_string()),
+                hidden: true,
+            })
+        );
+        assert_eq!(
+            parse_wifi_qr("WIFI:T:nopass;S:Lobby;;")
+                .map(|network| network.security),
+            Ok(WifiSecurity::Open)
+        );
+    }
+
+    #[test]
+    fn malformed_wifi_strings_are_rejected() {
+        assert!(parse_wifi_qr("https://example.com").is_err());
+        assert!(parse_wifi_qr("WIFI:T:WPA;P:secret;;").is_err());
+        assert!(parse_wifi_qr("WIFI:T:WPA;S:Home;;").is_err());
+        assert!(parse_wifi_qr("WIFI:T:WEP;S:Old;P:12345;;").is_err());
+    }
+
+    #[test]
+    fn export_format_follows_the_file_extension() {
+        assert_eq!(
+            QrExportFormat::from_path(Path::new("/tmp/guest.PNG")),
+            Ok(QrExportFormat::Png)
+        );
+        assert_eq!(
+            QrExportFormat::from_path(Path::new("guest.svg")),
+            Ok(QrExportFormat::Svg)
+        );
+        assert!(QrExportFormat::from_path(Path::new("guest.txt")).is_err());
+    }
+
+    #[test]
+    fn terminal_rendering_packs_two_module_rows_per_line() {
+        let lines = terminal_lines("WIFI:T:nopass;S:Lobby;;").expect("encodes");
+        // Version 2 codes are 25 modules wide, plus two quiet modules a side.
+        assert_eq!(lines.len(), 15);
+        assert!(lines.iter().all(|line| line.chars().count() == 29));
+        assert!(lines[0].chars().all(|c| c == '█'));
+    }
+
+    #[test]
+    fn exports_write_png_and_svg_files() {
+        let dir = std::env::temp_dir()
+            .join(format!("nm-wifi-qr-test-{}", std::process::id()));
+        std::fs::create_dir_all(&dir).expect("temp dir");
+        let payload = "WIFI:T:nopass;S:Lobby;;";
+
+        export_qr(payload, &dir.join("guest.png")).expect("png export");
+        export_qr(payload, &dir.join("guest.svg")).expect("svg export");
+
+        let png = std::fs::read(dir.join("guest.png")).expect("png written");
+        assert!(png.starts_with(b"\x89PNG"));
+        let svg = std::fs::read_to_string(dir.join("guest.svg"))
+            .expect("svg written");
+        assert!(svg.contains("<svg"));
+        std::fs::remove_dir_all(&dir).expect("cleanup");
+    }
+}
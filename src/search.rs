@@ -0,0 +1,42 @@
+//! Fuzzy matching for the network list filter typed after `/`.
+
+/// Positions of the characters of `text` that match `query`, ignoring
+/// case: the first run equal to the whole query when there is one,
+/// otherwise the query's characters in order with gaps between them.
+/// `None` when `text` does not match.
+pub fn fuzzy_match(query: &str, text: &str) -> Option<Vec<usize>> {
+    let lower = |c: char| c.to_lowercase().next().unwrap_or(c);
+    let query = query.chars().map(lower).collect::<Vec<_>>();
+    let text = text.chars().map(lower).collect::<Vec<_>>();
+    if query.is_empty() {
+        return Some(Vec::new());
+    }
+
+    if let Some(start) = text.windows(query.len()).position(|run| run == query)
+    {
+        return Some((start..start + query.len()).collect());
+    }
+
+    let mut wanted = query.iter().peekable();
+    let mut positions = Vec::with_capacity(query.len());
+    for (index, c) in text.iter().enumerate() {
+        if wanted.peek() == Some(&c) {
+            positions.push(index);
+            wanted.next();
+        }
+    }
+    wanted.peek().is_none().then_some(positions)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::fuzzy_match;
+
+    #[test]
+    fn queries_match_runs_first_then_scattered_characters() {
+        assert_eq!(fuzzy_match("CAFE", "Blue Cafe"), Some(vec![5, 6, 7, 8]));
+        assert_eq!(fuzzy_match("bcf", "Blue Cafe"), Some(vec![0, 5, 7]));
+        assert_eq!(fuzzy_match("fc", "Blue Cafe"), None);
+        assert_eq!(fuzzy_match("", "Blue Cafe"), Some(Vec::new()));
+    }
+}
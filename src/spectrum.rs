@@ -0,0 +1,171 @@
+//! How crowded each Wi-Fi channel is, from the access points of the last
+//! scan, and which channel would be the quietest for an access point of
+//! one's own.
+
+use crate::wifi::{WifiAccessPoint, WifiBand};
+
+/// 20 MHz channels of the 5 GHz band.
+const FIVE_GHZ_CHANNELS: [u32; 25] = [
+    36, 40, 44, 48, 52, 56, 60, 64, 100, 104, 108, 112, 116, 120, 124, 128,
+    132, 136, 140, 144, 149, 153, 157, 161, 165,
+];
+/// The non-overlapping 2.4 GHz channels worth recommending.
+const TWO_POINT_FOUR_GHZ_CHOICES: [u32; 3] = [1, 6, 11];
+
+/// Access points heard on one channel.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ChannelLoad {
+    pub channel: u32,
+    pub access_points: usize,
+    /// Signal of the strongest of them, 0 when there are none.
+    pub strongest: u8,
+}
+
+/// Channels plotted for `band`: every 2.4 GHz channel up to 13, the
+/// 20 MHz channels of 5 GHz and the preferred scanning channels of 6 GHz.
+fn channel_axis(band: WifiBand) -> Vec<u32> {
+    match band {
+        WifiBand::TwoPointFourGhz => (1..=13).collect(),
+        WifiBand::FiveGhz => FIVE_GHZ_CHANNELS.to_vec(),
+        WifiBand::SixGhz => (5..=229).step_by(16).collect(),
+    }
+}
+
+/// Load of every channel of `band`, in channel order. Channels off the
+/// usual axis (14, or a 6 GHz channel that is not a preferred one) are
+/// added when an access point uses them.
+pub fn channel_loads<'a>(
+    access_points: impl IntoIterator<Item = &'a WifiAccessPoint>,
+    band: WifiBand,
+) -> Vec<ChannelLoad> {
+    let mut loads = channel_axis(band)
+        .into_iter()
+        .map(|channel| ChannelLoad {
+            channel,
+            access_points: 0,
+            strongest: 0,
+        })
+        .collect::<Vec<_>>();
+
+    for access_point in access_points {
+        if access_point.band() != band {
+            continue;
+        }
+        let Some(channel) = access_point.channel() else {
+            continue;
+        };
+        let index = match loads.iter().position(|load| load.channel == channel)
+        {
+            Some(index) => index,
+            None => {
+                let index = loads
+                    .iter()
+                    .position(|load| load.channel > channel)
+                    .unwrap_or(loads.len());
+                loads.insert(
+                    index,
+                    ChannelLoad {
+                        channel,
+                        access_points: 0,
+                        strongest: 0,
+                    },
+                );
+                index
+            }
+        };
+        loads[index].access_points += 1;
+        loads[index].strongest =
+            loads[index].strongest.max(access_point.signal_strength);
+    }
+
+    loads
+}
+
+/// The channel with the least interference: on 2.4 GHz one of 1, 6 and 11,
+/// counting every access point within four channels since they overlap;
+/// elsewhere any plotted channel, counting only its own access points.
+/// Louder access points weigh more, and ties go to the lower channel.
+pub fn least_congested(loads: &[ChannelLoad], band: WifiBand) -> Option<u32> {
+    let interference = |channel: u32| -> u32 {
+        loads
+            .iter()
+            .filter(|load| match band {
+                WifiBand::TwoPointFourGhz => load.channel.abs_diff(channel) < 5,
+                _ => load.channel == channel,
+            })
+            .map(|load| load.access_points as u32 * u32::from(load.strongest))
+            .sum()
+    };
+
+    let candidates = match band {
+        WifiBand::TwoPointFourGhz => TWO_POINT_FOUR_GHZ_CHOICES.to_vec(),
+        _ => loads.iter().map(|load| load.channel).collect(),
+    };
+    candidates
+        .into_iter()
+        .min_by_key(|channel| (interference(*channel), *channel))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{ChannelLoad, channel_loads, least_congested};
+    use crate::wifi::{WifiAccessPoint, WifiBand};
+
+    fn access_point(frequency: u32, signal_strength: u8) -> WifiAccessPoint {
+        WifiAccessPoint {
+            bssid: format!("aa:bb:cc:dd:{frequency}"),
+            frequency,
+            signal_strength,
+            associated: false,
+        }
+    }
+
+    #[test]
+    fn access_points_are_counted_per_channel_of_their_band() {
+        let access_points = [
+            access_point(2412, 40),
+            access_point(2412, 75),
+            access_point(2484, 20),
+            access_point(5180, 60),
+        ];
+
+        let loads = channel_loads(&access_points, WifiBand::TwoPointFourGhz);
+
+        assert_eq!(loads.len(), 14, "channel 14 is added when used");
+        assert_eq!(
+            loads[0],
+            ChannelLoad {
+                channel: 1,
+                access_points: 2,
+                strongest: 75,
+            }
+        );
+        assert_eq!(loads[13].channel, 14);
+        assert_eq!(
+            channel_loads(&access_points, WifiBand::FiveGhz)[0].access_points,
+            1
+        );
+    }
+
+    #[test]
+    fn the_quietest_channel_accounts_for_overlap_on_two_point_four_ghz() {
+        let access_points = [
+            access_point(2412, 70),
+            access_point(2412, 50),
+            access_point(2462, 60),
+        ];
+        let loads = channel_loads(&access_points, WifiBand::TwoPointFourGhz);
+        assert_eq!(least_congested(&loads, WifiBand::TwoPointFourGhz), Some(6));
+
+        // Channel 4 overlaps both 1 and 6.
+        let crowded = [access_point(2412, 30), access_point(2427, 90)];
+        let loads = channel_loads(&crowded, WifiBand::TwoPointFourGhz);
+        assert_eq!(
+            least_congested(&loads, WifiBand::TwoPointFourGhz),
+            Some(11)
+        );
+
+        let loads = channel_loads(&[], WifiBand::FiveGhz);
+        assert_eq!(least_congested(&loads, WifiBand::FiveGhz), Some(36));
+    }
+}
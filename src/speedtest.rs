@@ -0,0 +1,179 @@
+//! Download and upload throughput against an HTTP endpoint, measured in
+//! fixed-size chunks so progress can be shown between them.
+
+use std::{
+    error::Error,
+    io,
+    time::{Duration, Instant},
+};
+
+pub const DEFAULT_SPEEDTEST_URL: &str = "https://speed.cloudflare.com";
+
+const CHUNK_BYTES: u64 = 4_000_000;
+const CHUNKS_PER_DIRECTION: u32 = 4;
+const CHUNK_TIMEOUT: Duration = Duration::from_secs(30);
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TransferDirection {
+    Download,
+    Upload,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ChunkTransfer {
+    pub bytes: u64,
+    pub elapsed: Duration,
+}
+
+/// Moves one chunk through `{endpoint}/__down?bytes=N` or `{endpoint}/__up`,
+/// the API of Cloudflare's speed test and servers that mimic it.
+pub fn transfer_chunk(
+    endpoint: &str,
+    direction: TransferDirection,
+) -> Result<ChunkTransfer, Box<dyn Error>> {
+    let agent = ureq::AgentBuilder::new().timeout(CHUNK_TIMEOUT).build();
+    let endpoint = endpoint.trim_end_matches('/');
+    let started = Instant::now();
+
+    let bytes = match direction {
+        TransferDirection::Download => {
+            let response = agent
+                .get(&format!("{endpoint}/__down"))
+                .query("bytes", &CHUNK_BYTES.to_string())
+                .call()?;
+            io::copy(&mut response.into_reader(), &mut io::sink())?
+        }
+        TransferDirection::Upload => {
+            agent
+                .post(&format!("{endpoint}/__up"))
+                .send_bytes(&vec![0; CHUNK_BYTES as usize])?;
+            CHUNK_BYTES
+        }
+    };
+
+    Ok(ChunkTransfer {
+        bytes,
+        elapsed: started.elapsed(),
+    })
+}
+
+/// Chunks moved in one direction so far.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct TransferProgress {
+    pub chunks: u32,
+    pub bytes: u64,
+    pub elapsed: Duration,
+}
+
+impl TransferProgress {
+    fn record(&mut self, chunk: ChunkTransfer) {
+        self.chunks += 1;
+        self.bytes += chunk.bytes;
+        self.elapsed += chunk.elapsed;
+    }
+
+    pub fn is_done(&self) -> bool {
+        self.chunks >= CHUNKS_PER_DIRECTION
+    }
+
+    pub fn percent(&self) -> u8 {
+        (self.chunks.min(CHUNKS_PER_DIRECTION) * 100 / CHUNKS_PER_DIRECTION)
+            as u8
+    }
+
+    /// Average throughput over the chunks so far, in megabits per second.
+    pub fn mbps(&self) -> Option<f64> {
+        let seconds = self.elapsed.as_secs_f64();
+        (seconds > 0.0).then(|| self.bytes as f64 * 8.0 / seconds / 1e6)
+    }
+}
+
+/// A run of the speed test: all download chunks, then all upload chunks.
+/// The first failed chunk ends the run.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct SpeedTest {
+    pub download: TransferProgress,
+    pub upload: TransferProgress,
+    pub error: Option<String>,
+}
+
+impl SpeedTest {
+    pub fn next_direction(&self) -> Option<TransferDirection> {
+        if self.error.is_some() {
+            None
+        } else if !self.download.is_done() {
+            Some(TransferDirection::Download)
+        } else if !self.upload.is_done() {
+            Some(TransferDirection::Upload)
+        } else {
+            None
+        }
+    }
+
+    pub fn is_finished(&self) -> bool {
+        self.next_direction().is_none()
+    }
+
+    pub fn record(
+        &mut self,
+        direction: TransferDirection,
+        result: Result<ChunkTransfer, String>,
+    ) {
+        match (direction, result) {
+            (TransferDirection::Download, Ok(chunk)) => {
+                self.download.record(chunk)
+            }
+            (TransferDirection::Upload, Ok(chunk)) => self.upload.record(chunk),
+            (_, Err(error)) => self.error = Some(error),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use super::{
+        CHUNK_BYTES,
+        CHUNKS_PER_DIRECTION,
+        ChunkTransfer,
+        SpeedTest,
+        TransferDirection,
+    };
+
+    fn chunk(millis: u64) -> Result<ChunkTransfer, String> {
+        Ok(ChunkTransfer {
+            bytes: CHUNK_BYTES,
+            elapsed: Duration::from_millis(millis),
+        })
+    }
+
+    #[test]
+    fn downloads_run_before_uploads() {
+        let mut test = SpeedTest::default();
+        assert_eq!(test.next_direction(), Some(TransferDirection::Download));
+
+        for _ in 0..CHUNKS_PER_DIRECTION {
+            test.record(TransferDirection::Download, chunk(400));
+        }
+        assert_eq!(test.download.percent(), 100);
+        assert_eq!(test.download.mbps(), Some(80.0));
+        assert_eq!(test.next_direction(), Some(TransferDirection::Upload));
+
+        test.record(TransferDirection::Upload, chunk(1600));
+        assert_eq!(test.upload.percent(), 25);
+        assert_eq!(test.upload.mbps(), Some(20.0));
+        assert!(!test.is_finished());
+    }
+
+    #[test]
+    fn a_failed_chunk_ends_the_run() {
+        let mut test = SpeedTest::default();
+        test.record(TransferDirection::Download, chunk(400));
+        test.record(TransferDirection::Download, Err("timed out".to_string()));
+
+        assert!(test.is_finished());
+        assert_eq!(test.download.chunks, 1);
+        assert_eq!(test.error.as_deref(), Some("timed out"));
+    }
+}
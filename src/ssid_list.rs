@@ -0,0 +1,99 @@
+//! SSIDs the user marked, stored one per line next to `config` in
+//! `$XDG_CONFIG_HOME/nm-wifi`: `ignored` networks are left out of the list
+//! unless asked for and are never suggested, `favorites` are pinned to the
+//! top of the list.
+
+use std::{fs, path::PathBuf};
+
+use crate::{
+    config::xdg_path,
+    history::{escape, unescape},
+};
+
+#[derive(Debug, Clone, Default)]
+pub struct SsidList {
+    /// Where the list is saved; `None` keeps it in memory only.
+    pub path: Option<PathBuf>,
+    pub ssids: Vec<String>,
+}
+
+impl SsidList {
+    pub fn ignored_path() -> Option<PathBuf> {
+        xdg_path("XDG_CONFIG_HOME", ".config", "ignored")
+    }
+
+    pub fn favorites_path() -> Option<PathBuf> {
+        xdg_path("XDG_CONFIG_HOME", ".config", "favorites")
+    }
+
+    /// Reads the list at `path`; an unreadable file lists nothing.
+    pub fn load(path: Option<PathBuf>) -> Self {
+        let ssids = path
+            .as_ref()
+            .and_then(|path| fs::read_to_string(path).ok())
+            .map(|contents| {
+                contents
+                    .lines()
+                    .filter(|line| !line.is_empty())
+                    .map(unescape)
+                    .collect()
+            })
+            .unwrap_or_default();
+
+        Self { path, ssids }
+    }
+
+    pub fn contains(&self, ssid: &str) -> bool {
+        self.ssids.iter().any(|listed| listed == ssid)
+    }
+
+    /// Adds `ssid`, or removes it when it was listed, and saves the list.
+    /// Returns whether it is listed now.
+    pub fn toggle(&mut self, ssid: &str) -> bool {
+        let listed = if self.contains(ssid) {
+            self.ssids.retain(|listed| listed != ssid);
+            false
+        } else {
+            self.ssids.push(ssid.to_string());
+            true
+        };
+        self.save();
+        listed
+    }
+
+    fn save(&self) {
+        let Some(path) = &self.path else {
+            return;
+        };
+        if let Some(parent) = path.parent() {
+            let _ = fs::create_dir_all(parent);
+        }
+        let contents = self
+            .ssids
+            .iter()
+            .map(|ssid| format!("{}\n", escape(ssid)))
+            .collect::<String>();
+        let _ = fs::write(path, contents);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::SsidList;
+
+    #[test]
+    fn the_list_survives_a_restart() {
+        let path = std::env::temp_dir()
+            .join(format!("nm-wifi-ssid-list-{}", std::process::id()));
+        let mut list = SsidList::load(Some(path.clone()));
+        assert!(list.toggle("Neighbour\n5G"));
+        assert!(list.toggle("Printer"));
+        assert!(!list.toggle("Printer"));
+
+        let reloaded = SsidList::load(Some(path.clone()));
+        std::fs::remove_file(&path).expect("remove fixture");
+
+        assert_eq!(reloaded.ssids, ["Neighbour\n5G"]);
+        assert!(!reloaded.contains("Printer"));
+    }
+}
@@ -0,0 +1,275 @@
+//! One-line connection status for status bars, printed by
+//! `nm-wifi --status [--format plain|tmux]`.
+//!
+//! Status bars poll every few seconds, so the answer comes from
+//! `$XDG_RUNTIME_DIR/nm-wifi/status` while it is fresh. A running nm-wifi
+//! rewrites that file on every signal refresh; otherwise `--status` asks
+//! NetworkManager for the active access point (without scanning) and
+//! caches what it got.
+
+use std::{
+    error::Error,
+    fs,
+    path::{Path, PathBuf},
+    time::{Duration, SystemTime, UNIX_EPOCH},
+};
+
+use crate::{
+    config::xdg_path,
+    history::{escape, unescape},
+    network::get_connected_ssid,
+    wifi::WifiNetwork,
+};
+
+/// How old a cached status may be before NetworkManager is asked again.
+const CACHE_MAX_AGE: Duration = Duration::from_secs(10);
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum StatusFormat {
+    #[default]
+    Plain,
+    /// Wrapped in `#[fg=...]` styles, with `#` escaped for tmux.
+    Tmux,
+}
+
+impl StatusFormat {
+    pub fn from_key(key: &str) -> Option<Self> {
+        match key {
+            "plain" => Some(Self::Plain),
+            "tmux" => Some(Self::Tmux),
+            _ => None,
+        }
+    }
+}
+
+/// Reads the command line: `None` runs the TUI, `Some` prints the status in
+/// that format.
+pub fn parse_args(
+    args: impl IntoIterator<Item = String>,
+) -> Result<Option<StatusFormat>, String> {
+    let mut status = false;
+    let mut format = StatusFormat::default();
+    let mut args = args.into_iter();
+
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--status" => status = true,
+            "--format" => {
+                let key = args.next().ok_or("--format needs a value")?;
+                format = StatusFormat::from_key(&key).ok_or_else(|| {
+                    format!("unknown format `{key}`, expected plain or tmux")
+                })?;
+            }
+            _ => return Err(format!("unknown argument `{arg}`")),
+        }
+    }
+
+    Ok(status.then_some(format))
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct LinkStatus {
+    pub ssid: String,
+    pub signal_strength: u8,
+}
+
+impl LinkStatus {
+    pub fn of(network: &WifiNetwork) -> Self {
+        Self {
+            ssid: network.ssid.clone(),
+            signal_strength: network.signal_strength,
+        }
+    }
+}
+
+pub fn render_status(
+    status: Option<&LinkStatus>,
+    format: StatusFormat,
+) -> String {
+    let Some(status) = status else {
+        return match format {
+            StatusFormat::Plain => "disconnected".to_string(),
+            StatusFormat::Tmux => "#[fg=colour8]offline#[default]".to_string(),
+        };
+    };
+
+    match format {
+        StatusFormat::Plain => {
+            format!("{} {}%", status.ssid, status.signal_strength)
+        }
+        StatusFormat::Tmux => {
+            let colour = match status.signal_strength {
+                70.. => "green",
+                40..70 => "yellow",
+                _ => "red",
+            };
+            format!(
+                "#[fg={colour}]{} {}%#[default]",
+                status.ssid.replace('#', "##"),
+                status.signal_strength
+            )
+        }
+    }
+}
+
+pub fn cache_path() -> Option<PathBuf> {
+    xdg_path("XDG_RUNTIME_DIR", ".cache", "status")
+}
+
+fn unix_seconds(time: SystemTime) -> u64 {
+    time.duration_since(UNIX_EPOCH)
+        .map(|elapsed| elapsed.as_secs())
+        .unwrap_or_default()
+}
+
+/// Saves `status` as `<timestamp>[\t<signal>\t<ssid>]`.
+pub fn write_cache(path: &Path, status: Option<&LinkStatus>, now: SystemTime) {
+    let mut contents = unix_seconds(now).to_string();
+    if let Some(status) = status {
+        contents.push_str(&format!(
+            "\t{}\t{}",
+            status.signal_strength,
+            escape(&status.ssid)
+        ));
+    }
+    if let Some(parent) = path.parent() {
+        let _ = fs::create_dir_all(parent);
+    }
+    let _ = fs::write(path, contents + "\n");
+}
+
+/// The cached status, if the file is younger than `CACHE_MAX_AGE`. The
+/// outer `None` means there is nothing usable cached.
+pub fn read_cache(path: &Path, now: SystemTime) -> Option<Option<LinkStatus>> {
+    let contents = fs::read_to_string(path).ok()?;
+    let mut fields = contents.trim_end_matches('\n').splitn(3, '\t');
+    let written = fields.next()?.parse::<u64>().ok()?;
+    if unix_seconds(now).saturating_sub(written) > CACHE_MAX_AGE.as_secs() {
+        return None;
+    }
+
+    match (fields.next(), fields.next()) {
+        (Some(signal), Some(ssid)) => Some(Some(LinkStatus {
+            ssid: unescape(ssid),
+            signal_strength: signal.parse().ok()?,
+        })),
+        _ => Some(None),
+    }
+}
+
+#[cfg(feature = "demo")]
+fn access_point_signals()
+-> Result<Vec<crate::wifi::WifiAccessPoint>, Box<dyn Error>> {
+    crate::network::demo::get_access_point_signals()
+}
+
+#[cfg(not(feature = "demo"))]
+fn access_point_signals()
+-> Result<Vec<crate::wifi::WifiAccessPoint>, Box<dyn Error>> {
+    crate::network::networkmanager::get_access_point_signals()
+}
+
+/// The SSID and signal of the associated access point, read from
+/// NetworkManager without requesting a scan.
+fn query_link_status() -> Result<Option<LinkStatus>, Box<dyn Error>> {
+    let Some(ssid) = get_connected_ssid()? else {
+        return Ok(None);
+    };
+    let signal_strength = access_point_signals()?
+        .into_iter()
+        .find(|access_point| access_point.associated)
+        .map(|access_point| access_point.signal_strength)
+        .unwrap_or_default();
+
+    Ok(Some(LinkStatus {
+        ssid,
+        signal_strength,
+    }))
+}
+
+/// The status line to print, from the cache when it is fresh.
+pub fn current_status(format: StatusFormat) -> Result<String, Box<dyn Error>> {
+    let path = cache_path();
+    let now = SystemTime::now();
+    if let Some(cached) = path.as_deref().and_then(|path| read_cache(path, now))
+    {
+        return Ok(render_status(cached.as_ref(), format));
+    }
+
+    let status = query_link_status()?;
+    if let Some(path) = &path {
+        write_cache(path, status.as_ref(), now);
+    }
+    Ok(render_status(status.as_ref(), format))
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::{Duration, SystemTime};
+
+    use super::{
+        LinkStatus,
+        StatusFormat,
+        parse_args,
+        read_cache,
+        render_status,
+        write_cache,
+    };
+
+    #[test]
+    fn status_flags_are_parsed() {
+        let args = |line: &str| {
+            parse_args(line.split_whitespace().map(str::to_string))
+        };
+
+        assert_eq!(args(""), Ok(None));
+        assert_eq!(args("--status"), Ok(Some(StatusFormat::Plain)));
+        assert_eq!(
+            args("--format tmux --status"),
+            Ok(Some(StatusFormat::Tmux))
+        );
+        assert!(args("--status --format i3").is_err());
+        assert!(args("--status --format").is_err());
+        assert!(args("--scan").is_err());
+    }
+
+    #[test]
+    fn tmux_output_is_coloured_and_escaped() {
+        let status = LinkStatus {
+            ssid: "Cafe #2".to_string(),
+            signal_strength: 55,
+        };
+
+        assert_eq!(
+            render_status(Some(&status), StatusFormat::Tmux),
+            "#[fg=yellow]Cafe ##2 55%#[default]"
+        );
+        assert_eq!(
+            render_status(Some(&status), StatusFormat::Plain),
+            "Cafe #2 55%"
+        );
+        assert_eq!(render_status(None, StatusFormat::Plain), "disconnected");
+    }
+
+    #[test]
+    fn the_cache_is_used_only_while_fresh() {
+        let path = std::env::temp_dir()
+            .join(format!("nm-wifi-status-{}", std::process::id()));
+        let written = SystemTime::UNIX_EPOCH + Duration::from_secs(1_000);
+        let status = LinkStatus {
+            ssid: "Home\tNet".to_string(),
+            signal_strength: 80,
+        };
+        write_cache(&path, Some(&status), written);
+
+        let fresh = read_cache(&path, written + Duration::from_secs(5));
+        let stale = read_cache(&path, written + Duration::from_secs(60));
+        write_cache(&path, None, written);
+        let offline = read_cache(&path, written);
+        std::fs::remove_file(&path).expect("remove fixture");
+
+        assert_eq!(fresh, Some(Some(status)));
+        assert_eq!(stale, None);
+        assert_eq!(offline, Some(None));
+    }
+}
@@ -0,0 +1,156 @@
+//! Site survey: the signal of one access point sampled every half second
+//! while walking around, to find the dead zones of a home, and exported as
+//! CSV.
+
+use std::{
+    fs,
+    io,
+    path::Path,
+    time::{Duration, Instant},
+};
+
+use crate::wifi::WifiAccessPoint;
+
+/// How often the surveyed access point is sampled.
+pub const SURVEY_SAMPLE_INTERVAL: Duration = Duration::from_millis(500);
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct SurveySample {
+    /// Time since the survey started.
+    pub elapsed: Duration,
+    /// `None` when the access point was not heard at all.
+    pub signal_strength: Option<u8>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct SurveyStats {
+    pub min: u8,
+    pub average: u8,
+    pub max: u8,
+    /// Samples in which the access point was not heard.
+    pub missed: usize,
+}
+
+#[derive(Debug, Clone)]
+pub struct SiteSurvey {
+    pub ssid: String,
+    pub bssid: String,
+    pub channel: Option<u32>,
+    started: Instant,
+    pub samples: Vec<SurveySample>,
+    pub export_result: Option<Result<String, String>>,
+}
+
+impl SiteSurvey {
+    pub fn new(
+        ssid: &str,
+        access_point: &WifiAccessPoint,
+        now: Instant,
+    ) -> Self {
+        Self {
+            ssid: ssid.to_string(),
+            bssid: access_point.bssid.clone(),
+            channel: access_point.channel(),
+            started: now,
+            samples: Vec::new(),
+            export_result: None,
+        }
+    }
+
+    pub fn record(&mut self, signal_strength: Option<u8>, at: Instant) {
+        self.samples.push(SurveySample {
+            elapsed: at.saturating_duration_since(self.started),
+            signal_strength,
+        });
+    }
+
+    /// Drops the samples so far, e.g. before walking to another floor.
+    pub fn restart(&mut self, now: Instant) {
+        self.started = now;
+        self.samples.clear();
+        self.export_result = None;
+    }
+
+    pub fn current(&self) -> Option<u8> {
+        self.samples.last()?.signal_strength
+    }
+
+    /// Range of the samples the access point was heard in; `None` until it
+    /// was heard once.
+    pub fn stats(&self) -> Option<SurveyStats> {
+        let heard = self
+            .samples
+            .iter()
+            .filter_map(|sample| sample.signal_strength)
+            .collect::<Vec<_>>();
+        let total = heard.iter().map(|signal| u32::from(*signal)).sum::<u32>();
+        Some(SurveyStats {
+            min: *heard.iter().min()?,
+            average: (total / heard.len() as u32) as u8,
+            max: *heard.iter().max()?,
+            missed: self.samples.len() - heard.len(),
+        })
+    }
+
+    /// One `seconds,signal` row per sample; missed samples leave the signal
+    /// empty.
+    pub fn to_csv(&self) -> String {
+        let mut csv = String::from("seconds,signal\n");
+        for sample in &self.samples {
+            csv.push_str(&format!(
+                "{:.1},{}\n",
+                sample.elapsed.as_secs_f64(),
+                sample
+                    .signal_strength
+                    .map(|signal| signal.to_string())
+                    .unwrap_or_default()
+            ));
+        }
+        csv
+    }
+
+    pub fn export(&self, path: &Path) -> io::Result<()> {
+        fs::write(path, self.to_csv())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::{Duration, Instant};
+
+    use super::{SiteSurvey, SurveyStats};
+    use crate::wifi::WifiAccessPoint;
+
+    #[test]
+    fn stats_leave_out_missed_samples() {
+        let start = Instant::now();
+        let access_point = WifiAccessPoint {
+            bssid: "aa:bb:cc:dd:ee:ff".to_string(),
+            frequency: 5180,
+            signal_strength: 80,
+            associated: true,
+        };
+        let mut survey = SiteSurvey::new("home", &access_point, start);
+        assert_eq!(survey.channel, Some(36));
+        assert_eq!(survey.stats(), None);
+
+        survey.record(Some(80), start + Duration::from_millis(500));
+        survey.record(None, start + Duration::from_secs(1));
+        survey.record(Some(41), start + Duration::from_millis(1500));
+
+        assert_eq!(survey.current(), Some(41));
+        assert_eq!(
+            survey.stats(),
+            Some(SurveyStats {
+                min: 41,
+                average: 60,
+                max: 80,
+                missed: 1,
+            })
+        );
+        assert_eq!(survey.to_csv(), "seconds,signal\n0.5,80\n1.0,\n1.5,41\n");
+
+        survey.restart(start + Duration::from_secs(2));
+        assert!(survey.samples.is_empty());
+    }
+}
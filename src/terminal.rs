@@ -0,0 +1,230 @@
+//! What the terminal shows the UI on. Its background colour picks between
+//! the dark and the light palette of the `auto` theme, and its colour depth
+//! decides whether the palette is drawn as is or approximated; `NO_COLOR`
+//! turns colours off altogether.
+
+use std::{
+    env,
+    fs::{File, OpenOptions},
+    io::{Read, Write},
+    os::fd::AsRawFd,
+    time::{Duration, Instant},
+};
+
+/// How long the terminal gets to answer the background colour query.
+const QUERY_TIMEOUT: Duration = Duration::from_millis(200);
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum Background {
+    #[default]
+    Dark,
+    Light,
+}
+
+impl Background {
+    /// Asks the terminal for its background colour (OSC 11), falling back
+    /// to `$COLORFGBG`. Runs in raw mode, so the answer is not echoed, and
+    /// before the app reads any input.
+    pub fn detect() -> Option<Self> {
+        query_background()
+            .as_deref()
+            .and_then(Self::from_osc_reply)
+            .or_else(|| Self::from_colorfgbg(&env::var("COLORFGBG").ok()?))
+    }
+
+    /// Reads an `ESC ] 11 ; rgb:RRRR/GGGG/BBBB` reply, whose channels have
+    /// one to four hex digits each.
+    fn from_osc_reply(reply: &[u8]) -> Option<Self> {
+        let reply = String::from_utf8_lossy(reply);
+        let (_, color) = reply.split_once("]11;rgb:")?;
+        let color = color.split(['\x07', '\x1b']).next()?;
+
+        let mut channels = color.split('/').map(|digits| {
+            if !(1..=4).contains(&digits.len()) {
+                return None;
+            }
+            let value = u16::from_str_radix(digits, 16).ok()?;
+            let max = (1u32 << (4 * digits.len())) - 1;
+            Some(f64::from(value) / f64::from(max))
+        });
+        let (red, green, blue) =
+            (channels.next()??, channels.next()??, channels.next()??);
+
+        let luminance = 0.2126 * red + 0.7152 * green + 0.0722 * blue;
+        Some(if luminance > 0.5 {
+            Self::Light
+        } else {
+            Self::Dark
+        })
+    }
+
+    /// `COLORFGBG` is `foreground;background` (rxvt sometimes puts another
+    /// field in between) in ANSI colour numbers; white and the bright
+    /// colours but black make a light background.
+    fn from_colorfgbg(value: &str) -> Option<Self> {
+        let background = value.rsplit(';').next()?.parse::<u8>().ok()?;
+        Some(match background {
+            7 | 9..=15 => Self::Light,
+            _ => Self::Dark,
+        })
+    }
+}
+
+/// The `color_depth` setting, and how many colours the terminal shows.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum ColorDepth {
+    /// Decide from `$COLORTERM` and `$TERM`.
+    #[default]
+    Auto,
+    TrueColor,
+    /// The xterm 256-colour palette.
+    Ansi256,
+    /// The 16 colours of the terminal's own scheme.
+    Ansi16,
+}
+
+impl ColorDepth {
+    pub fn from_key(key: &str) -> Option<Self> {
+        match key {
+            "auto" => Some(Self::Auto),
+            "truecolor" => Some(Self::TrueColor),
+            "256" => Some(Self::Ansi256),
+            "16" => Some(Self::Ansi16),
+            _ => None,
+        }
+    }
+
+    pub fn resolve(self) -> Self {
+        match self {
+            Self::Auto => Self::for_terminal(
+                env::var("COLORTERM").ok().as_deref(),
+                env::var("TERM").ok().as_deref(),
+            ),
+            depth => depth,
+        }
+    }
+
+    /// Truecolor terminals say so in `$COLORTERM` (or with a `-direct`
+    /// terminfo entry); SSH does not pass that on, so `$TERM` names the
+    /// 256-colour ones. Anything else gets the 16 colours every terminal
+    /// has.
+    fn for_terminal(colorterm: Option<&str>, term: Option<&str>) -> Self {
+        let term = term.unwrap_or("dumb");
+        if matches!(colorterm, Some("truecolor" | "24bit"))
+            || term.ends_with("-direct")
+        {
+            Self::TrueColor
+        } else if term.contains("256color") {
+            Self::Ansi256
+        } else {
+            Self::Ansi16
+        }
+    }
+}
+
+/// Whether `$NO_COLOR` asks for output without colours; see
+/// <https://no-color.org>.
+pub fn no_color_requested() -> bool {
+    env::var_os("NO_COLOR").is_some_and(|value| !value.is_empty())
+}
+
+/// Everything the terminal sent back for an OSC 11 query followed by a
+/// primary device attributes query. Every terminal answers the latter, so
+/// the reading stops after it whether or not OSC 11 is understood. Bytes
+/// are only read once `poll` says they are there, so nothing is left
+/// reading the terminal when the time is up.
+fn query_background() -> Option<Vec<u8>> {
+    let mut tty = OpenOptions::new()
+        .read(true)
+        .write(true)
+        .open("/dev/tty")
+        .ok()?;
+    tty.write_all(b"\x1b]11;?\x1b\\\x1b[c").ok()?;
+    tty.flush().ok()?;
+
+    let deadline = Instant::now() + QUERY_TIMEOUT;
+    let mut reply = Vec::new();
+    let mut byte = [0];
+    while !device_attributes_end(&reply) {
+        let left = deadline.saturating_duration_since(Instant::now());
+        if left.is_zero() || !readable(&tty, left) {
+            break;
+        }
+        if tty.read(&mut byte).ok()? == 0 {
+            break;
+        }
+        reply.push(byte[0]);
+    }
+    Some(reply)
+}
+
+/// Waits up to `timeout` for `file` to have input.
+fn readable(file: &File, timeout: Duration) -> bool {
+    let mut poll = libc::pollfd {
+        fd: file.as_raw_fd(),
+        events: libc::POLLIN,
+        revents: 0,
+    };
+    let millis = timeout.as_millis().try_into().unwrap_or(libc::c_int::MAX);
+    // SAFETY: `poll` points at one initialised `pollfd` for the call.
+    unsafe { libc::poll(&mut poll, 1, millis) > 0 }
+}
+
+/// Whether `reply` ends with the `ESC [ ? ... c` device attributes answer.
+fn device_attributes_end(reply: &[u8]) -> bool {
+    reply.ends_with(b"c") && reply.windows(3).any(|window| window == b"\x1b[?")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{Background, ColorDepth, device_attributes_end};
+
+    #[test]
+    fn osc_replies_are_judged_by_luminance() {
+        assert_eq!(
+            Background::from_osc_reply(b"\x1b]11;rgb:1e1e/1e1e/2e2e\x1b\\"),
+            Some(Background::Dark)
+        );
+        assert_eq!(
+            Background::from_osc_reply(b"\x1b]11;rgb:ef/f1/f5\x07\x1b[?62;c"),
+            Some(Background::Light)
+        );
+        assert_eq!(Background::from_osc_reply(b"\x1b[?1;2c"), None);
+        assert_eq!(
+            Background::from_osc_reply(b"\x1b]11;rgb:ffff/ffff\x07"),
+            None
+        );
+    }
+
+    #[test]
+    fn colorfgbg_names_the_background_last() {
+        assert_eq!(Background::from_colorfgbg("15;0"), Some(Background::Dark));
+        assert_eq!(Background::from_colorfgbg("0;15"), Some(Background::Light));
+        assert_eq!(
+            Background::from_colorfgbg("0;default;7"),
+            Some(Background::Light)
+        );
+        assert_eq!(Background::from_colorfgbg("default"), None);
+    }
+
+    #[test]
+    fn the_reader_stops_after_the_device_attributes() {
+        assert!(!device_attributes_end(b"\x1b]11;rgb:1e1e/1e1e/2e2c"));
+        assert!(device_attributes_end(b"\x1b]11;rgb:0/0/0\x07\x1b[?64;1c"));
+    }
+
+    #[test]
+    fn colour_depth_follows_colorterm_then_term() {
+        let detect = ColorDepth::for_terminal;
+        assert_eq!(
+            detect(Some("truecolor"), Some("xterm-256color")),
+            ColorDepth::TrueColor
+        );
+        assert_eq!(detect(None, Some("xterm-direct")), ColorDepth::TrueColor);
+        assert_eq!(detect(None, Some("tmux-256color")), ColorDepth::Ansi256);
+        assert_eq!(detect(None, Some("xterm")), ColorDepth::Ansi16);
+        assert_eq!(detect(None, None), ColorDepth::Ansi16);
+        assert_eq!(ColorDepth::Ansi256.resolve(), ColorDepth::Ansi256);
+        assert_eq!(ColorDepth::from_key("88"), None);
+    }
+}
@@ -1,33 +1,510 @@
+//! Colour palettes. Every role of the Catppuccin palette is a field of
+//! `Theme`, so the four flavors swap in for one another; the terminal theme
+//! maps the roles onto the 16 ANSI colours of the terminal's own scheme.
+//! `ThemeColors` holds the colours of the `[theme]` config section, laid
+//! over whichever palette is selected. Terminals without truecolor get the
+//! palette approximated in the colours they have.
+
 use ratatui::style::Color;
 
-#[allow(dead_code)]
-pub struct CatppuccinColors;
-
-#[allow(dead_code)]
-impl CatppuccinColors {
-    pub const BASE: Color = Color::Rgb(30, 30, 46); // #1e1e2e
-    pub const MANTLE: Color = Color::Rgb(24, 24, 37); // #181825
-    pub const SURFACE0: Color = Color::Rgb(49, 50, 68); // #313244
-    pub const SURFACE1: Color = Color::Rgb(69, 71, 90); // #45475a
-    pub const SURFACE2: Color = Color::Rgb(88, 91, 112); // #585b70
-    pub const TEXT: Color = Color::Rgb(205, 214, 244); // #cdd6f4
-    pub const SUBTEXT1: Color = Color::Rgb(186, 194, 222); // #bac2de
-    pub const SUBTEXT0: Color = Color::Rgb(166, 173, 200); // #a6adc8
-    pub const OVERLAY2: Color = Color::Rgb(147, 153, 178); // #9399b2
-    pub const OVERLAY1: Color = Color::Rgb(127, 132, 156); // #7f849c
-    pub const OVERLAY0: Color = Color::Rgb(108, 112, 134); // #6c7086
-    pub const LAVENDER: Color = Color::Rgb(180, 190, 254); // #b4befe
-    pub const BLUE: Color = Color::Rgb(137, 180, 250); // #89b4fa
-    pub const SAPPHIRE: Color = Color::Rgb(116, 199, 236); // #74c7ec
-    pub const SKY: Color = Color::Rgb(137, 220, 235); // #89dceb
-    pub const TEAL: Color = Color::Rgb(148, 226, 213); // #94e2d5
-    pub const GREEN: Color = Color::Rgb(166, 227, 161); // #a6e3a1
-    pub const YELLOW: Color = Color::Rgb(249, 226, 175); // #f9e2af
-    pub const PEACH: Color = Color::Rgb(250, 179, 135); // #fab387
-    pub const MAROON: Color = Color::Rgb(235, 160, 172); // #eba0ac
-    pub const RED: Color = Color::Rgb(243, 139, 168); // #f38ba8
-    pub const MAUVE: Color = Color::Rgb(203, 166, 247); // #cba6f7
-    pub const PINK: Color = Color::Rgb(245, 194, 231); // #f5c2e7
-    pub const FLAMINGO: Color = Color::Rgb(242, 205, 205); // #f2cdcd
-    pub const ROSEWATER: Color = Color::Rgb(245, 224, 220); // #f5e0dc
+use crate::terminal::{Background, ColorDepth};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Theme {
+    pub base: Color,
+    pub mantle: Color,
+    pub surface0: Color,
+    pub surface1: Color,
+    pub surface2: Color,
+    pub text: Color,
+    pub subtext1: Color,
+    pub subtext0: Color,
+    pub overlay2: Color,
+    pub overlay1: Color,
+    pub overlay0: Color,
+    pub lavender: Color,
+    pub blue: Color,
+    pub sapphire: Color,
+    pub sky: Color,
+    pub teal: Color,
+    pub green: Color,
+    pub yellow: Color,
+    pub peach: Color,
+    pub maroon: Color,
+    pub red: Color,
+    pub mauve: Color,
+    pub pink: Color,
+    pub flamingo: Color,
+    pub rosewater: Color,
+}
+
+impl Theme {
+    /// This palette as a terminal of `depth` shows it, with every RGB colour
+    /// replaced by the closest one the terminal has.
+    pub fn for_depth(mut self, depth: ColorDepth) -> Self {
+        let approximate: fn(u8, u8, u8) -> Color = match depth {
+            ColorDepth::Auto | ColorDepth::TrueColor => return self,
+            ColorDepth::Ansi256 => nearest_indexed,
+            ColorDepth::Ansi16 => nearest_ansi,
+        };
+        for color in [
+            &mut self.base,
+            &mut self.mantle,
+            &mut self.surface0,
+            &mut self.surface1,
+            &mut self.surface2,
+            &mut self.text,
+            &mut self.subtext1,
+            &mut self.subtext0,
+            &mut self.overlay2,
+            &mut self.overlay1,
+            &mut self.overlay0,
+            &mut self.lavender,
+            &mut self.blue,
+            &mut self.sapphire,
+            &mut self.sky,
+            &mut self.teal,
+            &mut self.green,
+            &mut self.yellow,
+            &mut self.peach,
+            &mut self.maroon,
+            &mut self.red,
+            &mut self.mauve,
+            &mut self.pink,
+            &mut self.flamingo,
+            &mut self.rosewater,
+        ] {
+            if let Color::Rgb(red, green, blue) = *color {
+                *color = approximate(red, green, blue);
+            }
+        }
+        self
+    }
+
+    /// The colour of the role called `name`, as the `[theme]` section
+    /// spells it.
+    fn role_mut(&mut self, name: &str) -> Option<&mut Color> {
+        match name {
+            "base" => Some(&mut self.base),
+            "mantle" => Some(&mut self.mantle),
+            "surface0" => Some(&mut self.surface0),
+            "surface1" => Some(&mut self.surface1),
+            "surface2" => Some(&mut self.surface2),
+            "text" => Some(&mut self.text),
+            "subtext1" => Some(&mut self.subtext1),
+            "subtext0" => Some(&mut self.subtext0),
+            "overlay2" => Some(&mut self.overlay2),
+            "overlay1" => Some(&mut self.overlay1),
+            "overlay0" => Some(&mut self.overlay0),
+            "lavender" => Some(&mut self.lavender),
+            "blue" => Some(&mut self.blue),
+            "sapphire" => Some(&mut self.sapphire),
+            "sky" => Some(&mut self.sky),
+            "teal" => Some(&mut self.teal),
+            "green" => Some(&mut self.green),
+            "yellow" => Some(&mut self.yellow),
+            "peach" => Some(&mut self.peach),
+            "maroon" => Some(&mut self.maroon),
+            "red" => Some(&mut self.red),
+            "mauve" => Some(&mut self.mauve),
+            "pink" => Some(&mut self.pink),
+            "flamingo" => Some(&mut self.flamingo),
+            "rosewater" => Some(&mut self.rosewater),
+            _ => None,
+        }
+    }
+}
+
+/// Catppuccin Mocha, the darkest flavor and the default.
+pub const MOCHA: Theme = Theme {
+    base: Color::Rgb(30, 30, 46),         // #1e1e2e
+    mantle: Color::Rgb(24, 24, 37),       // #181825
+    surface0: Color::Rgb(49, 50, 68),     // #313244
+    surface1: Color::Rgb(69, 71, 90),     // #45475a
+    surface2: Color::Rgb(88, 91, 112),    // #585b70
+    text: Color::Rgb(205, 214, 244),      // #cdd6f4
+    subtext1: Color::Rgb(186, 194, 222),  // #bac2de
+    subtext0: Color::Rgb(166, 173, 200),  // #a6adc8
+    overlay2: Color::Rgb(147, 153, 178),  // #9399b2
+    overlay1: Color::Rgb(127, 132, 156),  // #7f849c
+    overlay0: Color::Rgb(108, 112, 134),  // #6c7086
+    lavender: Color::Rgb(180, 190, 254),  // #b4befe
+    blue: Color::Rgb(137, 180, 250),      // #89b4fa
+    sapphire: Color::Rgb(116, 199, 236),  // #74c7ec
+    sky: Color::Rgb(137, 220, 235),       // #89dceb
+    teal: Color::Rgb(148, 226, 213),      // #94e2d5
+    green: Color::Rgb(166, 227, 161),     // #a6e3a1
+    yellow: Color::Rgb(249, 226, 175),    // #f9e2af
+    peach: Color::Rgb(250, 179, 135),     // #fab387
+    maroon: Color::Rgb(235, 160, 172),    // #eba0ac
+    red: Color::Rgb(243, 139, 168),       // #f38ba8
+    mauve: Color::Rgb(203, 166, 247),     // #cba6f7
+    pink: Color::Rgb(245, 194, 231),      // #f5c2e7
+    flamingo: Color::Rgb(242, 205, 205),  // #f2cdcd
+    rosewater: Color::Rgb(245, 224, 220), // #f5e0dc
+};
+
+/// Catppuccin Macchiato.
+pub const MACCHIATO: Theme = Theme {
+    base: Color::Rgb(36, 39, 58),         // #24273a
+    mantle: Color::Rgb(30, 32, 48),       // #1e2030
+    surface0: Color::Rgb(54, 58, 79),     // #363a4f
+    surface1: Color::Rgb(73, 77, 100),    // #494d64
+    surface2: Color::Rgb(91, 96, 120),    // #5b6078
+    text: Color::Rgb(202, 211, 245),      // #cad3f5
+    subtext1: Color::Rgb(184, 192, 224),  // #b8c0e0
+    subtext0: Color::Rgb(165, 173, 203),  // #a5adcb
+    overlay2: Color::Rgb(147, 154, 183),  // #939ab7
+    overlay1: Color::Rgb(128, 135, 162),  // #8087a2
+    overlay0: Color::Rgb(110, 115, 141),  // #6e738d
+    lavender: Color::Rgb(183, 189, 248),  // #b7bdf8
+    blue: Color::Rgb(138, 173, 244),      // #8aadf4
+    sapphire: Color::Rgb(125, 196, 228),  // #7dc4e4
+    sky: Color::Rgb(145, 215, 227),       // #91d7e3
+    teal: Color::Rgb(139, 213, 202),      // #8bd5ca
+    green: Color::Rgb(166, 218, 149),     // #a6da95
+    yellow: Color::Rgb(238, 212, 159),    // #eed49f
+    peach: Color::Rgb(245, 169, 127),     // #f5a97f
+    maroon: Color::Rgb(238, 153, 160),    // #ee99a0
+    red: Color::Rgb(237, 135, 150),       // #ed8796
+    mauve: Color::Rgb(198, 160, 246),     // #c6a0f6
+    pink: Color::Rgb(245, 189, 230),      // #f5bde6
+    flamingo: Color::Rgb(240, 198, 198),  // #f0c6c6
+    rosewater: Color::Rgb(244, 219, 214), // #f4dbd6
+};
+
+/// Catppuccin Frappé.
+pub const FRAPPE: Theme = Theme {
+    base: Color::Rgb(48, 52, 70),         // #303446
+    mantle: Color::Rgb(41, 44, 60),       // #292c3c
+    surface0: Color::Rgb(65, 69, 89),     // #414559
+    surface1: Color::Rgb(81, 87, 109),    // #51576d
+    surface2: Color::Rgb(98, 104, 128),   // #626880
+    text: Color::Rgb(198, 208, 245),      // #c6d0f5
+    subtext1: Color::Rgb(181, 191, 226),  // #b5bfe2
+    subtext0: Color::Rgb(165, 173, 206),  // #a5adce
+    overlay2: Color::Rgb(148, 156, 187),  // #949cbb
+    overlay1: Color::Rgb(131, 139, 167),  // #838ba7
+    overlay0: Color::Rgb(115, 121, 148),  // #737994
+    lavender: Color::Rgb(186, 187, 241),  // #babbf1
+    blue: Color::Rgb(140, 170, 238),      // #8caaee
+    sapphire: Color::Rgb(133, 193, 220),  // #85c1dc
+    sky: Color::Rgb(153, 209, 219),       // #99d1db
+    teal: Color::Rgb(129, 200, 190),      // #81c8be
+    green: Color::Rgb(166, 209, 137),     // #a6d189
+    yellow: Color::Rgb(229, 200, 144),    // #e5c890
+    peach: Color::Rgb(239, 159, 118),     // #ef9f76
+    maroon: Color::Rgb(234, 153, 156),    // #ea999c
+    red: Color::Rgb(231, 130, 132),       // #e78284
+    mauve: Color::Rgb(202, 158, 230),     // #ca9ee6
+    pink: Color::Rgb(244, 184, 228),      // #f4b8e4
+    flamingo: Color::Rgb(238, 190, 190),  // #eebebe
+    rosewater: Color::Rgb(242, 213, 207), // #f2d5cf
+};
+
+/// Catppuccin Latte, the light flavor.
+pub const LATTE: Theme = Theme {
+    base: Color::Rgb(239, 241, 245),      // #eff1f5
+    mantle: Color::Rgb(230, 233, 239),    // #e6e9ef
+    surface0: Color::Rgb(204, 208, 218),  // #ccd0da
+    surface1: Color::Rgb(188, 192, 204),  // #bcc0cc
+    surface2: Color::Rgb(172, 176, 190),  // #acb0be
+    text: Color::Rgb(76, 79, 105),        // #4c4f69
+    subtext1: Color::Rgb(92, 95, 119),    // #5c5f77
+    subtext0: Color::Rgb(108, 111, 133),  // #6c6f85
+    overlay2: Color::Rgb(124, 127, 147),  // #7c7f93
+    overlay1: Color::Rgb(140, 143, 161),  // #8c8fa1
+    overlay0: Color::Rgb(156, 160, 176),  // #9ca0b0
+    lavender: Color::Rgb(114, 135, 253),  // #7287fd
+    blue: Color::Rgb(30, 102, 245),       // #1e66f5
+    sapphire: Color::Rgb(32, 159, 181),   // #209fb5
+    sky: Color::Rgb(4, 165, 229),         // #04a5e5
+    teal: Color::Rgb(23, 146, 153),       // #179299
+    green: Color::Rgb(64, 160, 43),       // #40a02b
+    yellow: Color::Rgb(223, 142, 29),     // #df8e1d
+    peach: Color::Rgb(254, 100, 11),      // #fe640b
+    maroon: Color::Rgb(230, 69, 83),      // #e64553
+    red: Color::Rgb(210, 15, 57),         // #d20f39
+    mauve: Color::Rgb(136, 57, 239),      // #8839ef
+    pink: Color::Rgb(234, 118, 203),      // #ea76cb
+    flamingo: Color::Rgb(221, 120, 120),  // #dd7878
+    rosewater: Color::Rgb(220, 138, 120), // #dc8a78
+};
+
+/// The terminal's own colours: no background, and ANSI colours that
+/// follow whatever scheme the terminal is set to.
+pub const TERMINAL: Theme = Theme {
+    base: Color::Reset,
+    mantle: Color::Reset,
+    surface0: Color::DarkGray,
+    surface1: Color::DarkGray,
+    surface2: Color::Gray,
+    text: Color::Reset,
+    subtext1: Color::Gray,
+    subtext0: Color::Gray,
+    overlay2: Color::DarkGray,
+    overlay1: Color::DarkGray,
+    overlay0: Color::DarkGray,
+    lavender: Color::LightBlue,
+    blue: Color::Blue,
+    sapphire: Color::Cyan,
+    sky: Color::LightCyan,
+    teal: Color::Cyan,
+    green: Color::Green,
+    yellow: Color::Yellow,
+    peach: Color::LightRed,
+    maroon: Color::LightRed,
+    red: Color::Red,
+    mauve: Color::Magenta,
+    pink: Color::LightMagenta,
+    flamingo: Color::LightRed,
+    rosewater: Color::White,
+};
+
+/// The `theme` setting.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum ThemeName {
+    /// Mocha on a dark terminal background, Latte on a light one.
+    #[default]
+    Auto,
+    Mocha,
+    Macchiato,
+    Frappe,
+    Latte,
+    Terminal,
+}
+
+impl ThemeName {
+    pub fn from_key(key: &str) -> Option<Self> {
+        match key {
+            "auto" => Some(Self::Auto),
+            "mocha" => Some(Self::Mocha),
+            "macchiato" => Some(Self::Macchiato),
+            "frappe" => Some(Self::Frappe),
+            "latte" => Some(Self::Latte),
+            "terminal" => Some(Self::Terminal),
+            _ => None,
+        }
+    }
+
+    pub fn key(self) -> &'static str {
+        match self {
+            Self::Auto => "auto",
+            Self::Mocha => "mocha",
+            Self::Macchiato => "macchiato",
+            Self::Frappe => "frappe",
+            Self::Latte => "latte",
+            Self::Terminal => "terminal",
+        }
+    }
+
+    pub fn label(self) -> &'static str {
+        match self {
+            Self::Auto => "Catppuccin, light or dark like the terminal",
+            Self::Mocha => "Catppuccin Mocha",
+            Self::Macchiato => "Catppuccin Macchiato",
+            Self::Frappe => "Catppuccin Frappé",
+            Self::Latte => "Catppuccin Latte",
+            Self::Terminal => "Terminal colours",
+        }
+    }
+
+    pub fn next(self) -> Self {
+        match self {
+            Self::Auto => Self::Mocha,
+            Self::Mocha => Self::Macchiato,
+            Self::Macchiato => Self::Frappe,
+            Self::Frappe => Self::Latte,
+            Self::Latte => Self::Terminal,
+            Self::Terminal => Self::Auto,
+        }
+    }
+
+    /// The palette on a terminal with this `background`.
+    pub fn palette(self, background: Background) -> &'static Theme {
+        match self {
+            Self::Auto => match background {
+                Background::Dark => &MOCHA,
+                Background::Light => &LATTE,
+            },
+            Self::Mocha => &MOCHA,
+            Self::Macchiato => &MACCHIATO,
+            Self::Frappe => &FRAPPE,
+            Self::Latte => &LATTE,
+            Self::Terminal => &TERMINAL,
+        }
+    }
+}
+
+/// Colours of the `[theme]` config section by role, in the order given.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct ThemeColors(Vec<(String, Color)>);
+
+impl ThemeColors {
+    /// Sets the colour of the role `name` from a `#rrggbb` value.
+    pub fn set(&mut self, name: &str, value: &str) -> Result<(), String> {
+        let mut probe = MOCHA;
+        if probe.role_mut(name).is_none() {
+            return Err(format!("unknown theme colour `{name}`"));
+        }
+        let color = parse_hex(value).ok_or_else(|| {
+            format!("{name} must be a hex colour like #1e1e2e")
+        })?;
+        self.0.retain(|(role, _)| role != name);
+        self.0.push((name.to_string(), color));
+        Ok(())
+    }
+
+    /// `palette` with these colours in place of its own.
+    pub fn apply(&self, palette: &Theme) -> Theme {
+        let mut theme = *palette;
+        for (name, color) in &self.0 {
+            if let Some(role) = theme.role_mut(name) {
+                *role = *color;
+            }
+        }
+        theme
+    }
+}
+
+fn parse_hex(value: &str) -> Option<Color> {
+    let digits = value.strip_prefix('#')?;
+    if digits.len() != 6 || !digits.is_ascii() {
+        return None;
+    }
+    let channel = |at: usize| u8::from_str_radix(&digits[at..at + 2], 16).ok();
+    Some(Color::Rgb(channel(0)?, channel(2)?, channel(4)?))
+}
+
+/// The entry of the xterm colour cube (16-231) or grey ramp (232-255)
+/// closest to the colour; 0-15 are left out as terminals redefine them.
+fn nearest_indexed(red: u8, green: u8, blue: u8) -> Color {
+    let distance = |(r, g, b): (u8, u8, u8)| {
+        [(r, red), (g, green), (b, blue)]
+            .into_iter()
+            .map(|(a, b)| (i32::from(a) - i32::from(b)).pow(2))
+            .sum::<i32>()
+    };
+    (16..=255)
+        .min_by_key(|&index| distance(xterm_rgb(index)))
+        .map_or(Color::Reset, Color::Indexed)
+}
+
+fn xterm_rgb(index: u8) -> (u8, u8, u8) {
+    if index >= 232 {
+        let level = 8 + 10 * (index - 232);
+        return (level, level, level);
+    }
+    let cube = index - 16;
+    let level = |step: u8| if step == 0 { 0 } else { 55 + 40 * step };
+    (level(cube / 36), level(cube / 6 % 6), level(cube % 6))
+}
+
+/// The ANSI colour of the colour's hue, in its bright variant for light
+/// colours. Near-greys, like backgrounds and text, go by lightness alone,
+/// as the nearest colour by distance would turn every pastel accent grey.
+fn nearest_ansi(red: u8, green: u8, blue: u8) -> Color {
+    let max = red.max(green).max(blue);
+    let min = red.min(green).min(blue);
+    let lightness = (u16::from(max) + u16::from(min)) / 2;
+    if max - min < 48 {
+        return match lightness {
+            0..64 => Color::Black,
+            64..128 => Color::DarkGray,
+            128..200 => Color::Gray,
+            _ => Color::White,
+        };
+    }
+
+    let (r, g, b) = (f64::from(red), f64::from(green), f64::from(blue));
+    let chroma = f64::from(max - min);
+    let hue = if max == red {
+        60.0 * ((g - b) / chroma).rem_euclid(6.0)
+    } else if max == green {
+        60.0 * ((b - r) / chroma + 2.0)
+    } else {
+        60.0 * ((r - g) / chroma + 4.0)
+    };
+    let (dark, light) = match ((hue + 30.0) / 60.0) as u8 % 6 {
+        0 => (Color::Red, Color::LightRed),
+        1 => (Color::Yellow, Color::LightYellow),
+        2 => (Color::Green, Color::LightGreen),
+        3 => (Color::Cyan, Color::LightCyan),
+        4 => (Color::Blue, Color::LightBlue),
+        _ => (Color::Magenta, Color::LightMagenta),
+    };
+    if lightness >= 150 { light } else { dark }
+}
+
+#[cfg(test)]
+mod tests {
+    use ratatui::style::Color;
+
+    use super::{LATTE, MOCHA, TERMINAL, ThemeColors};
+    use crate::terminal::ColorDepth;
+
+    #[test]
+    fn palettes_are_approximated_for_terminals_without_truecolor() {
+        let indexed = MOCHA.for_depth(ColorDepth::Ansi256);
+        assert_eq!(indexed.base, Color::Indexed(235));
+        assert_eq!(indexed.text, Color::Indexed(189));
+        assert_eq!(
+            ThemeColors::default()
+                .apply(&MOCHA)
+                .for_depth(ColorDepth::Auto),
+            MOCHA
+        );
+
+        let ansi = MOCHA.for_depth(ColorDepth::Ansi16);
+        assert_eq!(
+            [ansi.base, ansi.surface1, ansi.subtext0, ansi.text],
+            [Color::Black, Color::DarkGray, Color::Gray, Color::White]
+        );
+        assert_eq!(
+            [
+                ansi.red,
+                ansi.yellow,
+                ansi.green,
+                ansi.sky,
+                ansi.blue,
+                ansi.pink
+            ],
+            [
+                Color::LightRed,
+                Color::LightYellow,
+                Color::LightGreen,
+                Color::LightCyan,
+                Color::LightBlue,
+                Color::LightMagenta
+            ]
+        );
+        let latte = LATTE.for_depth(ColorDepth::Ansi16);
+        assert_eq!([latte.base, latte.red], [Color::White, Color::Red]);
+        assert_eq!(TERMINAL.for_depth(ColorDepth::Ansi16), TERMINAL);
+    }
+
+    #[test]
+    fn theme_colors_replace_roles_of_any_palette() {
+        let mut colors = ThemeColors::default();
+        colors.set("base", "#000000").expect("known role");
+        colors.set("red", "#FF5555").expect("known role");
+        colors.set("base", "#101010").expect("known role");
+
+        let theme = colors.apply(&LATTE);
+        assert_eq!(theme.base, Color::Rgb(16, 16, 16));
+        assert_eq!(theme.red, Color::Rgb(255, 85, 85));
+        assert_eq!(theme.text, LATTE.text);
+        assert_eq!(ThemeColors::default().apply(&MOCHA), MOCHA);
+
+        assert_eq!(
+            colors.set("background", "#000000"),
+            Err("unknown theme colour `background`".to_string())
+        );
+        for value in ["000000", "#00000", "#00000g", "#0000000"] {
+            assert_eq!(
+                colors.set("text", value),
+                Err("text must be a hex colour like #1e1e2e".to_string())
+            );
+        }
+    }
 }
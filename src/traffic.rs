@@ -0,0 +1,154 @@
+//! RX/TX byte counters for the Wi-Fi interface, read from
+//! `/sys/class/net/<iface>/statistics`, and the throughput derived from them.
+
+use std::{
+    fs,
+    io,
+    path::Path,
+    time::{Duration, Instant},
+};
+
+const SYSFS_NET: &str = "/sys/class/net";
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct TrafficCounters {
+    pub rx_bytes: u64,
+    pub tx_bytes: u64,
+}
+
+pub fn read_interface_counters(interface: &str) -> io::Result<TrafficCounters> {
+    read_counters_from(Path::new(SYSFS_NET), interface)
+}
+
+fn read_counters_from(
+    root: &Path,
+    interface: &str,
+) -> io::Result<TrafficCounters> {
+    let statistics = root.join(interface).join("statistics");
+    let read = |name: &str| -> io::Result<u64> {
+        fs::read_to_string(statistics.join(name))?
+            .trim()
+            .parse()
+            .map_err(|error| io::Error::new(io::ErrorKind::InvalidData, error))
+    };
+
+    Ok(TrafficCounters {
+        rx_bytes: read("rx_bytes")?,
+        tx_bytes: read("tx_bytes")?,
+    })
+}
+
+/// Totals plus the per-second rates since the previous sample.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct TrafficStats {
+    pub totals: TrafficCounters,
+    pub rx_per_second: u64,
+    pub tx_per_second: u64,
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct TrafficMeter {
+    last_sample: Option<(Instant, TrafficCounters)>,
+    stats: Option<TrafficStats>,
+}
+
+impl TrafficMeter {
+    /// Records a reading. Rates need two samples; a counter that went
+    /// backwards (the interface was recreated) reads as no traffic.
+    pub fn sample(&mut self, counters: TrafficCounters, at: Instant) {
+        let rate = |previous: u64, current: u64, elapsed: Duration| {
+            let seconds = elapsed.as_secs_f64();
+            if seconds > 0.0 {
+                (current.saturating_sub(previous) as f64 / seconds) as u64
+            } else {
+                0
+            }
+        };
+
+        if let Some((previous_at, previous)) = self.last_sample {
+            let elapsed = at.saturating_duration_since(previous_at);
+            self.stats = Some(TrafficStats {
+                totals: counters,
+                rx_per_second: rate(
+                    previous.rx_bytes,
+                    counters.rx_bytes,
+                    elapsed,
+                ),
+                tx_per_second: rate(
+                    previous.tx_bytes,
+                    counters.tx_bytes,
+                    elapsed,
+                ),
+            });
+        }
+        self.last_sample = Some((at, counters));
+    }
+
+    pub fn stats(&self) -> Option<&TrafficStats> {
+        self.stats.as_ref()
+    }
+
+    pub fn reset(&mut self) {
+        *self = Self::default();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{
+        fs,
+        time::{Duration, Instant},
+    };
+
+    use super::{TrafficCounters, TrafficMeter, read_counters_from};
+
+    #[test]
+    fn counters_are_read_from_the_statistics_directory() {
+        let root = std::env::temp_dir()
+            .join(format!("nm-wifi-traffic-{}", std::process::id()));
+        let statistics = root.join("wlan0").join("statistics");
+        fs::create_dir_all(&statistics).expect("create sysfs fixture");
+        fs::write(statistics.join("rx_bytes"), "1048576\n").expect("write rx");
+        fs::write(statistics.join("tx_bytes"), "2048\n").expect("write tx");
+
+        let counters = read_counters_from(&root, "wlan0");
+        let missing = read_counters_from(&root, "wlan1");
+        fs::remove_dir_all(&root).expect("remove sysfs fixture");
+
+        assert_eq!(
+            counters.expect("counters"),
+            TrafficCounters {
+                rx_bytes: 1_048_576,
+                tx_bytes: 2048,
+            }
+        );
+        assert!(missing.is_err());
+    }
+
+    #[test]
+    fn rates_come_from_the_previous_sample() {
+        let start = Instant::now();
+        let mut meter = TrafficMeter::default();
+        meter.sample(
+            TrafficCounters {
+                rx_bytes: 1000,
+                tx_bytes: 500,
+            },
+            start,
+        );
+        assert!(meter.stats().is_none());
+
+        meter.sample(
+            TrafficCounters {
+                rx_bytes: 5000,
+                tx_bytes: 400,
+            },
+            start + Duration::from_secs(2),
+        );
+
+        let stats = meter.stats().expect("two samples");
+        assert_eq!(stats.rx_per_second, 2000);
+        assert_eq!(stats.tx_per_second, 0, "counter reset reads as idle");
+        assert_eq!(stats.totals.rx_bytes, 5000);
+    }
+}
@@ -2,16 +2,25 @@ mod format;
 mod header_footer;
 mod list;
 mod modals;
+mod mouse;
 mod screen;
+mod toasts;
 
 pub use format::{
     create_signal_graph,
+    format_duration,
     format_signal_strength,
     format_ssid_column,
+    format_timestamp,
     get_frequency_band,
 };
-pub use header_footer::{keybindings_hint, render_header, render_status_bar};
-pub use list::create_network_list_item;
+pub use header_footer::{
+    keybindings_hint,
+    manager_status_text,
+    render_header,
+    render_status_bar,
+};
+pub use list::{NetworkColumns, RowCache, RowMarks, create_network_row};
 pub use modals::{
     centered_rect,
     render_enhanced_connecting_modal,
@@ -21,16 +30,41 @@ pub use modals::{
     render_help_screen,
     render_network_details,
 };
+pub use mouse::{MouseTarget, mouse_target};
 pub use screen::ui;
 
 #[cfg(test)]
 mod tests {
-    use ratatui::{Terminal, backend::TestBackend};
+    use crossterm::event::KeyCode;
+    use ratatui::{
+        Terminal,
+        backend::TestBackend,
+        layout::Rect,
+        style::{Color, Modifier},
+    };
     use unicode_width::UnicodeWidthStr;
 
-    use super::{format_ssid_column, get_frequency_band, keybindings_hint, ui};
+    use super::{
+        MouseTarget,
+        NetworkColumns,
+        format_duration,
+        format_ssid_column,
+        format_timestamp,
+        get_frequency_band,
+        keybindings_hint,
+        manager_status_text,
+        mouse_target,
+        ui,
+    };
     use crate::{
-        app_state::{App, AppState},
+        app_state::{App, AppState, PasswordMask},
+        glyphs::{GlyphWidth, Glyphs},
+        network::{
+            ActivationStep,
+            ManagerState,
+            ManagerStatus,
+            PrimaryConnection,
+        },
         wifi::{WifiNetwork, WifiSecurity},
     };
 
@@ -41,10 +75,16 @@ mod tests {
     ) -> WifiNetwork {
         WifiNetwork {
             ssid: ssid.to_string(),
+            raw_ssid: None,
             signal_strength: 78,
             security,
             frequency: 5180,
             connected,
+            saved: false,
+            max_bitrate: 0,
+            wpa_flags: 0,
+            rsn_flags: 0,
+            access_points: Vec::new(),
         }
     }
 
@@ -68,8 +108,8 @@ mod tests {
 
     #[test]
     fn connecting_and_disconnecting_hints_show_only_quit_action() {
-        assert_eq!(keybindings_hint(&AppState::Connecting), "Esc Quit");
-        assert_eq!(keybindings_hint(&AppState::Disconnecting), "Esc Quit");
+        assert_eq!(keybindings_hint(&AppState::Connecting), "Esc Cancel");
+        assert_eq!(keybindings_hint(&AppState::Disconnecting), "Disconnecting");
     }
 
     #[test]
@@ -88,17 +128,300 @@ mod tests {
         );
     }
 
+    #[test]
+    fn header_explains_when_traffic_uses_a_non_wifi_primary_connection() {
+        let status = ManagerStatus {
+            state: ManagerState::ConnectedGlobal,
+            primary_connection: Some(PrimaryConnection {
+                id: "Wired connection 1".to_string(),
+                connection_type: "802-3-ethernet".to_string(),
+            }),
+            wifi_state_reason: None,
+        };
+
+        assert_eq!(
+            manager_status_text(Some(&status)),
+            "NM: online via Wired connection 1 (ethernet)"
+        );
+        assert_eq!(manager_status_text(None), "NM: unknown");
+    }
+
     #[test]
     fn six_ghz_networks_are_labeled_correctly() {
         assert_eq!(get_frequency_band(5975), "6G");
     }
 
+    #[test]
+    fn durations_are_formatted_compactly() {
+        assert_eq!(format_duration(42), "42s");
+        assert_eq!(format_duration(5 * 60 + 10), "5m");
+        assert_eq!(format_duration(3600 + 23 * 60), "1h 23m");
+    }
+
+    #[test]
+    fn timestamps_are_shown_as_utc_dates() {
+        assert_eq!(format_timestamp(0), "1970-01-01 00:00 UTC");
+        assert_eq!(format_timestamp(951_782_400), "2000-02-29 00:00 UTC");
+        assert_eq!(format_timestamp(1_792_203_180), "2026-10-17 02:13 UTC");
+    }
+
     #[test]
     fn ssid_column_uses_terminal_display_width() {
         let formatted = format_ssid_column("網😊", 6);
         assert_eq!(UnicodeWidthStr::width(formatted.as_str()), 6);
     }
 
+    #[test]
+    fn lookalike_ssids_show_their_invisible_characters() {
+        let mut app = App::new();
+        app.state = AppState::NetworkList;
+        app.networks = vec![
+            network("Home", WifiSecurity::WpaSae, false),
+            network("Home\u{200B}", WifiSecurity::WpaSae, false),
+        ];
+        app.network_count = 2;
+
+        let list_text = render_text(&app);
+        assert!(list_text.contains("⚠ Home<U+200B>"));
+
+        app.selected_index = 1;
+        app.state = AppState::NetworkDetails;
+        let details_text = render_text(&app);
+        assert!(details_text.contains("SSID: Home<U+200B>"));
+        assert!(details_text.contains("hides invisible characters"));
+    }
+
+    #[test]
+    fn networks_with_a_saved_profile_are_starred() {
+        let mut app = App::new();
+        app.state = AppState::NetworkList;
+        app.glyphs = Glyphs::resolve(GlyphWidth::Single);
+        app.networks = vec![
+            WifiNetwork {
+                saved: true,
+                ..network("Home", WifiSecurity::WpaPsk, false)
+            },
+            network("Cafe", WifiSecurity::WpaPsk, false),
+        ];
+        app.network_count = 2;
+
+        let text = render_text(&app);
+        let row = |ssid: &str| {
+            text.lines()
+                .find(|line| line.contains(ssid))
+                .map(str::to_string)
+                .unwrap_or_default()
+        };
+        assert!(row("Home").contains('★'));
+        assert!(!row("Cafe").contains('★'));
+    }
+
+    #[test]
+    fn monochrome_mode_emphasises_with_reverse_and_bold() {
+        let mut app = App::new();
+        app.state = AppState::NetworkList;
+        app.monochrome = true;
+        app.networks = vec![
+            network("Home", WifiSecurity::WpaPsk, true),
+            network("Cafe", WifiSecurity::WpaPsk, false),
+        ];
+        app.network_count = 2;
+
+        let backend = TestBackend::new(120, 36);
+        let mut terminal = Terminal::new(backend).expect("terminal created");
+        terminal
+            .draw(|frame| ui(frame, &app))
+            .expect("render succeeds");
+        let buffer = terminal.backend().buffer();
+        let row_of = |ssid: &str| {
+            (0..buffer.area.height).find(|&y| {
+                (0..buffer.area.width)
+                    .map(|x| buffer[(x, y)].symbol())
+                    .collect::<String>()
+                    .contains(ssid)
+            })
+        };
+        let reversed = |y: u16| {
+            (0..buffer.area.width)
+                .any(|x| buffer[(x, y)].modifier.contains(Modifier::REVERSED))
+        };
+
+        assert!(buffer.content.iter().all(|cell| {
+            cell.fg == Color::Reset && cell.bg == Color::Reset
+        }));
+        assert!(row_of("Home").is_some_and(reversed));
+        assert!(row_of("Cafe").is_some_and(|y| !reversed(y)));
+    }
+
+    #[test]
+    fn failed_networks_are_marked_with_their_last_error() {
+        let mut app = App::new();
+        app.state = AppState::NetworkList;
+        app.glyphs = Glyphs::resolve(GlyphWidth::Single);
+        app.networks = vec![network("Cafe", WifiSecurity::WpaPsk, false)];
+        app.network_count = 1;
+        app.connect_errors
+            .insert("Cafe".to_string(), "No DHCP lease from Cafe".to_string());
+
+        let text = render_text(&app);
+        assert!(
+            text.lines()
+                .find(|line| line.contains("Cafe"))
+                .is_some_and(|line| line.contains('!'))
+        );
+
+        app.state = AppState::NetworkDetails;
+        let details = render_text(&app);
+        assert!(details.contains("Last error: No DHCP lease from Cafe"));
+    }
+
+    #[test]
+    fn favorites_are_marked_and_listed_first() {
+        let mut app = App::new();
+        app.state = AppState::NetworkList;
+        app.glyphs = Glyphs::resolve(GlyphWidth::Single);
+        app.networks = vec![
+            network("Cafe", WifiSecurity::WpaPsk, false),
+            network("Home", WifiSecurity::WpaPsk, false),
+        ];
+        app.network_count = 2;
+        app.favorites.toggle("Home");
+
+        let text = render_text(&app);
+        let rows = text
+            .lines()
+            .filter(|line| line.contains("Home") || line.contains("Cafe"))
+            .collect::<Vec<_>>();
+        assert_eq!(rows.len(), 2);
+        assert!(rows[0].contains("Home") && rows[0].contains('♥'));
+        assert!(!rows[1].contains('♥'));
+    }
+
+    #[test]
+    fn typing_after_slash_narrows_the_list_by_fuzzy_match() {
+        let mut app = App::new();
+        app.state = AppState::NetworkList;
+        app.networks = vec![
+            network("Blue Cafe", WifiSecurity::WpaPsk, false),
+            network("Home", WifiSecurity::WpaPsk, false),
+            network("BACKUP-Cellar", WifiSecurity::Open, false),
+        ];
+        app.network_count = 3;
+        app.selected_index = 2;
+
+        app.open_search();
+        "bcf".chars().for_each(|c| app.push_search_char(c));
+        let text = render_text(&app);
+        assert!(text.contains("/bcf"));
+        assert!(text.contains("1 of 3 shown"));
+        assert!(text.contains("Blue Cafe") && !text.contains("Home"));
+        assert_eq!(app.selected_index, 0);
+
+        app.pop_search_char();
+        app.finish_search();
+        assert_eq!(app.visible_networks().len(), 2, "bc matches both B..C");
+        app.clear_search();
+        assert_eq!(app.visible_networks().len(), 3);
+        assert!(!render_text(&app).contains("shown"));
+    }
+
+    #[test]
+    fn the_network_table_drops_columns_on_narrow_terminals() {
+        let glyphs = Glyphs::resolve(GlyphWidth::Double);
+        assert_eq!(
+            NetworkColumns::fitting(116, &glyphs),
+            NetworkColumns {
+                band: true,
+                channel: true,
+                security: true,
+                signal_graph: true,
+            }
+        );
+        assert_eq!(
+            NetworkColumns::fitting(60, &glyphs),
+            NetworkColumns {
+                band: true,
+                channel: true,
+                security: true,
+                signal_graph: false,
+            }
+        );
+        assert_eq!(
+            NetworkColumns::fitting(30, &glyphs),
+            NetworkColumns {
+                band: false,
+                channel: false,
+                security: false,
+                signal_graph: false,
+            }
+        );
+
+        let mut app = App::new();
+        app.state = AppState::NetworkList;
+        app.networks = vec![WifiNetwork {
+            frequency: 2437,
+            ..network("網咖😊 Cafe", WifiSecurity::WpaSae, false)
+        }];
+        let text = render_text(&app);
+        let header = text
+            .lines()
+            .find(|line| line.contains("SSID"))
+            .unwrap_or_default();
+        let row = text
+            .lines()
+            .find(|line| line.contains("Cafe"))
+            .unwrap_or_default();
+        assert!(header.contains("Band") && header.contains("Security"));
+        assert!(row.contains("2.4G") && row.contains(" 6 "));
+        assert!(row.contains("WPA3") && row.contains("78%"));
+        // The rendered text holds one symbol per cell.
+        let column = |line: &str, needle: &str| {
+            line.find(needle).map(|index| line[..index].chars().count())
+        };
+        assert_eq!(
+            column(header, "Signal").map(|column| column + 1),
+            column(row, "78%"),
+            "the signal column lines up under its header"
+        );
+    }
+
+    #[test]
+    fn clicks_pick_network_rows_and_status_bar_hints() {
+        let mut app = App::new();
+        app.state = AppState::NetworkList;
+        app.networks = vec![
+            network("Home", WifiSecurity::WpaPsk, false),
+            network("Cafe", WifiSecurity::Open, false),
+        ];
+        let text = render_text(&app);
+        let area = Rect::new(0, 0, 120, 36);
+        let at = |needle: &str| {
+            text.lines().enumerate().find_map(|(y, line)| {
+                let x = line.find(needle)?;
+                Some((UnicodeWidthStr::width(&line[..x]) as u16, y as u16))
+            })
+        };
+
+        let (x, y) = at("Cafe").expect("row rendered");
+        assert_eq!(
+            mouse_target(&app, area, x, y),
+            Some(MouseTarget::NetworkRow(1))
+        );
+        let (x, y) = at("SSID").expect("header rendered");
+        assert_eq!(mouse_target(&app, area, x, y), None);
+        let (x, y) = at("Enter Connect").expect("hint rendered");
+        assert_eq!(
+            mouse_target(&app, area, x + 2, y),
+            Some(MouseTarget::Key(KeyCode::Enter))
+        );
+        let (x, y) = at("↑↓/jk").expect("hint rendered");
+        assert_eq!(mouse_target(&app, area, x, y), None);
+
+        app.state = AppState::Help;
+        let (x, y) = at("Cafe").expect("row rendered");
+        assert_eq!(mouse_target(&app, area, x, y), None);
+    }
+
     #[test]
     fn password_modal_masks_and_reveals_input() {
         let mut hidden_app = App::new();
@@ -118,6 +441,14 @@ mod tests {
         hidden_app.password_visible = true;
         let visible_text = render_text(&hidden_app);
         assert!(visible_text.contains("hunter2"));
+
+        hidden_app.password_visible = false;
+        hidden_app.password_input = "hi".to_string();
+        hidden_app.password_mask = PasswordMask::Fixed;
+        assert!(render_text(&hidden_app).contains("••••••••"));
+
+        hidden_app.password_mask = PasswordMask::Hidden;
+        assert!(!render_text(&hidden_app).contains("••"));
     }
 
     #[test]
@@ -133,6 +464,15 @@ mod tests {
         assert!(connecting_text.contains("Network: CatCat"));
         assert!(connecting_text.contains("Security: WPA3 Personal"));
         assert!(connecting_text.contains("Signal: 78% (5G)"));
+        assert!(!connecting_text.contains("Preparing the adapter"));
+
+        app.activation_progress.reach(ActivationStep::Prepare);
+        app.activation_progress.reach(ActivationStep::Config);
+        let stepped_text = render_text(&app);
+        assert!(stepped_text.contains("✓ Preparing the adapter"));
+        assert!(stepped_text.contains("› Associating with the access point"));
+        assert!(!stepped_text.contains("Waiting for credentials"));
+        assert!(stepped_text.contains("· Obtaining an IP address"));
 
         app.state = AppState::Disconnecting;
         let disconnecting_text = render_text(&app);
@@ -160,4 +500,47 @@ mod tests {
         assert!(text.contains("Interface: demo-wlan0"));
         assert!(text.contains("Failed to find WiFi device in NetworkManager"));
     }
+
+    #[test]
+    fn long_lists_scroll_to_keep_the_selection_on_screen() {
+        let mut app = App::new();
+        app.state = AppState::NetworkList;
+        app.networks = (0..600)
+            .map(|index| {
+                network(&format!("Net-{index:03}"), WifiSecurity::Open, false)
+            })
+            .collect();
+        let highlighted_row = |text: &str| {
+            text.lines()
+                .find(|line| line.contains('►'))
+                .map(str::to_string)
+                .unwrap_or_default()
+        };
+
+        app.selected_index = 2;
+        let top = render_text(&app);
+        assert!(highlighted_row(&top).contains("Net-002"));
+        assert!(top.contains("Net-000"));
+
+        app.selected_index = 300;
+        let scrolled = render_text(&app);
+        assert!(highlighted_row(&scrolled).contains("Net-300"));
+        assert!(scrolled.contains("Net-299"));
+        assert!(!scrolled.contains("Net-301"));
+        assert!(!scrolled.contains("Net-000"));
+    }
+
+    #[test]
+    fn cached_rows_are_rebuilt_once_their_network_changes() {
+        let mut app = App::new();
+        app.state = AppState::NetworkList;
+        app.networks = vec![network("Home", WifiSecurity::WpaPsk, false)];
+        assert!(render_text(&app).contains(" 78%"));
+
+        app.networks[0].signal_strength = 35;
+        let text = render_text(&app);
+        assert!(text.contains(" 35%"));
+        assert!(!text.contains(" 78%"));
+        assert_eq!(app.visible_networks()[0].signal_strength, 35);
+    }
 }
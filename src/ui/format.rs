@@ -1,5 +1,11 @@
 use unicode_width::{UnicodeWidthChar, UnicodeWidthStr};
 
+use crate::{
+    app_state::ConnectionUptime,
+    latency::Probe,
+    wifi::{WifiBand, is_invisible_char},
+};
+
 pub fn create_signal_graph(strength: u8) -> String {
     let bars = (strength as f32 / 100.0 * 20.0) as usize;
     let filled = "█".repeat(bars);
@@ -8,10 +14,101 @@ pub fn create_signal_graph(strength: u8) -> String {
 }
 
 pub fn get_frequency_band(frequency: u32) -> &'static str {
-    match frequency {
-        5925.. => "6G",
-        5000.. => "5G",
-        _ => "2.4G",
+    WifiBand::from_frequency(frequency).label()
+}
+
+/// Seconds since the Unix epoch as a UTC date and time, e.g.
+/// `2026-10-17 02:13 UTC`.
+pub fn format_timestamp(timestamp: u64) -> String {
+    // Civil date from days since the epoch, after Howard Hinnant's
+    // `civil_from_days`.
+    let days = timestamp / 86_400 + 719_468;
+    let era = days / 146_097;
+    let day_of_era = days % 146_097;
+    let year_of_era = (day_of_era - day_of_era / 1460 + day_of_era / 36_524
+        - day_of_era / 146_096)
+        / 365;
+    let day_of_year =
+        day_of_era - (365 * year_of_era + year_of_era / 4 - year_of_era / 100);
+    let month_index = (5 * day_of_year + 2) / 153;
+    let day = day_of_year - (153 * month_index + 2) / 5 + 1;
+    let month = if month_index < 10 {
+        month_index + 3
+    } else {
+        month_index - 9
+    };
+    let year = year_of_era + era * 400 + u64::from(month <= 2);
+
+    let seconds_of_day = timestamp % 86_400;
+    format!(
+        "{year}-{month:02}-{day:02} {:02}:{:02} UTC",
+        seconds_of_day / 3600,
+        (seconds_of_day % 3600) / 60
+    )
+}
+
+pub fn format_duration(seconds: u64) -> String {
+    match seconds {
+        0..60 => format!("{seconds}s"),
+        60..3600 => format!("{}m", seconds / 60),
+        _ => format!("{}h {}m", seconds / 3600, (seconds % 3600) / 60),
+    }
+}
+
+/// Uptime such as `1h 23m`; a `+` marks a connection that was already up
+/// when the app first saw it.
+pub fn format_uptime(uptime: &ConnectionUptime) -> String {
+    let elapsed = format_duration(uptime.since.elapsed().as_secs());
+    if uptime.observed {
+        format!("{elapsed}+")
+    } else {
+        elapsed
+    }
+}
+
+/// A PHY rate given in kb/s, as NetworkManager reports it, e.g. `866 Mb/s`.
+pub fn format_bitrate(kilobits_per_second: u32) -> String {
+    if kilobits_per_second >= 1000 {
+        format!("{} Mb/s", kilobits_per_second / 1000)
+    } else {
+        format!("{kilobits_per_second} kb/s")
+    }
+}
+
+/// Measured throughput, or `-` before anything was transferred.
+pub fn format_throughput(megabits_per_second: Option<f64>) -> String {
+    match megabits_per_second {
+        Some(mbps) => format!("{mbps:.1} Mb/s"),
+        None => "-".to_string(),
+    }
+}
+
+/// Byte counts in binary units, e.g. `1.5 MB`.
+pub fn format_bytes(bytes: u64) -> String {
+    const UNITS: [&str; 5] = ["B", "KB", "MB", "GB", "TB"];
+
+    let mut value = bytes as f64;
+    let mut unit = 0;
+    while value >= 1024.0 && unit < UNITS.len() - 1 {
+        value /= 1024.0;
+        unit += 1;
+    }
+
+    if unit == 0 {
+        format!("{bytes} B")
+    } else {
+        format!("{value:.1} {}", UNITS[unit])
+    }
+}
+
+/// A round-trip time in whole milliseconds without the unit, `lost`, or
+/// `-` before the first probe.
+pub fn format_probe(probe: Option<Probe>) -> String {
+    match probe {
+        Some(Probe::Reply(rtt)) if rtt.as_millis() == 0 => "<1".to_string(),
+        Some(Probe::Reply(rtt)) => rtt.as_millis().to_string(),
+        Some(Probe::Lost) => "lost".to_string(),
+        None => "-".to_string(),
     }
 }
 
@@ -19,11 +116,25 @@ pub fn format_signal_strength(strength: u8) -> String {
     format!("{}%", strength)
 }
 
+/// The SSID with invisible characters spelled out, e.g. `Home<U+200B>`, so
+/// a lookalike of another network's name shows where it differs.
+pub fn display_ssid(ssid: &str) -> String {
+    ssid.chars()
+        .map(|c| {
+            if is_invisible_char(c) {
+                format!("<U+{:04X}>", c as u32)
+            } else {
+                c.to_string()
+            }
+        })
+        .collect()
+}
+
 pub fn format_ssid_column(ssid: &str, width: usize) -> String {
     let mut formatted = String::new();
     let mut current_width = 0;
 
-    for ch in ssid.chars() {
+    for ch in display_ssid(ssid).chars() {
         let ch_width = UnicodeWidthChar::width(ch).unwrap_or(0);
         if current_width + ch_width > width {
             break;
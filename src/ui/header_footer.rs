@@ -1,14 +1,26 @@
+use std::rc::Rc;
+
+use crossterm::event::KeyCode;
 use ratatui::{
     Frame,
-    layout::{Alignment, Constraint, Direction, Layout, Rect},
-    style::{Modifier, Style},
+    layout::{Alignment, Constraint, Direction, Layout, Position, Rect},
+    style::{Color, Modifier, Style},
     text::{Line, Span},
     widgets::{Block, Borders, Paragraph},
 };
+use unicode_width::UnicodeWidthStr;
 
+use super::format::{
+    format_bytes,
+    format_duration,
+    format_probe,
+    format_uptime,
+};
 use crate::{
     app_state::{App, AppState},
-    theme::CatppuccinColors,
+    latency::{LatencyMonitor, Probe},
+    network::{ManagerState, ManagerStatus},
+    theme::Theme,
 };
 
 pub fn keybindings_hint(state: &AppState) -> &'static str {
@@ -18,19 +30,157 @@ pub fn keybindings_hint(state: &AppState) -> &'static str {
         }
         AppState::Help => "h/q/Esc Back",
         AppState::NetworkDetails => "q/i/Esc Back",
+        AppState::Roaming => "↑↓ Select  b Avoid  p Pin  q/a/Esc Back",
+        AppState::SpeedTest => "Enter Run Again  q/t/Esc Back",
+        AppState::Spectrum => "←→/Tab Band  q/w/Esc Back",
+        AppState::ConnectionHistory => "↑↓/jk Move  q/H/Esc Back",
+        AppState::NetworkManagerLog => {
+            "↑↓/jk Scroll  G Follow  f All/Filtered  q/J/Esc Back"
+        }
+        AppState::SiteSurvey => "e Export CSV  c Restart  q/Esc Back",
+        AppState::Devices => {
+            "↑↓/jk Move  Enter Up/Down  r Refresh  Tab Next  q/Esc Wi-Fi"
+        }
+        AppState::P2pPeers => "↑↓/jk Move  Enter Connect  r Search  q/Esc Back",
+        AppState::ShareQr => "Enter Export  Esc Close",
+        AppState::CommandPalette => "Enter Run  Esc Close",
+        AppState::ForgetProfiles => "Enter Forget  Esc Cancel",
+        AppState::ExportProfiles => "Enter Export  Tab Secrets  Esc Close",
+        AppState::KnownNetworks => {
+            "↑↓/jk Move  s Scope  b Band  a AP  r Rename  c Clone  p Password  q/Esc Back"
+        }
+        AppState::CleanupProfiles => {
+            "↑↓ Move  Space Pick  a All  Enter Forget  Esc Cancel"
+        }
+        AppState::ImportQr => "Enter Connect  Esc Close",
         AppState::PasswordInput => "Enter Connect  Tab Show/Hide  Esc Cancel",
-        AppState::Connecting | AppState::Disconnecting => "Esc Quit",
+        AppState::Connecting => "Esc Cancel",
+        AppState::Disconnecting => "Disconnecting",
         AppState::Scanning => "Scanning  Esc Quit",
         AppState::ConnectionResult => "Enter Return  q/Esc Quit",
+        AppState::ManagerUnavailable => {
+            "s Start NetworkManager  r Retry  q Quit"
+        }
+    }
+}
+
+pub fn manager_status_text(status: Option<&ManagerStatus>) -> String {
+    match status {
+        Some(status) => match &status.primary_connection {
+            Some(primary) => format!(
+                "NM: {} via {} ({})",
+                status.state.display_name(),
+                primary.id,
+                primary.kind_label()
+            ),
+            None => format!("NM: {}", status.state.display_name()),
+        },
+        None => "NM: unknown".to_string(),
+    }
+}
+
+fn manager_status_color(
+    status: Option<&ManagerStatus>,
+    theme: &Theme,
+) -> Color {
+    let Some(status) = status else {
+        return theme.overlay1;
+    };
+
+    match status.state {
+        ManagerState::ConnectedGlobal
+            if status
+                .primary_connection
+                .as_ref()
+                .is_some_and(|primary| !primary.is_wifi()) =>
+        {
+            theme.peach
+        }
+        ManagerState::ConnectedGlobal => theme.green,
+        ManagerState::ConnectedLocal
+        | ManagerState::ConnectedSite
+        | ManagerState::Connecting
+        | ManagerState::Disconnecting => theme.yellow,
+        ManagerState::Asleep | ManagerState::Disconnected => theme.red,
+        ManagerState::Unknown => theme.overlay1,
+    }
+}
+
+/// Fits `↓ 999.9 KB/s ↑ 999.9 KB/s` inside the borders.
+const TRAFFIC_WIDTH: u16 = 27;
+/// Fits `999 / 999 ms` (gateway / host) and the loss title inside the
+/// borders.
+const LATENCY_WIDTH: u16 = 16;
+
+fn latency_color(latency: &LatencyMonitor, theme: &Theme) -> Color {
+    match (latency.remote.last(), latency.loss_percent()) {
+        (Some(Probe::Lost), _) | (_, Some(100)) => theme.red,
+        (Some(Probe::Reply(rtt)), Some(0)) if rtt.as_millis() < 100 => {
+            theme.green
+        }
+        (None, _) => theme.overlay1,
+        _ => theme.yellow,
     }
 }
 
 pub fn render_header(f: &mut Frame, app: &App, area: Rect) {
+    let theme = app.colors();
+    let traffic =
+        app.connected_network()
+            .and(app.traffic.stats())
+            .map(|stats| {
+                Paragraph::new(format!(
+                    "↓ {}/s ↑ {}/s",
+                    format_bytes(stats.rx_per_second),
+                    format_bytes(stats.tx_per_second)
+                ))
+                .block(Block::default().borders(Borders::ALL).title(format!(
+                    "{}↓ {}↑",
+                    format_bytes(stats.totals.rx_bytes),
+                    format_bytes(stats.totals.tx_bytes)
+                )))
+                .style(Style::default().fg(theme.teal).bg(theme.base))
+                .alignment(Alignment::Center)
+            });
+
+    let latency =
+        app.connected_network()
+            .and(app.latency.as_ref())
+            .map(|latency| {
+                let loss =
+                    latency.loss_percent().map_or("ping".to_string(), |loss| {
+                        format!("ping {loss}% loss")
+                    });
+                Paragraph::new(format!(
+                    "{} / {} ms",
+                    format_probe(latency.gateway.last()),
+                    format_probe(latency.remote.last())
+                ))
+                .block(Block::default().borders(Borders::ALL).title(loss))
+                .style(
+                    Style::default()
+                        .fg(latency_color(latency, theme))
+                        .bg(theme.base),
+                )
+                .alignment(Alignment::Center)
+            });
+
     let header_chunks = Layout::default()
         .direction(Direction::Horizontal)
         .constraints([
-            Constraint::Length(30),
+            Constraint::Length(18),
             Constraint::Min(0),
+            Constraint::Length(36),
+            Constraint::Length(if traffic.is_some() {
+                TRAFFIC_WIDTH
+            } else {
+                0
+            }),
+            Constraint::Length(if latency.is_some() {
+                LATENCY_WIDTH
+            } else {
+                0
+            }),
             Constraint::Length(25),
         ])
         .split(area);
@@ -39,18 +189,24 @@ pub fn render_header(f: &mut Frame, app: &App, area: Rect) {
         Span::styled(
             "nm-wifi",
             Style::default()
-                .fg(CatppuccinColors::MAUVE)
+                .fg(theme.mauve)
                 .add_modifier(Modifier::BOLD),
         ),
         Span::styled(
             concat!(" v", env!("CARGO_PKG_VERSION")),
-            Style::default().fg(CatppuccinColors::SUBTEXT1),
+            Style::default().fg(theme.subtext1),
         ),
     ]))
     .block(Block::default().borders(Borders::ALL))
-    .style(Style::default().bg(CatppuccinColors::BASE));
+    .style(Style::default().bg(theme.base));
 
-    let scan_info = if let Some(scan_time) = app.last_scan_time {
+    let mut scan_info = if let Some(countdown) = app.rescan_countdown() {
+        format!(
+            "Networks: {} | Next scan in {}s",
+            app.network_count,
+            countdown.as_secs()
+        )
+    } else if let Some(scan_time) = app.last_scan_time {
         let elapsed = scan_time.elapsed().as_secs();
         format!(
             "Networks: {} | Last scan: {}s ago",
@@ -60,54 +216,189 @@ pub fn render_header(f: &mut Frame, app: &App, area: Rect) {
         format!("Networks: {}", app.network_count)
     };
 
+    if let Some(uptime) = &app.connection_uptime {
+        scan_info
+            .push_str(&format!(" | Connected for {}", format_uptime(uptime)));
+    }
+
+    if let Some(session) = &app.guest_session {
+        scan_info.push_str(&format!(
+            " | Guest {}",
+            format_duration(session.started.elapsed().as_secs())
+        ));
+    }
+
     let info = Paragraph::new(scan_info)
+        .block(Block::default().borders(Borders::ALL))
+        .style(Style::default().fg(theme.text).bg(theme.base))
+        .alignment(Alignment::Center);
+
+    let manager_status = app.manager_status.as_ref();
+    let manager = Paragraph::new(manager_status_text(manager_status))
         .block(Block::default().borders(Borders::ALL))
         .style(
             Style::default()
-                .fg(CatppuccinColors::TEXT)
-                .bg(CatppuccinColors::BASE),
+                .fg(manager_status_color(manager_status, theme))
+                .bg(theme.base),
         )
         .alignment(Alignment::Center);
 
     let adapter_text = app.adapter_name.as_deref().unwrap_or("WiFi Adapter");
+    let (adapter_text, adapter_color) = if app.adapter_unmanaged() {
+        (format!("{adapter_text} (unmanaged)"), theme.yellow)
+    } else {
+        (adapter_text.to_string(), theme.blue)
+    };
     let adapter = Paragraph::new(adapter_text)
         .block(Block::default().borders(Borders::ALL))
-        .style(
-            Style::default()
-                .fg(CatppuccinColors::BLUE)
-                .bg(CatppuccinColors::BASE),
-        )
+        .style(Style::default().fg(adapter_color).bg(theme.base))
         .alignment(Alignment::Center);
 
     f.render_widget(title, header_chunks[0]);
     f.render_widget(info, header_chunks[1]);
-    f.render_widget(adapter, header_chunks[2]);
+    f.render_widget(manager, header_chunks[2]);
+    if let Some(traffic) = traffic {
+        f.render_widget(traffic, header_chunks[3]);
+    }
+    if let Some(latency) = latency {
+        f.render_widget(latency, header_chunks[4]);
+    }
+    f.render_widget(adapter, header_chunks[5]);
 }
 
-pub fn render_status_bar(f: &mut Frame, app: &App, area: Rect) {
-    let status_chunks = Layout::default()
+/// Spinner, name and elapsed time of a slow backend request, e.g.
+/// `⠹ Scanning 2.4s`.
+fn busy_text(app: &App) -> Option<String> {
+    let activity = app.visible_backend_activity()?;
+    let elapsed = activity.started.elapsed();
+    let frames = app.glyphs.spinner;
+    let frame = frames[(elapsed.as_millis() / 100) as usize % frames.len()];
+    let elapsed = if elapsed.as_secs() < 60 {
+        format!("{:.1}s", elapsed.as_secs_f64())
+    } else {
+        format_duration(elapsed.as_secs())
+    };
+    Some(format!("{frame} {} {elapsed}", activity.operation))
+}
+
+/// Gateway pings lost in a row against the health monitor's limit, while
+/// connected with the monitor on.
+fn health_text(app: &App) -> Option<(String, Color)> {
+    let theme = app.colors();
+    let health = app.health.as_ref()?;
+    app.connected_network()?;
+    Some(match health.failures {
+        0 => ("Gateway OK".to_string(), theme.green),
+        failures => (
+            format!("Gateway {failures}/{} lost", health.threshold),
+            theme.peach,
+        ),
+    })
+}
+
+/// The status message, health, busy indicator and key hints, left to right.
+fn status_bar_chunks(app: &App, area: Rect) -> Rc<[Rect]> {
+    let busy_width = busy_text(app)
+        .as_ref()
+        .map_or(0, |text| text.chars().count() as u16 + 4);
+    let health_width = health_text(app)
+        .as_ref()
+        .map_or(0, |(text, _)| text.chars().count() as u16 + 4);
+    Layout::default()
         .direction(Direction::Horizontal)
-        .constraints([Constraint::Min(0), Constraint::Length(40)])
-        .split(area);
+        .constraints([
+            Constraint::Min(0),
+            Constraint::Length(health_width),
+            Constraint::Length(busy_width),
+            Constraint::Length(40),
+        ])
+        .split(area)
+}
+
+/// The key of the hint drawn at `position` in a status bar rendered into
+/// `area`, so that clicking a hint does what pressing its key would.
+pub(crate) fn hint_key_at(
+    app: &App,
+    area: Rect,
+    position: Position,
+) -> Option<KeyCode> {
+    let hints_area = Block::default()
+        .borders(Borders::ALL)
+        .inner(status_bar_chunks(app, area)[3]);
+    if !hints_area.contains(position) {
+        return None;
+    }
+
+    // Centred the way the paragraph centres it, or cut off on the right.
+    let hint = keybindings_hint(&app.state);
+    let width = (UnicodeWidthStr::width(hint) as u16).min(hints_area.width);
+    let mut x = hints_area.x + (hints_area.width / 2).saturating_sub(width / 2);
+    for segment in hint.split("  ") {
+        let segment_width = UnicodeWidthStr::width(segment) as u16;
+        if (x..x + segment_width).contains(&position.x) {
+            return hint_key(segment);
+        }
+        x += segment_width + 2;
+    }
+    None
+}
+
+/// The key named by a hint such as `q/i/Esc Back`: the first of its
+/// alternatives that is a single key. Arrows and key pairs like `jk` name
+/// none, since the wheel already moves the selection.
+fn hint_key(segment: &str) -> Option<KeyCode> {
+    let (keys, _action) = segment.split_once(' ')?;
+    keys.split('/').find_map(|key| match key {
+        "Enter" => Some(KeyCode::Enter),
+        "Esc" => Some(KeyCode::Esc),
+        "Tab" => Some(KeyCode::Tab),
+        "Space" => Some(KeyCode::Char(' ')),
+        _ => {
+            let mut chars = key.chars();
+            match (chars.next(), chars.next()) {
+                (Some(c), None) if c.is_ascii_graphic() => {
+                    Some(KeyCode::Char(c))
+                }
+                _ => None,
+            }
+        }
+    })
+}
+
+pub fn render_status_bar(f: &mut Frame, app: &App, area: Rect) {
+    let theme = app.colors();
+    let busy = busy_text(app);
+    let health = health_text(app);
+    let status_chunks = status_bar_chunks(app, area);
 
     let status = Paragraph::new(app.status_message.as_str())
         .block(Block::default().borders(Borders::ALL))
-        .style(
-            Style::default()
-                .fg(CatppuccinColors::SUBTEXT1)
-                .bg(CatppuccinColors::BASE),
-        )
+        .style(Style::default().fg(theme.subtext1).bg(theme.base))
         .alignment(Alignment::Left);
 
     let hints = Paragraph::new(keybindings_hint(&app.state))
         .block(Block::default().borders(Borders::ALL))
-        .style(
-            Style::default()
-                .fg(CatppuccinColors::OVERLAY1)
-                .bg(CatppuccinColors::BASE),
-        )
+        .style(Style::default().fg(theme.overlay1).bg(theme.base))
         .alignment(Alignment::Center);
 
     f.render_widget(status, status_chunks[0]);
-    f.render_widget(hints, status_chunks[1]);
+    if let Some((health, color)) = health {
+        f.render_widget(
+            Paragraph::new(health)
+                .block(Block::default().borders(Borders::ALL))
+                .style(Style::default().fg(color).bg(theme.base))
+                .alignment(Alignment::Center),
+            status_chunks[1],
+        );
+    }
+    if let Some(busy) = busy {
+        f.render_widget(
+            Paragraph::new(busy)
+                .block(Block::default().borders(Borders::ALL))
+                .style(Style::default().fg(theme.yellow).bg(theme.base))
+                .alignment(Alignment::Center),
+            status_chunks[2],
+        );
+    }
+    f.render_widget(hints, status_chunks[3]);
 }
@@ -1,61 +1,445 @@
+use std::{
+    cell::RefCell,
+    collections::HashMap,
+    ops::Range,
+    rc::Rc,
+    time::Duration,
+};
+
 use ratatui::{
     Frame,
-    layout::Rect,
+    layout::{Constraint, Direction, Layout, Position, Rect},
     style::{Modifier, Style},
     text::{Line, Span},
-    widgets::{Block, Borders, List, ListItem, ListState},
+    widgets::{
+        Block,
+        Borders,
+        Cell,
+        HighlightSpacing,
+        Paragraph,
+        Row,
+        Table,
+        TableState,
+    },
 };
+use unicode_width::UnicodeWidthStr;
 
 use super::format::{
     create_signal_graph,
+    display_ssid,
+    format_duration,
     format_signal_strength,
-    format_ssid_column,
     get_frequency_band,
 };
-use crate::{app_state::App, theme::CatppuccinColors, wifi::WifiNetwork};
+use crate::{
+    app_state::App,
+    glyphs::Glyphs,
+    search::fuzzy_match,
+    theme::Theme,
+    wifi::WifiNetwork,
+};
+
+/// What the app remembers about a network beyond its scan result.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct RowMarks<'q> {
+    /// How long ago a network the last scan missed was seen; the row is
+    /// dimmed and says so instead of showing signal bars.
+    pub stale_for: Option<Duration>,
+    /// Dims a network listed only because ignored networks are shown.
+    pub ignored: bool,
+    pub favorite: bool,
+    /// The last connect to the network failed.
+    pub failed: bool,
+    /// The list filter, whose matching characters are highlighted.
+    pub query: &'q str,
+}
+
+impl<'q> RowMarks<'q> {
+    pub fn of(app: &'q App, network: &WifiNetwork) -> Self {
+        Self {
+            stale_for: app.stale_for(network),
+            ignored: app.ignored.contains(&network.ssid),
+            favorite: app.favorites.contains(&network.ssid),
+            failed: app.connect_errors.contains_key(&network.ssid),
+            query: &app.search_query,
+        }
+    }
+}
+
+/// Rows built on earlier frames by position in `App::networks`, each with
+/// what it was built from, so a redraw only rebuilds the rows whose
+/// network, marks or layout changed. Rows that scroll off screen are
+/// dropped.
+#[derive(Debug, Default)]
+pub struct RowCache(RefCell<HashMap<usize, CachedRow>>);
+
+#[derive(Debug)]
+struct CachedRow {
+    network: WifiNetwork,
+    /// Only the seconds are shown, so a stale row is rebuilt once a second.
+    stale_secs: Option<u64>,
+    ignored: bool,
+    favorite: bool,
+    failed: bool,
+    query: String,
+    columns: NetworkColumns,
+    glyphs: Glyphs,
+    theme: Theme,
+    row: Row<'static>,
+}
+
+impl CachedRow {
+    fn build(
+        network: &WifiNetwork,
+        marks: RowMarks,
+        columns: NetworkColumns,
+        glyphs: &Glyphs,
+        theme: &Theme,
+    ) -> Self {
+        Self {
+            network: network.clone(),
+            stale_secs: marks.stale_for.map(|elapsed| elapsed.as_secs()),
+            ignored: marks.ignored,
+            favorite: marks.favorite,
+            failed: marks.failed,
+            query: marks.query.to_string(),
+            columns,
+            glyphs: *glyphs,
+            theme: *theme,
+            row: create_network_row(network, glyphs, marks, columns, theme),
+        }
+    }
+
+    fn is_for(
+        &self,
+        network: &WifiNetwork,
+        marks: RowMarks,
+        columns: NetworkColumns,
+        glyphs: &Glyphs,
+        theme: &Theme,
+    ) -> bool {
+        self.stale_secs == marks.stale_for.map(|elapsed| elapsed.as_secs())
+            && self.ignored == marks.ignored
+            && self.favorite == marks.favorite
+            && self.failed == marks.failed
+            && self.query == marks.query
+            && self.columns == columns
+            && self.glyphs == *glyphs
+            && self.theme == *theme
+            && self.network == *network
+    }
+}
+
+impl RowCache {
+    /// The rows of the networks at `indices` in `app.networks`, reusing
+    /// the cached ones that are current.
+    fn rows(
+        &self,
+        app: &App,
+        indices: &[usize],
+        columns: NetworkColumns,
+    ) -> Vec<Row<'static>> {
+        let mut cached = self.0.borrow_mut();
+        let mut kept = HashMap::with_capacity(indices.len());
+        let rows = indices
+            .iter()
+            .map(|&index| {
+                let network = &app.networks[index];
+                let marks = RowMarks::of(app, network);
+                let entry = match cached.remove(&index) {
+                    Some(entry)
+                        if entry.is_for(
+                            network,
+                            marks,
+                            columns,
+                            &app.glyphs,
+                            app.colors(),
+                        ) =>
+                    {
+                        entry
+                    }
+                    _ => CachedRow::build(
+                        network,
+                        marks,
+                        columns,
+                        &app.glyphs,
+                        app.colors(),
+                    ),
+                };
+                let row = entry.row.clone();
+                kept.insert(index, entry);
+                row
+            })
+            .collect();
+        *cached = kept;
+        rows
+    }
+}
+
+/// Space kept left of the rows for the selection marker.
+const HIGHLIGHT_SYMBOL: &str = "► ";
+const SSID_MIN_WIDTH: u16 = 12;
+/// Band and estimated Wi-Fi generation, e.g. `2.4G ~W6`.
+const BAND_WIDTH: u16 = 8;
+const CHANNEL_WIDTH: u16 = 3;
+/// The longest short security name, `802.1X`.
+const SECURITY_NAME_WIDTH: u16 = 6;
+/// Percentage and a 20-bar graph.
+const SIGNAL_GRAPH_WIDTH: u16 = 25;
+/// Percentage alone, or how long ago a missing network was seen.
+const SIGNAL_WIDTH: u16 = 10;
+
+/// The optional columns of the network table that fit the terminal.
+/// Narrower terminals give up the signal graph first, then the channel,
+/// the security and the band, so the SSID keeps room to be read.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct NetworkColumns {
+    pub band: bool,
+    pub channel: bool,
+    pub security: bool,
+    pub signal_graph: bool,
+}
+
+impl NetworkColumns {
+    const ALL: Self = Self {
+        band: true,
+        channel: true,
+        security: true,
+        signal_graph: true,
+    };
+    const SSID_AND_SIGNAL: Self = Self {
+        band: false,
+        channel: false,
+        security: false,
+        signal_graph: false,
+    };
+
+    /// The most columns that fit in `width` cells.
+    pub fn fitting(width: u16, glyphs: &Glyphs) -> Self {
+        [
+            Self::ALL,
+            Self {
+                signal_graph: false,
+                ..Self::ALL
+            },
+            Self {
+                signal_graph: false,
+                channel: false,
+                ..Self::ALL
+            },
+            Self {
+                band: true,
+                ..Self::SSID_AND_SIGNAL
+            },
+        ]
+        .into_iter()
+        .find(|columns| columns.min_width(glyphs) <= width)
+        .unwrap_or(Self::SSID_AND_SIGNAL)
+    }
+
+    fn widths(self, glyphs: &Glyphs) -> Vec<Constraint> {
+        let icon = UnicodeWidthStr::width(glyphs.blank) as u16;
+        let mut widths = vec![
+            Constraint::Length(icon * 3),
+            Constraint::Min(SSID_MIN_WIDTH),
+        ];
+        if self.band {
+            widths.push(Constraint::Length(BAND_WIDTH));
+        }
+        if self.channel {
+            widths.push(Constraint::Length(CHANNEL_WIDTH));
+        }
+        if self.security {
+            widths.push(Constraint::Length(icon + 1 + SECURITY_NAME_WIDTH));
+        }
+        widths.push(Constraint::Length(if self.signal_graph {
+            SIGNAL_GRAPH_WIDTH
+        } else {
+            SIGNAL_WIDTH
+        }));
+        widths
+    }
 
-pub fn create_network_list_item<'a>(network: &WifiNetwork) -> ListItem<'a> {
-    let signal_graph = create_signal_graph(network.signal_strength);
+    /// Cells needed for every column at its narrowest, with the selection
+    /// marker and the spaces between columns.
+    fn min_width(self, glyphs: &Glyphs) -> u16 {
+        let widths = self.widths(glyphs);
+        let columns = widths
+            .iter()
+            .map(|width| match width {
+                Constraint::Length(cells) | Constraint::Min(cells) => *cells,
+                _ => 0,
+            })
+            .sum::<u16>();
+        UnicodeWidthStr::width(HIGHLIGHT_SYMBOL) as u16
+            + columns
+            + widths.len() as u16
+            - 1
+    }
+
+    fn header(self, theme: &Theme) -> Row<'static> {
+        let mut titles = vec!["", "SSID"];
+        if self.band {
+            titles.push("Band");
+        }
+        if self.channel {
+            titles.push("Ch");
+        }
+        if self.security {
+            titles.push("Security");
+        }
+        titles.push("Signal");
+        Row::new(titles).style(
+            Style::default()
+                .fg(theme.subtext0)
+                .add_modifier(Modifier::BOLD),
+        )
+    }
+}
+
+pub fn create_network_row(
+    network: &WifiNetwork,
+    glyphs: &Glyphs,
+    marks: RowMarks,
+    columns: NetworkColumns,
+    theme: &Theme,
+) -> Row<'static> {
     let signal_percent = format_signal_strength(network.signal_strength);
     let frequency_band = get_frequency_band(network.frequency);
-    let security_icon = if network.is_secured() { "🔒" } else { "  " };
-    let connection_icon = if network.connected { "🔗" } else { "  " };
+    let generation_badge = network
+        .generation()
+        .map(|generation| generation.badge())
+        .unwrap_or("");
+    let security_icon = if network.is_secured() {
+        glyphs.secured
+    } else {
+        glyphs.blank
+    };
+    let saved_icon = if network.saved {
+        glyphs.saved
+    } else {
+        glyphs.blank
+    };
+    let favorite_icon = if marks.favorite {
+        glyphs.favorite
+    } else {
+        glyphs.blank
+    };
+    let (connection_icon, connection_color) = if network.connected {
+        (glyphs.connected, theme.green)
+    } else if marks.failed {
+        (glyphs.failed, theme.red)
+    } else {
+        (glyphs.blank, theme.green)
+    };
 
     let signal_color = match network.signal_strength {
-        80..=100 => CatppuccinColors::GREEN,
-        60..=79 => CatppuccinColors::YELLOW,
-        40..=59 => CatppuccinColors::PEACH,
-        _ => CatppuccinColors::RED,
+        80..=100 => theme.green,
+        60..=79 => theme.yellow,
+        40..=59 => theme.peach,
+        _ => theme.red,
     };
-    let ssid_color = if network.connected {
-        CatppuccinColors::GREEN
+    let (ssid_text, ssid_color) = if network.has_invisible_characters() {
+        (format!("⚠ {}", network.ssid), theme.peach)
+    } else if network.connected {
+        (network.ssid.clone(), theme.green)
     } else {
-        CatppuccinColors::TEXT
+        (network.ssid.clone(), theme.text)
     };
 
-    ListItem::new(Line::from(vec![
-        Span::styled(
-            connection_icon.to_string(),
-            Style::default().fg(CatppuccinColors::GREEN),
-        ),
-        Span::styled(
-            format!("{} ", security_icon),
-            Style::default().fg(CatppuccinColors::MAUVE),
-        ),
-        Span::styled(
-            format_ssid_column(&network.ssid, 24),
-            Style::default().fg(ssid_color),
+    let signal_text = match (marks.stale_for, columns.signal_graph) {
+        (Some(elapsed), true) => {
+            format!("last seen {} ago", format_duration(elapsed.as_secs()))
+        }
+        (Some(elapsed), false) => {
+            format!("{} ago", format_duration(elapsed.as_secs()))
+        }
+        (None, true) => format!(
+            "{:>4} {}",
+            signal_percent,
+            create_signal_graph(network.signal_strength)
         ),
-        Span::styled(
-            format!("{:>4} ", frequency_band),
-            Style::default().fg(CatppuccinColors::SAPPHIRE),
-        ),
-        Span::styled(
-            format!("{:>4} ", signal_percent),
-            Style::default().fg(signal_color),
-        ),
-        Span::styled(signal_graph, Style::default().fg(signal_color)),
-    ]))
+        (None, false) => format!("{:>4}", signal_percent),
+    };
+    let style = |color| {
+        if marks.stale_for.is_some() || marks.ignored {
+            Style::default().fg(theme.overlay0)
+        } else {
+            Style::default().fg(color)
+        }
+    };
+
+    let mut cells = vec![
+        Cell::from(Line::from(vec![
+            Span::styled(connection_icon, style(connection_color)),
+            Span::styled(favorite_icon, style(theme.pink)),
+            Span::styled(saved_icon, style(theme.yellow)),
+        ])),
+        Cell::from(Line::from(highlighted_ssid(
+            display_ssid(&ssid_text),
+            marks.query,
+            style(ssid_color),
+            theme,
+        ))),
+    ];
+    if columns.band {
+        cells.push(Cell::from(Line::from(vec![
+            Span::styled(
+                format!("{:<4} ", frequency_band),
+                style(theme.sapphire),
+            ),
+            Span::styled(generation_badge, style(theme.lavender)),
+        ])));
+    }
+    if columns.channel {
+        let channel = network
+            .channel()
+            .map_or_else(|| "-".to_string(), |channel| channel.to_string());
+        cells.push(Cell::from(
+            Line::styled(channel, style(theme.sapphire)).right_aligned(),
+        ));
+    }
+    if columns.security {
+        cells.push(Cell::from(Span::styled(
+            format!("{security_icon} {}", network.security.short_name()),
+            style(theme.mauve),
+        )));
+    }
+    cells.push(Cell::from(Span::styled(signal_text, style(signal_color))));
+    Row::new(cells)
+}
+
+/// The SSID with the characters matching the list filter picked out.
+fn highlighted_ssid(
+    ssid: String,
+    query: &str,
+    style: Style,
+    theme: &Theme,
+) -> Vec<Span<'static>> {
+    let Some(positions) =
+        fuzzy_match(query, &ssid).filter(|positions| !positions.is_empty())
+    else {
+        return vec![Span::styled(ssid, style)];
+    };
+
+    let matched = style
+        .fg(theme.peach)
+        .add_modifier(Modifier::BOLD | Modifier::UNDERLINED);
+    let mut spans: Vec<Span<'static>> = Vec::new();
+    for (index, c) in ssid.chars().enumerate() {
+        let char_style = if positions.contains(&index) {
+            matched
+        } else {
+            style
+        };
+        match spans.last_mut() {
+            Some(span) if span.style == char_style => {
+                span.content.to_mut().push(c)
+            }
+            _ => spans.push(Span::styled(c.to_string(), char_style)),
+        }
+    }
+    spans
 }
 
 pub(crate) fn render_network_list_background(
@@ -64,30 +448,198 @@ pub(crate) fn render_network_list_background(
     area: Rect,
     title: Option<Line<'static>>,
 ) {
-    let items: Vec<ListItem> =
-        app.networks.iter().map(create_network_list_item).collect();
+    let theme = app.colors();
+    let visible_indices = app.visible_network_indices();
 
-    let mut block =
-        Block::default().style(Style::default().bg(CatppuccinColors::BASE));
+    let mut block = Block::default().style(Style::default().bg(theme.base));
     if let Some(title) = title {
         block = block.title(title);
     }
     block = block.borders(Borders::ALL);
 
-    let list = List::new(items)
-        .block(block)
-        .highlight_style(
+    let list_area = block.inner(area);
+    f.render_widget(block, area);
+
+    let banner_lines = banner_lines(app, visible_indices.len());
+    let chunks = split_banners(list_area, banner_lines.len());
+    f.render_widget(Paragraph::new(banner_lines), chunks[0]);
+    let list_area = chunks[1];
+
+    let columns = NetworkColumns::fitting(list_area.width, &app.glyphs);
+
+    // Dense areas can list hundreds of networks; only the rows on screen are
+    // built, scrolled the way a fresh `TableState` would scroll them.
+    let selected = (!visible_indices.is_empty())
+        .then(|| app.selected_index.min(visible_indices.len() - 1));
+    let rows = visible_rows(
+        selected.unwrap_or_default(),
+        visible_indices.len(),
+        list_area.height.saturating_sub(1) as usize,
+    );
+    let table_rows =
+        app.list_rows
+            .rows(app, &visible_indices[rows.clone()], columns);
+
+    let table = Table::new(table_rows, columns.widths(&app.glyphs))
+        .header(columns.header(theme))
+        .row_highlight_style(
             Style::default()
-                .bg(CatppuccinColors::SURFACE0)
-                .fg(CatppuccinColors::TEXT)
+                .bg(theme.surface0)
+                .fg(theme.text)
                 .add_modifier(Modifier::BOLD),
         )
-        .highlight_symbol("► ");
+        .highlight_symbol(HIGHLIGHT_SYMBOL)
+        .highlight_spacing(HighlightSpacing::Always);
+
+    let mut table_state = TableState::default();
+    table_state.select(selected.map(|selected| selected - rows.start));
+
+    f.render_stateful_widget(table, list_area, &mut table_state);
+}
 
-    let mut list_state = ListState::default();
-    if !app.networks.is_empty() {
-        list_state.select(Some(app.selected_index.min(app.networks.len() - 1)));
+/// The position in `visible_networks` of the row drawn at `position` by a
+/// list rendered into `area`.
+pub(crate) fn network_row_at(
+    app: &App,
+    area: Rect,
+    position: Position,
+) -> Option<usize> {
+    let visible_count = app.visible_networks().len();
+    let list_area = Block::default().borders(Borders::ALL).inner(area);
+    let table_area =
+        split_banners(list_area, banner_lines(app, visible_count).len())[1];
+    // The header row sits above the networks.
+    let rows_area = Rect {
+        y: table_area.y.saturating_add(1),
+        height: table_area.height.saturating_sub(1),
+        ..table_area
+    };
+    if visible_count == 0 || !rows_area.contains(position) {
+        return None;
+    }
+
+    let rows = visible_rows(
+        app.selected_index.min(visible_count - 1),
+        visible_count,
+        rows_area.height as usize,
+    );
+    let index = rows.start + usize::from(position.y - rows_area.y);
+    rows.contains(&index).then_some(index)
+}
+
+/// Lines shown above the networks: the captive portal, the suggestion, and
+/// the active filters and search.
+fn banner_lines(app: &App, visible_count: usize) -> Vec<Line<'static>> {
+    let theme = app.colors();
+    let mut lines = Vec::new();
+    if let Some(url) = &app.captive_portal {
+        lines.push(portal_line(url, theme));
     }
+    if let Some(network) = app.suggested_network() {
+        lines.push(suggestion_line(network, theme));
+    }
+    if app.filters.is_active() {
+        lines.push(filter_chips_line(app, visible_count));
+    }
+    if app.search_editing || !app.search_query.is_empty() {
+        lines.push(search_line(app, visible_count));
+    }
+    lines
+}
 
-    f.render_stateful_widget(list, area, &mut list_state);
+fn split_banners(area: Rect, banner_count: usize) -> Rc<[Rect]> {
+    Layout::default()
+        .direction(Direction::Vertical)
+        .constraints([
+            Constraint::Length(banner_count as u16),
+            Constraint::Min(0),
+        ])
+        .split(area)
+}
+
+/// The rows of a `len`-row list shown in `height` lines: from the top until
+/// `selected` would fall off the bottom, then keeping it on the last line.
+pub(crate) fn visible_rows(
+    selected: usize,
+    len: usize,
+    height: usize,
+) -> Range<usize> {
+    let start = selected.saturating_sub(height.saturating_sub(1));
+    start..len.min(start + height.max(1))
+}
+
+fn suggestion_line(network: &WifiNetwork, theme: &Theme) -> Line<'static> {
+    Line::from(vec![
+        Span::styled("★ ", Style::default().fg(theme.yellow)),
+        Span::styled(
+            "You usually connect to ",
+            Style::default().fg(theme.subtext0),
+        ),
+        Span::styled(
+            display_ssid(&network.ssid),
+            Style::default().fg(theme.text).add_modifier(Modifier::BOLD),
+        ),
+        Span::styled(
+            " here · press y to connect",
+            Style::default().fg(theme.subtext0),
+        ),
+    ])
+}
+
+fn portal_line(url: &str, theme: &Theme) -> Line<'static> {
+    Line::from(vec![
+        Span::styled("⚠ ", Style::default().fg(theme.peach)),
+        Span::styled(
+            "Captive portal: ",
+            Style::default()
+                .fg(theme.peach)
+                .add_modifier(Modifier::BOLD),
+        ),
+        Span::styled(url.to_string(), Style::default().fg(theme.text)),
+        Span::styled(
+            " · press o to sign in",
+            Style::default().fg(theme.subtext0),
+        ),
+    ])
+}
+
+fn search_line(app: &App, visible_count: usize) -> Line<'static> {
+    let theme = app.colors();
+    let hint = if app.search_editing {
+        "▏ · Enter keep · Esc clear"
+    } else {
+        " · / edit · Esc clear"
+    };
+    Line::from(vec![
+        Span::styled("/", Style::default().fg(theme.blue)),
+        Span::styled(
+            app.search_query.clone(),
+            Style::default().fg(theme.text).add_modifier(Modifier::BOLD),
+        ),
+        Span::styled(hint, Style::default().fg(theme.subtext0)),
+        Span::styled(
+            format!("  {visible_count} of {} shown", app.networks.len()),
+            Style::default().fg(theme.subtext0),
+        ),
+    ])
+}
+
+fn filter_chips_line(app: &App, visible_count: usize) -> Line<'static> {
+    let theme = app.colors();
+    let mut spans = Vec::new();
+    for filter in app.filters.active() {
+        spans.push(Span::styled(
+            format!(" {} ", filter.label()),
+            Style::default()
+                .fg(theme.base)
+                .bg(theme.blue)
+                .add_modifier(Modifier::BOLD),
+        ));
+        spans.push(Span::raw(" "));
+    }
+    spans.push(Span::styled(
+        format!("{visible_count} of {} shown", app.networks.len()),
+        Style::default().fg(theme.subtext0),
+    ));
+    Line::from(spans)
 }
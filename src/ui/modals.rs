@@ -1,20 +1,76 @@
+use std::time::{Instant, SystemTime, UNIX_EPOCH};
+
 use ratatui::{
     Frame,
     layout::{Alignment, Constraint, Direction, Layout, Rect},
     style::{Color, Modifier, Style},
     text::{Line, Span},
-    widgets::{Block, Borders, Clear, Paragraph},
+    widgets::{
+        Bar,
+        BarChart,
+        Block,
+        Borders,
+        Clear,
+        Gauge,
+        Paragraph,
+        Sparkline,
+    },
 };
 
-use super::format::get_frequency_band;
-use crate::{app_state::App, theme::CatppuccinColors, wifi::WifiNetwork};
+use super::{
+    format::{
+        create_signal_graph,
+        display_ssid,
+        format_bitrate,
+        format_duration,
+        format_ssid_column,
+        format_throughput,
+        format_timestamp,
+        format_uptime,
+        get_frequency_band,
+    },
+    list::visible_rows,
+};
+use crate::{
+    app_state::{
+        App,
+        AutoconnectCheck,
+        ConnectivityCheck,
+        ExportField,
+        ForgetStage,
+        OperationKind,
+        PasswordField,
+        PasswordPrefill,
+        ProfileCheck,
+        ProfileEdit,
+        SharePassword,
+    },
+    history::HistoryEvent,
+    network::{
+        ActivationProgress,
+        ActivationStep,
+        Connectivity,
+        DeviceLink,
+        DeviceState,
+        ProfilePattern,
+        ProxyConfig,
+        ProxyMode,
+        SavedProfile,
+    },
+    qr::{parse_wifi_qr, terminal_lines},
+    spectrum::least_congested,
+    speedtest::{TransferDirection, TransferProgress},
+    theme::Theme,
+    wifi::WifiNetwork,
+};
 
-pub fn render_help_screen(f: &mut Frame, _app: &App, area: Rect) {
+pub fn render_help_screen(f: &mut Frame, app: &App, area: Rect) {
+    let theme = app.colors();
     let help_text = vec![
         Line::from(vec![Span::styled(
             "Navigation",
             Style::default()
-                .fg(CatppuccinColors::MAUVE)
+                .fg(theme.mauve)
                 .add_modifier(Modifier::BOLD),
         )]),
         Line::from(""),
@@ -24,19 +80,42 @@ pub fn render_help_screen(f: &mut Frame, _app: &App, area: Rect) {
         Line::from(vec![Span::styled(
             "Actions",
             Style::default()
-                .fg(CatppuccinColors::MAUVE)
+                .fg(theme.mauve)
                 .add_modifier(Modifier::BOLD),
         )]),
         Line::from(""),
         Line::from("Enter/c    Connect or disconnect selection"),
         Line::from("d          Disconnect selected active network"),
+        Line::from("g          Guest connect to an open network"),
         Line::from("r          Rescan networks"),
+        Line::from("s          Cycle the sort order"),
+        Line::from("T          Switch the colour theme"),
+        Line::from("y          Connect to the suggested network"),
         Line::from("i          Show network details"),
+        Line::from("u          Clear an autoconnect block (in details)"),
+        Line::from("a          Access points of the connected SSID"),
+        Line::from("w          Channel spectrum of every band"),
+        Line::from("S          Site survey of the selected network"),
+        Line::from("H          Connection history"),
+        Line::from("J          NetworkManager log"),
+        Line::from("t          Speed test the connected network"),
+        Line::from("R          Reconnect the current network"),
+        Line::from("L          Renew the DHCP lease"),
+        Line::from("M          Let NetworkManager manage the adapter"),
+        Line::from("o          Open the captive portal login page"),
+        Line::from("x          Share as QR code / export file"),
+        Line::from(":          Command palette (forget, export, import, ...)"),
+        Line::from("Tab        Ethernet, mobile and Bluetooth"),
+        Line::from("/          Filter by name, Esc clears"),
+        Line::from("F2/F3/F4   Filter 5 GHz / open / saved"),
+        Line::from("f          Pin the selected network to the top"),
+        Line::from("I          Ignore the selected network"),
+        Line::from("F5         Show or hide ignored networks"),
         Line::from(""),
         Line::from(vec![Span::styled(
             "Other",
             Style::default()
-                .fg(CatppuccinColors::MAUVE)
+                .fg(theme.mauve)
                 .add_modifier(Modifier::BOLD),
         )]),
         Line::from(""),
@@ -46,11 +125,12 @@ pub fn render_help_screen(f: &mut Frame, _app: &App, area: Rect) {
         Line::from(vec![Span::styled(
             "Markers",
             Style::default()
-                .fg(CatppuccinColors::MAUVE)
+                .fg(theme.mauve)
                 .add_modifier(Modifier::BOLD),
         )]),
         Line::from(""),
         Line::from("Link icon   Connected network"),
+        Line::from("Star icon   Saved profile, connects without a password"),
         Line::from("Lock icon   Protected network"),
         Line::from("2.4G/5G     Frequency band"),
     ];
@@ -62,17 +142,348 @@ pub fn render_help_screen(f: &mut Frame, _app: &App, area: Rect) {
                 .title("Help - nm-wifi")
                 .title_style(
                     Style::default()
-                        .fg(CatppuccinColors::BLUE)
+                        .fg(theme.blue)
                         .add_modifier(Modifier::BOLD),
                 ),
         )
-        .style(Style::default().bg(CatppuccinColors::BASE))
+        .style(Style::default().bg(theme.base))
         .alignment(Alignment::Left);
 
     f.render_widget(help_paragraph, area);
 }
 
+pub fn render_device_tab(f: &mut Frame, app: &App, area: Rect) {
+    let theme = app.colors();
+    let Some(tab) = &app.device_tab else {
+        return;
+    };
+
+    let mut lines = Vec::new();
+    match &tab.devices {
+        None => lines.push(Line::from(format!(
+            "Listing {} devices...",
+            tab.kind.label()
+        ))),
+        Some(Err(error)) => lines.push(Line::styled(
+            format!("Could not list {} devices: {error}", tab.kind.label()),
+            Style::default().fg(theme.red),
+        )),
+        Some(Ok(devices)) if devices.is_empty() => {
+            lines.push(Line::from(format!("No {} devices.", tab.kind.label())))
+        }
+        Some(Ok(devices)) => {
+            let selected = tab.selected_row();
+            for device in devices {
+                let state_color = match device.state {
+                    DeviceState::Connected => theme.green,
+                    DeviceState::Connecting | DeviceState::Disconnecting => {
+                        theme.yellow
+                    }
+                    DeviceState::Failed => theme.red,
+                    _ => theme.subtext1,
+                };
+                let link = match &device.link {
+                    DeviceLink::Wired { carrier: false, .. } => {
+                        "no cable".to_string()
+                    }
+                    DeviceLink::Wired {
+                        speed_mbps: Some(speed),
+                        ..
+                    } => format_bitrate(speed * 1000),
+                    DeviceLink::Wired { .. } => "link up".to_string(),
+                    DeviceLink::Modem {
+                        signal_quality,
+                        operator,
+                    } => {
+                        let signal = signal_quality.map_or_else(
+                            || "no signal".to_string(),
+                            |quality| format!("signal {quality}%"),
+                        );
+                        match operator {
+                            Some(operator) => format!("{signal}  {operator}"),
+                            None => signal,
+                        }
+                    }
+                    DeviceLink::Bluetooth { name } => name
+                        .clone()
+                        .unwrap_or_else(|| "paired device".to_string()),
+                };
+                lines.push(Line::from(vec![
+                    Span::styled(
+                        format!("{:<18}", device.interface),
+                        Style::default()
+                            .fg(theme.mauve)
+                            .add_modifier(Modifier::BOLD),
+                    ),
+                    Span::styled(
+                        format!("{:<14}", device.state.display_name()),
+                        Style::default().fg(state_color),
+                    ),
+                    Span::styled(link, Style::default().fg(theme.sapphire)),
+                ]));
+                if device.profiles.is_empty() {
+                    lines.push(Line::styled(
+                        "    no saved profiles",
+                        Style::default().fg(theme.overlay0),
+                    ));
+                }
+                for profile in &device.profiles {
+                    let is_selected =
+                        selected.is_some_and(|(row_device, row_profile)| {
+                            row_device.interface == device.interface
+                                && row_profile.uuid == profile.uuid
+                        });
+                    let mut spans = vec![Span::raw(format!(
+                        "  {} {}",
+                        if is_selected { ">" } else { " " },
+                        profile.id
+                    ))];
+                    if device.is_active(profile) {
+                        spans.push(Span::styled(
+                            "  active",
+                            Style::default().fg(theme.green),
+                        ));
+                    }
+                    lines.push(Line::from(spans));
+                }
+                lines.push(Line::from(""));
+            }
+        }
+    }
+
+    lines.push(match &tab.result {
+        _ if tab.switching => Line::from("Switching..."),
+        Some(Ok(message)) => {
+            Line::styled(message.clone(), Style::default().fg(theme.green))
+        }
+        Some(Err(error)) => {
+            Line::styled(error.clone(), Style::default().fg(theme.red))
+        }
+        None => Line::from(
+            "Enter brings the profile up, or its device down when active",
+        ),
+    });
+
+    let paragraph = Paragraph::new(lines)
+        .block(
+            Block::default()
+                .borders(Borders::ALL)
+                .title(tab.kind.label())
+                .title_style(
+                    Style::default()
+                        .fg(theme.blue)
+                        .add_modifier(Modifier::BOLD),
+                ),
+        )
+        .style(Style::default().bg(theme.base))
+        .alignment(Alignment::Left);
+
+    f.render_widget(paragraph, area);
+}
+
+pub fn render_p2p_tab(f: &mut Frame, app: &App, area: Rect) {
+    let theme = app.colors();
+    let Some(tab) = &app.p2p_tab else {
+        return;
+    };
+
+    let mut lines = Vec::new();
+    match &tab.peers {
+        None => lines.push(Line::from("Searching for Wi-Fi Direct peers...")),
+        Some(Err(error)) => lines.push(Line::styled(
+            format!("Could not search for peers: {error}"),
+            Style::default().fg(theme.red),
+        )),
+        Some(Ok(peers)) if peers.is_empty() => {
+            lines.push(Line::from("No Wi-Fi Direct peers nearby."))
+        }
+        Some(Ok(peers)) => {
+            for (index, peer) in peers.iter().enumerate() {
+                let selected = index == tab.selected;
+                let device = match (&peer.manufacturer, &peer.model) {
+                    (Some(manufacturer), Some(model)) => {
+                        format!("{manufacturer} {model}")
+                    }
+                    (Some(name), None) | (None, Some(name)) => name.clone(),
+                    (None, None) => peer.hw_address.clone(),
+                };
+                lines.push(Line::from(vec![
+                    Span::raw(if selected { "> " } else { "  " }),
+                    Span::styled(
+                        format!("{:<28}", peer.name),
+                        Style::default()
+                            .fg(theme.mauve)
+                            .add_modifier(Modifier::BOLD),
+                    ),
+                    Span::styled(
+                        format!("{:>4}%  ", peer.signal_strength),
+                        Style::default().fg(theme.sapphire),
+                    ),
+                    Span::styled(device, Style::default().fg(theme.subtext1)),
+                ]));
+            }
+        }
+    }
+
+    lines.push(Line::from(""));
+    lines.push(match &tab.result {
+        _ if tab.connecting => Line::from("Connecting..."),
+        Some(Ok(message)) => {
+            Line::styled(message.clone(), Style::default().fg(theme.green))
+        }
+        Some(Err(error)) => {
+            Line::styled(error.clone(), Style::default().fg(theme.red))
+        }
+        None => Line::from("Enter connects to the peer; accept on the device"),
+    });
+
+    let paragraph = Paragraph::new(lines)
+        .block(
+            Block::default()
+                .borders(Borders::ALL)
+                .title("Wi-Fi Direct peers")
+                .title_style(
+                    Style::default()
+                        .fg(theme.blue)
+                        .add_modifier(Modifier::BOLD),
+                ),
+        )
+        .style(Style::default().bg(theme.base))
+        .alignment(Alignment::Left);
+
+    f.render_widget(paragraph, area);
+}
+
+/// Why NM is not autoconnecting to a saved network, when it was checked.
+fn autoconnect_line(app: &App, network: &WifiNetwork) -> Option<Line<'static>> {
+    let theme = app.colors();
+    let diagnosis = app
+        .autoconnect
+        .as_ref()
+        .filter(|diagnosis| diagnosis.ssid == network.ssid)?;
+    let (text, color) = match &diagnosis.check {
+        AutoconnectCheck::Loading => {
+            ("checking...".to_string(), theme.subtext1)
+        }
+        AutoconnectCheck::Clearing => {
+            ("clearing block...".to_string(), theme.subtext1)
+        }
+        AutoconnectCheck::Loaded(None) => {
+            ("not blocked".to_string(), theme.green)
+        }
+        AutoconnectCheck::Loaded(Some(block)) if block.clearable() => (
+            format!("{} (u clears it)", block.description()),
+            theme.yellow,
+        ),
+        AutoconnectCheck::Loaded(Some(block)) => {
+            (block.description().to_string(), theme.yellow)
+        }
+        AutoconnectCheck::Failed(error) => {
+            (format!("check failed: {error}"), theme.red)
+        }
+    };
+
+    Some(Line::from(vec![
+        Span::styled(
+            "Autoconnect: ",
+            Style::default()
+                .fg(theme.mauve)
+                .add_modifier(Modifier::BOLD),
+        ),
+        Span::styled(text, Style::default().fg(color)),
+    ]))
+}
+
+fn connectivity_line(
+    check: &ConnectivityCheck,
+    theme: &Theme,
+) -> Line<'static> {
+    let (text, color) = match check {
+        ConnectivityCheck::Checking => {
+            ("checking...".to_string(), theme.subtext1)
+        }
+        ConnectivityCheck::Checked(connectivity) => {
+            let color = match connectivity {
+                Connectivity::Full => theme.green,
+                Connectivity::Portal | Connectivity::Limited => theme.yellow,
+                Connectivity::None => theme.red,
+                Connectivity::Unknown => theme.subtext1,
+            };
+            (connectivity.description().to_string(), color)
+        }
+        ConnectivityCheck::Failed(error) => {
+            (format!("check failed: {error}"), theme.red)
+        }
+    };
+
+    Line::from(vec![
+        Span::styled(
+            "Internet: ",
+            Style::default()
+                .fg(theme.mauve)
+                .add_modifier(Modifier::BOLD),
+        ),
+        Span::styled(text, Style::default().fg(color)),
+    ])
+}
+
+/// Proxy and visibility of the saved profile, once it was looked up.
+fn profile_lines(app: &App, network: &WifiNetwork) -> Vec<Line<'static>> {
+    let theme = app.colors();
+    let Some(lookup) = app
+        .profile_lookup
+        .as_ref()
+        .filter(|lookup| lookup.ssid == network.ssid)
+    else {
+        return Vec::new();
+    };
+    let (text, color) = match &lookup.check {
+        ProfileCheck::Loading => ("loading...".to_string(), theme.subtext1),
+        ProfileCheck::Loaded(Some(settings)) => match &settings.proxy {
+            ProxyConfig::None => ("none".to_string(), theme.text),
+            proxy => (proxy.description(), theme.sapphire),
+        },
+        ProfileCheck::Loaded(None) => {
+            ("no saved profile".to_string(), theme.subtext1)
+        }
+        ProfileCheck::Failed(error) => {
+            (format!("lookup failed: {error}"), theme.red)
+        }
+    };
+    let label = |label: &'static str| {
+        Span::styled(
+            label,
+            Style::default()
+                .fg(theme.mauve)
+                .add_modifier(Modifier::BOLD),
+        )
+    };
+
+    let mut lines = vec![
+        Line::from(""),
+        Line::from(vec![
+            label("Proxy: "),
+            Span::styled(text, Style::default().fg(color)),
+        ]),
+    ];
+    if let ProfileCheck::Loaded(Some(settings)) = &lookup.check {
+        lines.push(Line::from(vec![
+            label("Available to: "),
+            if settings.users.is_empty() {
+                Span::styled("all users", Style::default().fg(theme.text))
+            } else {
+                Span::styled(
+                    format!("only {}", settings.users.join(", ")),
+                    Style::default().fg(theme.sapphire),
+                )
+            },
+        ]));
+    }
+    lines
+}
+
 pub fn render_network_details(f: &mut Frame, app: &App) {
+    let theme = app.colors();
     if let Some(network) = app.selected_network_in_list() {
         let popup_area = centered_rect(60, 70, f.area());
         f.render_widget(Clear, popup_area);
@@ -89,74 +500,142 @@ pub fn render_network_details(f: &mut Frame, app: &App) {
 
         let signal_text =
             format!("{}% ({})", network.signal_strength, signal_description);
+        let status_text = match app.uptime_of(network) {
+            Some(uptime) => format!("Connected for {}", format_uptime(uptime)),
+            None if network.connected => "Connected".to_string(),
+            None => "Available".to_string(),
+        };
         let frequency_text = format!(
             "{} MHz ({})",
             network.frequency,
             get_frequency_band(network.frequency)
         );
+        let generation_text = network.generation().map_or_else(
+            || "Legacy (802.11a/b/g), estimated".to_string(),
+            |generation| format!("{}, estimated", generation.display_name()),
+        );
+        let suite_lines = [
+            ("WPA: ", network.wpa_suites()),
+            ("RSN: ", network.rsn_suites()),
+        ]
+        .into_iter()
+        .filter(|(_, suites)| !suites.is_empty())
+        .map(|(label, suites)| {
+            Line::from(vec![
+                Span::styled(
+                    label,
+                    Style::default()
+                        .fg(theme.mauve)
+                        .add_modifier(Modifier::BOLD),
+                ),
+                Span::styled(
+                    suites.summary(),
+                    Style::default().fg(if suites.uses_tkip() {
+                        theme.yellow
+                    } else {
+                        theme.text
+                    }),
+                ),
+            ])
+        })
+        .collect::<Vec<_>>();
 
-        let details_text = vec![
+        let mut details_text = vec![
             Line::from(vec![
                 Span::styled(
                     "SSID: ",
                     Style::default()
-                        .fg(CatppuccinColors::MAUVE)
+                        .fg(theme.mauve)
                         .add_modifier(Modifier::BOLD),
                 ),
                 Span::styled(
-                    &network.ssid,
-                    Style::default().fg(CatppuccinColors::TEXT),
+                    display_ssid(&network.ssid),
+                    Style::default().fg(theme.text),
                 ),
             ]),
-            Line::from(""),
+            if network.has_invisible_characters() {
+                invisible_characters_warning(theme)
+            } else {
+                Line::from("")
+            },
             Line::from(vec![
                 Span::styled(
                     "Status: ",
                     Style::default()
-                        .fg(CatppuccinColors::MAUVE)
+                        .fg(theme.mauve)
                         .add_modifier(Modifier::BOLD),
                 ),
                 Span::styled(
-                    if network.connected {
-                        "Connected"
-                    } else {
-                        "Available"
-                    },
+                    status_text,
                     Style::default().fg(if network.connected {
-                        CatppuccinColors::GREEN
+                        theme.green
                     } else {
-                        CatppuccinColors::TEXT
+                        theme.text
                     }),
                 ),
             ]),
+        ];
+        details_text.extend(autoconnect_line(app, network));
+        if let Some(error) = app.connect_errors.get(&network.ssid) {
+            details_text.push(Line::from(vec![
+                Span::styled(
+                    "Last error: ",
+                    Style::default()
+                        .fg(theme.mauve)
+                        .add_modifier(Modifier::BOLD),
+                ),
+                Span::styled(error.clone(), Style::default().fg(theme.red)),
+            ]));
+        }
+        details_text.extend([
             Line::from(""),
             Line::from(vec![
                 Span::styled(
                     "Security: ",
                     Style::default()
-                        .fg(CatppuccinColors::MAUVE)
+                        .fg(theme.mauve)
+                        .add_modifier(Modifier::BOLD),
+                ),
+                Span::styled(security_type, Style::default().fg(theme.text)),
+            ]),
+        ]);
+        details_text.extend(suite_lines);
+        if network.is_secured() {
+            details_text.push(Line::from(vec![
+                Span::styled(
+                    "PMF: ",
+                    Style::default()
+                        .fg(theme.mauve)
                         .add_modifier(Modifier::BOLD),
                 ),
                 Span::styled(
-                    security_type,
-                    Style::default().fg(CatppuccinColors::TEXT),
+                    network.rsn_suites().pmf_capability().description(),
+                    Style::default().fg(theme.text),
                 ),
-            ]),
+            ]));
+        }
+        if network.uses_tkip() {
+            details_text.push(Line::from(Span::styled(
+                "Legacy TKIP cipher advertised",
+                Style::default().fg(theme.yellow),
+            )));
+        }
+        details_text.extend([
             Line::from(""),
             Line::from(vec![
                 Span::styled(
                     "Signal Strength: ",
                     Style::default()
-                        .fg(CatppuccinColors::MAUVE)
+                        .fg(theme.mauve)
                         .add_modifier(Modifier::BOLD),
                 ),
                 Span::styled(
                     &signal_text,
                     Style::default().fg(match network.signal_strength {
-                        80..=100 => CatppuccinColors::GREEN,
-                        60..=79 => CatppuccinColors::YELLOW,
-                        40..=59 => CatppuccinColors::PEACH,
-                        _ => CatppuccinColors::RED,
+                        80..=100 => theme.green,
+                        60..=79 => theme.yellow,
+                        40..=59 => theme.peach,
+                        _ => theme.red,
                     }),
                 ),
             ]),
@@ -165,43 +644,69 @@ pub fn render_network_details(f: &mut Frame, app: &App) {
                 Span::styled(
                     "Frequency: ",
                     Style::default()
-                        .fg(CatppuccinColors::MAUVE)
+                        .fg(theme.mauve)
                         .add_modifier(Modifier::BOLD),
                 ),
                 Span::styled(
                     &frequency_text,
-                    Style::default().fg(CatppuccinColors::SAPPHIRE),
+                    Style::default().fg(theme.sapphire),
                 ),
             ]),
             Line::from(""),
-            Line::from(""),
             Line::from(vec![
                 Span::styled(
-                    "Press ",
-                    Style::default().fg(CatppuccinColors::SUBTEXT1),
-                ),
-                Span::styled(
-                    "i",
+                    "Generation: ",
                     Style::default()
-                        .fg(CatppuccinColors::GREEN)
+                        .fg(theme.mauve)
                         .add_modifier(Modifier::BOLD),
                 ),
                 Span::styled(
-                    " or ",
-                    Style::default().fg(CatppuccinColors::SUBTEXT1),
+                    generation_text,
+                    Style::default().fg(theme.lavender),
                 ),
+            ]),
+        ]);
+        if network.connected {
+            details_text.extend([
+                Line::from(""),
+                Line::from(vec![
+                    Span::styled(
+                        "Link Rate: ",
+                        Style::default()
+                            .fg(theme.mauve)
+                            .add_modifier(Modifier::BOLD),
+                    ),
+                    Span::styled(
+                        app.link_bitrate
+                            .map(format_bitrate)
+                            .unwrap_or_else(|| "measuring...".to_string()),
+                        Style::default().fg(theme.teal),
+                    ),
+                ]),
+            ]);
+        }
+        details_text.extend(profile_lines(app, network));
+        details_text.extend([
+            Line::from(""),
+            Line::from(""),
+            Line::from(vec![
+                Span::styled("Press ", Style::default().fg(theme.subtext1)),
                 Span::styled(
-                    "Esc",
+                    "i",
                     Style::default()
-                        .fg(CatppuccinColors::GREEN)
+                        .fg(theme.green)
                         .add_modifier(Modifier::BOLD),
                 ),
+                Span::styled(" or ", Style::default().fg(theme.subtext1)),
                 Span::styled(
-                    " to close",
-                    Style::default().fg(CatppuccinColors::SUBTEXT1),
+                    "Esc",
+                    Style::default()
+                        .fg(theme.green)
+                        .add_modifier(Modifier::BOLD),
                 ),
+                Span::styled(" to close", Style::default().fg(theme.subtext1)),
             ]),
-        ];
+        ]);
 
         let details_paragraph = Paragraph::new(details_text)
             .block(
@@ -210,11 +715,11 @@ pub fn render_network_details(f: &mut Frame, app: &App) {
                     .title("Network Details")
                     .title_style(
                         Style::default()
-                            .fg(CatppuccinColors::BLUE)
+                            .fg(theme.blue)
                             .add_modifier(Modifier::BOLD),
                     ),
             )
-            .style(Style::default().bg(CatppuccinColors::BASE))
+            .style(Style::default().bg(theme.base))
             .alignment(Alignment::Left);
 
         f.render_widget(details_paragraph, popup_area);
@@ -230,10 +735,10 @@ fn modal_shadow_area(popup_area: Rect) -> Rect {
     }
 }
 
-fn render_modal_shell(f: &mut Frame, popup_area: Rect) {
+fn render_modal_shell(f: &mut Frame, popup_area: Rect, theme: &Theme) {
     f.render_widget(Clear, popup_area);
     f.render_widget(
-        Block::default().style(Style::default().bg(CatppuccinColors::SURFACE0)),
+        Block::default().style(Style::default().bg(theme.surface0)),
         modal_shadow_area(popup_area),
     );
 }
@@ -256,24 +761,40 @@ fn render_modal(
     title: &str,
     border_color: Color,
     lines: Vec<Line<'static>>,
+    theme: &Theme,
 ) {
-    render_modal_shell(f, popup_area);
+    render_modal_shell(f, popup_area, theme);
     let modal = Paragraph::new(lines)
         .block(modal_block(title, border_color))
-        .style(Style::default().bg(CatppuccinColors::BASE))
+        .style(Style::default().bg(theme.base))
         .alignment(Alignment::Left);
 
     f.render_widget(modal, popup_area);
 }
 
+fn invisible_characters_warning(theme: &Theme) -> Line<'static> {
+    Line::styled(
+        "⚠ The name hides invisible characters; it may imitate another network",
+        Style::default().fg(theme.peach),
+    )
+}
+
 fn network_summary_lines(
     network: &WifiNetwork,
     include_signal: bool,
+    theme: &Theme,
 ) -> Vec<Line<'static>> {
-    let mut lines = vec![
-        Line::from(format!("Network: {}", network.ssid)),
-        Line::from(format!("Security: {}", network.security.display_name())),
-    ];
+    let mut lines = vec![Line::from(format!(
+        "Network: {}",
+        display_ssid(&network.ssid)
+    ))];
+    if network.has_invisible_characters() {
+        lines.push(invisible_characters_warning(theme));
+    }
+    lines.push(Line::from(format!(
+        "Security: {}",
+        network.security.display_name()
+    )));
 
     if include_signal {
         lines.push(Line::from(format!(
@@ -286,139 +807,1431 @@ fn network_summary_lines(
     lines
 }
 
+fn input_box_lines(
+    content: String,
+    content_style: Style,
+    focused: bool,
+    theme: &Theme,
+) -> [Line<'static>; 3] {
+    let border_style =
+        Style::default().fg(if focused { theme.blue } else { theme.surface2 });
+
+    [
+        Line::from(vec![
+            Span::styled("┌", border_style),
+            Span::styled("─".repeat(40), border_style),
+            Span::styled("┐", border_style),
+        ]),
+        Line::from(vec![
+            Span::styled("│ ", border_style),
+            Span::styled(
+                format!("{:<38}", content),
+                content_style.bg(theme.surface0),
+            ),
+            Span::styled(" │", border_style),
+        ]),
+        Line::from(vec![
+            Span::styled("└", border_style),
+            Span::styled("─".repeat(40), border_style),
+            Span::styled("┘", border_style),
+        ]),
+    ]
+}
+
 pub fn render_enhanced_password_modal(f: &mut Frame, app: &App) {
+    let theme = app.colors();
     if let Some(network) = &app.selected_network {
-        let popup_area = centered_rect(64, 28, f.area());
+        let popup_area = centered_rect(64, 60, f.area());
         let password_display = if app.password_visible {
             app.password_input.clone()
         } else {
-            "•".repeat(app.password_input.len())
+            app.password_mask.apply(&app.password_input)
         };
-        let password_field = format!("{:<38}", password_display);
+        let (profile_display, profile_style) =
+            if app.profile_name_input.is_empty() {
+                (
+                    format!("nm-wifi-{}", network.ssid),
+                    Style::default().fg(theme.overlay0),
+                )
+            } else {
+                (
+                    app.profile_name_input.clone(),
+                    Style::default().fg(theme.text),
+                )
+            };
 
-        let mut password_text = network_summary_lines(network, false);
+        let mut password_text = network_summary_lines(network, false, theme);
+        password_text.push(Line::from(""));
+        password_text.push(Line::from("Password:"));
+        password_text.extend(input_box_lines(
+            password_display,
+            Style::default().fg(theme.text),
+            app.password_field == PasswordField::Password,
+            theme,
+        ));
+        if app.password_rejected {
+            password_text.push(Line::styled(
+                "Incorrect password, try again",
+                Style::default().fg(theme.red),
+            ));
+        } else if app.password_prefill == PasswordPrefill::Loading {
+            password_text.push(Line::styled(
+                "Reading the saved password...",
+                Style::default().fg(theme.overlay0),
+            ));
+        } else if app.password_prefill == PasswordPrefill::Filled {
+            password_text.push(Line::styled(
+                "Filled in from the saved profile",
+                Style::default().fg(theme.green),
+            ));
+        }
+        password_text.push(Line::from("Profile name (optional):"));
+        password_text.extend(input_box_lines(
+            profile_display,
+            profile_style,
+            app.password_field == PasswordField::ProfileName,
+            theme,
+        ));
+        let choice_line = |label: &'static str, value: &str, field| {
+            let style = if app.password_field == field {
+                Style::default().fg(theme.blue).add_modifier(Modifier::BOLD)
+            } else {
+                Style::default().fg(theme.text)
+            };
+            Line::from(vec![
+                Span::raw(label),
+                Span::styled(format!("◂ {value} ▸"), style),
+            ])
+        };
+        let visibility = match (&app.user_name, app.private_profile) {
+            (Some(user), true) => format!("only {user}"),
+            _ => "all users".to_string(),
+        };
+        password_text.push(choice_line(
+            "Proxy: ",
+            app.proxy_mode_input.label(),
+            PasswordField::ProxyMode,
+        ));
+        let proxy_field = match app.proxy_mode_input {
+            ProxyMode::None => None,
+            ProxyMode::Auto => Some(("PAC URL (optional):", "WPAD discovery")),
+            ProxyMode::Manual => {
+                Some(("Proxy host:port:", "proxy.example:3128"))
+            }
+        };
+        if let Some((label, placeholder)) = proxy_field {
+            let (proxy_display, proxy_style) = if app.proxy_input.is_empty() {
+                (placeholder.to_string(), Style::default().fg(theme.overlay0))
+            } else {
+                (app.proxy_input.clone(), Style::default().fg(theme.text))
+            };
+            password_text.push(Line::from(label));
+            password_text.extend(input_box_lines(
+                proxy_display,
+                proxy_style,
+                app.password_field == PasswordField::ProxyAddress,
+                theme,
+            ));
+        }
         password_text.extend([
+            choice_line(
+                "Management frame protection (PMF): ",
+                app.pmf_input.label(),
+                PasswordField::Pmf,
+            ),
+            choice_line(
+                "Profile available to: ",
+                &visibility,
+                PasswordField::Visibility,
+            ),
+            choice_line(
+                "Password stored in: ",
+                if app.keyring_secrets {
+                    "my keyring"
+                } else {
+                    "system profile"
+                },
+                PasswordField::SecretStorage,
+            ),
             Line::from(""),
-            Line::from("Password:"),
-            Line::from(""),
-            Line::from(vec![
-                Span::styled(
-                    "┌",
-                    Style::default().fg(CatppuccinColors::SURFACE2),
+            Line::from(
+                "Enter: connect  Tab: show/hide  ↑↓: field  ←→: choose  Esc: cancel",
+            ),
+        ]);
+
+        render_modal(
+            f,
+            popup_area,
+            "Password",
+            theme.blue,
+            password_text,
+            theme,
+        );
+    }
+}
+
+pub fn render_qr_share_modal(f: &mut Frame, app: &App) {
+    let theme = app.colors();
+    let Some(share) = &app.qr_share else {
+        return;
+    };
+
+    let mut lines = Vec::new();
+    match (&share.password, share.payload()) {
+        (_, Some(payload)) => match terminal_lines(&payload) {
+            Ok(qr_lines) => lines.extend(qr_lines.into_iter().map(|line| {
+                Line::styled(line, Style::default().fg(theme.text))
+                    .alignment(Alignment::Center)
+            })),
+            Err(error) => lines.push(Line::styled(
+                format!("Could not encode QR code: {error}"),
+                Style::default().fg(theme.red),
+            )),
+        },
+        (SharePassword::Loading, None) => {
+            lines.push(Line::from("Reading saved password..."));
+        }
+        (SharePassword::Failed(error), None) => lines.push(Line::styled(
+            format!("Could not read saved password: {error}"),
+            Style::default().fg(theme.red),
+        )),
+        (SharePassword::Loaded(_), None) => lines.push(Line::styled(
+            "No saved password for this network",
+            Style::default().fg(theme.red),
+        )),
+    }
+
+    lines.push(Line::from(""));
+    lines.push(
+        Line::from(format!(
+            "Scan to join {}",
+            display_ssid(&share.network.ssid)
+        ))
+        .alignment(Alignment::Center),
+    );
+    lines.push(Line::from(""));
+    lines.push(Line::from("Export to (.png or .svg):"));
+    lines.extend(input_box_lines(
+        share.export_path.clone(),
+        Style::default().fg(theme.text),
+        true,
+        theme,
+    ));
+    match &share.export_result {
+        Some(Ok(message)) => lines.push(Line::styled(
+            message.clone(),
+            Style::default().fg(theme.green),
+        )),
+        Some(Err(message)) => lines.push(Line::styled(
+            message.clone(),
+            Style::default().fg(theme.red),
+        )),
+        None => lines.push(Line::from("")),
+    }
+    lines.push(Line::from("Enter: export  Esc: close"));
+
+    let area = f.area();
+    let width = lines
+        .iter()
+        .map(Line::width)
+        .max()
+        .unwrap_or_default()
+        .max(44) as u16
+        + 4;
+    let height = lines.len() as u16 + 2;
+    let popup_area = Rect {
+        x: area.x + area.width.saturating_sub(width) / 2,
+        y: area.y + area.height.saturating_sub(height) / 2,
+        width: width.min(area.width),
+        height: height.min(area.height),
+    };
+
+    render_modal(f, popup_area, "Share", theme.blue, lines, theme);
+}
+
+/// Signal advantage, in percentage points, at which another AP of the same
+/// SSID and band is pointed out as a better choice than the associated one.
+const ROAMING_HINT_MARGIN: u8 = 10;
+
+/// Access points per channel of one band as bars, coloured by the strongest
+/// of them, with the quietest channel to put an access point on.
+pub fn render_spectrum_view(f: &mut Frame, app: &App, area: Rect) {
+    let theme = app.colors();
+    let loads = app.spectrum();
+    let quietest = least_congested(&loads, app.spectrum_band);
+
+    let bars = loads
+        .iter()
+        .map(|load| {
+            let color = match load.strongest {
+                0 => theme.overlay0,
+                70.. => theme.red,
+                40..70 => theme.yellow,
+                _ => theme.green,
+            };
+            let label_style = if Some(load.channel) == quietest {
+                Style::default()
+                    .fg(theme.green)
+                    .add_modifier(Modifier::BOLD)
+            } else {
+                Style::default().fg(theme.subtext1)
+            };
+            Bar::with_label(
+                Line::styled(load.channel.to_string(), label_style),
+                load.access_points as u64,
+            )
+            .style(Style::default().fg(color))
+        })
+        .collect::<Vec<_>>();
+
+    let chunks = Layout::default()
+        .direction(Direction::Vertical)
+        .constraints([Constraint::Min(5), Constraint::Length(2)])
+        .split(area);
+
+    let chart = BarChart::new(bars)
+        .block(
+            Block::default()
+                .borders(Borders::ALL)
+                .title(format!(
+                    "Channel spectrum - {}Hz",
+                    app.spectrum_band.label()
+                ))
+                .title_style(
+                    Style::default()
+                        .fg(theme.blue)
+                        .add_modifier(Modifier::BOLD),
                 ),
-                Span::styled(
-                    "─".repeat(40),
-                    Style::default().fg(CatppuccinColors::SURFACE2),
+        )
+        .bar_width(3)
+        .bar_gap(1)
+        .value_style(
+            Style::default().fg(theme.base).add_modifier(Modifier::BOLD),
+        )
+        .style(Style::default().bg(theme.base));
+    f.render_widget(chart, chunks[0]);
+
+    let total: usize = loads.iter().map(|load| load.access_points).sum();
+    let summary = vec![
+        Line::from(format!(
+            "{total} access points; bar height counts them, colour is the \
+             strongest signal"
+        )),
+        match quietest {
+            Some(channel) if total > 0 => Line::styled(
+                format!("Least congested: channel {channel}"),
+                Style::default().fg(theme.green),
+            ),
+            _ => Line::from("No access points on this band"),
+        },
+    ];
+    f.render_widget(
+        Paragraph::new(summary)
+            .style(Style::default().bg(theme.base))
+            .alignment(Alignment::Left),
+        chunks[1],
+    );
+}
+
+pub fn render_connection_history(f: &mut Frame, app: &App, area: Rect) {
+    let theme = app.colors();
+    let block = Block::default()
+        .borders(Borders::ALL)
+        .title("Connection history")
+        .title_style(
+            Style::default().fg(theme.blue).add_modifier(Modifier::BOLD),
+        )
+        .style(Style::default().bg(theme.base));
+    let inner = block.inner(area);
+    f.render_widget(block, area);
+
+    let entries = &app.history.entries;
+    if entries.is_empty() {
+        f.render_widget(
+            Paragraph::new("No connections recorded yet.")
+                .style(Style::default().fg(theme.subtext1)),
+            inner,
+        );
+        return;
+    }
+
+    let selected = app.history_selected.min(entries.len() - 1);
+    let rows = visible_rows(selected, entries.len(), inner.height as usize);
+    let lines = entries
+        .iter()
+        .rev()
+        .enumerate()
+        .skip(rows.start)
+        .take(rows.len())
+        .map(|(index, entry)| {
+            let (event_color, detail) = match entry.event {
+                HistoryEvent::Connected => (theme.green, None),
+                HistoryEvent::GatewayLost => (theme.red, None),
+                HistoryEvent::Disconnected => (
+                    theme.peach,
+                    entry.duration.map(|seconds| {
+                        format!("after {}", format_duration(seconds))
+                    }),
                 ),
+            };
+            let mut spans = vec![
+                Span::raw(if index == selected { "> " } else { "  " }),
                 Span::styled(
-                    "┐",
-                    Style::default().fg(CatppuccinColors::SURFACE2),
+                    format!("{}  ", format_timestamp(entry.timestamp)),
+                    Style::default().fg(theme.subtext1),
                 ),
-            ]),
-            Line::from(vec![
                 Span::styled(
-                    "│ ",
-                    Style::default().fg(CatppuccinColors::SURFACE2),
+                    format!("{:<13}", entry.event.label()),
+                    Style::default().fg(event_color),
                 ),
                 Span::styled(
-                    password_field,
-                    Style::default()
-                        .fg(CatppuccinColors::TEXT)
-                        .bg(CatppuccinColors::SURFACE0),
+                    format_ssid_column(&display_ssid(&entry.ssid), 24),
+                    Style::default().fg(theme.text),
                 ),
-                Span::styled(
-                    " │",
-                    Style::default().fg(CatppuccinColors::SURFACE2),
+            ];
+            if let Some(detail) = detail {
+                spans.push(Span::styled(
+                    format!("  {detail}"),
+                    Style::default().fg(theme.yellow),
+                ));
+            }
+            if let Some(adapter) = &entry.adapter {
+                spans.push(Span::styled(
+                    format!("  {adapter}"),
+                    Style::default().fg(theme.sapphire),
+                ));
+            }
+            let line = Line::from(spans);
+            if index == selected {
+                line.style(
+                    Style::default()
+                        .bg(theme.surface0)
+                        .add_modifier(Modifier::BOLD),
+                )
+            } else {
+                line
+            }
+        })
+        .collect::<Vec<_>>();
+    f.render_widget(Paragraph::new(lines), inner);
+}
+
+pub fn render_nm_log(f: &mut Frame, app: &App, area: Rect) {
+    let theme = app.colors();
+    let Some(view) = app.log_view.as_ref() else {
+        return;
+    };
+
+    let scope = if !view.filtered {
+        "all".to_string()
+    } else {
+        match app.log_filters() {
+            filters if filters.is_empty() => "all".to_string(),
+            filters => filters.join(", "),
+        }
+    };
+    let block = Block::default()
+        .borders(Borders::ALL)
+        .title(format!("NetworkManager log ({scope})"))
+        .title_style(
+            Style::default().fg(theme.blue).add_modifier(Modifier::BOLD),
+        )
+        .style(Style::default().bg(theme.base));
+    let inner = block.inner(area);
+    f.render_widget(block, area);
+
+    let message = |text: String, color| {
+        Paragraph::new(text).style(Style::default().fg(color))
+    };
+    let lines = match &view.lines {
+        None => {
+            f.render_widget(
+                message("Reading the journal...".to_string(), theme.subtext1),
+                inner,
+            );
+            return;
+        }
+        Some(Err(error)) => {
+            f.render_widget(
+                message(
+                    format!("Could not read the journal: {error}"),
+                    theme.red,
                 ),
-            ]),
-            Line::from(vec![
-                Span::styled(
-                    "└",
-                    Style::default().fg(CatppuccinColors::SURFACE2),
+                inner,
+            );
+            return;
+        }
+        Some(Ok(lines)) if lines.is_empty() => {
+            f.render_widget(
+                message(
+                    "No NetworkManager messages match.".to_string(),
+                    theme.subtext1,
                 ),
-                Span::styled(
-                    "─".repeat(40),
-                    Style::default().fg(CatppuccinColors::SURFACE2),
+                inner,
+            );
+            return;
+        }
+        Some(Ok(lines)) => lines,
+    };
+
+    let height = inner.height as usize;
+    let end = lines.len() - view.scroll.min(lines.len() - 1);
+    let start = end.saturating_sub(height);
+    let rendered = lines[start..end]
+        .iter()
+        .map(|line| {
+            let lower = line.to_lowercase();
+            let color = if lower.contains("error") || lower.contains("fail") {
+                theme.red
+            } else if lower.contains("warn") {
+                theme.yellow
+            } else {
+                theme.text
+            };
+            Line::styled(line.as_str(), Style::default().fg(color))
+        })
+        .collect::<Vec<_>>();
+    f.render_widget(Paragraph::new(rendered), inner);
+}
+
+pub fn render_site_survey(f: &mut Frame, app: &App, area: Rect) {
+    let theme = app.colors();
+    let Some(survey) = app.site_survey.as_ref() else {
+        return;
+    };
+
+    let signal_color = |strength: u8| match strength {
+        80..=100 => theme.green,
+        60..=79 => theme.yellow,
+        40..=59 => theme.peach,
+        _ => theme.red,
+    };
+    let block = |title: String| {
+        Block::default()
+            .borders(Borders::ALL)
+            .title(title)
+            .title_style(
+                Style::default().fg(theme.blue).add_modifier(Modifier::BOLD),
+            )
+    };
+
+    let chunks = Layout::default()
+        .direction(Direction::Vertical)
+        .constraints([
+            Constraint::Length(7),
+            Constraint::Length(2),
+            Constraint::Min(4),
+            Constraint::Length(1),
+        ])
+        .split(area);
+
+    let channel = survey
+        .channel
+        .map_or_else(String::new, |channel| format!(", channel {channel}"));
+    let title = format!(
+        "Site survey - {} ({}{channel})",
+        display_ssid(&survey.ssid),
+        survey.bssid
+    );
+    let gauge = match survey.current() {
+        Some(strength) => Gauge::default()
+            .percent(u16::from(strength.min(100)))
+            .label(format!("{strength}%"))
+            .gauge_style(Style::default().fg(signal_color(strength))),
+        None => Gauge::default()
+            .percent(0)
+            .label("Not heard")
+            .gauge_style(Style::default().fg(theme.red)),
+    };
+    f.render_widget(
+        gauge
+            .block(block(title))
+            .style(Style::default().bg(theme.base)),
+        chunks[0],
+    );
+
+    let stats = match survey.stats() {
+        Some(stats) => Line::from(vec![
+            Span::styled("Min ", Style::default().fg(theme.subtext1)),
+            Span::styled(
+                format!("{}%", stats.min),
+                Style::default().fg(signal_color(stats.min)),
+            ),
+            Span::styled("  Avg ", Style::default().fg(theme.subtext1)),
+            Span::styled(
+                format!("{}%", stats.average),
+                Style::default().fg(signal_color(stats.average)),
+            ),
+            Span::styled("  Max ", Style::default().fg(theme.subtext1)),
+            Span::styled(
+                format!("{}%", stats.max),
+                Style::default().fg(signal_color(stats.max)),
+            ),
+            Span::styled(
+                format!(
+                    "  {} samples, {} missed",
+                    survey.samples.len(),
+                    stats.missed
                 ),
-                Span::styled(
-                    "┘",
-                    Style::default().fg(CatppuccinColors::SURFACE2),
+                Style::default().fg(theme.subtext1),
+            ),
+        ]),
+        None => Line::styled(
+            format!(
+                "Not heard in {} samples; walk closer to the access point",
+                survey.samples.len()
+            ),
+            Style::default().fg(theme.peach),
+        ),
+    };
+    f.render_widget(
+        Paragraph::new(vec![stats])
+            .style(Style::default().bg(theme.base))
+            .alignment(Alignment::Left),
+        chunks[1],
+    );
+
+    // Only the most recent samples that fit in the box.
+    let width = usize::from(chunks[2].width.saturating_sub(2));
+    let history = survey
+        .samples
+        .iter()
+        .skip(survey.samples.len().saturating_sub(width))
+        .map(|sample| sample.signal_strength.map(u64::from))
+        .collect::<Vec<_>>();
+    f.render_widget(
+        Sparkline::default()
+            .block(block("History".to_string()))
+            .data(history)
+            .max(100)
+            .style(Style::default().fg(theme.teal).bg(theme.base)),
+        chunks[2],
+    );
+
+    let export = match &survey.export_result {
+        Some(Ok(message)) => {
+            Line::styled(message.clone(), Style::default().fg(theme.green))
+        }
+        Some(Err(message)) => {
+            Line::styled(message.clone(), Style::default().fg(theme.red))
+        }
+        None => Line::styled(
+            "Walk around; weak spots show as dips in the history",
+            Style::default().fg(theme.subtext1),
+        ),
+    };
+    f.render_widget(
+        Paragraph::new(vec![export]).style(Style::default().bg(theme.base)),
+        chunks[3],
+    );
+}
+
+pub fn render_roaming_view(f: &mut Frame, app: &App) {
+    let theme = app.colors();
+    let Some(network) = app.connected_network() else {
+        return;
+    };
+
+    let popup_area = centered_rect(64, 60, f.area());
+    let access_points = app.roaming_access_points();
+
+    let header_style = Style::default()
+        .fg(theme.mauve)
+        .add_modifier(Modifier::BOLD);
+    let mut lines = vec![
+        Line::from(vec![
+            Span::styled("SSID: ", header_style),
+            Span::raw(display_ssid(&network.ssid)),
+        ]),
+        Line::from(""),
+        Line::styled(
+            format!(
+                "   {:<17}  {:<4}  {:>3}  {:>6}",
+                "BSSID", "Band", "Ch", "Signal"
+            ),
+            header_style,
+        ),
+    ];
+    lines.extend(access_points.iter().enumerate().map(
+        |(index, access_point)| {
+            let row = format!(
+                "{}{} {:<17}  {:<4}  {:>3}  {:>5}%",
+                if index == app.roaming_index { ">" } else { " " },
+                if access_point.associated { "►" } else { " " },
+                access_point.bssid,
+                get_frequency_band(access_point.frequency),
+                access_point
+                    .channel()
+                    .map(|channel| channel.to_string())
+                    .unwrap_or_else(|| "?".to_string()),
+                access_point.signal_strength,
+            );
+            let avoided =
+                app.avoided.contains(&network.ssid, &access_point.bssid);
+            let mut spans = vec![Span::styled(
+                row,
+                if access_point.associated {
+                    Style::default()
+                        .fg(theme.green)
+                        .add_modifier(Modifier::BOLD)
+                } else {
+                    Style::default()
+                },
+            )];
+            if access_point.associated {
+                spans.push(Span::styled(
+                    "  associated",
+                    Style::default().fg(theme.green),
+                ));
+            }
+            if avoided {
+                spans.push(Span::styled(
+                    "  avoided",
+                    Style::default().fg(theme.red),
+                ));
+            }
+            Line::from(spans)
+        },
+    ));
+
+    lines.push(Line::from(""));
+    let associated = access_points
+        .iter()
+        .find(|access_point| access_point.associated);
+    // Bands trade range for throughput, so only same-band APs are compared.
+    let strongest_on_band = associated.and_then(|current| {
+        access_points
+            .iter()
+            .find(|access_point| access_point.band() == current.band())
+    });
+    match (associated, strongest_on_band) {
+        (Some(current), Some(strongest))
+            if strongest.signal_strength
+                >= current.signal_strength + ROAMING_HINT_MARGIN =>
+        {
+            lines.push(Line::styled(
+                format!(
+                    "{} is {} points stronger on the same band",
+                    strongest.bssid,
+                    strongest.signal_strength - current.signal_strength
                 ),
-            ]),
-            Line::from(""),
-            Line::from("Enter: connect"),
-            Line::from("Tab: show or hide password"),
-            Line::from("Esc: cancel"),
-        ]);
+                Style::default().fg(theme.yellow),
+            ))
+        }
+        (Some(_), _) => lines.push(Line::styled(
+            "Associated with the strongest access point on its band",
+            Style::default().fg(theme.green),
+        )),
+        (None, _) => lines.push(Line::styled(
+            "Associated BSSID not reported by NetworkManager",
+            Style::default().fg(theme.overlay1),
+        )),
+    }
+    lines.extend([
+        Line::from(""),
+        Line::from("b: avoid/allow the highlighted AP  ↑↓: select  Esc: back"),
+    ]);
 
-        render_modal(
-            f,
-            popup_area,
-            "Password",
-            CatppuccinColors::BLUE,
-            password_text,
-        );
+    render_modal(f, popup_area, "Roaming", theme.sapphire, lines, theme);
+}
+
+fn transfer_line(
+    label: &str,
+    progress: &TransferProgress,
+    theme: &Theme,
+) -> Line<'static> {
+    let color = if progress.is_done() {
+        theme.green
+    } else {
+        theme.sapphire
+    };
+    Line::from(vec![
+        Span::styled(
+            format!("{label:<10}"),
+            Style::default()
+                .fg(theme.mauve)
+                .add_modifier(Modifier::BOLD),
+        ),
+        Span::styled(
+            create_signal_graph(progress.percent()),
+            Style::default().fg(color),
+        ),
+        Span::styled(
+            format!(" {:>4}%  ", progress.percent()),
+            Style::default().fg(theme.subtext1),
+        ),
+        Span::styled(
+            format_throughput(progress.mbps()),
+            Style::default().fg(theme.text).add_modifier(Modifier::BOLD),
+        ),
+    ])
+}
+
+pub fn render_speed_test(f: &mut Frame, app: &App) {
+    let theme = app.colors();
+    let Some(test) = &app.speed_test else {
+        return;
+    };
+
+    let popup_area = centered_rect(64, 40, f.area());
+    let header_style = Style::default()
+        .fg(theme.mauve)
+        .add_modifier(Modifier::BOLD);
+    let mut lines = vec![
+        Line::from(vec![
+            Span::styled("Network: ", header_style),
+            Span::raw(
+                app.connected_network()
+                    .map(|network| network.ssid.clone())
+                    .unwrap_or_default(),
+            ),
+        ]),
+        Line::from(vec![
+            Span::styled("Server: ", header_style),
+            Span::raw(app.speedtest_url.clone()),
+        ]),
+        Line::from(""),
+        transfer_line("Download", &test.download, theme),
+        transfer_line("Upload", &test.upload, theme),
+        Line::from(""),
+    ];
+
+    lines.push(match (&test.error, test.next_direction()) {
+        (Some(error), _) => Line::styled(
+            format!("Speed test failed: {error}"),
+            Style::default().fg(theme.red),
+        ),
+        (None, Some(TransferDirection::Download)) => Line::styled(
+            "Measuring download...",
+            Style::default().fg(theme.subtext1),
+        ),
+        (None, Some(TransferDirection::Upload)) => Line::styled(
+            "Measuring upload...",
+            Style::default().fg(theme.subtext1),
+        ),
+        (None, None) => Line::styled("Done", Style::default().fg(theme.green)),
+    });
+    if test.is_finished() {
+        lines.push(Line::from(""));
+        lines.push(Line::styled(
+            "Enter: run again  Esc: close",
+            Style::default().fg(theme.overlay1),
+        ));
     }
+
+    render_modal(f, popup_area, "Speed Test", theme.sapphire, lines, theme);
+}
+
+pub fn render_command_palette(f: &mut Frame, app: &App) {
+    let theme = app.colors();
+    let Some(palette) = &app.palette else {
+        return;
+    };
+
+    let popup_area = centered_rect(56, 40, f.area());
+    let mut lines = input_box_lines(
+        format!(":{}", palette.query),
+        Style::default().fg(theme.text),
+        true,
+        theme,
+    )
+    .to_vec();
+
+    let commands = palette.commands();
+    if commands.is_empty() {
+        lines.push(Line::styled(
+            "No matching commands",
+            Style::default().fg(theme.overlay0),
+        ));
+    }
+    for (index, command) in commands.into_iter().enumerate() {
+        lines.push(if index == palette.selected {
+            Line::styled(
+                format!("► {}", command.label()),
+                Style::default().fg(theme.blue).add_modifier(Modifier::BOLD),
+            )
+        } else {
+            Line::from(format!("  {}", command.label()))
+        });
+    }
+    lines.extend([
+        Line::from(""),
+        Line::from("Enter: run  ↑↓: select  Esc: close"),
+    ]);
+
+    render_modal(f, popup_area, "Commands", theme.mauve, lines, theme);
+}
+
+/// Matching profiles listed in the bulk forget preview before eliding.
+const FORGET_PREVIEW_LIMIT: usize = 8;
+
+/// How many saved profiles a pattern matches, with a preview of them.
+fn profile_match_lines(
+    profiles: &Option<Result<Vec<SavedProfile>, String>>,
+    pattern: Result<ProfilePattern, String>,
+    input: &str,
+    matches: &[&SavedProfile],
+    color: Color,
+    theme: &Theme,
+) -> Vec<Line<'static>> {
+    let mut lines = Vec::new();
+    match (profiles, pattern) {
+        (None, _) => lines.push(Line::from("Loading saved profiles...")),
+        (Some(Err(error)), _) => lines.push(Line::styled(
+            format!("Could not list saved profiles: {error}"),
+            Style::default().fg(theme.red),
+        )),
+        (Some(Ok(_)), Err(error)) if !input.trim().is_empty() => {
+            lines.push(Line::styled(error, Style::default().fg(theme.red)))
+        }
+        (Some(Ok(profiles)), _) => {
+            lines.push(Line::from(format!(
+                "{} of {} saved profiles match:",
+                matches.len(),
+                profiles.len()
+            )));
+            lines.extend(matches.iter().take(FORGET_PREVIEW_LIMIT).map(
+                |profile| {
+                    Line::styled(
+                        if profile.id == profile.ssid {
+                            format!("  • {}", profile.id)
+                        } else {
+                            format!("  • {} ({})", profile.id, profile.ssid)
+                        },
+                        Style::default().fg(color),
+                    )
+                },
+            ));
+            if matches.len() > FORGET_PREVIEW_LIMIT {
+                lines.push(Line::from(format!(
+                    "  … and {} more",
+                    matches.len() - FORGET_PREVIEW_LIMIT
+                )));
+            }
+        }
+    }
+    lines
+}
+
+pub fn render_bulk_forget_modal(f: &mut Frame, app: &App) {
+    let theme = app.colors();
+    let Some(forget) = &app.bulk_forget else {
+        return;
+    };
+
+    let popup_area = centered_rect(64, 60, f.area());
+    let mut lines =
+        vec![Line::from("Pattern (glob with * and ?, or /regex/):")];
+    lines.extend(input_box_lines(
+        forget.pattern.clone(),
+        Style::default().fg(theme.text),
+        forget.stage == ForgetStage::Editing,
+        theme,
+    ));
+
+    let matches = forget.matches();
+    lines.extend(profile_match_lines(
+        &forget.profiles,
+        forget.compiled_pattern(),
+        &forget.pattern,
+        &matches,
+        theme.peach,
+        theme,
+    ));
+
+    lines.push(Line::from(""));
+    lines.push(match forget.stage {
+        ForgetStage::Editing => {
+            Line::from("Enter: forget matches  Esc: cancel")
+        }
+        ForgetStage::Confirming => Line::styled(
+            format!("Forget {} saved profile(s)? y: yes  n: no", matches.len()),
+            Style::default().fg(theme.red).add_modifier(Modifier::BOLD),
+        ),
+        ForgetStage::Forgetting => Line::from("Forgetting..."),
+    });
+
+    render_modal(
+        f,
+        popup_area,
+        "Forget saved networks",
+        theme.red,
+        lines,
+        theme,
+    );
+}
+
+/// How long ago a profile was last connected, in whole days.
+fn last_used_text(last_used: Option<u64>) -> String {
+    let Some(last_used) = last_used else {
+        return "never used".to_string();
+    };
+    let now = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|elapsed| elapsed.as_secs())
+        .unwrap_or_default();
+    match now.saturating_sub(last_used) / (24 * 60 * 60) {
+        0 => "used today".to_string(),
+        1 => "used yesterday".to_string(),
+        days => format!("used {days} days ago"),
+    }
+}
+
+pub fn render_profile_cleanup_modal(f: &mut Frame, app: &App) {
+    let theme = app.colors();
+    let Some(cleanup) = &app.profile_cleanup else {
+        return;
+    };
+
+    let popup_area = centered_rect(64, 60, f.area());
+    let mut lines = vec![Line::from(format!(
+        "Out of range and unused for {} days:",
+        app.unused_profile_days
+    ))];
+    match &cleanup.profiles {
+        None => lines.push(Line::from("Loading saved profiles...")),
+        Some(Err(error)) => lines.push(Line::styled(
+            format!("Could not list saved profiles: {error}"),
+            Style::default().fg(theme.red),
+        )),
+        Some(Ok(profiles)) if profiles.is_empty() => {
+            lines.push(Line::from("Every saved profile is in use."))
+        }
+        Some(Ok(profiles)) => {
+            lines.extend(profiles.iter().enumerate().map(
+                |(index, profile)| {
+                    let picked = cleanup.picked.contains(&profile.uuid);
+                    Line::styled(
+                        format!(
+                            "{} [{}] {:<32}  {}",
+                            if index == cleanup.selected { ">" } else { " " },
+                            if picked { "x" } else { " " },
+                            profile.id,
+                            last_used_text(profile.last_used)
+                        ),
+                        if picked {
+                            Style::default().fg(theme.peach)
+                        } else {
+                            Style::default()
+                        },
+                    )
+                },
+            ));
+        }
+    }
+
+    let picked = cleanup.picked_profiles().len();
+    lines.push(Line::from(""));
+    lines.push(match cleanup.stage {
+        ForgetStage::Editing => {
+            Line::from("Space: pick  a: all  Enter: forget picked  Esc: cancel")
+        }
+        ForgetStage::Confirming => Line::styled(
+            format!("Forget {picked} saved profile(s)? y: yes  n: no"),
+            Style::default().fg(theme.red).add_modifier(Modifier::BOLD),
+        ),
+        ForgetStage::Forgetting => Line::from("Forgetting..."),
+    });
+
+    render_modal(
+        f,
+        popup_area,
+        "Clean up unused profiles",
+        theme.red,
+        lines,
+        theme,
+    );
+}
+
+pub fn render_profile_export_modal(f: &mut Frame, app: &App) {
+    let theme = app.colors();
+    let Some(export) = &app.profile_export else {
+        return;
+    };
+
+    let popup_area = centered_rect(64, 60, f.area());
+    let text_style = Style::default().fg(theme.text);
+    let mut lines =
+        vec![Line::from("Profiles (glob with * and ?, or /regex/):")];
+    lines.extend(input_box_lines(
+        export.pattern.clone(),
+        text_style,
+        export.field == ExportField::Pattern,
+        theme,
+    ));
+    lines.push(Line::from("Directory:"));
+    lines.extend(input_box_lines(
+        export.directory.clone(),
+        text_style,
+        export.field == ExportField::Directory,
+        theme,
+    ));
+    lines.push(if export.include_secrets {
+        Line::styled(
+            "[x] Include secrets (passwords are written in plain text)",
+            Style::default().fg(theme.yellow),
+        )
+    } else {
+        Line::from("[ ] Include secrets")
+    });
+    lines.push(Line::from(""));
+
+    let matches = export.matches();
+    lines.extend(profile_match_lines(
+        &export.profiles,
+        export.compiled_pattern(),
+        &export.pattern,
+        &matches,
+        theme.teal,
+        theme,
+    ));
+
+    lines.push(Line::from(""));
+    lines.push(match &export.result {
+        _ if export.exporting => Line::from("Exporting..."),
+        Some(Ok(message)) => {
+            Line::styled(message.clone(), Style::default().fg(theme.green))
+        }
+        Some(Err(error)) => {
+            Line::styled(error.clone(), Style::default().fg(theme.red))
+        }
+        None => {
+            Line::from("Enter: export  ↑↓: field  Tab: secrets  Esc: close")
+        }
+    });
+
+    render_modal(
+        f,
+        popup_area,
+        "Export saved profiles",
+        theme.teal,
+        lines,
+        theme,
+    );
+}
+
+pub fn render_known_networks_modal(f: &mut Frame, app: &App) {
+    let theme = app.colors();
+    let Some(known) = &app.known_networks else {
+        return;
+    };
+
+    let popup_area = centered_rect(64, 60, f.area());
+    let mut lines = Vec::new();
+    match &known.profiles {
+        None => lines.push(Line::from("Loading saved profiles...")),
+        Some(Err(error)) => lines.push(Line::styled(
+            format!("Could not list saved profiles: {error}"),
+            Style::default().fg(theme.red),
+        )),
+        Some(Ok(profiles)) if profiles.is_empty() => {
+            lines.push(Line::from("No saved Wi-Fi profiles."))
+        }
+        Some(Ok(profiles)) => {
+            lines.extend(profiles.iter().enumerate().map(
+                |(index, profile)| {
+                    let name = if profile.id == profile.ssid {
+                        profile.id.clone()
+                    } else {
+                        format!("{} ({})", profile.id, profile.ssid)
+                    };
+                    let scope = if profile.users.is_empty() {
+                        Span::styled("system", Style::default().fg(theme.text))
+                    } else {
+                        Span::styled(
+                            format!("only {}", profile.users.join(", ")),
+                            Style::default().fg(theme.sapphire),
+                        )
+                    };
+                    let mut spans = vec![
+                        Span::raw(format!(
+                            "{} {:<32}  ",
+                            if index == known.selected { ">" } else { " " },
+                            name
+                        )),
+                        Span::styled(
+                            format!(
+                                "{:<5}",
+                                profile.band.map_or("any", |band| band.label())
+                            ),
+                            Style::default().fg(theme.lavender),
+                        ),
+                        scope,
+                    ];
+                    if let Some(bssid) = &profile.bssid {
+                        spans.push(Span::styled(
+                            format!("  pinned {bssid}"),
+                            Style::default().fg(theme.peach),
+                        ));
+                    }
+                    if let Some(mtu) = profile.mtu {
+                        spans.push(Span::styled(
+                            format!("  mtu {mtu}"),
+                            Style::default().fg(theme.teal),
+                        ));
+                    }
+                    if let Some(mac) = &profile.cloned_mac {
+                        spans.push(Span::styled(
+                            format!("  mac {mac}"),
+                            Style::default().fg(theme.teal),
+                        ));
+                    }
+                    if let Some(vpn) = &profile.vpn {
+                        spans.push(Span::styled(
+                            format!("  vpn {vpn}"),
+                            Style::default().fg(theme.mauve),
+                        ));
+                    }
+                    Line::from(spans)
+                },
+            ));
+        }
+    }
+
+    if let Some((action, input)) = &known.edit_input {
+        lines.push(Line::from(""));
+        lines.push(Line::from(match action {
+            ProfileEdit::Rename => "New name:",
+            ProfileEdit::Clone => "Name of the copy:",
+            ProfileEdit::Password => "New password:",
+            ProfileEdit::Bssid => "Pin to BSSID (empty for any):",
+            ProfileEdit::Mtu => "MTU (empty for automatic):",
+            ProfileEdit::ClonedMac => {
+                "MAC address or random/stable (empty for the adapter's):"
+            }
+            ProfileEdit::Vpn => "VPN to bring up on connect (empty for none):",
+        }));
+        lines.extend(input_box_lines(
+            match action {
+                ProfileEdit::Password => app.password_mask.apply(input),
+                ProfileEdit::Rename
+                | ProfileEdit::Clone
+                | ProfileEdit::Bssid
+                | ProfileEdit::Mtu
+                | ProfileEdit::ClonedMac
+                | ProfileEdit::Vpn => input.clone(),
+            },
+            Style::default().fg(theme.text),
+            !known.saving_edit,
+            theme,
+        ));
+    }
+
+    lines.push(Line::from(""));
+    lines.push(match &known.result {
+        _ if known.converting => Line::from("Changing scope..."),
+        _ if known.changing_band => Line::from("Changing band..."),
+        _ if known.saving_edit => Line::from("Saving..."),
+        None if known.edit_input.is_some() => {
+            Line::from("Enter: save  Esc: cancel")
+        }
+        Some(Ok(message)) => Line::styled(
+            message.clone(),
+            Style::default().fg(theme.green),
+        ),
+        Some(Err(error)) => Line::styled(
+            error.clone(),
+            Style::default().fg(theme.red),
+        ),
+        None => Line::from(
+            "s: scope  b: band  a: AP  m: MTU  M: MAC  v: VPN  r: rename  c: clone  p: password  Esc: close",
+        ),
+    });
+
+    render_modal(
+        f,
+        popup_area,
+        "Known networks",
+        theme.sapphire,
+        lines,
+        theme,
+    );
+}
+
+pub fn render_qr_import_modal(f: &mut Frame, app: &App) {
+    let theme = app.colors();
+    let Some(import) = &app.qr_import else {
+        return;
+    };
+
+    let popup_area = centered_rect(64, 40, f.area());
+    // Long strings scroll so the end being typed stays in view.
+    let visible = import.input.chars().count().saturating_sub(38);
+    let mut lines = vec![Line::from("Paste or type a WIFI: string:")];
+    lines.extend(input_box_lines(
+        import.input.chars().skip(visible).collect(),
+        Style::default().fg(theme.text),
+        true,
+        theme,
+    ));
+    lines.push(Line::from(""));
+
+    lines.push(match (&import.error, parse_wifi_qr(&import.input)) {
+        (Some(error), _) => {
+            Line::styled(error.clone(), Style::default().fg(theme.red))
+        }
+        (None, Ok(network)) => Line::styled(
+            format!(
+                "{}{} · {}{}",
+                display_ssid(&network.ssid),
+                if network.hidden { " (hidden)" } else { "" },
+                network.security.display_name(),
+                if network.password.is_some() {
+                    " · password included"
+                } else {
+                    ""
+                }
+            ),
+            Style::default().fg(theme.green),
+        ),
+        (None, Err(_)) => Line::styled(
+            "e.g. WIFI:T:WPA;S:Home;P:secret;;",
+            Style::default().fg(theme.overlay1),
+        ),
+    });
+    lines.push(Line::from(""));
+    lines.push(Line::from("Enter: connect  Esc: close"));
+
+    render_modal(f, popup_area, "Import network", theme.teal, lines, theme);
 }
 
 pub fn render_enhanced_connecting_modal(f: &mut Frame, app: &App) {
+    let theme = app.colors();
     if let Some(network) = &app.selected_network {
-        let popup_area = centered_rect(64, 28, f.area());
-        let mut connecting_text = network_summary_lines(network, true);
-        connecting_text.extend([
-            Line::from(""),
-            Line::from("Activating connection via NetworkManager..."),
-            Line::from("Press Esc to quit the application."),
-        ]);
+        let popup_area = centered_rect(64, 44, f.area());
+        let mut connecting_text = network_summary_lines(network, true, theme);
+        if app.guest_connection {
+            connecting_text.push(Line::from(
+                "Guest mode: random MAC, metered, no autoconnect",
+            ));
+        }
+        if app.hidden_network {
+            connecting_text
+                .push(Line::from("Hidden network: probing for it by name"));
+        }
+        connecting_text.push(Line::from(""));
+        match (app.connect_retry, app.connect_attempt()) {
+            (Some(retry), Some((attempt, total)))
+                if !app.connect_attempt_due() =>
+            {
+                let wait = retry.due.saturating_duration_since(Instant::now());
+                connecting_text.extend([
+                    Line::from(Span::styled(
+                        format!("{}.", retry.failure.label()),
+                        Style::default().fg(theme.peach),
+                    )),
+                    Line::from(format!(
+                        "Retrying in {}s (attempt {attempt} of {total})...",
+                        wait.as_secs() + 1
+                    )),
+                ]);
+            }
+            (_, attempt) => {
+                connecting_text.push(Line::from(match attempt {
+                    Some((attempt, total)) => format!(
+                        "Activating via NetworkManager (attempt {attempt} of \
+                         {total})..."
+                    ),
+                    None => "Activating connection via NetworkManager..."
+                        .to_string(),
+                }));
+                connecting_text.extend(activation_step_lines(
+                    &app.activation_progress,
+                    theme,
+                ));
+            }
+        }
+        connecting_text.push(Line::from("Press Esc to cancel."));
 
         render_modal(
             f,
             popup_area,
             "Connecting",
-            CatppuccinColors::YELLOW,
+            theme.yellow,
             connecting_text,
+            theme,
         );
     }
 }
 
+/// A checklist of the activation steps the device went through, the one it
+/// is in and the ones still ahead; empty until the first state change.
+/// Asking for credentials only shows up when it happened.
+fn activation_step_lines(
+    progress: &ActivationProgress,
+    theme: &Theme,
+) -> Vec<Line<'static>> {
+    let Some(current) = progress.current() else {
+        return Vec::new();
+    };
+    ActivationStep::ALL
+        .into_iter()
+        .filter_map(|step| {
+            let (mark, style) = if step == current {
+                (
+                    "›",
+                    Style::default()
+                        .fg(theme.yellow)
+                        .add_modifier(Modifier::BOLD),
+                )
+            } else if progress.reached(step) {
+                ("✓", Style::default().fg(theme.green))
+            } else if step > current && step != ActivationStep::NeedAuth {
+                ("·", Style::default().fg(theme.overlay1))
+            } else {
+                return None;
+            };
+            Some(Line::styled(format!("  {mark} {}", step.label()), style))
+        })
+        .collect()
+}
+
 pub fn render_enhanced_disconnecting_modal(f: &mut Frame, app: &App) {
+    let theme = app.colors();
     if let Some(network) = &app.selected_network {
         let popup_area = centered_rect(64, 24, f.area());
-        let mut disconnecting_text = network_summary_lines(network, false);
-        disconnecting_text.extend([
-            Line::from("Disconnecting via NetworkManager..."),
-            Line::from("Press Esc to quit the application."),
-        ]);
+        let mut disconnecting_text =
+            network_summary_lines(network, false, theme);
+        disconnecting_text
+            .push(Line::from("Disconnecting via NetworkManager..."));
 
         render_modal(
             f,
             popup_area,
             "Disconnecting",
-            CatppuccinColors::PEACH,
+            theme.peach,
             disconnecting_text,
+            theme,
         );
     }
 }
 
 pub fn render_enhanced_result_modal(f: &mut Frame, app: &App) {
+    let theme = app.colors();
     let popup_area = centered_rect(68, 38, f.area());
 
-    let (title, color) = if app.connection_success {
+    let (title, color) = if app.operation == OperationKind::RenewLease {
+        if app.connection_success {
+            ("Lease renewed", theme.green)
+        } else {
+            ("Lease renewal failed", theme.red)
+        }
+    } else if app.connection_success {
         if app.is_disconnect_operation {
-            ("Disconnection complete", CatppuccinColors::GREEN)
+            ("Disconnection complete", theme.green)
         } else {
-            ("Connection complete", CatppuccinColors::GREEN)
+            ("Connection complete", theme.green)
         }
     } else if app.is_disconnect_operation {
-        ("Disconnection failed", CatppuccinColors::RED)
+        ("Disconnection failed", theme.red)
     } else {
-        ("Connection failed", CatppuccinColors::RED)
+        ("Connection failed", theme.red)
     };
 
     let mut result_text = vec![];
 
     if let Some(network) = &app.selected_network {
-        result_text.extend(network_summary_lines(network, true));
+        result_text.extend(network_summary_lines(network, true, theme));
     } else {
         result_text.push(Line::from("Network: Unknown"));
     }
@@ -432,6 +2245,25 @@ pub fn render_enhanced_result_modal(f: &mut Frame, app: &App) {
     if app.connection_success {
         result_text
             .push(Line::from("Status: NetworkManager reported success."));
+        if let Some(check) = &app.connectivity {
+            result_text.push(connectivity_line(check, theme));
+        }
+        if let Some(url) = &app.captive_portal {
+            result_text.push(Line::from(format!("Login page: {url}")));
+        }
+        if let Some(lease) = &app.renewed_lease {
+            result_text.push(Line::from(format!("Address: {}", lease.address)));
+            result_text.push(Line::from(format!(
+                "Gateway: {}",
+                lease.gateway.as_deref().unwrap_or("none")
+            )));
+            if let Some(seconds) = lease.lease_seconds {
+                result_text.push(Line::from(format!(
+                    "Lease time: {}",
+                    format_duration(seconds)
+                )));
+            }
+        }
     } else {
         let error_msg =
             app.connection_error.as_deref().unwrap_or("Unknown error");
@@ -442,18 +2274,85 @@ pub fn render_enhanced_result_modal(f: &mut Frame, app: &App) {
             ),
             Span::styled(
                 error_msg.to_string(),
-                Style::default().fg(CatppuccinColors::TEXT),
+                Style::default().fg(theme.text),
             ),
         ]));
     }
 
+    result_text.push(Line::from(""));
+    if app.captive_portal.is_some() {
+        result_text.push(Line::from("o: open the login page in a browser"));
+    }
     result_text.extend([
-        Line::from(""),
         Line::from("Enter: return to the network list"),
         Line::from("q/Esc: quit"),
     ]);
 
-    render_modal(f, popup_area, title, color, result_text);
+    render_modal(f, popup_area, title, color, result_text, theme);
+}
+
+pub fn render_manager_unavailable_modal(f: &mut Frame, app: &App) {
+    let theme = app.colors();
+    let popup_area = centered_rect(68, 50, f.area());
+    let mut lines = vec![
+        Line::from("nm-wifi manages Wi-Fi through NetworkManager,"),
+        Line::from("which could not be reached:"),
+        Line::from(""),
+        Line::from(Span::styled(
+            app.manager_error
+                .as_deref()
+                .unwrap_or("Unknown error")
+                .to_string(),
+            Style::default().fg(theme.red),
+        )),
+        Line::from("Checking again every few seconds."),
+        Line::from(""),
+    ];
+    if app.starting_manager {
+        lines.push(Line::from(
+            "Starting NetworkManager, answer the authentication prompt...",
+        ));
+    } else {
+        lines.extend([
+            Line::from("s: start it (pkexec systemctl start NetworkManager)"),
+            Line::from("r: try again"),
+            Line::from("q/Esc: quit"),
+        ]);
+    }
+
+    render_modal(
+        f,
+        popup_area,
+        "NetworkManager unavailable",
+        theme.red,
+        lines,
+        theme,
+    );
+}
+
+/// Shown over any screen when polkit refused an action.
+pub fn render_authorization_denied_modal(f: &mut Frame, app: &App) {
+    let theme = app.colors();
+    let Some(error) = &app.authorization_denied else {
+        return;
+    };
+    let popup_area = centered_rect(72, 70, f.area());
+    let lines = vec![
+        Line::from("polkit did not allow this action:"),
+        Line::from(Span::styled(error.clone(), Style::default().fg(theme.red))),
+        Line::from(""),
+        Line::from("NetworkManager asks polkit before changing the system's"),
+        Line::from("networks. To be allowed:"),
+        Line::from("• run nm-wifi in a desktop session, whose polkit agent"),
+        Line::from("  can ask for your password"),
+        Line::from("• run it as an administrator, e.g. `pkexec nm-wifi`"),
+        Line::from("• add a polkit rule granting your user the"),
+        Line::from("  org.freedesktop.NetworkManager.* actions"),
+        Line::from(""),
+        Line::from("Enter/Esc: close"),
+    ];
+
+    render_modal(f, popup_area, "Not authorized", theme.red, lines, theme);
 }
 
 pub fn centered_rect(percent_x: u16, percent_y: u16, r: Rect) -> Rect {
@@ -0,0 +1,40 @@
+use crossterm::event::KeyCode;
+use ratatui::layout::{Position, Rect};
+
+use super::{
+    header_footer::hint_key_at,
+    list::network_row_at,
+    screen::screen_chunks,
+};
+use crate::app_state::{App, AppState};
+
+/// What a click lands on.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum MouseTarget {
+    /// A row of the network list, by its position among the visible
+    /// networks.
+    NetworkRow(usize),
+    /// A status-bar hint, standing for its key.
+    Key(KeyCode),
+}
+
+/// The target under (`column`, `row`) of a screen of `area` drawn by `ui`.
+/// Rows can only be picked on the plain network list, not under a dialog.
+pub fn mouse_target(
+    app: &App,
+    area: Rect,
+    column: u16,
+    row: u16,
+) -> Option<MouseTarget> {
+    let position = Position::new(column, row);
+    let chunks = screen_chunks(area);
+    if let Some(key) = hint_key_at(app, chunks[2], position) {
+        return Some(MouseTarget::Key(key));
+    }
+
+    if app.state != AppState::NetworkList || app.authorization_denied.is_some()
+    {
+        return None;
+    }
+    network_row_at(app, chunks[1], position).map(MouseTarget::NetworkRow)
+}
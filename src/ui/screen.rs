@@ -1,7 +1,10 @@
+use std::{rc::Rc, time::Instant};
+
 use ratatui::{
     Frame,
-    layout::{Alignment, Constraint, Direction, Layout},
-    style::{Modifier, Style},
+    buffer::Buffer,
+    layout::{Alignment, Constraint, Direction, Layout, Rect},
+    style::{Color, Modifier, Style},
     text::{Line, Span},
     widgets::{Block, Borders, Clear, Paragraph},
 };
@@ -11,21 +14,40 @@ use super::{
     list::render_network_list_background,
     modals::{
         centered_rect,
+        render_authorization_denied_modal,
+        render_bulk_forget_modal,
+        render_command_palette,
+        render_connection_history,
+        render_device_tab,
         render_enhanced_connecting_modal,
         render_enhanced_disconnecting_modal,
         render_enhanced_password_modal,
         render_enhanced_result_modal,
         render_help_screen,
+        render_known_networks_modal,
+        render_manager_unavailable_modal,
         render_network_details,
+        render_nm_log,
+        render_p2p_tab,
+        render_profile_cleanup_modal,
+        render_profile_export_modal,
+        render_qr_import_modal,
+        render_qr_share_modal,
+        render_roaming_view,
+        render_site_survey,
+        render_spectrum_view,
+        render_speed_test,
     },
+    toasts::render_toasts,
 };
 use crate::{
     app_state::{App, AppState},
-    theme::CatppuccinColors,
+    theme::Theme,
 };
 
-pub fn ui(f: &mut Frame, app: &App) {
-    let chunks = Layout::default()
+/// The header, the main area and the status bar, top to bottom.
+pub(crate) fn screen_chunks(area: Rect) -> Rc<[Rect]> {
+    Layout::default()
         .direction(Direction::Vertical)
         .margin(0)
         .constraints(
@@ -36,7 +58,12 @@ pub fn ui(f: &mut Frame, app: &App) {
             ]
             .as_ref(),
         )
-        .split(f.area());
+        .split(area)
+}
+
+pub fn ui(f: &mut Frame, app: &App) {
+    let theme = app.colors();
+    let chunks = screen_chunks(f.area());
 
     render_header(f, app, chunks[0]);
 
@@ -50,24 +77,17 @@ pub fn ui(f: &mut Frame, app: &App) {
                     "Scanning for WiFi networks...\n\nPlease wait...",
                 )
                 .block(Block::default().borders(Borders::ALL).title("Scanning"))
-                .style(
-                    Style::default()
-                        .fg(CatppuccinColors::BLUE)
-                        .bg(CatppuccinColors::BASE),
-                )
+                .style(Style::default().fg(theme.blue).bg(theme.base))
                 .alignment(Alignment::Center);
 
                 f.render_widget(scanning_modal, popup_area);
             } else {
                 let scanning_title = Line::from(vec![
-                    Span::styled(
-                        "🔍 ",
-                        Style::default().fg(CatppuccinColors::YELLOW),
-                    ),
+                    Span::styled("🔍 ", Style::default().fg(theme.yellow)),
                     Span::styled(
                         "Scanning...",
                         Style::default()
-                            .fg(CatppuccinColors::YELLOW)
+                            .fg(theme.yellow)
                             .add_modifier(Modifier::BOLD),
                     ),
                 ]);
@@ -83,30 +103,32 @@ pub fn ui(f: &mut Frame, app: &App) {
         AppState::NetworkList => {
             let list_title = Line::from(vec![
                 Span::styled(
-                    "📶 ",
-                    Style::default().fg(CatppuccinColors::BLUE),
+                    format!("{} ", app.glyphs.signal),
+                    Style::default().fg(theme.blue),
                 ),
                 Span::styled(
                     "WiFi Networks",
                     Style::default()
-                        .fg(CatppuccinColors::TEXT)
+                        .fg(theme.text)
                         .add_modifier(Modifier::BOLD),
                 ),
+                Span::styled(" | ", Style::default().fg(theme.subtext1)),
                 Span::styled(
-                    " | ",
-                    Style::default().fg(CatppuccinColors::SUBTEXT1),
+                    format!("{}:Connected ", app.glyphs.connected),
+                    Style::default().fg(theme.green),
                 ),
                 Span::styled(
-                    "🔗:Connected ",
-                    Style::default().fg(CatppuccinColors::GREEN),
+                    format!("{}:Saved ", app.glyphs.saved),
+                    Style::default().fg(theme.yellow),
                 ),
                 Span::styled(
-                    "🔒:Secured ",
-                    Style::default().fg(CatppuccinColors::MAUVE),
+                    format!("{}:Secured", app.glyphs.secured),
+                    Style::default().fg(theme.mauve),
                 ),
+                Span::styled(" | ", Style::default().fg(theme.subtext1)),
                 Span::styled(
-                    "2.4G/5G:Band",
-                    Style::default().fg(CatppuccinColors::SAPPHIRE),
+                    format!("Sort: {}", app.sort_order.label()),
+                    Style::default().fg(theme.peach),
                 ),
             ]);
 
@@ -119,6 +141,60 @@ pub fn ui(f: &mut Frame, app: &App) {
             render_network_list_background(f, app, chunks[1], None);
             render_network_details(f, app);
         }
+        AppState::Roaming => {
+            render_network_list_background(f, app, chunks[1], None);
+            render_roaming_view(f, app);
+        }
+        AppState::Devices => {
+            render_device_tab(f, app, chunks[1]);
+        }
+        AppState::P2pPeers => {
+            render_p2p_tab(f, app, chunks[1]);
+        }
+        AppState::Spectrum => {
+            render_spectrum_view(f, app, chunks[1]);
+        }
+        AppState::ConnectionHistory => {
+            render_connection_history(f, app, chunks[1]);
+        }
+        AppState::NetworkManagerLog => {
+            render_nm_log(f, app, chunks[1]);
+        }
+        AppState::SiteSurvey => {
+            render_site_survey(f, app, chunks[1]);
+        }
+        AppState::SpeedTest => {
+            render_network_list_background(f, app, chunks[1], None);
+            render_speed_test(f, app);
+        }
+        AppState::CommandPalette => {
+            render_network_list_background(f, app, chunks[1], None);
+            render_command_palette(f, app);
+        }
+        AppState::ForgetProfiles => {
+            render_network_list_background(f, app, chunks[1], None);
+            render_bulk_forget_modal(f, app);
+        }
+        AppState::ExportProfiles => {
+            render_network_list_background(f, app, chunks[1], None);
+            render_profile_export_modal(f, app);
+        }
+        AppState::KnownNetworks => {
+            render_network_list_background(f, app, chunks[1], None);
+            render_known_networks_modal(f, app);
+        }
+        AppState::CleanupProfiles => {
+            render_network_list_background(f, app, chunks[1], None);
+            render_profile_cleanup_modal(f, app);
+        }
+        AppState::ImportQr => {
+            render_network_list_background(f, app, chunks[1], None);
+            render_qr_import_modal(f, app);
+        }
+        AppState::ShareQr => {
+            render_network_list_background(f, app, chunks[1], None);
+            render_qr_share_modal(f, app);
+        }
         AppState::PasswordInput => {
             render_network_list_background(f, app, chunks[1], None);
             render_enhanced_password_modal(f, app);
@@ -135,7 +211,53 @@ pub fn ui(f: &mut Frame, app: &App) {
             render_network_list_background(f, app, chunks[1], None);
             render_enhanced_result_modal(f, app);
         }
+        AppState::ManagerUnavailable => {
+            render_network_list_background(f, app, chunks[1], None);
+            render_manager_unavailable_modal(f, app);
+        }
     }
+    render_authorization_denied_modal(f, app);
 
+    render_toasts(f, app, chunks[1]);
     render_status_bar(f, app, chunks[2]);
+
+    if app.monochrome {
+        strip_colors(f.buffer_mut(), theme);
+    }
+    if app.notifier.flash().is_active(Instant::now()) {
+        let area = f.area();
+        f.buffer_mut()
+            .set_style(area, Style::default().add_modifier(Modifier::REVERSED));
+    }
+}
+
+/// Puts every cell in the terminal's default colours. Cells set off by a
+/// background, like the selected row, are reversed instead, and text in an
+/// accent colour is bolded, so what the colours stressed still stands out.
+fn strip_colors(buffer: &mut Buffer, theme: &Theme) {
+    let backgrounds = [Color::Reset, theme.base, theme.mantle];
+    let plain_text = [
+        Color::Reset,
+        theme.base,
+        theme.mantle,
+        theme.surface0,
+        theme.surface1,
+        theme.surface2,
+        theme.overlay0,
+        theme.overlay1,
+        theme.overlay2,
+        theme.subtext0,
+        theme.subtext1,
+        theme.text,
+    ];
+    for cell in &mut buffer.content {
+        if !backgrounds.contains(&cell.bg) {
+            cell.modifier |= Modifier::REVERSED;
+        }
+        if !plain_text.contains(&cell.fg) {
+            cell.modifier |= Modifier::BOLD;
+        }
+        cell.fg = Color::Reset;
+        cell.bg = Color::Reset;
+    }
 }
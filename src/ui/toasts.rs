@@ -0,0 +1,61 @@
+use std::time::Instant;
+
+use ratatui::{
+    Frame,
+    layout::Rect,
+    style::{Modifier, Style},
+    text::Line,
+    widgets::{Block, Borders, Clear, Paragraph},
+};
+
+use crate::{app_state::App, notify::ToastLevel};
+
+const TOAST_WIDTH: u16 = 44;
+const TOAST_HEIGHT: u16 = 4;
+
+/// Stacks unexpired toasts in the top-right corner of `area`, newest first,
+/// bordered in the colour of their level.
+pub fn render_toasts(f: &mut Frame, app: &App, area: Rect) {
+    let theme = app.colors();
+    let width = TOAST_WIDTH.min(area.width);
+    for (index, toast) in app
+        .notifier
+        .toasts()
+        .active(Instant::now())
+        .into_iter()
+        .enumerate()
+    {
+        let y = area.y + 1 + index as u16 * TOAST_HEIGHT;
+        if y + TOAST_HEIGHT > area.bottom() {
+            break;
+        }
+
+        let toast_area = Rect {
+            x: area.right().saturating_sub(width + 1),
+            y,
+            width,
+            height: TOAST_HEIGHT,
+        };
+        let color = match toast.level {
+            ToastLevel::Info => theme.green,
+            ToastLevel::Warning => theme.yellow,
+            ToastLevel::Error => theme.red,
+        };
+        let paragraph = Paragraph::new(vec![
+            Line::styled(
+                toast.summary,
+                Style::default().fg(color).add_modifier(Modifier::BOLD),
+            ),
+            Line::styled(toast.body, Style::default().fg(theme.text)),
+        ])
+        .block(
+            Block::default()
+                .borders(Borders::ALL)
+                .border_style(Style::default().fg(color)),
+        )
+        .style(Style::default().bg(theme.surface0));
+
+        f.render_widget(Clear, toast_area);
+        f.render_widget(paragraph, toast_area);
+    }
+}
@@ -21,19 +21,450 @@ impl WifiSecurity {
             Self::Unsupported => "Unsupported secured network",
         }
     }
+
+    /// A label short enough for the security column of the network list.
+    pub fn short_name(self) -> &'static str {
+        match self {
+            Self::Open => "Open",
+            Self::WpaPsk => "WPA2",
+            Self::WpaSae => "WPA3",
+            Self::Enterprise => "802.1X",
+            Self::Unsupported => "Other",
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum WifiBand {
+    TwoPointFourGhz,
+    FiveGhz,
+    SixGhz,
 }
 
-#[derive(Debug, Clone)]
+impl WifiBand {
+    pub fn from_frequency(frequency: u32) -> Self {
+        match frequency {
+            5925.. => Self::SixGhz,
+            5000.. => Self::FiveGhz,
+            _ => Self::TwoPointFourGhz,
+        }
+    }
+
+    /// `2.4`, `5` or `6`, as the `scan_band` setting names bands.
+    pub fn from_key(key: &str) -> Option<Self> {
+        match key {
+            "2.4" => Some(Self::TwoPointFourGhz),
+            "5" => Some(Self::FiveGhz),
+            "6" => Some(Self::SixGhz),
+            _ => None,
+        }
+    }
+
+    pub fn label(self) -> &'static str {
+        match self {
+            Self::TwoPointFourGhz => "2.4G",
+            Self::FiveGhz => "5G",
+            Self::SixGhz => "6G",
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum WifiGeneration {
+    Wifi4,
+    Wifi5,
+    Wifi6,
+    Wifi7,
+}
+
+impl WifiGeneration {
+    /// Best-effort classification from the band and the AP's advertised
+    /// `MaxBitrate` (kb/s). NetworkManager does not expose HT/VHT/HE/EHT
+    /// capabilities directly, so this relies on the rate ranges each
+    /// standard can reach plus the canonical 802.11ax 80/160 MHz rates.
+    pub fn classify(frequency: u32, max_bitrate: u32) -> Option<Self> {
+        let band = WifiBand::from_frequency(frequency);
+
+        if max_bitrate > 4_804_000 {
+            Some(Self::Wifi7)
+        } else if band == WifiBand::SixGhz
+            || matches!(max_bitrate, 1_201_000 | 2_402_000 | 4_804_000)
+        {
+            Some(Self::Wifi6)
+        } else if max_bitrate <= 54_000 {
+            None
+        } else if max_bitrate <= 600_000 {
+            Some(Self::Wifi4)
+        } else if band == WifiBand::TwoPointFourGhz || max_bitrate > 3_466_000 {
+            Some(Self::Wifi6)
+        } else {
+            Some(Self::Wifi5)
+        }
+    }
+
+    /// The list badge, marked with `~` as the estimate it is: an 802.11ac
+    /// AP advertising no more than 802.11n rates reads as Wi-Fi 4.
+    pub fn badge(self) -> &'static str {
+        match self {
+            Self::Wifi4 => "~W4",
+            Self::Wifi5 => "~W5",
+            Self::Wifi6 => "~W6",
+            Self::Wifi7 => "~W7",
+        }
+    }
+
+    pub fn display_name(self) -> &'static str {
+        match self {
+            Self::Wifi4 => "Wi-Fi 4 (802.11n)",
+            Self::Wifi5 => "Wi-Fi 5 (802.11ac)",
+            Self::Wifi6 => "Wi-Fi 6 (802.11ax)",
+            Self::Wifi7 => "Wi-Fi 7 (802.11be)",
+        }
+    }
+}
+
+const PAIRWISE_CIPHERS: [(u32, &str); 4] = [
+    (0x1, "WEP-40"),
+    (0x2, "WEP-104"),
+    (0x4, "TKIP"),
+    (0x8, "CCMP"),
+];
+const GROUP_CIPHERS: [(u32, &str); 4] = [
+    (0x10, "WEP-40"),
+    (0x20, "WEP-104"),
+    (0x40, "TKIP"),
+    (0x80, "CCMP"),
+];
+const KEY_MANAGEMENT: [(u32, &str); 6] = [
+    (0x100, "PSK"),
+    (0x200, "802.1X"),
+    (0x400, "SAE"),
+    (0x800, "OWE"),
+    (0x1000, "OWE-TM"),
+    (0x2000, "EAP Suite B-192"),
+];
+const TKIP_CIPHERS: u32 = 0x4 | 0x40;
+/// Key management that works without management frame protection.
+const UNPROTECTED_KEY_MANAGEMENT: u32 = 0x100 | 0x200;
+/// Key management that mandates it: SAE, OWE and Suite B-192.
+const PROTECTED_KEY_MANAGEMENT: u32 = 0x400 | 0x800 | 0x2000;
+
+/// Whether an AP protects management frames (802.11w). NetworkManager does
+/// not report the MFPC/MFPR bits themselves, so this is inferred from the
+/// key management in the RSN element.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PmfCapability {
+    /// WPA3-only, OWE or Suite B networks.
+    Required,
+    /// WPA2/WPA3 transition networks, which must offer PMF to WPA3 clients.
+    Optional,
+    /// WPA2 and 802.1X networks may offer it without saying so.
+    Unknown,
+    /// Without an RSN element there is no PMF.
+    Unsupported,
+}
+
+impl PmfCapability {
+    pub fn description(self) -> &'static str {
+        match self {
+            Self::Required => "required",
+            Self::Optional => "optional (WPA2/WPA3 transition)",
+            Self::Unknown => "not advertised (WPA2 may still offer it)",
+            Self::Unsupported => "not supported",
+        }
+    }
+}
+
+/// Cipher and key-management suites advertised in one of an AP's WPA or RSN
+/// information elements, as NetworkManager's `NM80211ApSecurityFlags`.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct SecuritySuites(pub u32);
+
+impl SecuritySuites {
+    fn names(self, table: &[(u32, &'static str)]) -> Vec<&'static str> {
+        table
+            .iter()
+            .filter(|(flag, _)| self.0 & flag != 0)
+            .map(|(_, name)| *name)
+            .collect()
+    }
+
+    pub fn is_empty(self) -> bool {
+        self.0 == 0
+    }
+
+    pub fn pairwise_ciphers(self) -> Vec<&'static str> {
+        self.names(&PAIRWISE_CIPHERS)
+    }
+
+    pub fn group_ciphers(self) -> Vec<&'static str> {
+        self.names(&GROUP_CIPHERS)
+    }
+
+    pub fn key_management(self) -> Vec<&'static str> {
+        self.names(&KEY_MANAGEMENT)
+    }
+
+    pub fn uses_tkip(self) -> bool {
+        self.0 & TKIP_CIPHERS != 0
+    }
+
+    /// PMF capability of the network advertising these RSN suites.
+    pub fn pmf_capability(self) -> PmfCapability {
+        let unprotected = self.0 & UNPROTECTED_KEY_MANAGEMENT != 0;
+        let protected = self.0 & PROTECTED_KEY_MANAGEMENT != 0;
+        match (protected, unprotected) {
+            (true, false) => PmfCapability::Required,
+            (true, true) => PmfCapability::Optional,
+            (false, true) => PmfCapability::Unknown,
+            (false, false) => PmfCapability::Unsupported,
+        }
+    }
+
+    /// Renders the suites as `pairwise CCMP, group TKIP, PSK`.
+    pub fn summary(self) -> String {
+        let mut parts = Vec::new();
+        let pairwise = self.pairwise_ciphers();
+        if !pairwise.is_empty() {
+            parts.push(format!("pairwise {}", pairwise.join("/")));
+        }
+        let group = self.group_ciphers();
+        if !group.is_empty() {
+            parts.push(format!("group {}", group.join("/")));
+        }
+        let key_management = self.key_management();
+        if !key_management.is_empty() {
+            parts.push(key_management.join("/"));
+        }
+        parts.join(", ")
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct WifiAccessPoint {
+    pub bssid: String,
+    pub frequency: u32,
+    pub signal_strength: u8,
+    /// Whether the adapter is currently associated with this BSSID.
+    pub associated: bool,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
 pub struct WifiNetwork {
+    /// The SSID as shown and matched against profiles; see `ssid_name`.
     pub ssid: String,
+    /// The SSID's bytes when they are not UTF-8, so connecting uses the
+    /// exact name the access points broadcast.
+    pub raw_ssid: Option<Vec<u8>>,
     pub signal_strength: u8,
     pub security: WifiSecurity,
     pub frequency: u32,
     pub connected: bool,
+    /// Whether NetworkManager already has a profile for this SSID.
+    pub saved: bool,
+    pub max_bitrate: u32,
+    pub wpa_flags: u32,
+    pub rsn_flags: u32,
+    pub access_points: Vec<WifiAccessPoint>,
+}
+
+impl WifiAccessPoint {
+    pub fn band(&self) -> WifiBand {
+        WifiBand::from_frequency(self.frequency)
+    }
+
+    pub fn channel(&self) -> Option<u32> {
+        channel_number(self.frequency)
+    }
+}
+
+/// IEEE 802.11 channel number for the centre frequency in MHz.
+pub fn channel_number(frequency: u32) -> Option<u32> {
+    match frequency {
+        2484 => Some(14),
+        2412..=2472 => Some((frequency - 2407) / 5),
+        5160..=5885 => Some((frequency - 5000) / 5),
+        5955..=7115 => Some((frequency - 5950) / 5),
+        _ => None,
+    }
+}
+
+/// Characters that draw nothing or reorder the text around them: controls,
+/// zero-width spaces and joiners, the byte order mark, soft hyphens and
+/// bidirectional overrides. An SSID using them can pass for another one.
+pub fn is_invisible_char(c: char) -> bool {
+    c.is_control()
+        || matches!(
+            c,
+            '\u{00AD}'
+                | '\u{061C}'
+                | '\u{180E}'
+                | '\u{200B}'..='\u{200F}'
+                | '\u{202A}'..='\u{202E}'
+                | '\u{2060}'..='\u{2064}'
+                | '\u{2066}'..='\u{2069}'
+                | '\u{FEFF}'
+        )
+}
+
+/// SSIDs are arbitrary bytes. Valid UTF-8 reads as is; bytes that are not
+/// part of a UTF-8 sequence are written as `\xNN`.
+pub fn ssid_name(bytes: &[u8]) -> String {
+    let mut name = String::new();
+    for chunk in bytes.utf8_chunks() {
+        name.push_str(chunk.valid());
+        for byte in chunk.invalid() {
+            name.push_str(&format!("\\x{byte:02X}"));
+        }
+    }
+    name
 }
 
 impl WifiNetwork {
     pub fn is_secured(&self) -> bool {
         self.security.is_secured()
     }
+
+    /// The SSID exactly as broadcast.
+    pub fn ssid_bytes(&self) -> &[u8] {
+        self.raw_ssid.as_deref().unwrap_or(self.ssid.as_bytes())
+    }
+
+    /// Whether the SSID hides characters that could make it look like the
+    /// name of another network.
+    pub fn has_invisible_characters(&self) -> bool {
+        self.ssid.chars().any(is_invisible_char)
+    }
+
+    pub fn band(&self) -> WifiBand {
+        WifiBand::from_frequency(self.frequency)
+    }
+
+    pub fn channel(&self) -> Option<u32> {
+        channel_number(self.frequency)
+    }
+
+    pub fn generation(&self) -> Option<WifiGeneration> {
+        WifiGeneration::classify(self.frequency, self.max_bitrate)
+    }
+
+    pub fn wpa_suites(&self) -> SecuritySuites {
+        SecuritySuites(self.wpa_flags)
+    }
+
+    pub fn rsn_suites(&self) -> SecuritySuites {
+        SecuritySuites(self.rsn_flags)
+    }
+
+    pub fn uses_tkip(&self) -> bool {
+        self.wpa_suites().uses_tkip() || self.rsn_suites().uses_tkip()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{
+        PmfCapability,
+        SecuritySuites,
+        WifiAccessPoint,
+        WifiGeneration,
+        is_invisible_char,
+        ssid_name,
+    };
+
+    #[test]
+    fn zero_width_and_bidi_characters_are_invisible() {
+        assert!("Home\u{200B}".chars().any(is_invisible_char));
+        assert!("\u{202E}emoH".chars().any(is_invisible_char));
+        assert!("Cafe\u{FEFF}".chars().any(is_invisible_char));
+        assert!(!"Café 網 😊".chars().any(is_invisible_char));
+    }
+
+    #[test]
+    fn ssids_that_are_not_utf8_show_their_bytes() {
+        assert_eq!(ssid_name("Café".as_bytes()), "Café");
+        assert_eq!(ssid_name(b"Caf\xe9 \xff\x01"), "Caf\\xE9 \\xFF\u{1}");
+        assert_eq!(ssid_name(b""), "");
+    }
+
+    #[test]
+    fn security_suites_decode_ciphers_and_key_management() {
+        let mixed = SecuritySuites(0x8 | 0x40 | 0x100);
+        assert_eq!(mixed.pairwise_ciphers(), vec!["CCMP"]);
+        assert_eq!(mixed.group_ciphers(), vec!["TKIP"]);
+        assert_eq!(mixed.key_management(), vec!["PSK"]);
+        assert!(mixed.uses_tkip());
+        assert_eq!(mixed.summary(), "pairwise CCMP, group TKIP, PSK");
+
+        let transition = SecuritySuites(0x8 | 0x80 | 0x100 | 0x400);
+        assert_eq!(transition.summary(), "pairwise CCMP, group CCMP, PSK/SAE");
+        assert!(!transition.uses_tkip());
+
+        assert!(SecuritySuites::default().is_empty());
+        assert_eq!(SecuritySuites::default().summary(), "");
+    }
+
+    #[test]
+    fn pmf_capability_follows_rsn_key_management() {
+        let rsn = |flags| SecuritySuites(flags).pmf_capability();
+
+        assert_eq!(rsn(0x8 | 0x80 | 0x400), PmfCapability::Required);
+        assert_eq!(rsn(0x8 | 0x80 | 0x100 | 0x400), PmfCapability::Optional);
+        assert_eq!(rsn(0x8 | 0x80 | 0x100), PmfCapability::Unknown);
+        assert_eq!(rsn(0), PmfCapability::Unsupported);
+    }
+
+    #[test]
+    fn access_point_channels_follow_the_band_plan() {
+        let channel = |frequency| {
+            WifiAccessPoint {
+                bssid: String::new(),
+                frequency,
+                signal_strength: 0,
+                associated: false,
+            }
+            .channel()
+        };
+
+        assert_eq!(channel(2412), Some(1));
+        assert_eq!(channel(2484), Some(14));
+        assert_eq!(channel(5180), Some(36));
+        assert_eq!(channel(5745), Some(149));
+        assert_eq!(channel(5955), Some(1));
+        assert_eq!(channel(900), None);
+    }
+
+    #[test]
+    fn legacy_rates_have_no_generation_badge() {
+        assert_eq!(WifiGeneration::classify(2412, 54_000), None);
+    }
+
+    #[test]
+    fn generations_are_inferred_from_band_and_max_bitrate() {
+        assert_eq!(
+            WifiGeneration::classify(2437, 144_400),
+            Some(WifiGeneration::Wifi4)
+        );
+        assert_eq!(
+            WifiGeneration::classify(5180, 866_700),
+            Some(WifiGeneration::Wifi5)
+        );
+        assert_eq!(
+            WifiGeneration::classify(5180, 2_402_000),
+            Some(WifiGeneration::Wifi6)
+        );
+        assert_eq!(
+            WifiGeneration::classify(2437, 1_147_000),
+            Some(WifiGeneration::Wifi6)
+        );
+        assert_eq!(
+            WifiGeneration::classify(5955, 0),
+            Some(WifiGeneration::Wifi6)
+        );
+        assert_eq!(
+            WifiGeneration::classify(5180, 5_764_000),
+            Some(WifiGeneration::Wifi7)
+        );
+        assert_eq!(WifiGeneration::Wifi5.badge(), "~W5");
+    }
 }
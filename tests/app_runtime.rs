@@ -7,10 +7,16 @@ use nm_wifi::{
 fn network(ssid: &str, connected: bool) -> WifiNetwork {
     WifiNetwork {
         ssid: ssid.to_string(),
+        raw_ssid: None,
         signal_strength: 80,
         security: WifiSecurity::WpaPsk,
         frequency: 5180,
         connected,
+        saved: false,
+        max_bitrate: 0,
+        wpa_flags: 0,
+        rsn_flags: 0,
+        access_points: Vec::new(),
     }
 }
 
@@ -8,10 +8,16 @@ use ratatui::{Terminal, backend::TestBackend};
 fn network(ssid: &str, security: WifiSecurity, connected: bool) -> WifiNetwork {
     WifiNetwork {
         ssid: ssid.to_string(),
+        raw_ssid: None,
         signal_strength: 78,
         security,
         frequency: 5180,
         connected,
+        saved: false,
+        max_bitrate: 0,
+        wpa_flags: 0,
+        rsn_flags: 0,
+        access_points: Vec::new(),
     }
 }
 
@@ -9,15 +9,34 @@ use nm_wifi::{
 use nm_wifi::{
     backend::{BackendFuture, NetworkBackend},
     demo_screenshots::write_demo_svgs_with_backend,
-    theme::CatppuccinColors,
+    terminal::Background,
+    theme::{LATTE, MOCHA, ThemeName},
     wifi::{WifiNetwork, WifiSecurity},
 };
 use ratatui::style::Color;
 
 #[test]
 fn theme_palette_exposes_expected_base_colors() {
-    assert_eq!(CatppuccinColors::BASE, Color::Rgb(30, 30, 46));
-    assert_eq!(CatppuccinColors::TEXT, Color::Rgb(205, 214, 244));
+    assert_eq!(MOCHA.base, Color::Rgb(30, 30, 46));
+    assert_eq!(MOCHA.text, Color::Rgb(205, 214, 244));
+}
+
+#[test]
+fn theme_names_cycle_through_every_flavor() {
+    let mut name = ThemeName::default();
+    let mut seen = Vec::new();
+    for _ in 0..6 {
+        seen.push(name.key());
+        name = name.next();
+    }
+    assert_eq!(name, ThemeName::default());
+    assert_eq!(
+        seen,
+        ["auto", "mocha", "macchiato", "frappe", "latte", "terminal"]
+    );
+    assert_eq!(ThemeName::from_key("latte"), Some(ThemeName::Latte));
+    assert_eq!(ThemeName::Auto.palette(Background::Dark), &MOCHA);
+    assert_eq!(ThemeName::Auto.palette(Background::Light), &LATTE);
 }
 
 #[derive(Clone)]
@@ -56,10 +75,16 @@ impl NetworkBackend for StaticScanBackend {
 fn network(ssid: &str, security: WifiSecurity, connected: bool) -> WifiNetwork {
     WifiNetwork {
         ssid: ssid.to_string(),
+        raw_ssid: None,
         signal_strength: 78,
         security,
         frequency: 5180,
         connected,
+        saved: false,
+        max_bitrate: 0,
+        wpa_flags: 0,
+        rsn_flags: 0,
+        access_points: Vec::new(),
     }
 }
 
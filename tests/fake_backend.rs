@@ -73,9 +73,8 @@ impl NetworkBackend for FakeBackend {
         let mut state = self.state.borrow_mut();
         let ssid = match request {
             ConnectionRequest::Open { network }
-            | ConnectionRequest::Secured { network, .. } => {
-                network.ssid.clone()
-            }
+            | ConnectionRequest::Secured { network, .. }
+            | ConnectionRequest::Guest { network } => network.ssid.clone(),
         };
         state.connect_calls.push(ssid);
         match &state.connect_error {
@@ -97,10 +96,16 @@ impl NetworkBackend for FakeBackend {
 fn network(ssid: &str, security: WifiSecurity, connected: bool) -> WifiNetwork {
     WifiNetwork {
         ssid: ssid.to_string(),
+        raw_ssid: None,
         signal_strength: 77,
         security,
         frequency: 5180,
         connected,
+        saved: false,
+        max_bitrate: 0,
+        wpa_flags: 0,
+        rsn_flags: 0,
+        access_points: Vec::new(),
     }
 }
 